@@ -0,0 +1,242 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Boots a minimal x86_64 microVM with KVM, wired with the devices of
+//! `vm-superio`.
+//!
+//! The guest is a few bytes of real mode code, which prints the value of the
+//! PL031 RTC on COM1, then echoes the input of the console until it reads a
+//! `q`, on which it resets the machine through the i8042 controller:
+//!
+//! ```bash
+//! echo "hello q" | cargo run -p kvm-microvm
+//! ```
+//!
+//! The VMM side shows the wiring every VMM needs:
+//! - the interrupts of the devices are `EventFdTrigger` objects registered as
+//!   irqfds of the in-kernel interrupt controllers, so that the devices raise
+//!   them without going through the VMM;
+//! - the devices are shared through an `Arc<Mutex<_>>` between the
+//!   dispatchers, which the vCPU calls on its port I/O and MMIO exits, and the
+//!   threads of the VMM, such as the console thread sending the input;
+//! - the reset requested by the guest is an event the VMM checks after the
+//!   exits, instead of a special exit reason.
+
+#[cfg(target_arch = "x86_64")]
+mod vmm {
+    use std::error::Error;
+    use std::io::{self, Read};
+    use std::ptr::null_mut;
+    use std::slice;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use kvm_bindings::kvm_userspace_memory_region;
+    use kvm_ioctls::{Kvm, VcpuExit};
+    use vm_superio::eventfd::EventFdTrigger;
+    use vm_superio::{I8042Device, MmioDispatcher, PortIoDispatcher, Rtc, Serial};
+    use vmm_sys_util::eventfd::EFD_NONBLOCK;
+
+    // The guest RAM, below the RTC.
+    const MEM_SIZE: usize = 0xD_0000;
+    // The address at which the guest code is loaded, and starts.
+    const CODE_ADDR: usize = 0x1000;
+    // The register window of the PL031 RTC, which isn't backed by the RAM so
+    // that its accesses exit to the VMM.
+    const RTC_ADDR: u64 = 0xD_0000;
+    const RTC_SIZE: u64 = 0x1000;
+    // The ISA interrupts of the devices.
+    const COM1_IRQ: u32 = 4;
+    const KBD_IRQ: u32 = 1;
+    const AUX_IRQ: u32 = 12;
+
+    // The guest, assembled from:
+    //
+    //     mov $0x3f8, %dx
+    //     mov $msg, %si
+    // 1:  lodsb                   # Print the message on COM1.
+    //     test %al, %al
+    //     jz 2f
+    //     out %al, %dx
+    //     jmp 1b
+    // 2:  mov $0xd000, %ax        # Read the data register of the RTC.
+    //     mov %ax, %es
+    //     mov %es:0, %ebx
+    //     mov $8, %cx
+    // 3:  rol $4, %ebx            # Print it in hexadecimal.
+    //     mov %bl, %al
+    //     and $0xf, %al
+    //     add $0x30, %al
+    //     cmp $0x39, %al
+    //     jbe 4f
+    //     add $7, %al
+    // 4:  out %al, %dx
+    //     loop 3b
+    //     mov $0x0a, %al
+    //     out %al, %dx
+    // echo:
+    //     mov $0x3fd, %dx         # Wait for the data ready bit of the LSR.
+    // 5:  in %dx, %al
+    //     test $1, %al
+    //     jz 5b
+    //     mov $0x3f8, %dx         # Read the byte, and echo it unless it is
+    //     in %dx, %al             # a 'q'.
+    //     cmp $0x71, %al
+    //     je reset
+    //     out %al, %dx
+    //     jmp echo
+    // reset:
+    //     mov $0xfe, %al          # Pulse the reset line of the i8042.
+    //     out %al, $0x64
+    //     hlt
+    // msg:
+    //     .asciz "RTC: 0x"
+    const GUEST_CODE: [u8; 82] = [
+        0xba, 0xf8, 0x03, 0xbe, 0x4a, 0x10, 0xac, 0x84, 0xc0, 0x74, 0x03, 0xee, 0xeb, 0xf8, 0xb8,
+        0x00, 0xd0, 0x8e, 0xc0, 0x26, 0x66, 0x8b, 0x1e, 0x00, 0x00, 0xb9, 0x08, 0x00, 0x66, 0xc1,
+        0xc3, 0x04, 0x88, 0xd8, 0x24, 0x0f, 0x04, 0x30, 0x3c, 0x39, 0x76, 0x02, 0x04, 0x07, 0xee,
+        0xe2, 0xed, 0xb0, 0x0a, 0xee, 0xba, 0xfd, 0x03, 0xec, 0xa8, 0x01, 0x74, 0xfb, 0xba, 0xf8,
+        0x03, 0xec, 0x3c, 0x71, 0x74, 0x03, 0xee, 0xeb, 0xed, 0xb0, 0xfe, 0xe6, 0x64, 0xf4, 0x52,
+        0x54, 0x43, 0x3a, 0x20, 0x30, 0x78, 0x00,
+    ];
+
+    // Maps the anonymous memory backing the guest RAM.
+    fn map_guest_memory(size: usize) -> io::Result<&'static mut [u8]> {
+        // SAFETY: We map a new anonymous region, and check the result.
+        let addr = unsafe {
+            libc::mmap(
+                null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_ANONYMOUS | libc::MAP_PRIVATE | libc::MAP_NORESERVE,
+                -1,
+                0,
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: The region is `size` bytes long, and is never unmapped.
+        Ok(unsafe { slice::from_raw_parts_mut(addr as *mut u8, size) })
+    }
+
+    pub fn run() -> Result<(), Box<dyn Error>> {
+        let kvm = Kvm::new()?;
+        let vm = kvm.create_vm()?;
+
+        let mem = map_guest_memory(MEM_SIZE)?;
+        mem[CODE_ADDR..CODE_ADDR + GUEST_CODE.len()].copy_from_slice(&GUEST_CODE);
+        let region = kvm_userspace_memory_region {
+            slot: 0,
+            guest_phys_addr: 0,
+            memory_size: MEM_SIZE as u64,
+            userspace_addr: mem.as_ptr() as u64,
+            flags: 0,
+        };
+        // SAFETY: The memory is valid for the lifetime of the VM.
+        unsafe { vm.set_user_memory_region(region)? };
+
+        // The PIC and the IOAPIC are emulated by KVM, so the devices raise
+        // their interrupts by writing their irqfd, from any thread. They must
+        // exist before the irqfds are registered, and the vCPUs created.
+        vm.create_irq_chip()?;
+
+        // COM1 writes the output of the guest to the standard output.
+        let serial_evt = EventFdTrigger::new(EFD_NONBLOCK)?;
+        vm.register_irqfd(&serial_evt, COM1_IRQ)?;
+        let serial = Arc::new(Mutex::new(Serial::new(serial_evt, io::stdout())));
+
+        // The reset event isn't an interrupt: the VMM checks it after the
+        // exits, and tears down or restarts the VM.
+        let reset_evt = EventFdTrigger::new(EFD_NONBLOCK)?;
+        let kbd_evt = EventFdTrigger::new(EFD_NONBLOCK)?;
+        vm.register_irqfd(&kbd_evt, KBD_IRQ)?;
+        let aux_evt = EventFdTrigger::new(EFD_NONBLOCK)?;
+        vm.register_irqfd(&aux_evt, AUX_IRQ)?;
+        let i8042 = I8042Device::with_irqs(reset_evt.try_clone()?, kbd_evt, aux_evt);
+
+        let mut pio = PortIoDispatcher::new();
+        pio.insert_com(1, serial.clone())?;
+        pio.insert_i8042(Arc::new(Mutex::new(i8042)))?;
+
+        // The RTC counts from the time of the host.
+        let mut mmio = MmioDispatcher::new();
+        mmio.insert(RTC_ADDR, RTC_SIZE, Arc::new(Mutex::new(Rtc::new())))?;
+
+        // The console thread sends the input to the guest, waiting for room in
+        // the FIFO of COM1 instead of dropping the bytes.
+        thread::spawn(move || {
+            let mut buf = [0; 64];
+            while let Ok(len @ 1..) = io::stdin().read(&mut buf) {
+                let mut input = &buf[..len];
+                while !input.is_empty() {
+                    let mut serial = serial.lock().unwrap();
+                    let count = serial.fifo_capacity().min(input.len());
+                    if count == 0 {
+                        drop(serial);
+                        thread::sleep(Duration::from_millis(1));
+                        continue;
+                    }
+                    let _ = serial.enqueue_raw_bytes(&input[..count]);
+                    input = &input[count..];
+                }
+            }
+        });
+
+        // The vCPU starts in real mode, at the guest code.
+        let mut vcpu = vm.create_vcpu(0)?;
+        let mut sregs = vcpu.get_sregs()?;
+        sregs.cs.base = 0;
+        sregs.cs.selector = 0;
+        vcpu.set_sregs(&sregs)?;
+        let mut regs = vcpu.get_regs()?;
+        regs.rip = CODE_ADDR as u64;
+        regs.rflags = 0x2;
+        vcpu.set_regs(&regs)?;
+
+        loop {
+            match vcpu.run()? {
+                VcpuExit::IoIn(port, data) => {
+                    // The ports without a device float high.
+                    if !pio.pio_read(port, data) {
+                        data.fill(0xFF);
+                    }
+                }
+                VcpuExit::IoOut(port, data) => {
+                    pio.pio_write(port, data);
+                }
+                VcpuExit::MmioRead(addr, data) => {
+                    if !mmio.mmio_read(addr, data) {
+                        data.fill(0xFF);
+                    }
+                }
+                VcpuExit::MmioWrite(addr, data) => {
+                    mmio.mmio_write(addr, data);
+                }
+                VcpuExit::Hlt => break,
+                exit => return Err(format!("Unexpected exit: {:?}", exit).into()),
+            }
+            if reset_evt.read().is_ok() {
+                println!("\nThe guest requested a reset.");
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn main() {
+    if let Err(e) = vmm::run() {
+        eprintln!("Failed to run the microVM: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn main() {
+    eprintln!("The microVM example only supports x86_64.");
+    std::process::exit(1);
+}
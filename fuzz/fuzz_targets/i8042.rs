@@ -0,0 +1,55 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use vm_superio::i8042::NoEvents;
+use vm_superio::testing::MockTrigger;
+use vm_superio::I8042Device;
+
+// The size of the keyboard and auxiliary device buffers of the controller.
+const BUFFER_SIZE: usize = 16;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Read(u8),
+    Write(u8, u8),
+    EnqueueKbd(Vec<u8>),
+    EnqueueAux(Vec<u8>),
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let reset_evt = MockTrigger::new();
+    let kbd_evt = MockTrigger::new();
+    let aux_evt = MockTrigger::new();
+    let mut i8042 = I8042Device::with_irqs(reset_evt.clone(), kbd_evt.clone(), aux_evt.clone());
+
+    for op in ops {
+        match op {
+            Op::Read(offset) => {
+                i8042.read(offset);
+            }
+            Op::Write(offset, value) => {
+                let _ = i8042.write(offset, value);
+            }
+            Op::EnqueueKbd(bytes) => {
+                let _ = i8042.enqueue_kbd_bytes(&bytes);
+            }
+            Op::EnqueueAux(bytes) => {
+                let _ = i8042.enqueue_aux_bytes(&bytes);
+            }
+        }
+
+        let state = i8042.state();
+        assert!(state.kbd_buffer.len() <= BUFFER_SIZE);
+        assert!(state.aux_buffer.len() <= BUFFER_SIZE);
+    }
+
+    // The state of the device is always restorable.
+    let state = i8042.state();
+    let restored = I8042Device::from_state(&state, reset_evt, kbd_evt, aux_evt, NoEvents).unwrap();
+    assert_eq!(restored.state(), state);
+});
@@ -0,0 +1,46 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use vm_superio::testing::{MockClock, MockTrigger};
+use vm_superio::{Pit, PitState};
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Read(u8),
+    Write(u8, u8),
+    SetGate(bool),
+    Advance(u64),
+    TimerExpired,
+}
+
+fuzz_target!(|input: (PitState, u64, Vec<Op>)| {
+    let (state, now_ns, ops) = input;
+    let clock = MockClock::new();
+    clock.advance_ns(now_ns);
+    // The restore path accepts any state, e.g. from a corrupted snapshot.
+    let mut pit = Pit::from_state(&state, MockTrigger::new(), clock.clone());
+    pit.next_deadline();
+
+    for op in ops {
+        match op {
+            Op::Read(offset) => {
+                pit.read(offset % 4);
+            }
+            Op::Write(offset, value) => pit.write(offset % 4, value),
+            Op::SetGate(level) => pit.set_channel2_gate(level),
+            Op::Advance(ns) => clock.advance_ns(ns),
+            Op::TimerExpired => pit.on_timer_expired().unwrap(),
+        }
+        pit.next_deadline();
+    }
+
+    // The state of the device is always restorable.
+    let state = pit.state();
+    let restored = Pit::from_state(&state, MockTrigger::new(), clock);
+    assert_eq!(restored.state(), state);
+});
@@ -0,0 +1,34 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use vm_superio::rtc_pl031::NoEvents;
+use vm_superio::Rtc;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Read(u16),
+    Write(u16, u32),
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let mut rtc = Rtc::new();
+
+    for op in ops {
+        match op {
+            Op::Read(offset) => {
+                let mut data = [0u8; 4];
+                rtc.read(offset, &mut data);
+            }
+            Op::Write(offset, value) => rtc.write(offset, &value.to_le_bytes()),
+        }
+    }
+
+    // The state of the device is always restorable.
+    let state = rtc.state();
+    assert_eq!(Rtc::from_state(&state, NoEvents).state(), state);
+});
@@ -0,0 +1,59 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use vm_superio::serial::NoEvents;
+use vm_superio::testing::MockTrigger;
+use vm_superio::Serial;
+use vm_superio_fuzz::BoundedWriter;
+
+// The size of the receive FIFO of the serial port.
+const FIFO_SIZE: usize = 64;
+// The capacity of the output of the serial port.
+const OUT_CAPACITY: usize = 256;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Read(u8),
+    Write(u8, u8),
+    Enqueue(Vec<u8>),
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let trigger = MockTrigger::new();
+    let mut serial = Serial::new(trigger.clone(), BoundedWriter::new(OUT_CAPACITY));
+
+    for op in ops {
+        match op {
+            Op::Read(offset) => {
+                serial.read(offset);
+            }
+            Op::Write(offset, value) => {
+                let _ = serial.write(offset, value);
+            }
+            Op::Enqueue(bytes) => {
+                let _ = serial.enqueue_raw_bytes(&bytes);
+            }
+        }
+
+        let state = serial.state();
+        assert!(state.in_buffer.len() <= FIFO_SIZE);
+        assert_eq!(serial.fifo_capacity(), FIFO_SIZE - state.in_buffer.len());
+        assert!(serial.writer().data().len() <= OUT_CAPACITY);
+    }
+
+    // The state of the device is always restorable.
+    let state = serial.state();
+    let restored = Serial::from_state(
+        &state,
+        trigger.clone(),
+        NoEvents,
+        BoundedWriter::new(OUT_CAPACITY),
+    )
+    .unwrap();
+    assert_eq!(restored.state(), state);
+});
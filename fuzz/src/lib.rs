@@ -0,0 +1,45 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! The objects shared by the fuzz targets of the devices.
+
+use std::io;
+
+/// A writer holding at most `capacity` bytes, which fails the writes past
+/// them, like an output whose consumer stalled.
+#[derive(Debug)]
+pub struct BoundedWriter {
+    data: Vec<u8>,
+    capacity: usize,
+}
+
+impl BoundedWriter {
+    /// Creates a new `BoundedWriter` holding at most `capacity` bytes.
+    pub fn new(capacity: usize) -> Self {
+        BoundedWriter {
+            data: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Returns the bytes written so far.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl io::Write for BoundedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = buf.len().min(self.capacity - self.data.len());
+        if count == 0 && !buf.is_empty() {
+            return Err(io::Error::from(io::ErrorKind::WriteZero));
+        }
+        self.data.extend_from_slice(&buf[..count]);
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
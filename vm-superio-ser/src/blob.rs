@@ -0,0 +1,448 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the framing of the serialized device states in identified blobs.
+//!
+//! A blob is a header followed by the serialized state, in any format, e.g.
+//! `bincode` or `Versionize`. The header, of `HEADER_SIZE` bytes, holds the
+//! following little-endian fields:
+//! - the `MAGIC` bytes;
+//! - the `DeviceType` tag of the state;
+//! - the version of the format of the state, chosen by the VMM, e.g. the
+//!   version of its `VersionMap`;
+//! - the length of the serialized state;
+//! - the flags of the blob, i.e. `FLAG_CRC32` when it is checksummed;
+//! - the CRC32 of the serialized state, or 0 without the `FLAG_CRC32` flag.
+//!
+//! The reader validates the header against the expected device and the
+//! highest supported version before returning the serialized state, so that
+//! a truncated, foreign or newer blob is rejected before deserializing it.
+//! The blobs framed with `frame_with_crc32` are also checked against their
+//! CRC32, so that a corrupted snapshot file is detected before restoring a
+//! device with a state which deserializes, but holds flipped bits.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use vm_superio::Serial;
+//! # use vm_superio_ser::blob::{self, DeviceType};
+//! # use vm_superio_ser::SerialStateSer;
+//! # struct DummyTrigger;
+//! # impl vm_superio::Trigger for DummyTrigger {
+//! #     type E = ();
+//! #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+//! # }
+//! let serial = Serial::new(DummyTrigger, Vec::new());
+//! let state = SerialStateSer::from(&serial.state());
+//!
+//! let payload = bincode::serialize(&state).unwrap();
+//! let blob = blob::frame(DeviceType::Serial, 1, &payload).unwrap();
+//!
+//! let (version, payload) = blob::unframe(&blob, DeviceType::Serial, 1).unwrap();
+//! assert_eq!(version, 1);
+//! let restored: SerialStateSer = bincode::deserialize(payload).unwrap();
+//! assert_eq!(restored, state);
+//! ```
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
+
+/// The magic bytes starting the blobs.
+pub const MAGIC: [u8; 4] = *b"VMSS";
+/// The size of the header of the blobs, in bytes.
+pub const HEADER_SIZE: usize = 20;
+/// The flag of the blobs holding the CRC32 of their serialized state.
+pub const FLAG_CRC32: u32 = 1;
+
+/// The devices whose states are framed in the blobs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DeviceType {
+    /// A serial port, with a `SerialStateSer`.
+    Serial,
+    /// An i8042 controller, with an `I8042StateSer`.
+    I8042,
+    /// A PL031 RTC, with an `RtcStateSer`.
+    Rtc,
+    /// An i8254 PIT, with a `PitStateSer`.
+    Pit,
+    /// All the legacy devices of a VM, with a `SuperioStateSer`.
+    Superio,
+}
+
+impl DeviceType {
+    /// Returns the tag of the device in the header. The tags never change
+    /// across the releases.
+    pub fn tag(self) -> u16 {
+        match self {
+            DeviceType::Serial => 1,
+            DeviceType::I8042 => 2,
+            DeviceType::Rtc => 3,
+            DeviceType::Pit => 4,
+            DeviceType::Superio => 5,
+        }
+    }
+}
+
+impl TryFrom<u16> for DeviceType {
+    type Error = Error;
+
+    fn try_from(tag: u16) -> Result<Self, Error> {
+        match tag {
+            1 => Ok(DeviceType::Serial),
+            2 => Ok(DeviceType::I8042),
+            3 => Ok(DeviceType::Rtc),
+            4 => Ok(DeviceType::Pit),
+            5 => Ok(DeviceType::Superio),
+            _ => Err(Error::UnknownDevice(tag)),
+        }
+    }
+}
+
+/// Errors encountered while framing or reading the blobs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// The serialized state doesn't fit in the 32-bit length of the header.
+    TooLarge(usize),
+    /// The blob is shorter than the header.
+    Truncated,
+    /// The blob doesn't start with the `MAGIC` bytes.
+    BadMagic,
+    /// The tag of the device is unknown.
+    UnknownDevice(u16),
+    /// The blob holds the state of another device.
+    UnexpectedDevice(DeviceType),
+    /// The version of the format is higher than the supported one.
+    UnsupportedVersion(u16),
+    /// The length in the header, and the one of the serialized state differ.
+    LengthMismatch {
+        /// The length in the header.
+        expected: usize,
+        /// The length of the serialized state.
+        actual: usize,
+    },
+    /// The header holds unknown flags.
+    UnknownFlags(u32),
+    /// The CRC32 in the header, and the one of the serialized state differ.
+    ChecksumMismatch {
+        /// The CRC32 in the header.
+        expected: u32,
+        /// The CRC32 of the serialized state.
+        actual: u32,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::TooLarge(len) => write!(f, "State too large: {} bytes", len),
+            Error::Truncated => write!(f, "Blob shorter than its header"),
+            Error::BadMagic => write!(f, "Invalid magic bytes"),
+            Error::UnknownDevice(tag) => write!(f, "Unknown device tag {}", tag),
+            Error::UnexpectedDevice(device) => write!(f, "Unexpected {:?} device state", device),
+            Error::UnsupportedVersion(version) => {
+                write!(f, "Unsupported format version {}", version)
+            }
+            Error::LengthMismatch { expected, actual } => write!(
+                f,
+                "Invalid state length: expected {} bytes, got {}",
+                expected, actual
+            ),
+            Error::UnknownFlags(flags) => write!(f, "Unknown flags {:#x}", flags),
+            Error::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "Invalid state checksum: expected {:#010x}, got {:#010x}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl StdError for Error {}
+
+/// The header of a blob.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Header {
+    /// The device whose state is framed.
+    pub device: DeviceType,
+    /// The version of the format of the state.
+    pub version: u16,
+    /// The length of the serialized state, in bytes.
+    pub len: u32,
+    /// The CRC32 of the serialized state, if the blob is checksummed.
+    pub crc32: Option<u32>,
+}
+
+impl Header {
+    /// Parses the header at the start of `blob`, checking its magic bytes,
+    /// its device tag and its flags.
+    ///
+    /// # Arguments
+    /// * `blob` - The blob, or at least its first `HEADER_SIZE` bytes.
+    pub fn parse(blob: &[u8]) -> Result<Self, Error> {
+        let header = blob.get(..HEADER_SIZE).ok_or(Error::Truncated)?;
+        if header[0..4] != MAGIC {
+            return Err(Error::BadMagic);
+        }
+        let device = DeviceType::try_from(u16::from_le_bytes([header[4], header[5]]))?;
+        let flags = u32::from_le_bytes([header[12], header[13], header[14], header[15]]);
+        if flags & !FLAG_CRC32 != 0 {
+            return Err(Error::UnknownFlags(flags));
+        }
+        Ok(Header {
+            device,
+            version: u16::from_le_bytes([header[6], header[7]]),
+            len: u32::from_le_bytes([header[8], header[9], header[10], header[11]]),
+            crc32: (flags & FLAG_CRC32 != 0)
+                .then(|| u32::from_le_bytes([header[16], header[17], header[18], header[19]])),
+        })
+    }
+
+    /// Returns the encoded header.
+    pub fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0; HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&MAGIC);
+        bytes[4..6].copy_from_slice(&self.device.tag().to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.version.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.len.to_le_bytes());
+        if let Some(crc32) = self.crc32 {
+            bytes[12..16].copy_from_slice(&FLAG_CRC32.to_le_bytes());
+            bytes[16..20].copy_from_slice(&crc32.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// Returns the blob framing the `payload` serialized state of `device`.
+///
+/// # Arguments
+/// * `device` - The device whose state is serialized.
+/// * `version` - The version of the format of the state.
+/// * `payload` - The serialized state.
+pub fn frame(device: DeviceType, version: u16, payload: &[u8]) -> Result<Vec<u8>, Error> {
+    frame_with(device, version, payload, None)
+}
+
+/// Returns the blob framing the `payload` serialized state of `device`, with
+/// its CRC32, which `unframe` checks.
+///
+/// # Arguments
+/// * `device` - The device whose state is serialized.
+/// * `version` - The version of the format of the state.
+/// * `payload` - The serialized state.
+pub fn frame_with_crc32(
+    device: DeviceType,
+    version: u16,
+    payload: &[u8],
+) -> Result<Vec<u8>, Error> {
+    frame_with(device, version, payload, Some(crc32(payload)))
+}
+
+fn frame_with(
+    device: DeviceType,
+    version: u16,
+    payload: &[u8],
+    crc32: Option<u32>,
+) -> Result<Vec<u8>, Error> {
+    let len = u32::try_from(payload.len()).map_err(|_| Error::TooLarge(payload.len()))?;
+    let header = Header {
+        device,
+        version,
+        len,
+        crc32,
+    };
+    let mut blob = Vec::with_capacity(HEADER_SIZE + payload.len());
+    blob.extend_from_slice(&header.to_bytes());
+    blob.extend_from_slice(payload);
+    Ok(blob)
+}
+
+/// Validates the header of `blob`, and the CRC32 of the serialized state if
+/// the blob holds one, and returns the version of the format and the
+/// serialized state it frames.
+///
+/// # Arguments
+/// * `blob` - The blob, which must hold exactly one serialized state.
+/// * `device` - The expected device.
+/// * `max_version` - The highest version of the format supported by the
+///   reader.
+pub fn unframe(blob: &[u8], device: DeviceType, max_version: u16) -> Result<(u16, &[u8]), Error> {
+    let header = Header::parse(blob)?;
+    if header.device != device {
+        return Err(Error::UnexpectedDevice(header.device));
+    }
+    if header.version > max_version {
+        return Err(Error::UnsupportedVersion(header.version));
+    }
+    let payload = &blob[HEADER_SIZE..];
+    if payload.len() != header.len as usize {
+        return Err(Error::LengthMismatch {
+            expected: header.len as usize,
+            actual: payload.len(),
+        });
+    }
+    if let Some(expected) = header.crc32 {
+        let actual = crc32(payload);
+        if actual != expected {
+            return Err(Error::ChecksumMismatch { expected, actual });
+        }
+    }
+    Ok((header.version, payload))
+}
+
+/// Returns the CRC32 of `data`, with the IEEE polynomial, as computed by e.g.
+/// zlib or `cksum -a crc32b`.
+///
+/// # Arguments
+/// * `data` - The checksummed bytes.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{I8042StateSer, RtcStateSer};
+
+    #[test]
+    fn test_frame_unframe() {
+        let state = RtcStateSer {
+            lr: 0x1234,
+            offset: -5,
+            mr: 1,
+            imsc: 1,
+            ris: 0,
+        };
+        let payload = bincode::serialize(&state).unwrap();
+        let blob = frame(DeviceType::Rtc, 2, &payload).unwrap();
+        assert_eq!(blob.len(), HEADER_SIZE + payload.len());
+        assert_eq!(&blob[..4], b"VMSS");
+        assert_eq!(
+            Header::parse(&blob).unwrap(),
+            Header {
+                device: DeviceType::Rtc,
+                version: 2,
+                len: payload.len() as u32,
+                crc32: None,
+            }
+        );
+
+        let (version, framed) = unframe(&blob, DeviceType::Rtc, 3).unwrap();
+        assert_eq!(version, 2);
+        let restored: RtcStateSer = bincode::deserialize(framed).unwrap();
+        assert_eq!(restored, state);
+
+        // An empty state is framed as well.
+        let blob = frame(DeviceType::Pit, 0, &[]).unwrap();
+        assert_eq!(unframe(&blob, DeviceType::Pit, 0).unwrap(), (0, &[][..]));
+    }
+
+    #[test]
+    fn test_invalid_blobs() {
+        let payload = bincode::serialize(&I8042StateSer::default()).unwrap();
+        let blob = frame(DeviceType::I8042, 1, &payload).unwrap();
+
+        assert_eq!(
+            unframe(&blob[..HEADER_SIZE - 1], DeviceType::I8042, 1),
+            Err(Error::Truncated)
+        );
+        assert_eq!(
+            unframe(&blob[..blob.len() - 1], DeviceType::I8042, 1),
+            Err(Error::LengthMismatch {
+                expected: payload.len(),
+                actual: payload.len() - 1,
+            })
+        );
+        let mut trailing = blob.clone();
+        trailing.push(0);
+        assert!(matches!(
+            unframe(&trailing, DeviceType::I8042, 1),
+            Err(Error::LengthMismatch { .. })
+        ));
+
+        assert_eq!(
+            unframe(&blob, DeviceType::Serial, 1),
+            Err(Error::UnexpectedDevice(DeviceType::I8042))
+        );
+        assert_eq!(
+            unframe(&blob, DeviceType::I8042, 0),
+            Err(Error::UnsupportedVersion(1))
+        );
+
+        let mut bad_magic = blob.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(
+            unframe(&bad_magic, DeviceType::I8042, 1),
+            Err(Error::BadMagic)
+        );
+        let mut bad_tag = blob;
+        bad_tag[4] = 0xFF;
+        assert_eq!(
+            unframe(&bad_tag, DeviceType::I8042, 1),
+            Err(Error::UnknownDevice(0xFF))
+        );
+        assert_eq!(
+            Error::UnknownDevice(0xFF).to_string(),
+            "Unknown device tag 255"
+        );
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+        let payload = bincode::serialize(&I8042StateSer::default()).unwrap();
+        let blob = frame_with_crc32(DeviceType::I8042, 1, &payload).unwrap();
+        assert_eq!(blob.len(), HEADER_SIZE + payload.len());
+        assert_eq!(Header::parse(&blob).unwrap().crc32, Some(crc32(&payload)));
+        assert_eq!(
+            unframe(&blob, DeviceType::I8042, 1).unwrap(),
+            (1, &payload[..])
+        );
+
+        // A flipped bit of the state is detected, while it goes unnoticed
+        // without the checksum.
+        let mut corrupted = blob;
+        corrupted[HEADER_SIZE] ^= 0x01;
+        assert!(matches!(
+            unframe(&corrupted, DeviceType::I8042, 1),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+        let mut unchecked = frame(DeviceType::I8042, 1, &payload).unwrap();
+        unchecked[HEADER_SIZE] ^= 0x01;
+        assert!(unframe(&unchecked, DeviceType::I8042, 1).is_ok());
+
+        let mut bad_flags = frame(DeviceType::I8042, 1, &payload).unwrap();
+        bad_flags[12] = 0x02;
+        assert_eq!(
+            unframe(&bad_flags, DeviceType::I8042, 1),
+            Err(Error::UnknownFlags(0x02))
+        );
+    }
+
+    #[test]
+    fn test_device_tags() {
+        for device in [
+            DeviceType::Serial,
+            DeviceType::I8042,
+            DeviceType::Rtc,
+            DeviceType::Pit,
+            DeviceType::Superio,
+        ] {
+            assert_eq!(DeviceType::try_from(device.tag()), Ok(device));
+        }
+        assert_eq!(DeviceType::try_from(0), Err(Error::UnknownDevice(0)));
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the state of all the legacy devices of a VM, with serialization
+//! capabilities.
+//!
+//! This module defines the `SuperioStateSer` abstraction, which holds the
+//! state objects of the serial ports, and of the i8042 controller, the RTC and
+//! the PIT when the VM has them, so that the snapshot code serializes a
+//! single object per VM. It mirrors the `SuperioChassisState` from the base
+//! crate, in which all the devices are present, and adds on top of it derives
+//! for the `Serialize`, `Deserialize` and `Versionize` traits.
+
+use alloc::vec::Vec;
+use core::convert::{TryFrom, TryInto};
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "versionize")]
+use versionize::{VersionMap, Versionize, VersionizeResult};
+#[cfg(feature = "versionize")]
+use versionize_derive::Versionize;
+use vm_superio::chassis::SuperioChassisState;
+
+use crate::{Error, I8042StateSer, PitStateSer, RtcStateSer, SerialStateSer};
+
+/// The states of the legacy devices of a VM, with serialization capabilities.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "versionize", derive(Versionize))]
+pub struct SuperioStateSer {
+    /// The state of each serial port, in the order of their creation.
+    pub serials: Vec<SerialStateSer>,
+    /// The state of the i8042 controller, if the VM has one.
+    pub i8042: Option<I8042StateSer>,
+    /// The state of the RTC, if the VM has one.
+    pub rtc: Option<RtcStateSer>,
+    /// The state of the PIT, if the VM has one.
+    pub pit: Option<PitStateSer>,
+}
+
+// The following implementations can be used to convert from a `SuperioStateSer` to the
+// `SuperioChassisState` from the base crate, validating it, and vice versa.
+impl TryFrom<&SuperioStateSer> for SuperioChassisState {
+    type Error = Error;
+
+    fn try_from(state: &SuperioStateSer) -> Result<Self, Error> {
+        Ok(SuperioChassisState {
+            serials: state
+                .serials
+                .iter()
+                .map(TryFrom::try_from)
+                .collect::<Result<_, _>>()?,
+            i8042: state
+                .i8042
+                .as_ref()
+                .ok_or(Error::MissingDevice("i8042"))?
+                .try_into()?,
+            rtc: state
+                .rtc
+                .as_ref()
+                .ok_or(Error::MissingDevice("rtc"))?
+                .try_into()?,
+            pit: state
+                .pit
+                .as_ref()
+                .ok_or(Error::MissingDevice("pit"))?
+                .try_into()?,
+        })
+    }
+}
+
+impl From<&SuperioChassisState> for SuperioStateSer {
+    fn from(state: &SuperioChassisState) -> Self {
+        SuperioStateSer {
+            serials: state.serials.iter().map(SerialStateSer::from).collect(),
+            i8042: Some(I8042StateSer::from(&state.i8042)),
+            rtc: Some(RtcStateSer::from(&state.rtc)),
+            pit: Some(PitStateSer::from(&state.pit)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{sink, Sink};
+    use vm_superio::chassis::{ChassisResources, SuperioChassis};
+    use vm_superio::testing::MockClock;
+    use vm_superio::StateError;
+    use vm_superio::Trigger;
+
+    struct DummyTrigger;
+
+    impl Trigger for DummyTrigger {
+        type E = ();
+
+        fn trigger(&self) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    fn resources(clock: MockClock) -> ChassisResources<DummyTrigger, Sink, MockClock, MockClock> {
+        ChassisResources {
+            serials: vec![(DummyTrigger, sink()), (DummyTrigger, sink())],
+            i8042_reset_evt: DummyTrigger,
+            i8042_kbd_evt: DummyTrigger,
+            i8042_aux_evt: DummyTrigger,
+            pit_irq0_evt: DummyTrigger,
+            clock: clock.clone(),
+            rtc_clock: clock,
+        }
+    }
+
+    #[test]
+    fn test_state_ser() {
+        let clock = MockClock::new();
+        let mut chassis = SuperioChassis::new(resources(clock.clone()));
+        chassis
+            .serial_mut(1)
+            .unwrap()
+            .enqueue_raw_bytes(b"abc")
+            .unwrap();
+        clock.set_ns(1_000);
+
+        let state = chassis.state();
+        let ser_state = SuperioStateSer::from(&state);
+        assert_eq!(ser_state.serials.len(), 2);
+        assert_eq!(ser_state.serials[1].in_buffer, b"abc");
+
+        let bytes = bincode::serialize(&ser_state).unwrap();
+        let ser_state: SuperioStateSer = bincode::deserialize(&bytes).unwrap();
+        let state_after_restore = SuperioChassisState::try_from(&ser_state).unwrap();
+        assert_eq!(state_after_restore, state);
+        assert!(SuperioChassis::from_state(&state_after_restore, resources(clock)).is_ok());
+    }
+
+    #[test]
+    fn test_missing_devices() {
+        // A VM with a serial port only.
+        let state = SuperioStateSer {
+            serials: vec![SerialStateSer::default()],
+            ..Default::default()
+        };
+        let bytes = bincode::serialize(&state).unwrap();
+        assert_eq!(
+            bincode::deserialize::<SuperioStateSer>(&bytes).unwrap(),
+            state
+        );
+
+        // The chassis needs all the devices.
+        assert_eq!(
+            SuperioChassisState::try_from(&state),
+            Err(Error::MissingDevice("i8042"))
+        );
+        let state = SuperioStateSer {
+            i8042: Some(I8042StateSer::default()),
+            rtc: Some(RtcStateSer::default()),
+            ..Default::default()
+        };
+        assert_eq!(
+            SuperioChassisState::try_from(&state),
+            Err(Error::MissingDevice("pit"))
+        );
+
+        // The states of the devices are validated.
+        let state = SuperioStateSer {
+            serials: vec![SerialStateSer {
+                interrupt_identification: 0,
+                ..Default::default()
+            }],
+            i8042: Some(I8042StateSer::default()),
+            rtc: Some(RtcStateSer::default()),
+            pit: Some(PitStateSer::default()),
+        };
+        assert!(matches!(
+            SuperioChassisState::try_from(&state),
+            Err(Error::InvalidState(StateError::InvalidRegister { .. }))
+        ));
+    }
+
+    #[cfg(feature = "versionize")]
+    #[test]
+    fn test_versionize() {
+        let map = VersionMap::new();
+        let state = SuperioStateSer {
+            serials: vec![SerialStateSer::default()],
+            pit: Some(PitStateSer::default()),
+            ..Default::default()
+        };
+        let mut v1_state = Vec::new();
+
+        Versionize::serialize(&state, &mut v1_state, &map, 1).unwrap();
+
+        let from_v1: SuperioStateSer =
+            Versionize::deserialize(&mut v1_state.as_slice(), &map, 1).unwrap();
+
+        assert_eq!(from_v1, state);
+    }
+}
@@ -0,0 +1,334 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the comparison of the states, field by field.
+//!
+//! When a device behaves differently after a migration, the states saved on
+//! the source and restored on the destination are compared through
+//! [`StateDiff`](trait.StateDiff.html), which lists the fields whose values
+//! differ, with the path of the field in the state, e.g. `channels[0].mode`
+//! for the PIT.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use vm_superio_ser::diff::StateDiff;
+//! # use vm_superio_ser::RtcStateSer;
+//! let source = RtcStateSer::default();
+//! let destination = RtcStateSer {
+//!     lr: 0x1234,
+//!     ..Default::default()
+//! };
+//!
+//! let diffs = source.diff(&destination);
+//! assert_eq!(diffs.len(), 1);
+//! assert_eq!(diffs[0].to_string(), "lr: 0 -> 4660");
+//! ```
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::pit::PitChannelStateSer;
+#[cfg(feature = "std")]
+use crate::SuperioStateSer;
+use crate::{I8042StateSer, PitStateSer, RtcStateSer, SerialStateSer};
+
+/// A field whose value differs between two states.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FieldDiff {
+    /// The path of the field in the state.
+    pub field: String,
+    /// The value of the field in the first state, formatted with `Debug`.
+    pub old: String,
+    /// The value of the field in the second state, formatted with `Debug`.
+    pub new: String,
+}
+
+impl FieldDiff {
+    fn new(field: &str, old: &dyn fmt::Debug, new: &dyn fmt::Debug) -> Self {
+        FieldDiff {
+            field: String::from(field),
+            old: format!("{:?}", old),
+            new: format!("{:?}", new),
+        }
+    }
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.field, self.old, self.new)
+    }
+}
+
+/// The comparison of two states of the same type.
+pub trait StateDiff {
+    /// Returns the fields whose values differ between this state and
+    /// `other`, in the order of their declaration, or an empty list if the
+    /// states are equal.
+    ///
+    /// # Arguments
+    /// * `other` - The state compared to this one.
+    fn diff(&self, other: &Self) -> Vec<FieldDiff>;
+}
+
+// Returns the differences of a nested state, with their fields prefixed by the
+// path of the nested state.
+fn nested<'a>(prefix: &'a str, diffs: Vec<FieldDiff>) -> impl Iterator<Item = FieldDiff> + 'a {
+    diffs.into_iter().map(move |diff| FieldDiff {
+        field: format!("{}.{}", prefix, diff.field),
+        ..diff
+    })
+}
+
+// Pushes to `$diffs` the listed fields which differ between `$old` and `$new`.
+macro_rules! diff_fields {
+    ($diffs:ident, $old:expr, $new:expr, $($field:ident),+ $(,)?) => {
+        $(
+            if $old.$field != $new.$field {
+                $diffs.push(FieldDiff::new(stringify!($field), &$old.$field, &$new.$field));
+            }
+        )+
+    };
+}
+
+impl StateDiff for SerialStateSer {
+    fn diff(&self, other: &Self) -> Vec<FieldDiff> {
+        let mut diffs = Vec::new();
+        diff_fields!(
+            diffs,
+            self,
+            other,
+            baud_divisor_low,
+            baud_divisor_high,
+            interrupt_enable,
+            interrupt_identification,
+            fifo_control,
+            line_control,
+            line_status,
+            modem_control,
+            modem_status,
+            scratch,
+            in_buffer,
+        );
+        diffs
+    }
+}
+
+impl StateDiff for RtcStateSer {
+    fn diff(&self, other: &Self) -> Vec<FieldDiff> {
+        let mut diffs = Vec::new();
+        diff_fields!(diffs, self, other, lr, offset, mr, imsc, ris);
+        diffs
+    }
+}
+
+impl StateDiff for I8042StateSer {
+    fn diff(&self, other: &Self) -> Vec<FieldDiff> {
+        let mut diffs = Vec::new();
+        diff_fields!(
+            diffs,
+            self,
+            other,
+            command_byte,
+            output_port,
+            pending_command,
+            last_write_command,
+            kbd_pending_command,
+            kbd_scanning,
+            kbd_leds,
+            kbd_typematic,
+            kbd_break_pending,
+            kbd_overrun,
+            kbd_buffer,
+            aux_buffer,
+        );
+        diffs
+    }
+}
+
+impl StateDiff for PitChannelStateSer {
+    fn diff(&self, other: &Self) -> Vec<FieldDiff> {
+        let mut diffs = Vec::new();
+        diff_fields!(
+            diffs,
+            self,
+            other,
+            mode,
+            access,
+            reload,
+            elapsed_ns,
+            armed,
+            counting,
+            gate,
+            paused_ticks,
+            write_low,
+            read_high,
+            latched_count,
+            latched_status,
+            next_edge,
+        );
+        diffs
+    }
+}
+
+impl StateDiff for PitStateSer {
+    fn diff(&self, other: &Self) -> Vec<FieldDiff> {
+        self.channels
+            .iter()
+            .zip(other.channels.iter())
+            .enumerate()
+            .flat_map(|(index, (old, new))| {
+                nested(&format!("channels[{}]", index), old.diff(new)).collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+// Returns the differences of an optional nested state: the whole states when
+// only one of them is present, or the differences of their fields otherwise.
+#[cfg(feature = "std")]
+fn optional<S: StateDiff + fmt::Debug>(
+    field: &str,
+    old: &Option<S>,
+    new: &Option<S>,
+) -> Vec<FieldDiff> {
+    match (old, new) {
+        (Some(old), Some(new)) => nested(field, old.diff(new)).collect(),
+        (None, None) => Vec::new(),
+        _ => vec![FieldDiff::new(field, old, new)],
+    }
+}
+
+#[cfg(feature = "std")]
+impl StateDiff for SuperioStateSer {
+    fn diff(&self, other: &Self) -> Vec<FieldDiff> {
+        let mut diffs = Vec::new();
+        if self.serials.len() != other.serials.len() {
+            diffs.push(FieldDiff::new(
+                "serials.len",
+                &self.serials.len(),
+                &other.serials.len(),
+            ));
+        }
+        for (index, (old, new)) in self.serials.iter().zip(&other.serials).enumerate() {
+            diffs.extend(nested(&format!("serials[{}]", index), old.diff(new)));
+        }
+        diffs.extend(optional("i8042", &self.i8042, &other.i8042));
+        diffs.extend(optional("rtc", &self.rtc, &other.rtc));
+        diffs.extend(optional("pit", &self.pit, &other.pit));
+        diffs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serial_diff() {
+        let old = SerialStateSer::default();
+        assert!(old.diff(&old.clone()).is_empty());
+
+        let new = SerialStateSer {
+            line_control: 0x80,
+            scratch: 0x42,
+            in_buffer: vec![b'a'],
+            ..Default::default()
+        };
+        let diffs: Vec<String> = old.diff(&new).iter().map(FieldDiff::to_string).collect();
+        assert_eq!(
+            diffs,
+            [
+                "line_control: 3 -> 128",
+                "scratch: 0 -> 66",
+                "in_buffer: [] -> [97]"
+            ]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_rtc_diff() {
+        let old = RtcStateSer::default();
+        let new = RtcStateSer {
+            offset: -1,
+            ris: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            old.diff(&new),
+            [
+                FieldDiff {
+                    field: String::from("offset"),
+                    old: String::from("0"),
+                    new: String::from("-1"),
+                },
+                FieldDiff {
+                    field: String::from("ris"),
+                    old: String::from("0"),
+                    new: String::from("1"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_i8042_diff() {
+        let old = I8042StateSer::default();
+        let new = I8042StateSer {
+            pending_command: Some(0x60),
+            aux_buffer: vec![0xFA],
+            ..Default::default()
+        };
+        let diffs: Vec<String> = new.diff(&old).iter().map(FieldDiff::to_string).collect();
+        assert_eq!(
+            diffs,
+            [
+                "pending_command: Some(96) -> None",
+                "aux_buffer: [250] -> []"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pit_diff() {
+        let old = PitStateSer::default();
+        let mut new = old.clone();
+        new.channels[0].reload = 0x1234;
+        new.channels[2].gate = false;
+        let diffs: Vec<String> = old.diff(&new).iter().map(FieldDiff::to_string).collect();
+        assert_eq!(
+            diffs,
+            [
+                "channels[0].reload: 0 -> 4660",
+                "channels[2].gate: true -> false"
+            ]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_superio_diff() {
+        let old = SuperioStateSer {
+            serials: vec![SerialStateSer::default()],
+            i8042: Some(I8042StateSer::default()),
+            pit: Some(PitStateSer::default()),
+            ..Default::default()
+        };
+        let mut new = old.clone();
+        new.serials.push(SerialStateSer::default());
+        new.serials[0].scratch = 1;
+        new.pit.as_mut().unwrap().channels[1].mode = 2;
+        new.i8042 = None;
+        let diffs: Vec<String> = old.diff(&new).iter().map(FieldDiff::to_string).collect();
+        assert_eq!(diffs[0], "serials.len: 1 -> 2");
+        assert_eq!(diffs[1], "serials[0].scratch: 0 -> 1");
+        assert!(diffs[2].starts_with("i8042: Some(I8042StateSer {"));
+        assert!(diffs[2].ends_with("}) -> None"));
+        assert_eq!(diffs[3], "pit.channels[1].mode: 0 -> 2");
+        assert_eq!(diffs.len(), 4);
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides a wrapper over an `I8042State` that has serialization capabilities.
+//!
+//! This module defines the `I8042StateSer` abstraction which mirrors the
+//! `I8042State` from the base crate, and adds on top of it derives for
+//! the `Serialize`, `Deserialize` and `Versionize` traits.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "versionize")]
+use versionize::{VersionMap, Versionize, VersionizeResult};
+#[cfg(feature = "versionize")]
+use versionize_derive::Versionize;
+use vm_superio::I8042State;
+
+use crate::Error;
+
+/// Wrapper over an `I8042State` that has serialization capabilities.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "versionize", derive(Versionize))]
+pub struct I8042StateSer {
+    /// Controller command byte.
+    pub command_byte: u8,
+    /// Controller output port, which includes the A20 gate.
+    pub output_port: u8,
+    /// Controller command waiting for its parameter on the data port.
+    pub pending_command: Option<u8>,
+    /// Whether the last byte written by the driver went to the command
+    /// register.
+    pub last_write_command: bool,
+    /// Keyboard command waiting for its parameter on the data port.
+    pub kbd_pending_command: Option<u8>,
+    /// Whether the keyboard sends scancodes.
+    pub kbd_scanning: bool,
+    /// Keyboard LEDs.
+    pub kbd_leds: u8,
+    /// Keyboard typematic rate and delay.
+    pub kbd_typematic: u8,
+    /// Whether the next translated scancode is a break code.
+    pub kbd_break_pending: bool,
+    /// Whether keyboard data was dropped, and the overrun code is waiting for
+    /// space in the output buffer.
+    pub kbd_overrun: bool,
+    /// Keyboard and controller bytes waiting in the output buffer.
+    pub kbd_buffer: Vec<u8>,
+    /// Auxiliary device bytes waiting in the output buffer.
+    pub aux_buffer: Vec<u8>,
+}
+
+// The following implementations can be used to convert from an `I8042StateSer` to the
+// `I8042State` from the base crate, validating it, and vice versa.
+impl TryFrom<&I8042StateSer> for I8042State {
+    type Error = Error;
+
+    fn try_from(state: &I8042StateSer) -> Result<Self, Error> {
+        let i8042_state = I8042State {
+            command_byte: state.command_byte,
+            output_port: state.output_port,
+            pending_command: state.pending_command,
+            last_write_command: state.last_write_command,
+            kbd_pending_command: state.kbd_pending_command,
+            kbd_scanning: state.kbd_scanning,
+            kbd_leds: state.kbd_leds,
+            kbd_typematic: state.kbd_typematic,
+            kbd_break_pending: state.kbd_break_pending,
+            kbd_overrun: state.kbd_overrun,
+            kbd_buffer: state.kbd_buffer.clone(),
+            aux_buffer: state.aux_buffer.clone(),
+        };
+        i8042_state.validate()?;
+        Ok(i8042_state)
+    }
+}
+
+impl From<&I8042State> for I8042StateSer {
+    fn from(state: &I8042State) -> Self {
+        I8042StateSer {
+            command_byte: state.command_byte,
+            output_port: state.output_port,
+            pending_command: state.pending_command,
+            last_write_command: state.last_write_command,
+            kbd_pending_command: state.kbd_pending_command,
+            kbd_scanning: state.kbd_scanning,
+            kbd_leds: state.kbd_leds,
+            kbd_typematic: state.kbd_typematic,
+            kbd_break_pending: state.kbd_break_pending,
+            kbd_overrun: state.kbd_overrun,
+            kbd_buffer: state.kbd_buffer.clone(),
+            aux_buffer: state.aux_buffer.clone(),
+        }
+    }
+}
+
+impl Default for I8042StateSer {
+    fn default() -> Self {
+        I8042StateSer::from(&I8042State::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vm_superio::i8042::NoEvents;
+    use vm_superio::StateError;
+    use vm_superio::{I8042Device, Trigger};
+
+    struct DummyTrigger;
+
+    impl Trigger for DummyTrigger {
+        type E = ();
+
+        fn trigger(&self) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_state_ser_default() {
+        let default_i8042_state_ser = I8042StateSer::default();
+        assert_eq!(
+            I8042State::try_from(&default_i8042_state_ser).unwrap(),
+            I8042State::default()
+        );
+    }
+
+    #[test]
+    fn test_state_ser() {
+        let mut i8042 = I8042Device::new(DummyTrigger);
+
+        // Disable the A20 gate and send some keyboard and mouse data.
+        i8042.write(4, 0xD1).unwrap();
+        i8042.write(0, 0x01).unwrap();
+        i8042.write(0, 0xED).unwrap();
+        i8042.write(0, 0x02).unwrap();
+        i8042.enqueue_kbd_bytes(&[0x1C, 0xF0, 0x1C]).unwrap();
+        i8042.enqueue_aux_bytes(&[0x08, 0x01, 0x02]).unwrap();
+
+        let state = i8042.state();
+        let ser_state = I8042StateSer::from(&state);
+
+        let state_after_restore = I8042State::try_from(&ser_state).unwrap();
+        let mut i8042_after_restore = I8042Device::from_state(
+            &state_after_restore,
+            DummyTrigger,
+            DummyTrigger,
+            DummyTrigger,
+            NoEvents,
+        )
+        .unwrap();
+
+        assert!(!i8042_after_restore.a20_enabled());
+        for &byte in [0xFA, 0xFA, 0x1E, 0x9E, 0x08, 0x01, 0x02].iter() {
+            assert_eq!(i8042_after_restore.read(0), byte);
+        }
+        assert_eq!(state, state_after_restore);
+    }
+
+    #[test]
+    fn test_invalid_state() {
+        // The state is validated by `I8042State::validate`.
+        let state = I8042StateSer {
+            kbd_leds: 0x08,
+            ..Default::default()
+        };
+        assert_eq!(
+            I8042State::try_from(&state),
+            Err(Error::InvalidState(StateError::InvalidRegister {
+                register: "kbd_leds",
+                value: 0x08,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_ser_der_binary() {
+        let state = I8042StateSer {
+            pending_command: Some(0x60),
+            kbd_buffer: vec![0xFA],
+            ..Default::default()
+        };
+        let state_ser = bincode::serialize(&state).unwrap();
+        let state_der = bincode::deserialize(&state_ser).unwrap();
+
+        assert_eq!(state, state_der);
+    }
+
+    #[cfg(feature = "versionize")]
+    #[test]
+    fn test_versionize() {
+        let map = VersionMap::new();
+        let state = I8042StateSer {
+            pending_command: Some(0xD1),
+            aux_buffer: vec![0x08, 0x01, 0x02],
+            ..Default::default()
+        };
+        let mut v1_state = Vec::new();
+
+        Versionize::serialize(&state, &mut v1_state, &map, 1).unwrap();
+
+        let from_v1: I8042StateSer =
+            Versionize::deserialize(&mut v1_state.as_slice(), &map, 1).unwrap();
+
+        assert_eq!(from_v1, state);
+    }
+}
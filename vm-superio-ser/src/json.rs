@@ -0,0 +1,113 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the human-readable JSON dumps of the states.
+//!
+//! The states are written as pretty JSON objects, with the names of their
+//! fields, so that the state of a device, e.g. the registers and the FIFO of a
+//! serial port, can be inspected in a debugging session or a support bundle
+//! without a custom decoder. The dumps aren't meant for migrating the devices:
+//! they aren't versioned, so the snapshots should use a `Versionize` or a
+//! framed `bincode` state instead, as described in the [blob](../blob/index.html)
+//! module.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use vm_superio::Serial;
+//! # use vm_superio_ser::{json, SerialStateSer};
+//! # struct DummyTrigger;
+//! # impl vm_superio::Trigger for DummyTrigger {
+//! #     type E = ();
+//! #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+//! # }
+//! let mut serial = Serial::new(DummyTrigger, Vec::new());
+//! serial.enqueue_raw_bytes(b"a").unwrap();
+//!
+//! let dump = json::to_json(&SerialStateSer::from(&serial.state())).unwrap();
+//! assert!(dump.contains("\"in_buffer\": [\n    97\n  ]"));
+//! ```
+
+use std::io::Write;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// The error of the JSON serialization and deserialization.
+pub use serde_json::Error;
+
+/// Returns the pretty JSON dump of `state`.
+///
+/// # Arguments
+/// * `state` - The state to dump, e.g. a `SerialStateSer`.
+pub fn to_json<S: Serialize>(state: &S) -> Result<String, Error> {
+    serde_json::to_string_pretty(state)
+}
+
+/// Writes the pretty JSON dump of `state` to `writer`, e.g. to a file of a
+/// support bundle.
+///
+/// # Arguments
+/// * `writer` - The destination of the dump.
+/// * `state` - The state to dump, e.g. a `SerialStateSer`.
+pub fn write_json<W: Write, S: Serialize>(writer: W, state: &S) -> Result<(), Error> {
+    serde_json::to_writer_pretty(writer, state)
+}
+
+/// Parses a state from its JSON dump, e.g. for replaying the state of a
+/// support bundle in a test.
+///
+/// # Arguments
+/// * `json` - The JSON dump of the state.
+pub fn from_json<S: DeserializeOwned>(json: &str) -> Result<S, Error> {
+    serde_json::from_str(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{I8042StateSer, PitStateSer, RtcStateSer, SerialStateSer};
+
+    #[test]
+    fn test_json_dump() {
+        let state = RtcStateSer {
+            lr: 0x1234,
+            offset: -5,
+            mr: 0,
+            imsc: 1,
+            ris: 0,
+        };
+        let dump = to_json(&state).unwrap();
+        assert_eq!(
+            dump,
+            "{\n  \"lr\": 4660,\n  \"offset\": -5,\n  \"mr\": 0,\n  \"imsc\": 1,\n  \"ris\": 0\n}"
+        );
+        assert_eq!(from_json::<RtcStateSer>(&dump).unwrap(), state);
+
+        let mut buf = Vec::new();
+        write_json(&mut buf, &state).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), dump);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let serial = SerialStateSer::default();
+        assert_eq!(
+            from_json::<SerialStateSer>(&to_json(&serial).unwrap()).unwrap(),
+            serial
+        );
+        let i8042 = I8042StateSer::default();
+        assert_eq!(
+            from_json::<I8042StateSer>(&to_json(&i8042).unwrap()).unwrap(),
+            i8042
+        );
+        let pit = PitStateSer::default();
+        assert_eq!(
+            from_json::<PitStateSer>(&to_json(&pit).unwrap()).unwrap(),
+            pit
+        );
+
+        assert!(from_json::<RtcStateSer>("{\"lr\": 1}").is_err());
+    }
+}
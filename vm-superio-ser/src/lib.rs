@@ -6,11 +6,97 @@
 //!
 //! Provides wrappers over the state objects from `vm-superio` crate which
 //! implement the `Serialize`, `Deserialize` and `Versionize` traits as well.
+//!
+//! The [SuperioStateSer](chassis/struct.SuperioStateSer.html) holds the
+//! states of all the legacy devices of a VM, so that they are serialized as a
+//! single object.
+//!
+//! The [blob](blob/index.html) module frames the serialized states in blobs
+//! identifying their device and the version of their format.
+//! The [diff](diff/index.html) module lists the fields which differ between
+//! two states, e.g. when investigating a migration, while the
+//! [migration](migration/index.html) module saves and loads the blobs in the
+//! layouts of the older versions of the states.
+//! With the `json` feature, the [json](json/index.html) module dumps the
+//! states as pretty JSON, for debugging them.
+//!
+//! The `Versionize` derives come with the default `versionize` feature, which
+//! needs the standard library. Without it, the state objects only implement
+//! `Serialize` and `Deserialize`, and the crate doesn't depend on
+//! `versionize`.
+//!
+//! The crate is `no_std` when the default `std` and `versionize` features are
+//! disabled, in which case it only depends on `alloc`. The `SuperioStateSer`
+//! needs the `std` feature, like the `SuperioChassis`.
+//!
+//! The conversions from the state objects to the states of `vm-superio` are
+//! fallible: they validate the fields, e.g. the length of the FIFO of a serial
+//! port or the reserved bits of its registers, so that a corrupted or
+//! malicious snapshot is rejected with an [Error](enum.Error.html) instead of
+//! restoring a device in a state it can never reach.
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(missing_docs)]
 
+extern crate alloc;
+
+pub mod blob;
+#[cfg(feature = "std")]
+pub mod chassis;
+pub mod diff;
+pub mod i8042;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod migration;
+pub mod pit;
 pub mod rtc_pl031;
 pub mod serial;
 
+#[cfg(feature = "std")]
+pub use chassis::SuperioStateSer;
+pub use i8042::I8042StateSer;
+pub use pit::PitStateSer;
 pub use rtc_pl031::RtcStateSer;
-pub use serial::SerialStateSer;
+pub use serial::{SerialStateSer, SerialStateSerV1};
+
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
+
+use vm_superio::StateError;
+
+/// Errors encountered while converting the state objects to the states of
+/// `vm-superio`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// The state of a device is one the device can never be in, as checked
+    /// by its `validate` method in `vm-superio`.
+    InvalidState(StateError),
+    /// The state of a device is missing.
+    MissingDevice(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidState(e) => write!(f, "Invalid state: {}", e),
+            Error::MissingDevice(device) => write!(f, "Missing state of device {}", device),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::InvalidState(e) => Some(e),
+            Error::MissingDevice(_) => None,
+        }
+    }
+}
+
+impl From<StateError> for Error {
+    fn from(e: StateError) -> Self {
+        Error::InvalidState(e)
+    }
+}
@@ -0,0 +1,378 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the saving and loading of the states in versioned blobs.
+//!
+//! Each state object has a layout version, its `Migrate::VERSION`, which the
+//! blobs hold next to the state they frame. When a layout changes, its
+//! `upgrade` and `downgrade` functions are written by hand, with the
+//! conversions from and to the previous layout: `load` upgrades the state of
+//! a blob from its version, and `save` downgrades it to the one requested by
+//! the destination.
+//!
+//! The `SerialStateSer` is at its second layout, which adds the FIFO Control
+//! Register to the [SerialStateSerV1](../serial/struct.SerialStateSerV1.html).
+//! Its upgrade fills the register with the FIFOs enabled, as they always were
+//! in the releases saving the first layout, and its downgrade fails with
+//! `LossyDowngrade` when the FIFOs are disabled. The other layouts are at
+//! their first version, the `SuperioStateSer` holding the second layout of the
+//! serial ports.
+//!
+//! The migrations don't depend on the serialization format, which is provided
+//! by a [`Format`](trait.Format.html), e.g. `bincode`.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use serde::de::DeserializeOwned;
+//! # use serde::Serialize;
+//! # use vm_superio_ser::migration::{self, Format};
+//! # use vm_superio_ser::SerialStateSer;
+//! struct Bincode;
+//!
+//! impl Format for Bincode {
+//!     type Error = bincode::Error;
+//!
+//!     fn encode<S: Serialize>(&self, state: &S) -> Result<Vec<u8>, Self::Error> {
+//!         bincode::serialize(state)
+//!     }
+//!
+//!     fn decode<S: DeserializeOwned>(&self, bytes: &[u8]) -> Result<S, Self::Error> {
+//!         bincode::deserialize(bytes)
+//!     }
+//! }
+//!
+//! let state = SerialStateSer::default();
+//! // Save the state for a destination supporting the first version only.
+//! let blob = migration::save(&Bincode, &state, 1).unwrap();
+//! let restored: SerialStateSer = migration::load(&Bincode, &blob).unwrap();
+//! assert_eq!(restored, state);
+//!
+//! // The first version can't hold a serial port with its FIFOs disabled.
+//! let state = SerialStateSer {
+//!     fifo_control: 0,
+//!     ..Default::default()
+//! };
+//! assert!(migration::save(&Bincode, &state, 1).is_err());
+//! assert!(migration::save(&Bincode, &state, 2).is_ok());
+//! ```
+
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::blob::{self, DeviceType};
+#[cfg(feature = "std")]
+use crate::SuperioStateSer;
+use crate::{I8042StateSer, PitStateSer, RtcStateSer, SerialStateSer, SerialStateSerV1};
+
+/// The serialization format of the states.
+pub trait Format {
+    /// The error of the serialization and deserialization.
+    type Error;
+
+    /// Serializes `state`.
+    ///
+    /// # Arguments
+    /// * `state` - The state to serialize, in the layout of any version.
+    fn encode<S: Serialize>(&self, state: &S) -> Result<Vec<u8>, Self::Error>;
+
+    /// Deserializes a state from `bytes`.
+    ///
+    /// # Arguments
+    /// * `bytes` - The serialized state.
+    fn decode<S: DeserializeOwned>(&self, bytes: &[u8]) -> Result<S, Self::Error>;
+}
+
+/// Errors encountered while migrating the states.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Error<E> {
+    /// The blob is invalid, or holds a newer version of the state.
+    Blob(blob::Error),
+    /// The state can't be converted from or to this version.
+    UnsupportedVersion(u16),
+    /// The state can't be downgraded to this version without changing the
+    /// behaviour of the device.
+    LossyDowngrade(u16),
+    /// The state couldn't be serialized or deserialized.
+    Format(E),
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Blob(e) => write!(f, "Invalid state blob: {}", e),
+            Error::UnsupportedVersion(version) => {
+                write!(f, "Unsupported state version {}", version)
+            }
+            Error::LossyDowngrade(version) => {
+                write!(f, "The state doesn't fit in version {}", version)
+            }
+            Error::Format(e) => write!(f, "Failed to (de)serialize the state: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug + fmt::Display> StdError for Error<E> {}
+
+impl<E> From<blob::Error> for Error<E> {
+    fn from(e: blob::Error) -> Self {
+        Error::Blob(e)
+    }
+}
+
+/// A state object with a versioned layout.
+pub trait Migrate: Sized {
+    /// The device of the state, which identifies it in the blobs.
+    const DEVICE: DeviceType;
+    /// The version of the current layout of the state.
+    const VERSION: u16;
+
+    /// Deserializes a state saved in the layout of `version`, and upgrades it
+    /// to the current layout, version by version.
+    ///
+    /// # Arguments
+    /// * `format` - The serialization format of the state.
+    /// * `version` - The version of the layout of the serialized state.
+    /// * `bytes` - The serialized state.
+    fn upgrade<F: Format>(format: &F, version: u16, bytes: &[u8]) -> Result<Self, Error<F::Error>>;
+
+    /// Downgrades the state to the layout of `version`, version by version,
+    /// and serializes it.
+    ///
+    /// # Arguments
+    /// * `format` - The serialization format of the state.
+    /// * `version` - The version of the layout expected by the destination.
+    fn downgrade<F: Format>(&self, format: &F, version: u16) -> Result<Vec<u8>, Error<F::Error>>;
+}
+
+/// Returns the blob framing `state`, downgraded to the layout of `version`.
+///
+/// # Arguments
+/// * `format` - The serialization format of the state.
+/// * `state` - The state to save.
+/// * `version` - The version of the layout expected by the destination, at
+///   most `S::VERSION`.
+pub fn save<S: Migrate, F: Format>(
+    format: &F,
+    state: &S,
+    version: u16,
+) -> Result<Vec<u8>, Error<F::Error>> {
+    let bytes = state.downgrade(format, version)?;
+    Ok(blob::frame(S::DEVICE, version, &bytes)?)
+}
+
+/// Returns the state framed in `blob`, upgraded from the layout of its
+/// version to the current one.
+///
+/// # Arguments
+/// * `format` - The serialization format of the state.
+/// * `blob` - The blob, in any version up to `S::VERSION`.
+pub fn load<S: Migrate, F: Format>(format: &F, blob: &[u8]) -> Result<S, Error<F::Error>> {
+    let (version, bytes) = blob::unframe(blob, S::DEVICE, S::VERSION)?;
+    S::upgrade(format, version, bytes)
+}
+
+// The second layout adds the FIFO Control Register to the first one.
+impl Migrate for SerialStateSer {
+    const DEVICE: DeviceType = DeviceType::Serial;
+    const VERSION: u16 = 2;
+
+    fn upgrade<F: Format>(format: &F, version: u16, bytes: &[u8]) -> Result<Self, Error<F::Error>> {
+        match version {
+            1 => format
+                .decode::<SerialStateSerV1>(bytes)
+                .map(SerialStateSer::from)
+                .map_err(Error::Format),
+            2 => format.decode(bytes).map_err(Error::Format),
+            _ => Err(Error::UnsupportedVersion(version)),
+        }
+    }
+
+    fn downgrade<F: Format>(&self, format: &F, version: u16) -> Result<Vec<u8>, Error<F::Error>> {
+        match version {
+            1 if !self.fits_v1() => Err(Error::LossyDowngrade(version)),
+            1 => format
+                .encode(&SerialStateSerV1::from(self))
+                .map_err(Error::Format),
+            2 => format.encode(self).map_err(Error::Format),
+            _ => Err(Error::UnsupportedVersion(version)),
+        }
+    }
+}
+
+// Implements `Migrate` for a state whose layout never changed since its first
+// version, which only (de)serializes it as it is.
+macro_rules! migrate_v1 {
+    ($state:ty, $device:expr) => {
+        impl Migrate for $state {
+            const DEVICE: DeviceType = $device;
+            const VERSION: u16 = 1;
+
+            fn upgrade<F: Format>(
+                format: &F,
+                version: u16,
+                bytes: &[u8],
+            ) -> Result<Self, Error<F::Error>> {
+                match version {
+                    1 => format.decode(bytes).map_err(Error::Format),
+                    _ => Err(Error::UnsupportedVersion(version)),
+                }
+            }
+
+            fn downgrade<F: Format>(
+                &self,
+                format: &F,
+                version: u16,
+            ) -> Result<Vec<u8>, Error<F::Error>> {
+                match version {
+                    1 => format.encode(self).map_err(Error::Format),
+                    _ => Err(Error::UnsupportedVersion(version)),
+                }
+            }
+        }
+    };
+}
+
+migrate_v1!(I8042StateSer, DeviceType::I8042);
+migrate_v1!(RtcStateSer, DeviceType::Rtc);
+migrate_v1!(PitStateSer, DeviceType::Pit);
+#[cfg(feature = "std")]
+migrate_v1!(SuperioStateSer, DeviceType::Superio);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Bincode;
+
+    impl Format for Bincode {
+        type Error = String;
+
+        fn encode<S: Serialize>(&self, state: &S) -> Result<Vec<u8>, String> {
+            bincode::serialize(state).map_err(|e| e.to_string())
+        }
+
+        fn decode<S: DeserializeOwned>(&self, bytes: &[u8]) -> Result<S, String> {
+            bincode::deserialize(bytes).map_err(|e| e.to_string())
+        }
+    }
+
+    // The blobs saved by the first version of the states, with `bincode`.
+    const SERIAL_V1_BLOB: [u8; 39] = [
+        0x56, 0x4d, 0x53, 0x53, 0x01, 0x00, 0x01, 0x00, 0x13, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x01, 0x03, 0x60, 0x08, 0xb0, 0x42, 0x02,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x61, 0x62,
+    ];
+    const RTC_V1_BLOB: [u8; 44] = [
+        0x56, 0x4d, 0x53, 0x53, 0x03, 0x00, 0x01, 0x00, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x34, 0x12, 0x00, 0x00, 0xfb, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn test_load_stored_blobs() {
+        let serial: SerialStateSer = load(&Bincode, &SERIAL_V1_BLOB).unwrap();
+        assert_eq!(
+            serial,
+            SerialStateSer {
+                scratch: 0x42,
+                in_buffer: vec![b'a', b'b'],
+                ..Default::default()
+            }
+        );
+        assert_eq!(save(&Bincode, &serial, 1).unwrap(), SERIAL_V1_BLOB);
+
+        let rtc: RtcStateSer = load(&Bincode, &RTC_V1_BLOB).unwrap();
+        assert_eq!(
+            rtc,
+            RtcStateSer {
+                lr: 0x1234,
+                offset: -5,
+                ..Default::default()
+            }
+        );
+        assert_eq!(save(&Bincode, &rtc, 1).unwrap(), RTC_V1_BLOB);
+    }
+
+    #[test]
+    fn test_serial_layouts() {
+        let state = SerialStateSer {
+            fifo_control: 0xC1,
+            scratch: 0x42,
+            ..Default::default()
+        };
+        let blob = save(&Bincode, &state, 2).unwrap();
+        assert_eq!(
+            load::<SerialStateSer, _>(&Bincode, &blob),
+            Ok(state.clone())
+        );
+
+        // The trigger level is dropped by the first layout, and the FIFOs are
+        // enabled when upgrading from it.
+        let blob = save(&Bincode, &state, 1).unwrap();
+        assert_eq!(
+            load::<SerialStateSer, _>(&Bincode, &blob),
+            Ok(SerialStateSer {
+                fifo_control: 0x01,
+                ..state
+            })
+        );
+
+        let state = SerialStateSer {
+            fifo_control: 0,
+            ..Default::default()
+        };
+        assert_eq!(save(&Bincode, &state, 1), Err(Error::LossyDowngrade(1)));
+        assert_eq!(save(&Bincode, &state, 3), Err(Error::UnsupportedVersion(3)));
+    }
+
+    #[test]
+    fn test_unsupported_versions() {
+        let state = PitStateSer::default();
+        assert_eq!(save(&Bincode, &state, 0), Err(Error::UnsupportedVersion(0)));
+        assert_eq!(
+            save(&Bincode, &state, PitStateSer::VERSION + 1),
+            Err(Error::UnsupportedVersion(PitStateSer::VERSION + 1))
+        );
+
+        // A blob of a newer release is rejected before being decoded.
+        let payload = bincode::serialize(&state).unwrap();
+        let newer = blob::frame(DeviceType::Pit, PitStateSer::VERSION + 1, &payload).unwrap();
+        assert_eq!(
+            load::<PitStateSer, _>(&Bincode, &newer),
+            Err(Error::Blob(blob::Error::UnsupportedVersion(
+                PitStateSer::VERSION + 1
+            )))
+        );
+        let older = blob::frame(DeviceType::Pit, 0, &payload).unwrap();
+        assert_eq!(
+            load::<PitStateSer, _>(&Bincode, &older),
+            Err(Error::UnsupportedVersion(0))
+        );
+
+        // The state of another device, or a truncated one, is rejected.
+        assert_eq!(
+            load::<I8042StateSer, _>(&Bincode, &SERIAL_V1_BLOB),
+            Err(Error::Blob(blob::Error::UnexpectedDevice(
+                DeviceType::Serial
+            )))
+        );
+        let mut truncated = blob::frame(DeviceType::I8042, 1, &[0x01]).unwrap();
+        assert!(matches!(
+            load::<I8042StateSer, _>(&Bincode, &truncated),
+            Err(Error::Format(_))
+        ));
+        truncated.pop();
+        assert!(matches!(
+            load::<I8042StateSer, _>(&Bincode, &truncated),
+            Err(Error::Blob(blob::Error::LengthMismatch { .. }))
+        ));
+    }
+}
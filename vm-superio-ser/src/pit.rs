@@ -0,0 +1,243 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides a wrapper over a `PitState` that has serialization capabilities.
+//!
+//! This module defines the `PitStateSer` abstraction which mirrors the
+//! `PitState` from the base crate, and adds on top of it derives for
+//! the `Serialize`, `Deserialize` and `Versionize` traits.
+
+use core::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "versionize")]
+use versionize::{VersionMap, Versionize, VersionizeResult};
+#[cfg(feature = "versionize")]
+use versionize_derive::Versionize;
+use vm_superio::pit::PitChannelState;
+use vm_superio::PitState;
+
+use crate::Error;
+
+/// Wrapper over a `PitChannelState` that has serialization capabilities.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "versionize", derive(Versionize))]
+pub struct PitChannelStateSer {
+    /// Operating mode.
+    pub mode: u8,
+    /// Access mode.
+    pub access: u8,
+    /// Initial count. A value of 0 stands for 0x10000.
+    pub reload: u16,
+    /// Time elapsed since the counter was loaded with the initial count, in
+    /// nanoseconds.
+    pub elapsed_ns: u64,
+    /// Whether a complete initial count was written after the operating
+    /// mode was set.
+    pub armed: bool,
+    /// Whether the counter counts.
+    pub counting: bool,
+    /// Level of the gate input.
+    pub gate: bool,
+    /// Number of ticks counted before the gate went low, which pauses the
+    /// counter in modes 0 and 4.
+    pub paused_ticks: Option<u64>,
+    /// Low byte of the initial count, waiting for the high byte.
+    pub write_low: Option<u8>,
+    /// Whether the next read returns the high byte of the counter.
+    pub read_high: bool,
+    /// Counter value latched by the latch or read-back commands.
+    pub latched_count: Option<u16>,
+    /// Status byte latched by the read-back command.
+    pub latched_status: Option<u8>,
+    /// Number of ticks since the counter was loaded after which the output
+    /// has its next rising edge, if any.
+    pub next_edge: Option<u64>,
+}
+
+/// Wrapper over a `PitState` that has serialization capabilities.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "versionize", derive(Versionize))]
+pub struct PitStateSer {
+    /// The state of each channel.
+    pub channels: [PitChannelStateSer; 3],
+}
+
+// The following implementations can be used to convert from a `PitStateSer` to the
+// `PitState` from the base crate, validating it, and vice versa.
+impl TryFrom<&PitChannelStateSer> for PitChannelState {
+    type Error = Error;
+
+    fn try_from(state: &PitChannelStateSer) -> Result<Self, Error> {
+        let channel_state = PitChannelState {
+            mode: state.mode,
+            access: state.access,
+            reload: state.reload,
+            elapsed_ns: state.elapsed_ns,
+            armed: state.armed,
+            counting: state.counting,
+            gate: state.gate,
+            paused_ticks: state.paused_ticks,
+            write_low: state.write_low,
+            read_high: state.read_high,
+            latched_count: state.latched_count,
+            latched_status: state.latched_status,
+            next_edge: state.next_edge,
+        };
+        channel_state.validate()?;
+        Ok(channel_state)
+    }
+}
+
+impl From<&PitChannelState> for PitChannelStateSer {
+    fn from(state: &PitChannelState) -> Self {
+        PitChannelStateSer {
+            mode: state.mode,
+            access: state.access,
+            reload: state.reload,
+            elapsed_ns: state.elapsed_ns,
+            armed: state.armed,
+            counting: state.counting,
+            gate: state.gate,
+            paused_ticks: state.paused_ticks,
+            write_low: state.write_low,
+            read_high: state.read_high,
+            latched_count: state.latched_count,
+            latched_status: state.latched_status,
+            next_edge: state.next_edge,
+        }
+    }
+}
+
+impl TryFrom<&PitStateSer> for PitState {
+    type Error = Error;
+
+    fn try_from(state: &PitStateSer) -> Result<Self, Error> {
+        let mut pit_state = PitState::default();
+        for (channel, channel_ser) in pit_state.channels.iter_mut().zip(&state.channels) {
+            *channel = PitChannelState::try_from(channel_ser)?;
+        }
+        Ok(pit_state)
+    }
+}
+
+impl From<&PitState> for PitStateSer {
+    fn from(state: &PitState) -> Self {
+        PitStateSer {
+            channels: state
+                .channels
+                .map(|channel| PitChannelStateSer::from(&channel)),
+        }
+    }
+}
+
+impl Default for PitChannelStateSer {
+    fn default() -> Self {
+        PitChannelStateSer::from(&PitChannelState::default())
+    }
+}
+
+impl Default for PitStateSer {
+    fn default() -> Self {
+        PitStateSer::from(&PitState::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vm_superio::testing::MockClock;
+    use vm_superio::StateError;
+    use vm_superio::{Pit, Trigger};
+
+    struct DummyTrigger;
+
+    impl Trigger for DummyTrigger {
+        type E = ();
+
+        fn trigger(&self) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_state_ser_default() {
+        let default_pit_state_ser = PitStateSer::default();
+        assert_eq!(
+            PitState::try_from(&default_pit_state_ser).unwrap(),
+            PitState::default()
+        );
+    }
+
+    #[test]
+    fn test_state_ser() {
+        let clock = MockClock::new();
+        let mut pit = Pit::new(DummyTrigger, clock.clone());
+
+        // Program channel 0 as a rate generator, and let 1 ms pass.
+        pit.write(3, 0x34);
+        pit.write(0, 0x9B);
+        pit.write(0, 0x2E);
+        clock.set_ns(1_000_000);
+
+        let state = pit.state();
+        let ser_state = PitStateSer::from(&state);
+
+        // Restore the state on top of a different clock.
+        let state_after_restore = PitState::try_from(&ser_state).unwrap();
+        let mut pit_after_restore =
+            Pit::from_state(&state_after_restore, DummyTrigger, MockClock::new());
+
+        // The counter has the same value as before saving the state.
+        let low = pit_after_restore.read(0);
+        let high = pit_after_restore.read(0);
+        assert_eq!(u16::from_le_bytes([low, high]), 11931 - 1193);
+        // The first interrupt, due at 9_999_313 ns on the original clock, is
+        // due 1 ms earlier on the new one.
+        assert_eq!(pit_after_restore.next_deadline(), Some(8_999_313));
+        assert_eq!(state, state_after_restore);
+    }
+
+    #[test]
+    fn test_invalid_state() {
+        // Each channel is validated by `PitChannelState::validate`.
+        let mut state = PitStateSer::default();
+        state.channels[2].mode = 6;
+        assert_eq!(
+            PitState::try_from(&state),
+            Err(Error::InvalidState(StateError::InvalidRegister {
+                register: "mode",
+                value: 6,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_ser_der_binary() {
+        let mut state = PitStateSer::default();
+        state.channels[0].reload = 11932;
+        state.channels[2].latched_count = Some(0x1234);
+        let state_ser = bincode::serialize(&state).unwrap();
+        let state_der = bincode::deserialize(&state_ser).unwrap();
+
+        assert_eq!(state, state_der);
+    }
+
+    #[cfg(feature = "versionize")]
+    #[test]
+    fn test_versionize() {
+        let map = VersionMap::new();
+        let mut state = PitStateSer::default();
+        state.channels[0].elapsed_ns = 1_000_000;
+        state.channels[1].next_edge = Some(0x10000);
+        let mut v1_state = Vec::new();
+
+        Versionize::serialize(&state, &mut v1_state, &map, 1).unwrap();
+
+        let from_v1: PitStateSer =
+            Versionize::deserialize(&mut v1_state.as_slice(), &map, 1).unwrap();
+
+        assert_eq!(from_v1, state);
+    }
+}
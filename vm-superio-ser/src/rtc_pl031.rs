@@ -8,13 +8,20 @@
 //! `RtcState` from the base crate, and adds on top of it derives for
 //! the `Serialize`, `Deserialize` and `Versionize` traits.
 
+use core::convert::TryFrom;
+
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "versionize")]
 use versionize::{VersionMap, Versionize, VersionizeResult};
+#[cfg(feature = "versionize")]
 use versionize_derive::Versionize;
 use vm_superio::RtcState;
 
+use crate::Error;
+
 /// Wrapper over an `RtcState` that has serialization capabilities.
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Versionize)]
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "versionize", derive(Versionize))]
 pub struct RtcStateSer {
     /// The load register.
     pub lr: u32,
@@ -28,17 +35,21 @@ pub struct RtcStateSer {
     pub ris: u32,
 }
 
-// The following `From` implementations can be used to convert from an `RtcStateSer` to the
-// `RtcState` from the base crate and vice versa.
-impl From<&RtcStateSer> for RtcState {
-    fn from(state: &RtcStateSer) -> Self {
-        RtcState {
+// The following implementations can be used to convert from an `RtcStateSer` to the
+// `RtcState` from the base crate, validating it, and vice versa.
+impl TryFrom<&RtcStateSer> for RtcState {
+    type Error = Error;
+
+    fn try_from(state: &RtcStateSer) -> Result<Self, Error> {
+        let rtc_state = RtcState {
             lr: state.lr,
             offset: state.offset,
             mr: state.mr,
             imsc: state.imsc,
             ris: state.ris,
-        }
+        };
+        rtc_state.validate()?;
+        Ok(rtc_state)
     }
 }
 
@@ -65,6 +76,7 @@ mod tests {
     use super::*;
     use vm_superio::rtc_pl031::NoEvents;
     use vm_superio::Rtc;
+    use vm_superio::StateError;
 
     #[test]
     fn test_state_ser() {
@@ -82,7 +94,7 @@ mod tests {
         let state = rtc.state();
         let ser_state = RtcStateSer::from(&state);
 
-        let state_after_restore = RtcState::from(&ser_state);
+        let state_after_restore = RtcState::try_from(&ser_state).unwrap();
         let mut rtc_after_restore = Rtc::from_state(&state_after_restore, NoEvents);
 
         // Reading from the LR register should return the same value as before saving the state.
@@ -95,6 +107,25 @@ mod tests {
 
         // Test the `Default` implementation of RtcStateSer.
         let default_rtc_state_ser = RtcStateSer::default();
-        assert_eq!(RtcState::from(&default_rtc_state_ser), RtcState::default());
+        assert_eq!(
+            RtcState::try_from(&default_rtc_state_ser).unwrap(),
+            RtcState::default()
+        );
+    }
+
+    #[test]
+    fn test_invalid_state() {
+        // The state is validated by `RtcState::validate`.
+        let state = RtcStateSer {
+            imsc: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            RtcState::try_from(&state),
+            Err(Error::InvalidState(StateError::InvalidRegister {
+                register: "imsc",
+                value: 2,
+            }))
+        );
     }
 }
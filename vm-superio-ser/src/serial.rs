@@ -7,15 +7,65 @@
 //! This module defines the `SerialStateSer` abstraction which mirrors the
 //! `SerialState` from the base crate, and adds on top of it derives for
 //! the `Serialize`, `Deserialize` and `Versionize` traits.
+//!
+//! The `SerialStateSerV1` is the first layout of the state, saved by the
+//! releases before the FIFO Control Register was kept, from which the
+//! [migration](../migration/index.html) module upgrades the state.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
 
+#[cfg(feature = "versionize")]
+use alloc::format;
 use serde::{Deserialize, Serialize};
-use versionize::{VersionMap, Versionize, VersionizeResult};
+#[cfg(feature = "versionize")]
+use versionize::{VersionMap, Versionize, VersionizeError, VersionizeResult};
+#[cfg(feature = "versionize")]
 use versionize_derive::Versionize;
 use vm_superio::SerialState;
 
+use crate::Error;
+
 /// Wrapper over an `SerialState` that has serialization capabilities.
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Versionize)]
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "versionize", derive(Versionize))]
 pub struct SerialStateSer {
+    /// Divisor Latch Low Byte
+    pub baud_divisor_low: u8,
+    /// Divisor Latch High Byte
+    pub baud_divisor_high: u8,
+    /// Interrupt Enable Register
+    pub interrupt_enable: u8,
+    /// Interrupt Identification Register
+    pub interrupt_identification: u8,
+    /// FIFO Control Register
+    #[cfg_attr(
+        feature = "versionize",
+        version(
+            start = 2,
+            default_fn = "default_fifo_control",
+            ser_fn = "ser_fifo_control"
+        )
+    )]
+    pub fifo_control: u8,
+    /// Line Control Register
+    pub line_control: u8,
+    /// Line Status Register
+    pub line_status: u8,
+    /// Modem Control Register
+    pub modem_control: u8,
+    /// Modem Status Register
+    pub modem_status: u8,
+    /// Scratch Register
+    pub scratch: u8,
+    /// Transmitter Holding Buffer/Receiver Buffer
+    pub in_buffer: Vec<u8>,
+}
+
+/// The first layout of the `SerialStateSer`, without the FIFO Control
+/// Register.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct SerialStateSerV1 {
     /// Divisor Latch Low Byte
     pub baud_divisor_low: u8,
     /// Divisor Latch High Byte
@@ -38,11 +88,58 @@ pub struct SerialStateSer {
     pub in_buffer: Vec<u8>,
 }
 
-// The following `From` implementations can be used to convert from an `SerialStateSer` to the
-// `SerialState` from the base crate and vice versa.
-impl From<&SerialStateSer> for SerialState {
+impl SerialStateSer {
+    // The serial ports of the releases saving the first layout always have
+    // their FIFOs enabled, as they are out of reset.
+    fn default_fifo_control(_source_version: u16) -> u8 {
+        SerialState::default_state().fifo_control
+    }
+
+    // Returns whether the first layout keeps the state, which is the case
+    // when the FIFOs are enabled. The trigger level and the DMA mode, which
+    // aren't emulated, are dropped.
+    pub(crate) fn fits_v1(&self) -> bool {
+        let enabled = Self::default_fifo_control(1);
+        self.fifo_control & enabled == enabled
+    }
+
+    #[cfg(feature = "versionize")]
+    fn ser_fifo_control(&mut self, target_version: u16) -> VersionizeResult<()> {
+        if self.fits_v1() {
+            Ok(())
+        } else {
+            Err(VersionizeError::Semantic(format!(
+                "The FIFOs are disabled, which version {} doesn't support",
+                target_version
+            )))
+        }
+    }
+}
+
+// The state is upgraded from its first layout with the FIFOs enabled, and
+// downgraded to it by dropping the FIFO Control Register, once checked with
+// `fits_v1`.
+impl From<SerialStateSerV1> for SerialStateSer {
+    fn from(state: SerialStateSerV1) -> Self {
+        SerialStateSer {
+            baud_divisor_low: state.baud_divisor_low,
+            baud_divisor_high: state.baud_divisor_high,
+            interrupt_enable: state.interrupt_enable,
+            interrupt_identification: state.interrupt_identification,
+            fifo_control: Self::default_fifo_control(1),
+            line_control: state.line_control,
+            line_status: state.line_status,
+            modem_control: state.modem_control,
+            modem_status: state.modem_status,
+            scratch: state.scratch,
+            in_buffer: state.in_buffer,
+        }
+    }
+}
+
+impl From<&SerialStateSer> for SerialStateSerV1 {
     fn from(state: &SerialStateSer) -> Self {
-        SerialState {
+        SerialStateSerV1 {
             baud_divisor_low: state.baud_divisor_low,
             baud_divisor_high: state.baud_divisor_high,
             interrupt_enable: state.interrupt_enable,
@@ -57,6 +154,30 @@ impl From<&SerialStateSer> for SerialState {
     }
 }
 
+// The following implementations can be used to convert from an `SerialStateSer` to the
+// `SerialState` from the base crate, validating it, and vice versa.
+impl TryFrom<&SerialStateSer> for SerialState {
+    type Error = Error;
+
+    fn try_from(state: &SerialStateSer) -> Result<Self, Error> {
+        let serial_state = SerialState {
+            baud_divisor_low: state.baud_divisor_low,
+            baud_divisor_high: state.baud_divisor_high,
+            interrupt_enable: state.interrupt_enable,
+            interrupt_identification: state.interrupt_identification,
+            fifo_control: state.fifo_control,
+            line_control: state.line_control,
+            line_status: state.line_status,
+            modem_control: state.modem_control,
+            modem_status: state.modem_status,
+            scratch: state.scratch,
+            in_buffer: state.in_buffer.clone(),
+        };
+        serial_state.validate()?;
+        Ok(serial_state)
+    }
+}
+
 impl From<&SerialState> for SerialStateSer {
     fn from(state: &SerialState) -> Self {
         SerialStateSer {
@@ -64,6 +185,7 @@ impl From<&SerialState> for SerialStateSer {
             baud_divisor_high: state.baud_divisor_high,
             interrupt_enable: state.interrupt_enable,
             interrupt_identification: state.interrupt_identification,
+            fifo_control: state.fifo_control,
             line_control: state.line_control,
             line_status: state.line_status,
             modem_control: state.modem_control,
@@ -86,6 +208,7 @@ mod tests {
     use std::io::sink;
     use std::ops::Deref;
     use vm_superio::serial::NoEvents;
+    use vm_superio::StateError;
     use vm_superio::{Serial, Trigger};
     use vmm_sys_util::eventfd::EventFd;
 
@@ -122,7 +245,7 @@ mod tests {
     fn test_state_ser_default() {
         let default_serial_state_ser = SerialStateSer::default();
         assert_eq!(
-            SerialState::from(&default_serial_state_ser),
+            SerialState::try_from(&default_serial_state_ser).unwrap(),
             SerialState::default()
         );
     }
@@ -131,7 +254,7 @@ mod tests {
     fn test_state_ser_idempotency() {
         let state = SerialState::default();
         let state_ser = SerialStateSer::from(&state);
-        let state_from_ser = SerialState::from(&state_ser);
+        let state_from_ser = SerialState::try_from(&state_ser).unwrap();
 
         assert_eq!(state, state_from_ser);
     }
@@ -146,7 +269,7 @@ mod tests {
         let state = serial.state();
         let ser_state = SerialStateSer::from(&state);
 
-        let state_after_restore = SerialState::from(&ser_state);
+        let state_after_restore = SerialState::try_from(&ser_state).unwrap();
         let mut serial_after_restore =
             Serial::from_state(&state_after_restore, intr_evt.try_clone(), NoEvents, sink())
                 .unwrap();
@@ -157,6 +280,22 @@ mod tests {
         assert_eq!(state, state_after_restore);
     }
 
+    #[test]
+    fn test_invalid_state() {
+        // The state is validated by `SerialState::validate`.
+        let state = SerialStateSer {
+            interrupt_enable: 0x10,
+            ..Default::default()
+        };
+        assert_eq!(
+            SerialState::try_from(&state),
+            Err(Error::InvalidState(StateError::InvalidRegister {
+                register: "interrupt_enable",
+                value: 0x10,
+            }))
+        );
+    }
+
     #[test]
     fn test_ser_der_binary() {
         let state = SerialStateSer::default();
@@ -166,6 +305,7 @@ mod tests {
         assert_eq!(state, state_der);
     }
 
+    #[cfg(feature = "versionize")]
     #[test]
     fn test_versionize() {
         let map = VersionMap::new();
@@ -178,5 +318,52 @@ mod tests {
             Versionize::deserialize(&mut v1_state.as_slice(), &map, 1).unwrap();
 
         assert_eq!(from_v1, state);
+
+        // The first version has no FIFO Control Register, and can't hold the
+        // serial ports with their FIFOs disabled.
+        let state = SerialStateSer {
+            fifo_control: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            Versionize::serialize(&state, &mut Vec::new(), &map, 1),
+            Err(VersionizeError::Semantic(_))
+        ));
+
+        let mut map = VersionMap::new();
+        map.new_version()
+            .set_type_version(SerialStateSer::type_id(), 2);
+        let mut v2_state = Vec::new();
+        Versionize::serialize(&state, &mut v2_state, &map, 2).unwrap();
+        let from_v2: SerialStateSer =
+            Versionize::deserialize(&mut v2_state.as_slice(), &map, 2).unwrap();
+        assert_eq!(from_v2, state);
+    }
+
+    #[test]
+    fn test_first_layout() {
+        let state = SerialStateSer {
+            fifo_control: 0xC1,
+            scratch: 0x42,
+            ..Default::default()
+        };
+        assert!(state.fits_v1());
+        let v1_state = SerialStateSerV1::from(&state);
+        assert_eq!(v1_state.scratch, 0x42);
+
+        // The FIFOs are enabled when upgrading, but the trigger level is lost.
+        assert_eq!(
+            SerialStateSer::from(v1_state),
+            SerialStateSer {
+                scratch: 0x42,
+                ..Default::default()
+            }
+        );
+
+        let state = SerialStateSer {
+            fifo_control: 0,
+            ..Default::default()
+        };
+        assert!(!state.fits_v1());
     }
 }
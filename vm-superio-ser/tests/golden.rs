@@ -0,0 +1,186 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Checks that the states serialized by the published releases of
+//! `vm-superio-ser` are still deserialized and restored.
+//!
+//! The `golden` directory holds, for each release, the states saved with
+//! `bincode` and with `Versionize`, at version 1 of an empty `VersionMap`:
+//! `serial.*` from a serial port in 8N1 mode, with the received data
+//! available interrupt enabled, the scratch register set to 0x42 and the
+//! bytes `ab` in its FIFO, and `rtc.*` from an RTC with the load register set
+//! to 0x1234, an offset of -5 and its interrupt unmasked. The releases before
+//! v0.2.0 have no serial port state.
+//!
+//! The blobs are built by the `golden/generator` crate, from the releases
+//! pinned in its manifest. They are never rewritten by the current code: each
+//! release adds its own directory, and a change breaking the restore of an
+//! older one must come with a migration. The serial port states of the
+//! releases are in the first layout of the `SerialStateSer`, without the FIFO
+//! Control Register.
+
+#![cfg(feature = "std")]
+
+use std::convert::TryFrom;
+use std::fs;
+use std::io::sink;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+#[cfg(feature = "versionize")]
+use versionize::{VersionMap, Versionize};
+use vm_superio::rtc_pl031::NoEvents as NoRtcEvents;
+use vm_superio::serial::NoEvents as NoSerialEvents;
+use vm_superio::testing::MockTrigger;
+use vm_superio::{Rtc, RtcState, Serial, SerialState};
+use vm_superio_ser::blob::{self, DeviceType};
+use vm_superio_ser::migration::{self, Format};
+use vm_superio_ser::{RtcStateSer, SerialStateSer, SerialStateSerV1};
+
+// The published releases, with whether they have a serial port state.
+const RELEASES: [(&str, bool); 4] = [
+    ("v0.1.0", false),
+    ("v0.2.0", true),
+    ("v0.3.0", true),
+    ("v0.4.0", true),
+];
+
+fn golden(release: &str, name: &str) -> Vec<u8> {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", release, name]
+        .iter()
+        .collect();
+    fs::read(&path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e))
+}
+
+fn expected_serial() -> SerialStateSer {
+    SerialStateSer {
+        baud_divisor_low: 0x0C,
+        baud_divisor_high: 0x00,
+        interrupt_enable: 0x01,
+        interrupt_identification: 0x04,
+        // The FIFOs are enabled when upgrading from the first layout.
+        fifo_control: 0x01,
+        line_control: 0x03,
+        line_status: 0x61,
+        modem_control: 0x08,
+        modem_status: 0xB0,
+        scratch: 0x42,
+        in_buffer: b"ab".to_vec(),
+    }
+}
+
+fn expected_rtc() -> RtcStateSer {
+    RtcStateSer {
+        lr: 0x1234,
+        offset: -5,
+        mr: 0,
+        imsc: 1,
+        ris: 0,
+    }
+}
+
+// Restores a serial port from `state`, and checks that the guest reads the
+// bytes of its FIFO.
+fn check_serial_restore(release: &str, state: &SerialStateSer) {
+    assert_eq!(*state, expected_serial(), "serial state of {}", release);
+
+    let state = SerialState::try_from(state).unwrap();
+    let intr_evt = MockTrigger::new();
+    let mut serial = Serial::from_state(&state, intr_evt.clone(), NoSerialEvents, sink()).unwrap();
+    // The pending received data raises an interrupt on restore.
+    assert_eq!(intr_evt.count(), 1);
+    assert_eq!(serial.read(7), 0x42);
+    assert_eq!(serial.read(0), b'a');
+    assert_eq!(serial.read(0), b'b');
+    assert_eq!(serial.state().in_buffer, Vec::<u8>::new());
+}
+
+// Restores an RTC from `state`, and checks its registers.
+fn check_rtc_restore(release: &str, state: &RtcStateSer) {
+    assert_eq!(*state, expected_rtc(), "RTC state of {}", release);
+
+    let state = RtcState::try_from(state).unwrap();
+    let mut rtc = Rtc::from_state(&state, NoRtcEvents);
+    let mut data = [0; 4];
+    // RTCLR.
+    rtc.read(0x008, &mut data);
+    assert_eq!(u32::from_le_bytes(data), 0x1234);
+    // RTCIMSC.
+    rtc.read(0x010, &mut data);
+    assert_eq!(u32::from_le_bytes(data), 1);
+}
+
+#[test]
+fn test_golden_bincode() {
+    for (release, has_serial) in RELEASES {
+        if has_serial {
+            let state: SerialStateSerV1 =
+                bincode::deserialize(&golden(release, "serial.bincode")).unwrap();
+            check_serial_restore(release, &SerialStateSer::from(state));
+        }
+        let state: RtcStateSer = bincode::deserialize(&golden(release, "rtc.bincode")).unwrap();
+        check_rtc_restore(release, &state);
+    }
+}
+
+#[cfg(feature = "versionize")]
+#[test]
+fn test_golden_versionize() {
+    let map = VersionMap::new();
+    for (release, has_serial) in RELEASES {
+        if has_serial {
+            let bytes = golden(release, "serial.versionize");
+            let state = SerialStateSer::deserialize(&mut bytes.as_slice(), &map, 1).unwrap();
+            check_serial_restore(release, &state);
+            // The state is saved back in the first version as it was.
+            let mut saved = Vec::new();
+            Versionize::serialize(&state, &mut saved, &map, 1).unwrap();
+            assert_eq!(saved, bytes);
+        }
+        let bytes = golden(release, "rtc.versionize");
+        let state = RtcStateSer::deserialize(&mut bytes.as_slice(), &map, 1).unwrap();
+        check_rtc_restore(release, &state);
+    }
+}
+
+struct Bincode;
+
+impl Format for Bincode {
+    type Error = String;
+
+    fn encode<S: Serialize>(&self, state: &S) -> Result<Vec<u8>, String> {
+        bincode::serialize(state).map_err(|e| e.to_string())
+    }
+
+    fn decode<S: DeserializeOwned>(&self, bytes: &[u8]) -> Result<S, String> {
+        bincode::deserialize(bytes).map_err(|e| e.to_string())
+    }
+}
+
+// The releases before the blobs saved the states as they are, which must be
+// the payloads of the blobs of the first version of the layouts. They are
+// upgraded to the current layouts, and downgraded back to the same bytes.
+#[test]
+fn test_golden_first_layout() {
+    for (release, has_serial) in RELEASES {
+        if has_serial {
+            let bytes = golden(release, "serial.bincode");
+            let blob = blob::frame(DeviceType::Serial, 1, &bytes).unwrap();
+            let state: SerialStateSer = migration::load(&Bincode, &blob).unwrap();
+            check_serial_restore(release, &state);
+            assert_eq!(migration::save(&Bincode, &state, 1).unwrap(), blob);
+            let blob = migration::save(&Bincode, &state, 2).unwrap();
+            assert_eq!(
+                migration::load::<SerialStateSer, _>(&Bincode, &blob),
+                Ok(state)
+            );
+        }
+        let bytes = golden(release, "rtc.bincode");
+        let blob = blob::frame(DeviceType::Rtc, 1, &bytes).unwrap();
+        let state: RtcStateSer = migration::load(&Bincode, &blob).unwrap();
+        check_rtc_restore(release, &state);
+        assert_eq!(migration::save(&Bincode, &state, 1).unwrap(), blob);
+    }
+}
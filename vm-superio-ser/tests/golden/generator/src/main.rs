@@ -0,0 +1,93 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Writes the states checked by the `golden` test, as serialized by each
+//! published release of `vm-superio-ser`, to `<dir>/<release>`.
+
+use std::env;
+use std::fs;
+use std::io::sink;
+use std::path::{Path, PathBuf};
+
+struct NoopTrigger;
+
+macro_rules! impl_trigger {
+    ($superio:ident) => {
+        impl $superio::Trigger for NoopTrigger {
+            type E = ();
+
+            fn trigger(&self) -> Result<(), ()> {
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_trigger!(superio_0_6);
+impl_trigger!(superio_0_7);
+impl_trigger!(superio_0_8);
+
+fn write(dir: &Path, name: &str, bytes: &[u8]) {
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join(name), bytes).unwrap();
+}
+
+// An RTC with the load register set to 0x1234, an offset of -5 and its
+// interrupt unmasked.
+macro_rules! write_rtc {
+    ($dir:expr, $ser:ident, $versionize:ident) => {{
+        use $versionize::Versionize;
+
+        let state = $ser::RtcStateSer {
+            lr: 0x1234,
+            offset: -5,
+            mr: 0,
+            imsc: 1,
+            ris: 0,
+        };
+        let mut bytes = Vec::new();
+        state
+            .serialize(&mut bytes, &$versionize::VersionMap::new(), 1)
+            .unwrap();
+        write($dir, "rtc.versionize", &bytes);
+        write($dir, "rtc.bincode", &bincode::serialize(&state).unwrap());
+    }};
+}
+
+// A serial port in 8N1 mode, with the received data available interrupt
+// enabled, the scratch register set to 0x42 and the bytes `ab` in its FIFO.
+macro_rules! write_serial {
+    ($dir:expr, $ser:ident, $superio:ident, $versionize:ident) => {{
+        use $versionize::Versionize;
+
+        let mut serial = $superio::Serial::new(NoopTrigger, sink());
+        serial.write(3, 0x03).unwrap();
+        serial.write(1, 0x01).unwrap();
+        serial.write(7, 0x42).unwrap();
+        serial.enqueue_raw_bytes(b"ab").unwrap();
+        let state = $ser::SerialStateSer::from(&serial.state());
+        let mut bytes = Vec::new();
+        state
+            .serialize(&mut bytes, &$versionize::VersionMap::new(), 1)
+            .unwrap();
+        write($dir, "serial.versionize", &bytes);
+        write($dir, "serial.bincode", &bincode::serialize(&state).unwrap());
+    }};
+}
+
+fn main() {
+    let out = PathBuf::from(env::args().nth(1).expect("usage: golden-generator <dir>"));
+
+    // v0.1.0 has no serial port state.
+    write_rtc!(&out.join("v0.1.0"), ser_0_1, versionize_0_2);
+
+    write_rtc!(&out.join("v0.2.0"), ser_0_2, versionize_0_2);
+    write_serial!(&out.join("v0.2.0"), ser_0_2, superio_0_6, versionize_0_2);
+
+    write_rtc!(&out.join("v0.3.0"), ser_0_3, versionize_0_1);
+    write_serial!(&out.join("v0.3.0"), ser_0_3, superio_0_7, versionize_0_1);
+
+    write_rtc!(&out.join("v0.4.0"), ser_0_4, versionize_0_2);
+    write_serial!(&out.join("v0.4.0"), ser_0_4, superio_0_8, versionize_0_2);
+}
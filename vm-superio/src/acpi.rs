@@ -0,0 +1,277 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides helpers generating the ACPI device entries of the port I/O
+//! devices.
+//!
+//! The helpers return the AML encoding of `Device` objects, with their
+//! hardware ID and their current resources, which the VMM appends to the
+//! DSDT, in the scope of the ISA bridge or of `\_SB`. The I/O ranges are
+//! derived from the registers of the emulated devices, so that the tables
+//! describe the devices as they are instantiated.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::i8042::{COMMAND_OFFSET, DATA_OFFSET};
+
+// Number of the serial port registers, from the data register to the
+// scratch one.
+const SERIAL_NUM_REGS: u8 = 8;
+
+// AML opcodes.
+const ZERO_OP: u8 = 0x00;
+const ONE_OP: u8 = 0x01;
+const NAME_OP: u8 = 0x08;
+const BYTE_PREFIX: u8 = 0x0A;
+const DWORD_PREFIX: u8 = 0x0C;
+const BUFFER_OP: u8 = 0x11;
+const EXT_OP_PREFIX: u8 = 0x5B;
+const DEVICE_OP: u8 = 0x82;
+
+// Small resource descriptors.
+// IRQ descriptor, without the information byte, i.e. an edge triggered,
+// active high interrupt.
+const IRQ_NO_FLAGS_TAG: u8 = 0x22;
+// I/O port descriptor.
+const IO_PORT_TAG: u8 = 0x47;
+// Decodes the 16 bits of the port addresses.
+const IO_DECODE_16: u8 = 0x01;
+// End tag, followed by a zero checksum, which means that the template is
+// considered valid.
+const END_TAG: u8 = 0x79;
+
+// Returns the AML encoding of the package length of `len` bytes, which
+// counts the bytes of the encoding itself.
+fn pkg_length(len: usize) -> Vec<u8> {
+    // A single byte holds up to 63.
+    if len < 0x40 - 1 {
+        return vec![(len + 1) as u8];
+    }
+
+    // Otherwise, the lead byte holds the number of the following bytes, and
+    // the low 4 bits of the length, while the following bytes hold the rest.
+    let mut num_bytes = 1;
+    while (len + num_bytes + 1) >= (1 << (4 + 8 * num_bytes)) {
+        num_bytes += 1;
+    }
+    let total = len + num_bytes + 1;
+    let mut bytes = vec![((num_bytes as u8) << 6) | (total as u8 & 0x0F)];
+    for i in 0..num_bytes {
+        bytes.push((total >> (4 + 8 * i)) as u8);
+    }
+    bytes
+}
+
+// Returns the AML encoding of `value`.
+fn integer(value: u32) -> Vec<u8> {
+    match value {
+        0 => vec![ZERO_OP],
+        1 => vec![ONE_OP],
+        _ => match u8::try_from(value) {
+            Ok(byte) => vec![BYTE_PREFIX, byte],
+            Err(_) => {
+                let mut bytes = vec![DWORD_PREFIX];
+                bytes.extend_from_slice(&value.to_le_bytes());
+                bytes
+            }
+        },
+    }
+}
+
+// Returns the compressed EISA ID of `id`, e.g. "PNP0501", as encoded by the
+// `EISAID` ASL macro.
+fn eisa_id(id: &str) -> u32 {
+    let bytes = id.as_bytes();
+    let vendor = bytes[..3]
+        .iter()
+        .fold(0u32, |value, &c| (value << 5) | u32::from(c - 0x40));
+    let product = u32::from_str_radix(&id[3..], 16).unwrap_or(0);
+    // The ID is stored in big-endian order.
+    ((vendor << 16) | product).swap_bytes()
+}
+
+// Returns the AML encoding of a `Name` object called `name`, holding the
+// encoded `value`.
+fn name(name: &str, value: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![NAME_OP];
+    bytes.extend_from_slice(name.as_bytes());
+    bytes.extend_from_slice(value);
+    bytes
+}
+
+// Returns the AML encoding of a resource template, i.e. of a buffer holding
+// the `descriptors`, followed by the end tag.
+fn resource_template(descriptors: &[Vec<u8>]) -> Vec<u8> {
+    let mut buffer: Vec<u8> = descriptors.concat();
+    buffer.extend_from_slice(&[END_TAG, 0]);
+
+    let mut contents = integer(buffer.len() as u32);
+    contents.extend(buffer);
+    let mut bytes = vec![BUFFER_OP];
+    bytes.extend(pkg_length(contents.len()));
+    bytes.extend(contents);
+    bytes
+}
+
+// Returns the I/O port descriptor of the `len` ports starting at `base`.
+fn io_port(base: u16, len: u8) -> Vec<u8> {
+    let mut bytes = vec![IO_PORT_TAG, IO_DECODE_16];
+    bytes.extend_from_slice(&base.to_le_bytes());
+    bytes.extend_from_slice(&base.to_le_bytes());
+    // Alignment, and number of ports.
+    bytes.extend_from_slice(&[1, len]);
+    bytes
+}
+
+// Returns the IRQ descriptor of `irq`.
+fn irq(irq: u8) -> Vec<u8> {
+    let mask = 1u16.checked_shl(u32::from(irq)).unwrap_or(0);
+    let mut bytes = vec![IRQ_NO_FLAGS_TAG];
+    bytes.extend_from_slice(&mask.to_le_bytes());
+    bytes
+}
+
+// Returns the AML encoding of a `Device` object called `device_name`, with
+// the `hid` hardware ID, the `uid` unique ID and the `resources`.
+fn device(device_name: &str, hid: &str, uid: u32, resources: &[Vec<u8>]) -> Vec<u8> {
+    let mut contents = device_name.as_bytes().to_vec();
+    contents.extend(name("_HID", &integer(eisa_id(hid))));
+    contents.extend(name("_UID", &integer(uid)));
+    contents.extend(name("_CRS", &resource_template(resources)));
+
+    let mut bytes = vec![EXT_OP_PREFIX, DEVICE_OP];
+    bytes.extend(pkg_length(contents.len()));
+    bytes.extend(contents);
+    bytes
+}
+
+/// Returns the AML encoding of the `COMn` device entry of a
+/// [`Serial`](../serial/struct.Serial.html) port, with the `PNP0501`
+/// hardware ID.
+///
+/// # Arguments
+/// * `index` - The number of the port, between 1 and 9, used in the name and
+///   as the unique ID of the device.
+/// * `base` - The base I/O address of the port, e.g. 0x3F8 for COM1.
+/// * `irq_num` - The interrupt of the port, e.g. 4 for COM1.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::acpi::serial_aml;
+/// let com1 = serial_aml(1, 0x3F8, 4);
+/// // A `Device` object called "COM1".
+/// assert_eq!(&com1[..2], &[0x5B, 0x82]);
+/// assert_eq!(&com1[3..7], b"COM1");
+/// ```
+pub fn serial_aml(index: u8, base: u16, irq_num: u8) -> Vec<u8> {
+    device(
+        &format!("COM{}", index % 10),
+        "PNP0501",
+        u32::from(index),
+        &[io_port(base, SERIAL_NUM_REGS), irq(irq_num)],
+    )
+}
+
+/// Returns the AML encoding of the keyboard (`KBD_`, with the `PNP0303`
+/// hardware ID) and mouse (`MOU_`, with the `PNP0F13` hardware ID) device
+/// entries of an [`I8042Device`](../i8042/struct.I8042Device.html).
+///
+/// The I/O ports, i.e. the data and the command ports, are assigned to the
+/// keyboard device, while the mouse device only has the auxiliary interrupt.
+///
+/// # Arguments
+/// * `base` - The base I/O address of the controller, usually 0x60.
+/// * `kbd_irq` - The keyboard interrupt, usually 1.
+/// * `aux_irq` - The auxiliary (mouse) interrupt, usually 12.
+pub fn i8042_aml(base: u16, kbd_irq: u8, aux_irq: u8) -> Vec<u8> {
+    let mut bytes = device(
+        "KBD_",
+        "PNP0303",
+        0,
+        &[
+            io_port(base + u16::from(DATA_OFFSET), 1),
+            io_port(base + u16::from(COMMAND_OFFSET), 1),
+            irq(kbd_irq),
+        ],
+    );
+    bytes.extend(device("MOU_", "PNP0F13", 0, &[irq(aux_irq)]));
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acpi_pkg_length() {
+        assert_eq!(pkg_length(0), vec![1]);
+        assert_eq!(pkg_length(62), vec![63]);
+        // 63 bytes, with 2 bytes of package length.
+        assert_eq!(pkg_length(63), vec![0x41, 0x04]);
+        assert_eq!(pkg_length(0x0FFD), vec![0x4F, 0xFF]);
+        assert_eq!(pkg_length(0x0FFE), vec![0x81, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn test_acpi_integer() {
+        assert_eq!(integer(0), vec![ZERO_OP]);
+        assert_eq!(integer(1), vec![ONE_OP]);
+        assert_eq!(integer(0x42), vec![BYTE_PREFIX, 0x42]);
+        assert_eq!(integer(0x1234), vec![DWORD_PREFIX, 0x34, 0x12, 0, 0]);
+    }
+
+    #[test]
+    fn test_acpi_eisa_id() {
+        // The values from the ACPI tables of QEMU.
+        assert_eq!(eisa_id("PNP0501"), 0x0105_D041);
+        assert_eq!(eisa_id("PNP0303"), 0x0303_D041);
+        assert_eq!(eisa_id("PNP0F13"), 0x130F_D041);
+    }
+
+    #[test]
+    fn test_acpi_serial_aml() {
+        // Device (COM1)
+        // {
+        //     Name (_HID, EisaId ("PNP0501"))
+        //     Name (_UID, One)
+        //     Name (_CRS, ResourceTemplate ()
+        //     {
+        //         IO (Decode16, 0x03F8, 0x03F8, 0x01, 0x08)
+        //         IRQNoFlags () {4}
+        //     })
+        // }
+        let expected = [
+            0x5B, 0x82, 0x2B, b'C', b'O', b'M', b'1', // Device (COM1)
+            0x08, b'_', b'H', b'I', b'D', 0x0C, 0x41, 0xD0, 0x05, 0x01, // _HID
+            0x08, b'_', b'U', b'I', b'D', 0x01, // _UID
+            0x08, b'_', b'C', b'R', b'S', 0x11, 0x10, 0x0A, 0x0D, // _CRS
+            0x47, 0x01, 0xF8, 0x03, 0xF8, 0x03, 0x01, 0x08, // IO
+            0x22, 0x10, 0x00, // IRQNoFlags
+            0x79, 0x00, // End
+        ];
+        assert_eq!(serial_aml(1, 0x3F8, 4), expected.to_vec());
+    }
+
+    #[test]
+    fn test_acpi_i8042_aml() {
+        let aml = i8042_aml(0x60, 1, 12);
+        let kbd_len = usize::from(aml[2]) + 2;
+        let (kbd, mouse) = aml.split_at(kbd_len);
+
+        assert_eq!(&kbd[3..7], b"KBD_");
+        let ports = [
+            0x47, 0x01, 0x60, 0x00, 0x60, 0x00, 0x01, 0x01, // IO 0x60
+            0x47, 0x01, 0x64, 0x00, 0x64, 0x00, 0x01, 0x01, // IO 0x64
+            0x22, 0x02, 0x00, // IRQNoFlags () {1}
+        ];
+        assert!(kbd.windows(ports.len()).any(|window| window == ports));
+
+        assert_eq!(&mouse[..2], &[EXT_OP_PREFIX, DEVICE_OP]);
+        assert_eq!(usize::from(mouse[2]) + 2, mouse.len());
+        assert_eq!(&mouse[3..7], b"MOU_");
+        assert_eq!(&mouse[mouse.len() - 5..], &[0x22, 0x00, 0x10, 0x79, 0x00]);
+    }
+}
@@ -0,0 +1,305 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the APM control and status ports (ports 0xB2 and
+//! 0xB3).
+//!
+//! The control port is the SMI command port described by the `SMI_CMD` field
+//! of the FADT. Writing the `ACPI_ENABLE` value to it asks the firmware to
+//! hand the power management over to the OS, which then waits for SCI_EN to
+//! be set in the PM1 control register. Without an SMM firmware, the VMM
+//! completes the handoff itself.
+
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+#[cfg(feature = "std")]
+use crate::pm1::{Pm1, Pm1Events};
+#[cfg(feature = "std")]
+use crate::Trigger;
+use crate::{byte_offset, LegacyDevice};
+
+// Offsets of the control (0xB2) and status (0xB3) registers.
+const CONTROL_OFFSET: u8 = 0;
+const STATUS_OFFSET: u8 = 1;
+
+/// The value written to the control port for switching to the ACPI mode,
+/// which should be given to the guest by the `ACPI_ENABLE` field of the FADT.
+pub const ACPI_ENABLE: u8 = 0xF1;
+/// The value written to the control port for switching back to the legacy
+/// mode, which should be given to the guest by the `ACPI_DISABLE` field of
+/// the FADT.
+pub const ACPI_DISABLE: u8 = 0xF0;
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the APM control port emulation logic. The methods below can be
+/// implemented by a backend that keeps track of such events by switching the power
+/// management mode, logging messages, or any other action.
+///
+/// It is implemented for a `Mutex` wrapping a [`Pm1`](../pm1/struct.Pm1.html),
+/// which then sets or clears SCI_EN as requested by the guest.
+pub trait ApmEvents {
+    /// The guest wrote `ACPI_ENABLE` (`enabled` is `true`) or `ACPI_DISABLE`
+    /// (`enabled` is `false`) to the control port.
+    fn acpi_mode_requested(&self, enabled: bool);
+
+    /// The guest wrote another command to the control port.
+    fn command(&self, command: u8);
+}
+
+/// Provides a no-op implementation of `ApmEvents` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `ApmEvents`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl ApmEvents for NoEvents {
+    fn acpi_mode_requested(&self, _enabled: bool) {}
+    fn command(&self, _command: u8) {}
+}
+
+impl<EV: ApmEvents> ApmEvents for Arc<EV> {
+    fn acpi_mode_requested(&self, enabled: bool) {
+        self.as_ref().acpi_mode_requested(enabled);
+    }
+
+    fn command(&self, command: u8) {
+        self.as_ref().command(command);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Trigger, EV: Pm1Events> ApmEvents for Mutex<Pm1<T, EV>> {
+    fn acpi_mode_requested(&self, enabled: bool) {
+        // A poisoned lock still holds a consistent PM1 block, since its
+        // methods don't panic halfway through an update.
+        let mut pm1 = self.lock().unwrap_or_else(|e| e.into_inner());
+        // SCI_EN is updated even if the SCI can't be raised, and the guest
+        // then finds the pending events in the status register.
+        let _ = pm1.set_sci_enabled(enabled);
+    }
+
+    fn command(&self, _command: u8) {}
+}
+
+/// The APM control and status ports, usually found at ports 0xB2 and 0xB3.
+///
+/// The `ACPI_ENABLE` and `ACPI_DISABLE` commands written to the control port
+/// are reported through [`ApmEvents`](trait.ApmEvents.html), and so are the
+/// other commands, which are otherwise ignored. The status port is a scratch
+/// register, which the firmware uses for passing data to the SMI handler.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::{Arc, Mutex};
+/// # use vm_superio::apm::{Apm, ACPI_ENABLE};
+/// # use vm_superio::pm1::Pm1;
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// // The PM1 block starts in legacy mode, as the FADT describes the SMI
+/// // command port.
+/// let pm1 = Arc::new(Mutex::new(Pm1::new(DummyTrigger, DummyTrigger)));
+/// pm1.lock().unwrap().set_sci_enabled(false).unwrap();
+/// let mut apm = Apm::with_events(pm1.clone());
+///
+/// // The guest requests the ACPI mode.
+/// apm.write(0, ACPI_ENABLE);
+/// assert!(pm1.lock().unwrap().sci_enabled());
+/// ```
+#[derive(Debug)]
+pub struct Apm<EV: ApmEvents> {
+    events: EV,
+    control: u8,
+    status: u8,
+}
+
+impl Apm<NoEvents> {
+    /// Creates a new `Apm` instance.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Apm`](struct.Apm.html#example).
+    pub const fn new() -> Self {
+        Self::with_events(NoEvents)
+    }
+}
+
+impl Default for Apm<NoEvents> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<EV: ApmEvents> Apm<EV> {
+    /// Creates a new `Apm` instance, which invokes the `apm_evts`
+    /// implementation of `ApmEvents` during operation.
+    ///
+    /// # Arguments
+    /// * `apm_evts` - The `ApmEvents` implementation used to handle the
+    ///   commands written by the guest.
+    pub const fn with_events(apm_evts: EV) -> Self {
+        Apm {
+            events: apm_evts,
+            control: 0,
+            status: 0,
+        }
+    }
+
+    /// Provides a reference to the APM events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Returns the last value written to the register, or 0xFF for invalid
+    /// offsets.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&self, offset: u8) -> u8 {
+        match offset {
+            CONTROL_OFFSET => self.control,
+            STATUS_OFFSET => self.status,
+            _ => {
+                guest_warn!("Invalid APM read at offset {:#x}", offset);
+                0xFF
+            }
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) {
+        match offset {
+            CONTROL_OFFSET => {
+                self.control = value;
+                match value {
+                    ACPI_ENABLE => self.events.acpi_mode_requested(true),
+                    ACPI_DISABLE => self.events.acpi_mode_requested(false),
+                    command => self.events.command(command),
+                }
+            }
+            STATUS_OFFSET => self.status = value,
+            _ => guest_warn!("Invalid APM write at offset {:#x}", offset),
+        }
+    }
+}
+
+impl<EV: ApmEvents> LegacyDevice for Apm<EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = Apm::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            Apm::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.control = 0;
+        self.status = 0;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vmm_sys_util::eventfd::EventFd;
+
+    #[derive(Default)]
+    struct ExampleApmEvents {
+        commands: Mutex<Vec<u8>>,
+        acpi_mode: Mutex<Option<bool>>,
+    }
+
+    impl ApmEvents for ExampleApmEvents {
+        fn acpi_mode_requested(&self, enabled: bool) {
+            *self.acpi_mode.lock().unwrap() = Some(enabled);
+        }
+
+        fn command(&self, command: u8) {
+            self.commands.lock().unwrap().push(command);
+        }
+    }
+
+    #[test]
+    fn test_apm_commands() {
+        let events = Arc::new(ExampleApmEvents::default());
+        let mut apm = Apm::with_events(events.clone());
+
+        apm.write(CONTROL_OFFSET, ACPI_ENABLE);
+        assert_eq!(*events.acpi_mode.lock().unwrap(), Some(true));
+        assert_eq!(apm.read(CONTROL_OFFSET), ACPI_ENABLE);
+        apm.write(CONTROL_OFFSET, ACPI_DISABLE);
+        assert_eq!(*events.acpi_mode.lock().unwrap(), Some(false));
+        assert!(events.commands.lock().unwrap().is_empty());
+
+        apm.write(CONTROL_OFFSET, 0x01);
+        assert_eq!(*apm.events().commands.lock().unwrap(), vec![0x01]);
+
+        // The status register is a scratch register.
+        apm.write(STATUS_OFFSET, 0x5A);
+        assert_eq!(apm.read(STATUS_OFFSET), 0x5A);
+        assert_eq!(apm.read(CONTROL_OFFSET), 0x01);
+        assert_eq!(*apm.events().commands.lock().unwrap(), vec![0x01]);
+
+        // Invalid offsets are ignored.
+        apm.write(STATUS_OFFSET + 1, ACPI_ENABLE);
+        assert_eq!(apm.read(STATUS_OFFSET + 1), 0xFF);
+        assert_eq!(*events.acpi_mode.lock().unwrap(), Some(false));
+    }
+
+    #[test]
+    fn test_apm_pm1() {
+        let pm1 = Arc::new(Mutex::new(Pm1::new(
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+        )));
+        pm1.lock().unwrap().set_sci_enabled(false).unwrap();
+        let mut apm = Apm::with_events(pm1.clone());
+
+        apm.write(CONTROL_OFFSET, 0x01);
+        assert!(!pm1.lock().unwrap().sci_enabled());
+        apm.write(CONTROL_OFFSET, ACPI_ENABLE);
+        assert!(pm1.lock().unwrap().sci_enabled());
+        apm.write(CONTROL_OFFSET, ACPI_DISABLE);
+        assert!(!pm1.lock().unwrap().sci_enabled());
+    }
+
+    #[test]
+    fn test_apm_legacy_device() {
+        let mut apm = Apm::new();
+
+        LegacyDevice::write(&mut apm, u16::from(STATUS_OFFSET), &[0x5A]);
+        let mut data = [0; 1];
+        LegacyDevice::read(&mut apm, u16::from(STATUS_OFFSET), &mut data);
+        assert_eq!(data, [0x5A]);
+
+        apm.reset();
+        assert_eq!(apm.read(STATUS_OFFSET), 0);
+    }
+}
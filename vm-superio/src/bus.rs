@@ -0,0 +1,229 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the implementations of the bus traits of `vm-device`.
+//!
+//! The port I/O devices implement `MutDevicePio`, and the MMIO ones
+//! `MutDeviceMmio`, so a `Mutex` wrapping them is registered directly on the
+//! `IoManager` of `vm-device`. The errors of the `Trigger` objects can't be
+//! returned through these traits, and are dropped.
+//!
+//! The port I/O devices have byte wide registers, so the wider accesses read
+//! as 0, and are otherwise ignored. The offsets past the register window of
+//! the MMIO devices are handled like their invalid offsets.
+
+use std::convert::TryFrom;
+
+use vm_device::bus::{MmioAddress, MmioAddressOffset, PioAddress, PioAddressOffset};
+use vm_device::{MutDeviceMmio, MutDevicePio};
+
+use crate::ged::Ged;
+use crate::i8042::{I8042Device, I8042Events};
+use crate::mmio::MmioEvents;
+use crate::pit::{ClockSource, Pit};
+use crate::rtc_pl031::{Rtc, RtcEvents, WallClock};
+use crate::serial::{Serial, SerialEvents, SerialMmio};
+use crate::{byte_offset, ByteSink, Trigger};
+
+// Returns the offset of an MMIO access, saturated to an invalid offset of the
+// devices when it doesn't fit.
+fn mmio_offset(offset: MmioAddressOffset) -> u16 {
+    u16::try_from(offset).unwrap_or(u16::MAX)
+}
+
+impl<T: Trigger, EV: SerialEvents, W: ByteSink> MutDevicePio for Serial<T, EV, W> {
+    fn pio_read(&mut self, _base: PioAddress, offset: PioAddressOffset, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = self.read(offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn pio_write(&mut self, _base: PioAddress, offset: PioAddressOffset, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            let _ = self.write(offset, data[0]);
+        }
+    }
+}
+
+impl<T: Trigger, EV: I8042Events> MutDevicePio for I8042Device<T, EV> {
+    fn pio_read(&mut self, _base: PioAddress, offset: PioAddressOffset, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = self.read(offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn pio_write(&mut self, _base: PioAddress, offset: PioAddressOffset, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            let _ = self.write(offset, data[0]);
+        }
+    }
+}
+
+impl<T: Trigger, C: ClockSource> MutDevicePio for Pit<T, C> {
+    fn pio_read(&mut self, _base: PioAddress, offset: PioAddressOffset, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = self.read(offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn pio_write(&mut self, _base: PioAddress, offset: PioAddressOffset, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            self.write(offset, data[0]);
+        }
+    }
+}
+
+impl<EV: RtcEvents, C: WallClock> MutDeviceMmio for Rtc<EV, C> {
+    fn mmio_read(&mut self, _base: MmioAddress, offset: MmioAddressOffset, data: &mut [u8]) {
+        match <&mut [u8; 4]>::try_from(data) {
+            Ok(data) => self.read(mmio_offset(offset), data),
+            Err(_) => self.bad_read(&mut [0; 4]),
+        }
+    }
+
+    fn mmio_write(&mut self, _base: MmioAddress, offset: MmioAddressOffset, data: &[u8]) {
+        match <&[u8; 4]>::try_from(data) {
+            Ok(data) => self.write(mmio_offset(offset), data),
+            Err(_) => self.bad_write(),
+        }
+    }
+}
+
+impl<T: Trigger, EV: MmioEvents> MutDeviceMmio for Ged<T, EV> {
+    fn mmio_read(&mut self, _base: MmioAddress, offset: MmioAddressOffset, data: &mut [u8]) {
+        self.read(mmio_offset(offset), data);
+    }
+
+    fn mmio_write(&mut self, _base: MmioAddress, offset: MmioAddressOffset, data: &[u8]) {
+        self.write(mmio_offset(offset), data);
+    }
+}
+
+impl<T: Trigger, EV: SerialEvents, W: ByteSink> MutDeviceMmio for SerialMmio<T, EV, W> {
+    fn mmio_read(&mut self, _base: MmioAddress, offset: MmioAddressOffset, data: &mut [u8]) {
+        self.read(mmio_offset(offset), data);
+    }
+
+    fn mmio_write(&mut self, _base: MmioAddress, offset: MmioAddressOffset, data: &[u8]) {
+        let _ = self.write(mmio_offset(offset), data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::{Arc, Mutex};
+    use vm_device::bus::{MmioRange, PioRange};
+    use vm_device::device_manager::{IoManager, MmioManager, PioManager};
+    use vmm_sys_util::eventfd::EventFd;
+    use vmm_sys_util::metric::Metric;
+
+    use crate::ged::GedEvent;
+    use crate::pit::MonotonicClock;
+
+    const COM1: u16 = 0x3F8;
+    const RTC_BASE: u64 = 0x1000;
+    const GED_BASE: u64 = 0x2000;
+
+    #[derive(Default)]
+    struct ExampleRtcMetrics {
+        invalid_reads: AtomicU64,
+    }
+
+    impl RtcEvents for ExampleRtcMetrics {
+        fn invalid_read(&self) {
+            self.invalid_reads.inc();
+        }
+
+        fn invalid_write(&self) {}
+    }
+
+    fn new_evt() -> EventFd {
+        EventFd::new(libc::EFD_NONBLOCK).unwrap()
+    }
+
+    #[test]
+    fn test_pio_devices() {
+        let serial = Arc::new(Mutex::new(Serial::new(new_evt(), Vec::new())));
+        let pit = Arc::new(Mutex::new(Pit::new(new_evt(), MonotonicClock::new())));
+        let mut io_manager = IoManager::new();
+        io_manager
+            .register_pio(PioRange::new(PioAddress(COM1), 8).unwrap(), serial.clone())
+            .unwrap();
+        io_manager
+            .register_pio(PioRange::new(PioAddress(0x40), 4).unwrap(), pit.clone())
+            .unwrap();
+
+        io_manager.pio_write(PioAddress(COM1), b"a").unwrap();
+        assert_eq!(serial.lock().unwrap().writer(), b"a");
+
+        // The wider accesses are ignored.
+        io_manager.pio_write(PioAddress(COM1), b"bc").unwrap();
+        assert_eq!(serial.lock().unwrap().writer(), b"a");
+        let mut data = [0xFF; 2];
+        io_manager
+            .pio_read(PioAddress(COM1 + 5), &mut data)
+            .unwrap();
+        assert_eq!(data, [0; 2]);
+
+        // The line status register reports the empty transmitter.
+        let mut data = [0; 1];
+        io_manager
+            .pio_read(PioAddress(COM1 + 5), &mut data)
+            .unwrap();
+        assert_eq!(data[0] & 0x60, 0x60);
+
+        // The PIT channel 0 is programmed one byte at a time.
+        io_manager.pio_write(PioAddress(0x43), &[0x34]).unwrap();
+        io_manager.pio_write(PioAddress(0x40), &[0xFF]).unwrap();
+        io_manager.pio_write(PioAddress(0x40), &[0xFF]).unwrap();
+        assert!(pit.lock().unwrap().next_deadline().is_some());
+    }
+
+    #[test]
+    fn test_mmio_devices() {
+        let metrics = Arc::new(ExampleRtcMetrics::default());
+        let ged = Arc::new(Mutex::new(Ged::new(new_evt())));
+        let mut io_manager = IoManager::new();
+        io_manager
+            .register_mmio(
+                MmioRange::new(MmioAddress(RTC_BASE), 0x1000).unwrap(),
+                Arc::new(Mutex::new(Rtc::with_events(metrics.clone()))),
+            )
+            .unwrap();
+        io_manager
+            .register_mmio(
+                MmioRange::new(MmioAddress(GED_BASE), 4).unwrap(),
+                ged.clone(),
+            )
+            .unwrap();
+
+        // The RTC control register reads as 1.
+        let mut data = [0; 4];
+        io_manager
+            .mmio_read(MmioAddress(RTC_BASE + 0xC), &mut data)
+            .unwrap();
+        assert_eq!(u32::from_le_bytes(data), 1);
+
+        // The accesses which aren't 4 bytes wide are invalid.
+        let mut data = [0xFF; 2];
+        io_manager
+            .mmio_read(MmioAddress(RTC_BASE + 0xC), &mut data)
+            .unwrap();
+        assert_eq!(data, [0xFF; 2]);
+        assert_eq!(metrics.invalid_reads.count(), 1);
+
+        ged.lock().unwrap().notify(GedEvent::PowerButton).unwrap();
+        let mut data = [0; 4];
+        io_manager
+            .mmio_read(MmioAddress(GED_BASE), &mut data)
+            .unwrap();
+        assert_eq!(u32::from_le_bytes(data), GedEvent::PowerButton.bit());
+        assert_eq!(ged.lock().unwrap().pending_events(), 0);
+    }
+}
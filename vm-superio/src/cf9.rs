@@ -0,0 +1,295 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the Reset Control Register (port 0xCF9).
+//!
+//! Many guests, including Linux, try resetting the system through this port
+//! before, or instead of, the i8042 controller.
+
+use alloc::sync::Arc;
+use core::result::Result;
+
+use crate::{byte_offset, LegacyDevice, Trigger};
+
+// Offset of the reset control register, which is the only register of the
+// device.
+const CONTROL_OFFSET: u8 = 0;
+
+// Reset control register bits.
+// Selects a full reset, instead of a CPU only one, when RST_CPU is set.
+const SYS_RST_BIT: u8 = 0b0000_0010;
+// Resets the CPU, or the system, when set. Always reads as 0.
+const RST_CPU_BIT: u8 = 0b0000_0100;
+// Selects a full reset, with the power cycled.
+const FULL_RST_BIT: u8 = 0b0000_1000;
+// The bits that read back as written.
+const WRITABLE_MASK: u8 = SYS_RST_BIT | FULL_RST_BIT;
+
+/// The type of the reset requested by the guest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResetType {
+    /// Only the CPU is reset (soft reset).
+    Cpu,
+    /// The whole system is reset (hard reset).
+    Full,
+}
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the port 0xCF9 emulation logic. The methods below can be implemented by a
+/// backend that keeps track of such events by incrementing metrics, logging messages, or any
+/// other action.
+pub trait Cf9Events {
+    /// The guest requested a reset of the given type. The reset event object
+    /// is signaled right after.
+    fn reset_requested(&self, reset_type: ResetType);
+}
+
+/// Provides a no-op implementation of `Cf9Events` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `Cf9Events`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl Cf9Events for NoEvents {
+    fn reset_requested(&self, _reset_type: ResetType) {}
+}
+
+impl<EV: Cf9Events> Cf9Events for Arc<EV> {
+    fn reset_requested(&self, reset_type: ResetType) {
+        self.as_ref().reset_requested(reset_type);
+    }
+}
+
+/// The Reset Control Register, usually found at port 0xCF9.
+///
+/// A [`Trigger`](../trait.Trigger.html) object is used for notifying the VMM
+/// about the reset requests. The type of the last requested reset is
+/// available through [`last_reset_type`](#method.last_reset_type), and is
+/// reported through [`Cf9Events`](trait.Cf9Events.html).
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::cf9::{Cf9, ResetType};
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut cf9 = Cf9::new(DummyTrigger);
+///
+/// // Linux selects a full reset, and then requests it.
+/// cf9.write(0, 0x02).unwrap();
+/// cf9.write(0, 0x06).unwrap();
+/// assert_eq!(cf9.last_reset_type(), Some(ResetType::Full));
+/// ```
+#[derive(Debug)]
+pub struct Cf9<T: Trigger, EV: Cf9Events> {
+    // Reset event object.
+    reset_evt: T,
+    events: EV,
+    // The value of the control register, without RST_CPU.
+    control: u8,
+    last_reset_type: Option<ResetType>,
+}
+
+impl<T: Trigger> Cf9<T, NoEvents> {
+    /// Creates a new `Cf9` instance, which will signal `reset_evt` when the
+    /// guest requests a reset.
+    ///
+    /// # Arguments
+    /// * `reset_evt` - A Trigger object that will be used to notify the driver
+    ///   about the reset event.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Cf9`](struct.Cf9.html#example).
+    pub const fn new(reset_evt: T) -> Self {
+        Self::with_events(reset_evt, NoEvents)
+    }
+}
+
+impl<T: Trigger, EV: Cf9Events> Cf9<T, EV> {
+    /// Creates a new `Cf9` instance, which will signal `reset_evt` when the
+    /// guest requests a reset, and invokes the `cf9_evts` implementation of
+    /// `Cf9Events` during operation.
+    ///
+    /// # Arguments
+    /// * `reset_evt` - A Trigger object that will be used to notify the driver
+    ///   about the reset event.
+    /// * `cf9_evts` - The `Cf9Events` implementation used to track the
+    ///   reset requests of the guest.
+    pub const fn with_events(reset_evt: T, cf9_evts: EV) -> Self {
+        Cf9 {
+            reset_evt,
+            events: cf9_evts,
+            control: 0,
+            last_reset_type: None,
+        }
+    }
+
+    /// Provides a reference to the reset event object.
+    pub fn reset_evt(&self) -> &T {
+        &self.reset_evt
+    }
+
+    /// Provides a reference to the port 0xCF9 events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Returns the type of the last reset requested by the guest, if any.
+    pub fn last_reset_type(&self) -> Option<ResetType> {
+        self.last_reset_type
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Returns the value of the control register, or 0 for invalid offsets.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&self, offset: u8) -> u8 {
+        match offset {
+            CONTROL_OFFSET => self.control,
+            _ => {
+                guest_warn!("Invalid CF9 read at offset {:#x}", offset);
+                0x00
+            }
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Setting RST_CPU requests a reset, which is a full one if either
+    /// SYS_RST or FULL_RST is set, and a CPU only one otherwise.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) -> Result<(), T::E> {
+        if offset != CONTROL_OFFSET {
+            guest_warn!("Invalid CF9 write at offset {:#x}", offset);
+            return Ok(());
+        }
+
+        self.control = value & WRITABLE_MASK;
+        if (value & RST_CPU_BIT) == 0 {
+            return Ok(());
+        }
+        let reset_type = if (value & (SYS_RST_BIT | FULL_RST_BIT)) != 0 {
+            ResetType::Full
+        } else {
+            ResetType::Cpu
+        };
+        self.last_reset_type = Some(reset_type);
+        self.events.reset_requested(reset_type);
+        self.reset_evt.trigger()
+    }
+}
+
+impl<T: Trigger, EV: Cf9Events> LegacyDevice for Cf9<T, EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = Cf9::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            let _ = Cf9::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.control = 0;
+        self.last_reset_type = None;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::Mutex;
+    use vmm_sys_util::eventfd::EventFd;
+
+    #[derive(Default)]
+    struct ExampleCf9Events {
+        resets: Mutex<Vec<ResetType>>,
+    }
+
+    impl Cf9Events for ExampleCf9Events {
+        fn reset_requested(&self, reset_type: ResetType) {
+            self.resets.lock().unwrap().push(reset_type);
+        }
+    }
+
+    #[test]
+    fn test_cf9_reset() {
+        let events = Arc::new(ExampleCf9Events::default());
+        let mut cf9 = Cf9::with_events(EventFd::new(libc::EFD_NONBLOCK).unwrap(), events.clone());
+        assert_eq!(cf9.last_reset_type(), None);
+
+        // Selecting the reset type doesn't reset.
+        cf9.write(CONTROL_OFFSET, SYS_RST_BIT).unwrap();
+        assert_eq!(cf9.read(CONTROL_OFFSET), SYS_RST_BIT);
+        assert_eq!(
+            cf9.reset_evt().read().unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+
+        // RST_CPU doesn't stick.
+        cf9.write(CONTROL_OFFSET, SYS_RST_BIT | RST_CPU_BIT)
+            .unwrap();
+        assert_eq!(cf9.read(CONTROL_OFFSET), SYS_RST_BIT);
+        assert_eq!(cf9.reset_evt().read().unwrap(), 1);
+        assert_eq!(cf9.last_reset_type(), Some(ResetType::Full));
+
+        cf9.write(CONTROL_OFFSET, RST_CPU_BIT).unwrap();
+        assert_eq!(cf9.last_reset_type(), Some(ResetType::Cpu));
+        cf9.write(CONTROL_OFFSET, FULL_RST_BIT | RST_CPU_BIT)
+            .unwrap();
+        assert_eq!(cf9.read(CONTROL_OFFSET), FULL_RST_BIT);
+        assert_eq!(cf9.reset_evt().read().unwrap(), 2);
+        assert_eq!(
+            *events.resets.lock().unwrap(),
+            vec![ResetType::Full, ResetType::Cpu, ResetType::Full]
+        );
+
+        // Other offsets are ignored.
+        cf9.write(CONTROL_OFFSET + 1, RST_CPU_BIT).unwrap();
+        assert_eq!(cf9.read(CONTROL_OFFSET + 1), 0x00);
+        assert_eq!(events.resets.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_cf9_legacy_device() {
+        let mut cf9 = Cf9::new(EventFd::new(libc::EFD_NONBLOCK).unwrap());
+
+        LegacyDevice::write(&mut cf9, u16::from(CONTROL_OFFSET), &[RST_CPU_BIT]);
+        assert_eq!(cf9.reset_evt().read().unwrap(), 1);
+        assert_eq!(cf9.last_reset_type(), Some(ResetType::Cpu));
+
+        // The wider accesses are ignored.
+        LegacyDevice::write(&mut cf9, u16::from(CONTROL_OFFSET), &[FULL_RST_BIT, 0]);
+        assert_eq!(cf9.read(CONTROL_OFFSET), 0);
+
+        cf9.write(CONTROL_OFFSET, FULL_RST_BIT).unwrap();
+        cf9.reset();
+        assert_eq!(cf9.read(CONTROL_OFFSET), 0);
+        assert_eq!(cf9.last_reset_type(), None);
+    }
+}
@@ -0,0 +1,427 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides a chassis bundling the legacy devices with a save/restore state.
+//!
+//! The `SuperioChassis` owns the serial ports, the i8042 controller, the RTC
+//! and the PIT of the machine, so that the VMM saves and restores all of them
+//! with a single `state()` and `from_state()`, instead of handling each
+//! device and its `Trigger` objects separately. The crate has no CMOS RTC, so
+//! the RTC of the chassis is the PL031 `Rtc`.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::result::Result;
+
+use crate::i8042::{self, I8042Device, I8042State};
+use crate::pit::{ClockSource, Pit, PitState};
+use crate::rtc_pl031::{self, Rtc, RtcState, WallClock};
+use crate::serial::{self, Serial, SerialState};
+use crate::{ByteSink, LegacyDevice, Persist, Trigger};
+
+/// Errors encountered while restoring the state of the chassis.
+#[derive(Debug)]
+pub enum Error<E> {
+    /// The number of serial ports doesn't match their saved states.
+    SerialCount(usize, usize),
+    /// Failed to restore the serial port at the index.
+    Serial(usize, serial::Error<E>),
+    /// Failed to restore the i8042 controller.
+    I8042(i8042::Error<E>),
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::SerialCount(ports, states) => {
+                write!(f, "{} serial ports for {} saved states", ports, states)
+            }
+            Error::Serial(index, e) => write!(f, "Failed to restore serial port {}: {}", index, e),
+            Error::I8042(e) => write!(f, "Failed to restore the i8042 controller: {}", e),
+        }
+    }
+}
+
+impl<E: StdError> StdError for Error<E> {}
+
+/// The objects used by the devices of the chassis to notify the VMM.
+#[derive(Debug)]
+pub struct ChassisResources<T: Trigger, W: ByteSink, C: ClockSource, R: WallClock> {
+    /// The interrupt `Trigger` and the output of each serial port.
+    pub serials: Vec<(T, W)>,
+    /// The `Trigger` of the CPU reset requested through the i8042 controller.
+    pub i8042_reset_evt: T,
+    /// The `Trigger` of the keyboard interrupt (IRQ1).
+    pub i8042_kbd_evt: T,
+    /// The `Trigger` of the auxiliary device interrupt (IRQ12).
+    pub i8042_aux_evt: T,
+    /// The `Trigger` of the PIT interrupt (IRQ0).
+    pub pit_irq0_evt: T,
+    /// The clock source of the PIT.
+    pub clock: C,
+    /// The wall clock from which the RTC takes its time.
+    pub rtc_clock: R,
+}
+
+/// The state of the devices of the `SuperioChassis`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuperioChassisState {
+    /// The state of each serial port.
+    pub serials: Vec<SerialState>,
+    /// The state of the i8042 controller.
+    pub i8042: I8042State,
+    /// The state of the RTC.
+    pub rtc: RtcState,
+    /// The state of the PIT.
+    pub pit: PitState,
+}
+
+/// The canonical set of legacy devices of a machine, saved and restored as a
+/// whole.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::io::{sink, Sink};
+/// # use vm_superio::chassis::{ChassisResources, SuperioChassis};
+/// # use vm_superio::pit::ClockSource;
+/// # use vm_superio::rtc_pl031::WallClock;
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// # struct DummyClock;
+/// # impl ClockSource for DummyClock {
+/// #     fn now_ns(&self) -> u64 { 0 }
+/// # }
+/// # impl WallClock for DummyClock {
+/// #     fn now_secs(&self) -> u64 { 0 }
+/// # }
+/// fn resources() -> ChassisResources<DummyTrigger, Sink, DummyClock, DummyClock> {
+///     ChassisResources {
+///         serials: vec![(DummyTrigger, sink()), (DummyTrigger, sink())],
+///         i8042_reset_evt: DummyTrigger,
+///         i8042_kbd_evt: DummyTrigger,
+///         i8042_aux_evt: DummyTrigger,
+///         pit_irq0_evt: DummyTrigger,
+///         clock: DummyClock,
+///         rtc_clock: DummyClock,
+///     }
+/// }
+///
+/// let mut chassis = SuperioChassis::new(resources());
+/// chassis.serial_mut(1).unwrap().write(7, 0x42).unwrap();
+///
+/// // Save the whole platform, and restore it on the destination.
+/// let state = chassis.state();
+/// let mut restored = SuperioChassis::from_state(&state, resources()).unwrap();
+/// assert_eq!(restored.serial_mut(1).unwrap().read(7), 0x42);
+/// ```
+#[derive(Debug)]
+pub struct SuperioChassis<T: Trigger, W: ByteSink, C: ClockSource, R: WallClock> {
+    serials: Vec<Serial<T, serial::NoEvents, W>>,
+    i8042: I8042Device<T, i8042::NoEvents>,
+    rtc: Rtc<rtc_pl031::NoEvents, R>,
+    pit: Pit<T, C>,
+}
+
+impl<T: Trigger, W: ByteSink, C: ClockSource, R: WallClock> SuperioChassis<T, W, C, R> {
+    /// Creates a new `SuperioChassis` instance, with the devices in their
+    /// reset state.
+    ///
+    /// # Arguments
+    /// * `resources` - The objects used by the devices to notify the VMM,
+    ///   with one entry in `serials` per serial port.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `SuperioChassis`](struct.SuperioChassis.html#example).
+    pub fn new(resources: ChassisResources<T, W, C, R>) -> Self {
+        trace_device!("chassis", "create");
+        SuperioChassis {
+            serials: resources
+                .serials
+                .into_iter()
+                .map(|(trigger, out)| Serial::new(trigger, out))
+                .collect(),
+            i8042: I8042Device::with_irqs(
+                resources.i8042_reset_evt,
+                resources.i8042_kbd_evt,
+                resources.i8042_aux_evt,
+            ),
+            rtc: Rtc::from_state_with_clock(
+                &RtcState::default(),
+                rtc_pl031::NoEvents,
+                resources.rtc_clock,
+            ),
+            pit: Pit::new(resources.pit_irq0_evt, resources.clock),
+        }
+    }
+
+    /// Creates a new `SuperioChassis` instance from the saved `state`.
+    ///
+    /// # Arguments
+    /// * `state` - The state of the devices, as returned by
+    ///   [`state`](#method.state).
+    /// * `resources` - The objects used by the devices to notify the VMM,
+    ///   with one entry in `serials` per saved serial port.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `SuperioChassis`](struct.SuperioChassis.html#example).
+    pub fn from_state(
+        state: &SuperioChassisState,
+        resources: ChassisResources<T, W, C, R>,
+    ) -> Result<Self, Error<T::E>> {
+        trace_device!("chassis", "restore");
+        if resources.serials.len() != state.serials.len() {
+            return Err(Error::SerialCount(
+                resources.serials.len(),
+                state.serials.len(),
+            ));
+        }
+
+        let serials = resources
+            .serials
+            .into_iter()
+            .zip(state.serials.iter())
+            .enumerate()
+            .map(|(index, ((trigger, out), state))| {
+                Serial::from_state(state, trigger, serial::NoEvents, out)
+                    .map_err(|e| Error::Serial(index, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let i8042 = I8042Device::from_state(
+            &state.i8042,
+            resources.i8042_reset_evt,
+            resources.i8042_kbd_evt,
+            resources.i8042_aux_evt,
+            i8042::NoEvents,
+        )
+        .map_err(Error::I8042)?;
+
+        Ok(SuperioChassis {
+            serials,
+            i8042,
+            rtc: Rtc::from_state_with_clock(&state.rtc, rtc_pl031::NoEvents, resources.rtc_clock),
+            pit: Pit::from_state(&state.pit, resources.pit_irq0_evt, resources.clock),
+        })
+    }
+
+    /// Returns the state of all the devices.
+    pub fn state(&self) -> SuperioChassisState {
+        trace_device!("chassis", "save");
+        SuperioChassisState {
+            serials: self.serials.iter().map(Serial::state).collect(),
+            i8042: self.i8042.state(),
+            rtc: self.rtc.state(),
+            pit: self.pit.state(),
+        }
+    }
+
+    /// Resets all the devices to their power-on state, e.g. when the guest
+    /// reboots, keeping their `Trigger` objects and output sinks.
+    pub fn reset(&mut self) {
+        self.serials.iter_mut().for_each(LegacyDevice::reset);
+        self.i8042.reset();
+        self.rtc.reset();
+        self.pit.reset();
+    }
+
+    /// Returns the serial port at `index`, if any.
+    pub fn serial(&self, index: usize) -> Option<&Serial<T, serial::NoEvents, W>> {
+        self.serials.get(index)
+    }
+
+    /// Returns a mutable reference to the serial port at `index`, if any.
+    pub fn serial_mut(&mut self, index: usize) -> Option<&mut Serial<T, serial::NoEvents, W>> {
+        self.serials.get_mut(index)
+    }
+
+    /// Returns the i8042 controller.
+    pub fn i8042(&self) -> &I8042Device<T, i8042::NoEvents> {
+        &self.i8042
+    }
+
+    /// Returns a mutable reference to the i8042 controller.
+    pub fn i8042_mut(&mut self) -> &mut I8042Device<T, i8042::NoEvents> {
+        &mut self.i8042
+    }
+
+    /// Returns the RTC.
+    pub fn rtc(&self) -> &Rtc<rtc_pl031::NoEvents, R> {
+        &self.rtc
+    }
+
+    /// Returns a mutable reference to the RTC.
+    pub fn rtc_mut(&mut self) -> &mut Rtc<rtc_pl031::NoEvents, R> {
+        &mut self.rtc
+    }
+
+    /// Returns the PIT.
+    pub fn pit(&self) -> &Pit<T, C> {
+        &self.pit
+    }
+
+    /// Returns a mutable reference to the PIT.
+    pub fn pit_mut(&mut self) -> &mut Pit<T, C> {
+        &mut self.pit
+    }
+}
+
+// The chassis is restored with the objects of all its devices.
+impl<T: Trigger, W: ByteSink, C: ClockSource, R: WallClock> Persist for SuperioChassis<T, W, C, R> {
+    type State = SuperioChassisState;
+    type ConstructorArgs = ChassisResources<T, W, C, R>;
+    type Error = Error<T::E>;
+
+    fn save(&self) -> Self::State {
+        self.state()
+    }
+
+    fn restore(resources: Self::ConstructorArgs, state: &Self::State) -> Result<Self, Self::Error> {
+        Self::from_state(state, resources)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{MockClock, MockTrigger};
+    use std::io::{sink, Sink};
+
+    fn resources(
+        serials: usize,
+        clock: MockClock,
+    ) -> ChassisResources<MockTrigger, Sink, MockClock, MockClock> {
+        ChassisResources {
+            serials: (0..serials).map(|_| (MockTrigger::new(), sink())).collect(),
+            i8042_reset_evt: MockTrigger::new(),
+            i8042_kbd_evt: MockTrigger::new(),
+            i8042_aux_evt: MockTrigger::new(),
+            pit_irq0_evt: MockTrigger::new(),
+            clock: clock.clone(),
+            rtc_clock: clock,
+        }
+    }
+
+    #[test]
+    fn test_chassis_state() {
+        let clock = MockClock::new();
+        let mut chassis = SuperioChassis::new(resources(4, clock.clone()));
+        assert!(chassis.serial(3).is_some());
+        assert!(chassis.serial(4).is_none());
+
+        chassis.serial_mut(0).unwrap().write(7, 0x12).unwrap();
+        chassis
+            .serial_mut(3)
+            .unwrap()
+            .enqueue_raw_bytes(&[1, 2, 3])
+            .unwrap();
+        chassis.i8042_mut().write(4, 0xAA).unwrap();
+        let mut data = [0u8; 4];
+        chassis.rtc_mut().write(0x08, &0x1234u32.to_le_bytes());
+        // Program the counter 0 of the PIT.
+        chassis.pit_mut().write(3, 0x34);
+        chassis.pit_mut().write(0, 0x00);
+        chassis.pit_mut().write(0, 0x10);
+        clock.set_ns(1_000_000);
+
+        let state = chassis.state();
+        assert_eq!(state.serials.len(), 4);
+        assert_eq!(state.serials[3].in_buffer, vec![1, 2, 3]);
+
+        let mut restored = SuperioChassis::from_state(&state, resources(4, clock.clone())).unwrap();
+        assert_eq!(restored.state(), state);
+        assert_eq!(restored.serial_mut(0).unwrap().read(7), 0x12);
+        assert_eq!(restored.i8042_mut().read(0), 0x55);
+        restored.rtc_mut().read(0x08, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 0x1234);
+        // The RTC counts the time of the clock from the resources.
+        clock.set_secs(10);
+        restored.rtc_mut().read(0x00, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 0x1234 + 10);
+        assert_eq!(
+            restored.pit().next_deadline(),
+            chassis.pit().next_deadline()
+        );
+    }
+
+    #[test]
+    fn test_chassis_reset() {
+        let clock = MockClock::new();
+        let mut chassis = SuperioChassis::new(resources(2, clock.clone()));
+        let power_on = chassis.state();
+
+        chassis.serial_mut(1).unwrap().write(7, 0x12).unwrap();
+        chassis.i8042_mut().write(4, 0xAA).unwrap();
+        chassis.pit_mut().write(3, 0x34);
+        chassis.pit_mut().write(0, 0x00);
+        chassis.pit_mut().write(0, 0x10);
+        assert!(chassis.pit().next_deadline().is_some());
+
+        chassis.reset();
+        assert_eq!(chassis.serial_mut(1).unwrap().read(7), 0x00);
+        assert_eq!(chassis.pit().next_deadline(), None);
+        let state = chassis.state();
+        assert_eq!(state.serials, power_on.serials);
+        assert_eq!(state.i8042, power_on.i8042);
+        assert_eq!(state.pit, power_on.pit);
+    }
+
+    #[test]
+    fn test_chassis_invalid_state() {
+        let clock = MockClock::new();
+        let chassis = SuperioChassis::new(resources(2, clock.clone()));
+        let mut state = chassis.state();
+
+        match SuperioChassis::from_state(&state, resources(1, clock.clone())) {
+            Err(Error::SerialCount(1, 2)) => (),
+            _ => unreachable!(),
+        }
+
+        state.serials[1].in_buffer = vec![0; 65];
+        match SuperioChassis::from_state(&state, resources(2, clock)) {
+            Err(Error::Serial(1, serial::Error::FullFifo)) => (),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_chassis_persist() {
+        let clock = MockClock::new();
+        let mut chassis = SuperioChassis::new(resources(1, clock.clone()));
+        chassis.serials[0].write(7, 0x42).unwrap();
+
+        let state = chassis.save();
+        assert_eq!(state, chassis.state());
+        let restored = SuperioChassis::restore(resources(1, clock.clone()), &state).unwrap();
+        assert_eq!(restored.save(), state);
+        assert!(matches!(
+            SuperioChassis::restore(resources(2, clock), &state),
+            Err(Error::SerialCount(2, 1))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_chassis_serde() {
+        let clock = MockClock::new();
+        let mut chassis = SuperioChassis::new(resources(2, clock.clone()));
+        chassis.serials[1].enqueue_raw_bytes(b"abc").unwrap();
+        clock.set_ns(1_000);
+
+        let state = chassis.state();
+        let json = serde_json::to_string(&state).unwrap();
+        let restored: SuperioChassisState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, state);
+        assert!(SuperioChassis::from_state(&restored, resources(2, clock)).is_ok());
+    }
+}
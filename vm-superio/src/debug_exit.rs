@@ -0,0 +1,232 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for an MMIO debug exit device.
+//!
+//! Like the `isa-debug-exit` device of QEMU, it lets the guest terminate the
+//! VM with an exit code, by storing the code to its register. It is meant for
+//! the guest test harnesses of the machines without port I/O, such as the
+//! aarch64 and riscv64 ones.
+
+use alloc::sync::Arc;
+
+use crate::LegacyDevice;
+
+// Offset of the exit register, which is the only register of the device.
+const EXIT_OFFSET: u16 = 0;
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the debug exit emulation logic. The methods below can be implemented by a
+/// backend that keeps track of such events by stopping the VM, logging messages, or any other
+/// action.
+pub trait DebugExitEvents {
+    /// The guest requested the VM exit with `code`.
+    fn exit_requested(&self, code: u32);
+}
+
+/// Provides a no-op implementation of `DebugExitEvents` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `DebugExitEvents`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl DebugExitEvents for NoEvents {
+    fn exit_requested(&self, _code: u32) {}
+}
+
+impl<EV: DebugExitEvents> DebugExitEvents for Arc<EV> {
+    fn exit_requested(&self, code: u32) {
+        self.as_ref().exit_requested(code);
+    }
+}
+
+/// An MMIO debug exit device.
+///
+/// The code stored by the guest to the exit register is delivered through
+/// [`DebugExitEvents`](trait.DebugExitEvents.html), and is available
+/// through [`exit_code`](#method.exit_code). The code is written as is,
+/// unlike with `isa-debug-exit`, which shifts it.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::atomic::{AtomicU32, Ordering};
+/// # use std::sync::Arc;
+/// # use vm_superio::debug_exit::{DebugExit, DebugExitEvents};
+/// #[derive(Default)]
+/// struct ExitEvents {
+///     code: AtomicU32,
+/// }
+///
+/// impl DebugExitEvents for ExitEvents {
+///     fn exit_requested(&self, code: u32) {
+///         self.code.store(code, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let events = Arc::new(ExitEvents::default());
+/// let mut debug_exit = DebugExit::with_events(events.clone());
+///
+/// // The test harness reports a failure.
+/// debug_exit.write(0, &3u32.to_le_bytes());
+/// assert_eq!(events.code.load(Ordering::Relaxed), 3);
+/// assert_eq!(debug_exit.exit_code(), Some(3));
+/// ```
+#[derive(Debug)]
+pub struct DebugExit<EV: DebugExitEvents> {
+    events: EV,
+    exit_code: Option<u32>,
+}
+
+impl DebugExit<NoEvents> {
+    /// Creates a new `DebugExit` instance, which only records the exit code.
+    pub const fn new() -> Self {
+        Self::with_events(NoEvents)
+    }
+}
+
+impl Default for DebugExit<NoEvents> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<EV: DebugExitEvents> DebugExit<EV> {
+    /// Creates a new `DebugExit` instance, which delivers the exit requests
+    /// to the `debug_exit_evts` implementation of `DebugExitEvents`.
+    ///
+    /// # Arguments
+    /// * `debug_exit_evts` - The `DebugExitEvents` implementation notified
+    ///   about the exit requests of the guest.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `DebugExit`](struct.DebugExit.html#example).
+    pub const fn with_events(debug_exit_evts: EV) -> Self {
+        DebugExit {
+            events: debug_exit_evts,
+            exit_code: None,
+        }
+    }
+
+    /// Provides a reference to the debug exit events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Returns the last exit code written by the guest, if any.
+    pub fn exit_code(&self) -> Option<u32> {
+        self.exit_code
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// The exit register is write only, so all the offsets read as 0.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The little-endian byte array storing the read value.
+    pub fn read(&self, _offset: u16, data: &mut [u8]) {
+        data.fill(0);
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// Any store of 1 to 4 bytes to the exit register requests the exit, with
+    /// the stored value as the code. The other writes are ignored.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The little-endian byte array to write to the register.
+    pub fn write(&mut self, offset: u16, data: &[u8]) {
+        if offset != EXIT_OFFSET || data.is_empty() || data.len() > 4 {
+            guest_warn!(
+                "Invalid debug exit write of {} bytes at offset {:#x}",
+                data.len(),
+                offset
+            );
+            return;
+        }
+
+        let mut bytes = [0; 4];
+        bytes[..data.len()].copy_from_slice(data);
+        let code = u32::from_le_bytes(bytes);
+        self.exit_code = Some(code);
+        self.events.exit_requested(code);
+    }
+}
+
+impl<EV: DebugExitEvents> LegacyDevice for DebugExit<EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        DebugExit::read(self, offset, data);
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        DebugExit::write(self, offset, data);
+    }
+
+    fn reset(&mut self) {
+        self.exit_code = None;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct ExampleDebugExitEvents {
+        codes: Mutex<Vec<u32>>,
+    }
+
+    impl DebugExitEvents for ExampleDebugExitEvents {
+        fn exit_requested(&self, code: u32) {
+            self.codes.lock().unwrap().push(code);
+        }
+    }
+
+    #[test]
+    fn test_debug_exit() {
+        let events = Arc::new(ExampleDebugExitEvents::default());
+        let mut debug_exit = DebugExit::with_events(events.clone());
+        assert_eq!(debug_exit.exit_code(), None);
+
+        // The stores of any width up to 4 bytes request the exit.
+        debug_exit.write(EXIT_OFFSET, &[0x01]);
+        debug_exit.write(EXIT_OFFSET, &0x0203u16.to_le_bytes());
+        debug_exit.write(EXIT_OFFSET, &0xDEAD_BEEFu32.to_le_bytes());
+        assert_eq!(debug_exit.exit_code(), Some(0xDEAD_BEEF));
+
+        // The other writes are ignored.
+        debug_exit.write(EXIT_OFFSET + 4, &[0x01]);
+        debug_exit.write(EXIT_OFFSET, &[0x01; 8]);
+        debug_exit.write(EXIT_OFFSET, &[]);
+        assert_eq!(*events.codes.lock().unwrap(), vec![1, 0x0203, 0xDEAD_BEEF]);
+
+        let mut data = [0xFF; 4];
+        debug_exit.read(EXIT_OFFSET, &mut data);
+        assert_eq!(data, [0; 4]);
+    }
+
+    #[test]
+    fn test_debug_exit_legacy_device() {
+        let mut debug_exit = DebugExit::new();
+
+        LegacyDevice::write(&mut debug_exit, EXIT_OFFSET, &[0x01]);
+        assert_eq!(debug_exit.exit_code(), Some(1));
+
+        debug_exit.reset();
+        assert_eq!(debug_exit.exit_code(), None);
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the Bochs debug console (port 0xE9).
+//!
+//! SeaBIOS, OVMF and many unikernels use this port as a simple log channel:
+//! each byte written to it is a character of the log, and reading it returns
+//! 0xE9, so that the guest can probe for the device.
+
+use crate::{byte_offset, ByteSink, LegacyDevice, SinkError};
+
+// Offset of the data register, which is the only register of the device.
+const DATA_OFFSET: u8 = 0;
+
+// Value returned by the reads of the data register, i.e. the usual port of
+// the device.
+const DEBUGCON_SIGNATURE: u8 = 0xE9;
+
+/// The Bochs debug console, usually found at port 0xE9.
+///
+/// The bytes written by the guest go to the `W: ByteSink` object provided at
+/// creation, e.g. `std::io::Stdout` or a log file.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::debugcon::DebugCon;
+/// let mut debugcon = DebugCon::new(Vec::new());
+///
+/// // The guest probes for the device, and then logs a message.
+/// assert_eq!(debugcon.read(0), 0xE9);
+/// for &byte in b"Hi\n".iter() {
+///     debugcon.write(0, byte).unwrap();
+/// }
+/// assert_eq!(debugcon.writer(), b"Hi\n");
+/// ```
+#[derive(Debug)]
+pub struct DebugCon<W: ByteSink> {
+    out: W,
+}
+
+impl<W: ByteSink> DebugCon<W> {
+    /// Creates a new `DebugCon` instance, which writes the guest output to
+    /// `out`.
+    ///
+    /// # Arguments
+    /// * `out` - An object for writing the guest output to. The output is
+    ///   flushed after each byte.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `DebugCon`](struct.DebugCon.html#example).
+    pub const fn new(out: W) -> Self {
+        DebugCon { out }
+    }
+
+    /// Gets a reference to the output `ByteSink` object.
+    pub fn writer(&self) -> &W {
+        &self.out
+    }
+
+    /// Gets a mutable reference to the output `ByteSink` object.
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    /// Consumes the device and retrieves the inner writer.
+    pub fn into_writer(self) -> W {
+        self.out
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Returns 0xE9, which the guest uses for detecting the device, or 0 for
+    /// invalid offsets.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&self, offset: u8) -> u8 {
+        match offset {
+            DATA_OFFSET => DEBUGCON_SIGNATURE,
+            _ => {
+                guest_warn!("Invalid debug console read at offset {:#x}", offset);
+                0x00
+            }
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Writes the byte to the output object, and flushes it.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) -> Result<(), SinkError> {
+        if offset != DATA_OFFSET {
+            guest_warn!("Invalid debug console write at offset {:#x}", offset);
+            return Ok(());
+        }
+        self.out.write_all(&[value])?;
+        self.out.flush()
+    }
+}
+
+impl<W: ByteSink> LegacyDevice for DebugCon<W> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = DebugCon::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            let _ = DebugCon::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Write};
+
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("write failed"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_debugcon() {
+        let mut debugcon = DebugCon::new(Vec::new());
+        assert_eq!(debugcon.read(DATA_OFFSET), DEBUGCON_SIGNATURE);
+
+        debugcon.write(DATA_OFFSET, b'a').unwrap();
+        debugcon.write(DATA_OFFSET, b'b').unwrap();
+        assert_eq!(debugcon.writer(), b"ab");
+
+        // Other offsets are ignored.
+        debugcon.write(DATA_OFFSET + 1, b'c').unwrap();
+        assert_eq!(debugcon.read(DATA_OFFSET + 1), 0x00);
+        debugcon.writer_mut().clear();
+        assert!(debugcon.into_writer().is_empty());
+    }
+
+    #[test]
+    fn test_debugcon_write_error() {
+        let mut debugcon = DebugCon::new(FailingWriter);
+        assert_eq!(
+            debugcon.write(DATA_OFFSET, b'a').unwrap_err().kind(),
+            io::ErrorKind::Other
+        );
+    }
+
+    #[test]
+    fn test_debugcon_legacy_device() {
+        let mut debugcon = DebugCon::new(Vec::new());
+
+        LegacyDevice::write(&mut debugcon, u16::from(DATA_OFFSET), b"a");
+        LegacyDevice::write(&mut debugcon, u16::from(DATA_OFFSET), b"bc");
+        assert_eq!(debugcon.writer(), b"a");
+        let mut data = [0; 1];
+        LegacyDevice::read(&mut debugcon, u16::from(DATA_OFFSET), &mut data);
+        assert_eq!(data, [DEBUGCON_SIGNATURE]);
+    }
+}
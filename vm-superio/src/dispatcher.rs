@@ -0,0 +1,895 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the dispatchers routing the port I/O and MMIO accesses to the
+//! devices.
+//!
+//! The VMM registers each device with the range of addresses it decodes, and
+//! forwards the port I/O or MMIO exits of the vCPUs to the dispatcher, which
+//! finds the device behind the address and calls it with the offset of the
+//! address from the base of the range. The devices are shared with the VMM
+//! through an `Arc<Mutex<_>>`, so that it can still inject input or run their
+//! timers.
+//!
+//! The `insert_*` helpers of the `PortIoDispatcher` register the devices at
+//! the ports of the classic PC layout, while the MMIO devices are placed by
+//! the VMM, as described by the device tree or the ACPI tables.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error as StdError;
+use std::fmt;
+use std::ops::Bound;
+use std::result::Result;
+use std::sync::{Arc, Mutex};
+
+use crate::{DmaPageRegisters, GamePort, LegacyDevice, I8237};
+
+/// The base ports of the COM1 to COM4 serial ports.
+pub const COM_PORTS: [u16; 4] = [0x3F8, 0x2F8, 0x3E8, 0x2E8];
+/// The number of ports decoded by a serial port.
+pub const SERIAL_PORT_SIZE: u16 = 8;
+/// The data port of the i8042 controller.
+pub const I8042_DATA_PORT: u16 = 0x60;
+/// The command and status port of the i8042 controller.
+pub const I8042_COMMAND_PORT: u16 = 0x64;
+/// The index and data ports of the CMOS RTC.
+pub const CMOS_PORT: u16 = 0x70;
+/// The number of ports decoded by the CMOS RTC.
+pub const CMOS_SIZE: u16 = 2;
+/// The counter and mode ports of the i8254 PIT.
+pub const PIT_PORT: u16 = 0x40;
+/// The number of ports decoded by the i8254 PIT.
+pub const PIT_SIZE: u16 = 4;
+/// The POST code port.
+pub const POST_CODE_PORT: u16 = 0x80;
+/// The System Control Port A.
+pub const PORT92_PORT: u16 = 0x92;
+/// The game port.
+pub const GAME_PORT: u16 = 0x201;
+/// The base port of the DMA controller of the 8-bit channels.
+pub const DMA1_PORT: u16 = 0x00;
+/// The number of ports decoded by the DMA controller of the 8-bit channels.
+pub const DMA1_SIZE: u16 = 0x10;
+/// The base port of the DMA controller of the 16-bit channels.
+pub const DMA2_PORT: u16 = 0xC0;
+/// The number of ports decoded by the DMA controller of the 16-bit channels.
+pub const DMA2_SIZE: u16 = 0x20;
+/// The first DMA page register registered by `insert_legacy_isa`, after the
+/// POST code port.
+pub const DMA_PAGE_PORT: u16 = 0x81;
+/// The number of DMA page registers registered by `insert_legacy_isa`.
+pub const DMA_PAGE_SIZE: u16 = 0x0F;
+/// The largest MMIO range of a device, as its offsets are 16 bits wide.
+pub const MAX_MMIO_SIZE: u64 = 0x1_0000;
+
+// The offset of the command port, as decoded by the `I8042Device`.
+const I8042_COMMAND_OFFSET: u16 = I8042_COMMAND_PORT - I8042_DATA_PORT;
+// The last port.
+const MAX_PORT: u64 = 0xFFFF;
+
+/// Errors encountered while registering the devices on the dispatchers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The range is empty, larger than `MAX_MMIO_SIZE`, or extends past the
+    /// last address.
+    InvalidRange(u64, u64),
+    /// The range overlaps the range of the device registered at the address.
+    Overlap(u64),
+    /// The serial port is not one of COM1 to COM4.
+    InvalidComPort(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidRange(base, size) => {
+                write!(f, "Invalid range of {:#x} bytes at {:#x}", size, base)
+            }
+            Error::Overlap(base) => write!(f, "The range overlaps the device at {:#x}", base),
+            Error::InvalidComPort(com) => write!(f, "Invalid serial port COM{}", com),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+// The part of `LegacyDevice` used by the dispatchers, which doesn't depend on
+// the state of the device, so that devices of different types can be
+// registered together.
+trait BusDevice: Send {
+    fn read(&mut self, offset: u16, data: &mut [u8]);
+    fn write(&mut self, offset: u16, data: &[u8]);
+    fn reset(&mut self);
+}
+
+impl<D: LegacyDevice + Send> BusDevice for D {
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        LegacyDevice::read(self, offset, data);
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        LegacyDevice::write(self, offset, data);
+    }
+
+    fn reset(&mut self) {
+        LegacyDevice::reset(self);
+    }
+}
+
+struct DeviceRange {
+    size: u64,
+    // The offset of the first address of the range, from the base of the
+    // device.
+    offset: u16,
+    device: Arc<Mutex<dyn BusDevice>>,
+}
+
+// The ranges of the devices, keyed by their base address.
+#[derive(Default)]
+struct RangeMap(BTreeMap<u64, DeviceRange>);
+
+impl RangeMap {
+    fn insert(
+        &mut self,
+        base: u64,
+        size: u64,
+        offset: u16,
+        max_addr: u64,
+        device: Arc<Mutex<dyn BusDevice>>,
+    ) -> Result<(), Error> {
+        let last = size
+            .checked_sub(1)
+            .filter(|_| size <= MAX_MMIO_SIZE)
+            .and_then(|len| base.checked_add(len))
+            .filter(|&last| last <= max_addr)
+            .ok_or(Error::InvalidRange(base, size))?;
+        if let Some((prev, _)) = self.find(base) {
+            return Err(Error::Overlap(prev));
+        }
+        if let Some((&next, _)) = self.0.range(base..=last).next() {
+            return Err(Error::Overlap(next));
+        }
+        self.0.insert(
+            base,
+            DeviceRange {
+                size,
+                offset,
+                device,
+            },
+        );
+        Ok(())
+    }
+
+    fn remove(&mut self, base: u64) -> bool {
+        self.0.remove(&base).is_some()
+    }
+
+    // Resets the devices, once each even if they decode several ranges.
+    fn reset(&self) {
+        let mut devices = BTreeSet::new();
+        for range in self.0.values() {
+            if devices.insert(Arc::as_ptr(&range.device) as *const () as usize) {
+                range.device.lock().unwrap().reset();
+            }
+        }
+    }
+
+    // Returns the range holding `addr`, with its base.
+    fn find(&self, addr: u64) -> Option<(u64, &DeviceRange)> {
+        self.0
+            .range((Bound::Unbounded, Bound::Included(addr)))
+            .next_back()
+            .filter(|(&base, range)| addr - base < range.size)
+            .map(|(&base, range)| (base, range))
+    }
+
+    // Returns the device handling an access of `len` bytes at `addr`, and the
+    // offset of the access. Unless `partial` is set, the access has to fit in
+    // the range of the device.
+    fn device(
+        &self,
+        addr: u64,
+        len: usize,
+        partial: bool,
+    ) -> Option<(&Arc<Mutex<dyn BusDevice>>, u16)> {
+        self.find(addr)
+            .filter(|(base, range)| partial || addr - base + len as u64 <= range.size)
+            .map(|(base, range)| (&range.device, range.offset + (addr - base) as u16))
+    }
+}
+
+impl fmt::Debug for RangeMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(
+                self.0
+                    .iter()
+                    .map(|(&base, range)| base..=base + (range.size - 1)),
+            )
+            .finish()
+    }
+}
+
+/// Routes the port I/O accesses of the guest to the registered devices.
+///
+/// The reads from the ports without any device behind them return 0xFF, like
+/// a floating ISA bus, and the writes to them are dropped. An access is
+/// routed on its first port, so the device gets the whole access even if it
+/// goes past the end of its range.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::{Arc, Mutex};
+/// # use vm_superio::dispatcher::PortIoDispatcher;
+/// # use vm_superio::Port80;
+/// let port80 = Arc::new(Mutex::new(Port80::new(16)));
+/// let mut dispatcher = PortIoDispatcher::new();
+/// dispatcher.insert_post_code(port80.clone()).unwrap();
+///
+/// // The firmware reports its progress.
+/// assert!(dispatcher.pio_write(0x80, &[0x55]));
+/// assert_eq!(port80.lock().unwrap().last_code(), Some(0x55));
+///
+/// // Nothing is behind port 0x81.
+/// let mut data = [0u8; 1];
+/// assert!(!dispatcher.pio_read(0x81, &mut data));
+/// assert_eq!(data, [0xFF]);
+/// ```
+#[derive(Debug, Default)]
+pub struct PortIoDispatcher {
+    ranges: RangeMap,
+}
+
+impl PortIoDispatcher {
+    /// Creates a new `PortIoDispatcher` instance, without any device.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `PortIoDispatcher`](struct.PortIoDispatcher.html#example).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `device` for the `size` ports starting at `base`. The device
+    /// is called with the offset of the port from `base`.
+    ///
+    /// # Arguments
+    /// * `base` - The first port decoded by the device.
+    /// * `size` - The number of ports decoded by the device.
+    /// * `device` - The device, shared with the VMM.
+    pub fn insert<D: LegacyDevice + Send + 'static>(
+        &mut self,
+        base: u16,
+        size: u16,
+        device: Arc<Mutex<D>>,
+    ) -> Result<(), Error> {
+        self.insert_range(base, size, 0, device)
+    }
+
+    fn insert_range(
+        &mut self,
+        base: u16,
+        size: u16,
+        offset: u16,
+        device: Arc<Mutex<dyn BusDevice>>,
+    ) -> Result<(), Error> {
+        self.ranges
+            .insert(u64::from(base), u64::from(size), offset, MAX_PORT, device)
+    }
+
+    /// Unregisters the device whose range starts at `base`. Returns whether
+    /// there was such a device.
+    ///
+    /// # Arguments
+    /// * `base` - The first port decoded by the device.
+    pub fn remove(&mut self, base: u16) -> bool {
+        self.ranges.remove(u64::from(base))
+    }
+
+    /// Resets all the registered devices to their power-on state, e.g. when
+    /// the guest reboots. The devices decoding several ranges, such as the
+    /// i8042 controller, are reset once.
+    pub fn reset(&self) {
+        self.ranges.reset();
+    }
+
+    /// Registers `device` as the serial port `com`, from COM1 to COM4, which
+    /// decodes the 8 ports at its base in `COM_PORTS`.
+    ///
+    /// # Arguments
+    /// * `com` - The number of the serial port, from 1 to 4.
+    /// * `device` - The serial port, usually a [`Serial`](../serial/struct.Serial.html).
+    pub fn insert_com<D: LegacyDevice + Send + 'static>(
+        &mut self,
+        com: usize,
+        device: Arc<Mutex<D>>,
+    ) -> Result<(), Error> {
+        let base = com
+            .checked_sub(1)
+            .and_then(|index| COM_PORTS.get(index))
+            .ok_or(Error::InvalidComPort(com))?;
+        self.insert(*base, SERIAL_PORT_SIZE, device)
+    }
+
+    /// Registers `device` as the i8042 controller, at the data (0x60) and the
+    /// command (0x64) ports, which it decodes at the offsets 0 and 4. The ports
+    /// in between, such as the System Control Port B (0x61), are left for
+    /// other devices.
+    ///
+    /// # Arguments
+    /// * `device` - The controller, usually an [`I8042Device`](../i8042/struct.I8042Device.html).
+    pub fn insert_i8042<D: LegacyDevice + Send + 'static>(
+        &mut self,
+        device: Arc<Mutex<D>>,
+    ) -> Result<(), Error> {
+        self.insert_range(I8042_DATA_PORT, 1, 0, device.clone())?;
+        if let Err(e) = self.insert_range(I8042_COMMAND_PORT, 1, I8042_COMMAND_OFFSET, device) {
+            self.remove(I8042_DATA_PORT);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Registers `device` as the CMOS RTC, at the index (0x70) and the data
+    /// (0x71) ports. The crate doesn't emulate this device, so it is provided
+    /// by the VMM.
+    ///
+    /// # Arguments
+    /// * `device` - The CMOS RTC.
+    pub fn insert_cmos<D: LegacyDevice + Send + 'static>(
+        &mut self,
+        device: Arc<Mutex<D>>,
+    ) -> Result<(), Error> {
+        self.insert(CMOS_PORT, CMOS_SIZE, device)
+    }
+
+    /// Registers `device` as the i8254 PIT, at the ports 0x40 to 0x43.
+    ///
+    /// # Arguments
+    /// * `device` - The timer, usually a [`Pit`](../pit/struct.Pit.html).
+    pub fn insert_pit<D: LegacyDevice + Send + 'static>(
+        &mut self,
+        device: Arc<Mutex<D>>,
+    ) -> Result<(), Error> {
+        self.insert(PIT_PORT, PIT_SIZE, device)
+    }
+
+    /// Registers `device` as the POST code port (0x80).
+    ///
+    /// # Arguments
+    /// * `device` - The port, usually a [`Port80`](../port80/struct.Port80.html).
+    pub fn insert_post_code<D: LegacyDevice + Send + 'static>(
+        &mut self,
+        device: Arc<Mutex<D>>,
+    ) -> Result<(), Error> {
+        self.insert(POST_CODE_PORT, 1, device)
+    }
+
+    /// Registers `device` as the System Control Port A (0x92).
+    ///
+    /// # Arguments
+    /// * `device` - The port, usually a [`Port92`](../port92/struct.Port92.html).
+    pub fn insert_port92<D: LegacyDevice + Send + 'static>(
+        &mut self,
+        device: Arc<Mutex<D>>,
+    ) -> Result<(), Error> {
+        self.insert(PORT92_PORT, 1, device)
+    }
+
+    /// Registers `device` as the game port (0x201).
+    ///
+    /// # Arguments
+    /// * `device` - The port, usually a [`GamePort`](../game_port/struct.GamePort.html).
+    pub fn insert_game_port<D: LegacyDevice + Send + 'static>(
+        &mut self,
+        device: Arc<Mutex<D>>,
+    ) -> Result<(), Error> {
+        self.insert(GAME_PORT, 1, device)
+    }
+
+    /// Registers the stubs of the other devices of a full legacy ISA PC,
+    /// which the firmware and the legacy guests probe even when they don't use
+    /// them: the [`I8237`](../i8237/struct.I8237.html) DMA controllers (ports
+    /// 0x00-0x0F and 0xC0-0xDF), their
+    /// [`DmaPageRegisters`](../i8237/struct.DmaPageRegisters.html) (ports
+    /// 0x81-0x8F, as port 0x80 is the POST code port) and a
+    /// [`GamePort`](../game_port/struct.GamePort.html) (port 0x201).
+    ///
+    /// The stubs have no inputs, so they are owned by the dispatcher, and
+    /// reset with the other devices. If one of their ranges is already taken,
+    /// none of them is registered.
+    pub fn insert_legacy_isa(&mut self) -> Result<(), Error> {
+        let stub = |size: u16, offset: u16, device: Arc<Mutex<dyn BusDevice>>| DeviceRange {
+            size: u64::from(size),
+            offset,
+            device,
+        };
+        let stubs = vec![
+            (
+                DMA1_PORT,
+                stub(DMA1_SIZE, 0, Arc::new(Mutex::new(I8237::new_8bit()))),
+            ),
+            (
+                DMA2_PORT,
+                stub(DMA2_SIZE, 0, Arc::new(Mutex::new(I8237::new_16bit()))),
+            ),
+            (
+                DMA_PAGE_PORT,
+                // The page registers are decoded from port 0x80.
+                stub(
+                    DMA_PAGE_SIZE,
+                    DMA_PAGE_PORT - POST_CODE_PORT,
+                    Arc::new(Mutex::new(DmaPageRegisters::new())),
+                ),
+            ),
+            (GAME_PORT, stub(1, 0, Arc::new(Mutex::new(GamePort::new())))),
+        ];
+
+        let mut inserted = Vec::new();
+        for (base, range) in stubs {
+            let base = u64::from(base);
+            if let Err(e) =
+                self.ranges
+                    .insert(base, range.size, range.offset, MAX_PORT, range.device)
+            {
+                for base in inserted {
+                    self.ranges.remove(base);
+                }
+                return Err(e);
+            }
+            inserted.push(base);
+        }
+        Ok(())
+    }
+
+    /// Handles a read of `data.len()` bytes from `port`. Returns whether a
+    /// device is registered at the port.
+    ///
+    /// # Arguments
+    /// * `port` - The first port of the access.
+    /// * `data` - The little-endian byte array storing the read value.
+    pub fn pio_read(&self, port: u16, data: &mut [u8]) -> bool {
+        trace_span!("pio_read", port);
+        match self.ranges.device(u64::from(port), data.len(), true) {
+            Some((device, offset)) => {
+                device.lock().unwrap().read(offset, data);
+                true
+            }
+            None => {
+                data.fill(0xFF);
+                false
+            }
+        }
+    }
+
+    /// Handles a write of `data` to `port`. Returns whether a device is
+    /// registered at the port.
+    ///
+    /// # Arguments
+    /// * `port` - The first port of the access.
+    /// * `data` - The little-endian byte array to write.
+    pub fn pio_write(&self, port: u16, data: &[u8]) -> bool {
+        trace_span!("pio_write", port);
+        match self.ranges.device(u64::from(port), data.len(), true) {
+            Some((device, offset)) => {
+                device.lock().unwrap().write(offset, data);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Routes the MMIO accesses of the guest to the registered devices.
+///
+/// The accesses are forwarded only when they fit in the range of the device,
+/// which then validates their width and alignment. The other accesses, and
+/// those to the addresses without any device behind them, read as 0, and
+/// their writes are dropped. As the offsets of the devices are 16 bits wide,
+/// the ranges are at most `MAX_MMIO_SIZE` bytes long.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::{Arc, Mutex};
+/// # use vm_superio::dispatcher::MmioDispatcher;
+/// # use vm_superio::Rtc;
+/// let rtc = Arc::new(Mutex::new(Rtc::new()));
+/// let mut dispatcher = MmioDispatcher::new();
+/// dispatcher.insert(0x901_0000, 0x1000, rtc).unwrap();
+///
+/// // Read the PrimeCell ID of the RTC.
+/// let mut data = [0u8; 4];
+/// assert!(dispatcher.mmio_read(0x901_0FF0, &mut data));
+/// assert_eq!(data, [0x0D, 0, 0, 0]);
+///
+/// // The access crosses the end of the range.
+/// assert!(!dispatcher.mmio_read(0x901_0FFE, &mut data));
+/// assert_eq!(data, [0; 4]);
+/// ```
+#[derive(Debug, Default)]
+pub struct MmioDispatcher {
+    ranges: RangeMap,
+}
+
+impl MmioDispatcher {
+    /// Creates a new `MmioDispatcher` instance, without any device.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `MmioDispatcher`](struct.MmioDispatcher.html#example).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `device` for the `size` bytes starting at `base`. The device
+    /// is called with the offset of the address from `base`.
+    ///
+    /// # Arguments
+    /// * `base` - The first address decoded by the device.
+    /// * `size` - The size of the register window of the device, of at most
+    ///   `MAX_MMIO_SIZE` bytes.
+    /// * `device` - The device, shared with the VMM.
+    pub fn insert<D: LegacyDevice + Send + 'static>(
+        &mut self,
+        base: u64,
+        size: u64,
+        device: Arc<Mutex<D>>,
+    ) -> Result<(), Error> {
+        self.ranges.insert(base, size, 0, u64::MAX, device)
+    }
+
+    /// Unregisters the device whose range starts at `base`. Returns whether
+    /// there was such a device.
+    ///
+    /// # Arguments
+    /// * `base` - The first address decoded by the device.
+    pub fn remove(&mut self, base: u64) -> bool {
+        self.ranges.remove(base)
+    }
+
+    /// Resets all the registered devices to their power-on state, e.g. when
+    /// the guest reboots.
+    pub fn reset(&self) {
+        self.ranges.reset();
+    }
+
+    /// Handles a read of `data.len()` bytes from `addr`. Returns whether the
+    /// access was forwarded to a device.
+    ///
+    /// # Arguments
+    /// * `addr` - The guest physical address of the access.
+    /// * `data` - The little-endian byte array storing the read value.
+    pub fn mmio_read(&self, addr: u64, data: &mut [u8]) -> bool {
+        trace_span!("mmio_read", addr);
+        match self.ranges.device(addr, data.len(), false) {
+            Some((device, offset)) => {
+                device.lock().unwrap().read(offset, data);
+                true
+            }
+            None => {
+                data.fill(0);
+                false
+            }
+        }
+    }
+
+    /// Handles a write of `data` to `addr`. Returns whether the access was
+    /// forwarded to a device.
+    ///
+    /// # Arguments
+    /// * `addr` - The guest physical address of the access.
+    /// * `data` - The little-endian byte array to write.
+    pub fn mmio_write(&self, addr: u64, data: &[u8]) -> bool {
+        trace_span!("mmio_write", addr);
+        match self.ranges.device(addr, data.len(), false) {
+            Some((device, offset)) => {
+                device.lock().unwrap().write(offset, data);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial::Serial;
+    use crate::testing::{MockClock, MockTrigger};
+    use crate::{I8042Device, Pit, Port80, Port92};
+    use std::io::sink;
+
+    // Records the offset and the value of the last access.
+    #[derive(Default)]
+    struct MockDevice {
+        last_read: Option<(u16, usize)>,
+        last_write: Option<(u16, Vec<u8>)>,
+        resets: usize,
+    }
+
+    impl LegacyDevice for MockDevice {
+        type State = ();
+
+        fn read(&mut self, offset: u16, data: &mut [u8]) {
+            self.last_read = Some((offset, data.len()));
+            data.fill(0x42);
+        }
+
+        fn write(&mut self, offset: u16, data: &[u8]) {
+            self.last_write = Some((offset, data.to_vec()));
+        }
+
+        fn reset(&mut self) {
+            self.resets += 1;
+        }
+
+        fn state(&self) -> Self::State {}
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let dev = Arc::new(Mutex::new(MockDevice::default()));
+        let mut dispatcher = PortIoDispatcher::new();
+        dispatcher.insert(0x100, 0x10, dev.clone()).unwrap();
+
+        let mut data = [0u8; 2];
+        assert!(dispatcher.pio_read(0x10F, &mut data));
+        assert_eq!(data, [0x42, 0x42]);
+        assert_eq!(dev.lock().unwrap().last_read, Some((0xF, 2)));
+
+        assert!(dispatcher.pio_write(0x104, &[1, 2, 3, 4]));
+        assert_eq!(dev.lock().unwrap().last_write, Some((4, vec![1, 2, 3, 4])));
+
+        // The ports around the range are unclaimed.
+        assert!(!dispatcher.pio_read(0xFF, &mut data));
+        assert_eq!(data, [0xFF, 0xFF]);
+        assert!(!dispatcher.pio_read(0x110, &mut data));
+        assert!(!dispatcher.pio_write(0x110, &[0]));
+        assert_eq!(dev.lock().unwrap().last_read, Some((0xF, 2)));
+
+        assert!(dispatcher.remove(0x100));
+        assert!(!dispatcher.remove(0x100));
+        assert!(!dispatcher.pio_read(0x100, &mut data));
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let dev = Arc::new(Mutex::new(MockDevice::default()));
+        let mut dispatcher = PortIoDispatcher::new();
+
+        assert_eq!(
+            dispatcher.insert(0x100, 0, dev.clone()),
+            Err(Error::InvalidRange(0x100, 0))
+        );
+        assert_eq!(
+            dispatcher.insert(0xFFFF, 2, dev.clone()),
+            Err(Error::InvalidRange(0xFFFF, 2))
+        );
+        dispatcher.insert(0xFFFF, 1, dev.clone()).unwrap();
+
+        dispatcher.insert(0x100, 0x10, dev.clone()).unwrap();
+        assert_eq!(
+            dispatcher.insert(0x10F, 1, dev.clone()),
+            Err(Error::Overlap(0x100))
+        );
+        assert_eq!(
+            dispatcher.insert(0xF0, 0x11, dev.clone()),
+            Err(Error::Overlap(0x100))
+        );
+        assert_eq!(
+            dispatcher.insert(0x100, 1, dev.clone()),
+            Err(Error::Overlap(0x100))
+        );
+        // The adjacent ranges don't overlap.
+        dispatcher.insert(0xF0, 0x10, dev.clone()).unwrap();
+        dispatcher.insert(0x110, 1, dev.clone()).unwrap();
+
+        assert_eq!(
+            dispatcher.insert_com(0, dev.clone()),
+            Err(Error::InvalidComPort(0))
+        );
+        assert_eq!(dispatcher.insert_com(5, dev), Err(Error::InvalidComPort(5)));
+    }
+
+    #[test]
+    fn test_classic_layout() {
+        let mut dispatcher = PortIoDispatcher::new();
+        let com1 = Arc::new(Mutex::new(Serial::new(MockTrigger::new(), sink())));
+        let com4 = Arc::new(Mutex::new(Serial::new(MockTrigger::new(), sink())));
+        let i8042 = Arc::new(Mutex::new(I8042Device::with_irqs(
+            MockTrigger::new(),
+            MockTrigger::new(),
+            MockTrigger::new(),
+        )));
+        let cmos = Arc::new(Mutex::new(MockDevice::default()));
+        let pit = Arc::new(Mutex::new(Pit::new(MockTrigger::new(), MockClock::new())));
+        let port80 = Arc::new(Mutex::new(Port80::new(4)));
+        let port92 = Arc::new(Mutex::new(Port92::new(MockTrigger::new())));
+        let port61 = Arc::new(Mutex::new(MockDevice::default()));
+        let game_port = Arc::new(Mutex::new(GamePort::new()));
+
+        dispatcher.insert_com(1, com1.clone()).unwrap();
+        dispatcher.insert_com(4, com4).unwrap();
+        dispatcher.insert_i8042(i8042).unwrap();
+        dispatcher.insert_cmos(cmos.clone()).unwrap();
+        dispatcher.insert_pit(pit).unwrap();
+        dispatcher.insert_post_code(port80.clone()).unwrap();
+        dispatcher.insert_port92(port92.clone()).unwrap();
+        dispatcher.insert_game_port(game_port).unwrap();
+        // Port 0x61 sits between the i8042 ports.
+        dispatcher.insert(0x61, 1, port61.clone()).unwrap();
+
+        // The scratch register of the serial ports.
+        assert!(dispatcher.pio_write(0x3F8 + 7, &[0x12]));
+        assert!(dispatcher.pio_write(0x2E8 + 7, &[0x34]));
+        let mut data = [0u8; 1];
+        dispatcher.pio_read(0x3FF, &mut data);
+        assert_eq!(data, [0x12]);
+        dispatcher.pio_read(0x2EF, &mut data);
+        assert_eq!(data, [0x34]);
+        assert!(!dispatcher.pio_read(0x2F8, &mut data));
+
+        // The i8042 self-test replies through the data port.
+        assert!(dispatcher.pio_write(0x64, &[0xAA]));
+        dispatcher.pio_read(0x64, &mut data);
+        assert_eq!(data[0] & 0x01, 0x01);
+        dispatcher.pio_read(0x60, &mut data);
+        assert_eq!(data, [0x55]);
+        assert!(!dispatcher.pio_read(0x62, &mut data));
+        dispatcher.pio_read(0x61, &mut data);
+        assert_eq!(port61.lock().unwrap().last_read, Some((0, 1)));
+
+        assert!(dispatcher.pio_write(0x71, &[0x0A]));
+        assert_eq!(cmos.lock().unwrap().last_write, Some((1, vec![0x0A])));
+
+        // Latch the counter 0 of the PIT.
+        assert!(dispatcher.pio_write(0x43, &[0x00]));
+        assert!(dispatcher.pio_read(0x40, &mut data));
+        assert!(!dispatcher.pio_read(0x44, &mut data));
+
+        assert!(dispatcher.pio_write(0x80, &[0x99]));
+        assert_eq!(port80.lock().unwrap().last_code(), Some(0x99));
+
+        assert!(dispatcher.pio_write(0x92, &[0x02]));
+        assert!(port92.lock().unwrap().a20_enabled());
+
+        // No joystick is attached to the game port.
+        assert!(dispatcher.pio_write(0x201, &[0xFF]));
+        assert!(dispatcher.pio_read(0x201, &mut data));
+        assert_eq!(data, [0xF0]);
+        assert!(!dispatcher.pio_read(0x202, &mut data));
+
+        assert_eq!(
+            dispatcher.insert_i8042(Arc::new(Mutex::new(MockDevice::default()))),
+            Err(Error::Overlap(0x60))
+        );
+    }
+
+    #[test]
+    fn test_legacy_isa() {
+        let mut dispatcher = PortIoDispatcher::new();
+        let port80 = Arc::new(Mutex::new(Port80::new(4)));
+        dispatcher.insert_post_code(port80.clone()).unwrap();
+        dispatcher.insert_legacy_isa().unwrap();
+
+        // No joystick is attached to the game port.
+        let mut data = [0u8; 1];
+        assert!(dispatcher.pio_read(0x201, &mut data));
+        assert_eq!(data, [0xF0]);
+
+        // The address of channel 2 of the first DMA controller, and the
+        // address of channel 5 of the second one, at the even ports, read back
+        // the low byte first.
+        for (flip_flop, address) in [(0x0C, 0x04), (0xD8, 0xC4)] {
+            assert!(dispatcher.pio_write(flip_flop, &[0]));
+            assert!(dispatcher.pio_write(address, &[0x34]));
+            assert!(dispatcher.pio_write(address, &[0x12]));
+            assert!(dispatcher.pio_read(address, &mut data));
+            assert_eq!(data, [0x34]);
+            assert!(dispatcher.pio_read(address, &mut data));
+            assert_eq!(data, [0x12]);
+        }
+        assert!(!dispatcher.pio_read(0xE0, &mut data));
+
+        // The page of channel 2 is at port 0x81, while port 0x80 is still the
+        // POST code port.
+        assert!(dispatcher.pio_write(0x81, &[0x12]));
+        assert!(dispatcher.pio_read(0x81, &mut data));
+        assert_eq!(data, [0x12]);
+        assert!(dispatcher.pio_write(0x80, &[0x99]));
+        assert_eq!(port80.lock().unwrap().last_code(), Some(0x99));
+
+        // The stubs are reset with the other devices.
+        dispatcher.reset();
+        dispatcher.pio_read(0x81, &mut data);
+        assert_eq!(data, [0x00]);
+
+        // The stubs are registered all together, or not at all.
+        let mut dispatcher = PortIoDispatcher::new();
+        let game_port = Arc::new(Mutex::new(MockDevice::default()));
+        dispatcher.insert_game_port(game_port).unwrap();
+        assert_eq!(dispatcher.insert_legacy_isa(), Err(Error::Overlap(0x201)));
+        assert!(!dispatcher.pio_read(0x00, &mut data));
+        assert!(!dispatcher.pio_read(0x81, &mut data));
+    }
+
+    #[test]
+    fn test_mmio_dispatch() {
+        let dev = Arc::new(Mutex::new(MockDevice::default()));
+        let mut dispatcher = MmioDispatcher::new();
+        dispatcher.insert(0x900_0000, 0x1000, dev.clone()).unwrap();
+
+        let mut data = [0u8; 4];
+        assert!(dispatcher.mmio_read(0x900_0018, &mut data));
+        assert_eq!(data, [0x42; 4]);
+        assert_eq!(dev.lock().unwrap().last_read, Some((0x18, 4)));
+        assert!(dispatcher.mmio_write(0x900_0FFC, &[1, 2, 3, 4]));
+        assert_eq!(
+            dev.lock().unwrap().last_write,
+            Some((0xFFC, vec![1, 2, 3, 4]))
+        );
+
+        // The accesses crossing the end of the range aren't forwarded.
+        assert!(!dispatcher.mmio_read(0x900_0FFE, &mut data));
+        assert_eq!(data, [0; 4]);
+        assert!(!dispatcher.mmio_write(0x900_0FFD, &[0; 4]));
+        assert!(!dispatcher.mmio_read(0x900_1000, &mut data[..1]));
+        assert_eq!(dev.lock().unwrap().last_read, Some((0x18, 4)));
+
+        assert_eq!(
+            dispatcher.insert(0x8FF_F000, 0x1001, dev.clone()),
+            Err(Error::Overlap(0x900_0000))
+        );
+        assert_eq!(
+            dispatcher.insert(0x900_0800, 0x10, dev.clone()),
+            Err(Error::Overlap(0x900_0000))
+        );
+        assert_eq!(
+            dispatcher.insert(0, MAX_MMIO_SIZE + 1, dev.clone()),
+            Err(Error::InvalidRange(0, MAX_MMIO_SIZE + 1))
+        );
+        assert_eq!(
+            dispatcher.insert(u64::MAX, 2, dev.clone()),
+            Err(Error::InvalidRange(u64::MAX, 2))
+        );
+        dispatcher
+            .insert(u64::MAX - 0xFFF, 0x1000, dev.clone())
+            .unwrap();
+        assert!(dispatcher.mmio_read(u64::MAX - 3, &mut data));
+        assert_eq!(dev.lock().unwrap().last_read, Some((0xFFC, 4)));
+
+        assert!(dispatcher.remove(0x900_0000));
+        assert!(!dispatcher.mmio_read(0x900_0018, &mut data));
+        dispatcher.insert(0x900_0000, MAX_MMIO_SIZE, dev).unwrap();
+    }
+
+    #[test]
+    fn test_reset() {
+        let i8042 = Arc::new(Mutex::new(MockDevice::default()));
+        let port80 = Arc::new(Mutex::new(MockDevice::default()));
+        let mut pio = PortIoDispatcher::new();
+        pio.insert_i8042(i8042.clone()).unwrap();
+        pio.insert_post_code(port80.clone()).unwrap();
+
+        // The i8042 controller decodes two ranges, but is reset once.
+        pio.reset();
+        assert_eq!(i8042.lock().unwrap().resets, 1);
+        assert_eq!(port80.lock().unwrap().resets, 1);
+
+        let rtc = Arc::new(Mutex::new(MockDevice::default()));
+        let mut mmio = MmioDispatcher::new();
+        mmio.insert(0x901_0000, 0x1000, rtc.clone()).unwrap();
+        mmio.reset();
+        mmio.reset();
+        assert_eq!(rtc.lock().unwrap().resets, 2);
+
+        // The devices are still registered.
+        assert!(pio.pio_write(0x64, &[0xAA]));
+        assert!(mmio.mmio_write(0x901_0000, &[0; 4]));
+    }
+}
@@ -0,0 +1,330 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the Edge/Level Control Registers (ports 0x4D0 and
+//! 0x4D1).
+//!
+//! The guest programs these registers for configuring the trigger mode of
+//! the legacy IRQs, e.g. when routing the PCI interrupts, which are level
+//! triggered, to the i8259 PICs.
+
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+#[cfg(feature = "std")]
+use crate::i8259::Pic;
+#[cfg(feature = "std")]
+use crate::Trigger;
+use crate::{byte_offset, LegacyDevice};
+
+// Offsets of the registers of the master (IRQs 0-7) and slave (IRQs 8-15)
+// PICs.
+const MASTER_OFFSET: u8 = 0;
+const SLAVE_OFFSET: u8 = 1;
+
+// The IRQs that can be level triggered. The timer (IRQ 0), the keyboard
+// (IRQ 1), the cascade (IRQ 2), the RTC (IRQ 8) and the FPU (IRQ 13) are
+// always edge triggered, so their bits read as 0.
+const MASTER_WRITABLE_MASK: u8 = 0b1111_1000;
+const SLAVE_WRITABLE_MASK: u8 = 0b1101_1110;
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the ELCR emulation logic. The methods below can be implemented by a
+/// backend that keeps track of such events by updating the interrupt controllers, logging
+/// messages, or any other action.
+///
+/// It is implemented for a `Mutex` wrapping a [`Pic`](../i8259/struct.Pic.html),
+/// which then honors the trigger modes programmed by the guest.
+pub trait ElcrEvents {
+    /// The driver changed the trigger mode of some IRQs. Bit N of
+    /// `level_mask` is set if IRQ N is level triggered.
+    fn level_mask_changed(&self, level_mask: u16);
+}
+
+/// Provides a no-op implementation of `ElcrEvents` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `ElcrEvents`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl ElcrEvents for NoEvents {
+    fn level_mask_changed(&self, _level_mask: u16) {}
+}
+
+impl<EV: ElcrEvents> ElcrEvents for Arc<EV> {
+    fn level_mask_changed(&self, level_mask: u16) {
+        self.as_ref().level_mask_changed(level_mask);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Trigger> ElcrEvents for Mutex<Pic<T>> {
+    fn level_mask_changed(&self, level_mask: u16) {
+        // A poisoned lock still holds a consistent PIC, since its methods
+        // don't panic halfway through an update.
+        let mut pic = self.lock().unwrap_or_else(|e| e.into_inner());
+        pic.set_elcr(level_mask);
+    }
+}
+
+/// The Edge/Level Control Registers, usually found at ports 0x4D0 (master)
+/// and 0x4D1 (slave).
+///
+/// The configured mask of the level triggered IRQs is available through
+/// [`level_mask`](#method.level_mask), and is reported through
+/// [`ElcrEvents`](trait.ElcrEvents.html) whenever the guest changes it, so
+/// that the interrupt controllers can honor it.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::{Arc, Mutex};
+/// # use vm_superio::elcr::Elcr;
+/// # use vm_superio::i8259::Pic;
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let pic = Arc::new(Mutex::new(Pic::new(DummyTrigger)));
+/// let mut elcr = Elcr::with_events(pic.clone());
+///
+/// // Make IRQs 5 and 11 level triggered.
+/// elcr.write(0, 0x20);
+/// elcr.write(1, 0x08);
+/// assert!(elcr.is_level_triggered(11));
+/// assert_eq!(elcr.level_mask(), 0x0820);
+/// assert_eq!(pic.lock().unwrap().elcr(), 0x0820);
+/// ```
+#[derive(Debug)]
+pub struct Elcr<EV: ElcrEvents> {
+    events: EV,
+    // The registers of the master and slave PICs.
+    master: u8,
+    slave: u8,
+}
+
+impl Elcr<NoEvents> {
+    /// Creates a new `Elcr` instance, with all the IRQs edge triggered.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Elcr`](struct.Elcr.html#example).
+    pub const fn new() -> Self {
+        Self::with_events(NoEvents)
+    }
+}
+
+impl Default for Elcr<NoEvents> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<EV: ElcrEvents> Elcr<EV> {
+    /// Creates a new `Elcr` instance, with all the IRQs edge triggered, which
+    /// invokes the `elcr_evts` implementation of `ElcrEvents` during
+    /// operation.
+    ///
+    /// # Arguments
+    /// * `elcr_evts` - The `ElcrEvents` implementation used to track the
+    ///   changes of the trigger modes.
+    pub const fn with_events(elcr_evts: EV) -> Self {
+        Elcr {
+            events: elcr_evts,
+            master: 0,
+            slave: 0,
+        }
+    }
+
+    /// Provides a reference to the ELCR events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Returns the mask of the level triggered IRQs, where bit N is set if
+    /// IRQ N is level triggered.
+    pub fn level_mask(&self) -> u16 {
+        u16::from(self.master) | (u16::from(self.slave) << 8)
+    }
+
+    /// Returns whether an IRQ is level triggered.
+    ///
+    /// # Arguments
+    /// * `irq` - The IRQ line, between 0 and 15. Other IRQs are reported as
+    ///   edge triggered.
+    pub fn is_level_triggered(&self, irq: u8) -> bool {
+        irq < 16 && (self.level_mask() & (1 << irq)) != 0
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Returns the register of the master or slave PIC, or 0 for invalid
+    /// offsets.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&self, offset: u8) -> u8 {
+        match offset {
+            MASTER_OFFSET => self.master,
+            SLAVE_OFFSET => self.slave,
+            _ => {
+                guest_warn!("Invalid ELCR read at offset {:#x}", offset);
+                0x00
+            }
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) {
+        let old_mask = self.level_mask();
+        let writable_mask = match offset {
+            MASTER_OFFSET => MASTER_WRITABLE_MASK,
+            SLAVE_OFFSET => SLAVE_WRITABLE_MASK,
+            _ => {
+                guest_warn!("Invalid ELCR write at offset {:#x}", offset);
+                return;
+            }
+        };
+        if (value & !writable_mask) != 0 {
+            guest_warn!(
+                "Ignoring the ELCR bits {:#x} of the edge triggered IRQs",
+                value & !writable_mask
+            );
+        }
+        match offset {
+            MASTER_OFFSET => self.master = value & MASTER_WRITABLE_MASK,
+            _ => self.slave = value & SLAVE_WRITABLE_MASK,
+        }
+        if self.level_mask() != old_mask {
+            self.events.level_mask_changed(self.level_mask());
+        }
+    }
+}
+
+impl<EV: ElcrEvents> LegacyDevice for Elcr<EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = Elcr::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            Elcr::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {
+        Elcr::write(self, MASTER_OFFSET, 0);
+        Elcr::write(self, SLAVE_OFFSET, 0);
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use vmm_sys_util::eventfd::EventFd;
+    use vmm_sys_util::metric::Metric;
+
+    #[derive(Default)]
+    struct ExampleElcrEvents {
+        changes: AtomicU64,
+        level_mask: AtomicU64,
+    }
+
+    impl ElcrEvents for ExampleElcrEvents {
+        fn level_mask_changed(&self, level_mask: u16) {
+            self.changes.inc();
+            self.level_mask
+                .store(u64::from(level_mask), Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_elcr_read_write() {
+        let events = Arc::new(ExampleElcrEvents::default());
+        let mut elcr = Elcr::with_events(events.clone());
+        assert_eq!(elcr.level_mask(), 0);
+
+        // The bits of the IRQs that are always edge triggered read as 0.
+        elcr.write(MASTER_OFFSET, 0xFF);
+        elcr.write(SLAVE_OFFSET, 0xFF);
+        assert_eq!(elcr.read(MASTER_OFFSET), 0xF8);
+        assert_eq!(elcr.read(SLAVE_OFFSET), 0xDE);
+        assert_eq!(elcr.level_mask(), 0xDEF8);
+        assert!(elcr.is_level_triggered(3));
+        assert!(!elcr.is_level_triggered(13));
+        assert!(!elcr.is_level_triggered(16));
+        assert_eq!(events.changes.count(), 2);
+        assert_eq!(events.level_mask.load(Ordering::Relaxed), 0xDEF8);
+
+        // Only the actual changes are reported.
+        elcr.write(MASTER_OFFSET, 0xFC);
+        assert_eq!(events.changes.count(), 2);
+        elcr.write(SLAVE_OFFSET, 0x00);
+        assert_eq!(events.changes.count(), 3);
+        assert_eq!(events.level_mask.load(Ordering::Relaxed), 0x00F8);
+
+        // Invalid offsets are ignored.
+        elcr.write(SLAVE_OFFSET + 1, 0xFF);
+        assert_eq!(elcr.read(SLAVE_OFFSET + 1), 0x00);
+        assert_eq!(events.changes.count(), 3);
+    }
+
+    #[test]
+    fn test_elcr_pic() {
+        let pic = Arc::new(Mutex::new(Pic::new(
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+        )));
+        let mut elcr = Elcr::with_events(pic.clone());
+
+        elcr.write(MASTER_OFFSET, 0x20);
+        elcr.write(SLAVE_OFFSET, 0x08);
+        assert_eq!(pic.lock().unwrap().elcr(), 0x0820);
+
+        // IRQ 5 stays pending as long as its line is high.
+        let mut pic = pic.lock().unwrap();
+        pic.set_irq(5, true).unwrap();
+        assert_eq!(pic.acknowledge(), 0x05);
+        pic.write(0x00, 0x20).unwrap();
+        assert!(pic.interrupt_pending());
+        pic.set_irq(5, false).unwrap();
+        assert!(!pic.interrupt_pending());
+    }
+
+    #[test]
+    fn test_elcr_legacy_device() {
+        let events = Arc::new(ExampleElcrEvents::default());
+        let mut elcr = Elcr::with_events(events.clone());
+
+        LegacyDevice::write(&mut elcr, u16::from(SLAVE_OFFSET), &[0x0E]);
+        let mut data = [0; 1];
+        LegacyDevice::read(&mut elcr, u16::from(SLAVE_OFFSET), &mut data);
+        assert_eq!(data, [0x0E]);
+
+        // The reset reports the change of the trigger modes.
+        elcr.reset();
+        assert_eq!(elcr.level_mask(), 0);
+        assert_eq!(events.changes.count(), 2);
+        assert_eq!(events.level_mask.load(Ordering::Relaxed), 0);
+    }
+}
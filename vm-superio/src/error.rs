@@ -0,0 +1,256 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the error type covering the errors of all the devices.
+//!
+//! Each module defines the errors of its device, which implement
+//! `fmt::Display`, and `std::error::Error` with the `std` feature. The
+//! [`Error`](enum.Error.html) of the crate wraps any of them, and is built
+//! from them through `From`, so that a VMM driving several devices handles
+//! their errors with a single type, e.g. with the `?` operator.
+//!
+//! The [`StateError`](enum.StateError.html) is returned by the validation of
+//! the states of the devices, e.g. by
+//! [`SerialState::validate`](../serial/struct.SerialState.html#method.validate).
+
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
+
+#[cfg(feature = "std")]
+use crate::{chassis, dispatcher};
+use crate::{fw_cfg, i8042, parallel, pl050, serial};
+
+/// The errors of the devices of the crate.
+///
+/// `E` is the error type of the `Trigger` objects of the devices. The devices
+/// whose methods return this error directly, such as the `Pit`, are covered
+/// by the `Trigger` variant.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::io::sink;
+/// # use vm_superio::{Error, I8042Device, Serial, Trigger};
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// fn send_input(
+///     serial: &mut Serial<DummyTrigger, vm_superio::serial::NoEvents, std::io::Sink>,
+///     i8042: &mut I8042Device<DummyTrigger, vm_superio::i8042::NoEvents>,
+/// ) -> Result<(), Error<()>> {
+///     serial.enqueue_raw_bytes(b"a")?;
+///     i8042.send_ctrl_alt_del()?;
+///     Ok(())
+/// }
+///
+/// let mut serial = Serial::new(DummyTrigger, sink());
+/// let mut i8042 = I8042Device::new(DummyTrigger);
+/// send_input(&mut serial, &mut i8042).unwrap();
+/// ```
+#[derive(Debug)]
+pub enum Error<E> {
+    /// Failed to trigger an event, or to raise an interrupt.
+    Trigger(E),
+    /// The error of a serial port.
+    Serial(serial::Error<E>),
+    /// The error of a parallel port.
+    Parallel(parallel::Error<E>),
+    /// The error of an i8042 controller.
+    I8042(i8042::Error<E>),
+    /// The error of a PL050 keyboard/mouse interface.
+    Pl050(pl050::Error<E>),
+    /// The error of a fw_cfg device.
+    FwCfg(fw_cfg::Error),
+    /// The error of a port I/O or MMIO dispatcher.
+    #[cfg(feature = "std")]
+    Dispatcher(dispatcher::Error),
+    /// The error of a chassis.
+    #[cfg(feature = "std")]
+    Chassis(chassis::Error<E>),
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Trigger(e) => write!(f, "Failed to trigger event: {}", e),
+            Error::Serial(e) => write!(f, "Serial port error: {}", e),
+            Error::Parallel(e) => write!(f, "Parallel port error: {}", e),
+            Error::I8042(e) => write!(f, "i8042 controller error: {}", e),
+            Error::Pl050(e) => write!(f, "PL050 error: {}", e),
+            Error::FwCfg(e) => write!(f, "fw_cfg error: {}", e),
+            #[cfg(feature = "std")]
+            Error::Dispatcher(e) => write!(f, "Dispatcher error: {}", e),
+            #[cfg(feature = "std")]
+            Error::Chassis(e) => write!(f, "Chassis error: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: StdError + 'static> StdError for Error<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Trigger(e) => Some(e),
+            Error::Serial(e) => Some(e),
+            Error::Parallel(e) => Some(e),
+            Error::I8042(e) => Some(e),
+            Error::Pl050(e) => Some(e),
+            Error::FwCfg(e) => Some(e),
+            Error::Dispatcher(e) => Some(e),
+            Error::Chassis(e) => Some(e),
+        }
+    }
+}
+
+impl<E> From<serial::Error<E>> for Error<E> {
+    fn from(e: serial::Error<E>) -> Self {
+        Error::Serial(e)
+    }
+}
+
+impl<E> From<parallel::Error<E>> for Error<E> {
+    fn from(e: parallel::Error<E>) -> Self {
+        Error::Parallel(e)
+    }
+}
+
+impl<E> From<i8042::Error<E>> for Error<E> {
+    fn from(e: i8042::Error<E>) -> Self {
+        Error::I8042(e)
+    }
+}
+
+impl<E> From<pl050::Error<E>> for Error<E> {
+    fn from(e: pl050::Error<E>) -> Self {
+        Error::Pl050(e)
+    }
+}
+
+impl<E> From<fw_cfg::Error> for Error<E> {
+    fn from(e: fw_cfg::Error) -> Self {
+        Error::FwCfg(e)
+    }
+}
+
+/// Errors encountered while validating the state of a device, e.g. one
+/// deserialized from a snapshot.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StateError {
+    /// A buffer holds more bytes than the device can.
+    BufferOverflow {
+        /// The field of the buffer.
+        buffer: &'static str,
+        /// The length of the buffer.
+        len: usize,
+        /// The capacity of the buffer in the device.
+        capacity: usize,
+    },
+    /// A register holds a value the device never produces.
+    InvalidRegister {
+        /// The field of the register.
+        register: &'static str,
+        /// The value of the register.
+        value: u32,
+    },
+    /// The line status register reports data ready while the FIFO is empty,
+    /// or the other way around.
+    DataReadyMismatch,
+    /// A time or a count is beyond the values the device can reach.
+    OutOfRange {
+        /// The field of the time or of the count.
+        field: &'static str,
+        /// The value of the field.
+        value: u64,
+        /// The highest value the device can reach.
+        max: u64,
+    },
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::BufferOverflow {
+                buffer,
+                len,
+                capacity,
+            } => write!(
+                f,
+                "Buffer {} holds {} bytes, more than its capacity of {}",
+                buffer, len, capacity
+            ),
+            StateError::InvalidRegister { register, value } => {
+                write!(f, "Invalid value {:#x} of register {}", value, register)
+            }
+            StateError::DataReadyMismatch => {
+                write!(f, "Data ready bit inconsistent with the FIFO")
+            }
+            StateError::OutOfRange { field, value, max } => {
+                write!(
+                    f,
+                    "Value {} of {} is above its maximum {}",
+                    value, field, max
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl StdError for StateError {}
+
+#[cfg(feature = "std")]
+impl<E> From<dispatcher::Error> for Error<E> {
+    fn from(e: dispatcher::Error) -> Self {
+        Error::Dispatcher(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E> From<chassis::Error<E>> for Error<E> {
+    fn from(e: chassis::Error<E>) -> Self {
+        Error::Chassis(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FwCfg, PortIoDispatcher, Serial};
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use vmm_sys_util::eventfd::EventFd;
+
+    #[test]
+    fn test_error_conversions() {
+        let intr_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let mut serial = Serial::new(intr_evt, io::sink());
+        let fifo_capacity = serial.fifo_capacity();
+        serial
+            .enqueue_raw_bytes(&vec![0; fifo_capacity])
+            .map_err(Error::<io::Error>::from)
+            .unwrap();
+        let e = Error::<io::Error>::from(serial.enqueue_raw_bytes(&[0]).unwrap_err());
+        assert!(matches!(e, Error::Serial(serial::Error::FullFifo)));
+        assert_eq!(e.to_string(), "Serial port error: No space left in FIFO");
+        assert_eq!(e.source().unwrap().to_string(), "No space left in FIFO");
+
+        let mut fw_cfg = FwCfg::new();
+        let e: Error<io::Error> = fw_cfg.add_file("", Vec::new()).unwrap_err().into();
+        assert_eq!(e.to_string(), "fw_cfg error: Invalid file name");
+
+        let mut dispatcher = PortIoDispatcher::new();
+        let e: Error<io::Error> = dispatcher
+            .insert_com(5, Arc::new(Mutex::new(serial)))
+            .unwrap_err()
+            .into();
+        assert_eq!(e.to_string(), "Dispatcher error: Invalid serial port COM5");
+
+        let e = Error::Trigger(io::Error::from(io::ErrorKind::WouldBlock));
+        assert!(e.to_string().starts_with("Failed to trigger event: "));
+        assert!(e.source().is_some());
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the `Trigger` implementations based on the `EventFd` of
+//! `vmm-sys-util`.
+//!
+//! The `EventFd` implements `Trigger` directly, by writing 1 to its counter,
+//! which is how the VMMs usually inject the interrupts through the irqfd of
+//! KVM. The `EventFdTrigger` wraps it with the infallible constructor and
+//! clone used by most VMMs, so that they don't have to define this newtype
+//! themselves. The `EventFdIrqLine` is an [`IrqLine`](../trait.IrqLine.html)
+//! backed by an irqfd registered with a resample eventfd, for the
+//! level-triggered interrupts.
+
+use std::io;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use vmm_sys_util::eventfd::EventFd;
+
+use crate::{IrqLine, Trigger};
+
+impl Trigger for EventFd {
+    type E = io::Error;
+
+    fn trigger(&self) -> io::Result<()> {
+        self.write(1)
+    }
+}
+
+/// A [`Trigger`](../trait.Trigger.html) that writes to an `EventFd`, which it
+/// dereferences to, e.g. for registering it as an irqfd.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::eventfd::EventFdTrigger;
+/// # use vm_superio::{Serial, Trigger};
+/// let intr_evt = EventFdTrigger::new(libc::EFD_NONBLOCK).unwrap();
+/// let mut serial = Serial::new(intr_evt.try_clone().unwrap(), Vec::new());
+///
+/// // Enable the received data available interrupt, and send a byte to the
+/// // guest.
+/// serial.write(1, 0x01).unwrap();
+/// serial.enqueue_raw_bytes(&[b'a']).unwrap();
+/// assert_eq!(intr_evt.read().unwrap(), 1);
+/// ```
+#[derive(Debug)]
+pub struct EventFdTrigger(EventFd);
+
+impl EventFdTrigger {
+    /// Creates a new `EventFdTrigger` instance, with a new `EventFd`.
+    ///
+    /// # Arguments
+    /// * `flag` - The flags of the `EventFd`, e.g. `libc::EFD_NONBLOCK`.
+    pub fn new(flag: i32) -> io::Result<Self> {
+        EventFd::new(flag).map(EventFdTrigger)
+    }
+
+    /// Returns a new `EventFdTrigger` sharing the `EventFd` of this one, so
+    /// that a device and the VMM hold the same interrupt.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        self.0.try_clone().map(EventFdTrigger)
+    }
+
+    /// Returns the `EventFd` of the trigger.
+    pub fn into_inner(self) -> EventFd {
+        self.0
+    }
+}
+
+impl From<EventFd> for EventFdTrigger {
+    fn from(evt: EventFd) -> Self {
+        EventFdTrigger(evt)
+    }
+}
+
+impl Deref for EventFdTrigger {
+    type Target = EventFd;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Trigger for EventFdTrigger {
+    type E = io::Error;
+
+    fn trigger(&self) -> io::Result<()> {
+        self.0.trigger()
+    }
+}
+
+/// An [`IrqLine`](../trait.IrqLine.html) backed by an irqfd with a resample
+/// eventfd.
+///
+/// KVM raises the interrupt when the irqfd is written, and lowers it on the
+/// end of interrupt, signaling the resample eventfd. The line therefore keeps
+/// its level: it writes the irqfd when it is asserted, and again on
+/// [`resample`](../trait.IrqLine.html#method.resample) if it is still
+/// asserted, so that the guest sees the interrupt until the device lowers it.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::eventfd::EventFdIrqLine;
+/// # use vm_superio::IrqLine;
+/// # use vmm_sys_util::eventfd::EventFd;
+/// let line = EventFdIrqLine::new(EventFd::new(libc::EFD_NONBLOCK).unwrap());
+///
+/// line.assert().unwrap();
+/// assert_eq!(line.irqfd().read().unwrap(), 1);
+/// // The guest acknowledged the interrupt, but the device still holds it.
+/// line.resample().unwrap();
+/// assert_eq!(line.irqfd().read().unwrap(), 1);
+/// ```
+#[derive(Debug)]
+pub struct EventFdIrqLine {
+    irqfd: EventFd,
+    asserted: AtomicBool,
+}
+
+impl EventFdIrqLine {
+    /// Creates a new deasserted `EventFdIrqLine` instance.
+    ///
+    /// # Arguments
+    /// * `irqfd` - The `EventFd` registered as an irqfd, along with a
+    ///   resample eventfd that the VMM watches.
+    pub const fn new(irqfd: EventFd) -> Self {
+        EventFdIrqLine {
+            irqfd,
+            asserted: AtomicBool::new(false),
+        }
+    }
+
+    /// Provides a reference to the irqfd.
+    pub fn irqfd(&self) -> &EventFd {
+        &self.irqfd
+    }
+
+    /// Returns whether the line is asserted.
+    pub fn is_asserted(&self) -> bool {
+        self.asserted.load(Ordering::SeqCst)
+    }
+}
+
+impl IrqLine for EventFdIrqLine {
+    type E = io::Error;
+
+    fn assert(&self) -> io::Result<()> {
+        // An asserted line already raised the interrupt, so only the rising
+        // edge writes the irqfd.
+        if !self.asserted.swap(true, Ordering::SeqCst) {
+            self.irqfd.write(1)?;
+        }
+        Ok(())
+    }
+
+    fn deassert(&self) -> io::Result<()> {
+        // KVM lowers the interrupt on the end of interrupt, which is then not
+        // raised again by `resample`.
+        self.asserted.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn resample(&self) -> io::Result<()> {
+        if self.is_asserted() {
+            self.irqfd.write(1)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eventfd_trigger() {
+        let evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        evt.trigger().unwrap();
+        evt.trigger().unwrap();
+        assert_eq!(evt.read().unwrap(), 2);
+
+        let trigger = EventFdTrigger::from(evt);
+        let clone = trigger.try_clone().unwrap();
+        clone.trigger().unwrap();
+        assert_eq!(trigger.read().unwrap(), 1);
+
+        let evt = trigger.into_inner();
+        assert_eq!(evt.read().unwrap_err().kind(), io::ErrorKind::WouldBlock);
+        assert!(EventFdTrigger::new(libc::EFD_NONBLOCK).is_ok());
+    }
+
+    #[test]
+    fn test_eventfd_irq_line() {
+        let line = EventFdIrqLine::new(EventFd::new(libc::EFD_NONBLOCK).unwrap());
+        assert!(!line.is_asserted());
+
+        // Only the rising edge raises the interrupt.
+        line.assert().unwrap();
+        line.set_level(true).unwrap();
+        assert!(line.is_asserted());
+        assert_eq!(line.irqfd().read().unwrap(), 1);
+
+        // The interrupt is raised again on resample while the line is high.
+        line.resample().unwrap();
+        assert_eq!(line.irqfd().read().unwrap(), 1);
+
+        line.set_level(false).unwrap();
+        assert!(!line.is_asserted());
+        line.resample().unwrap();
+        assert_eq!(
+            line.irqfd().read().unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+
+        line.deassert().unwrap();
+        line.assert().unwrap();
+        assert_eq!(line.irqfd().read().unwrap(), 1);
+    }
+}
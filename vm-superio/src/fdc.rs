@@ -0,0 +1,551 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides a stub of the i82077 floppy disk controller (ports 0x3F0-0x3F7).
+//!
+//! Guests, and their firmware, probe for the floppy controller at boot. This
+//! stub answers the probing and the reset sequence like a controller without
+//! any drive attached, so that the guests give up on the floppy drives
+//! quickly, instead of hanging or retrying.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::result::Result;
+
+use crate::{byte_offset, LegacyDevice, Trigger};
+
+// Register offsets.
+// Digital Output Register.
+const DOR_OFFSET: u8 = 2;
+// Tape Drive Register.
+const TDR_OFFSET: u8 = 3;
+// Main Status Register (read), Data rate Select Register (write).
+const MSR_DSR_OFFSET: u8 = 4;
+// The command, parameter and result bytes.
+const FIFO_OFFSET: u8 = 5;
+// Digital Input Register (read), Configuration Control Register (write).
+const DIR_CCR_OFFSET: u8 = 7;
+
+// DOR bits.
+// Resets the controller when cleared (active low).
+const DOR_NRESET_BIT: u8 = 0b0000_0100;
+// Enables the interrupt and the DMA requests.
+const DOR_IRQ_BIT: u8 = 0b0000_1000;
+// The value of DOR out of reset.
+const DOR_DEFAULT: u8 = DOR_NRESET_BIT | DOR_IRQ_BIT;
+// The bits of TDR.
+const TDR_MASK: u8 = 0b0000_0011;
+// DSR bits.
+// Resets the controller, and clears itself.
+const DSR_RESET_BIT: u8 = 0b1000_0000;
+
+// MSR bits.
+// The command is still being executed, or its result being read.
+const MSR_CB_BIT: u8 = 0b0001_0000;
+// The data register holds a result byte.
+const MSR_DIO_BIT: u8 = 0b0100_0000;
+// The data register is ready for the next transfer.
+const MSR_RQM_BIT: u8 = 0b1000_0000;
+// DIR bits.
+// The disk was changed, which is also what a missing disk reports.
+const DIR_DSKCHG_BIT: u8 = 0b1000_0000;
+
+// Commands, in the low 5 bits of the command byte.
+const COMMAND_MASK: u8 = 0b0001_1111;
+const CMD_READ_TRACK: u8 = 0x02;
+const CMD_SPECIFY: u8 = 0x03;
+const CMD_SENSE_DRIVE_STATUS: u8 = 0x04;
+const CMD_WRITE_DATA: u8 = 0x05;
+const CMD_READ_DATA: u8 = 0x06;
+const CMD_RECALIBRATE: u8 = 0x07;
+const CMD_SENSE_INTERRUPT: u8 = 0x08;
+const CMD_WRITE_DELETED_DATA: u8 = 0x09;
+const CMD_READ_ID: u8 = 0x0A;
+const CMD_READ_DELETED_DATA: u8 = 0x0C;
+const CMD_FORMAT_TRACK: u8 = 0x0D;
+const CMD_DUMPREG: u8 = 0x0E;
+const CMD_SEEK: u8 = 0x0F;
+const CMD_VERSION: u8 = 0x10;
+const CMD_PERPENDICULAR_MODE: u8 = 0x12;
+const CMD_CONFIGURE: u8 = 0x13;
+const CMD_LOCK: u8 = 0x14;
+const CMD_VERIFY: u8 = 0x16;
+// The LOCK command sets the lock when this bit is set, and clears it
+// otherwise.
+const LOCK_BIT: u8 = 0b1000_0000;
+
+// Status register 0 bits.
+// Selects the drive, along with the head.
+const ST0_DRIVE_HEAD_MASK: u8 = 0b0000_0111;
+const ST0_DRIVE_MASK: u8 = 0b0000_0011;
+// The drive isn't ready.
+const ST0_NOT_READY_BIT: u8 = 0b0000_1000;
+// The drive didn't signal its track 0.
+const ST0_EQUIPMENT_CHECK_BIT: u8 = 0b0001_0000;
+// The seek, or the recalibration, completed.
+const ST0_SEEK_END_BIT: u8 = 0b0010_0000;
+// Interrupt codes.
+const ST0_ABNORMAL_TERMINATION: u8 = 0b0100_0000;
+const ST0_INVALID_COMMAND: u8 = 0b1000_0000;
+const ST0_READY_CHANGED: u8 = 0b1100_0000;
+// Status register 1 bits.
+// The controller didn't find the address mark of the sector.
+const ST1_MISSING_ADDRESS_MARK_BIT: u8 = 0b0000_0001;
+
+// The result of the VERSION command, for an 82077.
+const VERSION_82077: u8 = 0x90;
+// Number of the drives, each of which reports a ready change after a reset.
+const DRIVE_COUNT: u8 = 4;
+
+/// A stub of the i82077 floppy disk controller, usually found at ports
+/// 0x3F0-0x3F7, with no drive attached.
+///
+/// The stub implements the command and result phases of the controller, and
+/// answers the commands the way a controller without any drive, and thus
+/// without any media, would. The VERSION and the configuration commands
+/// complete normally, the seeks fail with an equipment check, and the data
+/// transfers fail because the drive isn't ready. A
+/// [`Trigger`](../trait.Trigger.html) object is used for raising the
+/// interrupt of the controller, i.e. IRQ 6.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::fdc::Fdc;
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut fdc = Fdc::new(DummyTrigger);
+///
+/// // The guest asks for the version of the controller.
+/// assert_eq!(fdc.read(4), 0x80);
+/// fdc.write(5, 0x10).unwrap();
+/// assert_eq!(fdc.read(4), 0xD0);
+/// assert_eq!(fdc.read(5), 0x90);
+/// assert_eq!(fdc.read(4), 0x80);
+/// ```
+#[derive(Debug)]
+pub struct Fdc<T: Trigger> {
+    // Interrupt of the controller.
+    interrupt_evt: T,
+    dor: u8,
+    tdr: u8,
+    // The command being written, along with its parameters.
+    command: Vec<u8>,
+    // The result bytes of the last command, not yet read by the guest.
+    result: VecDeque<u8>,
+    // The ST0 values reported by the next SENSE INTERRUPT STATUS commands.
+    interrupt_status: VecDeque<u8>,
+    // The parameters of the SPECIFY and CONFIGURE commands, and the lock set
+    // by the LOCK command, which are reported by DUMPREG.
+    specify: [u8; 2],
+    configure: [u8; 2],
+    lock: bool,
+}
+
+impl<T: Trigger> Fdc<T> {
+    /// Creates a new `Fdc` instance, which uses `interrupt_evt` for raising
+    /// the interrupt of the controller.
+    ///
+    /// # Arguments
+    /// * `interrupt_evt` - A Trigger object that will be used to raise the
+    ///   interrupt of the controller.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Fdc`](struct.Fdc.html#example).
+    pub const fn new(interrupt_evt: T) -> Self {
+        Fdc {
+            interrupt_evt,
+            dor: DOR_DEFAULT,
+            tdr: 0,
+            command: Vec::new(),
+            result: VecDeque::new(),
+            interrupt_status: VecDeque::new(),
+            specify: [0; 2],
+            configure: [0; 2],
+            lock: false,
+        }
+    }
+
+    /// Provides a reference to the interrupt event object.
+    pub fn interrupt_evt(&self) -> &T {
+        &self.interrupt_evt
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Returns the value of the register, or 0 for the invalid offsets and
+    /// the registers missing from the AT mode of the controller.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&mut self, offset: u8) -> u8 {
+        match offset {
+            DOR_OFFSET => self.dor,
+            TDR_OFFSET => self.tdr,
+            MSR_DSR_OFFSET => self.msr(),
+            FIFO_OFFSET => self.result.pop_front().unwrap_or(0x00),
+            DIR_CCR_OFFSET => DIR_DSKCHG_BIT,
+            _ => {
+                guest_warn!("Invalid FDC read at offset {:#x}", offset);
+                0x00
+            }
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Writing the data register sends the bytes of a command, which is
+    /// executed once all its parameters are written. Clearing the reset bit
+    /// of DOR, or setting the one of DSR, resets the controller.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) -> Result<(), T::E> {
+        match offset {
+            DOR_OFFSET => {
+                let out_of_reset = (value & !self.dor & DOR_NRESET_BIT) != 0;
+                self.dor = value;
+                if (value & DOR_NRESET_BIT) == 0 {
+                    self.reset_controller();
+                } else if out_of_reset {
+                    return self.complete_reset();
+                }
+            }
+            TDR_OFFSET => self.tdr = value & TDR_MASK,
+            MSR_DSR_OFFSET if (value & DSR_RESET_BIT) != 0 => {
+                self.reset_controller();
+                return self.complete_reset();
+            }
+            FIFO_OFFSET if self.in_reset() || !self.result.is_empty() => (),
+            FIFO_OFFSET => {
+                self.command.push(value);
+                if self.command.len() == command_len(self.command[0]) {
+                    return self.execute();
+                }
+            }
+            // The data rate selection is not emulated.
+            MSR_DSR_OFFSET | DIR_CCR_OFFSET => (),
+            _ => guest_warn!("Invalid FDC write at offset {:#x}", offset),
+        }
+        Ok(())
+    }
+
+    fn in_reset(&self) -> bool {
+        (self.dor & DOR_NRESET_BIT) == 0
+    }
+
+    fn msr(&self) -> u8 {
+        if self.in_reset() {
+            0x00
+        } else if !self.result.is_empty() {
+            MSR_RQM_BIT | MSR_DIO_BIT | MSR_CB_BIT
+        } else if !self.command.is_empty() {
+            MSR_RQM_BIT | MSR_CB_BIT
+        } else {
+            MSR_RQM_BIT
+        }
+    }
+
+    // Aborts the current command, and holds the controller in reset.
+    fn reset_controller(&mut self) {
+        self.command.clear();
+        self.result.clear();
+        self.interrupt_status.clear();
+    }
+
+    // Brings the controller out of reset, which makes every drive report a
+    // ready change.
+    fn complete_reset(&mut self) -> Result<(), T::E> {
+        for drive in 0..DRIVE_COUNT {
+            self.interrupt_status.push_back(ST0_READY_CHANGED | drive);
+        }
+        self.raise_interrupt()
+    }
+
+    fn raise_interrupt(&self) -> Result<(), T::E> {
+        if (self.dor & DOR_IRQ_BIT) != 0 {
+            self.interrupt_evt.trigger()?;
+        }
+        Ok(())
+    }
+
+    // Executes the command held by `self.command`.
+    fn execute(&mut self) -> Result<(), T::E> {
+        let command = core::mem::take(&mut self.command);
+        let drive_head = command.get(1).map_or(0, |p| p & ST0_DRIVE_HEAD_MASK);
+
+        match command[0] & COMMAND_MASK {
+            CMD_SPECIFY => self.specify.copy_from_slice(&command[1..3]),
+            CMD_CONFIGURE => self.configure.copy_from_slice(&command[2..4]),
+            CMD_PERPENDICULAR_MODE => (),
+            CMD_VERSION => self.result.push_back(VERSION_82077),
+            CMD_LOCK => {
+                self.lock = (command[0] & LOCK_BIT) != 0;
+                self.result.push_back(u8::from(self.lock) << 4);
+            }
+            CMD_DUMPREG => {
+                // The cylinders of the four drives, which are all 0.
+                self.result.extend([0; 4].iter());
+                self.result.extend(self.specify.iter());
+                self.result.push_back(0);
+                self.result.push_back(u8::from(self.lock) << 7);
+                self.result.extend(self.configure.iter());
+            }
+            // No drive reports the ready and track 0 lines.
+            CMD_SENSE_DRIVE_STATUS => self.result.push_back(drive_head),
+            CMD_RECALIBRATE | CMD_SEEK => {
+                let drive = drive_head & ST0_DRIVE_MASK;
+                self.interrupt_status.push_back(
+                    ST0_ABNORMAL_TERMINATION | ST0_SEEK_END_BIT | ST0_EQUIPMENT_CHECK_BIT | drive,
+                );
+                return self.raise_interrupt();
+            }
+            CMD_SENSE_INTERRUPT => match self.interrupt_status.pop_front() {
+                // The present cylinder number follows the status.
+                Some(st0) => self.result.extend([st0, 0].iter()),
+                None => self.result.push_back(ST0_INVALID_COMMAND),
+            },
+            CMD_READ_TRACK
+            | CMD_WRITE_DATA
+            | CMD_READ_DATA
+            | CMD_WRITE_DELETED_DATA
+            | CMD_READ_ID
+            | CMD_READ_DELETED_DATA
+            | CMD_FORMAT_TRACK
+            | CMD_VERIFY => {
+                // ST0, ST1 and ST2, followed by the cylinder, head, sector
+                // and size of the last sector, which are all 0.
+                self.result.extend(
+                    [
+                        ST0_ABNORMAL_TERMINATION | ST0_NOT_READY_BIT | drive_head,
+                        ST1_MISSING_ADDRESS_MARK_BIT,
+                        0,
+                        0,
+                        0,
+                        0,
+                        0,
+                    ]
+                    .iter(),
+                );
+                return self.raise_interrupt();
+            }
+            _ => {
+                guest_warn!("Unsupported FDC command {:#x}", command[0]);
+                self.result.push_back(ST0_INVALID_COMMAND)
+            }
+        }
+        Ok(())
+    }
+}
+
+// Returns the length of the command starting with `command`, including its
+// parameters. The invalid commands are a single byte long.
+fn command_len(command: u8) -> usize {
+    match command & COMMAND_MASK {
+        CMD_READ_TRACK
+        | CMD_WRITE_DATA
+        | CMD_READ_DATA
+        | CMD_WRITE_DELETED_DATA
+        | CMD_READ_DELETED_DATA
+        | CMD_VERIFY => 9,
+        CMD_FORMAT_TRACK => 6,
+        CMD_CONFIGURE => 4,
+        CMD_SPECIFY | CMD_SEEK => 3,
+        CMD_SENSE_DRIVE_STATUS | CMD_RECALIBRATE | CMD_READ_ID | CMD_PERPENDICULAR_MODE => 2,
+        _ => 1,
+    }
+}
+
+impl<T: Trigger> LegacyDevice for Fdc<T> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = Fdc::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            let _ = Fdc::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.reset_controller();
+        self.dor = DOR_DEFAULT;
+        self.tdr = 0;
+        self.specify = [0; 2];
+        self.configure = [0; 2];
+        self.lock = false;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vmm_sys_util::eventfd::EventFd;
+
+    fn command(fdc: &mut Fdc<EventFd>, bytes: &[u8]) -> Vec<u8> {
+        for (i, &byte) in bytes.iter().enumerate() {
+            assert_eq!(fdc.msr() & MSR_DIO_BIT, 0);
+            fdc.write(FIFO_OFFSET, byte).unwrap();
+            if i + 1 < bytes.len() {
+                assert_eq!(fdc.read(MSR_DSR_OFFSET), MSR_RQM_BIT | MSR_CB_BIT);
+            }
+        }
+        let mut result = Vec::new();
+        while (fdc.read(MSR_DSR_OFFSET) & MSR_DIO_BIT) != 0 {
+            result.push(fdc.read(FIFO_OFFSET));
+        }
+        assert_eq!(fdc.read(MSR_DSR_OFFSET), MSR_RQM_BIT);
+        result
+    }
+
+    #[test]
+    fn test_fdc_reset() {
+        let mut fdc = Fdc::new(EventFd::new(libc::EFD_NONBLOCK).unwrap());
+        assert_eq!(fdc.read(DOR_OFFSET), DOR_DEFAULT);
+        assert_eq!(fdc.read(MSR_DSR_OFFSET), MSR_RQM_BIT);
+
+        // The controller doesn't accept commands while in reset.
+        fdc.write(DOR_OFFSET, 0).unwrap();
+        assert_eq!(fdc.read(MSR_DSR_OFFSET), 0);
+        fdc.write(FIFO_OFFSET, CMD_VERSION).unwrap();
+        assert!(fdc.interrupt_evt().read().is_err());
+
+        // Leaving the reset raises the interrupt, and every drive reports a
+        // ready change.
+        fdc.write(DOR_OFFSET, DOR_DEFAULT).unwrap();
+        assert_eq!(fdc.interrupt_evt().read().unwrap(), 1);
+        for drive in 0..DRIVE_COUNT {
+            assert_eq!(
+                command(&mut fdc, &[CMD_SENSE_INTERRUPT]),
+                [ST0_READY_CHANGED | drive, 0]
+            );
+        }
+        assert_eq!(
+            command(&mut fdc, &[CMD_SENSE_INTERRUPT]),
+            [ST0_INVALID_COMMAND]
+        );
+
+        // So does the reset through DSR, but the interrupt may be disabled.
+        fdc.write(DOR_OFFSET, DOR_NRESET_BIT).unwrap();
+        fdc.write(MSR_DSR_OFFSET, DSR_RESET_BIT).unwrap();
+        assert!(fdc.interrupt_evt().read().is_err());
+        assert_eq!(
+            command(&mut fdc, &[CMD_SENSE_INTERRUPT]),
+            [ST0_READY_CHANGED, 0]
+        );
+
+        // The reset aborts the current command.
+        fdc.write(FIFO_OFFSET, CMD_SPECIFY).unwrap();
+        fdc.write(DOR_OFFSET, 0).unwrap();
+        fdc.write(DOR_OFFSET, DOR_NRESET_BIT).unwrap();
+        assert_eq!(command(&mut fdc, &[CMD_VERSION]), [VERSION_82077]);
+    }
+
+    #[test]
+    fn test_fdc_probe() {
+        let mut fdc = Fdc::new(EventFd::new(libc::EFD_NONBLOCK).unwrap());
+
+        // The sequence Linux uses for identifying the controller.
+        assert_eq!(command(&mut fdc, &[CMD_VERSION]), [VERSION_82077]);
+        command(&mut fdc, &[CMD_SPECIFY, 0xDF, 0x02]);
+        command(&mut fdc, &[CMD_CONFIGURE, 0, 0x2A, 0]);
+        command(&mut fdc, &[CMD_PERPENDICULAR_MODE, 0]);
+        assert_eq!(command(&mut fdc, &[CMD_LOCK | LOCK_BIT]), [0x10]);
+        assert_eq!(
+            command(&mut fdc, &[CMD_DUMPREG]),
+            [0, 0, 0, 0, 0xDF, 0x02, 0, 0x80, 0x2A, 0]
+        );
+        assert_eq!(command(&mut fdc, &[CMD_LOCK]), [0x00]);
+        // PARTID isn't supported by the 82077.
+        assert_eq!(command(&mut fdc, &[0x18]), [ST0_INVALID_COMMAND]);
+        assert!(fdc.interrupt_evt().read().is_err());
+
+        // The registers without a function read back as written.
+        fdc.write(TDR_OFFSET, 0xFF).unwrap();
+        assert_eq!(fdc.read(TDR_OFFSET), TDR_MASK);
+        fdc.write(DOR_OFFSET, DOR_DEFAULT | 0x11).unwrap();
+        assert_eq!(fdc.read(DOR_OFFSET), DOR_DEFAULT | 0x11);
+        fdc.write(DIR_CCR_OFFSET, 0x00).unwrap();
+        assert_eq!(fdc.read(DIR_CCR_OFFSET), DIR_DSKCHG_BIT);
+        assert_eq!(fdc.read(0), 0x00);
+    }
+
+    #[test]
+    fn test_fdc_no_drive() {
+        let mut fdc = Fdc::new(EventFd::new(libc::EFD_NONBLOCK).unwrap());
+
+        // The drive doesn't report its track 0.
+        assert_eq!(command(&mut fdc, &[CMD_SENSE_DRIVE_STATUS, 0x05]), [0x05]);
+        command(&mut fdc, &[CMD_RECALIBRATE, 0x01]);
+        assert_eq!(fdc.interrupt_evt().read().unwrap(), 1);
+        command(&mut fdc, &[CMD_SEEK, 0x02, 10]);
+        assert_eq!(fdc.interrupt_evt().read().unwrap(), 1);
+        let st0 = ST0_ABNORMAL_TERMINATION | ST0_SEEK_END_BIT | ST0_EQUIPMENT_CHECK_BIT;
+        assert_eq!(command(&mut fdc, &[CMD_SENSE_INTERRUPT]), [st0 | 0x01, 0]);
+        assert_eq!(command(&mut fdc, &[CMD_SENSE_INTERRUPT]), [st0 | 0x02, 0]);
+
+        // The data transfers fail, since the drive isn't ready.
+        let read = [0xE6, 0x04, 0, 0, 1, 2, 18, 0x1B, 0xFF];
+        assert_eq!(
+            command(&mut fdc, &read),
+            [
+                ST0_ABNORMAL_TERMINATION | ST0_NOT_READY_BIT | 0x04,
+                ST1_MISSING_ADDRESS_MARK_BIT,
+                0,
+                0,
+                0,
+                0,
+                0
+            ]
+        );
+        assert_eq!(fdc.interrupt_evt().read().unwrap(), 1);
+        assert_eq!(command(&mut fdc, &[0x4A, 0x00]).len(), 7);
+        assert_eq!(command(&mut fdc, &[0x4D, 0, 2, 18, 0x54, 0xF6]).len(), 7);
+
+        // The writes to the data register are ignored during the result
+        // phase.
+        fdc.write(FIFO_OFFSET, CMD_VERSION).unwrap();
+        fdc.write(FIFO_OFFSET, CMD_VERSION).unwrap();
+        assert_eq!(fdc.read(FIFO_OFFSET), VERSION_82077);
+        assert_eq!(fdc.read(MSR_DSR_OFFSET), MSR_RQM_BIT);
+        assert_eq!(fdc.read(FIFO_OFFSET), 0x00);
+    }
+
+    #[test]
+    fn test_fdc_legacy_device() {
+        let mut fdc = Fdc::new(EventFd::new(libc::EFD_NONBLOCK).unwrap());
+
+        LegacyDevice::write(&mut fdc, u16::from(TDR_OFFSET), &[0xFF]);
+        LegacyDevice::write(&mut fdc, u16::from(FIFO_OFFSET), &[CMD_SPECIFY]);
+        let mut data = [0; 1];
+        LegacyDevice::read(&mut fdc, u16::from(TDR_OFFSET), &mut data);
+        assert_eq!(data, [TDR_MASK]);
+
+        // The reset aborts the current command, without raising the
+        // interrupt.
+        fdc.reset();
+        assert_eq!(fdc.read(TDR_OFFSET), 0);
+        assert_eq!(fdc.read(DOR_OFFSET), DOR_DEFAULT);
+        assert_eq!(command(&mut fdc, &[CMD_VERSION]), [VERSION_82077]);
+        assert!(fdc.interrupt_evt().read().is_err());
+    }
+}
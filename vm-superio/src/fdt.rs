@@ -0,0 +1,325 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides helpers generating the device tree nodes of the MMIO devices.
+//!
+//! The nodes are returned as [`FdtNode`](struct.FdtNode.html) objects, whose
+//! properties the VMM adds to the device tree with the FDT writer of its
+//! choice, through [`FdtValue::to_bytes`](enum.FdtValue.html#method.to_bytes).
+//! The `reg` properties use 2 address cells and 2 size cells, like the
+//! aarch64 and riscv64 virt machines do for their root node.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::syscon::SysconMagic;
+
+// The size of the register region of the PrimeCell devices.
+const PRIMECELL_SIZE: u64 = 0x1000;
+
+// Number of registers of the UART.
+const NS16550_NUM_REGS: u64 = 8;
+// The frequency of the clock of the UART, which the guest drivers use for
+// computing the divisors.
+const NS16550_CLOCK_HZ: u32 = 3_686_400;
+
+// Encoding of the interrupt specifiers of the Arm GIC.
+const GIC_SPI: u32 = 0;
+const IRQ_TYPE_LEVEL_HIGH: u32 = 4;
+
+/// The value of a device tree property.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FdtValue {
+    /// A property without value.
+    Empty,
+    /// A list of 32-bit cells.
+    Cells(Vec<u32>),
+    /// A list of strings.
+    Strings(Vec<String>),
+}
+
+impl FdtValue {
+    /// Returns the value encoded like in a flattened device tree, i.e. the
+    /// big endian cells, or the NUL terminated strings.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            FdtValue::Empty => Vec::new(),
+            FdtValue::Cells(cells) => cells.iter().flat_map(|cell| cell.to_be_bytes()).collect(),
+            FdtValue::Strings(strings) => strings
+                .iter()
+                .flat_map(|string| string.bytes().chain(Some(0)))
+                .collect(),
+        }
+    }
+}
+
+/// A device tree node, without children.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FdtNode {
+    /// The name of the node, including its unit address.
+    pub name: String,
+    /// The properties of the node, in their order.
+    pub properties: Vec<(String, FdtValue)>,
+}
+
+impl FdtNode {
+    fn new(name: String) -> Self {
+        FdtNode {
+            name,
+            properties: Vec::new(),
+        }
+    }
+
+    fn with_cells(mut self, name: &str, cells: Vec<u32>) -> Self {
+        self.properties
+            .push((name.to_string(), FdtValue::Cells(cells)));
+        self
+    }
+
+    fn with_strings(mut self, name: &str, strings: &[&str]) -> Self {
+        let strings = strings.iter().map(|string| string.to_string()).collect();
+        self.properties
+            .push((name.to_string(), FdtValue::Strings(strings)));
+        self
+    }
+
+    fn with_reg(self, base: u64, size: u64) -> Self {
+        let mut cells = split_u64(base);
+        cells.extend(split_u64(size));
+        self.with_cells("reg", cells)
+    }
+
+    /// Returns the value of the property called `name`, if any.
+    pub fn property(&self, name: &str) -> Option<&FdtValue> {
+        self.properties
+            .iter()
+            .find(|(property, _)| property == name)
+            .map(|(_, value)| value)
+    }
+}
+
+// Returns the two cells holding `value`.
+fn split_u64(value: u64) -> Vec<u32> {
+    vec![(value >> 32) as u32, value as u32]
+}
+
+/// The interrupt of a device, as encoded in its `interrupts` property.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FdtInterrupt {
+    /// A level triggered Shared Peripheral Interrupt of an Arm GIC, whose
+    /// node has 3 interrupt cells. The number is the one of the SPI, i.e.
+    /// the interrupt ID minus 32.
+    GicSpi(u32),
+    /// An interrupt of a controller whose node has a single interrupt cell,
+    /// such as the RISC-V PLIC.
+    Single(u32),
+}
+
+impl FdtInterrupt {
+    fn cells(self) -> Vec<u32> {
+        match self {
+            FdtInterrupt::GicSpi(spi) => vec![GIC_SPI, spi, IRQ_TYPE_LEVEL_HIGH],
+            FdtInterrupt::Single(irq) => vec![irq],
+        }
+    }
+}
+
+/// Returns the node of a [`Rtc`](../rtc_pl031/struct.Rtc.html) at `base`.
+///
+/// # Arguments
+/// * `base` - The base address of the registers of the device.
+/// * `irq` - The interrupt of the device.
+/// * `clock_phandle` - The phandle of the clock node used as the APB clock,
+///   which the Linux driver of the PrimeCell devices requires.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::fdt::{pl031_node, FdtInterrupt, FdtValue};
+/// let node = pl031_node(0x0901_0000, FdtInterrupt::GicSpi(2), 0x8000);
+/// assert_eq!(node.name, "rtc@9010000");
+/// assert_eq!(
+///     node.property("interrupts"),
+///     Some(&FdtValue::Cells(vec![0, 2, 4]))
+/// );
+/// ```
+pub fn pl031_node(base: u64, irq: FdtInterrupt, clock_phandle: u32) -> FdtNode {
+    FdtNode::new(format!("rtc@{:x}", base))
+        .with_strings("compatible", &["arm,pl031", "arm,primecell"])
+        .with_reg(base, PRIMECELL_SIZE)
+        .with_cells("interrupts", irq.cells())
+        .with_cells("clocks", vec![clock_phandle])
+        .with_strings("clock-names", &["apb_pclk"])
+}
+
+/// Returns the node of a [`SerialMmio`](../serial/struct.SerialMmio.html)
+/// UART at `base`.
+///
+/// # Arguments
+/// * `base` - The base address of the registers of the device.
+/// * `irq` - The interrupt of the device.
+/// * `reg_shift` - The distance between the registers, as given to the
+///   `SerialMmio`.
+/// * `reg_io_width` - The size of the accesses to the registers, as given
+///   to the `SerialMmio`.
+pub fn ns16550_node(base: u64, irq: FdtInterrupt, reg_shift: u32, reg_io_width: u32) -> FdtNode {
+    FdtNode::new(format!("serial@{:x}", base))
+        .with_strings("compatible", &["ns16550a"])
+        .with_reg(base, NS16550_NUM_REGS << reg_shift)
+        .with_cells("interrupts", irq.cells())
+        .with_cells("clock-frequency", vec![NS16550_CLOCK_HZ])
+        .with_cells("reg-shift", vec![reg_shift])
+        .with_cells("reg-io-width", vec![reg_io_width])
+}
+
+/// Returns the node of a [`Syscon`](../syscon/struct.Syscon.html) register
+/// block at `base`, which the `syscon-poweroff` and `syscon-reboot` nodes
+/// reference through its `phandle`.
+///
+/// # Arguments
+/// * `base` - The base address of the registers of the device.
+/// * `size` - The size of the register block.
+/// * `phandle` - The phandle of the node.
+pub fn syscon_node(base: u64, size: u64, phandle: u32) -> FdtNode {
+    FdtNode::new(format!("syscon@{:x}", base))
+        .with_strings("compatible", &["syscon"])
+        .with_reg(base, size)
+        .with_cells("phandle", vec![phandle])
+}
+
+// Returns the node of a syscon action, omitting the mask when it covers the
+// whole register, which is the default.
+fn syscon_action_node(name: &str, syscon_phandle: u32, magic: SysconMagic) -> FdtNode {
+    let node = FdtNode::new(name.to_string())
+        .with_strings("compatible", &[&format!("syscon-{}", name)])
+        .with_cells("regmap", vec![syscon_phandle])
+        .with_cells("offset", vec![u32::from(magic.offset)])
+        .with_cells("value", vec![magic.value]);
+    if magic.mask == 0xFFFF_FFFF {
+        node
+    } else {
+        node.with_cells("mask", vec![magic.mask])
+    }
+}
+
+/// Returns the `syscon-poweroff` node, for the power off action of a
+/// [`Syscon`](../syscon/struct.Syscon.html).
+///
+/// # Arguments
+/// * `syscon_phandle` - The phandle of the node returned by
+///   [`syscon_node`](fn.syscon_node.html).
+/// * `magic` - The register write requesting the power off.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::fdt::{syscon_node, syscon_poweroff_node, FdtValue};
+/// # use vm_superio::syscon::SysconMagic;
+/// let syscon = syscon_node(0x10_0000, 0x1000, 1);
+/// let poweroff = syscon_poweroff_node(1, SysconMagic::new(0, 0x5555));
+/// assert_eq!(
+///     poweroff.property("value").unwrap().to_bytes(),
+///     vec![0, 0, 0x55, 0x55]
+/// );
+/// ```
+pub fn syscon_poweroff_node(syscon_phandle: u32, magic: SysconMagic) -> FdtNode {
+    syscon_action_node("poweroff", syscon_phandle, magic)
+}
+
+/// Returns the `syscon-reboot` node, for the reboot action of a
+/// [`Syscon`](../syscon/struct.Syscon.html).
+///
+/// # Arguments
+/// * `syscon_phandle` - The phandle of the node returned by
+///   [`syscon_node`](fn.syscon_node.html).
+/// * `magic` - The register write requesting the reboot.
+pub fn syscon_reboot_node(syscon_phandle: u32, magic: SysconMagic) -> FdtNode {
+    syscon_action_node("reboot", syscon_phandle, magic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> FdtValue {
+        FdtValue::Strings(values.iter().map(|value| value.to_string()).collect())
+    }
+
+    #[test]
+    fn test_fdt_value_bytes() {
+        assert!(FdtValue::Empty.to_bytes().is_empty());
+        assert_eq!(
+            FdtValue::Cells(vec![1, 0x0203_0405]).to_bytes(),
+            vec![0, 0, 0, 1, 2, 3, 4, 5]
+        );
+        assert_eq!(strings(&["ab", "c"]).to_bytes(), b"ab\0c\0".to_vec());
+    }
+
+    #[test]
+    fn test_fdt_pl031_node() {
+        let node = pl031_node(0x1_0901_0000, FdtInterrupt::Single(5), 3);
+        assert_eq!(node.name, "rtc@109010000");
+        assert_eq!(
+            node.property("compatible"),
+            Some(&strings(&["arm,pl031", "arm,primecell"]))
+        );
+        assert_eq!(
+            node.property("reg"),
+            Some(&FdtValue::Cells(vec![1, 0x0901_0000, 0, 0x1000]))
+        );
+        assert_eq!(node.property("interrupts"), Some(&FdtValue::Cells(vec![5])));
+        assert_eq!(node.property("clocks"), Some(&FdtValue::Cells(vec![3])));
+        assert_eq!(node.property("clock-names"), Some(&strings(&["apb_pclk"])));
+        assert_eq!(node.property("status"), None);
+    }
+
+    #[test]
+    fn test_fdt_ns16550_node() {
+        let node = ns16550_node(0x1000_0000, FdtInterrupt::Single(10), 2, 4);
+        assert_eq!(node.name, "serial@10000000");
+        assert_eq!(node.property("compatible"), Some(&strings(&["ns16550a"])));
+        assert_eq!(
+            node.property("reg"),
+            Some(&FdtValue::Cells(vec![0, 0x1000_0000, 0, 0x20]))
+        );
+        assert_eq!(node.property("reg-shift"), Some(&FdtValue::Cells(vec![2])));
+        assert_eq!(
+            node.property("reg-io-width"),
+            Some(&FdtValue::Cells(vec![4]))
+        );
+        assert_eq!(
+            node.property("clock-frequency"),
+            Some(&FdtValue::Cells(vec![NS16550_CLOCK_HZ]))
+        );
+    }
+
+    #[test]
+    fn test_fdt_syscon_nodes() {
+        let node = syscon_node(0x10_0000, 0x1000, 7);
+        assert_eq!(node.name, "syscon@100000");
+        assert_eq!(node.property("phandle"), Some(&FdtValue::Cells(vec![7])));
+
+        let node = syscon_reboot_node(7, SysconMagic::new(4, 0x7777));
+        assert_eq!(node.name, "reboot");
+        assert_eq!(
+            node.property("compatible"),
+            Some(&strings(&["syscon-reboot"]))
+        );
+        assert_eq!(node.property("regmap"), Some(&FdtValue::Cells(vec![7])));
+        assert_eq!(node.property("offset"), Some(&FdtValue::Cells(vec![4])));
+        assert_eq!(node.property("mask"), None);
+
+        let magic = SysconMagic {
+            offset: 0,
+            value: 0x10,
+            mask: 0xF0,
+        };
+        let node = syscon_poweroff_node(7, magic);
+        assert_eq!(
+            node.property("compatible"),
+            Some(&strings(&["syscon-poweroff"]))
+        );
+        assert_eq!(node.property("mask"), Some(&FdtValue::Cells(vec![0xF0])));
+    }
+}
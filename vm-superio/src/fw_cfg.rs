@@ -0,0 +1,1306 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the QEMU firmware configuration (fw_cfg) device,
+//! in its port I/O (ports 0x510-0x51B) and MMIO flavors.
+//!
+//! Firmware such as SeaBIOS, OVMF or the cloud-hypervisor flavor of edk2
+//! reads its configuration, e.g. the number of CPUs or the kernel to boot,
+//! from this device. The guest writes the key of an item to the selector
+//! register, and then reads the item, one byte after the other, from the data
+//! register. Modern firmware rather uses the DMA interface, which transfers
+//! whole items to the guest memory at once.
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
+
+use crate::LegacyDevice;
+
+// Port I/O register offsets.
+// The selector register, 16 bits wide and little endian.
+const PIO_SELECTOR_OFFSET: u8 = 0;
+// The data register, 8 bits wide.
+const PIO_DATA_OFFSET: u8 = 1;
+// The DMA address register.
+const PIO_DMA_OFFSET: u8 = 4;
+
+// MMIO register offsets.
+// The data register, up to 64 bits wide.
+const MMIO_DATA_OFFSET: u16 = 0;
+const MMIO_DATA_SIZE: usize = 8;
+// The selector register, 16 bits wide and big endian.
+const MMIO_SELECTOR_OFFSET: u16 = 8;
+// The DMA address register.
+const MMIO_DMA_OFFSET: u16 = 16;
+
+// The DMA address register is 64 bits wide and big endian. It can be written
+// at once, or as two 32-bit halves, the high one first.
+const DMA_ADDRESS_SIZE: usize = 8;
+const DMA_ADDRESS_HALF_SIZE: usize = 4;
+// The DMA address register reads as "QEMU CFG".
+const DMA_SIGNATURE: [u8; DMA_ADDRESS_SIZE] = *b"QEMU CFG";
+
+// Size of the DMA access structure, made of the big endian 32-bit control,
+// 32-bit length and 64-bit address fields.
+const DMA_ACCESS_SIZE: usize = 16;
+// Bits of the control field.
+// Set by the device when the transfer fails.
+const DMA_ERROR_BIT: u32 = 1 << 0;
+// Reads the selected item into the guest memory.
+const DMA_READ_BIT: u32 = 1 << 1;
+// Skips bytes of the selected item.
+const DMA_SKIP_BIT: u32 = 1 << 2;
+// Selects the item whose key is in the upper 16 bits, before the transfer.
+const DMA_SELECT_BIT: u32 = 1 << 3;
+// Writes the guest memory to the selected item.
+const DMA_WRITE_BIT: u32 = 1 << 4;
+// The zeroes written to the guest memory for the bytes past the end of an
+// item are copied in chunks of this size.
+const DMA_ZERO_CHUNK_SIZE: usize = 4096;
+
+// Size of the name field of the file directory entries, including the
+// terminating NUL.
+const FILE_NAME_SIZE: usize = 56;
+// Names of the files holding the ACPI tables, as expected by the firmware.
+const ACPI_TABLES_FILE: &str = "etc/acpi/tables";
+const ACPI_RSDP_FILE: &str = "etc/acpi/rsdp";
+const ACPI_TABLE_LOADER_FILE: &str = "etc/table-loader";
+// Names of the files holding the SMBIOS tables.
+const SMBIOS_ANCHOR_FILE: &str = "etc/smbios/smbios-anchor";
+const SMBIOS_TABLES_FILE: &str = "etc/smbios/smbios-tables";
+
+// The selector bit requesting write access to the item, which isn't part of
+// its key.
+const WRITE_CHANNEL_BIT: u16 = 0x4000;
+
+// The content of the signature item.
+const SIGNATURE: [u8; 4] = *b"QEMU";
+// Bits of the ID item, which advertises the features of the device.
+// The selector and data registers are available.
+const FEATURE_TRADITIONAL: u32 = 1 << 0;
+// The DMA interface is available.
+const FEATURE_DMA: u32 = 1 << 1;
+
+/// The key of the signature item, which reads as "QEMU".
+pub const FW_CFG_SIGNATURE: u16 = 0x00;
+/// The key of the ID item, which holds the features of the device.
+pub const FW_CFG_ID: u16 = 0x01;
+/// The key of the item holding the UUID of the VM.
+pub const FW_CFG_UUID: u16 = 0x02;
+/// The key of the item holding the size of the RAM, as a 64-bit integer.
+pub const FW_CFG_RAM_SIZE: u16 = 0x03;
+/// The key of the item holding the number of boot CPUs, as a 16-bit integer.
+pub const FW_CFG_NB_CPUS: u16 = 0x05;
+/// The key of the item holding the size of the kernel, as a 32-bit integer.
+pub const FW_CFG_KERNEL_SIZE: u16 = 0x08;
+/// The key of the item holding the size of the initrd, as a 32-bit integer.
+pub const FW_CFG_INITRD_SIZE: u16 = 0x0B;
+/// The key of the item holding the maximum number of CPUs, as a 16-bit
+/// integer.
+pub const FW_CFG_MAX_CPUS: u16 = 0x0F;
+/// The key of the item holding the kernel.
+pub const FW_CFG_KERNEL_DATA: u16 = 0x11;
+/// The key of the item holding the initrd.
+pub const FW_CFG_INITRD_DATA: u16 = 0x12;
+/// The key of the item holding the size of the kernel command line, including
+/// its terminating NUL, as a 32-bit integer.
+pub const FW_CFG_CMDLINE_SIZE: u16 = 0x14;
+/// The key of the item holding the kernel command line.
+pub const FW_CFG_CMDLINE_DATA: u16 = 0x15;
+/// The key of the item holding the size of the setup header of the kernel, as
+/// a 32-bit integer.
+pub const FW_CFG_SETUP_SIZE: u16 = 0x17;
+/// The key of the item holding the setup header of the kernel.
+pub const FW_CFG_SETUP_DATA: u16 = 0x18;
+/// The key of the file directory item, which lists the named items.
+pub const FW_CFG_FILE_DIR: u16 = 0x19;
+/// The key of the first named item.
+pub const FW_CFG_FILE_FIRST: u16 = 0x20;
+
+/// Errors encountered while configuring the fw_cfg device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The key is reserved for an item managed by the device.
+    ReservedKey(u16),
+    /// The name of the file is empty, or longer than 55 bytes.
+    InvalidFileName,
+    /// A file with the same name was already added.
+    DuplicateFileName,
+    /// No key is left for another file.
+    TooManyFiles,
+    /// The item is larger than 4 GiB.
+    ItemTooLarge,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::ReservedKey(key) => write!(f, "The key {:#06x} is reserved", key),
+            Error::InvalidFileName => write!(f, "Invalid file name"),
+            Error::DuplicateFileName => write!(f, "Duplicate file name"),
+            Error::TooManyFiles => write!(f, "No key left for another file"),
+            Error::ItemTooLarge => write!(f, "The item is larger than 4 GiB"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl StdError for Error {}
+
+/// Provides access to the guest memory, for the DMA interface of the fw_cfg
+/// device.
+pub trait DmaMemory {
+    /// Underlying error type of the guest memory accesses.
+    type E: fmt::Debug;
+
+    /// Fills `data` with the guest memory found at the `addr` guest physical
+    /// address.
+    fn read_memory(&self, addr: u64, data: &mut [u8]) -> Result<(), Self::E>;
+
+    /// Writes `data` to the guest memory found at the `addr` guest physical
+    /// address.
+    fn write_memory(&self, addr: u64, data: &[u8]) -> Result<(), Self::E>;
+}
+
+/// Provides a `DmaMemory` implementation for the devices without the DMA
+/// interface, whose accesses always fail.
+#[derive(Debug, Clone, Copy)]
+pub struct NoDma;
+
+impl DmaMemory for NoDma {
+    type E = ();
+
+    fn read_memory(&self, _addr: u64, _data: &mut [u8]) -> Result<(), ()> {
+        Err(())
+    }
+
+    fn write_memory(&self, _addr: u64, _data: &[u8]) -> Result<(), ()> {
+        Err(())
+    }
+}
+
+impl<M: DmaMemory> DmaMemory for Arc<M> {
+    type E = M::E;
+
+    fn read_memory(&self, addr: u64, data: &mut [u8]) -> Result<(), Self::E> {
+        self.as_ref().read_memory(addr, data)
+    }
+
+    fn write_memory(&self, addr: u64, data: &[u8]) -> Result<(), Self::E> {
+        self.as_ref().write_memory(addr, data)
+    }
+}
+
+#[derive(Debug)]
+struct Item {
+    data: Vec<u8>,
+    // Whether the guest can modify the item through the DMA interface.
+    writable: bool,
+}
+
+/// The fw_cfg device, usually found at ports 0x510-0x51B.
+///
+/// The VMM adds the items with [`add_item`](#method.add_item), or as named
+/// files with [`add_file`](#method.add_file), while the signature, ID and
+/// file directory items are provided by the device. Selecting an item
+/// rewinds it, and the bytes past its end, or the ones of a missing item,
+/// read as 0. The guest can't modify the items through the data register.
+///
+/// The DMA interface is available when the device is created with
+/// [`with_dma`](#method.with_dma). The guest then writes the address of a
+/// DMA access structure to the DMA address register (port 0x514), and the
+/// device carries out the transfer right away, through the given
+/// [`DmaMemory`](trait.DmaMemory.html).
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::fw_cfg::{FwCfg, FW_CFG_NB_CPUS};
+/// let mut fw_cfg = FwCfg::new();
+/// fw_cfg.add_item(FW_CFG_NB_CPUS, 2u16.to_le_bytes().to_vec()).unwrap();
+///
+/// // The guest selects the item, and then reads it.
+/// fw_cfg.write(0, &FW_CFG_NB_CPUS.to_le_bytes());
+/// let mut data = [0; 2];
+/// fw_cfg.read(1, &mut data[..1]);
+/// fw_cfg.read(1, &mut data[1..]);
+/// assert_eq!(u16::from_le_bytes(data), 2);
+/// ```
+#[derive(Debug)]
+pub struct FwCfg<M: DmaMemory> {
+    items: BTreeMap<u16, Item>,
+    // The key of the selected item.
+    selector: u16,
+    // Offset of the next byte of the selected item.
+    offset: usize,
+    // Keys of the files, by name.
+    files: BTreeMap<String, u16>,
+    // Guest memory, when the DMA interface is available.
+    dma: Option<M>,
+    // The high half of the DMA address, waiting for the low one.
+    dma_address: u64,
+}
+
+impl FwCfg<NoDma> {
+    /// Creates a new `FwCfg` instance, without the DMA interface, and with
+    /// only the signature, ID and file directory items.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `FwCfg`](struct.FwCfg.html#example).
+    pub fn new() -> Self {
+        Self::with_dma_memory(None)
+    }
+}
+
+impl Default for FwCfg<NoDma> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: DmaMemory> FwCfg<M> {
+    /// Creates a new `FwCfg` instance, with the DMA interface accessing the
+    /// guest memory through `dma`, and with only the signature, ID and file
+    /// directory items.
+    ///
+    /// # Arguments
+    /// * `dma` - The `DmaMemory` implementation used for the DMA transfers.
+    pub fn with_dma(dma: M) -> Self {
+        Self::with_dma_memory(Some(dma))
+    }
+
+    fn with_dma_memory(dma: Option<M>) -> Self {
+        let mut features = FEATURE_TRADITIONAL;
+        if dma.is_some() {
+            features |= FEATURE_DMA;
+        }
+        let mut fw_cfg = FwCfg {
+            items: BTreeMap::new(),
+            selector: FW_CFG_SIGNATURE,
+            offset: 0,
+            files: BTreeMap::new(),
+            dma,
+            dma_address: 0,
+        };
+        fw_cfg.insert_item(FW_CFG_SIGNATURE, SIGNATURE.to_vec(), false);
+        fw_cfg.insert_item(FW_CFG_ID, features.to_le_bytes().to_vec(), false);
+        fw_cfg.insert_item(FW_CFG_FILE_DIR, 0u32.to_be_bytes().to_vec(), false);
+        fw_cfg
+    }
+
+    /// Provides a reference to the guest memory used by the DMA interface, if
+    /// it is available.
+    pub fn dma(&self) -> Option<&M> {
+        self.dma.as_ref()
+    }
+
+    /// Adds the item with the `key` key, replacing the previous one, if any.
+    ///
+    /// # Arguments
+    /// * `key` - The key of the item, without the write channel bit.
+    /// * `data` - The content of the item.
+    pub fn add_item(&mut self, key: u16, data: Vec<u8>) -> Result<(), Error> {
+        Self::check_key(key)?;
+        self.insert_item(key, data, false);
+        Ok(())
+    }
+
+    /// Adds the item with the `key` key, replacing the previous one, if any.
+    /// Unlike the ones added with [`add_item`](#method.add_item), the guest
+    /// can overwrite the bytes of this item through the DMA interface, but
+    /// can't change its size.
+    ///
+    /// # Arguments
+    /// * `key` - The key of the item, without the write channel bit.
+    /// * `data` - The initial content of the item.
+    pub fn add_writable_item(&mut self, key: u16, data: Vec<u8>) -> Result<(), Error> {
+        Self::check_key(key)?;
+        self.insert_item(key, data, true);
+        Ok(())
+    }
+
+    /// Adds the file named `name`, and returns its key, which follows the one
+    /// of the previous file. The file is listed in the file directory, from
+    /// which the firmware looks it up by name.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the file, e.g. "etc/acpi/tables", which is at
+    ///   most 55 bytes long.
+    /// * `data` - The content of the file.
+    pub fn add_file(&mut self, name: &str, data: Vec<u8>) -> Result<u16, Error> {
+        self.insert_file(name, data, false)
+    }
+
+    /// Adds the file named `name`, and returns its key. Unlike the ones added
+    /// with [`add_file`](#method.add_file), the guest can overwrite the bytes
+    /// of this file through the DMA interface, but can't change its size.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the file, which is at most 55 bytes long.
+    /// * `data` - The initial content of the file.
+    pub fn add_writable_file(&mut self, name: &str, data: Vec<u8>) -> Result<u16, Error> {
+        self.insert_file(name, data, true)
+    }
+
+    /// Returns the key of the file named `name`, if any.
+    pub fn file_key(&self, name: &str) -> Option<u16> {
+        self.files.get(name).copied()
+    }
+
+    /// Sets the kernel booted by the firmware, along with its size.
+    ///
+    /// # Arguments
+    /// * `kernel` - The kernel image.
+    pub fn set_kernel(&mut self, kernel: Vec<u8>) -> Result<(), Error> {
+        self.insert_sized_item(FW_CFG_KERNEL_SIZE, FW_CFG_KERNEL_DATA, kernel)
+    }
+
+    /// Sets the initrd of the kernel booted by the firmware, along with its
+    /// size.
+    ///
+    /// # Arguments
+    /// * `initrd` - The initrd image.
+    pub fn set_initrd(&mut self, initrd: Vec<u8>) -> Result<(), Error> {
+        self.insert_sized_item(FW_CFG_INITRD_SIZE, FW_CFG_INITRD_DATA, initrd)
+    }
+
+    /// Sets the command line of the kernel booted by the firmware, along with
+    /// its size. The terminating NUL is appended by the device.
+    ///
+    /// # Arguments
+    /// * `cmdline` - The kernel command line.
+    pub fn set_cmdline(&mut self, cmdline: &str) -> Result<(), Error> {
+        let mut data = Vec::with_capacity(cmdline.len() + 1);
+        data.extend_from_slice(cmdline.as_bytes());
+        data.push(0);
+        self.insert_sized_item(FW_CFG_CMDLINE_SIZE, FW_CFG_CMDLINE_DATA, data)
+    }
+
+    /// Adds the ACPI tables, as the files from which the firmware installs
+    /// them.
+    ///
+    /// # Arguments
+    /// * `tables` - The ACPI tables, e.g. the XSDT, the FADT and the DSDT.
+    /// * `rsdp` - The Root System Description Pointer.
+    /// * `table_loader` - The commands with which the firmware allocates,
+    ///   links and checksums the tables.
+    pub fn add_acpi_tables(
+        &mut self,
+        tables: Vec<u8>,
+        rsdp: Vec<u8>,
+        table_loader: Vec<u8>,
+    ) -> Result<(), Error> {
+        self.add_file(ACPI_TABLES_FILE, tables)?;
+        self.add_file(ACPI_RSDP_FILE, rsdp)?;
+        self.add_file(ACPI_TABLE_LOADER_FILE, table_loader)?;
+        Ok(())
+    }
+
+    /// Adds the SMBIOS tables, as the files from which the firmware installs
+    /// them.
+    ///
+    /// # Arguments
+    /// * `anchor` - The SMBIOS entry point structure.
+    /// * `tables` - The SMBIOS structure table.
+    pub fn add_smbios_tables(&mut self, anchor: Vec<u8>, tables: Vec<u8>) -> Result<(), Error> {
+        self.add_file(SMBIOS_ANCHOR_FILE, anchor)?;
+        self.add_file(SMBIOS_TABLES_FILE, tables)?;
+        Ok(())
+    }
+
+    fn check_key(key: u16) -> Result<(), Error> {
+        let is_file = (FW_CFG_FILE_FIRST..WRITE_CHANNEL_BIT).contains(&key);
+        if key == FW_CFG_SIGNATURE
+            || key == FW_CFG_ID
+            || key == FW_CFG_FILE_DIR
+            || is_file
+            || (key & WRITE_CHANNEL_BIT) != 0
+        {
+            return Err(Error::ReservedKey(key));
+        }
+        Ok(())
+    }
+
+    fn insert_sized_item(&mut self, size_key: u16, key: u16, data: Vec<u8>) -> Result<(), Error> {
+        let size = u32::try_from(data.len()).map_err(|_| Error::ItemTooLarge)?;
+        self.insert_item(size_key, size.to_le_bytes().to_vec(), false);
+        self.insert_item(key, data, false);
+        Ok(())
+    }
+
+    fn insert_file(&mut self, name: &str, data: Vec<u8>, writable: bool) -> Result<u16, Error> {
+        if name.is_empty() || name.len() >= FILE_NAME_SIZE || name.contains('\0') {
+            return Err(Error::InvalidFileName);
+        }
+        if self.files.contains_key(name) {
+            return Err(Error::DuplicateFileName);
+        }
+        if u32::try_from(data.len()).is_err() {
+            return Err(Error::ItemTooLarge);
+        }
+        let key = FW_CFG_FILE_FIRST + self.files.len() as u16;
+        if key >= WRITE_CHANNEL_BIT {
+            return Err(Error::TooManyFiles);
+        }
+
+        self.files.insert(name.to_string(), key);
+        self.insert_item(key, data, writable);
+        self.add_file_dir_entry(name, key);
+        Ok(key)
+    }
+
+    // Adds the entry of a new file to the file directory, which is made of
+    // the big endian count of the files, followed by their entries.
+    fn add_file_dir_entry(&mut self, name: &str, key: u16) {
+        let size = self.items.get(&key).map_or(0, |item| item.data.len());
+        let count = self.files.len() as u32;
+        if let Some(dir) = self.items.get_mut(&FW_CFG_FILE_DIR) {
+            dir.data[..4].copy_from_slice(&count.to_be_bytes());
+            // The big endian size and key, followed by a reserved field and
+            // the NUL padded name.
+            dir.data.extend_from_slice(&(size as u32).to_be_bytes());
+            dir.data.extend_from_slice(&key.to_be_bytes());
+            dir.data.extend_from_slice(&[0; 2]);
+            let mut file_name = [0; FILE_NAME_SIZE];
+            file_name[..name.len()].copy_from_slice(name.as_bytes());
+            dir.data.extend_from_slice(&file_name);
+        }
+    }
+
+    fn insert_item(&mut self, key: u16, data: Vec<u8>, writable: bool) {
+        self.items.insert(key, Item { data, writable });
+    }
+
+    /// Returns the content of the item with the `key` key, if any.
+    pub fn item(&self, key: u16) -> Option<&[u8]> {
+        self.items.get(&key).map(|item| item.data.as_slice())
+    }
+
+    /// Returns the key of the item selected by the guest.
+    pub fn selector(&self) -> u16 {
+        self.selector
+    }
+
+    // Selects the item with the `selector` key, and rewinds it.
+    fn select(&mut self, selector: u16) {
+        self.selector = selector & !WRITE_CHANNEL_BIT;
+        self.offset = 0;
+    }
+
+    // Fills `data` with the next bytes of the selected item.
+    fn read_data(&mut self, data: &mut [u8]) {
+        let item = self
+            .items
+            .get(&self.selector)
+            .map_or(&[][..], |item| item.data.as_slice());
+        for byte in data.iter_mut() {
+            *byte = item.get(self.offset).copied().unwrap_or(0);
+            if self.offset < item.len() {
+                self.offset += 1;
+            }
+        }
+    }
+
+    // Fills `data` with the bytes of the DMA address register, starting
+    // `offset` bytes from its start.
+    fn read_dma_address(&self, offset: usize, data: &mut [u8]) {
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = match self.dma {
+                Some(_) => DMA_SIGNATURE.get(offset + i).copied().unwrap_or(0),
+                None => 0,
+            };
+        }
+    }
+
+    // Writes `data` to the DMA address register, `offset` bytes from its
+    // start. Writing the low half of the register, or the whole register,
+    // starts the transfer.
+    fn write_dma_address(&mut self, offset: usize, data: &[u8]) {
+        if self.dma.is_none() {
+            return;
+        }
+        let address = match (offset, data.len()) {
+            (0, DMA_ADDRESS_HALF_SIZE) => {
+                self.dma_address = read_be(data) << 32;
+                return;
+            }
+            (DMA_ADDRESS_HALF_SIZE, DMA_ADDRESS_HALF_SIZE) => self.dma_address | read_be(data),
+            (0, DMA_ADDRESS_SIZE) => read_be(data),
+            _ => return,
+        };
+        self.dma_address = 0;
+        self.dma_transfer(address);
+    }
+
+    // Carries out the transfer described by the DMA access structure found
+    // at `address`, and reports its status in the control field.
+    fn dma_transfer(&mut self, address: u64) {
+        let dma = match self.dma.as_ref() {
+            Some(dma) => dma,
+            None => return,
+        };
+        let mut access = [0; DMA_ACCESS_SIZE];
+        if dma.read_memory(address, &mut access).is_err() {
+            return;
+        }
+        let control = read_be(&access[0..4]) as u32;
+        let length = read_be(&access[4..8]) as usize;
+        let buffer = read_be(&access[8..16]);
+
+        if (control & DMA_SELECT_BIT) != 0 {
+            self.selector = (control >> 16) as u16 & !WRITE_CHANNEL_BIT;
+            self.offset = 0;
+        }
+        let item = self.items.get_mut(&self.selector);
+        let success = if (control & DMA_READ_BIT) != 0 {
+            let data = item.map_or(&[][..], |item| item.data.as_slice());
+            dma_read(dma, data, &mut self.offset, buffer, length)
+        } else if (control & DMA_WRITE_BIT) != 0 {
+            match item {
+                Some(item) if item.writable => {
+                    dma_write(dma, &mut item.data, &mut self.offset, buffer, length)
+                }
+                _ => false,
+            }
+        } else {
+            if (control & DMA_SKIP_BIT) != 0 {
+                let len = item.map_or(0, |item| item.data.len());
+                self.offset = self.offset.saturating_add(length).min(len);
+            }
+            true
+        };
+
+        let status = if success { 0 } else { DMA_ERROR_BIT };
+        // There is nothing left to report the failure to.
+        let _ = dma.write_memory(address, &status.to_be_bytes());
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Reading the data register returns the next bytes of the selected item,
+    /// while the DMA address register reads as "QEMU CFG" when the DMA
+    /// interface is available. The other offsets read as 0.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    /// * `data` - The byte array storing the read value.
+    pub fn read(&mut self, offset: u8, data: &mut [u8]) {
+        match offset {
+            PIO_DATA_OFFSET => self.read_data(data),
+            _ if is_dma_offset(u16::from(offset), u16::from(PIO_DMA_OFFSET)) => {
+                self.read_dma_address(usize::from(offset - PIO_DMA_OFFSET), data)
+            }
+            _ => {
+                guest_warn!("Invalid fw_cfg read at offset {:#x}", offset);
+                data.fill(0)
+            }
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Writing the selector register selects an item, and writing the DMA
+    /// address register starts a DMA transfer, while the writes to the data
+    /// register are ignored.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `data` - The byte array to write to the register, which is little
+    ///   endian for the selector register, and big endian for the DMA
+    ///   address register.
+    pub fn write(&mut self, offset: u8, data: &[u8]) {
+        match offset {
+            PIO_SELECTOR_OFFSET => {
+                let mut selector = [0; 2];
+                let len = data.len().min(selector.len());
+                selector[..len].copy_from_slice(&data[..len]);
+                self.select(u16::from_le_bytes(selector));
+            }
+            _ if is_dma_offset(u16::from(offset), u16::from(PIO_DMA_OFFSET)) => {
+                self.write_dma_address(usize::from(offset - PIO_DMA_OFFSET), data)
+            }
+            PIO_DATA_OFFSET => (),
+            _ => guest_warn!("Invalid fw_cfg write at offset {:#x}", offset),
+        }
+    }
+}
+
+/// The MMIO flavor of the fw_cfg device, as used on aarch64 machines.
+///
+/// The data register is at the start of the MMIO region, and reads the
+/// bytes of the selected item in their order, while the selector register
+/// follows it, at offset 8, and is big endian. The DMA address register is
+/// at offset 16. The items are managed by the wrapped
+/// [`FwCfg`](struct.FwCfg.html).
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::fw_cfg::{FwCfg, FwCfgMmio, FW_CFG_SIGNATURE};
+/// let mut fw_cfg = FwCfgMmio::new(FwCfg::new());
+///
+/// fw_cfg.write(8, &FW_CFG_SIGNATURE.to_be_bytes());
+/// let mut data = [0; 4];
+/// fw_cfg.read(0, &mut data);
+/// assert_eq!(&data, b"QEMU");
+/// ```
+#[derive(Debug)]
+pub struct FwCfgMmio<M: DmaMemory> {
+    inner: FwCfg<M>,
+}
+
+impl<M: DmaMemory> FwCfgMmio<M> {
+    /// Creates a new `FwCfgMmio` instance, exposing the items of `fw_cfg`.
+    ///
+    /// # Arguments
+    /// * `fw_cfg` - The fw_cfg device holding the items.
+    pub const fn new(fw_cfg: FwCfg<M>) -> Self {
+        FwCfgMmio { inner: fw_cfg }
+    }
+
+    /// Provides a reference to the wrapped fw_cfg device.
+    pub fn fw_cfg(&self) -> &FwCfg<M> {
+        &self.inner
+    }
+
+    /// Provides a mutable reference to the wrapped fw_cfg device, e.g. for
+    /// adding items.
+    pub fn fw_cfg_mut(&mut self) -> &mut FwCfg<M> {
+        &mut self.inner
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// Reading the data register returns the next bytes of the selected item,
+    /// in their order, while the DMA address register reads as "QEMU CFG"
+    /// when the DMA interface is available. The other offsets read as 0.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The byte array storing the read value.
+    pub fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match offset {
+            MMIO_DATA_OFFSET if data.len() <= MMIO_DATA_SIZE => self.inner.read_data(data),
+            _ if is_dma_offset(offset, MMIO_DMA_OFFSET) => self
+                .inner
+                .read_dma_address(usize::from(offset - MMIO_DMA_OFFSET), data),
+            _ => {
+                guest_warn!(
+                    "Invalid fw_cfg read of {} bytes at offset {:#x}",
+                    data.len(),
+                    offset
+                );
+                data.fill(0)
+            }
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// Writing the selector register selects an item, and writing the DMA
+    /// address register starts a DMA transfer, while the other writes are
+    /// ignored.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The big endian byte array to write to the register.
+    pub fn write(&mut self, offset: u16, data: &[u8]) {
+        match offset {
+            MMIO_SELECTOR_OFFSET => {
+                if let Ok(selector) = <[u8; 2]>::try_from(data) {
+                    self.inner.select(u16::from_be_bytes(selector));
+                }
+            }
+            _ if is_dma_offset(offset, MMIO_DMA_OFFSET) => self
+                .inner
+                .write_dma_address(usize::from(offset - MMIO_DMA_OFFSET), data),
+            _ => guest_warn!(
+                "Invalid fw_cfg write of {} bytes at offset {:#x}",
+                data.len(),
+                offset
+            ),
+        }
+    }
+}
+
+// Returns whether `offset` falls within the DMA address register, found at
+// `dma_offset`.
+fn is_dma_offset(offset: u16, dma_offset: u16) -> bool {
+    offset >= dma_offset && usize::from(offset - dma_offset) < DMA_ADDRESS_SIZE
+}
+
+// Returns the big endian integer held by `data`, which is at most 8 bytes
+// long.
+fn read_be(data: &[u8]) -> u64 {
+    data.iter()
+        .fold(0, |value, &byte| (value << 8) | u64::from(byte))
+}
+
+// Reads `length` bytes of `item`, starting at `offset`, into the guest memory
+// found at `address`. The bytes past the end of the item read as 0.
+fn dma_read<M: DmaMemory>(
+    dma: &M,
+    item: &[u8],
+    offset: &mut usize,
+    address: u64,
+    length: usize,
+) -> bool {
+    let start = (*offset).min(item.len());
+    let end = start.saturating_add(length).min(item.len());
+    if dma.write_memory(address, &item[start..end]).is_err() {
+        return false;
+    }
+    *offset = end;
+
+    let zeroes = [0; DMA_ZERO_CHUNK_SIZE];
+    let mut done = end - start;
+    while done < length {
+        let len = (length - done).min(zeroes.len());
+        let chunk_address = address.wrapping_add(done as u64);
+        if dma.write_memory(chunk_address, &zeroes[..len]).is_err() {
+            return false;
+        }
+        done += len;
+    }
+    true
+}
+
+// Writes `length` bytes of the guest memory found at `address` to `item`,
+// starting at `offset`. The transfer fails if it doesn't fit in the item.
+fn dma_write<M: DmaMemory>(
+    dma: &M,
+    item: &mut [u8],
+    offset: &mut usize,
+    address: u64,
+    length: usize,
+) -> bool {
+    let end = match offset.checked_add(length) {
+        Some(end) if end <= item.len() => end,
+        _ => return false,
+    };
+    if dma.read_memory(address, &mut item[*offset..end]).is_err() {
+        return false;
+    }
+    *offset = end;
+    true
+}
+
+impl<M: DmaMemory> LegacyDevice for FwCfg<M> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match u8::try_from(offset) {
+            Ok(offset) => FwCfg::read(self, offset, data),
+            Err(_) => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Ok(offset) = u8::try_from(offset) {
+            FwCfg::write(self, offset, data);
+        }
+    }
+
+    // The items are added by the VMM, so they are kept.
+    fn reset(&mut self) {
+        self.select(FW_CFG_SIGNATURE);
+        self.dma_address = 0;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+impl<M: DmaMemory> LegacyDevice for FwCfgMmio<M> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        FwCfgMmio::read(self, offset, data);
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        FwCfgMmio::write(self, offset, data);
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    const MEMORY_SIZE: usize = 0x1000;
+    // Address of the DMA access structure.
+    const ACCESS_ADDRESS: u64 = 0x100;
+    // Address of the DMA buffer.
+    const BUFFER_ADDRESS: u64 = 0x200;
+
+    struct MockMemory {
+        mem: Mutex<Vec<u8>>,
+    }
+
+    impl MockMemory {
+        fn new() -> Self {
+            MockMemory {
+                mem: Mutex::new(vec![0xFF; MEMORY_SIZE]),
+            }
+        }
+
+        fn read(&self, addr: u64, len: usize) -> Vec<u8> {
+            let mut data = vec![0; len];
+            self.read_memory(addr, &mut data).unwrap();
+            data
+        }
+
+        // Writes the DMA access structure, and returns its address.
+        fn write_access(&self, control: u32, length: u32, address: u64) -> u64 {
+            let mut access = Vec::new();
+            access.extend_from_slice(&control.to_be_bytes());
+            access.extend_from_slice(&length.to_be_bytes());
+            access.extend_from_slice(&address.to_be_bytes());
+            self.write_memory(ACCESS_ADDRESS, &access).unwrap();
+            ACCESS_ADDRESS
+        }
+
+        fn control(&self) -> u32 {
+            read_be(&self.read(ACCESS_ADDRESS, 4)) as u32
+        }
+    }
+
+    impl DmaMemory for MockMemory {
+        type E = ();
+
+        fn read_memory(&self, addr: u64, data: &mut [u8]) -> Result<(), ()> {
+            let mem = self.mem.lock().unwrap();
+            let start = addr as usize;
+            let slice = mem.get(start..start + data.len()).ok_or(())?;
+            data.copy_from_slice(slice);
+            Ok(())
+        }
+
+        fn write_memory(&self, addr: u64, data: &[u8]) -> Result<(), ()> {
+            let mut mem = self.mem.lock().unwrap();
+            let start = addr as usize;
+            let slice = mem.get_mut(start..start + data.len()).ok_or(())?;
+            slice.copy_from_slice(data);
+            Ok(())
+        }
+    }
+
+    // Starts the transfer described at `address` through the two halves of
+    // the DMA address register, as SeaBIOS does.
+    fn start_dma<M: DmaMemory>(fw_cfg: &mut FwCfg<M>, address: u64) {
+        let address = address.to_be_bytes();
+        fw_cfg.write(PIO_DMA_OFFSET, &address[..4]);
+        fw_cfg.write(PIO_DMA_OFFSET + 4, &address[4..]);
+    }
+
+    fn read_pio<M: DmaMemory>(fw_cfg: &mut FwCfg<M>, len: usize) -> Vec<u8> {
+        let mut data = vec![0xFF; len];
+        for byte in data.iter_mut() {
+            fw_cfg.read(PIO_DATA_OFFSET, std::slice::from_mut(byte));
+        }
+        data
+    }
+
+    #[test]
+    fn test_fw_cfg_signature() {
+        let mut fw_cfg = FwCfg::default();
+
+        // The signature is selected at reset.
+        assert_eq!(fw_cfg.selector(), FW_CFG_SIGNATURE);
+        assert_eq!(read_pio(&mut fw_cfg, 4), b"QEMU");
+        // The bytes past the end read as 0.
+        assert_eq!(read_pio(&mut fw_cfg, 2), [0, 0]);
+
+        fw_cfg.write(PIO_SELECTOR_OFFSET, &FW_CFG_ID.to_le_bytes());
+        assert_eq!(read_pio(&mut fw_cfg, 4), [0x01, 0, 0, 0]);
+
+        // Selecting an item rewinds it.
+        fw_cfg.write(PIO_SELECTOR_OFFSET, &FW_CFG_SIGNATURE.to_le_bytes());
+        assert_eq!(read_pio(&mut fw_cfg, 2), b"QE");
+        fw_cfg.write(PIO_SELECTOR_OFFSET, &FW_CFG_SIGNATURE.to_le_bytes());
+        assert_eq!(read_pio(&mut fw_cfg, 4), b"QEMU");
+
+        // The selector register reads as 0.
+        let mut data = [0xFF; 2];
+        fw_cfg.read(PIO_SELECTOR_OFFSET, &mut data);
+        assert_eq!(data, [0, 0]);
+    }
+
+    #[test]
+    fn test_fw_cfg_items() {
+        let mut fw_cfg = FwCfg::new();
+        assert_eq!(
+            fw_cfg.add_item(FW_CFG_SIGNATURE, vec![0]),
+            Err(Error::ReservedKey(FW_CFG_SIGNATURE))
+        );
+        assert_eq!(
+            fw_cfg.add_item(FW_CFG_ID, vec![0]),
+            Err(Error::ReservedKey(FW_CFG_ID))
+        );
+        assert_eq!(
+            fw_cfg.add_item(WRITE_CHANNEL_BIT | FW_CFG_RAM_SIZE, vec![0]),
+            Err(Error::ReservedKey(WRITE_CHANNEL_BIT | FW_CFG_RAM_SIZE))
+        );
+
+        let ram_size = 0x1_2345_6789u64.to_le_bytes().to_vec();
+        fw_cfg.add_item(FW_CFG_RAM_SIZE, ram_size.clone()).unwrap();
+        assert_eq!(fw_cfg.item(FW_CFG_RAM_SIZE), Some(&ram_size[..]));
+        assert_eq!(fw_cfg.item(FW_CFG_UUID), None);
+
+        // The write channel bit isn't part of the key.
+        fw_cfg.write(
+            PIO_SELECTOR_OFFSET,
+            &(WRITE_CHANNEL_BIT | FW_CFG_RAM_SIZE).to_le_bytes(),
+        );
+        assert_eq!(fw_cfg.selector(), FW_CFG_RAM_SIZE);
+        // The writes to the data register are ignored.
+        fw_cfg.write(PIO_DATA_OFFSET, &[0xAA]);
+        assert_eq!(read_pio(&mut fw_cfg, 8), ram_size);
+        // And a missing item reads as 0.
+        fw_cfg.write(PIO_SELECTOR_OFFSET, &FW_CFG_UUID.to_le_bytes());
+        assert_eq!(read_pio(&mut fw_cfg, 4), [0; 4]);
+
+        // Wider reads of the data register return the next bytes.
+        fw_cfg.add_item(FW_CFG_NB_CPUS, vec![4, 0]).unwrap();
+        fw_cfg.write(PIO_SELECTOR_OFFSET, &FW_CFG_NB_CPUS.to_le_bytes());
+        let mut data = [0xFF; 4];
+        fw_cfg.read(PIO_DATA_OFFSET, &mut data);
+        assert_eq!(data, [4, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_fw_cfg_mmio() {
+        let mut fw_cfg = FwCfg::new();
+        fw_cfg
+            .add_item(FW_CFG_KERNEL_DATA, (0..12).collect())
+            .unwrap();
+        let mut fw_cfg = FwCfgMmio::new(fw_cfg);
+        assert_eq!(fw_cfg.fw_cfg().item(FW_CFG_KERNEL_DATA).unwrap().len(), 12);
+
+        // The selector is big endian, and must be written at once.
+        fw_cfg.write(MMIO_SELECTOR_OFFSET, &[FW_CFG_KERNEL_DATA as u8]);
+        assert_eq!(fw_cfg.fw_cfg().selector(), FW_CFG_SIGNATURE);
+        fw_cfg.write(MMIO_SELECTOR_OFFSET, &FW_CFG_KERNEL_DATA.to_be_bytes());
+        assert_eq!(fw_cfg.fw_cfg().selector(), FW_CFG_KERNEL_DATA);
+
+        // The data register reads up to 8 bytes at once, in their order.
+        let mut data = [0xFF; 8];
+        fw_cfg.read(MMIO_DATA_OFFSET, &mut data);
+        assert_eq!(data, [0, 1, 2, 3, 4, 5, 6, 7]);
+        let mut data = [0xFF; 16];
+        fw_cfg.read(MMIO_DATA_OFFSET, &mut data);
+        assert_eq!(data, [0; 16]);
+        let mut data = [0xFF; 8];
+        fw_cfg.read(MMIO_DATA_OFFSET, &mut data);
+        assert_eq!(data, [8, 9, 10, 11, 0, 0, 0, 0]);
+
+        // The other offsets read as 0.
+        let mut data = [0xFF; 2];
+        fw_cfg.read(MMIO_SELECTOR_OFFSET, &mut data);
+        assert_eq!(data, [0, 0]);
+
+        fw_cfg
+            .fw_cfg_mut()
+            .add_item(FW_CFG_KERNEL_SIZE, 12u32.to_le_bytes().to_vec())
+            .unwrap();
+        fw_cfg.write(MMIO_SELECTOR_OFFSET, &FW_CFG_KERNEL_SIZE.to_be_bytes());
+        let mut data = [0xFF; 4];
+        fw_cfg.read(MMIO_DATA_OFFSET, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 12);
+    }
+
+    #[test]
+    fn test_fw_cfg_dma_read() {
+        let mem = Arc::new(MockMemory::new());
+        let mut fw_cfg = FwCfg::with_dma(mem.clone());
+        assert!(fw_cfg.dma().is_some());
+        fw_cfg
+            .add_item(FW_CFG_KERNEL_DATA, (1..=12).collect())
+            .unwrap();
+
+        // The DMA interface is advertised, and its register reads as
+        // "QEMU CFG".
+        fw_cfg.write(PIO_SELECTOR_OFFSET, &FW_CFG_ID.to_le_bytes());
+        assert_eq!(read_pio(&mut fw_cfg, 4), [0x03, 0, 0, 0]);
+        let mut data = [0; 8];
+        fw_cfg.read(PIO_DMA_OFFSET, &mut data);
+        assert_eq!(&data, b"QEMU CFG");
+        let mut data = [0; 4];
+        fw_cfg.read(PIO_DMA_OFFSET + 4, &mut data);
+        assert_eq!(&data, b" CFG");
+
+        // Reading past the end of the item fills the buffer with 0.
+        let control = (u32::from(FW_CFG_KERNEL_DATA) << 16) | DMA_SELECT_BIT | DMA_READ_BIT;
+        let access = mem.write_access(control, 16, BUFFER_ADDRESS);
+        start_dma(&mut fw_cfg, access);
+        assert_eq!(mem.control(), 0);
+        assert_eq!(fw_cfg.selector(), FW_CFG_KERNEL_DATA);
+        let mut expected: Vec<u8> = (1..=12).collect();
+        expected.extend_from_slice(&[0; 4]);
+        assert_eq!(mem.read(BUFFER_ADDRESS, 16), expected);
+        assert_eq!(mem.read(BUFFER_ADDRESS + 16, 1), [0xFF]);
+
+        // Skip, and then read, the next bytes, through a 64-bit write.
+        let control = (u32::from(FW_CFG_KERNEL_DATA) << 16) | DMA_SELECT_BIT | DMA_SKIP_BIT;
+        let access = mem.write_access(control, 4, 0);
+        fw_cfg.write(PIO_DMA_OFFSET, &access.to_be_bytes());
+        assert_eq!(mem.control(), 0);
+        let access = mem.write_access(DMA_READ_BIT, 4, BUFFER_ADDRESS);
+        fw_cfg.write(PIO_DMA_OFFSET, &access.to_be_bytes());
+        assert_eq!(mem.control(), 0);
+        assert_eq!(mem.read(BUFFER_ADDRESS, 4), [5, 6, 7, 8]);
+        // The data register follows the DMA transfers.
+        assert_eq!(read_pio(&mut fw_cfg, 2), [9, 10]);
+
+        // A buffer outside of the guest memory fails the transfer.
+        let control = (u32::from(FW_CFG_SIGNATURE) << 16) | DMA_SELECT_BIT | DMA_READ_BIT;
+        let access = mem.write_access(control, 4, MEMORY_SIZE as u64);
+        start_dma(&mut fw_cfg, access);
+        assert_eq!(mem.control(), DMA_ERROR_BIT);
+        // And so does an access structure outside of it, which is left
+        // untouched.
+        start_dma(&mut fw_cfg, MEMORY_SIZE as u64);
+    }
+
+    #[test]
+    fn test_fw_cfg_dma_write() {
+        let mem = Arc::new(MockMemory::new());
+        let mut fw_cfg = FwCfg::with_dma(mem.clone());
+        fw_cfg.add_item(FW_CFG_UUID, vec![0; 16]).unwrap();
+        fw_cfg
+            .add_writable_item(FW_CFG_RAM_SIZE, vec![0; 8])
+            .unwrap();
+        mem.write_memory(BUFFER_ADDRESS, &[1, 2, 3, 4]).unwrap();
+
+        // The read only items can't be written.
+        let control = (u32::from(FW_CFG_UUID) << 16) | DMA_SELECT_BIT | DMA_WRITE_BIT;
+        let access = mem.write_access(control, 4, BUFFER_ADDRESS);
+        start_dma(&mut fw_cfg, access);
+        assert_eq!(mem.control(), DMA_ERROR_BIT);
+        assert_eq!(fw_cfg.item(FW_CFG_UUID), Some(&[0; 16][..]));
+
+        // Neither can the missing ones.
+        let control = (u32::from(FW_CFG_NB_CPUS) << 16) | DMA_SELECT_BIT | DMA_WRITE_BIT;
+        let access = mem.write_access(control, 4, BUFFER_ADDRESS);
+        start_dma(&mut fw_cfg, access);
+        assert_eq!(mem.control(), DMA_ERROR_BIT);
+
+        let control = (u32::from(FW_CFG_RAM_SIZE) << 16) | DMA_SELECT_BIT | DMA_WRITE_BIT;
+        let access = mem.write_access(control, 4, BUFFER_ADDRESS);
+        start_dma(&mut fw_cfg, access);
+        assert_eq!(mem.control(), 0);
+        let access = mem.write_access(DMA_WRITE_BIT, 2, BUFFER_ADDRESS);
+        start_dma(&mut fw_cfg, access);
+        assert_eq!(mem.control(), 0);
+        assert_eq!(
+            fw_cfg.item(FW_CFG_RAM_SIZE),
+            Some(&[1, 2, 3, 4, 1, 2, 0, 0][..])
+        );
+
+        // The writes past the end of the item fail.
+        let access = mem.write_access(DMA_WRITE_BIT, 4, BUFFER_ADDRESS);
+        start_dma(&mut fw_cfg, access);
+        assert_eq!(mem.control(), DMA_ERROR_BIT);
+        assert_eq!(fw_cfg.item(FW_CFG_RAM_SIZE).unwrap()[6..], [0, 0]);
+    }
+
+    #[test]
+    fn test_fw_cfg_no_dma() {
+        let mut fw_cfg = FwCfg::new();
+        assert!(fw_cfg.dma().is_none());
+
+        // The DMA address register reads as 0, and ignores the writes.
+        let mut data = [0xFF; 8];
+        fw_cfg.read(PIO_DMA_OFFSET, &mut data);
+        assert_eq!(data, [0; 8]);
+        fw_cfg.write(PIO_DMA_OFFSET, &ACCESS_ADDRESS.to_be_bytes());
+        assert_eq!(fw_cfg.selector(), FW_CFG_SIGNATURE);
+        assert_eq!(read_pio(&mut fw_cfg, 4), b"QEMU");
+
+        let mut fw_cfg = FwCfgMmio::new(fw_cfg);
+        let mut data = [0xFF; 8];
+        fw_cfg.read(MMIO_DMA_OFFSET, &mut data);
+        assert_eq!(data, [0; 8]);
+    }
+
+    #[test]
+    fn test_fw_cfg_mmio_dma() {
+        let mem = Arc::new(MockMemory::new());
+        let mut fw_cfg = FwCfgMmio::new(FwCfg::with_dma(mem.clone()));
+
+        let mut data = [0; 8];
+        fw_cfg.read(MMIO_DMA_OFFSET, &mut data);
+        assert_eq!(&data, b"QEMU CFG");
+
+        // The kernel reads the signature through a single 64-bit write.
+        let control = (u32::from(FW_CFG_SIGNATURE) << 16) | DMA_SELECT_BIT | DMA_READ_BIT;
+        let access = mem.write_access(control, 4, BUFFER_ADDRESS);
+        fw_cfg.write(MMIO_DMA_OFFSET, &access.to_be_bytes());
+        assert_eq!(mem.control(), 0);
+        assert_eq!(mem.read(BUFFER_ADDRESS, 4), b"QEMU");
+
+        // Or through two 32-bit ones.
+        let control = (u32::from(FW_CFG_ID) << 16) | DMA_SELECT_BIT | DMA_READ_BIT;
+        let access = mem.write_access(control, 4, BUFFER_ADDRESS).to_be_bytes();
+        fw_cfg.write(MMIO_DMA_OFFSET, &access[..4]);
+        assert_eq!(mem.control(), control);
+        fw_cfg.write(MMIO_DMA_OFFSET + 4, &access[4..]);
+        assert_eq!(mem.control(), 0);
+        assert_eq!(mem.read(BUFFER_ADDRESS, 4), [0x03, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_fw_cfg_files() {
+        let mut fw_cfg = FwCfg::new();
+        // The file directory is empty at first.
+        assert_eq!(fw_cfg.item(FW_CFG_FILE_DIR), Some(&[0; 4][..]));
+
+        assert_eq!(fw_cfg.add_file("etc/e820", vec![1; 20]), Ok(0x20));
+        assert_eq!(
+            fw_cfg.add_writable_file("etc/boot-menu-wait", vec![2; 2]),
+            Ok(0x21)
+        );
+        assert_eq!(fw_cfg.file_key("etc/e820"), Some(0x20));
+        assert_eq!(fw_cfg.file_key("etc/boot-menu-wait"), Some(0x21));
+        assert_eq!(fw_cfg.file_key("bootorder"), None);
+
+        // The firmware looks the files up in the directory.
+        fw_cfg.write(PIO_SELECTOR_OFFSET, &FW_CFG_FILE_DIR.to_le_bytes());
+        let dir = read_pio(&mut fw_cfg, 4 + 2 * 64);
+        assert_eq!(dir[..4], [0, 0, 0, 2]);
+        assert_eq!(dir[4..12], [0, 0, 0, 20, 0, 0x20, 0, 0]);
+        assert_eq!(&dir[12..20], b"etc/e820");
+        assert!(dir[20..68].iter().all(|&byte| byte == 0));
+        assert_eq!(dir[68..76], [0, 0, 0, 2, 0, 0x21, 0, 0]);
+        assert_eq!(&dir[76..94], b"etc/boot-menu-wait");
+        assert!(dir[94..132].iter().all(|&byte| byte == 0));
+
+        fw_cfg.write(PIO_SELECTOR_OFFSET, &0x20u16.to_le_bytes());
+        assert_eq!(read_pio(&mut fw_cfg, 2), [1, 1]);
+
+        // The keys of the files, and of the directory, are reserved.
+        assert_eq!(
+            fw_cfg.add_item(FW_CFG_FILE_DIR, vec![0]),
+            Err(Error::ReservedKey(FW_CFG_FILE_DIR))
+        );
+        assert_eq!(
+            fw_cfg.add_item(0x3FFF, vec![0]),
+            Err(Error::ReservedKey(0x3FFF))
+        );
+        // Unlike the architecture specific ones.
+        fw_cfg.add_item(0x8000, vec![0]).unwrap();
+    }
+
+    #[test]
+    fn test_fw_cfg_file_errors() {
+        let mut fw_cfg = FwCfg::new();
+        assert_eq!(fw_cfg.add_file("", vec![]), Err(Error::InvalidFileName));
+        assert_eq!(
+            fw_cfg.add_file("etc/\0", vec![]),
+            Err(Error::InvalidFileName)
+        );
+        let name = "a".repeat(FILE_NAME_SIZE);
+        assert_eq!(fw_cfg.add_file(&name, vec![]), Err(Error::InvalidFileName));
+        assert_eq!(fw_cfg.add_file(&name[1..], vec![]), Ok(FW_CFG_FILE_FIRST));
+        assert_eq!(
+            fw_cfg.add_file(&name[1..], vec![]),
+            Err(Error::DuplicateFileName)
+        );
+
+        for i in 1..(WRITE_CHANNEL_BIT - FW_CFG_FILE_FIRST) {
+            fw_cfg.add_file(&i.to_string(), vec![]).unwrap();
+        }
+        assert_eq!(fw_cfg.file_key("16351"), Some(0x3FFF));
+        assert_eq!(fw_cfg.add_file("last", vec![]), Err(Error::TooManyFiles));
+    }
+
+    #[test]
+    fn test_fw_cfg_boot_items() {
+        let mut fw_cfg = FwCfg::new();
+        fw_cfg.set_kernel(vec![1; 10]).unwrap();
+        fw_cfg.set_initrd(vec![2; 3]).unwrap();
+        fw_cfg.set_cmdline("console=ttyS0").unwrap();
+
+        assert_eq!(fw_cfg.item(FW_CFG_KERNEL_SIZE), Some(&[10, 0, 0, 0][..]));
+        assert_eq!(fw_cfg.item(FW_CFG_KERNEL_DATA), Some(&[1; 10][..]));
+        assert_eq!(fw_cfg.item(FW_CFG_INITRD_SIZE), Some(&[3, 0, 0, 0][..]));
+        assert_eq!(fw_cfg.item(FW_CFG_INITRD_DATA), Some(&[2; 3][..]));
+        assert_eq!(fw_cfg.item(FW_CFG_CMDLINE_SIZE), Some(&[14, 0, 0, 0][..]));
+        assert_eq!(
+            fw_cfg.item(FW_CFG_CMDLINE_DATA),
+            Some(&b"console=ttyS0\0"[..])
+        );
+        // They can be replaced.
+        fw_cfg.set_cmdline("").unwrap();
+        assert_eq!(fw_cfg.item(FW_CFG_CMDLINE_SIZE), Some(&[1, 0, 0, 0][..]));
+
+        fw_cfg
+            .add_acpi_tables(vec![3; 8], vec![4; 36], vec![5; 128])
+            .unwrap();
+        fw_cfg.add_smbios_tables(vec![6; 24], vec![7; 64]).unwrap();
+        let tables = fw_cfg.file_key(ACPI_TABLES_FILE).unwrap();
+        assert_eq!(fw_cfg.item(tables), Some(&[3; 8][..]));
+        let rsdp = fw_cfg.file_key(ACPI_RSDP_FILE).unwrap();
+        assert_eq!(fw_cfg.item(rsdp), Some(&[4; 36][..]));
+        let table_loader = fw_cfg.file_key(ACPI_TABLE_LOADER_FILE).unwrap();
+        assert_eq!(fw_cfg.item(table_loader), Some(&[5; 128][..]));
+        let anchor = fw_cfg.file_key(SMBIOS_ANCHOR_FILE).unwrap();
+        assert_eq!(fw_cfg.item(anchor), Some(&[6; 24][..]));
+        let smbios = fw_cfg.file_key(SMBIOS_TABLES_FILE).unwrap();
+        assert_eq!(fw_cfg.item(smbios), Some(&[7; 64][..]));
+        assert_eq!(fw_cfg.item(FW_CFG_FILE_DIR).unwrap()[..4], [0, 0, 0, 5]);
+
+        // The tables can only be added once.
+        assert_eq!(
+            fw_cfg.add_smbios_tables(vec![], vec![]),
+            Err(Error::DuplicateFileName)
+        );
+    }
+
+    #[test]
+    fn test_fw_cfg_legacy_device() {
+        let mut fw_cfg = FwCfg::new();
+        fw_cfg.add_item(FW_CFG_NB_CPUS, vec![4, 0]).unwrap();
+
+        LegacyDevice::write(
+            &mut fw_cfg,
+            u16::from(PIO_SELECTOR_OFFSET),
+            &FW_CFG_NB_CPUS.to_le_bytes(),
+        );
+        let mut data = [0xFF; 1];
+        LegacyDevice::read(&mut fw_cfg, u16::from(PIO_DATA_OFFSET), &mut data);
+        assert_eq!(data, [4]);
+
+        // The reset selects the signature again, and keeps the items.
+        let mut fw_cfg = FwCfgMmio::new(fw_cfg);
+        fw_cfg.reset();
+        assert_eq!(fw_cfg.fw_cfg().selector(), FW_CFG_SIGNATURE);
+        assert_eq!(fw_cfg.fw_cfg().item(FW_CFG_NB_CPUS), Some(&[4, 0][..]));
+        let mut data = [0xFF; 4];
+        LegacyDevice::read(&mut fw_cfg, MMIO_DATA_OFFSET, &mut data);
+        assert_eq!(&data, b"QEMU");
+    }
+}
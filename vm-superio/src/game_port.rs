@@ -0,0 +1,115 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides a stub of the game port (port 0x201).
+//!
+//! Legacy guests look for a joystick by writing to this port, which fires the
+//! one-shot timers of the four axes, and then measuring how long the axis bits
+//! stay set. This stub reports no joystick: the axes time out right away and
+//! the buttons are released, so that these probes complete quickly.
+
+use crate::{byte_offset, LegacyDevice};
+
+// Offset of the game port register, which is the only register of the
+// device.
+const GAME_PORT_OFFSET: u8 = 0;
+
+// The axis bits (0-3) read as 0, i.e. the one-shot timers already expired,
+// and the button bits (4-7) read as 1, i.e. released.
+const IDLE_VALUE: u8 = 0xF0;
+
+/// A stub of the game port, usually found at port 0x201, without any joystick
+/// attached.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::game_port::GamePort;
+/// let mut game_port = GamePort::new();
+///
+/// // The guest fires the one-shot timers, which expire right away.
+/// game_port.write(0, 0xFF);
+/// assert_eq!(game_port.read(0) & 0x0F, 0);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GamePort;
+
+impl GamePort {
+    /// Creates a new `GamePort` instance.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `GamePort`](struct.GamePort.html#example).
+    pub const fn new() -> Self {
+        GamePort
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Returns the state of the axes and of the buttons when no joystick is
+    /// attached, or 0xFF for invalid offsets.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&self, offset: u8) -> u8 {
+        match offset {
+            GAME_PORT_OFFSET => IDLE_VALUE,
+            _ => {
+                guest_warn!("Invalid game port read at offset {:#x}", offset);
+                0xFF
+            }
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// The writes fire the one-shot timers of the axes, which expire right
+    /// away, so they are ignored.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, _offset: u8, _value: u8) {}
+}
+
+impl LegacyDevice for GamePort {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = GamePort::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            GamePort::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game_port() {
+        let mut game_port = GamePort::new();
+        assert_eq!(game_port.read(GAME_PORT_OFFSET), IDLE_VALUE);
+
+        game_port.write(GAME_PORT_OFFSET, 0x00);
+        assert_eq!(game_port.read(GAME_PORT_OFFSET), IDLE_VALUE);
+        assert_eq!(game_port.read(GAME_PORT_OFFSET + 1), 0xFF);
+    }
+}
@@ -0,0 +1,347 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the ACPI Generic Event Device (GED).
+//!
+//! The GED lets the VMM notify the guest about the power button presses and
+//! the device hotplug events, on machines without the ACPI fixed hardware.
+//! The `_EVT` method of the device, in the DSDT, reads the event status
+//! register and dispatches the events to the corresponding AML code.
+
+use core::result::Result;
+
+use crate::mmio::{find_register, BadAccessPolicy, MmioEvents, MmioRegister, NoEvents};
+use crate::{LegacyDevice, Trigger};
+
+// Offset of the event status register, which is the only register of the
+// device. It is 32 bits wide.
+const STATUS_OFFSET: u16 = 0;
+const STATUS_SIZE: usize = 4;
+
+// The event status register accepts the accesses of any width, starting in
+// it, and the bytes of the wider ones read as 0.
+const REGISTERS: [MmioRegister; 1] = [MmioRegister {
+    offset: STATUS_OFFSET,
+    size: STATUS_SIZE as u8,
+    min_access: 1,
+    max_access: 8,
+    align: 1,
+}];
+
+/// The events notified to the guest through the GED.
+///
+/// Each event is a bit of the event status register, which the `_EVT`
+/// method of the device tests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GedEvent {
+    /// A CPU was hot plugged or unplugged (bit 0).
+    CpuHotplug,
+    /// A memory region was hot plugged or unplugged (bit 1).
+    MemoryHotplug,
+    /// A PCI device was hot plugged or unplugged (bit 2).
+    PciHotplug,
+    /// The power button was pressed (bit 3).
+    PowerButton,
+}
+
+impl GedEvent {
+    /// Returns the bit of the event in the event status register.
+    pub fn bit(self) -> u32 {
+        match self {
+            GedEvent::CpuHotplug => 1 << 0,
+            GedEvent::MemoryHotplug => 1 << 1,
+            GedEvent::PciHotplug => 1 << 2,
+            GedEvent::PowerButton => 1 << 3,
+        }
+    }
+}
+
+/// The ACPI Generic Event Device, found at the MMIO address given by its
+/// `_CRS` object in the DSDT.
+///
+/// A [`Trigger`](../trait.Trigger.html) object is used for raising the
+/// interrupt of the device, each time the VMM notifies an event. Reading the
+/// event status register returns the pending events, and clears them. The
+/// other accesses are handled as configured by a
+/// [`BadAccessPolicy`](../mmio/enum.BadAccessPolicy.html), which reads them
+/// as 0 by default.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::ged::{Ged, GedEvent};
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut ged = Ged::new(DummyTrigger);
+///
+/// ged.notify(GedEvent::PowerButton).unwrap();
+///
+/// // The `_EVT` method of the guest reads the pending events.
+/// let mut data = [0; 4];
+/// ged.read(0, &mut data);
+/// assert_eq!(u32::from_le_bytes(data), GedEvent::PowerButton.bit());
+/// ```
+#[derive(Debug)]
+pub struct Ged<T: Trigger, EV: MmioEvents> {
+    // Interrupt of the device.
+    interrupt_evt: T,
+    events: EV,
+    bad_access_policy: BadAccessPolicy,
+    // The pending events.
+    status: u32,
+}
+
+impl<T: Trigger> Ged<T, NoEvents> {
+    /// Creates a new `Ged` instance, which will signal `interrupt_evt` when
+    /// an event is notified.
+    ///
+    /// # Arguments
+    /// * `interrupt_evt` - A Trigger object that will be used to raise the
+    ///   interrupt of the device.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Ged`](struct.Ged.html#example).
+    pub const fn new(interrupt_evt: T) -> Self {
+        Self::with_events(interrupt_evt, NoEvents)
+    }
+}
+
+impl<T: Trigger, EV: MmioEvents> Ged<T, EV> {
+    /// Creates a new `Ged` instance, which will signal `interrupt_evt` when
+    /// an event is notified, and invokes the `mmio_evts` implementation of
+    /// `MmioEvents` during operation.
+    ///
+    /// # Arguments
+    /// * `interrupt_evt` - A Trigger object that will be used to raise the
+    ///   interrupt of the device.
+    /// * `mmio_evts` - The `MmioEvents` implementation used to track the bad
+    ///   accesses, with the `BadAccessPolicy::Event` policy.
+    pub const fn with_events(interrupt_evt: T, mmio_evts: EV) -> Self {
+        Ged {
+            interrupt_evt,
+            events: mmio_evts,
+            bad_access_policy: BadAccessPolicy::Zero,
+            status: 0,
+        }
+    }
+
+    /// Provides a reference to the interrupt event object.
+    pub fn interrupt_evt(&self) -> &T {
+        &self.interrupt_evt
+    }
+
+    /// Provides a reference to the MMIO events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Sets the handling of the accesses that don't match the event status
+    /// register, which is `BadAccessPolicy::Zero` by default.
+    ///
+    /// # Arguments
+    /// * `policy` - The policy applied to the bad accesses.
+    pub fn set_bad_access_policy(&mut self, policy: BadAccessPolicy) {
+        self.bad_access_policy = policy;
+    }
+
+    /// Returns the mask of the events that were notified, but not yet read
+    /// by the guest.
+    pub fn pending_events(&self) -> u32 {
+        self.status
+    }
+
+    /// Notifies the guest about an event, by setting its bit in the event
+    /// status register and raising the interrupt.
+    ///
+    /// # Arguments
+    /// * `event` - The event that occurred.
+    pub fn notify(&mut self, event: GedEvent) -> Result<(), T::E> {
+        self.status |= event.bit();
+        self.interrupt_evt.trigger()
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// `data` is filled with the little endian bytes of the event status
+    /// register, starting at `offset`, and the pending events are cleared.
+    /// The bytes past the register read as 0, while the bad accesses are
+    /// handled as configured by the `BadAccessPolicy`.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The byte array storing the read value.
+    pub fn read(&mut self, offset: u16, data: &mut [u8]) {
+        let access = match find_register(&REGISTERS, offset, data.len()) {
+            Some(access) => access,
+            None => {
+                if self.bad_access_policy.bad_read(data) {
+                    self.events.bad_read(offset, data.len());
+                }
+                return;
+            }
+        };
+        data.fill(0);
+        let status = self.status.to_le_bytes();
+        let status = &status[access.shift..];
+        let len = data.len().min(status.len());
+        data[..len].copy_from_slice(&status[..len]);
+        self.status = 0;
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// The event status register is read only, so the writes are ignored,
+    /// while the bad accesses are handled as configured by the
+    /// `BadAccessPolicy`.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The little endian byte array to write to the register.
+    pub fn write(&mut self, offset: u16, data: &[u8]) {
+        if find_register(&REGISTERS, offset, data.len()).is_none()
+            && self.bad_access_policy.bad_write()
+        {
+            self.events.bad_write(offset, data.len());
+        }
+    }
+}
+
+impl<T: Trigger, EV: MmioEvents> LegacyDevice for Ged<T, EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        Ged::read(self, offset, data);
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        Ged::write(self, offset, data);
+    }
+
+    fn reset(&mut self) {
+        self.status = 0;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::Arc;
+    use vmm_sys_util::eventfd::EventFd;
+    use vmm_sys_util::metric::Metric;
+
+    #[derive(Default)]
+    struct ExampleMmioEvents {
+        bad_reads: AtomicU64,
+        bad_writes: AtomicU64,
+    }
+
+    impl MmioEvents for ExampleMmioEvents {
+        fn bad_read(&self, _offset: u16, _len: usize) {
+            self.bad_reads.inc();
+        }
+
+        fn bad_write(&self, _offset: u16, _len: usize) {
+            self.bad_writes.inc();
+        }
+    }
+
+    fn read_status<EV: MmioEvents>(ged: &mut Ged<EventFd, EV>) -> u32 {
+        let mut data = [0; 4];
+        ged.read(STATUS_OFFSET, &mut data);
+        u32::from_le_bytes(data)
+    }
+
+    #[test]
+    fn test_ged_notify() {
+        let mut ged = Ged::new(EventFd::new(libc::EFD_NONBLOCK).unwrap());
+        assert_eq!(read_status(&mut ged), 0);
+
+        // The events accumulate until the guest reads them, and each one
+        // raises the interrupt.
+        ged.notify(GedEvent::CpuHotplug).unwrap();
+        ged.notify(GedEvent::PciHotplug).unwrap();
+        assert_eq!(ged.interrupt_evt().read().unwrap(), 2);
+        assert_eq!(ged.pending_events(), 0b0101);
+
+        // The writes are ignored.
+        ged.write(STATUS_OFFSET, &[0; 4]);
+        assert_eq!(read_status(&mut ged), 0b0101);
+        assert_eq!(ged.pending_events(), 0);
+        assert_eq!(read_status(&mut ged), 0);
+    }
+
+    #[test]
+    fn test_ged_read() {
+        let mut ged = Ged::new(EventFd::new(libc::EFD_NONBLOCK).unwrap());
+        ged.notify(GedEvent::MemoryHotplug).unwrap();
+        ged.notify(GedEvent::PowerButton).unwrap();
+
+        // Reads from invalid offsets don't clear the events.
+        let mut data = [0xFF; 4];
+        ged.read(STATUS_OFFSET + 4, &mut data);
+        assert_eq!(data, [0; 4]);
+
+        // A wider read is padded with 0.
+        let mut data = [0xFF; 8];
+        ged.read(STATUS_OFFSET, &mut data);
+        assert_eq!(data, [0x0A, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_ged_bad_access_policy() {
+        let events = Arc::new(ExampleMmioEvents::default());
+        let mut ged = Ged::with_events(EventFd::new(libc::EFD_NONBLOCK).unwrap(), events.clone());
+        ged.notify(GedEvent::PowerButton).unwrap();
+
+        // The reads of 3 bytes are bad, and leave the data unchanged.
+        ged.set_bad_access_policy(BadAccessPolicy::Event);
+        let mut data = [0xFF; 3];
+        ged.read(STATUS_OFFSET, &mut data);
+        assert_eq!(data, [0xFF; 3]);
+        ged.write(STATUS_OFFSET + 4, &[0; 4]);
+        ged.write(STATUS_OFFSET, &[0; 4]);
+        assert_eq!(events.bad_reads.count(), 1);
+        assert_eq!(events.bad_writes.count(), 1);
+
+        ged.set_bad_access_policy(BadAccessPolicy::Ignore);
+        ged.read(STATUS_OFFSET + 4, &mut data);
+        assert_eq!(data, [0xFF; 3]);
+        assert_eq!(events.bad_reads.count(), 1);
+
+        // The accesses starting in the register are valid.
+        let mut data = [0xFF; 1];
+        ged.read(STATUS_OFFSET + 1, &mut data);
+        assert_eq!(data, [0]);
+        assert_eq!(ged.pending_events(), 0);
+    }
+
+    #[test]
+    fn test_ged_legacy_device() {
+        let mut ged = Ged::new(EventFd::new(libc::EFD_NONBLOCK).unwrap());
+        ged.notify(GedEvent::PowerButton).unwrap();
+        ged.notify(GedEvent::CpuHotplug).unwrap();
+
+        let mut data = [0; 4];
+        LegacyDevice::read(&mut ged, STATUS_OFFSET, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 0b1001);
+
+        // The reset drops the pending events.
+        ged.notify(GedEvent::PowerButton).unwrap();
+        ged.reset();
+        assert_eq!(ged.pending_events(), 0);
+    }
+}
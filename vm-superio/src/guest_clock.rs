@@ -0,0 +1,243 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides a guest timeline, shared by the timer devices.
+//!
+//! The PIT, the ACPI PM timer and the 6300ESB watchdog derive their counters
+//! from a [`ClockSource`](../pit/trait.ClockSource.html). Giving them the same
+//! `GuestClock` keeps them on a single timeline, which the VMM pauses while
+//! the VM is paused, and moves to the saved guest time on restore, so that
+//! the guest never sees its timers disagreeing, e.g. after a migration.
+
+use std::sync::{Mutex, MutexGuard};
+
+use crate::pit::{ClockSource, MonotonicClock};
+
+#[derive(Clone, Copy, Debug)]
+struct Timeline {
+    // The guest time at `host_ns`.
+    guest_ns: u64,
+    // The time of the host clock when the guest time was `guest_ns`.
+    host_ns: u64,
+    paused: bool,
+}
+
+/// A `ClockSource` counting the guest time, derived from a host clock.
+///
+/// The guest time only advances while the clock is running, and can be moved
+/// to the time saved in a snapshot before restoring the devices. It is meant
+/// to be shared, through an `Arc`, by all the timer devices of a VM.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use vm_superio::guest_clock::GuestClock;
+/// # use vm_superio::pit::ClockSource;
+/// # use vm_superio::pm_timer::PmTimer;
+/// // Restore the guest time saved in the snapshot, and then the devices.
+/// let clock = Arc::new(GuestClock::with_time(5_000_000_000));
+/// let pm_timer = PmTimer::new(clock.clone());
+/// assert!(clock.now_ns() >= 5_000_000_000);
+///
+/// // The time stands still while the VM is paused.
+/// clock.pause();
+/// let counter = pm_timer.counter();
+/// assert_eq!(pm_timer.counter(), counter);
+/// clock.resume();
+/// ```
+#[derive(Debug)]
+pub struct GuestClock<C: ClockSource> {
+    host: C,
+    timeline: Mutex<Timeline>,
+}
+
+impl GuestClock<MonotonicClock> {
+    /// Creates a new running `GuestClock`, based on the host monotonic clock,
+    /// which starts counting from 0.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `GuestClock`](struct.GuestClock.html#example).
+    pub fn new() -> Self {
+        Self::with_time(0)
+    }
+
+    /// Creates a new running `GuestClock`, based on the host monotonic clock,
+    /// which starts counting from `guest_ns`.
+    ///
+    /// # Arguments
+    /// * `guest_ns` - The initial guest time, e.g. the one saved in a
+    ///   snapshot.
+    pub fn with_time(guest_ns: u64) -> Self {
+        Self::with_host_clock(MonotonicClock::new(), guest_ns)
+    }
+}
+
+impl Default for GuestClock<MonotonicClock> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: ClockSource> GuestClock<C> {
+    /// Creates a new running `GuestClock`, based on the `host` clock, which
+    /// starts counting from `guest_ns`.
+    ///
+    /// # Arguments
+    /// * `host` - The clock from which the guest time is derived.
+    /// * `guest_ns` - The initial guest time.
+    pub fn with_host_clock(host: C, guest_ns: u64) -> Self {
+        let host_ns = host.now_ns();
+        GuestClock {
+            host,
+            timeline: Mutex::new(Timeline {
+                guest_ns,
+                host_ns,
+                paused: false,
+            }),
+        }
+    }
+
+    // A poisoned lock still holds a consistent timeline, since it is only
+    // updated as a whole.
+    fn timeline(&self) -> MutexGuard<'_, Timeline> {
+        self.timeline.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn guest_ns(&self, timeline: &Timeline) -> u64 {
+        if timeline.paused {
+            timeline.guest_ns
+        } else {
+            let elapsed_ns = self.host.now_ns().saturating_sub(timeline.host_ns);
+            timeline.guest_ns.saturating_add(elapsed_ns)
+        }
+    }
+
+    /// Stops the guest time, e.g. while the VM is paused or being saved.
+    pub fn pause(&self) {
+        let mut timeline = self.timeline();
+        timeline.guest_ns = self.guest_ns(&timeline);
+        timeline.paused = true;
+    }
+
+    /// Lets the guest time advance again, from where it was paused.
+    pub fn resume(&self) {
+        let mut timeline = self.timeline();
+        timeline.guest_ns = self.guest_ns(&timeline);
+        timeline.host_ns = self.host.now_ns();
+        timeline.paused = false;
+    }
+
+    /// Returns whether the guest time is stopped.
+    pub fn is_paused(&self) -> bool {
+        self.timeline().paused
+    }
+
+    /// Moves the guest time to `guest_ns`, e.g. the time saved in a snapshot.
+    ///
+    /// This should happen before the devices sharing the clock are restored,
+    /// since they expect the time to be monotonic afterwards.
+    ///
+    /// # Arguments
+    /// * `guest_ns` - The new guest time.
+    pub fn set_now_ns(&self, guest_ns: u64) {
+        let mut timeline = self.timeline();
+        timeline.guest_ns = guest_ns;
+        timeline.host_ns = self.host.now_ns();
+    }
+
+    /// Provides a reference to the host clock.
+    pub fn host_clock(&self) -> &C {
+        &self.host
+    }
+}
+
+impl<C: ClockSource> ClockSource for GuestClock<C> {
+    fn now_ns(&self) -> u64 {
+        let timeline = self.timeline();
+        self.guest_ns(&timeline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockClock;
+    use std::sync::Arc;
+    use vmm_sys_util::eventfd::EventFd;
+
+    use crate::pit::Pit;
+    use crate::pm_timer::PmTimer;
+
+    fn new_clock(guest_ns: u64) -> (GuestClock<MockClock>, MockClock) {
+        let host = MockClock::new();
+        host.advance_ns(1_000);
+        (GuestClock::with_host_clock(host.clone(), guest_ns), host)
+    }
+
+    #[test]
+    fn test_guest_clock_pause_resume() {
+        let (clock, host) = new_clock(0);
+        assert_eq!(clock.now_ns(), 0);
+        host.advance_ns(500);
+        assert_eq!(clock.now_ns(), 500);
+
+        clock.pause();
+        assert!(clock.is_paused());
+        host.advance_ns(10_000);
+        assert_eq!(clock.now_ns(), 500);
+        // Pausing again doesn't move the time.
+        clock.pause();
+        assert_eq!(clock.now_ns(), 500);
+
+        clock.resume();
+        assert!(!clock.is_paused());
+        assert_eq!(clock.now_ns(), 500);
+        host.advance_ns(100);
+        assert_eq!(clock.now_ns(), 600);
+        clock.resume();
+        assert_eq!(clock.now_ns(), 600);
+    }
+
+    #[test]
+    fn test_guest_clock_set_time() {
+        let (clock, host) = new_clock(2_000);
+        assert_eq!(clock.now_ns(), 2_000);
+        assert_eq!(clock.host_clock().now_ns(), 1_000);
+
+        clock.set_now_ns(7_000);
+        host.advance_ns(10);
+        assert_eq!(clock.now_ns(), 7_010);
+
+        // The time is kept while paused.
+        clock.pause();
+        clock.set_now_ns(9_000);
+        host.advance_ns(10);
+        assert_eq!(clock.now_ns(), 9_000);
+        clock.resume();
+        host.advance_ns(10);
+        assert_eq!(clock.now_ns(), 9_010);
+    }
+
+    #[test]
+    fn test_guest_clock_shared() {
+        let (clock, host) = new_clock(0);
+        let clock = Arc::new(clock);
+        let pit = Pit::new(EventFd::new(libc::EFD_NONBLOCK).unwrap(), clock.clone());
+        let pm_timer = PmTimer::new(clock.clone());
+
+        host.advance_ns(1_000_000);
+        let counter = pm_timer.counter();
+        assert_ne!(counter, 0);
+        assert_eq!(pit.clock().now_ns(), 1_000_000);
+
+        // Both devices stand still while the clock is paused.
+        clock.pause();
+        host.advance_ns(1_000_000);
+        assert_eq!(pm_timer.counter(), counter);
+        assert_eq!(pit.clock().now_ns(), 1_000_000);
+    }
+}
@@ -0,0 +1,742 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the Intel 6300ESB watchdog timer.
+//!
+//! The watchdog is a PCI device, handled by the Linux `i6300esb` driver. It
+//! counts down in two stages: the first one raises an interrupt, and the
+//! second one resets the system, unless the guest reloads the timer in the
+//! meantime.
+//!
+//! The VMM provides the PCI function of the device, and forwards to this
+//! emulation the accesses to the memory BAR, and to the two watchdog
+//! specific registers of the PCI configuration space.
+
+use core::result::Result;
+
+use crate::pit::ClockSource;
+use crate::watchdog::{NoEvents, WatchdogAction, WatchdogEvents};
+use crate::{LegacyDevice, Trigger};
+
+// Offsets of the registers in the memory BAR.
+// Preload value of the first stage (20 bits).
+const TIMER1_OFFSET: u16 = 0x00;
+// Preload value of the second stage (20 bits).
+const TIMER2_OFFSET: u16 = 0x04;
+// General interrupt status register.
+const GINTSR_OFFSET: u16 = 0x08;
+// Reload register (16 bits), which is also used for unlocking the writes to
+// the registers above.
+const RELOAD_OFFSET: u16 = 0x0C;
+
+// Offsets of the watchdog registers in the PCI configuration space.
+// Configuration register (16 bits).
+const CONFIG_REG_OFFSET: u8 = 0x60;
+// Lock register (8 bits).
+const LOCK_REG_OFFSET: u8 = 0x68;
+
+// The preload values are 20 bits wide.
+const PRELOAD_MASK: u32 = 0x000F_FFFF;
+
+// Values written to the reload register, in this order, for unlocking the
+// next write to the preload or reload registers.
+const UNLOCK1: u16 = 0x80;
+const UNLOCK2: u16 = 0x86;
+// Steps of the unlock sequence.
+const LOCKED: u8 = 0;
+const UNLOCK_STARTED: u8 = 1;
+const UNLOCKED: u8 = 2;
+
+// Reload register bits.
+// Reloads the timer, restarting the first stage.
+const RELOAD_BIT: u16 = 1 << 8;
+// Set when the watchdog reset the system. Cleared by writing 1.
+const TIMEOUT_BIT: u16 = 1 << 9;
+
+// General interrupt status register bits.
+// The first stage expired. Cleared by writing 1.
+const GINTSR_INT_BIT: u8 = 1 << 0;
+
+// Configuration register bits.
+// Type of the interrupt raised when the first stage expires.
+const CONFIG_INT_TYPE_MASK: u16 = 0b11;
+const INT_TYPE_IRQ: u16 = 0b00;
+// Selects the 1 MHz clock, instead of the 1 KHz one.
+const CONFIG_FREQ_BIT: u16 = 1 << 2;
+// Disables the system reset when the second stage expires.
+const CONFIG_REBOOT_BIT: u16 = 1 << 5;
+const CONFIG_MASK: u16 = CONFIG_INT_TYPE_MASK | CONFIG_FREQ_BIT | CONFIG_REBOOT_BIT;
+
+// Lock register bits.
+// Locks the lock register until the next reset.
+const LOCK_BIT: u8 = 1 << 0;
+// Enables the watchdog.
+const ENABLE_BIT: u8 = 1 << 1;
+// Selects the free running mode, where the watchdog doesn't reset the
+// system. Stored, but otherwise not emulated.
+const FREE_RUN_BIT: u8 = 1 << 2;
+const LOCK_REG_MASK: u8 = LOCK_BIT | ENABLE_BIT | FREE_RUN_BIT;
+
+// The preload values count ticks of the 33 MHz PCI clock, scaled by 2^15
+// with the 1 KHz clock, and by 2^5 with the 1 MHz one.
+const PCI_CLOCK_PERIOD_NS: u64 = 30;
+const SCALE_1KHZ_SHIFT: u32 = 15;
+const SCALE_1MHZ_SHIFT: u32 = 5;
+
+/// The Intel 6300ESB watchdog timer.
+///
+/// The registers of the memory BAR are accessed through
+/// [`read`](#method.read) and [`write`](#method.write), while the watchdog
+/// specific registers of the PCI configuration space (at offsets 0x60 and
+/// 0x68) are accessed through [`read_config`](#method.read_config) and
+/// [`write_config`](#method.write_config).
+///
+/// The countdown is derived from the time reported by a
+/// [`ClockSource`](../pit/trait.ClockSource.html). The VMM arms a timer for
+/// the deadline returned by [`next_deadline`](#method.next_deadline), and
+/// calls [`on_timer_expired`](#method.on_timer_expired) when it fires.
+/// Two [`Trigger`](../trait.Trigger.html) objects are used: one for the
+/// interrupt raised when the first stage expires, and one for the
+/// [expiry action](#method.set_expiry_action) taken when the second stage
+/// expires, which is the system reset by default.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::i6300esb::I6300Esb;
+/// # use vm_superio::pit::MonotonicClock;
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut esb = I6300Esb::new(DummyTrigger, DummyTrigger, MonotonicClock::new());
+///
+/// // Set a 1 second timeout for the second stage, like the Linux driver.
+/// esb.write(0x0C, &0x80u16.to_le_bytes());
+/// esb.write(0x0C, &0x86u16.to_le_bytes());
+/// esb.write(0x04, &1000u32.to_le_bytes());
+///
+/// // Enable the watchdog.
+/// esb.write_config(0x68, &[0x02]);
+/// assert!(esb.next_deadline().is_some());
+///
+/// // Reload it periodically.
+/// esb.write(0x0C, &0x80u16.to_le_bytes());
+/// esb.write(0x0C, &0x86u16.to_le_bytes());
+/// esb.write(0x0C, &0x0100u16.to_le_bytes());
+/// ```
+#[derive(Debug)]
+pub struct I6300Esb<T: Trigger, C: ClockSource, EV: WatchdogEvents> {
+    // Interrupt raised when the first stage expires.
+    irq_evt: T,
+    // Expiry action event object, i.e. the system reset by default.
+    reset_evt: T,
+    clock: C,
+    events: EV,
+    expiry_action: WatchdogAction,
+    timer1_preload: u32,
+    timer2_preload: u32,
+    config: u16,
+    lock_reg: u8,
+    gintsr: u8,
+    // The step of the unlock sequence of the preload and reload registers.
+    unlock_state: u8,
+    // Whether the watchdog reset the system.
+    timeout_flag: bool,
+    // The stage of the countdown, 1 or 2.
+    stage: u8,
+    // The time when the current stage expires, if the watchdog is enabled.
+    deadline_ns: Option<u64>,
+}
+
+impl<T: Trigger, C: ClockSource> I6300Esb<T, C, NoEvents> {
+    /// Creates a new `I6300Esb` instance, which will signal `irq_evt` when
+    /// the first stage expires, and `reset_evt` when the second stage
+    /// expires.
+    ///
+    /// # Arguments
+    /// * `irq_evt` - A Trigger object that will be used to raise the
+    ///   interrupt of the device.
+    /// * `reset_evt` - A Trigger object that will be used to notify the VMM
+    ///   about the expiry action, i.e. the system reset by default.
+    /// * `clock` - The source of the time from which the countdown is
+    ///   derived.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `I6300Esb`](struct.I6300Esb.html#example).
+    pub fn new(irq_evt: T, reset_evt: T, clock: C) -> Self {
+        Self::with_events(irq_evt, reset_evt, clock, NoEvents)
+    }
+}
+
+impl<T: Trigger, C: ClockSource, EV: WatchdogEvents> I6300Esb<T, C, EV> {
+    /// Creates a new `I6300Esb` instance, which will signal `irq_evt` when
+    /// the first stage expires, and `reset_evt` when the second stage
+    /// expires, and invokes the `esb_evts` implementation of
+    /// `WatchdogEvents` during operation.
+    ///
+    /// # Arguments
+    /// * `irq_evt` - A Trigger object that will be used to raise the
+    ///   interrupt of the device.
+    /// * `reset_evt` - A Trigger object that will be used to notify the VMM
+    ///   about the expiry action, i.e. the system reset by default.
+    /// * `clock` - The source of the time from which the countdown is
+    ///   derived.
+    /// * `esb_evts` - The `WatchdogEvents` implementation used to track the
+    ///   expiry of the watchdog.
+    pub fn with_events(irq_evt: T, reset_evt: T, clock: C, esb_evts: EV) -> Self {
+        I6300Esb {
+            irq_evt,
+            reset_evt,
+            clock,
+            events: esb_evts,
+            expiry_action: WatchdogAction::default(),
+            timer1_preload: PRELOAD_MASK,
+            timer2_preload: PRELOAD_MASK,
+            config: 0,
+            lock_reg: 0,
+            gintsr: 0,
+            unlock_state: LOCKED,
+            timeout_flag: false,
+            stage: 1,
+            deadline_ns: None,
+        }
+    }
+
+    /// Provides a reference to the interrupt event object.
+    pub fn irq_evt(&self) -> &T {
+        &self.irq_evt
+    }
+
+    /// Provides a reference to the reset event object.
+    pub fn reset_evt(&self) -> &T {
+        &self.reset_evt
+    }
+
+    /// Provides a reference to the clock source.
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Provides a reference to the i6300esb events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Returns the action taken when the watchdog expires.
+    pub fn expiry_action(&self) -> WatchdogAction {
+        self.expiry_action
+    }
+
+    /// Sets the action taken when the watchdog expires, which is
+    /// `WatchdogAction::Reset` by default.
+    ///
+    /// # Arguments
+    /// * `action` - The action reported through `WatchdogEvents::expired`,
+    ///   for which the reset event object is signaled, unless it is
+    ///   `WatchdogAction::NotifyOnly`.
+    pub fn set_expiry_action(&mut self, action: WatchdogAction) {
+        self.expiry_action = action;
+    }
+
+    /// Returns whether the watchdog is enabled.
+    pub fn enabled(&self) -> bool {
+        (self.lock_reg & ENABLE_BIT) != 0
+    }
+
+    /// Returns the time, as reported by the clock source, when the current
+    /// stage of the countdown expires, or `None` if the watchdog is
+    /// disabled.
+    ///
+    /// The deadline changes when the guest reloads or reconfigures the
+    /// watchdog, so the VMM should query it again after writes to the
+    /// device, as well as after calling
+    /// [`on_timer_expired`](#method.on_timer_expired).
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.deadline_ns
+    }
+
+    // Returns the duration of the given stage.
+    fn stage_duration_ns(&self, stage: u8) -> u64 {
+        let preload = if stage == 1 {
+            self.timer1_preload
+        } else {
+            self.timer2_preload
+        };
+        let shift = if (self.config & CONFIG_FREQ_BIT) != 0 {
+            SCALE_1MHZ_SHIFT
+        } else {
+            SCALE_1KHZ_SHIFT
+        };
+        (u64::from(preload) << shift) * PCI_CLOCK_PERIOD_NS
+    }
+
+    // Starts the given stage, if the watchdog is enabled.
+    fn start_stage(&mut self, stage: u8, now_ns: u64) {
+        self.stage = stage;
+        self.deadline_ns = if self.enabled() {
+            Some(now_ns + self.stage_duration_ns(stage))
+        } else {
+            None
+        };
+    }
+
+    /// Handles the expiry of the deadline returned by
+    /// [`next_deadline`](#method.next_deadline), if it passed.
+    ///
+    /// The expiry of the first stage raises the interrupt, if configured so,
+    /// and starts the second stage. The expiry of the second stage takes the
+    /// expiry action, unless the guest disabled the reset, and starts the
+    /// first stage again.
+    pub fn on_timer_expired(&mut self) -> Result<(), T::E> {
+        let now_ns = self.clock.now_ns();
+        match self.deadline_ns {
+            Some(deadline_ns) if now_ns >= deadline_ns => (),
+            _ => return Ok(()),
+        }
+
+        if self.stage == 1 {
+            self.start_stage(2, now_ns);
+            self.events.pretimeout();
+            if (self.config & CONFIG_INT_TYPE_MASK) == INT_TYPE_IRQ {
+                self.gintsr |= GINTSR_INT_BIT;
+                return self.irq_evt.trigger();
+            }
+        } else {
+            self.start_stage(1, now_ns);
+            let action = if (self.config & CONFIG_REBOOT_BIT) == 0 {
+                self.expiry_action
+            } else {
+                WatchdogAction::NotifyOnly
+            };
+            self.events.expired(action);
+            if action != WatchdogAction::NotifyOnly {
+                self.timeout_flag = true;
+                return self.reset_evt.trigger();
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base address of the memory BAR.
+    ///
+    /// The preload registers are write only, so they read as 0, like the
+    /// invalid offsets.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The little endian byte array storing the read value.
+    pub fn read(&self, offset: u16, data: &mut [u8]) {
+        let value = match offset {
+            GINTSR_OFFSET => u32::from(self.gintsr),
+            RELOAD_OFFSET if self.timeout_flag => u32::from(TIMEOUT_BIT),
+            TIMER1_OFFSET | TIMER2_OFFSET | RELOAD_OFFSET => 0,
+            _ => {
+                guest_warn!("Invalid i6300ESB read at offset {:#x}", offset);
+                0
+            }
+        };
+        write_le(value, data);
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base address of the memory BAR.
+    ///
+    /// The writes to the preload and reload registers need to be unlocked
+    /// first, by writing 0x80 and then 0x86 to the reload register.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The little endian byte array to write to the register.
+    pub fn write(&mut self, offset: u16, data: &[u8]) {
+        let value = read_le(data);
+        let unlocked = self.unlock_state == UNLOCKED;
+        match offset {
+            TIMER1_OFFSET | TIMER2_OFFSET if unlocked => {
+                self.unlock_state = LOCKED;
+                if offset == TIMER1_OFFSET {
+                    self.timer1_preload = value & PRELOAD_MASK;
+                } else {
+                    self.timer2_preload = value & PRELOAD_MASK;
+                }
+            }
+            GINTSR_OFFSET => self.gintsr &= !(value as u8 & GINTSR_INT_BIT),
+            RELOAD_OFFSET if unlocked => {
+                self.unlock_state = LOCKED;
+                let value = value as u16;
+                if (value & RELOAD_BIT) != 0 {
+                    let now_ns = self.clock.now_ns();
+                    self.start_stage(1, now_ns);
+                }
+                if (value & TIMEOUT_BIT) != 0 {
+                    self.timeout_flag = false;
+                }
+            }
+            RELOAD_OFFSET => {
+                self.unlock_state = match (self.unlock_state, value as u16) {
+                    (_, UNLOCK1) => UNLOCK_STARTED,
+                    (UNLOCK_STARTED, UNLOCK2) => UNLOCKED,
+                    _ => LOCKED,
+                };
+            }
+            TIMER1_OFFSET | TIMER2_OFFSET => {
+                guest_warn!("Ignoring the locked i6300ESB preload register write")
+            }
+            _ => guest_warn!("Invalid i6300ESB write at offset {:#x}", offset),
+        }
+    }
+
+    /// Handles a read request from the driver at `offset` offset in the PCI
+    /// configuration space.
+    ///
+    /// Only the configuration (0x60) and lock (0x68) registers are handled,
+    /// the other offsets read as 0.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset of the register in the configuration space.
+    /// * `data` - The little endian byte array storing the read value.
+    pub fn read_config(&self, offset: u8, data: &mut [u8]) {
+        let value = match offset {
+            CONFIG_REG_OFFSET => u32::from(self.config),
+            LOCK_REG_OFFSET => u32::from(self.lock_reg),
+            _ => 0,
+        };
+        write_le(value, data);
+    }
+
+    /// Handles a write request from the driver at `offset` offset in the PCI
+    /// configuration space.
+    ///
+    /// Once the lock bit is set, the lock register can't be changed anymore,
+    /// i.e. the guest can't disable the watchdog.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset of the register in the configuration space.
+    /// * `data` - The little endian byte array to write to the register.
+    pub fn write_config(&mut self, offset: u8, data: &[u8]) {
+        let value = read_le(data);
+        match offset {
+            CONFIG_REG_OFFSET => self.config = value as u16 & CONFIG_MASK,
+            LOCK_REG_OFFSET if (self.lock_reg & LOCK_BIT) == 0 => {
+                let was_enabled = self.enabled();
+                self.lock_reg = value as u8 & LOCK_REG_MASK;
+                if self.enabled() != was_enabled {
+                    let now_ns = self.clock.now_ns();
+                    self.start_stage(1, now_ns);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+// Returns the value of up to 4 little endian bytes.
+fn read_le(data: &[u8]) -> u32 {
+    data.iter()
+        .take(4)
+        .enumerate()
+        .fold(0, |value, (i, &byte)| value | (u32::from(byte) << (i * 8)))
+}
+
+// Fills `data` with the little endian bytes of `value`, padded with 0.
+fn write_le(value: u32, data: &mut [u8]) {
+    let bytes = value.to_le_bytes();
+    for (i, byte) in data.iter_mut().enumerate() {
+        *byte = bytes.get(i).copied().unwrap_or(0);
+    }
+}
+
+impl<T: Trigger, C: ClockSource, EV: WatchdogEvents> LegacyDevice for I6300Esb<T, C, EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        I6300Esb::read(self, offset, data);
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        I6300Esb::write(self, offset, data);
+    }
+
+    // The timeout flag survives the reset, so the guest finds out that the
+    // watchdog reset the system.
+    fn reset(&mut self) {
+        self.timer1_preload = PRELOAD_MASK;
+        self.timer2_preload = PRELOAD_MASK;
+        self.config = 0;
+        self.lock_reg = 0;
+        self.gintsr = 0;
+        self.unlock_state = LOCKED;
+        self.stage = 1;
+        self.deadline_ns = None;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockClock;
+    use std::io;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::{Arc, Mutex};
+    use vmm_sys_util::eventfd::EventFd;
+    use vmm_sys_util::metric::Metric;
+
+    #[derive(Default)]
+    struct ExampleI6300EsbEvents {
+        pretimeouts: AtomicU64,
+        timeouts: AtomicU64,
+        reboots: AtomicU64,
+        actions: Mutex<Vec<WatchdogAction>>,
+    }
+
+    impl WatchdogEvents for ExampleI6300EsbEvents {
+        fn pretimeout(&self) {
+            self.pretimeouts.inc();
+        }
+
+        fn expired(&self, action: WatchdogAction) {
+            self.timeouts.inc();
+            if action != WatchdogAction::NotifyOnly {
+                self.reboots.inc();
+            }
+            self.actions.lock().unwrap().push(action);
+        }
+    }
+
+    type TestEsb = I6300Esb<EventFd, MockClock, Arc<ExampleI6300EsbEvents>>;
+
+    fn new_esb() -> (TestEsb, MockClock) {
+        let clock = MockClock::new();
+        let esb = I6300Esb::with_events(
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            clock.clone(),
+            Arc::new(ExampleI6300EsbEvents::default()),
+        );
+        (esb, clock)
+    }
+
+    fn evt_count(evt: &EventFd) -> u64 {
+        match evt.read() {
+            Ok(count) => count,
+            Err(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+                0
+            }
+        }
+    }
+
+    fn unlock(esb: &mut TestEsb) {
+        esb.write(RELOAD_OFFSET, &UNLOCK1.to_le_bytes());
+        esb.write(RELOAD_OFFSET, &UNLOCK2.to_le_bytes());
+    }
+
+    fn expire(esb: &mut TestEsb, clock: &MockClock) {
+        clock.set_ns(esb.next_deadline().unwrap());
+        esb.on_timer_expired().unwrap();
+    }
+
+    #[test]
+    fn test_i6300esb_unlock() {
+        let (mut esb, _) = new_esb();
+
+        // The preload registers can't be written while locked.
+        esb.write(TIMER1_OFFSET, &10u32.to_le_bytes());
+        assert_eq!(esb.timer1_preload, PRELOAD_MASK);
+
+        // The unlock sequence allows a single write.
+        unlock(&mut esb);
+        esb.write(TIMER1_OFFSET, &0xFFF0_000Au32.to_le_bytes());
+        assert_eq!(esb.timer1_preload, 0x0000_000A);
+        esb.write(TIMER2_OFFSET, &20u32.to_le_bytes());
+        assert_eq!(esb.timer2_preload, PRELOAD_MASK);
+        unlock(&mut esb);
+        esb.write(TIMER2_OFFSET, &20u32.to_le_bytes());
+        assert_eq!(esb.timer2_preload, 20);
+
+        // A wrong sequence doesn't unlock.
+        esb.write(RELOAD_OFFSET, &UNLOCK1.to_le_bytes());
+        esb.write(RELOAD_OFFSET, &0x42u16.to_le_bytes());
+        esb.write(RELOAD_OFFSET, &UNLOCK2.to_le_bytes());
+        esb.write(TIMER1_OFFSET, &30u32.to_le_bytes());
+        assert_eq!(esb.timer1_preload, 10);
+
+        // The preload registers are write only.
+        let mut data = [0xFF; 4];
+        esb.read(TIMER1_OFFSET, &mut data);
+        assert_eq!(data, [0; 4]);
+    }
+
+    #[test]
+    fn test_i6300esb_stages() {
+        let (mut esb, clock) = new_esb();
+        assert_eq!(esb.next_deadline(), None);
+        unlock(&mut esb);
+        esb.write(TIMER1_OFFSET, &1u32.to_le_bytes());
+        unlock(&mut esb);
+        esb.write(TIMER2_OFFSET, &2u32.to_le_bytes());
+
+        // With the 1 KHz clock, a preload of 1 is 32768 PCI clock ticks.
+        clock.set_ns(1000);
+        esb.write_config(LOCK_REG_OFFSET, &[ENABLE_BIT]);
+        assert!(esb.enabled());
+        assert_eq!(esb.next_deadline(), Some(1000 + 32768 * 30));
+
+        // Nothing happens before the deadline.
+        esb.on_timer_expired().unwrap();
+        assert_eq!(esb.events().pretimeouts.count(), 0);
+
+        // The first stage raises the interrupt.
+        expire(&mut esb, &clock);
+        assert_eq!(esb.events().pretimeouts.count(), 1);
+        assert_eq!(evt_count(esb.irq_evt()), 1);
+        let mut data = [0; 1];
+        esb.read(GINTSR_OFFSET, &mut data);
+        assert_eq!(data, [GINTSR_INT_BIT]);
+        esb.write(GINTSR_OFFSET, &[GINTSR_INT_BIT]);
+        esb.read(GINTSR_OFFSET, &mut data);
+        assert_eq!(data, [0]);
+
+        // The second stage resets the system, and restarts the first one.
+        let now_ns = clock.now_ns();
+        assert_eq!(esb.next_deadline(), Some(now_ns + 2 * 32768 * 30));
+        expire(&mut esb, &clock);
+        assert_eq!(esb.events().timeouts.count(), 1);
+        assert_eq!(esb.events().reboots.count(), 1);
+        assert_eq!(evt_count(esb.reset_evt()), 1);
+        assert_eq!(esb.stage, 1);
+
+        // The timeout flag is cleared by writing 1.
+        let mut data = [0; 2];
+        esb.read(RELOAD_OFFSET, &mut data);
+        assert_eq!(u16::from_le_bytes(data), TIMEOUT_BIT);
+        unlock(&mut esb);
+        esb.write(RELOAD_OFFSET, &TIMEOUT_BIT.to_le_bytes());
+        esb.read(RELOAD_OFFSET, &mut data);
+        assert_eq!(data, [0; 2]);
+    }
+
+    #[test]
+    fn test_i6300esb_reload() {
+        let (mut esb, clock) = new_esb();
+        // Use the 1 MHz clock, without the interrupt and the reset, like the
+        // Linux driver with the reboot disabled.
+        esb.write_config(
+            CONFIG_REG_OFFSET,
+            &(CONFIG_FREQ_BIT | CONFIG_REBOOT_BIT | 0b11).to_le_bytes(),
+        );
+        let mut data = [0; 2];
+        esb.read_config(CONFIG_REG_OFFSET, &mut data);
+        assert_eq!(
+            u16::from_le_bytes(data),
+            CONFIG_FREQ_BIT | CONFIG_REBOOT_BIT | 0b11
+        );
+        esb.write_config(LOCK_REG_OFFSET, &[ENABLE_BIT]);
+        assert_eq!(esb.next_deadline(), Some(u64::from(PRELOAD_MASK) * 32 * 30));
+
+        // The reload restarts the first stage.
+        clock.set_ns(1_000_000);
+        unlock(&mut esb);
+        esb.write(RELOAD_OFFSET, &RELOAD_BIT.to_le_bytes());
+        assert_eq!(
+            esb.next_deadline(),
+            Some(1_000_000 + u64::from(PRELOAD_MASK) * 32 * 30)
+        );
+
+        // Neither the interrupt, nor the reset, are signaled.
+        expire(&mut esb, &clock);
+        expire(&mut esb, &clock);
+        assert_eq!(esb.events().pretimeouts.count(), 1);
+        assert_eq!(esb.events().timeouts.count(), 1);
+        assert_eq!(esb.events().reboots.count(), 0);
+        assert_eq!(evt_count(esb.irq_evt()), 0);
+        assert_eq!(evt_count(esb.reset_evt()), 0);
+
+        // Disabling the watchdog stops it.
+        esb.write_config(LOCK_REG_OFFSET, &[0]);
+        assert_eq!(esb.next_deadline(), None);
+    }
+
+    #[test]
+    fn test_i6300esb_lock() {
+        let (mut esb, _) = new_esb();
+
+        // Once locked, the watchdog can't be disabled.
+        esb.write_config(LOCK_REG_OFFSET, &[ENABLE_BIT | LOCK_BIT | 0xF8]);
+        let mut data = [0; 1];
+        esb.read_config(LOCK_REG_OFFSET, &mut data);
+        assert_eq!(data, [ENABLE_BIT | LOCK_BIT]);
+        esb.write_config(LOCK_REG_OFFSET, &[0]);
+        assert!(esb.enabled());
+        assert!(esb.next_deadline().is_some());
+
+        // The other offsets of the configuration space are ignored.
+        esb.write_config(0x40, &[0xFF; 4]);
+        let mut data = [0xFF; 4];
+        esb.read_config(0x40, &mut data);
+        assert_eq!(data, [0; 4]);
+    }
+
+    #[test]
+    fn test_i6300esb_expiry_action() {
+        let (mut esb, clock) = new_esb();
+        assert_eq!(esb.expiry_action(), WatchdogAction::Reset);
+        esb.set_expiry_action(WatchdogAction::Poweroff);
+        esb.write_config(LOCK_REG_OFFSET, &[ENABLE_BIT]);
+
+        // The configured action is taken, through the reset event object.
+        expire(&mut esb, &clock);
+        expire(&mut esb, &clock);
+        assert_eq!(evt_count(esb.reset_evt()), 1);
+
+        // With the notification only, the event object isn't signaled.
+        esb.set_expiry_action(WatchdogAction::NotifyOnly);
+        expire(&mut esb, &clock);
+        expire(&mut esb, &clock);
+        assert_eq!(evt_count(esb.reset_evt()), 0);
+
+        // The guest can still disable the reset.
+        esb.set_expiry_action(WatchdogAction::Reset);
+        esb.write_config(CONFIG_REG_OFFSET, &CONFIG_REBOOT_BIT.to_le_bytes());
+        expire(&mut esb, &clock);
+        expire(&mut esb, &clock);
+        assert_eq!(evt_count(esb.reset_evt()), 0);
+        assert_eq!(
+            *esb.events().actions.lock().unwrap(),
+            vec![
+                WatchdogAction::Poweroff,
+                WatchdogAction::NotifyOnly,
+                WatchdogAction::NotifyOnly
+            ]
+        );
+    }
+
+    #[test]
+    fn test_i6300esb_legacy_device() {
+        let (mut esb, _) = new_esb();
+        esb.write_config(LOCK_REG_OFFSET, &[ENABLE_BIT | LOCK_BIT]);
+        assert!(esb.next_deadline().is_some());
+
+        LegacyDevice::write(&mut esb, RELOAD_OFFSET, &UNLOCK1.to_le_bytes());
+        assert_eq!(esb.unlock_state, UNLOCK_STARTED);
+
+        // The reset disables the watchdog, even if it is locked.
+        esb.reset();
+        assert!(!esb.enabled());
+        assert_eq!(esb.next_deadline(), None);
+        assert_eq!(esb.unlock_state, LOCKED);
+        let mut data = [0xFF; 4];
+        LegacyDevice::read(&mut esb, RELOAD_OFFSET, &mut data);
+        assert_eq!(data, [0; 4]);
+    }
+}
@@ -5,85 +5,1001 @@
 // Use of this source code is governed by a BSD-style license that can be
 // found in the THIRD-PARTY file.
 
-//! Provides emulation for a super minimal i8042 controller.
+//! Provides emulation for a minimal i8042 controller.
 //!
-//! This emulates just the CPU reset command.
+//! This emulates the CPU reset command, the controller output port, which
+//! carries the A20 gate and the system reset line, and the controller command
+//! byte, which controls the keyboard (IRQ1) and auxiliary device (IRQ12)
+//! interrupts.
 
-use std::result::Result;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::result::Result;
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
 
-use crate::Trigger;
+use crate::{byte_offset, LegacyDevice, Persist, StateError, Trigger};
 
+// Offset of the data register (port 0x60), used for reading the controller
+// output buffer and for writing command parameters.
+pub(crate) const DATA_OFFSET: u8 = 0;
 // Offset of the command register, for write accesses (port 0x64). The same
 // offset can be used, in case of read operations, to access the status
-// register (in which we are not interested for an i8042 that only knows
-// about reset).
-const COMMAND_OFFSET: u8 = 4;
+// register.
+pub(crate) const COMMAND_OFFSET: u8 = 4;
 
-// Reset CPU command.
-const CMD_RESET_CPU: u8 = 0xFE;
+// Controller commands.
+// Read the command byte; the value is placed in the output buffer.
+const CMD_READ_COMMAND_BYTE: u8 = 0x20;
+// Write the command byte; the value is the next byte written to the data port.
+const CMD_WRITE_COMMAND_BYTE: u8 = 0x60;
+// Disable the auxiliary device interface.
+const CMD_DISABLE_AUX: u8 = 0xA7;
+// Enable the auxiliary device interface.
+const CMD_ENABLE_AUX: u8 = 0xA8;
+// Test the auxiliary device interface.
+const CMD_TEST_AUX: u8 = 0xA9;
+// Controller self-test.
+const CMD_SELF_TEST: u8 = 0xAA;
+// Test the keyboard interface.
+const CMD_TEST_KBD: u8 = 0xAB;
+// Disable the keyboard interface.
+const CMD_DISABLE_KBD: u8 = 0xAD;
+// Enable the keyboard interface.
+const CMD_ENABLE_KBD: u8 = 0xAE;
+// Read the output port; the value is placed in the output buffer.
+const CMD_READ_OUTPUT_PORT: u8 = 0xD0;
+// Write the output port; the value is the next byte written to the data port.
+const CMD_WRITE_OUTPUT_PORT: u8 = 0xD1;
+// Pulse the output lines whose bits are cleared in the low nibble of the
+// command, i.e. 0xF0 pulses all four lines, while 0xFF pulses none.
+const CMD_PULSE_OUTPUT: u8 = 0xF0;
 
-/// An i8042 PS/2 controller that emulates just enough to shutdown the machine.
+// Keyboard commands, written by the driver to the data port.
+// Set the LEDs; the value is the next byte written to the data port.
+pub(crate) const KBD_CMD_SET_LEDS: u8 = 0xED;
+// Echo the 0xEE byte back.
+pub(crate) const KBD_CMD_ECHO: u8 = 0xEE;
+// Identify the keyboard.
+pub(crate) const KBD_CMD_IDENTIFY: u8 = 0xF2;
+// Set the typematic rate and delay; the value is the next byte written to the
+// data port.
+pub(crate) const KBD_CMD_SET_TYPEMATIC: u8 = 0xF3;
+// Enable scanning, i.e. the keyboard sends scancodes.
+pub(crate) const KBD_CMD_ENABLE_SCANNING: u8 = 0xF4;
+// Disable scanning.
+pub(crate) const KBD_CMD_DISABLE_SCANNING: u8 = 0xF5;
+// Restore the default parameters.
+pub(crate) const KBD_CMD_SET_DEFAULTS: u8 = 0xF6;
+// Reset the keyboard and run its self-test (BAT).
+pub(crate) const KBD_CMD_RESET: u8 = 0xFF;
+
+// Keyboard responses.
+// The self-test (BAT) passed.
+pub(crate) const KBD_BAT_OK: u8 = 0xAA;
+// Response to the echo command.
+pub(crate) const KBD_ECHO: u8 = 0xEE;
+// The command was acknowledged.
+pub(crate) const KBD_ACK: u8 = 0xFA;
+// The command is not known, and it should be resent.
+pub(crate) const KBD_RESEND: u8 = 0xFE;
+// Scancode set 2 overrun code, sent after keyboard data was dropped. It is
+// translated to the set 1 one (0xFF).
+const KBD_OVERRUN: u8 = 0x00;
+// Identification bytes of a standard MF2 keyboard.
+pub(crate) const KBD_ID: [u8; 2] = [0xAB, 0x83];
+
+// Controller responses.
+// The controller self-test passed.
+const SELF_TEST_OK: u8 = 0x55;
+// The interface test found no errors.
+const INTERFACE_TEST_OK: u8 = 0x00;
+
+// Status register bits.
+// The output buffer holds data for the driver.
+const SB_OUT_DATA_AVAIL: u8 = 0b0000_0001;
+// Mirrors the system flag from the command byte.
+const SB_SYS_FLAG: u8 = 0b0000_0100;
+// The last byte written by the driver went to the command register, rather
+// than to the data one.
+const SB_LAST_WRITE_COMMAND: u8 = 0b0000_1000;
+// The keyboard lock switch is off, i.e. the keyboard is not inhibited.
+const SB_KBD_UNLOCKED: u8 = 0b0001_0000;
+// The data waiting in the output buffer comes from the auxiliary device.
+const SB_AUX_DATA: u8 = 0b0010_0000;
+
+// Command byte bits.
+// Raise IRQ1 when the output buffer holds keyboard data.
+const CB_KBD_INT: u8 = 0b0000_0001;
+// Raise IRQ12 when the output buffer holds auxiliary device data.
+const CB_AUX_INT: u8 = 0b0000_0010;
+// System flag, set by the firmware once the power-on self test passes.
+const CB_SYS_FLAG: u8 = 0b0000_0100;
+// Disable the keyboard interface, i.e. inhibit the keyboard clock.
+const CB_KBD_DISABLE: u8 = 0b0001_0000;
+// Disable the auxiliary device interface.
+const CB_AUX_DISABLE: u8 = 0b0010_0000;
+// Translate the keyboard scancodes from set 2 to set 1.
+const CB_TRANSLATE: u8 = 0b0100_0000;
+
+// Output port bits.
+// System reset line. The CPU is reset when the driver pulls it low.
+const OUTPUT_PORT_RESET_BIT: u8 = 0b0000_0001;
+// A20 gate.
+const OUTPUT_PORT_A20_BIT: u8 = 0b0000_0010;
+
+// Keyboard LED bits: Scroll Lock, Num Lock and Caps Lock.
+pub(crate) const KBD_LEDS_MASK: u8 = 0b0000_0111;
+// Typematic bits: the repeat rate (bits 0-4) and the delay (bits 5-6).
+pub(crate) const KBD_TYPEMATIC_MASK: u8 = 0b0111_1111;
+// A rate of 10.9 characters per second, after a delay of 500 ms.
+pub(crate) const DEFAULT_KBD_TYPEMATIC: u8 = 0x2B;
+
+// Number of output lines which can be pulsed.
+const PULSE_LINES: usize = 4;
+// Only the system reset line (line 0) is wired by default.
+const DEFAULT_PULSE_ACTIONS: [PulseAction; PULSE_LINES] = [
+    PulseAction::Reset,
+    PulseAction::Ignore,
+    PulseAction::Ignore,
+    PulseAction::Ignore,
+];
+
+// Scancode set 2 prefix of the break (key release) codes.
+const SET2_BREAK_PREFIX: u8 = 0xF0;
+// Scancode set 2 code sent by the Print Screen key while Alt is held, i.e.
+// the SysRq key.
+const SET2_SYSRQ: u8 = 0x84;
+// Scancode set 1 bit marking the break codes.
+const SET1_BREAK_BIT: u8 = 0x80;
+
+// Scancode set 2 to set 1 translation table, indexed by the set 2 code. The
+// codes from 0x88 upwards, which include the 0xE0/0xE1 prefixes, are passed
+// through unchanged by the controller.
+const TRANSLATION_TABLE: [u8; 0x88] = [
+    0xff, 0x43, 0x41, 0x3f, 0x3d, 0x3b, 0x3c, 0x58, 0x64, 0x44, 0x42, 0x40, 0x3e, 0x0f, 0x29, 0x59,
+    0x65, 0x38, 0x2a, 0x70, 0x1d, 0x10, 0x02, 0x5a, 0x66, 0x71, 0x2c, 0x1f, 0x1e, 0x11, 0x03, 0x5b,
+    0x67, 0x2e, 0x2d, 0x20, 0x12, 0x05, 0x04, 0x5c, 0x68, 0x39, 0x2f, 0x21, 0x14, 0x13, 0x06, 0x5d,
+    0x69, 0x31, 0x30, 0x23, 0x22, 0x15, 0x07, 0x5e, 0x6a, 0x72, 0x32, 0x24, 0x16, 0x08, 0x09, 0x5f,
+    0x6b, 0x33, 0x25, 0x17, 0x18, 0x0b, 0x0a, 0x60, 0x6c, 0x34, 0x35, 0x26, 0x27, 0x19, 0x0c, 0x61,
+    0x6d, 0x73, 0x28, 0x74, 0x1a, 0x0d, 0x62, 0x6e, 0x3a, 0x36, 0x1c, 0x1b, 0x75, 0x2b, 0x63, 0x76,
+    0x55, 0x56, 0x77, 0x78, 0x79, 0x7a, 0x0e, 0x7b, 0x7c, 0x4f, 0x7d, 0x4b, 0x47, 0x7e, 0x7f, 0x6f,
+    0x52, 0x53, 0x50, 0x4c, 0x4d, 0x48, 0x01, 0x45, 0x57, 0x4e, 0x51, 0x4a, 0x37, 0x49, 0x46, 0x54,
+    0x80, 0x81, 0x82, 0x41, 0x54, 0x85, 0x86, 0x87,
+];
+
+// Maximum number of bytes waiting in each of the output buffers. Further
+// bytes are dropped until the driver reads some of them.
+pub(crate) const BUFFER_SIZE: usize = 16;
+
+// Both interrupts are enabled, and the keyboard output is translated, which is
+// how firmware usually leaves the controller.
+const DEFAULT_COMMAND_BYTE: u8 = CB_KBD_INT | CB_AUX_INT | CB_SYS_FLAG | CB_TRANSLATE;
+// The reset line is not asserted and the A20 gate is enabled.
+const DEFAULT_OUTPUT_PORT: u8 = OUTPUT_PORT_RESET_BIT | OUTPUT_PORT_A20_BIT;
+
+// Translates a set 2 scancode byte to set 1.
+fn translate(code: u8) -> u8 {
+    TRANSLATION_TABLE
+        .get(code as usize)
+        .copied()
+        .unwrap_or(code)
+}
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the i8042 emulation logic (for example, when the guest requests a reset).
+/// The methods below can be implemented by a backend that keeps track of such events by
+/// incrementing metrics, logging messages, or any other action.
+pub trait I8042Events {
+    /// The guest requested a CPU reset, either through the reset command or
+    /// through the output port reset line.
+    fn reset_requested(&self);
+    /// The `dropped` bytes were lost because there was no space left in the
+    /// output buffer.
+    fn buffer_overflow(&self, dropped: usize);
+    /// The driver sent a command which is not known by the controller or by
+    /// the keyboard.
+    fn unknown_command(&self);
+    /// Raising the keyboard or the auxiliary device interrupt failed.
+    fn interrupt_failed(&self);
+    /// The driver changed the keyboard LEDs. Bit 0 of `leds` is Scroll Lock,
+    /// bit 1 is Num Lock and bit 2 is Caps Lock.
+    fn leds_changed(&self, leds: u8);
+    /// The driver changed the keyboard typematic byte. Bits 0-4 of
+    /// `typematic` encode the repeat rate, and bits 5-6 the delay before the
+    /// key starts repeating.
+    fn typematic_changed(&self, typematic: u8);
+    /// The driver pulsed the output `line`, which is mapped to
+    /// [`PulseAction::Notify`](enum.PulseAction.html#variant.Notify).
+    fn line_pulsed(&self, line: u8);
+    /// The driver enabled or disabled the A20 gate through the output port.
+    /// The VMM can implement the address wrap-around of the disabled gate
+    /// here, or just log the transition.
+    fn a20_changed(&self, enabled: bool);
+}
+
+/// Provides a no-op implementation of `I8042Events` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `I8042Events`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl I8042Events for NoEvents {
+    fn reset_requested(&self) {}
+    fn buffer_overflow(&self, _dropped: usize) {}
+    fn unknown_command(&self) {}
+    fn interrupt_failed(&self) {}
+    fn leds_changed(&self, _leds: u8) {}
+    fn typematic_changed(&self, _typematic: u8) {}
+    fn line_pulsed(&self, _line: u8) {}
+    fn a20_changed(&self, _enabled: bool) {}
+}
+
+impl<EV: I8042Events> I8042Events for Arc<EV> {
+    fn reset_requested(&self) {
+        self.as_ref().reset_requested();
+    }
+
+    fn buffer_overflow(&self, dropped: usize) {
+        self.as_ref().buffer_overflow(dropped);
+    }
+
+    fn unknown_command(&self) {
+        self.as_ref().unknown_command();
+    }
+
+    fn interrupt_failed(&self) {
+        self.as_ref().interrupt_failed();
+    }
+
+    fn leds_changed(&self, leds: u8) {
+        self.as_ref().leds_changed(leds);
+    }
+
+    fn typematic_changed(&self, typematic: u8) {
+        self.as_ref().typematic_changed(typematic);
+    }
+
+    fn line_pulsed(&self, line: u8) {
+        self.as_ref().line_pulsed(line);
+    }
+
+    fn a20_changed(&self, enabled: bool) {
+        self.as_ref().a20_changed(enabled);
+    }
+}
+
+/// The state of the i8042 device.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct I8042State {
+    /// Controller command byte.
+    pub command_byte: u8,
+    /// Controller output port, which includes the A20 gate.
+    pub output_port: u8,
+    /// Controller command waiting for its parameter on the data port.
+    pub pending_command: Option<u8>,
+    /// Whether the last byte written by the driver went to the command
+    /// register.
+    pub last_write_command: bool,
+    /// Keyboard command waiting for its parameter on the data port.
+    pub kbd_pending_command: Option<u8>,
+    /// Whether the keyboard sends scancodes.
+    pub kbd_scanning: bool,
+    /// Keyboard LEDs.
+    pub kbd_leds: u8,
+    /// Keyboard typematic rate and delay.
+    pub kbd_typematic: u8,
+    /// Whether the next translated scancode is a break code.
+    pub kbd_break_pending: bool,
+    /// Whether keyboard data was dropped, and the overrun code is waiting for
+    /// space in the output buffer.
+    pub kbd_overrun: bool,
+    /// Keyboard and controller bytes waiting in the output buffer.
+    pub kbd_buffer: Vec<u8>,
+    /// Auxiliary device bytes waiting in the output buffer.
+    pub aux_buffer: Vec<u8>,
+}
+
+impl I8042State {
+    /// Checks that the device can be in this state, e.g. before restoring a
+    /// state deserialized from a snapshot: the output buffers fit in the
+    /// device, and the reserved bits of the keyboard LEDs and typematic
+    /// settings are clear.
+    pub fn validate(&self) -> Result<(), StateError> {
+        for (buffer, bytes) in [
+            ("kbd_buffer", &self.kbd_buffer),
+            ("aux_buffer", &self.aux_buffer),
+        ] {
+            if bytes.len() > BUFFER_SIZE {
+                return Err(StateError::BufferOverflow {
+                    buffer,
+                    len: bytes.len(),
+                    capacity: BUFFER_SIZE,
+                });
+            }
+        }
+        for (register, value, mask) in [
+            ("kbd_leds", self.kbd_leds, KBD_LEDS_MASK),
+            ("kbd_typematic", self.kbd_typematic, KBD_TYPEMATIC_MASK),
+        ] {
+            if value & !mask != 0 {
+                return Err(StateError::InvalidRegister {
+                    register,
+                    value: u32::from(value),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for I8042State {
+    fn default() -> Self {
+        I8042State {
+            command_byte: DEFAULT_COMMAND_BYTE,
+            output_port: DEFAULT_OUTPUT_PORT,
+            pending_command: None,
+            last_write_command: false,
+            kbd_pending_command: None,
+            kbd_scanning: true,
+            kbd_leds: 0,
+            kbd_typematic: DEFAULT_KBD_TYPEMATIC,
+            kbd_break_pending: false,
+            kbd_overrun: false,
+            kbd_buffer: Vec::new(),
+            aux_buffer: Vec::new(),
+        }
+    }
+}
+
+/// The action taken when the driver pulses one of the controller output lines,
+/// through the 0xF0-0xFF commands.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PulseAction {
+    /// The pulse has no effect.
+    Ignore,
+    /// The CPU is reset, as when pulsing the system reset line.
+    Reset,
+    /// The pulse is reported through
+    /// [`I8042Events::line_pulsed`](trait.I8042Events.html#tymethod.line_pulsed).
+    Notify,
+}
+
+/// A key of a standard 104-key (or 105-key, for ISO layouts) PC keyboard.
+///
+/// The keys are named after their position on a US keyboard, as in the
+/// [`code`](https://www.w3.org/TR/uievents-code/) values of the UI Events,
+/// regardless of the layout used by the guest.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum KeyCode {
+    /// Escape.
+    Escape,
+    /// F1.
+    F1,
+    /// F2.
+    F2,
+    /// F3.
+    F3,
+    /// F4.
+    F4,
+    /// F5.
+    F5,
+    /// F6.
+    F6,
+    /// F7.
+    F7,
+    /// F8.
+    F8,
+    /// F9.
+    F9,
+    /// F10.
+    F10,
+    /// F11.
+    F11,
+    /// F12.
+    F12,
+    /// ` and ~.
+    Backquote,
+    /// 1 and !.
+    Digit1,
+    /// 2 and @.
+    Digit2,
+    /// 3 and #.
+    Digit3,
+    /// 4 and $.
+    Digit4,
+    /// 5 and %.
+    Digit5,
+    /// 6 and ^.
+    Digit6,
+    /// 7 and &.
+    Digit7,
+    /// 8 and *.
+    Digit8,
+    /// 9 and (.
+    Digit9,
+    /// 0 and ).
+    Digit0,
+    /// - and _.
+    Minus,
+    /// = and +.
+    Equal,
+    /// Backspace.
+    Backspace,
+    /// Tab.
+    Tab,
+    /// Q.
+    KeyQ,
+    /// W.
+    KeyW,
+    /// E.
+    KeyE,
+    /// R.
+    KeyR,
+    /// T.
+    KeyT,
+    /// Y.
+    KeyY,
+    /// U.
+    KeyU,
+    /// I.
+    KeyI,
+    /// O.
+    KeyO,
+    /// P.
+    KeyP,
+    /// [ and {.
+    BracketLeft,
+    /// ] and }.
+    BracketRight,
+    /// \\ and |.
+    Backslash,
+    /// Caps Lock.
+    CapsLock,
+    /// A.
+    KeyA,
+    /// S.
+    KeyS,
+    /// D.
+    KeyD,
+    /// F.
+    KeyF,
+    /// G.
+    KeyG,
+    /// H.
+    KeyH,
+    /// J.
+    KeyJ,
+    /// K.
+    KeyK,
+    /// L.
+    KeyL,
+    /// ; and :.
+    Semicolon,
+    /// ' and ".
+    Quote,
+    /// Enter.
+    Enter,
+    /// Left Shift.
+    ShiftLeft,
+    /// The key between Left Shift and Z on ISO keyboards.
+    IntlBackslash,
+    /// Z.
+    KeyZ,
+    /// X.
+    KeyX,
+    /// C.
+    KeyC,
+    /// V.
+    KeyV,
+    /// B.
+    KeyB,
+    /// N.
+    KeyN,
+    /// M.
+    KeyM,
+    /// , and <.
+    Comma,
+    /// . and >.
+    Period,
+    /// / and ?.
+    Slash,
+    /// Right Shift.
+    ShiftRight,
+    /// Left Ctrl.
+    ControlLeft,
+    /// Left Windows/Command.
+    MetaLeft,
+    /// Left Alt.
+    AltLeft,
+    /// Space.
+    Space,
+    /// Right Alt (AltGr).
+    AltRight,
+    /// Right Windows/Command.
+    MetaRight,
+    /// Menu.
+    ContextMenu,
+    /// Right Ctrl.
+    ControlRight,
+    /// Print Screen (SysRq).
+    PrintScreen,
+    /// Scroll Lock.
+    ScrollLock,
+    /// Pause (Break).
+    Pause,
+    /// Insert.
+    Insert,
+    /// Home.
+    Home,
+    /// Page Up.
+    PageUp,
+    /// Delete.
+    Delete,
+    /// End.
+    End,
+    /// Page Down.
+    PageDown,
+    /// Up Arrow.
+    ArrowUp,
+    /// Left Arrow.
+    ArrowLeft,
+    /// Down Arrow.
+    ArrowDown,
+    /// Right Arrow.
+    ArrowRight,
+    /// Num Lock.
+    NumLock,
+    /// Keypad /.
+    NumpadDivide,
+    /// Keypad *.
+    NumpadMultiply,
+    /// Keypad -.
+    NumpadSubtract,
+    /// Keypad 7 and Home.
+    Numpad7,
+    /// Keypad 8 and Up Arrow.
+    Numpad8,
+    /// Keypad 9 and Page Up.
+    Numpad9,
+    /// Keypad +.
+    NumpadAdd,
+    /// Keypad 4 and Left Arrow.
+    Numpad4,
+    /// Keypad 5.
+    Numpad5,
+    /// Keypad 6 and Right Arrow.
+    Numpad6,
+    /// Keypad 1 and End.
+    Numpad1,
+    /// Keypad 2 and Down Arrow.
+    Numpad2,
+    /// Keypad 3 and Page Down.
+    Numpad3,
+    /// Keypad Enter.
+    NumpadEnter,
+    /// Keypad 0 and Insert.
+    Numpad0,
+    /// Keypad . and Delete.
+    NumpadDecimal,
+}
+
+impl KeyCode {
+    // Returns the scancode set 2 make code of the key.
+    pub(crate) fn make_code(self) -> &'static [u8] {
+        match self {
+            KeyCode::Escape => &[0x76],
+            KeyCode::F1 => &[0x05],
+            KeyCode::F2 => &[0x06],
+            KeyCode::F3 => &[0x04],
+            KeyCode::F4 => &[0x0C],
+            KeyCode::F5 => &[0x03],
+            KeyCode::F6 => &[0x0B],
+            KeyCode::F7 => &[0x83],
+            KeyCode::F8 => &[0x0A],
+            KeyCode::F9 => &[0x01],
+            KeyCode::F10 => &[0x09],
+            KeyCode::F11 => &[0x78],
+            KeyCode::F12 => &[0x07],
+            KeyCode::Backquote => &[0x0E],
+            KeyCode::Digit1 => &[0x16],
+            KeyCode::Digit2 => &[0x1E],
+            KeyCode::Digit3 => &[0x26],
+            KeyCode::Digit4 => &[0x25],
+            KeyCode::Digit5 => &[0x2E],
+            KeyCode::Digit6 => &[0x36],
+            KeyCode::Digit7 => &[0x3D],
+            KeyCode::Digit8 => &[0x3E],
+            KeyCode::Digit9 => &[0x46],
+            KeyCode::Digit0 => &[0x45],
+            KeyCode::Minus => &[0x4E],
+            KeyCode::Equal => &[0x55],
+            KeyCode::Backspace => &[0x66],
+            KeyCode::Tab => &[0x0D],
+            KeyCode::KeyQ => &[0x15],
+            KeyCode::KeyW => &[0x1D],
+            KeyCode::KeyE => &[0x24],
+            KeyCode::KeyR => &[0x2D],
+            KeyCode::KeyT => &[0x2C],
+            KeyCode::KeyY => &[0x35],
+            KeyCode::KeyU => &[0x3C],
+            KeyCode::KeyI => &[0x43],
+            KeyCode::KeyO => &[0x44],
+            KeyCode::KeyP => &[0x4D],
+            KeyCode::BracketLeft => &[0x54],
+            KeyCode::BracketRight => &[0x5B],
+            KeyCode::Backslash => &[0x5D],
+            KeyCode::CapsLock => &[0x58],
+            KeyCode::KeyA => &[0x1C],
+            KeyCode::KeyS => &[0x1B],
+            KeyCode::KeyD => &[0x23],
+            KeyCode::KeyF => &[0x2B],
+            KeyCode::KeyG => &[0x34],
+            KeyCode::KeyH => &[0x33],
+            KeyCode::KeyJ => &[0x3B],
+            KeyCode::KeyK => &[0x42],
+            KeyCode::KeyL => &[0x4B],
+            KeyCode::Semicolon => &[0x4C],
+            KeyCode::Quote => &[0x52],
+            KeyCode::Enter => &[0x5A],
+            KeyCode::ShiftLeft => &[0x12],
+            KeyCode::IntlBackslash => &[0x61],
+            KeyCode::KeyZ => &[0x1A],
+            KeyCode::KeyX => &[0x22],
+            KeyCode::KeyC => &[0x21],
+            KeyCode::KeyV => &[0x2A],
+            KeyCode::KeyB => &[0x32],
+            KeyCode::KeyN => &[0x31],
+            KeyCode::KeyM => &[0x3A],
+            KeyCode::Comma => &[0x41],
+            KeyCode::Period => &[0x49],
+            KeyCode::Slash => &[0x4A],
+            KeyCode::ShiftRight => &[0x59],
+            KeyCode::ControlLeft => &[0x14],
+            KeyCode::MetaLeft => &[0xE0, 0x1F],
+            KeyCode::AltLeft => &[0x11],
+            KeyCode::Space => &[0x29],
+            KeyCode::AltRight => &[0xE0, 0x11],
+            KeyCode::MetaRight => &[0xE0, 0x27],
+            KeyCode::ContextMenu => &[0xE0, 0x2F],
+            KeyCode::ControlRight => &[0xE0, 0x14],
+            KeyCode::PrintScreen => &[0xE0, 0x12, 0xE0, 0x7C],
+            KeyCode::ScrollLock => &[0x7E],
+            KeyCode::Pause => &[0xE1, 0x14, 0x77, 0xE1, 0xF0, 0x14, 0xF0, 0x77],
+            KeyCode::Insert => &[0xE0, 0x70],
+            KeyCode::Home => &[0xE0, 0x6C],
+            KeyCode::PageUp => &[0xE0, 0x7D],
+            KeyCode::Delete => &[0xE0, 0x71],
+            KeyCode::End => &[0xE0, 0x69],
+            KeyCode::PageDown => &[0xE0, 0x7A],
+            KeyCode::ArrowUp => &[0xE0, 0x75],
+            KeyCode::ArrowLeft => &[0xE0, 0x6B],
+            KeyCode::ArrowDown => &[0xE0, 0x72],
+            KeyCode::ArrowRight => &[0xE0, 0x74],
+            KeyCode::NumLock => &[0x77],
+            KeyCode::NumpadDivide => &[0xE0, 0x4A],
+            KeyCode::NumpadMultiply => &[0x7C],
+            KeyCode::NumpadSubtract => &[0x7B],
+            KeyCode::Numpad7 => &[0x6C],
+            KeyCode::Numpad8 => &[0x75],
+            KeyCode::Numpad9 => &[0x7D],
+            KeyCode::NumpadAdd => &[0x79],
+            KeyCode::Numpad4 => &[0x6B],
+            KeyCode::Numpad5 => &[0x73],
+            KeyCode::Numpad6 => &[0x74],
+            KeyCode::Numpad1 => &[0x69],
+            KeyCode::Numpad2 => &[0x72],
+            KeyCode::Numpad3 => &[0x7A],
+            KeyCode::NumpadEnter => &[0xE0, 0x5A],
+            KeyCode::Numpad0 => &[0x70],
+            KeyCode::NumpadDecimal => &[0x71],
+        }
+    }
+
+    // Returns the scancode set 2 break code of the key.
+    pub(crate) fn break_code(self) -> Vec<u8> {
+        match self {
+            // Print Screen sends two break codes, in the reverse order.
+            KeyCode::PrintScreen => vec![0xE0, 0xF0, 0x7C, 0xE0, 0xF0, 0x12],
+            // Pause sends the break code along with the make code, and nothing
+            // when it is released.
+            KeyCode::Pause => Vec::new(),
+            key => {
+                // The break code of the other keys is their make code with the
+                // 0xF0 prefix placed right before the last byte.
+                let (last, prefix) = key.make_code().split_last().unwrap();
+                [prefix, &[SET2_BREAK_PREFIX, *last]].concat()
+            }
+        }
+    }
+}
+
+/// A key press or release, sent to the guest through
+/// [`I8042Device::send_key_event`](struct.I8042Device.html#method.send_key_event).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct KeyEvent {
+    /// The key which is pressed or released.
+    pub key: KeyCode,
+    /// Whether the key is pressed.
+    pub pressed: bool,
+}
+
+/// Errors encountered while handling i8042 operations.
+#[derive(Debug)]
+pub enum Error<E> {
+    /// Failed to trigger interrupt.
+    Trigger(E),
+    /// No space left in the output buffer.
+    FullBuffer,
+    /// The driver disabled the keyboard scanning or the keyboard interface,
+    /// so the keyboard input is dropped.
+    KeyboardDisabled,
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Trigger(e) => write!(f, "Failed to trigger interrupt: {}", e),
+            Error::FullBuffer => write!(f, "No space left in the output buffer"),
+            Error::KeyboardDisabled => write!(f, "The keyboard is disabled by the driver"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: StdError> StdError for Error<E> {}
+
+/// An i8042 PS/2 controller that emulates just enough to shutdown the machine,
+/// to toggle the A20 gate and to pass keyboard and auxiliary device data to
+/// the driver.
 ///
-/// A [`Trigger`](../trait.Trigger.html) object is used for notifying the VMM
-/// about the CPU reset event.
+/// [`Trigger`](../trait.Trigger.html) objects are used for notifying the VMM
+/// about the CPU reset event, and for raising the keyboard (IRQ1) and the
+/// auxiliary device (IRQ12) interrupts, unless the device is created with
+/// [`new`](#method.new), in which case the driver polls the status register
+/// for its data. An [`I8042Events`](trait.I8042Events.html)
+/// implementation can be used to keep track of significant events, such as
+/// dropped bytes or unknown commands.
 ///
 /// # Example
 ///
 /// ```rust
-/// # use std::io::{Error, Result};
-/// # use std::ops::Deref;
-/// # use vm_superio::Trigger;
+/// # use vm_superio::testing::MockTrigger;
 /// # use vm_superio::I8042Device;
-/// # use vmm_sys_util::eventfd::EventFd;
+/// let reset_evt = MockTrigger::new();
+/// let kbd_evt = MockTrigger::new();
+/// let aux_evt = MockTrigger::new();
+/// let mut i8042 = I8042Device::with_irqs(reset_evt.clone(), kbd_evt.clone(), aux_evt);
 ///
-/// struct EventFdTrigger(EventFd);
-/// impl Trigger for EventFdTrigger {
-///     type E = Error;
+/// // Check read/write operations.
+/// assert_eq!(i8042.read(0), 0);
 ///
-///     fn trigger(&self) -> Result<()> {
-///         self.write(1)
-///     }
-/// }
-/// impl Deref for EventFdTrigger {
-///     type Target = EventFd;
-///     fn deref(&self) -> &Self::Target {
-///         &self.0
-///     }
-/// }
-/// impl EventFdTrigger {
-///     pub fn new(flag: i32) -> Self {
-///         EventFdTrigger(EventFd::new(flag).unwrap())
-///     }
-/// }
+/// // Disable the A20 gate through the output port.
+/// i8042.write(4, 0xD1).unwrap();
+/// i8042.write(0, 0x01).unwrap();
+/// assert!(!i8042.a20_enabled());
 ///
-/// let reset_evt = EventFdTrigger::new(libc::EFD_NONBLOCK);
-/// let mut i8042 = I8042Device::new(reset_evt);
+/// // Send a key press to the guest.
+/// i8042.enqueue_kbd_bytes(&[0x1C]).unwrap();
+/// assert_eq!(kbd_evt.count(), 1);
 ///
-/// // Check read/write operations.
-/// assert_eq!(i8042.read(0), 0);
 /// i8042.write(4, 0xFE).unwrap();
+/// assert_eq!(reset_evt.count(), 1);
 /// ```
 #[derive(Debug)]
-pub struct I8042Device<T: Trigger> {
+pub struct I8042Device<T: Trigger, EV: I8042Events> {
     /// CPU reset event object. We will trigger this event when the guest issues
     /// the reset CPU command.
     reset_evt: T,
+    // Keyboard interrupt (IRQ1) object, if the device has interrupt lines.
+    kbd_evt: Option<T>,
+    // Auxiliary device interrupt (IRQ12) object, if the device has interrupt
+    // lines.
+    aux_evt: Option<T>,
+    events: EV,
+    // The actions taken when the driver pulses each of the output lines.
+    pulse_actions: [PulseAction; PULSE_LINES],
+    // The controller command byte.
+    command_byte: u8,
+    // The controller output port.
+    output_port: u8,
+    // The controller command waiting for its parameter on the data port.
+    pending_command: Option<u8>,
+    // Whether the last byte written by the driver went to the command register.
+    last_write_command: bool,
+    // The keyboard command waiting for its parameter on the data port.
+    kbd_pending_command: Option<u8>,
+    // Whether the keyboard sends scancodes.
+    kbd_scanning: bool,
+    // The keyboard LEDs.
+    kbd_leds: u8,
+    // The keyboard typematic rate and delay.
+    kbd_typematic: u8,
+    // A set 2 break prefix was received while translating, so the next
+    // scancode is translated to a set 1 break code.
+    kbd_break_pending: bool,
+    // Keyboard data was dropped, so the overrun code is sent as soon as there
+    // is space for it in the output buffer.
+    kbd_overrun: bool,
+    // Bytes coming from the keyboard, or from the controller itself, waiting
+    // to be read by the driver from the data port.
+    kbd_buffer: VecDeque<u8>,
+    // Bytes coming from the auxiliary device waiting to be read by the driver
+    // from the data port. These are read only after the keyboard ones.
+    aux_buffer: VecDeque<u8>,
 }
 
-impl<T: Trigger> I8042Device<T> {
+impl<T: Trigger> I8042Device<T, NoEvents> {
     /// Constructs an i8042 device that will signal the given event when the
-    /// guest requests it.
+    /// guest requests a reset. The device raises no keyboard or auxiliary
+    /// device interrupt, so the driver polls the status register for its
+    /// data; [`with_irqs`](#method.with_irqs) creates a device raising them.
+    ///
+    /// # Arguments
+    /// * `reset_evt` - A Trigger object that will be used to notify the driver
+    ///   about the reset event.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use vm_superio::testing::MockTrigger;
+    /// # use vm_superio::I8042Device;
+    /// let reset_evt = MockTrigger::new();
+    /// let mut i8042 = I8042Device::new(reset_evt.clone());
+    ///
+    /// // Send a key press, which the driver finds by polling the status
+    /// // register, translated to scancode set 1.
+    /// i8042.enqueue_kbd_bytes(&[0x1C]).unwrap();
+    /// assert_eq!(i8042.read(4) & 0x01, 0x01);
+    /// assert_eq!(i8042.read(0), 0x1E);
+    ///
+    /// i8042.write(4, 0xFE).unwrap();
+    /// assert_eq!(reset_evt.count(), 1);
+    /// ```
+    pub fn new(reset_evt: T) -> I8042Device<T, NoEvents> {
+        trace_device!("i8042", "create");
+        // Safe because we are using the default state that has empty output
+        // buffers and there are no pending interrupts to be triggered.
+        Self::with_state(&I8042State::default(), reset_evt, None, None, NoEvents).unwrap()
+    }
+
+    /// Constructs an i8042 device that will signal the given events when the
+    /// guest requests a reset, or when there is data for the driver.
     ///
     /// # Arguments
     /// * `reset_evt` - A Trigger object that will be used to notify the driver
-    ///                 about the reset event.
+    ///   about the reset event.
+    /// * `kbd_evt` - A Trigger object that will be used to raise the keyboard
+    ///   interrupt (IRQ1).
+    /// * `aux_evt` - A Trigger object that will be used to raise the auxiliary
+    ///   device interrupt (IRQ12).
     ///
     /// # Example
     ///
     /// You can see an example of how to use this function in the
     /// [`Example` section from `I8042Device`](struct.I8042Device.html#example).
-    pub fn new(reset_evt: T) -> I8042Device<T> {
-        I8042Device { reset_evt }
+    pub fn with_irqs(reset_evt: T, kbd_evt: T, aux_evt: T) -> I8042Device<T, NoEvents> {
+        Self::with_events(reset_evt, kbd_evt, aux_evt, NoEvents)
+    }
+}
+
+impl<T: Trigger, EV: I8042Events> I8042Device<T, EV> {
+    /// Constructs an i8042 device that will signal the given events when the
+    /// guest requests a reset, or when there is data for the driver, and
+    /// invokes the `i8042_evts` implementation of `I8042Events` during
+    /// operation.
+    ///
+    /// # Arguments
+    /// * `reset_evt` - A Trigger object that will be used to notify the driver
+    ///   about the reset event.
+    /// * `kbd_evt` - A Trigger object that will be used to raise the keyboard
+    ///   interrupt (IRQ1).
+    /// * `aux_evt` - A Trigger object that will be used to raise the auxiliary
+    ///   device interrupt (IRQ12).
+    /// * `i8042_evts` - The `I8042Events` implementation used to track the
+    ///   occurrence of significant events in the i8042 operation logic.
+    pub fn with_events(reset_evt: T, kbd_evt: T, aux_evt: T, i8042_evts: EV) -> Self {
+        trace_device!("i8042", "create");
+        // Safe because we are using the default state that has empty output
+        // buffers and there are no pending interrupts to be triggered.
+        Self::with_state(
+            &I8042State::default(),
+            reset_evt,
+            Some(kbd_evt),
+            Some(aux_evt),
+            i8042_evts,
+        )
+        .unwrap()
+    }
+
+    /// Creates a new `I8042Device` instance from a given `state`, which will
+    /// signal the given events when the guest requests a reset, or when there
+    /// is data for the driver.
+    /// For creating the instance from a default state,
+    /// [`with_events`](#method.with_events) method can be used.
+    ///
+    /// If the state has data waiting in the output buffer, the corresponding
+    /// interrupt is raised again.
+    ///
+    /// # Arguments
+    /// * `state` - A reference to the state from which the `I8042Device` is
+    ///   constructed.
+    /// * `reset_evt` - A Trigger object that will be used to notify the driver
+    ///   about the reset event.
+    /// * `kbd_evt` - A Trigger object that will be used to raise the keyboard
+    ///   interrupt (IRQ1).
+    /// * `aux_evt` - A Trigger object that will be used to raise the auxiliary
+    ///   device interrupt (IRQ12).
+    /// * `i8042_evts` - The `I8042Events` implementation used to track the
+    ///   occurrence of significant events in the i8042 operation logic.
+    pub fn from_state(
+        state: &I8042State,
+        reset_evt: T,
+        kbd_evt: T,
+        aux_evt: T,
+        i8042_evts: EV,
+    ) -> Result<Self, Error<T::E>> {
+        trace_device!("i8042", "restore");
+        Self::with_state(state, reset_evt, Some(kbd_evt), Some(aux_evt), i8042_evts)
+    }
+
+    fn with_state(
+        state: &I8042State,
+        reset_evt: T,
+        kbd_evt: Option<T>,
+        aux_evt: Option<T>,
+        i8042_evts: EV,
+    ) -> Result<Self, Error<T::E>> {
+        if state.kbd_buffer.len() > BUFFER_SIZE || state.aux_buffer.len() > BUFFER_SIZE {
+            return Err(Error::FullBuffer);
+        }
+
+        let mut i8042 = I8042Device {
+            reset_evt,
+            kbd_evt,
+            aux_evt,
+            events: i8042_evts,
+            pulse_actions: DEFAULT_PULSE_ACTIONS,
+            command_byte: state.command_byte,
+            output_port: state.output_port,
+            pending_command: state.pending_command,
+            last_write_command: state.last_write_command,
+            kbd_pending_command: state.kbd_pending_command,
+            kbd_scanning: state.kbd_scanning,
+            kbd_leds: state.kbd_leds,
+            kbd_typematic: state.kbd_typematic,
+            kbd_break_pending: state.kbd_break_pending,
+            kbd_overrun: state.kbd_overrun,
+            kbd_buffer: VecDeque::from(state.kbd_buffer.clone()),
+            aux_buffer: VecDeque::from(state.aux_buffer.clone()),
+        };
+        i8042.queue_overrun();
+        i8042.output_interrupt().map_err(Error::Trigger)?;
+
+        Ok(i8042)
+    }
+
+    /// Returns the state of the i8042 device.
+    pub fn state(&self) -> I8042State {
+        trace_device!("i8042", "save");
+        I8042State {
+            command_byte: self.command_byte,
+            output_port: self.output_port,
+            pending_command: self.pending_command,
+            last_write_command: self.last_write_command,
+            kbd_pending_command: self.kbd_pending_command,
+            kbd_scanning: self.kbd_scanning,
+            kbd_leds: self.kbd_leds,
+            kbd_typematic: self.kbd_typematic,
+            kbd_break_pending: self.kbd_break_pending,
+            kbd_overrun: self.kbd_overrun,
+            kbd_buffer: Vec::from(self.kbd_buffer.clone()),
+            aux_buffer: Vec::from(self.aux_buffer.clone()),
+        }
     }
 
     /// Provides a reference to the reset event object.
@@ -91,22 +1007,305 @@ impl<T: Trigger> I8042Device<T> {
         &self.reset_evt
     }
 
-    /// Handles a read request from the driver at `_offset` offset from the
+    /// Provides a reference to the keyboard interrupt object, which is `None`
+    /// for the device created with [`new`](#method.new).
+    pub fn kbd_evt(&self) -> Option<&T> {
+        self.kbd_evt.as_ref()
+    }
+
+    /// Provides a reference to the auxiliary device interrupt object, which is
+    /// `None` for the device created with [`new`](#method.new).
+    pub fn aux_evt(&self) -> Option<&T> {
+        self.aux_evt.as_ref()
+    }
+
+    /// Provides a reference to the i8042 events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Configures the actions taken when the driver pulses the controller
+    /// output lines. By default, pulsing line 0 resets the CPU, and pulsing
+    /// any of the other lines has no effect.
+    ///
+    /// # Arguments
+    /// * `actions` - The actions corresponding to the output lines 0 to 3.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use vm_superio::i8042::PulseAction;
+    /// # use vm_superio::{I8042Device, Trigger};
+    /// # struct DummyTrigger;
+    /// # impl Trigger for DummyTrigger {
+    /// #     type E = ();
+    /// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+    /// # }
+    /// let mut i8042 = I8042Device::new(DummyTrigger);
+    ///
+    /// // Report the pulses of line 1 through the events object.
+    /// i8042.set_pulse_actions([
+    ///     PulseAction::Reset,
+    ///     PulseAction::Notify,
+    ///     PulseAction::Ignore,
+    ///     PulseAction::Ignore,
+    /// ]);
+    /// ```
+    pub fn set_pulse_actions(&mut self, actions: [PulseAction; PULSE_LINES]) {
+        self.pulse_actions = actions;
+    }
+
+    /// Returns whether the A20 gate is enabled in the controller output port.
+    pub fn a20_enabled(&self) -> bool {
+        (self.output_port & OUTPUT_PORT_A20_BIT) != 0
+    }
+
+    fn is_translating(&self) -> bool {
+        (self.command_byte & CB_TRANSLATE) != 0
+    }
+
+    fn is_output_empty(&self) -> bool {
+        self.kbd_buffer.is_empty() && self.aux_buffer.is_empty()
+    }
+
+    // Raises the interrupt corresponding to the byte the driver will read next,
+    // if that interrupt is enabled in the command byte, and the device has
+    // interrupt lines.
+    fn output_interrupt(&mut self) -> Result<(), T::E> {
+        let (evt, interrupt) = if !self.kbd_buffer.is_empty() {
+            if (self.command_byte & CB_KBD_INT) == 0 {
+                return Ok(());
+            }
+            (&self.kbd_evt, "kbd")
+        } else if !self.aux_buffer.is_empty() && (self.command_byte & CB_AUX_INT) != 0 {
+            (&self.aux_evt, "aux")
+        } else {
+            return Ok(());
+        };
+        let evt = match evt {
+            Some(evt) => evt,
+            None => return Ok(()),
+        };
+        trace_interrupt!("i8042", interrupt);
+        let result = evt.trigger();
+        if result.is_err() {
+            self.events.interrupt_failed();
+        }
+        result
+    }
+
+    fn request_reset(&mut self) -> Result<(), T::E> {
+        self.events.reset_requested();
+        trace_interrupt!("i8042", "reset");
+        self.reset_evt.trigger()
+    }
+
+    // Pulses the output lines whose bits are cleared in `lines`.
+    fn pulse_output(&mut self, lines: u8) -> Result<(), T::E> {
+        for line in 0..PULSE_LINES as u8 {
+            if (lines & (1 << line)) != 0 {
+                continue;
+            }
+            match self.pulse_actions[line as usize] {
+                PulseAction::Ignore => {}
+                PulseAction::Reset => self.request_reset()?,
+                PulseAction::Notify => self.events.line_pulsed(line),
+            }
+        }
+        Ok(())
+    }
+
+    // Queues `bytes` in the auxiliary device buffer when `aux` is set, or in the
+    // keyboard one otherwise, and notifies the driver if they are the next ones
+    // to be read. The bytes which don't fit are dropped, without reporting it.
+    fn queue_output(&mut self, bytes: &[u8], aux: bool) -> Result<usize, T::E> {
+        let was_empty = self.is_output_empty();
+        let buffer = if aux {
+            &mut self.aux_buffer
+        } else {
+            &mut self.kbd_buffer
+        };
+        let count = core::cmp::min(BUFFER_SIZE - buffer.len(), bytes.len());
+        buffer.extend(&bytes[..count]);
+
+        if was_empty && count > 0 {
+            self.output_interrupt()?;
+        }
+        Ok(count)
+    }
+
+    // Same as `queue_output`, but the dropped bytes are reported through the
+    // events object.
+    fn push_output(&mut self, bytes: &[u8], aux: bool) -> Result<usize, T::E> {
+        let count = self.queue_output(bytes, aux)?;
+        if count < bytes.len() {
+            self.events.buffer_overflow(bytes.len() - count);
+        }
+        Ok(count)
+    }
+
+    // Queues the keyboard overrun code, if keyboard data was dropped and there
+    // is space for it. The caller is responsible for raising the interrupt.
+    fn queue_overrun(&mut self) {
+        if self.kbd_overrun && self.kbd_buffer.len() < BUFFER_SIZE {
+            let mut code = KBD_OVERRUN;
+            if self.is_translating() {
+                code = translate(code);
+            }
+            self.kbd_buffer.push_back(code);
+            self.kbd_overrun = false;
+        }
+    }
+
+    fn pop_output(&mut self) -> u8 {
+        let byte = self
+            .kbd_buffer
+            .pop_front()
+            .or_else(|| self.aux_buffer.pop_front())
+            .unwrap_or_default();
+        self.queue_overrun();
+        // The interrupt is raised again for the next byte, if any. There is no
+        // way of reporting a failure to the driver from a read, so we only
+        // account it through the events object.
+        let _ = self.output_interrupt();
+        byte
+    }
+
+    fn status(&self) -> u8 {
+        let mut status = SB_KBD_UNLOCKED;
+        if !self.kbd_buffer.is_empty() {
+            status |= SB_OUT_DATA_AVAIL;
+        } else if !self.aux_buffer.is_empty() {
+            status |= SB_OUT_DATA_AVAIL | SB_AUX_DATA;
+        }
+        if (self.command_byte & CB_SYS_FLAG) != 0 {
+            status |= SB_SYS_FLAG;
+        }
+        if self.last_write_command {
+            status |= SB_LAST_WRITE_COMMAND;
+        }
+        status
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
     /// base I/O address.
     ///
-    /// Returns the read value, which at this moment is 0x00, since we're not
-    /// interested in an i8042 operation other than CPU reset.
+    /// Returns the read value. Reads from the data register return the oldest
+    /// byte from the output buffer (or 0x00 when it is empty), while reads
+    /// from the command register return the status register, which reports
+    /// whether the output buffer holds data and whether it comes from the
+    /// auxiliary device, along with the system flag.
     ///
     /// # Arguments
-    /// * `_offset` - The offset that will be added to the base address
-    ///              for writing to a specific register.
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
     ///
     /// # Example
     ///
     /// You can see an example of how to use this function in the
     /// [`Example` section from `I8042Device`](struct.I8042Device.html#example).
-    pub fn read(&mut self, _offset: u8) -> u8 {
-        0x00
+    pub fn read(&mut self, offset: u8) -> u8 {
+        let value = match offset {
+            DATA_OFFSET => self.pop_output(),
+            COMMAND_OFFSET => self.status(),
+            _ => {
+                guest_warn!("Invalid i8042 read at offset {:#x}", offset);
+                0x00
+            }
+        };
+        trace_access!("i8042", "read", offset, value);
+        value
+    }
+
+    fn write_command_byte(&mut self, value: u8) -> Result<(), T::E> {
+        self.command_byte = value;
+        // Newly enabled interrupts are raised for data which is already waiting.
+        self.output_interrupt()
+    }
+
+    fn write_output_port(&mut self, value: u8) -> Result<(), T::E> {
+        let old_value = self.output_port;
+        self.output_port = value;
+        if ((old_value ^ value) & OUTPUT_PORT_A20_BIT) != 0 {
+            self.events.a20_changed(self.a20_enabled());
+        }
+        if (value & OUTPUT_PORT_RESET_BIT) == 0 {
+            // The reset line is active low, so clearing the bit resets the CPU.
+            // The line is released as soon as the reset is handled.
+            self.output_port |= OUTPUT_PORT_RESET_BIT;
+            return self.request_reset();
+        }
+        Ok(())
+    }
+
+    fn set_kbd_leds(&mut self, leds: u8) {
+        if self.kbd_leds != leds {
+            self.kbd_leds = leds;
+            self.events.leds_changed(leds);
+        }
+    }
+
+    fn set_kbd_typematic(&mut self, typematic: u8) {
+        if self.kbd_typematic != typematic {
+            self.kbd_typematic = typematic;
+            self.events.typematic_changed(typematic);
+        }
+    }
+
+    // Handles a byte sent by the driver to the keyboard, which is either the
+    // parameter of the pending keyboard command, or a new command.
+    fn write_kbd(&mut self, value: u8) -> Result<(), T::E> {
+        match self.kbd_pending_command.take() {
+            Some(KBD_CMD_SET_LEDS) => self.set_kbd_leds(value & KBD_LEDS_MASK),
+            Some(KBD_CMD_SET_TYPEMATIC) => self.set_kbd_typematic(value & KBD_TYPEMATIC_MASK),
+            _ => return self.write_kbd_command(value),
+        }
+        self.push_output(&[KBD_ACK], false)?;
+        Ok(())
+    }
+
+    // Handles a command sent by the driver to the keyboard.
+    fn write_kbd_command(&mut self, value: u8) -> Result<(), T::E> {
+        match value {
+            KBD_CMD_SET_LEDS | KBD_CMD_SET_TYPEMATIC => {
+                self.kbd_pending_command = Some(value);
+                self.push_output(&[KBD_ACK], false)?;
+            }
+            KBD_CMD_ECHO => {
+                self.push_output(&[KBD_ECHO], false)?;
+            }
+            KBD_CMD_IDENTIFY => {
+                let mut id = KBD_ID;
+                if self.is_translating() {
+                    id = [translate(id[0]), translate(id[1])];
+                }
+                self.push_output(&[KBD_ACK, id[0], id[1]], false)?;
+            }
+            KBD_CMD_ENABLE_SCANNING => {
+                self.kbd_scanning = true;
+                self.push_output(&[KBD_ACK], false)?;
+            }
+            KBD_CMD_DISABLE_SCANNING => {
+                self.kbd_scanning = false;
+                self.push_output(&[KBD_ACK], false)?;
+            }
+            KBD_CMD_SET_DEFAULTS => {
+                self.set_kbd_typematic(DEFAULT_KBD_TYPEMATIC);
+                self.push_output(&[KBD_ACK], false)?;
+            }
+            KBD_CMD_RESET => {
+                self.kbd_scanning = true;
+                self.set_kbd_leds(0);
+                self.set_kbd_typematic(DEFAULT_KBD_TYPEMATIC);
+                self.push_output(&[KBD_ACK, KBD_BAT_OK], false)?;
+            }
+            _ => {
+                guest_warn!("Unknown keyboard command {:#x}", value);
+                self.events.unknown_command();
+                self.push_output(&[KBD_RESEND], false)?;
+            }
+        }
+        Ok(())
     }
 
     /// Handles a write request from the driver at `offset` offset from the
@@ -114,7 +1313,7 @@ impl<T: Trigger> I8042Device<T> {
     ///
     /// # Arguments
     /// * `offset` - The offset that will be added to the base address
-    ///              for writing to a specific register.
+    ///   for writing to a specific register.
     /// * `value` - The byte that should be written.
     ///
     /// # Example
@@ -122,25 +1321,459 @@ impl<T: Trigger> I8042Device<T> {
     /// You can see an example of how to use this function in the
     /// [`Example` section from `I8042Device`](struct.I8042Device.html#example).
     pub fn write(&mut self, offset: u8, value: u8) -> Result<(), T::E> {
+        trace_access!("i8042", "write", offset, value);
         match offset {
-            COMMAND_OFFSET if value == CMD_RESET_CPU => {
-                // Trigger the exit event.
-                self.reset_evt.trigger()
+            COMMAND_OFFSET => {
+                // A new command aborts the one waiting for its parameter.
+                self.pending_command = None;
+                self.last_write_command = true;
+                match value {
+                    CMD_READ_COMMAND_BYTE => {
+                        self.push_output(&[self.command_byte], false)?;
+                    }
+                    CMD_READ_OUTPUT_PORT => {
+                        self.push_output(&[self.output_port], false)?;
+                    }
+                    CMD_SELF_TEST => {
+                        self.push_output(&[SELF_TEST_OK], false)?;
+                    }
+                    CMD_TEST_AUX | CMD_TEST_KBD => {
+                        self.push_output(&[INTERFACE_TEST_OK], false)?;
+                    }
+                    CMD_DISABLE_KBD => self.command_byte |= CB_KBD_DISABLE,
+                    CMD_ENABLE_KBD => self.command_byte &= !CB_KBD_DISABLE,
+                    CMD_DISABLE_AUX => self.command_byte |= CB_AUX_DISABLE,
+                    CMD_ENABLE_AUX => self.command_byte &= !CB_AUX_DISABLE,
+                    CMD_WRITE_COMMAND_BYTE | CMD_WRITE_OUTPUT_PORT => {
+                        self.pending_command = Some(value)
+                    }
+                    CMD_PULSE_OUTPUT..=0xFF => return self.pulse_output(value),
+                    _ => {
+                        guest_warn!("Unknown i8042 command {:#x}", value);
+                        self.events.unknown_command()
+                    }
+                }
+                Ok(())
+            }
+            DATA_OFFSET => {
+                self.last_write_command = false;
+                match self.pending_command.take() {
+                    Some(CMD_WRITE_COMMAND_BYTE) => self.write_command_byte(value),
+                    Some(CMD_WRITE_OUTPUT_PORT) => self.write_output_port(value),
+                    // Bytes which are not command parameters are sent to the
+                    // keyboard.
+                    _ => self.write_kbd(value),
+                }
+            }
+            _ => {
+                guest_warn!("Invalid i8042 write at offset {:#x}", offset);
+                Ok(())
+            }
+        }
+    }
+
+    /// Sends bytes coming from the keyboard to the guest, by storing them in
+    /// the output buffer and raising the keyboard interrupt when enabled.
+    ///
+    /// The bytes are scancode set 2 codes, as sent by a PS/2 keyboard. When
+    /// the driver enabled the translation in the command byte, they are
+    /// translated to scancode set 1 before reaching the output buffer.
+    ///
+    /// Returns the number of bytes that were consumed. The other ones didn't
+    /// fit in the output buffer and are dropped, and so are all of them when
+    /// the driver disabled the keyboard scanning or the keyboard interface,
+    /// so that the caller can hold them and send them again later. The driver is told about
+    /// the dropped bytes through the keyboard overrun code, which is queued
+    /// as soon as it reads some of the data, while the VMM is told through
+    /// [`I8042Events::buffer_overflow`](trait.I8042Events.html#tymethod.buffer_overflow).
+    ///
+    /// # Arguments
+    /// * `bytes` - The keyboard data to be sent to the guest.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `I8042Device`](struct.I8042Device.html#example).
+    pub fn enqueue_kbd_bytes(&mut self, bytes: &[u8]) -> Result<usize, T::E> {
+        if !self.kbd_enabled() {
+            return Ok(0);
+        }
+
+        let count = self.queue_kbd_bytes(bytes)?;
+        if count < bytes.len() {
+            // The driver finds out about the lost data through the overrun
+            // code, which follows the data that is already queued.
+            self.kbd_overrun = true;
+            self.events.buffer_overflow(bytes.len() - count);
+        }
+        Ok(count)
+    }
+
+    fn queue_kbd_bytes(&mut self, bytes: &[u8]) -> Result<usize, T::E> {
+        if !self.is_translating() {
+            return self.queue_output(bytes, false);
+        }
+
+        for (count, &byte) in bytes.iter().enumerate() {
+            // Set 1 break codes are the make codes with the highest bit set,
+            // instead of being prefixed.
+            if byte == SET2_BREAK_PREFIX {
+                self.kbd_break_pending = true;
+                continue;
             }
-            _ => Ok(()),
+            let mut code = translate(byte);
+            if self.kbd_break_pending {
+                code |= SET1_BREAK_BIT;
+            }
+            if self.queue_output(&[code], false)? == 0 {
+                return Ok(count);
+            }
+            self.kbd_break_pending = false;
+        }
+        Ok(bytes.len())
+    }
+
+    /// Sends bytes coming from the auxiliary device (i.e. the mouse) to the
+    /// guest, by storing them in the output buffer and raising the auxiliary
+    /// device interrupt when enabled.
+    ///
+    /// Returns the number of bytes that fit in the output buffer. The other
+    /// ones are dropped, and so are all of them when the driver disabled the
+    /// auxiliary device interface.
+    ///
+    /// # Arguments
+    /// * `bytes` - The auxiliary device data to be sent to the guest.
+    pub fn enqueue_aux_bytes(&mut self, bytes: &[u8]) -> Result<usize, T::E> {
+        if (self.command_byte & CB_AUX_DISABLE) != 0 {
+            return Ok(0);
         }
+        self.push_output(bytes, true)
+    }
+
+    /// Sends a key combination to the guest: the keys are pressed in the
+    /// given order, and then released in the reverse order.
+    ///
+    /// The whole sequence is sent only if it fits in the output buffer, so
+    /// that the guest never sees a key that is pressed, but not released. It
+    /// fails with `Error::KeyboardDisabled` while the driver disabled the
+    /// keyboard scanning or the keyboard interface, instead of dropping it.
+    ///
+    /// # Arguments
+    /// * `keys` - The scancode set 2 make codes of the keys, including the
+    ///   0xE0 prefix for the extended keys.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use vm_superio::Trigger;
+    /// # use vm_superio::I8042Device;
+    /// # struct DummyTrigger;
+    /// # impl Trigger for DummyTrigger {
+    /// #     type E = ();
+    /// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+    /// # }
+    /// let mut i8042 = I8042Device::new(DummyTrigger);
+    ///
+    /// // Send Ctrl+Alt+F2, i.e. switch to the second virtual terminal.
+    /// i8042.send_key_combo(&[&[0x14], &[0x11], &[0x06]]).unwrap();
+    /// ```
+    pub fn send_key_combo(&mut self, keys: &[&[u8]]) -> Result<(), Error<T::E>> {
+        let keys: Vec<&[u8]> = keys.iter().copied().filter(|k| !k.is_empty()).collect();
+        let mut sequence: Vec<u8> = keys.concat();
+        // The set 2 break code of a key is its make code with the 0xF0 prefix
+        // placed right before the last byte.
+        for key in keys.iter().rev() {
+            let (last, prefix) = key.split_last().unwrap();
+            sequence.extend_from_slice(prefix);
+            sequence.extend_from_slice(&[SET2_BREAK_PREFIX, *last]);
+        }
+
+        self.enqueue_kbd_sequence(&sequence)
+    }
+
+    /// Sends a key press or release to the guest. The key is encoded using
+    /// scancode set 2, which the controller translates to set 1 when the
+    /// driver enabled the translation, so the frontends don't need their
+    /// own scancode tables.
+    ///
+    /// The event is sent only if the whole scancode sequence fits in the
+    /// output buffer and the keyboard is enabled, as for
+    /// [`send_key_combo`](#method.send_key_combo).
+    ///
+    /// # Arguments
+    /// * `event` - The key event to be sent to the guest.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use vm_superio::i8042::{KeyCode, KeyEvent};
+    /// # use vm_superio::{I8042Device, Trigger};
+    /// # struct DummyTrigger;
+    /// # impl Trigger for DummyTrigger {
+    /// #     type E = ();
+    /// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+    /// # }
+    /// let mut i8042 = I8042Device::new(DummyTrigger);
+    ///
+    /// for &pressed in [true, false].iter() {
+    ///     let event = KeyEvent {
+    ///         key: KeyCode::ArrowUp,
+    ///         pressed,
+    ///     };
+    ///     i8042.send_key_event(event).unwrap();
+    /// }
+    /// ```
+    pub fn send_key_event(&mut self, event: KeyEvent) -> Result<(), Error<T::E>> {
+        if event.pressed {
+            self.enqueue_kbd_sequence(event.key.make_code())
+        } else {
+            self.enqueue_kbd_sequence(&event.key.break_code())
+        }
+    }
+
+    // Whether the driver lets the keyboard send its input, i.e. it enabled
+    // both the keyboard scanning and the keyboard interface.
+    fn kbd_enabled(&self) -> bool {
+        self.kbd_scanning && (self.command_byte & CB_KBD_DISABLE) == 0
+    }
+
+    // Sends a set 2 scancode sequence to the guest, only if all of it fits in
+    // the output buffer.
+    fn enqueue_kbd_sequence(&mut self, sequence: &[u8]) -> Result<(), Error<T::E>> {
+        if !sequence.is_empty() && !self.kbd_enabled() {
+            return Err(Error::KeyboardDisabled);
+        }
+        // The break prefixes don't reach the output buffer when translating.
+        let mut len = sequence.len();
+        if self.is_translating() {
+            len -= sequence.iter().filter(|&&b| b == SET2_BREAK_PREFIX).count();
+        }
+        if len > BUFFER_SIZE - self.kbd_buffer.len() {
+            return Err(Error::FullBuffer);
+        }
+
+        self.enqueue_kbd_bytes(sequence).map_err(Error::Trigger)?;
+        Ok(())
+    }
+
+    /// Sends the Alt+SysRq+`key` sequence to the guest, which triggers the
+    /// corresponding magic SysRq action of a Linux guest (e.g. `KeyCode::KeyC`
+    /// crashes the guest, which can be used for testing crash dumps).
+    ///
+    /// Alt and SysRq are pressed first, then `key` is pressed and released,
+    /// and finally SysRq and Alt are released. The sequence is sent only if
+    /// all of it fits in the output buffer and the keyboard is enabled, as
+    /// for [`send_key_combo`](#method.send_key_combo).
+    ///
+    /// # Arguments
+    /// * `key` - The key selecting the SysRq action.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use vm_superio::i8042::KeyCode;
+    /// # use vm_superio::{I8042Device, Trigger};
+    /// # struct DummyTrigger;
+    /// # impl Trigger for DummyTrigger {
+    /// #     type E = ();
+    /// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+    /// # }
+    /// let mut i8042 = I8042Device::new(DummyTrigger);
+    ///
+    /// // Sync the guest filesystems.
+    /// i8042.send_sysrq(KeyCode::KeyS).unwrap();
+    /// ```
+    pub fn send_sysrq(&mut self, key: KeyCode) -> Result<(), Error<T::E>> {
+        let sequence = [
+            KeyCode::AltLeft.make_code(),
+            &[SET2_SYSRQ],
+            key.make_code(),
+            &key.break_code(),
+            &[SET2_BREAK_PREFIX, SET2_SYSRQ],
+            &KeyCode::AltLeft.break_code(),
+        ]
+        .concat();
+        self.enqueue_kbd_sequence(&sequence)
+    }
+
+    /// Sends the Ctrl+Alt+Del key combination to the guest.
+    ///
+    /// This is a shorthand for [`send_key_combo`](#method.send_key_combo)
+    /// with the Left Ctrl, Left Alt and Delete keys.
+    pub fn send_ctrl_alt_del(&mut self) -> Result<(), Error<T::E>> {
+        self.send_key_combo(&[
+            KeyCode::ControlLeft.make_code(),
+            KeyCode::AltLeft.make_code(),
+            KeyCode::Delete.make_code(),
+        ])
+    }
+}
+
+impl<T: Trigger, EV: I8042Events> LegacyDevice for I8042Device<T, EV> {
+    type State = I8042State;
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = I8042Device::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            let _ = I8042Device::write(self, offset, data[0]);
+        }
+    }
+
+    // The bytes waiting in the output buffer are dropped, and the actions of
+    // the output lines are kept.
+    fn reset(&mut self) {
+        let a20_enabled = self.a20_enabled();
+        let state = I8042State::default();
+        self.command_byte = state.command_byte;
+        self.output_port = state.output_port;
+        self.pending_command = state.pending_command;
+        self.last_write_command = state.last_write_command;
+        self.kbd_pending_command = state.kbd_pending_command;
+        self.kbd_scanning = state.kbd_scanning;
+        self.kbd_leds = state.kbd_leds;
+        self.kbd_typematic = state.kbd_typematic;
+        self.kbd_break_pending = state.kbd_break_pending;
+        self.kbd_overrun = state.kbd_overrun;
+        self.kbd_buffer.clear();
+        self.aux_buffer.clear();
+        if self.a20_enabled() != a20_enabled {
+            self.events.a20_changed(self.a20_enabled());
+        }
+    }
+
+    fn state(&self) -> Self::State {
+        I8042Device::state(self)
+    }
+}
+
+// The controller is restored with its reset, keyboard and auxiliary device
+// `Trigger` objects, and its events object.
+impl<T: Trigger, EV: I8042Events> Persist for I8042Device<T, EV> {
+    type State = I8042State;
+    type ConstructorArgs = (T, T, T, EV);
+    type Error = Error<T::E>;
+
+    fn save(&self) -> Self::State {
+        self.state()
+    }
+
+    fn restore(
+        (reset_evt, kbd_evt, aux_evt, i8042_evts): Self::ConstructorArgs,
+        state: &Self::State,
+    ) -> Result<Self, Self::Error> {
+        Self::from_state(state, reset_evt, kbd_evt, aux_evt, i8042_evts)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io;
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
     use vmm_sys_util::eventfd::EventFd;
+    use vmm_sys_util::metric::Metric;
+
+    // Pulse the system reset line only.
+    const CMD_RESET_CPU: u8 = 0xFE;
+
+    struct FailingTrigger;
+
+    impl Trigger for FailingTrigger {
+        type E = ();
+
+        fn trigger(&self) -> Result<(), ()> {
+            Err(())
+        }
+    }
+
+    struct ExampleI8042Events {
+        reset_count: AtomicU64,
+        dropped_count: AtomicU64,
+        unknown_command_count: AtomicU64,
+        interrupt_failed_count: AtomicU64,
+        leds: AtomicU64,
+        typematic: AtomicU64,
+        pulsed_lines: AtomicU64,
+        a20_changes: AtomicU64,
+        a20_enabled: AtomicBool,
+    }
+
+    impl ExampleI8042Events {
+        fn new() -> Self {
+            ExampleI8042Events {
+                reset_count: AtomicU64::new(0),
+                dropped_count: AtomicU64::new(0),
+                unknown_command_count: AtomicU64::new(0),
+                interrupt_failed_count: AtomicU64::new(0),
+                leds: AtomicU64::new(0),
+                typematic: AtomicU64::new(DEFAULT_KBD_TYPEMATIC as u64),
+                pulsed_lines: AtomicU64::new(0),
+                a20_changes: AtomicU64::new(0),
+                a20_enabled: AtomicBool::new(true),
+            }
+        }
+    }
+
+    impl I8042Events for ExampleI8042Events {
+        fn reset_requested(&self) {
+            self.reset_count.inc();
+        }
+
+        fn buffer_overflow(&self, dropped: usize) {
+            self.dropped_count.add(dropped as u64);
+        }
+
+        fn unknown_command(&self) {
+            self.unknown_command_count.inc();
+        }
+
+        fn interrupt_failed(&self) {
+            self.interrupt_failed_count.inc();
+        }
+
+        fn leds_changed(&self, leds: u8) {
+            self.leds.store(leds as u64, Ordering::Relaxed);
+        }
+
+        fn typematic_changed(&self, typematic: u8) {
+            self.typematic.store(typematic as u64, Ordering::Relaxed);
+        }
+
+        fn line_pulsed(&self, line: u8) {
+            self.pulsed_lines.fetch_or(1 << line, Ordering::Relaxed);
+        }
+
+        fn a20_changed(&self, enabled: bool) {
+            self.a20_changes.inc();
+            self.a20_enabled.store(enabled, Ordering::Relaxed);
+        }
+    }
+
+    fn new_i8042() -> (I8042Device<EventFd, NoEvents>, EventFd, EventFd, EventFd) {
+        let reset_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let kbd_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let aux_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let i8042 = I8042Device::with_irqs(
+            reset_evt.try_clone().unwrap(),
+            kbd_evt.try_clone().unwrap(),
+            aux_evt.try_clone().unwrap(),
+        );
+        (i8042, reset_evt, kbd_evt, aux_evt)
+    }
+
+    fn assert_not_triggered(evt: &EventFd) {
+        assert_eq!(evt.read().unwrap_err().kind(), io::ErrorKind::WouldBlock);
+    }
 
     #[test]
     fn test_i8042_valid_ops() {
-        let reset_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
-        let mut i8042 = I8042Device::new(reset_evt.try_clone().unwrap());
+        let (mut i8042, reset_evt, _, _) = new_i8042();
 
         assert_eq!(i8042.read(0), 0);
 
@@ -151,8 +1784,7 @@ mod tests {
 
     #[test]
     fn test_i8042_invalid_reset() {
-        let reset_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
-        let mut i8042 = I8042Device::new(reset_evt.try_clone().unwrap());
+        let (mut i8042, reset_evt, _, _) = new_i8042();
 
         // Write something different than CPU reset and check that the reset event
         // was not triggered. For this we have to write 1 to the reset event fd, so
@@ -170,4 +1802,996 @@ mod tests {
         i8042.write(COMMAND_OFFSET + 1, CMD_RESET_CPU).unwrap();
         assert_eq!(reset_evt.read().unwrap(), 1);
     }
+
+    #[test]
+    fn test_i8042_pulse_output() {
+        let reset_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let events = Arc::new(ExampleI8042Events::new());
+        let mut i8042 = I8042Device::with_events(
+            reset_evt.try_clone().unwrap(),
+            evt.try_clone().unwrap(),
+            evt.try_clone().unwrap(),
+            events.clone(),
+        );
+
+        // By default, only line 0 resets the CPU, while the other lines are
+        // ignored.
+        i8042.write(COMMAND_OFFSET, CMD_PULSE_OUTPUT).unwrap();
+        assert_eq!(reset_evt.read().unwrap(), 1);
+        i8042.write(COMMAND_OFFSET, 0xFD).unwrap();
+        assert_not_triggered(&reset_evt);
+        assert_eq!(events.pulsed_lines.load(Ordering::Relaxed), 0);
+        assert_eq!(events.unknown_command_count.count(), 0);
+
+        i8042.set_pulse_actions([
+            PulseAction::Notify,
+            PulseAction::Notify,
+            PulseAction::Reset,
+            PulseAction::Ignore,
+        ]);
+        // Pulse lines 1 and 3.
+        i8042.write(COMMAND_OFFSET, 0xF5).unwrap();
+        assert_not_triggered(&reset_evt);
+        assert_eq!(events.pulsed_lines.load(Ordering::Relaxed), 0b0010);
+        // Pulse lines 0 and 2.
+        i8042.write(COMMAND_OFFSET, 0xFA).unwrap();
+        assert_eq!(reset_evt.read().unwrap(), 1);
+        assert_eq!(events.pulsed_lines.load(Ordering::Relaxed), 0b0011);
+        assert_eq!(events.reset_count.count(), 2);
+
+        // No line is pulsed.
+        i8042.write(COMMAND_OFFSET, 0xFF).unwrap();
+        assert_not_triggered(&reset_evt);
+        assert_eq!(events.reset_count.count(), 2);
+    }
+
+    #[test]
+    fn test_i8042_a20_events() {
+        let evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let events = Arc::new(ExampleI8042Events::new());
+        let mut i8042 = I8042Device::with_events(
+            evt.try_clone().unwrap(),
+            evt.try_clone().unwrap(),
+            evt.try_clone().unwrap(),
+            events.clone(),
+        );
+
+        // Disable the A20 gate.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_OUTPUT_PORT).unwrap();
+        i8042.write(DATA_OFFSET, OUTPUT_PORT_RESET_BIT).unwrap();
+        assert_eq!(events.a20_changes.count(), 1);
+        assert!(!events.a20_enabled.load(Ordering::Relaxed));
+
+        // Writing the same value is not a transition.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_OUTPUT_PORT).unwrap();
+        i8042.write(DATA_OFFSET, OUTPUT_PORT_RESET_BIT).unwrap();
+        assert_eq!(events.a20_changes.count(), 1);
+
+        // Enable the A20 gate while resetting the CPU.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_OUTPUT_PORT).unwrap();
+        i8042.write(DATA_OFFSET, OUTPUT_PORT_A20_BIT).unwrap();
+        assert_eq!(events.a20_changes.count(), 2);
+        assert!(events.a20_enabled.load(Ordering::Relaxed));
+        assert_eq!(events.reset_count.count(), 1);
+    }
+
+    #[test]
+    fn test_i8042_output_port() {
+        let (mut i8042, reset_evt, _, _) = new_i8042();
+
+        // The A20 gate is enabled by default, and the status register reports
+        // an empty output buffer.
+        assert!(i8042.a20_enabled());
+        assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+
+        // Reading the output port places its value in the output buffer.
+        i8042.write(COMMAND_OFFSET, CMD_READ_OUTPUT_PORT).unwrap();
+        assert_ne!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+        assert_eq!(i8042.read(DATA_OFFSET), DEFAULT_OUTPUT_PORT);
+        assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+
+        // Disable the A20 gate while keeping the reset line high.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_OUTPUT_PORT).unwrap();
+        i8042.write(DATA_OFFSET, OUTPUT_PORT_RESET_BIT).unwrap();
+        assert!(!i8042.a20_enabled());
+        assert_not_triggered(&reset_evt);
+
+        i8042.write(COMMAND_OFFSET, CMD_READ_OUTPUT_PORT).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), OUTPUT_PORT_RESET_BIT);
+
+        // A data port write that is not a command parameter leaves the output
+        // port unchanged.
+        i8042.write(DATA_OFFSET, DEFAULT_OUTPUT_PORT).unwrap();
+        assert!(!i8042.a20_enabled());
+        // The keyboard does not know such a command.
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_RESEND);
+
+        // Re-enable the A20 gate.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_OUTPUT_PORT).unwrap();
+        i8042.write(DATA_OFFSET, DEFAULT_OUTPUT_PORT).unwrap();
+        assert!(i8042.a20_enabled());
+
+        // The driver cannot grow the output buffer past its size.
+        for _ in 0..BUFFER_SIZE + 1 {
+            i8042.write(COMMAND_OFFSET, CMD_READ_OUTPUT_PORT).unwrap();
+        }
+        assert_eq!(i8042.kbd_buffer.len(), BUFFER_SIZE);
+    }
+
+    #[test]
+    fn test_i8042_output_port_reset() {
+        let (mut i8042, reset_evt, _, _) = new_i8042();
+
+        // Pulling the reset line low through the output port resets the CPU.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_OUTPUT_PORT).unwrap();
+        i8042.write(DATA_OFFSET, OUTPUT_PORT_A20_BIT).unwrap();
+        assert_eq!(reset_evt.read().unwrap(), 1);
+
+        // The reset line is released afterwards.
+        i8042.write(COMMAND_OFFSET, CMD_READ_OUTPUT_PORT).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), DEFAULT_OUTPUT_PORT);
+    }
+
+    #[test]
+    fn test_i8042_inhibit() {
+        let (mut i8042, _, kbd_evt, aux_evt) = new_i8042();
+
+        // While the keyboard interface is disabled, its input is not consumed,
+        // while the controller responses still reach the driver.
+        i8042.write(COMMAND_OFFSET, CMD_DISABLE_KBD).unwrap();
+        assert_eq!(i8042.enqueue_kbd_bytes(&[0x1C]).unwrap(), 0);
+        assert_eq!(i8042.enqueue_aux_bytes(&[0x08]).unwrap(), 1);
+        assert_eq!(aux_evt.read().unwrap(), 1);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x08);
+        i8042.write(COMMAND_OFFSET, CMD_READ_COMMAND_BYTE).unwrap();
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+        assert_eq!(
+            i8042.read(DATA_OFFSET),
+            DEFAULT_COMMAND_BYTE | CB_KBD_DISABLE
+        );
+
+        i8042.write(COMMAND_OFFSET, CMD_ENABLE_KBD).unwrap();
+        assert_eq!(i8042.enqueue_kbd_bytes(&[0x1C]).unwrap(), 1);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x1E);
+
+        // Same for the auxiliary device interface.
+        i8042.write(COMMAND_OFFSET, CMD_DISABLE_AUX).unwrap();
+        assert_eq!(i8042.enqueue_aux_bytes(&[0x08]).unwrap(), 0);
+        assert_eq!(i8042.enqueue_kbd_bytes(&[0x1C]).unwrap(), 1);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x1E);
+        i8042.write(COMMAND_OFFSET, CMD_READ_COMMAND_BYTE).unwrap();
+        assert_eq!(
+            i8042.read(DATA_OFFSET),
+            DEFAULT_COMMAND_BYTE | CB_AUX_DISABLE
+        );
+        i8042.write(COMMAND_OFFSET, CMD_ENABLE_AUX).unwrap();
+        assert_eq!(i8042.enqueue_aux_bytes(&[0x08]).unwrap(), 1);
+
+        // The interfaces can be disabled through the command byte as well.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_COMMAND_BYTE).unwrap();
+        i8042
+            .write(DATA_OFFSET, CB_KBD_DISABLE | CB_AUX_DISABLE)
+            .unwrap();
+        assert_eq!(i8042.enqueue_kbd_bytes(&[0x1C]).unwrap(), 0);
+        assert_eq!(i8042.enqueue_aux_bytes(&[0x08]).unwrap(), 0);
+        assert_eq!(i8042.state().command_byte, CB_KBD_DISABLE | CB_AUX_DISABLE);
+    }
+
+    #[test]
+    fn test_i8042_status() {
+        let (mut i8042, _, _, _) = new_i8042();
+
+        assert_eq!(i8042.read(COMMAND_OFFSET), SB_KBD_UNLOCKED | SB_SYS_FLAG);
+
+        // The output buffer holds a response of the controller.
+        i8042.write(COMMAND_OFFSET, CMD_SELF_TEST).unwrap();
+        assert_eq!(
+            i8042.read(COMMAND_OFFSET),
+            SB_KBD_UNLOCKED | SB_LAST_WRITE_COMMAND | SB_SYS_FLAG | SB_OUT_DATA_AVAIL
+        );
+        // Reading the status doesn't consume the data.
+        assert_ne!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+        assert_eq!(i8042.read(DATA_OFFSET), SELF_TEST_OK);
+
+        // Clear the system flag through the command byte.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_COMMAND_BYTE).unwrap();
+        i8042.write(DATA_OFFSET, CB_KBD_INT | CB_AUX_INT).unwrap();
+        assert_eq!(i8042.read(COMMAND_OFFSET), SB_KBD_UNLOCKED);
+
+        // The keyboard data is read first, and the status reports the
+        // auxiliary device data only once it is the next one to be read.
+        i8042.enqueue_aux_bytes(&[0x08]).unwrap();
+        assert_eq!(
+            i8042.read(COMMAND_OFFSET),
+            SB_KBD_UNLOCKED | SB_AUX_DATA | SB_OUT_DATA_AVAIL
+        );
+        i8042.enqueue_kbd_bytes(&[0x1C]).unwrap();
+        assert_eq!(
+            i8042.read(COMMAND_OFFSET),
+            SB_KBD_UNLOCKED | SB_OUT_DATA_AVAIL
+        );
+        assert_eq!(i8042.read(DATA_OFFSET), 0x1C);
+        assert_eq!(
+            i8042.read(COMMAND_OFFSET),
+            SB_KBD_UNLOCKED | SB_AUX_DATA | SB_OUT_DATA_AVAIL
+        );
+        assert_eq!(i8042.read(DATA_OFFSET), 0x08);
+        assert_eq!(i8042.read(COMMAND_OFFSET), SB_KBD_UNLOCKED);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x00);
+    }
+
+    #[test]
+    fn test_i8042_command_byte() {
+        let (mut i8042, _, kbd_evt, _) = new_i8042();
+
+        i8042.write(COMMAND_OFFSET, CMD_READ_COMMAND_BYTE).unwrap();
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+        assert_eq!(i8042.read(DATA_OFFSET), DEFAULT_COMMAND_BYTE);
+
+        // Disable the interrupts.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_COMMAND_BYTE).unwrap();
+        i8042.write(DATA_OFFSET, CB_SYS_FLAG).unwrap();
+        assert_not_triggered(&kbd_evt);
+
+        // The command byte is still readable, but no interrupt is raised.
+        i8042.write(COMMAND_OFFSET, CMD_READ_COMMAND_BYTE).unwrap();
+        assert_not_triggered(&kbd_evt);
+        assert_ne!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+        assert_eq!(i8042.read(DATA_OFFSET), CB_SYS_FLAG);
+
+        // A command received while waiting for a parameter aborts the pending one.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_COMMAND_BYTE).unwrap();
+        i8042.write(COMMAND_OFFSET, CMD_READ_OUTPUT_PORT).unwrap();
+        i8042.write(DATA_OFFSET, 0xFF).unwrap();
+        assert_eq!(i8042.command_byte, CB_SYS_FLAG);
+        assert_eq!(i8042.read(DATA_OFFSET), DEFAULT_OUTPUT_PORT);
+    }
+
+    #[test]
+    fn test_i8042_self_tests() {
+        let (mut i8042, _, kbd_evt, _) = new_i8042();
+
+        i8042.write(COMMAND_OFFSET, CMD_SELF_TEST).unwrap();
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+        assert_ne!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+        assert_eq!(i8042.read(DATA_OFFSET), SELF_TEST_OK);
+
+        i8042.write(COMMAND_OFFSET, CMD_TEST_KBD).unwrap();
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+        assert_ne!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+        assert_eq!(i8042.read(DATA_OFFSET), INTERFACE_TEST_OK);
+
+        i8042.write(COMMAND_OFFSET, CMD_TEST_AUX).unwrap();
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+        assert_ne!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+        assert_eq!(i8042.read(DATA_OFFSET), INTERFACE_TEST_OK);
+
+        assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+    }
+
+    #[test]
+    fn test_i8042_kbd_commands() {
+        let (mut i8042, _, kbd_evt, _) = new_i8042();
+
+        i8042.write(DATA_OFFSET, KBD_CMD_RESET).unwrap();
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_BAT_OK);
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+
+        i8042.write(DATA_OFFSET, KBD_CMD_IDENTIFY).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        // The identification bytes are translated as well.
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ID[0]);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x41);
+
+        i8042.write(DATA_OFFSET, KBD_CMD_ECHO).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ECHO);
+
+        i8042.write(DATA_OFFSET, KBD_CMD_SET_DEFAULTS).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+
+        // While scanning is disabled, the keyboard input is dropped.
+        i8042.write(DATA_OFFSET, KBD_CMD_DISABLE_SCANNING).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        assert_eq!(i8042.enqueue_kbd_bytes(&[0x1C]).unwrap(), 0);
+        assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+
+        i8042.write(DATA_OFFSET, KBD_CMD_ENABLE_SCANNING).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        assert_eq!(i8042.enqueue_kbd_bytes(&[0x1C]).unwrap(), 1);
+        assert_eq!(i8042.read(DATA_OFFSET), translate(0x1C));
+
+        // Resetting the keyboard enables scanning as well.
+        i8042.write(DATA_OFFSET, KBD_CMD_DISABLE_SCANNING).unwrap();
+        i8042.write(DATA_OFFSET, KBD_CMD_RESET).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_BAT_OK);
+        assert_eq!(i8042.enqueue_kbd_bytes(&[0x1C]).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_i8042_kbd_leds_and_typematic() {
+        let evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let events = Arc::new(ExampleI8042Events::new());
+        let mut i8042 = I8042Device::with_events(
+            evt.try_clone().unwrap(),
+            evt.try_clone().unwrap(),
+            evt.try_clone().unwrap(),
+            events.clone(),
+        );
+
+        // Turn on Num Lock and Caps Lock. Both the command and its parameter
+        // are acknowledged.
+        i8042.write(DATA_OFFSET, KBD_CMD_SET_LEDS).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        i8042.write(DATA_OFFSET, 0b0000_0110).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        assert_eq!(i8042.kbd_leds, 0b0000_0110);
+        assert_eq!(events.leds.load(Ordering::Relaxed), 0b0000_0110);
+
+        // The reserved bits are ignored.
+        i8042.write(DATA_OFFSET, KBD_CMD_SET_LEDS).unwrap();
+        i8042.write(DATA_OFFSET, 0xF9).unwrap();
+        assert_eq!(events.leds.load(Ordering::Relaxed), 0b0000_0001);
+
+        // Set the fastest rate, after the shortest delay.
+        i8042.write(DATA_OFFSET, KBD_CMD_SET_TYPEMATIC).unwrap();
+        i8042.write(DATA_OFFSET, 0x80).unwrap();
+        assert_eq!(events.typematic.load(Ordering::Relaxed), 0);
+        for _ in 0..4 {
+            assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        }
+
+        // The parameter is a value, even when it matches a command.
+        i8042.write(DATA_OFFSET, KBD_CMD_SET_TYPEMATIC).unwrap();
+        i8042.write(DATA_OFFSET, KBD_CMD_ECHO).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        assert_eq!(events.typematic.load(Ordering::Relaxed), 0x6E);
+
+        // Restoring the defaults only affects the typematic byte, while the
+        // reset turns off the LEDs as well.
+        i8042.write(DATA_OFFSET, KBD_CMD_SET_DEFAULTS).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        assert_eq!(events.typematic.load(Ordering::Relaxed), 0x2B);
+        assert_eq!(events.leds.load(Ordering::Relaxed), 0b0000_0001);
+        i8042.write(DATA_OFFSET, KBD_CMD_RESET).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_BAT_OK);
+        assert_eq!(events.leds.load(Ordering::Relaxed), 0);
+        assert_eq!(i8042.state(), I8042State::default());
+    }
+
+    #[test]
+    fn test_i8042_translation() {
+        let (mut i8042, _, _, _) = new_i8042();
+
+        // Press and release 'A', Right Ctrl and F7.
+        let set2 = [
+            0x1C, 0xF0, 0x1C, 0xE0, 0x14, 0xE0, 0xF0, 0x14, 0x83, 0xF0, 0x83,
+        ];
+        let set1 = [0x1E, 0x9E, 0xE0, 0x1D, 0xE0, 0x9D, 0x41, 0xC1];
+
+        // The translation is enabled by default.
+        assert_eq!(i8042.enqueue_kbd_bytes(&set2).unwrap(), set2.len());
+        for &code in set1.iter() {
+            assert_eq!(i8042.read(DATA_OFFSET), code);
+        }
+        assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+
+        // A break code split across calls is translated as well.
+        i8042.enqueue_kbd_bytes(&[0xF0]).unwrap();
+        assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+        i8042.enqueue_kbd_bytes(&[0x1C]).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), 0x9E);
+
+        // When the output buffer is full, the bytes which were not consumed
+        // can be sent again.
+        let presses = [0x1C; BUFFER_SIZE];
+        i8042.enqueue_kbd_bytes(&presses).unwrap();
+        assert_eq!(i8042.enqueue_kbd_bytes(&[0xF0, 0x1C]).unwrap(), 1);
+        // The first byte read makes room for the overrun code.
+        i8042.read(DATA_OFFSET);
+        assert_eq!(*i8042.kbd_buffer.back().unwrap(), translate(KBD_OVERRUN));
+        i8042.read(DATA_OFFSET);
+        assert_eq!(i8042.enqueue_kbd_bytes(&[0x1C]).unwrap(), 1);
+        assert_eq!(*i8042.kbd_buffer.back().unwrap(), 0x9E);
+
+        // Without translation, the set 2 codes reach the driver unchanged.
+        i8042.kbd_buffer.clear();
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_COMMAND_BYTE).unwrap();
+        i8042.write(DATA_OFFSET, CB_KBD_INT).unwrap();
+        assert_eq!(i8042.enqueue_kbd_bytes(&set2).unwrap(), set2.len());
+        for &code in set2.iter() {
+            assert_eq!(i8042.read(DATA_OFFSET), code);
+        }
+    }
+
+    #[test]
+    fn test_i8042_overrun() {
+        let (mut i8042, _, _, _) = new_i8042();
+
+        // Half of the data doesn't fit in the output buffer.
+        let data = [0x1C; 2 * BUFFER_SIZE];
+        assert_eq!(i8042.enqueue_kbd_bytes(&data).unwrap(), BUFFER_SIZE);
+        assert!(i8042.state().kbd_overrun);
+        assert_eq!(i8042.enqueue_kbd_bytes(&data).unwrap(), 0);
+
+        // The overrun code takes the space freed by the first read, so it
+        // follows the data which was already queued.
+        assert_eq!(i8042.read(DATA_OFFSET), 0x1E);
+        assert!(!i8042.state().kbd_overrun);
+        assert_eq!(i8042.enqueue_kbd_bytes(&data).unwrap(), 0);
+        for _ in 1..BUFFER_SIZE {
+            assert_eq!(i8042.read(DATA_OFFSET), 0x1E);
+        }
+        assert_eq!(i8042.read(DATA_OFFSET), translate(KBD_OVERRUN));
+        // The last overflow is reported after the first overrun code.
+        assert_eq!(i8042.read(DATA_OFFSET), translate(KBD_OVERRUN));
+        assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+
+        // Without translation, the set 2 overrun code is sent. A restored
+        // device queues it right away if there is space for it.
+        let state = I8042State {
+            command_byte: CB_KBD_INT,
+            kbd_overrun: true,
+            ..Default::default()
+        };
+        let evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let mut i8042 = I8042Device::from_state(
+            &state,
+            evt.try_clone().unwrap(),
+            evt.try_clone().unwrap(),
+            evt.try_clone().unwrap(),
+            NoEvents,
+        )
+        .unwrap();
+        assert_eq!(evt.read().unwrap(), 1);
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_OVERRUN);
+        assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+    }
+
+    #[test]
+    fn test_i8042_ctrl_alt_del() {
+        let (mut i8042, _, kbd_evt, _) = new_i8042();
+
+        // Translated to scancode set 1.
+        i8042.send_ctrl_alt_del().unwrap();
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+        let set1 = [0x1D, 0x38, 0xE0, 0x53, 0xE0, 0xD3, 0xB8, 0x9D];
+        for &code in set1.iter() {
+            assert_eq!(i8042.read(DATA_OFFSET), code);
+        }
+        assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+
+        // As sent by the keyboard.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_COMMAND_BYTE).unwrap();
+        i8042.write(DATA_OFFSET, CB_KBD_INT).unwrap();
+        i8042.send_ctrl_alt_del().unwrap();
+        let set2 = [
+            0x14, 0x11, 0xE0, 0x71, 0xE0, 0xF0, 0x71, 0xF0, 0x11, 0xF0, 0x14,
+        ];
+        for &code in set2.iter() {
+            assert_eq!(i8042.read(DATA_OFFSET), code);
+        }
+        assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+    }
+
+    #[test]
+    fn test_i8042_key_combo() {
+        let (mut i8042, _, _, _) = new_i8042();
+
+        // Empty keys are ignored.
+        i8042.send_key_combo(&[]).unwrap();
+        i8042.send_key_combo(&[&[], &[0x1C]]).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), 0x1E);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x9E);
+        assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+
+        // A combination which doesn't fit in the output buffer is not sent at
+        // all.
+        i8042.enqueue_kbd_bytes(&[0x1C; BUFFER_SIZE - 7]).unwrap();
+        assert!(matches!(i8042.send_ctrl_alt_del(), Err(Error::FullBuffer)));
+        assert_eq!(i8042.kbd_buffer.len(), BUFFER_SIZE - 7);
+        i8042.read(DATA_OFFSET);
+        i8042.send_ctrl_alt_del().unwrap();
+        assert_eq!(i8042.kbd_buffer.len(), BUFFER_SIZE);
+    }
+
+    #[test]
+    fn test_i8042_keyboard_disabled() {
+        let (mut i8042, _, kbd_evt, _) = new_i8042();
+        let event = KeyEvent {
+            key: KeyCode::KeyA,
+            pressed: true,
+        };
+
+        // The driver disables the keyboard scanning.
+        i8042.write(DATA_OFFSET, KBD_CMD_DISABLE_SCANNING).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+        assert!(matches!(
+            i8042.send_ctrl_alt_del(),
+            Err(Error::KeyboardDisabled)
+        ));
+        assert!(matches!(
+            i8042.send_key_event(event),
+            Err(Error::KeyboardDisabled)
+        ));
+        assert!(matches!(
+            i8042.send_sysrq(KeyCode::KeyS),
+            Err(Error::KeyboardDisabled)
+        ));
+        // There is nothing to drop.
+        i8042.send_key_combo(&[]).unwrap();
+        assert!(i8042.kbd_buffer.is_empty());
+        assert!(kbd_evt.read().is_err());
+
+        i8042.write(DATA_OFFSET, KBD_CMD_ENABLE_SCANNING).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_ACK);
+        i8042.send_key_event(event).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), 0x1E);
+
+        // The driver disables the keyboard interface.
+        i8042.write(COMMAND_OFFSET, CMD_DISABLE_KBD).unwrap();
+        assert!(matches!(
+            i8042.send_ctrl_alt_del(),
+            Err(Error::KeyboardDisabled)
+        ));
+        assert!(matches!(
+            i8042.send_key_event(event),
+            Err(Error::KeyboardDisabled)
+        ));
+        assert!(matches!(
+            i8042.send_sysrq(KeyCode::KeyS),
+            Err(Error::KeyboardDisabled)
+        ));
+        assert!(i8042.kbd_buffer.is_empty());
+
+        i8042.write(COMMAND_OFFSET, CMD_ENABLE_KBD).unwrap();
+        i8042.send_sysrq(KeyCode::KeyS).unwrap();
+        assert_eq!(i8042.read(DATA_OFFSET), 0x38);
+    }
+
+    #[test]
+    fn test_i8042_key_events() {
+        let (mut i8042, _, _, _) = new_i8042();
+        let press = |key| KeyEvent { key, pressed: true };
+        let release = |key| KeyEvent {
+            key,
+            pressed: false,
+        };
+
+        // The events are translated to set 1 by default.
+        let events = [
+            (press(KeyCode::KeyA), vec![0x1E]),
+            (release(KeyCode::KeyA), vec![0x9E]),
+            (press(KeyCode::ArrowUp), vec![0xE0, 0x48]),
+            (release(KeyCode::ArrowUp), vec![0xE0, 0xC8]),
+            (press(KeyCode::PrintScreen), vec![0xE0, 0x2A, 0xE0, 0x37]),
+            (release(KeyCode::PrintScreen), vec![0xE0, 0xB7, 0xE0, 0xAA]),
+            (
+                press(KeyCode::Pause),
+                vec![0xE1, 0x1D, 0x45, 0xE1, 0x9D, 0xC5],
+            ),
+            (release(KeyCode::Pause), vec![]),
+            (press(KeyCode::F7), vec![0x41]),
+            (release(KeyCode::NumpadEnter), vec![0xE0, 0x9C]),
+        ];
+        for (event, set1) in events.iter() {
+            i8042.send_key_event(*event).unwrap();
+            for &code in set1.iter() {
+                assert_eq!(i8042.read(DATA_OFFSET), code);
+            }
+            assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+        }
+
+        // Without translation, the guest gets the set 2 codes.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_COMMAND_BYTE).unwrap();
+        i8042.write(DATA_OFFSET, CB_KBD_INT).unwrap();
+        let events = [
+            (press(KeyCode::KeyA), vec![0x1C]),
+            (release(KeyCode::KeyA), vec![0xF0, 0x1C]),
+            (release(KeyCode::ControlRight), vec![0xE0, 0xF0, 0x14]),
+            (
+                release(KeyCode::PrintScreen),
+                vec![0xE0, 0xF0, 0x7C, 0xE0, 0xF0, 0x12],
+            ),
+        ];
+        for (event, set2) in events.iter() {
+            i8042.send_key_event(*event).unwrap();
+            for &code in set2.iter() {
+                assert_eq!(i8042.read(DATA_OFFSET), code);
+            }
+            assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+        }
+
+        // An event which doesn't fit in the output buffer is not sent at all.
+        i8042.enqueue_kbd_bytes(&[0x1C; BUFFER_SIZE - 7]).unwrap();
+        assert!(matches!(
+            i8042.send_key_event(press(KeyCode::Pause)),
+            Err(Error::FullBuffer)
+        ));
+        assert_eq!(i8042.kbd_buffer.len(), BUFFER_SIZE - 7);
+        i8042.send_key_event(press(KeyCode::PrintScreen)).unwrap();
+        assert_eq!(i8042.kbd_buffer.len(), BUFFER_SIZE - 3);
+    }
+
+    #[test]
+    fn test_i8042_sysrq() {
+        let (mut i8042, _, _, _) = new_i8042();
+
+        // Alt+SysRq+C, translated to set 1.
+        i8042.send_sysrq(KeyCode::KeyC).unwrap();
+        for &code in [0x38, 0x54, 0x2E, 0xAE, 0xD4, 0xB8].iter() {
+            assert_eq!(i8042.read(DATA_OFFSET), code);
+        }
+        assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+
+        // Alt+SysRq+B, in set 2.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_COMMAND_BYTE).unwrap();
+        i8042.write(DATA_OFFSET, CB_KBD_INT).unwrap();
+        i8042.send_sysrq(KeyCode::KeyB).unwrap();
+        for &code in [0x11, 0x84, 0x32, 0xF0, 0x32, 0xF0, 0x84, 0xF0, 0x11].iter() {
+            assert_eq!(i8042.read(DATA_OFFSET), code);
+        }
+        assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+
+        // The sequence is sent only if it fits in the output buffer.
+        i8042.enqueue_kbd_bytes(&[0x1C; BUFFER_SIZE - 8]).unwrap();
+        assert!(matches!(
+            i8042.send_sysrq(KeyCode::KeyB),
+            Err(Error::FullBuffer)
+        ));
+        assert_eq!(i8042.kbd_buffer.len(), BUFFER_SIZE - 8);
+    }
+
+    #[test]
+    fn test_i8042_state_default() {
+        let (i8042, _, _, _) = new_i8042();
+        assert_eq!(i8042.state(), I8042State::default());
+    }
+
+    #[test]
+    fn test_i8042_state() {
+        let (mut i8042, _, kbd_evt, aux_evt) = new_i8042();
+
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_OUTPUT_PORT).unwrap();
+        i8042.write(DATA_OFFSET, OUTPUT_PORT_RESET_BIT).unwrap();
+        i8042.write(DATA_OFFSET, KBD_CMD_DISABLE_SCANNING).unwrap();
+        i8042.write(DATA_OFFSET, KBD_CMD_SET_LEDS).unwrap();
+        i8042.write(DATA_OFFSET, 0b0000_0100).unwrap();
+        i8042.write(DATA_OFFSET, KBD_CMD_SET_TYPEMATIC).unwrap();
+        i8042.enqueue_aux_bytes(&[0x08, 0x01]).unwrap();
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_COMMAND_BYTE).unwrap();
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+
+        let state = i8042.state();
+        assert_eq!(state.output_port, OUTPUT_PORT_RESET_BIT);
+        assert_eq!(state.pending_command, Some(CMD_WRITE_COMMAND_BYTE));
+        assert!(state.last_write_command);
+        assert_eq!(state.kbd_pending_command, Some(KBD_CMD_SET_TYPEMATIC));
+        assert!(!state.kbd_scanning);
+        assert_eq!(state.kbd_leds, 0b0000_0100);
+        assert_eq!(state.kbd_buffer, vec![KBD_ACK; 4]);
+        assert_eq!(state.aux_buffer, vec![0x08, 0x01]);
+
+        // The pending keyboard data raises the interrupt again.
+        let mut restored_i8042 = I8042Device::from_state(
+            &state,
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            kbd_evt.try_clone().unwrap(),
+            aux_evt.try_clone().unwrap(),
+            NoEvents,
+        )
+        .unwrap();
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+        assert_eq!(restored_i8042.state(), state);
+        assert!(!restored_i8042.a20_enabled());
+
+        // The restored device completes the pending command.
+        restored_i8042.write(DATA_OFFSET, CB_AUX_INT).unwrap();
+        assert_eq!(restored_i8042.command_byte, CB_AUX_INT);
+        restored_i8042.write(DATA_OFFSET, 0x20).unwrap();
+        assert_eq!(restored_i8042.kbd_typematic, 0x20);
+        for _ in 0..5 {
+            assert_eq!(restored_i8042.read(DATA_OFFSET), KBD_ACK);
+        }
+        assert_eq!(aux_evt.read().unwrap(), 1);
+        assert_eq!(restored_i8042.read(DATA_OFFSET), 0x08);
+        assert_eq!(restored_i8042.read(DATA_OFFSET), 0x01);
+    }
+
+    #[test]
+    fn test_i8042_from_state_with_too_many_bytes() {
+        let state = I8042State {
+            aux_buffer: vec![0; BUFFER_SIZE + 1],
+            ..Default::default()
+        };
+
+        let evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let i8042 = I8042Device::from_state(
+            &state,
+            evt.try_clone().unwrap(),
+            evt.try_clone().unwrap(),
+            evt.try_clone().unwrap(),
+            NoEvents,
+        );
+        assert!(matches!(i8042, Err(Error::FullBuffer)));
+    }
+
+    #[test]
+    fn test_i8042_interrupts() {
+        let (mut i8042, _, kbd_evt, aux_evt) = new_i8042();
+
+        // Use the keyboard data as is.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_COMMAND_BYTE).unwrap();
+        i8042.write(DATA_OFFSET, CB_KBD_INT | CB_AUX_INT).unwrap();
+
+        // Keyboard data raises IRQ1, once for each byte read by the driver.
+        assert_eq!(i8042.enqueue_kbd_bytes(&[0x1C, 0xF0, 0x1C]).unwrap(), 3);
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x1C);
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+        assert_eq!(i8042.read(DATA_OFFSET), 0xF0);
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x1C);
+        assert_not_triggered(&kbd_evt);
+
+        // Auxiliary device data raises IRQ12.
+        i8042.enqueue_aux_bytes(&[0x08, 0x01, 0x02]).unwrap();
+        assert_eq!(aux_evt.read().unwrap(), 1);
+        // Keyboard data is read before the auxiliary device data, and the
+        // interrupt is raised only for the data which is read next.
+        i8042.enqueue_kbd_bytes(&[0x1D]).unwrap();
+        assert_not_triggered(&kbd_evt);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x1D);
+        assert_eq!(aux_evt.read().unwrap(), 1);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x08);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x01);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x02);
+        assert_eq!(aux_evt.read().unwrap(), 2);
+        assert_not_triggered(&kbd_evt);
+
+        // With the auxiliary device interrupt disabled, IRQ12 is not raised,
+        // until the interrupt is enabled again.
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_COMMAND_BYTE).unwrap();
+        i8042.write(DATA_OFFSET, CB_KBD_INT).unwrap();
+        i8042.enqueue_aux_bytes(&[0x08]).unwrap();
+        assert_not_triggered(&aux_evt);
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_COMMAND_BYTE).unwrap();
+        i8042.write(DATA_OFFSET, CB_KBD_INT | CB_AUX_INT).unwrap();
+        assert_eq!(aux_evt.read().unwrap(), 1);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x08);
+
+        // Bytes which don't fit in the output buffer are dropped.
+        let bytes = [0x1C; BUFFER_SIZE + 1];
+        assert_eq!(i8042.enqueue_kbd_bytes(&bytes).unwrap(), BUFFER_SIZE);
+        assert_eq!(i8042.enqueue_kbd_bytes(&bytes).unwrap(), 0);
+        assert_eq!(i8042.enqueue_kbd_bytes(&[]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_i8042_without_irqs() {
+        // The device created with `new` only has the reset trigger, which
+        // would fail the enqueues if they raised an interrupt.
+        let mut i8042 = I8042Device::new(FailingTrigger);
+        assert!(i8042.kbd_evt().is_none());
+        assert!(i8042.aux_evt().is_none());
+
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_COMMAND_BYTE).unwrap();
+        i8042.write(DATA_OFFSET, CB_KBD_INT | CB_AUX_INT).unwrap();
+        assert_eq!(i8042.enqueue_kbd_bytes(&[0x1C]).unwrap(), 1);
+        assert_eq!(i8042.enqueue_aux_bytes(&[0x08]).unwrap(), 1);
+
+        // The driver polls the status register for the data.
+        assert_ne!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x1C);
+        assert_eq!(i8042.read(DATA_OFFSET), 0x08);
+        assert_eq!(i8042.read(COMMAND_OFFSET) & SB_OUT_DATA_AVAIL, 0);
+
+        assert!(i8042.write(COMMAND_OFFSET, CMD_RESET_CPU).is_err());
+    }
+
+    #[test]
+    fn test_i8042_events() {
+        let evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let events = Arc::new(ExampleI8042Events::new());
+        let mut i8042 = I8042Device::with_events(
+            evt.try_clone().unwrap(),
+            evt.try_clone().unwrap(),
+            evt.try_clone().unwrap(),
+            events.clone(),
+        );
+
+        // Both the reset command and the output port reset line are accounted.
+        i8042.write(COMMAND_OFFSET, CMD_RESET_CPU).unwrap();
+        i8042.write(COMMAND_OFFSET, CMD_WRITE_OUTPUT_PORT).unwrap();
+        i8042.write(DATA_OFFSET, OUTPUT_PORT_A20_BIT).unwrap();
+        assert_eq!(events.reset_count.count(), 2);
+
+        // Unknown controller and keyboard commands.
+        i8042.write(COMMAND_OFFSET, 0xC0).unwrap();
+        i8042.write(DATA_OFFSET, 0x00).unwrap();
+        assert_eq!(events.unknown_command_count.count(), 2);
+        assert_eq!(i8042.read(DATA_OFFSET), KBD_RESEND);
+
+        // Fill the keyboard buffer, and then overflow it.
+        assert_eq!(
+            i8042.enqueue_kbd_bytes(&[0x1C; BUFFER_SIZE]).unwrap(),
+            BUFFER_SIZE
+        );
+        assert_eq!(events.dropped_count.count(), 0);
+        assert_eq!(i8042.enqueue_kbd_bytes(&[0x1C, 0x1C]).unwrap(), 0);
+        assert_eq!(events.dropped_count.count(), 2);
+        assert_eq!(
+            i8042.enqueue_aux_bytes(&[0x08; BUFFER_SIZE + 1]).unwrap(),
+            BUFFER_SIZE
+        );
+        assert_eq!(events.dropped_count.count(), 3);
+
+        assert_eq!(events.reset_count.count(), 2);
+        assert_eq!(events.unknown_command_count.count(), 2);
+        assert_eq!(events.interrupt_failed_count.count(), 0);
+    }
+
+    #[test]
+    fn test_i8042_interrupt_failed() {
+        let events = Arc::new(ExampleI8042Events::new());
+        let mut i8042 = I8042Device::with_events(
+            FailingTrigger,
+            FailingTrigger,
+            FailingTrigger,
+            events.clone(),
+        );
+
+        // The failure is both returned and accounted when enqueueing data.
+        assert!(i8042.enqueue_kbd_bytes(&[0x1C]).is_err());
+        assert_eq!(events.interrupt_failed_count.count(), 1);
+        // The output buffer was not empty, so no interrupt is raised here.
+        assert_eq!(i8042.enqueue_aux_bytes(&[0x08]).unwrap(), 1);
+        assert_eq!(events.interrupt_failed_count.count(), 1);
+
+        // A read raises the interrupt for the next byte, and the failure can
+        // only be accounted.
+        assert_eq!(i8042.read(DATA_OFFSET), translate(0x1C));
+        assert_eq!(events.interrupt_failed_count.count(), 2);
+
+        // No interrupt is raised once the output buffer is empty.
+        assert_eq!(i8042.read(DATA_OFFSET), 0x08);
+        assert_eq!(events.interrupt_failed_count.count(), 2);
+        assert_eq!(events.reset_count.count(), 0);
+    }
+
+    #[test]
+    fn test_i8042_legacy_device() {
+        let (mut i8042, _, kbd_evt, _) = new_i8042();
+
+        LegacyDevice::write(
+            &mut i8042,
+            u16::from(DATA_OFFSET),
+            &[KBD_CMD_DISABLE_SCANNING],
+        );
+        assert_eq!(kbd_evt.read().unwrap(), 1);
+        let mut data = [0; 1];
+        LegacyDevice::read(&mut i8042, u16::from(DATA_OFFSET), &mut data);
+        assert_eq!(data, [KBD_ACK]);
+        LegacyDevice::write(
+            &mut i8042,
+            u16::from(COMMAND_OFFSET),
+            &[CMD_WRITE_OUTPUT_PORT],
+        );
+        LegacyDevice::write(&mut i8042, u16::from(DATA_OFFSET), &[OUTPUT_PORT_RESET_BIT]);
+        assert!(!i8042.a20_enabled());
+        i8042.enqueue_aux_bytes(&[0x08]).unwrap();
+
+        i8042.reset();
+        assert_eq!(LegacyDevice::state(&i8042), I8042State::default());
+        assert!(i8042.a20_enabled());
+    }
+
+    #[cfg(feature = "fuzzing")]
+    #[test]
+    fn test_arbitrary_state() {
+        let full_buffers = I8042State {
+            kbd_buffer: vec![0xFF; BUFFER_SIZE],
+            aux_buffer: vec![0xFF; BUFFER_SIZE],
+            pending_command: Some(0xFF),
+            kbd_pending_command: Some(0xFF),
+            ..Default::default()
+        };
+        let oversized_buffer = I8042State {
+            aux_buffer: vec![0xFF; BUFFER_SIZE + 1],
+            ..full_buffers.clone()
+        };
+        crate::testing::for_each_arbitrary_state(&[full_buffers, oversized_buffer], |state| {
+            let i8042 = I8042Device::from_state(
+                state,
+                EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+                EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+                EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+                NoEvents,
+            );
+            // The states with oversized buffers are rejected.
+            let mut i8042 = match i8042 {
+                Ok(i8042) => i8042,
+                Err(Error::FullBuffer) => return,
+                Err(e) => panic!("Unexpected error: {}", e),
+            };
+            i8042.read(0);
+            i8042.read(4);
+            i8042.write(0, 0xFF).ok();
+            i8042.enqueue_kbd_bytes(&[0x1C]).ok();
+            let state = i8042.state();
+            assert!(state.kbd_buffer.len() <= BUFFER_SIZE);
+            assert!(state.aux_buffer.len() <= BUFFER_SIZE);
+        });
+    }
+
+    #[test]
+    fn test_persist() {
+        let new_evt = || EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let mut i8042 = I8042Device::with_irqs(new_evt(), new_evt(), new_evt());
+        i8042.enqueue_kbd_bytes(&[0x1C, 0x9C]).unwrap();
+
+        let state = i8042.save();
+        assert_eq!(state, i8042.state());
+        let mut restored =
+            I8042Device::restore((new_evt(), new_evt(), new_evt(), NoEvents), &state).unwrap();
+        assert_eq!(restored.save(), state);
+        assert_eq!(restored.read(DATA_OFFSET), i8042.read(DATA_OFFSET));
+
+        let state = I8042State {
+            kbd_buffer: vec![0; BUFFER_SIZE + 1],
+            ..Default::default()
+        };
+        assert!(matches!(
+            I8042Device::restore((new_evt(), new_evt(), new_evt(), NoEvents), &state),
+            Err(Error::FullBuffer)
+        ));
+    }
+
+    #[test]
+    fn test_state_validate() {
+        assert_eq!(I8042State::default().validate(), Ok(()));
+
+        let state = I8042State {
+            aux_buffer: vec![0; BUFFER_SIZE + 1],
+            ..Default::default()
+        };
+        assert_eq!(
+            state.validate(),
+            Err(StateError::BufferOverflow {
+                buffer: "aux_buffer",
+                len: BUFFER_SIZE + 1,
+                capacity: BUFFER_SIZE,
+            })
+        );
+
+        let state = I8042State {
+            kbd_leds: 0x08,
+            ..Default::default()
+        };
+        assert_eq!(
+            state.validate(),
+            Err(StateError::InvalidRegister {
+                register: "kbd_leds",
+                value: 0x08,
+            })
+        );
+        let state = I8042State {
+            kbd_typematic: 0x80,
+            ..Default::default()
+        };
+        assert_eq!(
+            state.validate(),
+            Err(StateError::InvalidRegister {
+                register: "kbd_typematic",
+                value: 0x80,
+            })
+        );
+    }
 }
@@ -0,0 +1,516 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides a stub of the i8237 DMA controllers (ports 0x00-0x0F and
+//! 0xC0-0xDF) and of their page registers (ports 0x80-0x8F).
+//!
+//! The firmware and the guest kernels initialize the legacy DMA controllers,
+//! even when no device uses them. This stub keeps the state of the registers,
+//! so that the guests read back what they wrote, but never carries out any
+//! transfer.
+
+use crate::{byte_offset, LegacyDevice};
+
+// Number of the channels of a controller.
+const CHANNEL_COUNT: usize = 4;
+
+// Register offsets, in register units.
+// The address and count registers of the channels, which alternate from
+// channel 0 to 3.
+const LAST_CHANNEL_REG: u8 = 7;
+// Status register (read), command register (write).
+const STATUS_COMMAND_REG: u8 = 8;
+// Request register.
+const REQUEST_REG: u8 = 9;
+// Sets or clears the mask of a single channel.
+const SINGLE_MASK_REG: u8 = 10;
+// Mode register.
+const MODE_REG: u8 = 11;
+// Clears the byte pointer flip-flop.
+const CLEAR_FLIP_FLOP_REG: u8 = 12;
+// Temporary register (read), master clear (write).
+const TEMPORARY_MASTER_CLEAR_REG: u8 = 13;
+// Clears the masks of all the channels.
+const CLEAR_MASK_REG: u8 = 14;
+// Writes the masks of all the channels.
+const ALL_MASK_REG: u8 = 15;
+
+// Selects the channel, in the single mask, request and mode registers.
+const CHANNEL_MASK: u8 = 0b0000_0011;
+// Sets the mask of the channel, in the single mask register.
+const SINGLE_MASK_SET_BIT: u8 = 0b0000_0100;
+// Sets the request of the channel, in the request register.
+const REQUEST_SET_BIT: u8 = 0b0000_0100;
+// The masks of all the channels.
+const ALL_MASKS: u8 = 0b0000_1111;
+
+// Number of the page registers.
+const PAGE_REG_COUNT: usize = 16;
+
+/// A stub of an i8237 DMA controller.
+///
+/// The PC has two cascaded controllers: the first one, usually found at ports
+/// 0x00-0x0F, handles the 8-bit channels 0 to 3, while the second one, usually
+/// found at ports 0xC0-0xDF, handles the 16-bit channels 4 to 7. The registers
+/// of the second controller are found at the even ports only.
+///
+/// The registers keep the values written by the guest, with the address and
+/// count registers accessed one byte after the other, through the byte
+/// pointer flip-flop. No transfer is ever carried out, so the requests stay
+/// pending, and the status register never reports a terminal count.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::i8237::I8237;
+/// let mut dma = I8237::new_8bit();
+///
+/// // The guest programs the address of channel 2, the low byte first.
+/// dma.write(0x0C, 0);
+/// dma.write(0x04, 0x34);
+/// dma.write(0x04, 0x12);
+/// assert_eq!(dma.address(2), 0x1234);
+/// // And unmasks it.
+/// dma.write(0x0A, 0x02);
+/// assert_eq!(dma.mask(), 0b1011);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct I8237 {
+    // log2 of the distance between the ports of two registers.
+    reg_shift: u8,
+    base_address: [u16; CHANNEL_COUNT],
+    current_address: [u16; CHANNEL_COUNT],
+    base_count: [u16; CHANNEL_COUNT],
+    current_count: [u16; CHANNEL_COUNT],
+    mode: [u8; CHANNEL_COUNT],
+    command: u8,
+    // The software requests, in the low 4 bits.
+    request: u8,
+    // The masks of the channels, in the low 4 bits.
+    mask: u8,
+    // Whether the next access to an address or count register is for its
+    // high byte.
+    flip_flop: bool,
+}
+
+impl I8237 {
+    /// Creates a new `I8237` instance, for the controller of the 8-bit
+    /// channels, whose registers are found at consecutive ports.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `I8237`](struct.I8237.html#example).
+    pub fn new_8bit() -> Self {
+        Self::with_reg_shift(0)
+    }
+
+    /// Creates a new `I8237` instance, for the controller of the 16-bit
+    /// channels, whose registers are found at even ports.
+    pub fn new_16bit() -> Self {
+        Self::with_reg_shift(1)
+    }
+
+    fn with_reg_shift(reg_shift: u8) -> Self {
+        I8237 {
+            reg_shift,
+            base_address: [0; CHANNEL_COUNT],
+            current_address: [0; CHANNEL_COUNT],
+            base_count: [0; CHANNEL_COUNT],
+            current_count: [0; CHANNEL_COUNT],
+            mode: [0; CHANNEL_COUNT],
+            command: 0,
+            request: 0,
+            mask: ALL_MASKS,
+            flip_flop: false,
+        }
+    }
+
+    /// Returns the current address of the `channel` channel of the
+    /// controller, i.e. 0 to 3.
+    ///
+    /// # Panics
+    /// If `channel` is greater than 3.
+    pub fn address(&self, channel: usize) -> u16 {
+        self.current_address[channel]
+    }
+
+    /// Returns the current count of the `channel` channel of the controller,
+    /// i.e. 0 to 3.
+    ///
+    /// # Panics
+    /// If `channel` is greater than 3.
+    pub fn count(&self, channel: usize) -> u16 {
+        self.current_count[channel]
+    }
+
+    /// Returns the mode of the `channel` channel of the controller, i.e. 0 to
+    /// 3, as written to the mode register.
+    ///
+    /// # Panics
+    /// If `channel` is greater than 3.
+    pub fn mode(&self, channel: usize) -> u8 {
+        self.mode[channel]
+    }
+
+    /// Returns the value of the command register.
+    pub fn command(&self) -> u8 {
+        self.command
+    }
+
+    /// Returns the masks of the channels, one bit per channel.
+    pub fn mask(&self) -> u8 {
+        self.mask
+    }
+
+    // Returns the register found at `offset`, if any.
+    fn reg(&self, offset: u8) -> Option<u8> {
+        let unit = (1u8 << self.reg_shift) - 1;
+        if (offset & unit) != 0 {
+            return None;
+        }
+        Some(offset >> self.reg_shift)
+    }
+
+    // Selects the byte of a 16-bit register accessed next, and toggles the
+    // flip-flop.
+    fn next_byte(&mut self) -> usize {
+        let high = self.flip_flop;
+        self.flip_flop = !self.flip_flop;
+        usize::from(high)
+    }
+
+    fn master_clear(&mut self) {
+        self.command = 0;
+        self.request = 0;
+        self.mask = ALL_MASKS;
+        self.flip_flop = false;
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Returns the value of the register, or 0 for the write only registers
+    /// and the invalid offsets.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&mut self, offset: u8) -> u8 {
+        match self.reg(offset) {
+            Some(reg) if reg <= LAST_CHANNEL_REG => {
+                let channel = usize::from(reg >> 1);
+                let value = if (reg & 1) == 0 {
+                    self.current_address[channel]
+                } else {
+                    self.current_count[channel]
+                };
+                value.to_le_bytes()[self.next_byte()]
+            }
+            // The requests go in the high nibble, while the terminal counts,
+            // in the low one, are never reached.
+            Some(STATUS_COMMAND_REG) => self.request << 4,
+            Some(ALL_MASK_REG) => self.mask,
+            // The other registers are write only.
+            Some(reg) if reg < ALL_MASK_REG => 0x00,
+            _ => {
+                guest_warn!("Invalid DMA controller read at offset {:#x}", offset);
+                0x00
+            }
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) {
+        let reg = match self.reg(offset) {
+            Some(reg) => reg,
+            None => {
+                guest_warn!("Invalid DMA controller write at offset {:#x}", offset);
+                return;
+            }
+        };
+        let channel = usize::from(value & CHANNEL_MASK);
+        match reg {
+            0..=LAST_CHANNEL_REG => {
+                let index = usize::from(reg >> 1);
+                let byte = self.next_byte();
+                let (base, current) = if (reg & 1) == 0 {
+                    (&mut self.base_address, &mut self.current_address)
+                } else {
+                    (&mut self.base_count, &mut self.current_count)
+                };
+                let mut bytes = base[index].to_le_bytes();
+                bytes[byte] = value;
+                base[index] = u16::from_le_bytes(bytes);
+                current[index] = base[index];
+            }
+            STATUS_COMMAND_REG => self.command = value,
+            REQUEST_REG => {
+                if (value & REQUEST_SET_BIT) != 0 {
+                    self.request |= 1 << channel;
+                } else {
+                    self.request &= !(1 << channel);
+                }
+            }
+            SINGLE_MASK_REG => {
+                if (value & SINGLE_MASK_SET_BIT) != 0 {
+                    self.mask |= 1 << channel;
+                } else {
+                    self.mask &= !(1 << channel);
+                }
+            }
+            MODE_REG => self.mode[channel] = value,
+            CLEAR_FLIP_FLOP_REG => self.flip_flop = false,
+            TEMPORARY_MASTER_CLEAR_REG => self.master_clear(),
+            CLEAR_MASK_REG => self.mask = 0,
+            ALL_MASK_REG => self.mask = value & ALL_MASKS,
+            _ => guest_warn!("Invalid DMA controller write at offset {:#x}", offset),
+        }
+    }
+}
+
+/// The DMA page registers, usually found at ports 0x80-0x8F.
+///
+/// They hold the bits 16-23 of the addresses of the DMA channels, and are
+/// kept as written by the guest. Port 0x80 is also the POST code port, so the
+/// VMM may rather route it to [`Port80`](../port80/struct.Port80.html).
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::i8237::DmaPageRegisters;
+/// let mut pages = DmaPageRegisters::new();
+///
+/// // The page of channel 2 is at port 0x81.
+/// pages.write(1, 0x12);
+/// assert_eq!(pages.read(1), 0x12);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DmaPageRegisters {
+    pages: [u8; PAGE_REG_COUNT],
+}
+
+impl DmaPageRegisters {
+    /// Creates a new `DmaPageRegisters` instance, with all the registers
+    /// cleared.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Returns the value of the register, or 0 for the invalid offsets.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&self, offset: u8) -> u8 {
+        match self.pages.get(usize::from(offset)) {
+            Some(&page) => page,
+            None => {
+                guest_warn!("Invalid DMA page register read at offset {:#x}", offset);
+                0x00
+            }
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) {
+        match self.pages.get_mut(usize::from(offset)) {
+            Some(page) => *page = value,
+            None => guest_warn!("Invalid DMA page register write at offset {:#x}", offset),
+        }
+    }
+}
+
+impl LegacyDevice for I8237 {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = I8237::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            I8237::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::with_reg_shift(self.reg_shift);
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+impl LegacyDevice for DmaPageRegisters {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = DmaPageRegisters::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            DmaPageRegisters::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write16(dma: &mut I8237, offset: u8, value: u16) {
+        for &byte in value.to_le_bytes().iter() {
+            dma.write(offset, byte);
+        }
+    }
+
+    fn read16(dma: &mut I8237, offset: u8) -> u16 {
+        u16::from_le_bytes([dma.read(offset), dma.read(offset)])
+    }
+
+    #[test]
+    fn test_i8237_channels() {
+        let mut dma = I8237::new_8bit();
+
+        for channel in 0..CHANNEL_COUNT as u8 {
+            dma.write(CLEAR_FLIP_FLOP_REG, 0);
+            write16(&mut dma, channel * 2, 0x1000 + u16::from(channel));
+            write16(&mut dma, channel * 2 + 1, 0x2000 + u16::from(channel));
+            dma.write(MODE_REG, 0x48 | channel);
+        }
+        for channel in 0..CHANNEL_COUNT {
+            assert_eq!(dma.address(channel), 0x1000 + channel as u16);
+            assert_eq!(dma.count(channel), 0x2000 + channel as u16);
+            assert_eq!(dma.mode(channel), 0x48 | channel as u8);
+        }
+        assert_eq!(read16(&mut dma, 2), 0x1001);
+        assert_eq!(read16(&mut dma, 7), 0x2003);
+
+        // The flip-flop is shared by the reads and the writes.
+        dma.write(0, 0xAA);
+        assert_eq!(dma.read(0), 0x10);
+        dma.write(CLEAR_FLIP_FLOP_REG, 0);
+        assert_eq!(dma.read(0), 0xAA);
+        // The master clear resets it too.
+        dma.write(TEMPORARY_MASTER_CLEAR_REG, 0);
+        assert_eq!(read16(&mut dma, 0), 0x10AA);
+
+        // The write only registers read as 0, while the temporary register
+        // is never loaded.
+        assert_eq!(dma.read(MODE_REG), 0);
+        assert_eq!(dma.read(TEMPORARY_MASTER_CLEAR_REG), 0);
+        assert_eq!(dma.read(16), 0);
+    }
+
+    #[test]
+    fn test_i8237_mask_and_status() {
+        let mut dma = I8237::new_8bit();
+        assert_eq!(dma.mask(), ALL_MASKS);
+        assert_eq!(dma.read(ALL_MASK_REG), ALL_MASKS);
+
+        dma.write(SINGLE_MASK_REG, 2);
+        dma.write(SINGLE_MASK_REG, 0);
+        assert_eq!(dma.mask(), 0b1010);
+        dma.write(SINGLE_MASK_REG, SINGLE_MASK_SET_BIT);
+        assert_eq!(dma.mask(), 0b1011);
+        dma.write(CLEAR_MASK_REG, 0);
+        assert_eq!(dma.mask(), 0);
+        dma.write(ALL_MASK_REG, 0xF6);
+        assert_eq!(dma.mask(), 0b0110);
+
+        // The software requests are reported, but never served.
+        dma.write(REQUEST_REG, REQUEST_SET_BIT | 3);
+        assert_eq!(dma.read(STATUS_COMMAND_REG), 0x80);
+        dma.write(REQUEST_REG, 3);
+        assert_eq!(dma.read(STATUS_COMMAND_REG), 0x00);
+
+        dma.write(REQUEST_REG, REQUEST_SET_BIT | 1);
+        dma.write(STATUS_COMMAND_REG, 0x04);
+        assert_eq!(dma.command(), 0x04);
+        dma.write(TEMPORARY_MASTER_CLEAR_REG, 0);
+        assert_eq!(dma.command(), 0x00);
+        assert_eq!(dma.read(STATUS_COMMAND_REG), 0x00);
+        assert_eq!(dma.mask(), ALL_MASKS);
+    }
+
+    #[test]
+    fn test_i8237_16bit() {
+        let mut dma = I8237::new_16bit();
+
+        // The registers are at the even ports.
+        write16(&mut dma, 2 * 4, 0x1234);
+        assert_eq!(dma.address(2), 0x1234);
+        dma.write(2 * SINGLE_MASK_REG + 1, 0);
+        assert_eq!(dma.mask(), ALL_MASKS);
+        assert_eq!(dma.read(2 * ALL_MASK_REG + 1), 0);
+        dma.write(2 * SINGLE_MASK_REG, 0);
+        assert_eq!(dma.read(2 * ALL_MASK_REG), 0b1110);
+    }
+
+    #[test]
+    fn test_dma_page_registers() {
+        let mut pages = DmaPageRegisters::new();
+        for offset in 0..PAGE_REG_COUNT as u8 {
+            pages.write(offset, offset + 1);
+        }
+        for offset in 0..PAGE_REG_COUNT as u8 {
+            assert_eq!(pages.read(offset), offset + 1);
+        }
+
+        // Other offsets are ignored.
+        pages.write(PAGE_REG_COUNT as u8, 0xFF);
+        assert_eq!(pages.read(PAGE_REG_COUNT as u8), 0x00);
+    }
+
+    #[test]
+    fn test_i8237_legacy_device() {
+        let mut dma = I8237::new_16bit();
+        LegacyDevice::write(&mut dma, u16::from(2 * SINGLE_MASK_REG), &[0]);
+        let mut data = [0; 1];
+        LegacyDevice::read(&mut dma, u16::from(2 * ALL_MASK_REG), &mut data);
+        assert_eq!(data, [0b1110]);
+
+        // The reset masks all the channels again, and keeps the layout of the
+        // registers.
+        dma.reset();
+        assert_eq!(dma.mask(), ALL_MASKS);
+        LegacyDevice::write(&mut dma, u16::from(2 * SINGLE_MASK_REG), &[0]);
+        assert_eq!(dma.mask(), 0b1110);
+
+        let mut pages = DmaPageRegisters::new();
+        LegacyDevice::write(&mut pages, 1, &[0x12]);
+        LegacyDevice::read(&mut pages, 1, &mut data);
+        assert_eq!(data, [0x12]);
+        pages.reset();
+        assert_eq!(pages.read(1), 0);
+    }
+}
@@ -0,0 +1,1170 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the cascaded pair of i8259 Programmable Interrupt
+//! Controllers (PIC).
+//!
+//! The master PIC handles IRQs 0-7 and the slave PIC handles IRQs 8-15. The
+//! output of the slave is connected to the IRQ 2 input of the master, and
+//! the output of the master raises the interrupt request (INTR) of the CPU.
+
+use core::convert::Infallible;
+use core::result::Result;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+#[cfg(feature = "std")]
+use crate::interrupt::InterruptController;
+use crate::{byte_offset, LegacyDevice, Persist, Trigger};
+
+// Offsets of the master registers (ports 0x20 and 0x21), relative to the
+// base I/O address of the master.
+const MASTER_OFFSET: u8 = 0x00;
+// Offset of the slave registers (ports 0xA0 and 0xA1).
+const SLAVE_OFFSET: u8 = 0x80;
+// Offset of the command register of each PIC. The data register follows it.
+const COMMAND_OFFSET: u8 = 0x00;
+const DATA_OFFSET: u8 = 0x01;
+
+// Number of IRQs handled by each PIC.
+const IRQS_PER_PIC: u8 = 8;
+// The IRQ of the master connected to the output of the slave.
+const CASCADE_IRQ: u8 = 2;
+// The IRQ reported when the interrupt request went away before it was
+// acknowledged.
+const SPURIOUS_IRQ: u8 = 7;
+// Priority returned when no IRQ is set.
+const NO_PRIORITY: u8 = 8;
+// The IRQs that can be level triggered. The timer (IRQ 0), the keyboard
+// (IRQ 1), the cascade (IRQ 2), the RTC (IRQ 8) and the FPU (IRQ 13) are
+// always edge triggered.
+const MASTER_ELCR_MASK: u8 = 0b1111_1000;
+const SLAVE_ELCR_MASK: u8 = 0b1101_1110;
+
+// Initialization Command Word 1, written to the command register.
+// Distinguishes ICW1 from the operation command words.
+const ICW1_BIT: u8 = 0b0001_0000;
+// ICW4 follows.
+const ICW1_ICW4_BIT: u8 = 0b0000_0001;
+// Single PIC, without ICW3.
+const ICW1_SINGLE_BIT: u8 = 0b0000_0010;
+// The vector base is given by ICW2, in the upper 5 bits.
+const ICW2_VECTOR_MASK: u8 = 0b1111_1000;
+// Initialization Command Word 4.
+// Automatic End Of Interrupt.
+const ICW4_AUTO_EOI_BIT: u8 = 0b0000_0010;
+// Special fully nested mode.
+const ICW4_SFNM_BIT: u8 = 0b0001_0000;
+
+// Operation Command Word 3, written to the command register. OCW2 is
+// written otherwise.
+const OCW3_BIT: u8 = 0b0000_1000;
+// Reads the ISR instead of the IRR (with OCW3_READ_REG_BIT).
+const OCW3_READ_ISR_BIT: u8 = 0b0000_0001;
+// Selects the register returned by the reads of the command register.
+const OCW3_READ_REG_BIT: u8 = 0b0000_0010;
+// The next read of the command register acknowledges the highest priority
+// IRQ, and returns it.
+const OCW3_POLL_BIT: u8 = 0b0000_0100;
+// Sets the special mask mode (with OCW3_SPECIAL_MASK_BIT).
+const OCW3_SET_SPECIAL_MASK_BIT: u8 = 0b0010_0000;
+// Changes the special mask mode.
+const OCW3_SPECIAL_MASK_BIT: u8 = 0b0100_0000;
+// Set in the byte returned by a poll read when an IRQ was pending.
+const POLL_IRQ_BIT: u8 = 0b1000_0000;
+
+// Operation Command Word 2 commands, in the upper 3 bits. The lower 3 bits
+// hold the IRQ of the specific commands.
+const OCW2_CMD_SHIFT: u8 = 5;
+const OCW2_IRQ_MASK: u8 = 0b0000_0111;
+const OCW2_CLEAR_ROTATE_AUTO_EOI: u8 = 0b000;
+const OCW2_EOI: u8 = 0b001;
+const OCW2_SPECIFIC_EOI: u8 = 0b011;
+const OCW2_SET_ROTATE_AUTO_EOI: u8 = 0b100;
+const OCW2_ROTATE_EOI: u8 = 0b101;
+const OCW2_SET_PRIORITY: u8 = 0b110;
+const OCW2_ROTATE_SPECIFIC_EOI: u8 = 0b111;
+
+// Steps of the initialization sequence, i.e. the next expected ICW.
+const INIT_DONE: u8 = 0;
+const INIT_ICW2: u8 = 2;
+const INIT_ICW3: u8 = 3;
+const INIT_ICW4: u8 = 4;
+
+/// The state of one of the i8259 PICs.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PicChipState {
+    /// Interrupt Request Register.
+    pub irr: u8,
+    /// In-Service Register.
+    pub isr: u8,
+    /// Interrupt Mask Register.
+    pub imr: u8,
+    /// The levels of the IRQ lines.
+    pub lines: u8,
+    /// Edge/Level Control Register. The IRQs set in it are level triggered.
+    pub elcr: u8,
+    /// The vector of IRQ 0.
+    pub vector_base: u8,
+    /// The IRQ with the lowest priority is the one before this one.
+    pub lowest_priority_next: u8,
+    /// The next expected initialization command word (2, 3 or 4), or 0 when
+    /// the initialization is done.
+    pub init_step: u8,
+    /// Whether ICW4 is expected during the initialization.
+    pub icw4_needed: bool,
+    /// Whether ICW3 is skipped during the initialization.
+    pub single: bool,
+    /// Automatic End Of Interrupt mode.
+    pub auto_eoi: bool,
+    /// Rotation of the priorities on automatic End Of Interrupt.
+    pub rotate_on_auto_eoi: bool,
+    /// Special fully nested mode.
+    pub special_fully_nested: bool,
+    /// Special mask mode.
+    pub special_mask: bool,
+    /// Whether the reads of the command register return the ISR instead of
+    /// the IRR.
+    pub read_isr: bool,
+    /// Whether the next read of the command register is a poll.
+    pub poll: bool,
+}
+
+/// The state of the cascaded pair of i8259 PICs.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PicState {
+    /// The state of the master PIC, which handles IRQs 0-7.
+    pub master: PicChipState,
+    /// The state of the slave PIC, which handles IRQs 8-15.
+    pub slave: PicChipState,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Chip {
+    // Interrupt Request Register.
+    irr: u8,
+    // In-Service Register.
+    isr: u8,
+    // Interrupt Mask Register.
+    imr: u8,
+    // The levels of the IRQ lines, for detecting the rising edges.
+    lines: u8,
+    // Edge/Level Control Register. The IRQs set in it are level triggered.
+    elcr: u8,
+    // The vector of IRQ 0.
+    vector_base: u8,
+    // The IRQ with the lowest priority is the one before this one.
+    lowest_priority_next: u8,
+    // The next expected initialization command word.
+    init_step: u8,
+    // Whether ICW4 is expected during the initialization.
+    icw4_needed: bool,
+    // Whether ICW3 is skipped during the initialization.
+    single: bool,
+    auto_eoi: bool,
+    rotate_on_auto_eoi: bool,
+    special_fully_nested: bool,
+    special_mask: bool,
+    // Whether the reads of the command register return the ISR instead of
+    // the IRR.
+    read_isr: bool,
+    // Whether the next read of the command register is a poll.
+    poll: bool,
+}
+
+impl Chip {
+    // The registers are masked to their valid bits, so that a corrupted
+    // state doesn't overflow the vectors or the priorities.
+    fn from_state(state: &PicChipState, elcr_mask: u8) -> Self {
+        Chip {
+            irr: state.irr,
+            isr: state.isr,
+            imr: state.imr,
+            lines: state.lines,
+            elcr: state.elcr & elcr_mask,
+            vector_base: state.vector_base & ICW2_VECTOR_MASK,
+            lowest_priority_next: state.lowest_priority_next & OCW2_IRQ_MASK,
+            init_step: state.init_step,
+            icw4_needed: state.icw4_needed,
+            single: state.single,
+            auto_eoi: state.auto_eoi,
+            rotate_on_auto_eoi: state.rotate_on_auto_eoi,
+            special_fully_nested: state.special_fully_nested,
+            special_mask: state.special_mask,
+            read_isr: state.read_isr,
+            poll: state.poll,
+        }
+    }
+
+    fn state(&self) -> PicChipState {
+        PicChipState {
+            irr: self.irr,
+            isr: self.isr,
+            imr: self.imr,
+            lines: self.lines,
+            elcr: self.elcr,
+            vector_base: self.vector_base,
+            lowest_priority_next: self.lowest_priority_next,
+            init_step: self.init_step,
+            icw4_needed: self.icw4_needed,
+            single: self.single,
+            auto_eoi: self.auto_eoi,
+            rotate_on_auto_eoi: self.rotate_on_auto_eoi,
+            special_fully_nested: self.special_fully_nested,
+            special_mask: self.special_mask,
+            read_isr: self.read_isr,
+            poll: self.poll,
+        }
+    }
+
+    // Returns the chip to its power-on state. Like at the initialization,
+    // the lines keep their levels, and the ELCR is kept.
+    fn reset(&mut self) {
+        *self = Chip {
+            lines: self.lines,
+            elcr: self.elcr,
+            ..Default::default()
+        };
+    }
+
+    // Returns the priority of the highest priority IRQ set in `mask`, where
+    // 0 is the highest priority.
+    fn priority(&self, mask: u8) -> u8 {
+        if mask == 0 {
+            return NO_PRIORITY;
+        }
+        let mut priority = 0;
+        while (mask & (1 << ((priority + self.lowest_priority_next) & 7))) == 0 {
+            priority += 1;
+        }
+        priority
+    }
+
+    fn irq_of_priority(&self, priority: u8) -> u8 {
+        (priority + self.lowest_priority_next) & 7
+    }
+
+    // Returns the IRQ that should be delivered to the CPU, if any.
+    fn pending_irq(&self, is_master: bool) -> Option<u8> {
+        let priority = self.priority(self.irr & !self.imr);
+        if priority == NO_PRIORITY {
+            return None;
+        }
+        let mut in_service = self.isr;
+        // The special mask mode allows the IRQs with a lower priority than
+        // the ones in service, as long as they are not masked.
+        if self.special_mask {
+            in_service &= !self.imr;
+        }
+        // The special fully nested mode allows the slave to interrupt the
+        // IRQs in service on it.
+        if is_master && self.special_fully_nested {
+            in_service &= !(1 << CASCADE_IRQ);
+        }
+        if priority < self.priority(in_service) {
+            Some(self.irq_of_priority(priority))
+        } else {
+            None
+        }
+    }
+
+    fn set_irq(&mut self, irq: u8, level: bool) {
+        let mask = 1 << irq;
+        if (self.elcr & mask) != 0 {
+            // The level triggered IRQs are requested as long as the line is
+            // high.
+            if level {
+                self.irr |= mask;
+            } else {
+                self.irr &= !mask;
+            }
+        } else if level && (self.lines & mask) == 0 {
+            // The edge triggered IRQs are requested by a rising edge.
+            self.irr |= mask;
+        }
+        if level {
+            self.lines |= mask;
+        } else {
+            self.lines &= !mask;
+        }
+    }
+
+    // Moves `irq` from the IRR to the ISR, when the CPU acknowledges it.
+    fn intack(&mut self, irq: u8) {
+        let mask = 1 << irq;
+        if self.auto_eoi {
+            if self.rotate_on_auto_eoi {
+                self.lowest_priority_next = (irq + 1) & 7;
+            }
+        } else {
+            self.isr |= mask;
+        }
+        // The request of a level triggered IRQ goes away only when the line
+        // is lowered.
+        if (self.elcr & mask) == 0 {
+            self.irr &= !mask;
+        }
+    }
+
+    fn eoi(&mut self, irq: u8, rotate: bool) {
+        self.isr &= !(1 << irq);
+        if rotate {
+            self.lowest_priority_next = (irq + 1) & 7;
+        }
+    }
+
+    fn write_command(&mut self, value: u8) {
+        if (value & ICW1_BIT) != 0 {
+            // Start the initialization sequence. The lines keep their
+            // levels, but a new edge is needed to request an interrupt. The
+            // ELCR is a separate register, so it is kept as well.
+            *self = Chip {
+                lines: self.lines,
+                elcr: self.elcr,
+                init_step: INIT_ICW2,
+                icw4_needed: (value & ICW1_ICW4_BIT) != 0,
+                single: (value & ICW1_SINGLE_BIT) != 0,
+                ..Default::default()
+            };
+        } else if (value & OCW3_BIT) != 0 {
+            if (value & OCW3_POLL_BIT) != 0 {
+                self.poll = true;
+            }
+            if (value & OCW3_READ_REG_BIT) != 0 {
+                self.read_isr = (value & OCW3_READ_ISR_BIT) != 0;
+            }
+            if (value & OCW3_SPECIAL_MASK_BIT) != 0 {
+                self.special_mask = (value & OCW3_SET_SPECIAL_MASK_BIT) != 0;
+            }
+        } else {
+            let irq = value & OCW2_IRQ_MASK;
+            match value >> OCW2_CMD_SHIFT {
+                OCW2_CLEAR_ROTATE_AUTO_EOI => self.rotate_on_auto_eoi = false,
+                OCW2_SET_ROTATE_AUTO_EOI => self.rotate_on_auto_eoi = true,
+                cmd @ (OCW2_EOI | OCW2_ROTATE_EOI) => {
+                    let priority = self.priority(self.isr);
+                    if priority != NO_PRIORITY {
+                        self.eoi(self.irq_of_priority(priority), cmd == OCW2_ROTATE_EOI);
+                    }
+                }
+                OCW2_SPECIFIC_EOI => self.eoi(irq, false),
+                OCW2_ROTATE_SPECIFIC_EOI => self.eoi(irq, true),
+                OCW2_SET_PRIORITY => self.lowest_priority_next = (irq + 1) & 7,
+                // No operation.
+                _ => (),
+            }
+        }
+    }
+
+    fn write_data(&mut self, value: u8) {
+        match self.init_step {
+            INIT_ICW2 => {
+                self.vector_base = value & ICW2_VECTOR_MASK;
+                self.init_step = match (self.single, self.icw4_needed) {
+                    (false, _) => INIT_ICW3,
+                    (true, true) => INIT_ICW4,
+                    (true, false) => INIT_DONE,
+                };
+            }
+            // The cascade configuration is fixed, so ICW3 is ignored.
+            INIT_ICW3 => {
+                self.init_step = if self.icw4_needed {
+                    INIT_ICW4
+                } else {
+                    INIT_DONE
+                };
+            }
+            INIT_ICW4 => {
+                self.auto_eoi = (value & ICW4_AUTO_EOI_BIT) != 0;
+                self.special_fully_nested = (value & ICW4_SFNM_BIT) != 0;
+                self.init_step = INIT_DONE;
+            }
+            // OCW1 sets the IMR.
+            _ => self.imr = value,
+        }
+    }
+
+    fn read(&mut self, offset: u8, is_master: bool) -> u8 {
+        if self.poll {
+            self.poll = false;
+            return match self.pending_irq(is_master) {
+                Some(irq) => {
+                    self.intack(irq);
+                    POLL_IRQ_BIT | irq
+                }
+                None => 0x00,
+            };
+        }
+        match offset {
+            COMMAND_OFFSET if self.read_isr => self.isr,
+            COMMAND_OFFSET => self.irr,
+            _ => self.imr,
+        }
+    }
+}
+
+/// The cascaded pair of i8259 PICs, usually found at ports 0x20-0x21
+/// (master) and 0xA0-0xA1 (slave).
+///
+/// The registers of both PICs are accessed through offsets relative to the
+/// base I/O address of the master (0x20), so the slave registers are at
+/// offsets 0x80 and 0x81.
+///
+/// A [`Trigger`](../trait.Trigger.html) object is used for notifying the VMM
+/// when the interrupt request of the CPU (INTR) is raised. The VMM then
+/// calls [`acknowledge`](#method.acknowledge) for getting the vector that
+/// should be injected in the vCPU.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::i8259::Pic;
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut pic = Pic::new(DummyTrigger);
+///
+/// // Initialize the master with the vectors 0x20-0x27, like Linux does.
+/// for &value in [0x11, 0x20, 0x04, 0x01].iter() {
+///     let offset = if value == 0x11 { 0 } else { 1 };
+///     pic.write(offset, value).unwrap();
+/// }
+///
+/// // Raise IRQ 1, and acknowledge it.
+/// pic.set_irq(1, true).unwrap();
+/// assert!(pic.interrupt_pending());
+/// assert_eq!(pic.acknowledge(), 0x21);
+///
+/// // Send a non-specific EOI.
+/// pic.write(0, 0x20).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Pic<T: Trigger> {
+    // Interrupt request of the CPU (INTR).
+    intr_evt: T,
+    master: Chip,
+    slave: Chip,
+    // The level of the INTR output.
+    intr: bool,
+}
+
+impl<T: Trigger> Pic<T> {
+    /// Creates a new `Pic` instance, which will signal `intr_evt` when the
+    /// interrupt request of the CPU (INTR) is raised.
+    ///
+    /// # Arguments
+    /// * `intr_evt` - A Trigger object that will be used to notify the VMM
+    ///   about pending interrupts.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Pic`](struct.Pic.html#example).
+    pub fn new(intr_evt: T) -> Self {
+        Pic {
+            intr_evt,
+            master: Chip::default(),
+            slave: Chip::default(),
+            intr: false,
+        }
+    }
+
+    /// Creates a new `Pic` instance from a given `state`, which will signal
+    /// `intr_evt` when the interrupt request of the CPU (INTR) is raised.
+    ///
+    /// INTR is not signaled on the restore, so the VMM should check
+    /// [`interrupt_pending`](#method.interrupt_pending) afterwards.
+    /// The states are not validated: the bits of the always edge triggered
+    /// IRQs in the ELCR, the lower bits of the vector bases and the upper
+    /// bits of the priorities are ignored, like the guest writes do.
+    ///
+    /// # Arguments
+    /// * `state` - A reference to the state from which the `Pic` is
+    ///   constructed.
+    /// * `intr_evt` - A Trigger object that will be used to notify the VMM
+    ///   about pending interrupts.
+    pub fn from_state(state: &PicState, intr_evt: T) -> Self {
+        let mut pic = Pic {
+            intr_evt,
+            master: Chip::from_state(&state.master, MASTER_ELCR_MASK),
+            slave: Chip::from_state(&state.slave, SLAVE_ELCR_MASK),
+            intr: false,
+        };
+        pic.update_intr();
+        pic
+    }
+
+    /// Returns the state of the PICs.
+    pub fn state(&self) -> PicState {
+        PicState {
+            master: self.master.state(),
+            slave: self.slave.state(),
+        }
+    }
+
+    /// Provides a reference to the INTR event object.
+    pub fn intr_evt(&self) -> &T {
+        &self.intr_evt
+    }
+
+    /// Returns whether an interrupt is waiting to be acknowledged by the CPU,
+    /// i.e. the level of INTR.
+    pub fn interrupt_pending(&self) -> bool {
+        self.intr
+    }
+
+    // Propagates the output of the slave to the master, and the output of the
+    // master to INTR. Returns whether INTR was raised.
+    fn update_intr(&mut self) -> bool {
+        let slave_output = self.slave.pending_irq(false).is_some();
+        self.master.set_irq(CASCADE_IRQ, slave_output);
+        let intr = self.master.pending_irq(true).is_some();
+        let raised = intr && !self.intr;
+        self.intr = intr;
+        raised
+    }
+
+    // Updates INTR, and signals its rising edges.
+    fn update(&mut self) -> Result<(), T::E> {
+        if self.update_intr() {
+            return self.intr_evt.trigger();
+        }
+        Ok(())
+    }
+
+    /// Returns the Edge/Level Control Register mask, where bit N is set if
+    /// IRQ N is level triggered.
+    pub fn elcr(&self) -> u16 {
+        u16::from(self.master.elcr) | (u16::from(self.slave.elcr) << IRQS_PER_PIC)
+    }
+
+    /// Sets the Edge/Level Control Register mask, where bit N is set if IRQ N
+    /// is level triggered, e.g. as programmed by the guest through the
+    /// [`Elcr`](../elcr/struct.Elcr.html) device.
+    ///
+    /// The IRQs 0, 1, 2, 8 and 13 are always edge triggered, so their bits
+    /// are ignored. The new trigger modes apply from the next change of the
+    /// IRQ lines.
+    ///
+    /// # Arguments
+    /// * `elcr` - The mask of the level triggered IRQs.
+    pub fn set_elcr(&mut self, elcr: u16) {
+        self.master.elcr = (elcr as u8) & MASTER_ELCR_MASK;
+        self.slave.elcr = ((elcr >> IRQS_PER_PIC) as u8) & SLAVE_ELCR_MASK;
+    }
+
+    /// Sets the level of an IRQ line. The edge triggered IRQs are requested by
+    /// a rising edge, while the level triggered ones, as configured through
+    /// [`set_elcr`](#method.set_elcr), are requested as long as the line is
+    /// high.
+    ///
+    /// # Arguments
+    /// * `irq` - The IRQ line, between 0 and 15. Other values are ignored.
+    /// * `level` - The new level of the line.
+    pub fn set_irq(&mut self, irq: u8, level: bool) -> Result<(), T::E> {
+        match irq {
+            0..=7 => self.master.set_irq(irq, level),
+            8..=15 => self.slave.set_irq(irq - IRQS_PER_PIC, level),
+            _ => return Ok(()),
+        }
+        self.update()
+    }
+
+    /// Returns the vector of the highest priority interrupt, if INTR is
+    /// raised, without acknowledging it.
+    pub fn pending_vector(&self) -> Option<u8> {
+        match self.master.pending_irq(true)? {
+            CASCADE_IRQ => match self.slave.pending_irq(false) {
+                Some(irq) => Some(self.slave.vector_base + irq),
+                None => Some(self.slave.vector_base + SPURIOUS_IRQ),
+            },
+            irq => Some(self.master.vector_base + irq),
+        }
+    }
+
+    /// Handles the interrupt acknowledge cycle of the CPU, and returns the
+    /// vector of the highest priority interrupt.
+    ///
+    /// If no interrupt is pending anymore, the vector of the spurious IRQ 7
+    /// of the master is returned. The VMM should check
+    /// [`interrupt_pending`](#method.interrupt_pending) again afterwards,
+    /// since other interrupts may still be pending.
+    pub fn acknowledge(&mut self) -> u8 {
+        let vector = match self.master.pending_irq(true) {
+            Some(CASCADE_IRQ) => {
+                self.master.intack(CASCADE_IRQ);
+                match self.slave.pending_irq(false) {
+                    Some(irq) => {
+                        self.slave.intack(irq);
+                        self.slave.vector_base + irq
+                    }
+                    None => self.slave.vector_base + SPURIOUS_IRQ,
+                }
+            }
+            Some(irq) => {
+                self.master.intack(irq);
+                self.master.vector_base + irq
+            }
+            None => self.master.vector_base + SPURIOUS_IRQ,
+        };
+        // The acknowledge cycle can't raise INTR, since the highest priority
+        // interrupt was just moved in service.
+        self.update_intr();
+        vector
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address of the master.
+    ///
+    /// Returns the IRR or the ISR from the command registers, as selected by
+    /// OCW3, and the IMR from the data registers. Reads from invalid offsets
+    /// return 0.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&mut self, offset: u8) -> Result<u8, T::E> {
+        let (chip, is_master) = match offset & !DATA_OFFSET {
+            MASTER_OFFSET => (&mut self.master, true),
+            SLAVE_OFFSET => (&mut self.slave, false),
+            _ => {
+                guest_warn!("Invalid PIC read at offset {:#x}", offset);
+                return Ok(0x00);
+            }
+        };
+        let value = chip.read(offset & DATA_OFFSET, is_master);
+        // A poll acknowledges the interrupt.
+        self.update()?;
+        Ok(value)
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address of the master.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) -> Result<(), T::E> {
+        let chip = match offset & !DATA_OFFSET {
+            MASTER_OFFSET => &mut self.master,
+            SLAVE_OFFSET => &mut self.slave,
+            _ => {
+                guest_warn!("Invalid PIC write at offset {:#x}", offset);
+                return Ok(());
+            }
+        };
+        match offset & DATA_OFFSET {
+            COMMAND_OFFSET => chip.write_command(value),
+            _ => chip.write_data(value),
+        }
+        self.update()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Trigger> InterruptController for Mutex<Pic<T>> {
+    type E = T::E;
+
+    fn set_irq(&self, irq: u8, level: bool) -> Result<(), Self::E> {
+        // A poisoned lock still holds a consistent PIC, since its methods
+        // don't panic halfway through an update.
+        let mut pic = self.lock().unwrap_or_else(|e| e.into_inner());
+        pic.set_irq(irq, level)
+    }
+
+    fn get_pending_vector(&self) -> Option<u8> {
+        let pic = self.lock().unwrap_or_else(|e| e.into_inner());
+        pic.pending_vector()
+    }
+
+    fn acknowledge(&self) -> Option<u8> {
+        let mut pic = self.lock().unwrap_or_else(|e| e.into_inner());
+        if pic.interrupt_pending() {
+            Some(pic.acknowledge())
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Trigger> LegacyDevice for Pic<T> {
+    type State = PicState;
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = Pic::read(self, offset).unwrap_or(0),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            let _ = Pic::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.master.reset();
+        self.slave.reset();
+        self.intr = false;
+    }
+
+    fn state(&self) -> Self::State {
+        Pic::state(self)
+    }
+}
+
+// The PIC is restored with its INTR `Trigger` object, and never fails.
+impl<T: Trigger> Persist for Pic<T> {
+    type State = PicState;
+    type ConstructorArgs = T;
+    type Error = Infallible;
+
+    fn save(&self) -> Self::State {
+        self.state()
+    }
+
+    fn restore(intr_evt: Self::ConstructorArgs, state: &Self::State) -> Result<Self, Self::Error> {
+        Ok(Self::from_state(state, intr_evt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use vmm_sys_util::eventfd::EventFd;
+
+    fn intr_count(pic: &Pic<EventFd>) -> u64 {
+        match pic.intr_evt().read() {
+            Ok(count) => count,
+            Err(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+                0
+            }
+        }
+    }
+
+    // Initializes the PICs like Linux does, with the vectors 0x20-0x2F.
+    fn new_pic() -> Pic<EventFd> {
+        let mut pic = Pic::new(EventFd::new(libc::EFD_NONBLOCK).unwrap());
+        for &(offset, value) in [
+            (MASTER_OFFSET, 0x11),
+            (MASTER_OFFSET + 1, 0x20),
+            (MASTER_OFFSET + 1, 0x04),
+            (MASTER_OFFSET + 1, 0x01),
+            (SLAVE_OFFSET, 0x11),
+            (SLAVE_OFFSET + 1, 0x28),
+            (SLAVE_OFFSET + 1, 0x02),
+            (SLAVE_OFFSET + 1, 0x01),
+        ]
+        .iter()
+        {
+            pic.write(offset, value).unwrap();
+        }
+        pic
+    }
+
+    const EOI: u8 = OCW2_EOI << OCW2_CMD_SHIFT;
+    const READ_IRR: u8 = OCW3_BIT | OCW3_READ_REG_BIT;
+    const READ_ISR: u8 = OCW3_BIT | OCW3_READ_REG_BIT | OCW3_READ_ISR_BIT;
+
+    #[test]
+    fn test_pic_init() {
+        let mut pic = new_pic();
+        assert_eq!(pic.master.vector_base, 0x20);
+        assert_eq!(pic.slave.vector_base, 0x28);
+        assert_eq!(pic.master.init_step, INIT_DONE);
+        assert_eq!(pic.slave.init_step, INIT_DONE);
+        assert!(!pic.master.auto_eoi);
+
+        // OCW1 sets the IMR.
+        pic.write(MASTER_OFFSET + 1, 0xFB).unwrap();
+        pic.write(SLAVE_OFFSET + 1, 0xFF).unwrap();
+        assert_eq!(pic.read(MASTER_OFFSET + 1).unwrap(), 0xFB);
+        assert_eq!(pic.read(SLAVE_OFFSET + 1).unwrap(), 0xFF);
+
+        // A single PIC without ICW4 only expects ICW2, and the lower bits of
+        // the vector base are ignored.
+        pic.write(MASTER_OFFSET, ICW1_BIT | ICW1_SINGLE_BIT)
+            .unwrap();
+        assert_eq!(pic.read(MASTER_OFFSET + 1).unwrap(), 0x00);
+        pic.write(MASTER_OFFSET + 1, 0x47).unwrap();
+        assert_eq!(pic.master.vector_base, 0x40);
+        assert_eq!(pic.master.init_step, INIT_DONE);
+
+        // Invalid offsets are ignored.
+        pic.write(0x02, 0xFF).unwrap();
+        assert_eq!(pic.read(0x02).unwrap(), 0x00);
+        assert_eq!(pic.read(MASTER_OFFSET + 1).unwrap(), 0x00);
+    }
+
+    #[test]
+    fn test_pic_priorities() {
+        let mut pic = new_pic();
+        assert!(!pic.interrupt_pending());
+
+        pic.set_irq(4, true).unwrap();
+        assert!(pic.interrupt_pending());
+        assert_eq!(intr_count(&pic), 1);
+        pic.set_irq(1, true).unwrap();
+        assert_eq!(intr_count(&pic), 0);
+        pic.write(MASTER_OFFSET, READ_IRR).unwrap();
+        assert_eq!(pic.read(MASTER_OFFSET).unwrap(), 0x12);
+
+        // IRQ 1 has the highest priority.
+        assert_eq!(pic.acknowledge(), 0x21);
+        assert!(!pic.interrupt_pending());
+        pic.write(MASTER_OFFSET, READ_ISR).unwrap();
+        assert_eq!(pic.read(MASTER_OFFSET).unwrap(), 0x02);
+
+        // A higher priority IRQ interrupts it.
+        pic.set_irq(0, true).unwrap();
+        assert_eq!(intr_count(&pic), 1);
+        assert_eq!(pic.acknowledge(), 0x20);
+        assert_eq!(pic.read(MASTER_OFFSET).unwrap(), 0x03);
+
+        // The non-specific EOIs clear the highest priority IRQ in service,
+        // and then the pending IRQ 4 is delivered.
+        pic.write(MASTER_OFFSET, EOI).unwrap();
+        assert!(!pic.interrupt_pending());
+        pic.write(MASTER_OFFSET, EOI).unwrap();
+        assert!(pic.interrupt_pending());
+        assert_eq!(intr_count(&pic), 1);
+        assert_eq!(pic.acknowledge(), 0x24);
+        pic.write(MASTER_OFFSET, EOI).unwrap();
+        assert_eq!(pic.read(MASTER_OFFSET).unwrap(), 0x00);
+
+        // The IRQs are edge triggered.
+        pic.set_irq(4, true).unwrap();
+        assert!(!pic.interrupt_pending());
+        pic.set_irq(4, false).unwrap();
+        pic.set_irq(4, true).unwrap();
+        assert!(pic.interrupt_pending());
+
+        // Invalid IRQs are ignored.
+        pic.set_irq(16, true).unwrap();
+    }
+
+    #[test]
+    fn test_pic_mask() {
+        let mut pic = new_pic();
+
+        pic.write(MASTER_OFFSET + 1, 0x08).unwrap();
+        pic.set_irq(3, true).unwrap();
+        assert!(!pic.interrupt_pending());
+        assert_eq!(intr_count(&pic), 0);
+
+        // The IRQ is delivered once unmasked.
+        pic.write(MASTER_OFFSET + 1, 0x00).unwrap();
+        assert!(pic.interrupt_pending());
+        assert_eq!(intr_count(&pic), 1);
+
+        // The interrupt request goes away if the IRQ is masked again, and
+        // the acknowledge cycle returns a spurious IRQ.
+        pic.write(MASTER_OFFSET + 1, 0x08).unwrap();
+        assert!(!pic.interrupt_pending());
+        assert_eq!(pic.acknowledge(), 0x27);
+    }
+
+    #[test]
+    fn test_pic_cascade() {
+        let mut pic = new_pic();
+
+        // IRQ 12 goes through the slave and IRQ 2 of the master.
+        assert_eq!(pic.pending_vector(), None);
+        pic.set_irq(12, true).unwrap();
+        assert!(pic.interrupt_pending());
+        assert_eq!(intr_count(&pic), 1);
+        pic.set_irq(3, true).unwrap();
+        assert_eq!(pic.pending_vector(), Some(0x2C));
+        assert_eq!(pic.acknowledge(), 0x2C);
+        pic.write(SLAVE_OFFSET, READ_ISR).unwrap();
+        assert_eq!(pic.read(SLAVE_OFFSET).unwrap(), 0x10);
+        pic.write(MASTER_OFFSET, READ_ISR).unwrap();
+        assert_eq!(pic.read(MASTER_OFFSET).unwrap(), 0x04);
+
+        // Both PICs need an EOI before IRQ 3 is delivered.
+        pic.write(SLAVE_OFFSET, EOI).unwrap();
+        assert!(!pic.interrupt_pending());
+        pic.write(MASTER_OFFSET, EOI).unwrap();
+        assert!(pic.interrupt_pending());
+        assert_eq!(pic.acknowledge(), 0x23);
+        pic.write(MASTER_OFFSET, EOI).unwrap();
+
+        // The slave IRQ is masked before the acknowledge cycle, so the slave
+        // returns a spurious IRQ 15.
+        pic.set_irq(8, true).unwrap();
+        pic.write(SLAVE_OFFSET + 1, 0x01).unwrap();
+        assert!(pic.interrupt_pending());
+        assert_eq!(pic.pending_vector(), Some(0x2F));
+        assert_eq!(pic.acknowledge(), 0x2F);
+    }
+
+    #[test]
+    fn test_pic_specific_eoi_and_rotation() {
+        let mut pic = new_pic();
+
+        pic.set_irq(5, true).unwrap();
+        assert_eq!(pic.acknowledge(), 0x25);
+        pic.set_irq(1, true).unwrap();
+        assert_eq!(pic.acknowledge(), 0x21);
+
+        // A specific EOI clears IRQ 5, while IRQ 1 stays in service.
+        pic.write(MASTER_OFFSET, (OCW2_SPECIFIC_EOI << OCW2_CMD_SHIFT) | 5)
+            .unwrap();
+        pic.write(MASTER_OFFSET, READ_ISR).unwrap();
+        assert_eq!(pic.read(MASTER_OFFSET).unwrap(), 0x02);
+
+        // The rotate on specific EOI gives IRQ 1 the lowest priority.
+        pic.write(
+            MASTER_OFFSET,
+            (OCW2_ROTATE_SPECIFIC_EOI << OCW2_CMD_SHIFT) | 1,
+        )
+        .unwrap();
+        pic.set_irq(0, true).unwrap();
+        pic.set_irq(3, true).unwrap();
+        assert_eq!(pic.acknowledge(), 0x23);
+        pic.write(MASTER_OFFSET, OCW2_ROTATE_EOI << OCW2_CMD_SHIFT)
+            .unwrap();
+        // IRQ 3 has the lowest priority now.
+        pic.set_irq(4, true).unwrap();
+        assert_eq!(pic.acknowledge(), 0x24);
+        pic.write(MASTER_OFFSET, EOI).unwrap();
+        assert_eq!(pic.acknowledge(), 0x20);
+        pic.write(MASTER_OFFSET, EOI).unwrap();
+
+        // Set the priorities explicitly, so IRQ 7 has the highest one.
+        pic.write(MASTER_OFFSET, (OCW2_SET_PRIORITY << OCW2_CMD_SHIFT) | 6)
+            .unwrap();
+        pic.set_irq(0, false).unwrap();
+        pic.set_irq(0, true).unwrap();
+        pic.set_irq(7, true).unwrap();
+        assert_eq!(pic.acknowledge(), 0x27);
+    }
+
+    #[test]
+    fn test_pic_auto_eoi() {
+        let mut pic = new_pic();
+        pic.write(MASTER_OFFSET, 0x11).unwrap();
+        pic.write(MASTER_OFFSET + 1, 0x20).unwrap();
+        pic.write(MASTER_OFFSET + 1, 0x04).unwrap();
+        pic.write(MASTER_OFFSET + 1, 0x01 | ICW4_AUTO_EOI_BIT)
+            .unwrap();
+
+        // The IRQs don't stay in service, so the lower priority IRQ is
+        // delivered right after the higher priority one.
+        pic.set_irq(6, true).unwrap();
+        pic.set_irq(3, true).unwrap();
+        assert_eq!(pic.acknowledge(), 0x23);
+        assert!(pic.interrupt_pending());
+        assert_eq!(pic.acknowledge(), 0x26);
+        assert!(!pic.interrupt_pending());
+        pic.write(MASTER_OFFSET, READ_ISR).unwrap();
+        assert_eq!(pic.read(MASTER_OFFSET).unwrap(), 0x00);
+
+        // The rotation on automatic EOI gives the acknowledged IRQ the
+        // lowest priority.
+        pic.write(MASTER_OFFSET, OCW2_SET_ROTATE_AUTO_EOI << OCW2_CMD_SHIFT)
+            .unwrap();
+        pic.set_irq(0, true).unwrap();
+        assert_eq!(pic.acknowledge(), 0x20);
+        assert_eq!(pic.master.lowest_priority_next, 1);
+        pic.write(MASTER_OFFSET, OCW2_CLEAR_ROTATE_AUTO_EOI << OCW2_CMD_SHIFT)
+            .unwrap();
+        assert!(!pic.master.rotate_on_auto_eoi);
+    }
+
+    #[test]
+    fn test_pic_poll_and_special_mask() {
+        let mut pic = new_pic();
+
+        // A poll acknowledges the highest priority IRQ.
+        pic.set_irq(5, true).unwrap();
+        pic.write(MASTER_OFFSET, OCW3_BIT | OCW3_POLL_BIT).unwrap();
+        assert_eq!(pic.read(MASTER_OFFSET + 1).unwrap(), POLL_IRQ_BIT | 5);
+        assert!(!pic.interrupt_pending());
+        pic.write(MASTER_OFFSET, OCW3_BIT | OCW3_POLL_BIT).unwrap();
+        assert_eq!(pic.read(MASTER_OFFSET).unwrap(), 0x00);
+
+        // The special mask mode allows the lower priority IRQs while IRQ 5
+        // is in service and masked.
+        pic.set_irq(6, true).unwrap();
+        assert!(!pic.interrupt_pending());
+        pic.write(MASTER_OFFSET + 1, 0x20).unwrap();
+        pic.write(
+            MASTER_OFFSET,
+            OCW3_BIT | OCW3_SPECIAL_MASK_BIT | OCW3_SET_SPECIAL_MASK_BIT,
+        )
+        .unwrap();
+        assert!(pic.interrupt_pending());
+        assert_eq!(pic.acknowledge(), 0x26);
+        pic.write(MASTER_OFFSET, OCW3_BIT | OCW3_SPECIAL_MASK_BIT)
+            .unwrap();
+        assert!(!pic.master.special_mask);
+    }
+
+    #[test]
+    fn test_pic_level_triggered() {
+        let mut pic = new_pic();
+
+        // The bits of the IRQs that are always edge triggered are ignored.
+        pic.set_elcr(0xFFFF);
+        assert_eq!(pic.elcr(), 0xDEF8);
+        pic.set_elcr(0x0820);
+        assert_eq!(pic.elcr(), 0x0820);
+
+        // A level triggered IRQ is requested again after the EOI, as long as
+        // the line is high.
+        pic.set_irq(5, true).unwrap();
+        assert_eq!(pic.acknowledge(), 0x25);
+        pic.write(MASTER_OFFSET, EOI).unwrap();
+        assert!(pic.interrupt_pending());
+        assert_eq!(pic.acknowledge(), 0x25);
+
+        // Lowering the line withdraws the request.
+        pic.set_irq(5, false).unwrap();
+        pic.write(MASTER_OFFSET, EOI).unwrap();
+        assert!(!pic.interrupt_pending());
+        pic.set_irq(11, true).unwrap();
+        assert_eq!(pic.pending_vector(), Some(0x2B));
+        pic.set_irq(11, false).unwrap();
+        pic.write(SLAVE_OFFSET, READ_IRR).unwrap();
+        assert_eq!(pic.read(SLAVE_OFFSET).unwrap(), 0x00);
+        // The cascade IRQ is edge triggered, so the master still requests
+        // it, and the slave returns a spurious IRQ 15.
+        assert_eq!(pic.pending_vector(), Some(0x2F));
+
+        // The ELCR survives the initialization sequence.
+        pic.write(MASTER_OFFSET, 0x11).unwrap();
+        assert_eq!(pic.elcr(), 0x0820);
+
+        // The other IRQs are still edge triggered.
+        let mut pic = new_pic();
+        pic.set_elcr(0x0820);
+        pic.set_irq(3, true).unwrap();
+        pic.set_irq(3, false).unwrap();
+        assert_eq!(pic.acknowledge(), 0x23);
+        pic.write(MASTER_OFFSET, EOI).unwrap();
+        assert!(!pic.interrupt_pending());
+    }
+
+    #[test]
+    fn test_pic_legacy_device() {
+        let mut pic = new_pic();
+        pic.set_elcr(0x0820);
+
+        LegacyDevice::write(&mut pic, u16::from(MASTER_OFFSET + 1), &[0xFB]);
+        let mut data = [0; 1];
+        LegacyDevice::read(&mut pic, u16::from(MASTER_OFFSET + 1), &mut data);
+        assert_eq!(data, [0xFB]);
+
+        // The reset clears the IMR and the vector base, while the ELCR is
+        // kept.
+        pic.reset();
+        LegacyDevice::read(&mut pic, u16::from(MASTER_OFFSET + 1), &mut data);
+        assert_eq!(data, [0]);
+        assert_eq!(pic.master.vector_base, 0);
+        assert_eq!(pic.elcr(), 0x0820);
+    }
+
+    #[test]
+    fn test_pic_state() {
+        let mut pic = new_pic();
+        pic.set_elcr(0x0820);
+        // IRQ 1 in service, IRQ 4 masked, the level triggered IRQ 11
+        // requested, and the priorities rotated.
+        pic.write(MASTER_OFFSET, (OCW2_SET_PRIORITY << OCW2_CMD_SHIFT) | 7)
+            .unwrap();
+        pic.set_irq(1, true).unwrap();
+        assert_eq!(pic.acknowledge(), 0x21);
+        pic.write(MASTER_OFFSET + 1, 0x10).unwrap();
+        pic.set_irq(4, true).unwrap();
+        pic.set_irq(11, true).unwrap();
+        pic.write(MASTER_OFFSET, READ_ISR).unwrap();
+        pic.write(
+            MASTER_OFFSET,
+            OCW3_BIT | OCW3_SPECIAL_MASK_BIT | OCW3_SET_SPECIAL_MASK_BIT,
+        )
+        .unwrap();
+        // The slave is halfway through its initialization.
+        pic.write(SLAVE_OFFSET, 0x11).unwrap();
+        pic.write(SLAVE_OFFSET + 1, 0x70).unwrap();
+
+        let state = pic.state();
+        assert_eq!(state.master.isr, 0x02);
+        assert_eq!(state.master.imr, 0x10);
+        assert_eq!(state.master.lowest_priority_next, 0);
+        assert!(state.master.special_mask);
+        assert!(state.master.read_isr);
+        assert_eq!(state.slave.init_step, INIT_ICW3);
+
+        let mut restored = Pic::from_state(&state, EventFd::new(libc::EFD_NONBLOCK).unwrap());
+        assert_eq!(restored.state(), state);
+        assert_eq!(restored.elcr(), 0x0820);
+        assert_eq!(restored.interrupt_pending(), pic.interrupt_pending());
+        // The restore doesn't signal INTR.
+        assert_eq!(intr_count(&restored), 0);
+        assert_eq!(restored.read(MASTER_OFFSET).unwrap(), 0x02);
+
+        // Both PICs go on from where they were.
+        for pic in [&mut pic, &mut restored].iter_mut() {
+            pic.write(SLAVE_OFFSET + 1, 0x02).unwrap();
+            pic.write(SLAVE_OFFSET + 1, 0x01).unwrap();
+            pic.write(MASTER_OFFSET, EOI).unwrap();
+            pic.write(MASTER_OFFSET + 1, 0x00).unwrap();
+        }
+        assert_eq!(restored.state(), pic.state());
+        assert_eq!(restored.acknowledge(), pic.acknowledge());
+        assert_eq!(restored.acknowledge(), pic.acknowledge());
+        assert_eq!(restored.state(), pic.state());
+    }
+
+    #[test]
+    fn test_pic_invalid_state() {
+        // The out of range registers are masked instead of overflowing.
+        let state = PicState {
+            master: PicChipState {
+                irr: 0xFF,
+                elcr: 0xFF,
+                vector_base: 0xFF,
+                lowest_priority_next: 0xFF,
+                init_step: 0xFF,
+                ..Default::default()
+            },
+            slave: PicChipState {
+                elcr: 0xFF,
+                ..Default::default()
+            },
+        };
+        let mut pic = Pic::from_state(&state, EventFd::new(libc::EFD_NONBLOCK).unwrap());
+        assert_eq!(pic.elcr(), 0xDEF8);
+        // IRQ 7 has the highest priority, and its vector is the last one.
+        assert!(pic.interrupt_pending());
+        assert_eq!(pic.acknowledge(), 0xFF);
+        // An unknown initialization step behaves like a completed one.
+        pic.write(MASTER_OFFSET + 1, 0xFF).unwrap();
+        assert_eq!(pic.read(MASTER_OFFSET + 1).unwrap(), 0xFF);
+    }
+
+    #[test]
+    fn test_pic_persist() {
+        let mut pic = new_pic();
+        pic.set_irq(3, true).unwrap();
+
+        let state = pic.save();
+        assert_eq!(state, pic.state());
+        let mut restored = Pic::restore(EventFd::new(libc::EFD_NONBLOCK).unwrap(), &state).unwrap();
+        assert_eq!(restored.save(), state);
+        assert_eq!(restored.acknowledge(), 0x23);
+    }
+}
@@ -0,0 +1,263 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the routing of the legacy device interrupts to an interrupt
+//! controller.
+//!
+//! The devices raise their interrupts through [`Trigger`](../trait.Trigger.html)
+//! objects. An [`IrqTrigger`](struct.IrqTrigger.html) connects such a
+//! `Trigger` to an IRQ line of an
+//! [`InterruptController`](trait.InterruptController.html), e.g. the
+//! [`Pic`](../i8259/struct.Pic.html), which the VMM queries from its vCPU
+//! loop for the vectors that should be injected. The `IrqTrigger` is also an
+//! [`IrqLine`](../trait.IrqLine.html), for the devices that hold their
+//! interrupt at a level.
+
+use alloc::sync::Arc;
+use core::fmt;
+use core::result::Result;
+
+use crate::{IrqLine, Trigger};
+
+/// An interrupt controller, to which the interrupts of the legacy devices are
+/// routed.
+///
+/// The methods take `&self`, since the controller is shared between the
+/// devices and the vCPU loop, so the implementations rely on interior
+/// mutability.
+pub trait InterruptController {
+    /// Underlying type for the potential error conditions returned by
+    /// `Self::set_irq`.
+    type E: fmt::Debug;
+
+    /// Sets the level of an IRQ line.
+    ///
+    /// # Arguments
+    /// * `irq` - The IRQ line.
+    /// * `level` - The new level of the line.
+    fn set_irq(&self, irq: u8, level: bool) -> Result<(), Self::E>;
+
+    /// Returns the vector of the interrupt that should be injected next, if
+    /// any, without acknowledging it.
+    fn get_pending_vector(&self) -> Option<u8>;
+
+    /// Acknowledges the interrupt that should be injected next, if any, and
+    /// returns its vector.
+    fn acknowledge(&self) -> Option<u8>;
+}
+
+impl<IC: InterruptController> InterruptController for Arc<IC> {
+    type E = IC::E;
+
+    fn set_irq(&self, irq: u8, level: bool) -> Result<(), Self::E> {
+        self.as_ref().set_irq(irq, level)
+    }
+
+    fn get_pending_vector(&self) -> Option<u8> {
+        self.as_ref().get_pending_vector()
+    }
+
+    fn acknowledge(&self) -> Option<u8> {
+        self.as_ref().acknowledge()
+    }
+}
+
+/// A [`Trigger`](../trait.Trigger.html) which pulses an IRQ line of an
+/// interrupt controller, i.e. raises and then lowers it, so that the
+/// controller sees a rising edge each time the device triggers it.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::{Arc, Mutex};
+/// # use vm_superio::i8259::Pic;
+/// # use vm_superio::interrupt::{InterruptController, IrqTrigger};
+/// # use vm_superio::pit::{MonotonicClock, Pit};
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let pic = Arc::new(Mutex::new(Pic::new(DummyTrigger)));
+/// // Connect the PIT to IRQ 0.
+/// let pit = Pit::new(IrqTrigger::new(pic.clone(), 0), MonotonicClock::new());
+///
+/// pit.irq0_evt().trigger().unwrap();
+/// assert_eq!(pic.get_pending_vector(), Some(0x00));
+/// assert_eq!(pic.acknowledge(), Some(0x00));
+/// assert_eq!(pic.get_pending_vector(), None);
+/// ```
+#[derive(Debug)]
+pub struct IrqTrigger<IC: InterruptController> {
+    controller: Arc<IC>,
+    irq: u8,
+}
+
+impl<IC: InterruptController> IrqTrigger<IC> {
+    /// Creates a new `IrqTrigger` instance, which pulses the `irq` line of
+    /// `controller`.
+    ///
+    /// # Arguments
+    /// * `controller` - The interrupt controller to which the device is
+    ///   connected.
+    /// * `irq` - The IRQ line of the device.
+    pub const fn new(controller: Arc<IC>, irq: u8) -> Self {
+        IrqTrigger { controller, irq }
+    }
+
+    /// Returns the IRQ line of the device.
+    pub fn irq(&self) -> u8 {
+        self.irq
+    }
+
+    /// Provides a reference to the interrupt controller.
+    pub fn controller(&self) -> &Arc<IC> {
+        &self.controller
+    }
+}
+
+// Implemented by hand, since the derive would require `IC: Clone`.
+impl<IC: InterruptController> Clone for IrqTrigger<IC> {
+    fn clone(&self) -> Self {
+        IrqTrigger {
+            controller: self.controller.clone(),
+            irq: self.irq,
+        }
+    }
+}
+
+impl<IC: InterruptController> Trigger for IrqTrigger<IC> {
+    type E = IC::E;
+
+    fn trigger(&self) -> Result<(), Self::E> {
+        self.controller.set_irq(self.irq, true)?;
+        self.controller.set_irq(self.irq, false)
+    }
+}
+
+impl<IC: InterruptController> IrqLine for IrqTrigger<IC> {
+    type E = IC::E;
+
+    fn assert(&self) -> Result<(), Self::E> {
+        self.controller.set_irq(self.irq, true)
+    }
+
+    fn deassert(&self) -> Result<(), Self::E> {
+        self.controller.set_irq(self.irq, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i8259::Pic;
+    use crate::testing::MockClock;
+    use crate::{I8042Device, Pit, Serial};
+    use std::io::sink;
+    use std::sync::Mutex;
+    use vmm_sys_util::eventfd::EventFd;
+
+    // Initializes the PICs like Linux does, with the vectors 0x20-0x2F.
+    fn new_pic() -> Arc<Mutex<Pic<EventFd>>> {
+        let mut pic = Pic::new(EventFd::new(libc::EFD_NONBLOCK).unwrap());
+        for &(offset, value) in [
+            (0x00, 0x11),
+            (0x01, 0x20),
+            (0x01, 0x04),
+            (0x01, 0x01),
+            (0x80, 0x11),
+            (0x81, 0x28),
+            (0x81, 0x02),
+            (0x81, 0x01),
+        ]
+        .iter()
+        {
+            pic.write(offset, value).unwrap();
+        }
+        Arc::new(Mutex::new(pic))
+    }
+
+    #[test]
+    fn test_irq_trigger() {
+        let pic = new_pic();
+        let trigger = IrqTrigger::new(pic.clone(), 9);
+        assert_eq!(trigger.irq(), 9);
+
+        // Each trigger is a new edge.
+        trigger.trigger().unwrap();
+        assert_eq!(pic.acknowledge(), Some(0x29));
+        pic.lock().unwrap().write(0x80, 0x20).unwrap();
+        pic.lock().unwrap().write(0x00, 0x20).unwrap();
+        trigger.clone().trigger().unwrap();
+        assert_eq!(pic.get_pending_vector(), Some(0x29));
+        assert_eq!(pic.acknowledge(), Some(0x29));
+        assert_eq!(pic.acknowledge(), None);
+    }
+
+    #[test]
+    fn test_irq_line() {
+        let pic = new_pic();
+        // Program the IRQ 4 as level-triggered through the ELCR of the
+        // master PIC.
+        pic.lock().unwrap().set_elcr(1 << 4);
+        let line = IrqTrigger::new(pic.clone(), 4);
+
+        // The line is delivered once while it stays asserted.
+        line.assert().unwrap();
+        line.set_level(true).unwrap();
+        assert_eq!(pic.acknowledge(), Some(0x24));
+        assert_eq!(pic.acknowledge(), None);
+        pic.lock().unwrap().write(0x00, 0x20).unwrap();
+
+        // An asserted line is delivered again after the end of interrupt.
+        line.resample().unwrap();
+        assert_eq!(pic.acknowledge(), Some(0x24));
+        pic.lock().unwrap().write(0x00, 0x20).unwrap();
+
+        line.set_level(false).unwrap();
+        assert_eq!(pic.get_pending_vector(), None);
+        line.deassert().unwrap();
+        assert_eq!(pic.get_pending_vector(), None);
+    }
+
+    #[test]
+    fn test_legacy_devices_routing() {
+        let pic = new_pic();
+        let clock = MockClock::new();
+
+        let mut pit = Pit::new(IrqTrigger::new(pic.clone(), 0), clock.clone());
+        // The i8042 reset line is not an IRQ, so it is connected to an
+        // invalid line, which the PIC ignores.
+        let mut i8042 = I8042Device::with_irqs(
+            IrqTrigger::new(pic.clone(), 0xFF),
+            IrqTrigger::new(pic.clone(), 1),
+            IrqTrigger::new(pic.clone(), 12),
+        );
+        let mut serial = Serial::new(IrqTrigger::new(pic.clone(), 4), sink());
+
+        // The serial port raises IRQ 4, and the keyboard raises IRQ 1, when
+        // they receive data.
+        serial.write(1, 0x01).unwrap();
+        serial.enqueue_raw_bytes(b"a").unwrap();
+        i8042.enqueue_kbd_bytes(&[0x1C]).unwrap();
+        assert_eq!(pic.get_pending_vector(), Some(0x21));
+        assert!(pic.lock().unwrap().intr_evt().read().is_ok());
+
+        // Program the PIT for a 1000 ticks period, and let it expire.
+        pit.write(3, 0x34);
+        pit.write(0, 0xE8);
+        pit.write(0, 0x03);
+        clock.set_ns(pit.next_deadline().unwrap());
+        pit.on_timer_expired().unwrap();
+
+        // The interrupts are delivered by priority.
+        let mut vectors = Vec::new();
+        while let Some(vector) = pic.acknowledge() {
+            vectors.push(vector);
+            pic.lock().unwrap().write(0x00, 0x20).unwrap();
+        }
+        assert_eq!(vectors, vec![0x20, 0x21, 0x24]);
+    }
+}
@@ -8,25 +8,208 @@
 
 //! Emulation for legacy devices.
 //!
-//! For now, it offers emulation support only for the Linux serial console,
-//! an Arm PL031 Real Time Clock (RTC), and an i8042 PS/2 controller that only
-//! handles the CPU reset.
+//! For now, it offers emulation support only for:
+//! - the Linux serial console;
+//! - the parallel port (LPT1, ports 0x378-0x37A);
+//! - a stub of the i82077 floppy disk controller (ports 0x3F0-0x3F7);
+//! - a stub of the game port (port 0x201);
+//! - an Arm PL031 Real Time Clock (RTC);
+//! - an Arm PL050 Keyboard/Mouse Interface (KMI);
+//! - an Arm PL061 General Purpose Input/Output (GPIO) controller;
+//! - the SBSA Generic Watchdog;
+//! - an Arm SP804 dual-timer module;
+//! - an Arm SP805 watchdog module;
+//! - a syscon register block, for powering off and rebooting the system;
+//! - the SiFive test finisher of the RISC-V virt machine;
+//! - an MMIO debug exit device, for the guest test harnesses;
+//! - a minimal i8042 PS/2 controller;
+//! - the System Control Ports A and B (ports 0x92 and 0x61);
+//! - the POST code port (port 0x80);
+//! - the Reset Control Register (port 0xCF9);
+//! - the Bochs debug console (port 0xE9);
+//! - the pvpanic device, in its port I/O (port 0x505) and MMIO flavors;
+//! - an i8254 Programmable Interval Timer (PIT);
+//! - the ACPI Power Management timer, and the PM1 event and control blocks;
+//! - the APM control and status ports (ports 0xB2 and 0xB3);
+//! - the ACPI Generic Event Device (GED);
+//! - the Intel 6300ESB watchdog timer;
+//! - the QEMU firmware configuration (fw_cfg) device, in its port I/O
+//!   (ports 0x510-0x51B) and MMIO flavors;
+//! - a stub of the VMware backdoor port (port 0x5658);
+//! - a stub of the i8237 DMA controllers (ports 0x00-0x0F and 0xC0-0xDF) and
+//!   of their page registers (ports 0x80-0x8F);
+//! - the configuration interface of an LPC Super I/O chip (ports 0x2E and
+//!   0x2F);
+//! - a null device, for backing the unclaimed port ranges;
+//! - the cascaded pair of i8259 Programmable Interrupt Controllers (PIC) and
+//!   their Edge/Level Control Registers (ELCR).
 //!
 //! It also provides a [Trigger](trait.Trigger.html) interface for an object
-//! that can generate an event.
+//! that can generate an event, an [IrqLine](trait.IrqLine.html) interface for
+//! the level-triggered interrupt lines, the [LegacyDevice](trait.LegacyDevice.html)
+//! interface implemented by all the devices, the
+//! [PortIoDispatcher](dispatcher/struct.PortIoDispatcher.html) and
+//! [MmioDispatcher](dispatcher/struct.MmioDispatcher.html) routing the
+//! accesses to them, and the
+//! [WatchdogEvents](watchdog/trait.WatchdogEvents.html) interface shared by
+//! the watchdogs, while the [mmio](mmio/index.html) module validates the
+//! accesses to the registers of the MMIO devices. The
+//! [DeviceMetrics](metrics/trait.DeviceMetrics.html) interface counts the
+//! accesses, the interrupts and the errors of any device. The errors of the
+//! devices convert into the [Error](error/enum.Error.html) of the crate, so
+//! that they are handled with a single type.
+//!
+//! The [SuperioChassis](chassis/struct.SuperioChassis.html) bundles the
+//! serial ports, the i8042 controller, the RTC and the PIT, and saves and
+//! restores them with a single state. The devices with a state are saved
+//! and restored through the [Persist](trait.Persist.html) interface.
+//!
+//! With the `fdt` feature, the `fdt` module generates the device tree nodes
+//! of the MMIO devices, while with the `acpi` feature, the `acpi` module
+//! generates the DSDT entries of the serial ports and of the i8042 controller.
+//! With the `vm-device` feature, the devices implement the bus traits of
+//! `vm-device`, as described in the `bus` module, while with the `eventfd`
+//! feature, the `EventFd` of `vmm-sys-util` is a `Trigger`, and the
+//! `EventFdIrqLine` is an `IrqLine` backed by an irqfd.
+//! With the `log` feature, the devices emit rate-limited warnings through
+//! the `log` crate when the guest accesses invalid offsets or writes invalid
+//! values, and with the `tracing` feature, the main devices emit `tracing`
+//! events for their register accesses, interrupts and lifecycle, while with
+//! the `fuzzing` feature, the states of the devices implement
+//! `arbitrary::Arbitrary`. With the `serde` feature, they implement the
+//! `Serialize` and `Deserialize` traits of `serde`. The `testing` module provides the test
+//! doubles of the `Trigger` and of the output of the devices, and with the
+//! `proptest` feature, the `proptest` strategies and invariant checks of the
+//! devices. When verified with Kani, the proof harnesses of the `proofs`
+//! module prove that the register accesses of the main devices never panic.
+//!
+//! The crate is `no_std` when the default `std` feature is disabled, in which
+//! case it only depends on `alloc`. The devices then write their output to a
+//! [ByteSink](trait.ByteSink.html) instead of a `std::io::Write`, and the
+//! `Rtc` takes its time from a [WallClock](rtc_pl031/trait.WallClock.html) of
+//! the VMM, while the modules that need a `Mutex`, such as the dispatchers,
+//! the chassis and the `GuestClock`, are not available.
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(missing_docs)]
 #![deny(missing_copy_implementations)]
 
+#[macro_use]
+extern crate alloc;
+
+#[macro_use]
+mod logging;
+#[macro_use]
+mod trace;
+
+#[cfg(feature = "acpi")]
+pub mod acpi;
+pub mod apm;
+#[cfg(feature = "vm-device")]
+pub mod bus;
+pub mod cf9;
+#[cfg(feature = "std")]
+pub mod chassis;
+pub mod debug_exit;
+pub mod debugcon;
+#[cfg(feature = "std")]
+pub mod dispatcher;
+pub mod elcr;
+pub mod error;
+#[cfg(any(feature = "eventfd", test))]
+pub mod eventfd;
+pub mod fdc;
+#[cfg(feature = "fdt")]
+pub mod fdt;
+pub mod fw_cfg;
+pub mod game_port;
+pub mod ged;
+#[cfg(feature = "std")]
+pub mod guest_clock;
+pub mod i6300esb;
 pub mod i8042;
+pub mod i8237;
+pub mod i8259;
+pub mod interrupt;
+pub mod metrics;
+pub mod mmio;
+pub mod null_port;
+pub mod parallel;
+pub mod pit;
+pub mod pl050;
+pub mod pl061;
+pub mod pm1;
+pub mod pm_timer;
+pub mod port61;
+pub mod port80;
+pub mod port92;
+#[cfg(all(kani, feature = "std"))]
+mod proofs;
+pub mod pvpanic;
 pub mod rtc_pl031;
+pub mod sbsa_gwdt;
 pub mod serial;
+pub mod sifive_test;
+pub mod sp804;
+pub mod sp805;
+pub mod superio;
+pub mod syscon;
+#[cfg(feature = "std")]
+pub mod testing;
+pub mod vmport;
+pub mod watchdog;
 
-pub use i8042::I8042Device;
+pub use apm::Apm;
+pub use cf9::Cf9;
+#[cfg(feature = "std")]
+pub use chassis::{SuperioChassis, SuperioChassisState};
+pub use debug_exit::DebugExit;
+pub use debugcon::DebugCon;
+#[cfg(feature = "std")]
+pub use dispatcher::{MmioDispatcher, PortIoDispatcher};
+pub use elcr::Elcr;
+pub use error::{Error, StateError};
+pub use fdc::Fdc;
+pub use fw_cfg::{FwCfg, FwCfgMmio};
+pub use game_port::GamePort;
+pub use ged::Ged;
+#[cfg(feature = "std")]
+pub use guest_clock::GuestClock;
+pub use i6300esb::I6300Esb;
+pub use i8042::{I8042Device, I8042State};
+pub use i8237::{DmaPageRegisters, I8237};
+pub use i8259::{Pic, PicState};
+pub use metrics::DeviceMetrics;
+pub use mmio::{BadAccessPolicy, MmioEvents};
+pub use null_port::NullPortDevice;
+pub use parallel::Parallel;
+pub use pit::{Pit, PitState};
+pub use pl050::Pl050;
+pub use pl061::Pl061;
+pub use pm1::Pm1;
+pub use pm_timer::PmTimer;
+pub use port61::Port61;
+pub use port80::Port80;
+pub use port92::Port92;
+pub use pvpanic::{PvPanic, PvPanicMmio};
+#[cfg(feature = "std")]
+pub use rtc_pl031::SharedRtc;
 pub use rtc_pl031::{Rtc, RtcState};
-pub use serial::{Serial, SerialState};
+pub use sbsa_gwdt::SbsaGwdt;
+#[cfg(feature = "std")]
+pub use serial::SharedSerial;
+pub use serial::{Serial, SerialMmio, SerialState};
+pub use sifive_test::SifiveTest;
+pub use sp804::Sp804;
+pub use sp805::Sp805;
+pub use superio::SuperIo;
+pub use syscon::Syscon;
+pub use vmport::VmPort;
+pub use watchdog::{WatchdogAction, WatchdogEvents};
 
-use std::result::Result;
+use core::convert::TryFrom;
+use core::fmt;
+use core::result::Result;
 
 /// Abstraction for a simple, push-button like interrupt mechanism.
 /// This helps in abstracting away how events/interrupts are generated when
@@ -36,19 +219,217 @@ use std::result::Result;
 /// initializing that device. The generic type `T: Trigger` is used in the
 /// device's structure definition to mark the fact that the events notification
 /// mechanism is done via the Trigger interface.
-/// An example of how to implement the `Trigger` interface for
-/// [an eventfd wrapper](https://docs.rs/vmm-sys-util/latest/vmm_sys_util/eventfd/index.html)
-/// can be found in the
-/// [`Example` section from `Serial`](../vm_superio/serial/struct.Serial.html#example).
-/// The `EventFd` is wrapped in the `EventFdTrigger` newtype because Rust
-/// doesn't allow implementing an external trait on external types. To get
-/// around this restriction, the newtype pattern can be used. More details
-/// about this,
-/// [here](https://doc.rust-lang.org/book/ch19-03-advanced-traits.html#using-the-newtype-pattern-to-implement-external-traits-on-external-types).
+/// With the `eventfd` feature, the crate implements `Trigger` for the
+/// [`EventFd`](https://docs.rs/vmm-sys-util/latest/vmm_sys_util/eventfd/index.html)
+/// of `vmm-sys-util`, and for the
+/// [`eventfd::EventFdTrigger`](eventfd/struct.EventFdTrigger.html) newtype,
+/// whose documentation shows how to use it with a `Serial`. The tests and the
+/// examples can use the [`testing::MockTrigger`](testing/struct.MockTrigger.html)
+/// instead, which counts its triggers.
 pub trait Trigger {
     /// Underlying type for the potential error conditions returned by `Self::trigger`.
-    type E: std::fmt::Debug;
+    type E: fmt::Debug;
 
     /// Trigger an event.
     fn trigger(&self) -> Result<(), Self::E>;
 }
+
+/// Abstraction for an interrupt line with level semantics.
+///
+/// Unlike a [`Trigger`](trait.Trigger.html), which only notifies an event,
+/// the line is asserted while the interrupt condition of the device holds,
+/// and deasserted once the guest clears it. This is how the level-triggered
+/// sources, such as the UART, the RTC alarm or the watchdogs, are wired to
+/// the interrupt controller.
+///
+/// The [`IrqTrigger`](interrupt/struct.IrqTrigger.html) implements it on top
+/// of an [`InterruptController`](interrupt/trait.InterruptController.html),
+/// and, with the `eventfd` feature, the
+/// [`EventFdIrqLine`](eventfd/struct.EventFdIrqLine.html) implements it on
+/// top of an irqfd with a resample eventfd.
+pub trait IrqLine {
+    /// Underlying type for the potential error conditions returned by the
+    /// methods of the line.
+    type E: fmt::Debug;
+
+    /// Raises the line.
+    fn assert(&self) -> Result<(), Self::E>;
+
+    /// Lowers the line.
+    fn deassert(&self) -> Result<(), Self::E>;
+
+    /// Sets the level of the line, i.e. asserts it if `level` is `true`, and
+    /// deasserts it otherwise.
+    ///
+    /// # Arguments
+    /// * `level` - The new level of the line.
+    fn set_level(&self, level: bool) -> Result<(), Self::E> {
+        if level {
+            self.assert()
+        } else {
+            self.deassert()
+        }
+    }
+
+    /// Resamples the line, after the interrupt controller lowered it on the
+    /// end of interrupt, so that a line that is still asserted is delivered
+    /// again.
+    ///
+    /// It is called by the VMM, e.g. when the resample eventfd of a KVM
+    /// irqfd is signaled. The default implementation does nothing, which
+    /// suits the controllers that track the level of the line themselves.
+    fn resample(&self) -> Result<(), Self::E> {
+        Ok(())
+    }
+}
+
+/// The error returned by a [`ByteSink`](trait.ByteSink.html), which is an
+/// `std::io::Error` with the `std` feature.
+#[cfg(feature = "std")]
+pub type SinkError = std::io::Error;
+
+/// The error returned by a [`ByteSink`](trait.ByteSink.html) without the
+/// `std` feature.
+#[cfg(not(feature = "std"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SinkError;
+
+#[cfg(not(feature = "std"))]
+impl fmt::Display for SinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to write to the sink")
+    }
+}
+
+/// The destination of the output of the devices, such as the data sent by
+/// the driver through the serial port.
+///
+/// With the `std` feature, it is implemented by all the `std::io::Write`
+/// types, e.g. `std::io::Stdout` or `std::io::Sink`. Without it, it is
+/// implemented by `Vec<u8>`, and by the types of the VMM, e.g. for writing to
+/// the UART of the host.
+pub trait ByteSink {
+    /// Writes all the bytes of `buf`.
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), SinkError>;
+
+    /// Flushes the bytes written so far to their destination.
+    fn flush(&mut self) -> Result<(), SinkError>;
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> ByteSink for W {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), SinkError> {
+        std::io::Write::write_all(self, buf)
+    }
+
+    fn flush(&mut self) -> Result<(), SinkError> {
+        std::io::Write::flush(self)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl ByteSink for alloc::vec::Vec<u8> {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), SinkError> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), SinkError> {
+        Ok(())
+    }
+}
+
+/// The interface implemented by all the devices of the crate, through which
+/// the VMM drives them without knowing their type, e.g. from a collection of
+/// `Box<dyn LegacyDevice<State = ()>>`.
+///
+/// The accesses are relative to the base address of the device. The port I/O
+/// devices have byte wide registers, so their wider accesses read as 0, and
+/// are otherwise ignored, while the MMIO devices validate the accesses like
+/// their own `read` and `write` methods. The errors of the `Trigger` objects
+/// can't be returned through this interface, and are dropped.
+pub trait LegacyDevice {
+    /// The state of the device, as saved in a snapshot, or `()` for the
+    /// devices without such a state.
+    type State;
+
+    /// Handles a read of `data.len()` bytes at `offset`.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset of the access, from the base address of the
+    ///   device.
+    /// * `data` - The little-endian byte array storing the read value.
+    fn read(&mut self, offset: u16, data: &mut [u8]);
+
+    /// Handles a write of `data` at `offset`.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset of the access, from the base address of the
+    ///   device.
+    /// * `data` - The little-endian byte array to write to the device.
+    fn write(&mut self, offset: u16, data: &[u8]);
+
+    /// Resets the registers of the device to their power-on values, like a
+    /// system reset. The objects passed at creation, such as the `Trigger`
+    /// and the events objects, and the configuration of the VMM are kept.
+    fn reset(&mut self);
+
+    /// Returns the state of the device.
+    fn state(&self) -> Self::State;
+}
+
+/// The interface through which the devices with a state are saved in a
+/// snapshot, and restored from it, like the `Persist` trait of the VMMs.
+///
+/// The state only holds the registers of the device, while the objects the
+/// VMM passes at creation, such as the `Trigger` objects, are the
+/// `ConstructorArgs` of the restore.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::rtc_pl031::{NoEvents, SystemClock};
+/// # use vm_superio::{Persist, Rtc};
+/// fn snapshot<P: Persist>(device: &P) -> P::State {
+///     device.save()
+/// }
+///
+/// let mut rtc = Rtc::new();
+/// rtc.write(0x8, &0x1234u32.to_le_bytes());
+///
+/// let state = snapshot(&rtc);
+/// let restored = Rtc::restore((NoEvents, SystemClock), &state).unwrap();
+/// assert_eq!(restored.save(), state);
+/// ```
+pub trait Persist: Sized {
+    /// The state of the device, as saved in a snapshot.
+    type State;
+    /// The objects from which the device is restored, besides its state.
+    type ConstructorArgs;
+    /// The errors encountered while restoring the device.
+    type Error;
+
+    /// Returns the state of the device.
+    fn save(&self) -> Self::State;
+
+    /// Creates a new device from a saved `state`.
+    ///
+    /// # Arguments
+    /// * `constructor_args` - The objects used by the device, such as its
+    ///   `Trigger` and events objects.
+    /// * `state` - The state from which the device is restored.
+    fn restore(
+        constructor_args: Self::ConstructorArgs,
+        state: &Self::State,
+    ) -> Result<Self, Self::Error>;
+}
+
+// Returns the offset of the byte wide register accessed by `len` bytes at
+// `offset`, if the access is one byte wide.
+pub(crate) fn byte_offset(offset: u16, len: usize) -> Option<u8> {
+    if len != 1 {
+        guest_warn!("Ignoring a {}-byte access at offset {:#x}", len, offset);
+        return None;
+    }
+    u8::try_from(offset).ok()
+}
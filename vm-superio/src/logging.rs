@@ -0,0 +1,116 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+// Provides the rate-limited warnings about the invalid accesses of the guest,
+// which are emitted through the `log` crate with the `log` feature.
+//
+// A misbehaving guest may hit the same invalid register in a loop, so each
+// call site of `guest_warn!` logs its first `BURST` warnings, and then one in
+// every `INTERVAL`. Without the `log` feature, the macro only type checks its
+// arguments.
+
+#[cfg(feature = "log")]
+use core::sync::atomic::{AtomicU32, Ordering};
+
+// The number of warnings logged by a call site before the rate limiting.
+#[cfg(feature = "log")]
+const BURST: u32 = 10;
+// Once rate limited, a call site logs one warning in every `INTERVAL`.
+#[cfg(feature = "log")]
+const INTERVAL: u32 = 1000;
+
+// The number of warnings of a call site.
+#[cfg(feature = "log")]
+pub(crate) struct RateLimiter(AtomicU32);
+
+#[cfg(feature = "log")]
+impl RateLimiter {
+    pub(crate) const fn new() -> Self {
+        RateLimiter(AtomicU32::new(0))
+    }
+
+    // Returns whether the next warning is logged.
+    pub(crate) fn allow(&self) -> bool {
+        let count = self.0.fetch_add(1, Ordering::Relaxed);
+        count < BURST || count.is_multiple_of(INTERVAL)
+    }
+}
+
+// Logs a rate-limited warning about an invalid access of the guest, with the
+// same arguments as `format!`.
+macro_rules! guest_warn {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        {
+            static LIMITER: $crate::logging::RateLimiter = $crate::logging::RateLimiter::new();
+            if LIMITER.allow() {
+                log::warn!($($arg)+);
+            }
+        }
+        #[cfg(not(feature = "log"))]
+        {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+#[cfg(all(test, feature = "log"))]
+mod tests {
+    use super::*;
+    use crate::{LegacyDevice, Port92};
+    use std::sync::Mutex;
+    use vmm_sys_util::eventfd::EventFd;
+
+    // Records the warnings of the port 0x92 device, since the other tests
+    // run concurrently.
+    struct Port92Logger(Mutex<Vec<String>>);
+
+    impl log::Log for Port92Logger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.target() == "vm_superio::port92"
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                assert_eq!(record.level(), log::Level::Warn);
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: Port92Logger = Port92Logger(Mutex::new(Vec::new()));
+
+    #[test]
+    fn test_guest_warn() {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Warn);
+
+        let mut port92 = Port92::new(EventFd::new(libc::EFD_NONBLOCK).unwrap());
+        for _ in 0..2 * INTERVAL {
+            let _ = port92.write(1, 0x01);
+        }
+        // The valid accesses aren't logged.
+        LegacyDevice::read(&mut port92, 0, &mut [0; 1]);
+        assert_eq!(port92.read(1), 0x00);
+
+        let warnings = LOGGER.0.lock().unwrap();
+        assert_eq!(warnings.len(), BURST as usize + 2);
+        assert_eq!(warnings[0], "Invalid port 0x92 write at offset 0x1");
+        assert_eq!(
+            warnings.last().unwrap(),
+            "Invalid port 0x92 read at offset 0x1"
+        );
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new();
+        let allowed = (0..3 * INTERVAL).filter(|_| limiter.allow()).count() as u32;
+        // The first burst, and the warnings 1000 and 2000.
+        assert_eq!(allowed, BURST + 2);
+        assert!(limiter.allow());
+    }
+}
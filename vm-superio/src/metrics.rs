@@ -0,0 +1,396 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the metrics shared by all the devices.
+//!
+//! The [`DeviceMetrics`](trait.DeviceMetrics.html) trait counts the accesses
+//! of the driver, the interrupts and the errors of a device, so that the VMM
+//! wires a single metrics backend to all the devices, instead of adapting
+//! the events traits of each of them. The
+//! [`MeteredDevice`](struct.MeteredDevice.html) records the accesses to any
+//! [`LegacyDevice`](../trait.LegacyDevice.html), while the
+//! [`MeteredTrigger`](struct.MeteredTrigger.html) records the interrupts
+//! raised through the [`Trigger`](../trait.Trigger.html) passed to the device,
+//! and the errors of that `Trigger`, which `LegacyDevice` can't return.
+
+use alloc::sync::Arc;
+use core::result::Result;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{IrqLine, LegacyDevice, Trigger};
+
+/// Defines the counters shared by all the devices. The methods do nothing by
+/// default, so a backend only implements the ones it keeps track of, e.g. by
+/// incrementing metrics or logging messages.
+pub trait DeviceMetrics {
+    /// The driver read from the device.
+    fn read_access(&self) {}
+
+    /// The driver wrote to the device.
+    fn write_access(&self) {}
+
+    /// The driver accessed the device outside of its registers.
+    fn invalid_access(&self) {}
+
+    /// The device raised an interrupt.
+    fn interrupt(&self) {}
+
+    /// The device failed to raise an interrupt, or to signal an event.
+    fn error(&self) {}
+}
+
+/// Provides a no-op implementation of `DeviceMetrics` which can be used in situations that
+/// do not require keeping track of the device metrics.
+#[derive(Debug, Clone, Copy)]
+pub struct NoMetrics;
+
+impl DeviceMetrics for NoMetrics {}
+
+impl<M: DeviceMetrics> DeviceMetrics for Arc<M> {
+    fn read_access(&self) {
+        self.as_ref().read_access();
+    }
+
+    fn write_access(&self) {
+        self.as_ref().write_access();
+    }
+
+    fn invalid_access(&self) {
+        self.as_ref().invalid_access();
+    }
+
+    fn interrupt(&self) {
+        self.as_ref().interrupt();
+    }
+
+    fn error(&self) {
+        self.as_ref().error();
+    }
+}
+
+/// A `DeviceMetrics` backend counting the events, which is usually shared
+/// with the VMM in an `Arc`.
+#[derive(Debug, Default)]
+pub struct DeviceCounters {
+    reads: AtomicU64,
+    writes: AtomicU64,
+    invalid_accesses: AtomicU64,
+    interrupts: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl DeviceCounters {
+    /// Creates a new `DeviceCounters` instance, with all the counters at 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of reads.
+    pub fn reads(&self) -> u64 {
+        self.reads.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of writes.
+    pub fn writes(&self) -> u64 {
+        self.writes.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of invalid accesses.
+    pub fn invalid_accesses(&self) -> u64 {
+        self.invalid_accesses.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of interrupts.
+    pub fn interrupts(&self) -> u64 {
+        self.interrupts.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of errors.
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+}
+
+impl DeviceMetrics for DeviceCounters {
+    fn read_access(&self) {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn write_access(&self) {
+        self.writes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn invalid_access(&self) {
+        self.invalid_accesses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn interrupt(&self) {
+        self.interrupts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// A [`LegacyDevice`](../trait.LegacyDevice.html) recording the accesses of
+/// the driver to the device it wraps.
+///
+/// The accesses that are empty, wider than 8 bytes, or that don't fit in the
+/// `size` bytes of the device are recorded as invalid, and are still handled
+/// by the device, which ignores them.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use vm_superio::metrics::{DeviceCounters, MeteredDevice};
+/// # use vm_superio::{LegacyDevice, Port80};
+/// let counters = Arc::new(DeviceCounters::new());
+/// let mut port80 = MeteredDevice::new(Port80::new(16), 1, counters.clone());
+///
+/// LegacyDevice::write(&mut port80, 0, &[0x42]);
+/// LegacyDevice::write(&mut port80, 0, &[0x42, 0x00]);
+/// assert_eq!(counters.writes(), 2);
+/// assert_eq!(counters.invalid_accesses(), 1);
+/// ```
+#[derive(Debug)]
+pub struct MeteredDevice<D: LegacyDevice, M: DeviceMetrics> {
+    device: D,
+    size: u16,
+    metrics: M,
+}
+
+impl<D: LegacyDevice, M: DeviceMetrics> MeteredDevice<D, M> {
+    /// Creates a new `MeteredDevice` instance.
+    ///
+    /// # Arguments
+    /// * `device` - The device whose accesses are recorded.
+    /// * `size` - The size of the range of the device, in bytes.
+    /// * `metrics` - The metrics backend.
+    pub const fn new(device: D, size: u16, metrics: M) -> Self {
+        MeteredDevice {
+            device,
+            size,
+            metrics,
+        }
+    }
+
+    /// Provides a reference to the device.
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// Provides a mutable reference to the device.
+    pub fn device_mut(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    /// Provides a reference to the metrics backend.
+    pub fn metrics(&self) -> &M {
+        &self.metrics
+    }
+
+    /// Consumes the `MeteredDevice` and returns the device.
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+
+    fn check_access(&self, offset: u16, len: usize) {
+        let valid = (1..=8).contains(&len) && usize::from(offset) + len <= usize::from(self.size);
+        if !valid {
+            self.metrics.invalid_access();
+        }
+    }
+}
+
+impl<D: LegacyDevice, M: DeviceMetrics> LegacyDevice for MeteredDevice<D, M> {
+    type State = D::State;
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        self.metrics.read_access();
+        self.check_access(offset, data.len());
+        self.device.read(offset, data);
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        self.metrics.write_access();
+        self.check_access(offset, data.len());
+        self.device.write(offset, data);
+    }
+
+    fn reset(&mut self) {
+        self.device.reset();
+    }
+
+    fn state(&self) -> Self::State {
+        self.device.state()
+    }
+}
+
+/// A [`Trigger`](../trait.Trigger.html) recording the interrupts raised
+/// through the `Trigger` it wraps, and its errors. It is also an
+/// [`IrqLine`](../trait.IrqLine.html) when it wraps one, in which case the
+/// assertions are recorded as interrupts.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use vm_superio::metrics::{DeviceCounters, MeteredTrigger};
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let counters = Arc::new(DeviceCounters::new());
+/// let trigger = MeteredTrigger::new(DummyTrigger, counters.clone());
+///
+/// trigger.trigger().unwrap();
+/// assert_eq!(counters.interrupts(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MeteredTrigger<T, M: DeviceMetrics> {
+    inner: T,
+    metrics: M,
+}
+
+impl<T, M: DeviceMetrics> MeteredTrigger<T, M> {
+    /// Creates a new `MeteredTrigger` instance.
+    ///
+    /// # Arguments
+    /// * `inner` - The `Trigger` or `IrqLine` raising the interrupts.
+    /// * `metrics` - The metrics backend.
+    pub const fn new(inner: T, metrics: M) -> Self {
+        MeteredTrigger { inner, metrics }
+    }
+
+    /// Provides a reference to the wrapped `Trigger` or `IrqLine`.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Provides a reference to the metrics backend.
+    pub fn metrics(&self) -> &M {
+        &self.metrics
+    }
+
+    fn record<E>(&self, result: Result<(), E>) -> Result<(), E> {
+        match result {
+            Ok(()) => self.metrics.interrupt(),
+            Err(_) => self.metrics.error(),
+        }
+        result
+    }
+}
+
+impl<T: Trigger, M: DeviceMetrics> Trigger for MeteredTrigger<T, M> {
+    type E = T::E;
+
+    fn trigger(&self) -> Result<(), Self::E> {
+        self.record(self.inner.trigger())
+    }
+}
+
+impl<L: IrqLine, M: DeviceMetrics> IrqLine for MeteredTrigger<L, M> {
+    type E = L::E;
+
+    fn assert(&self) -> Result<(), Self::E> {
+        self.record(self.inner.assert())
+    }
+
+    fn deassert(&self) -> Result<(), Self::E> {
+        self.inner.deassert().inspect_err(|_| self.metrics.error())
+    }
+
+    fn resample(&self) -> Result<(), Self::E> {
+        self.inner.resample().inspect_err(|_| self.metrics.error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eventfd::EventFdIrqLine;
+    use crate::Serial;
+    use std::io::sink;
+    use vmm_sys_util::eventfd::EventFd;
+
+    struct FailingTrigger;
+
+    impl Trigger for FailingTrigger {
+        type E = ();
+
+        fn trigger(&self) -> Result<(), ()> {
+            Err(())
+        }
+    }
+
+    #[test]
+    fn test_metered_device() {
+        let counters = Arc::new(DeviceCounters::new());
+        let intr_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let trigger = MeteredTrigger::new(intr_evt.try_clone().unwrap(), counters.clone());
+        let mut serial = MeteredDevice::new(Serial::new(trigger, sink()), 8, counters.clone());
+
+        // Enable the THR empty interrupt, and send a byte.
+        LegacyDevice::write(&mut serial, 1, &[0x02]);
+        LegacyDevice::write(&mut serial, 0, b"a");
+        let mut data = [0u8; 1];
+        LegacyDevice::read(&mut serial, 5, &mut data);
+        assert_eq!(counters.writes(), 2);
+        assert_eq!(counters.reads(), 1);
+        assert_eq!(counters.interrupts(), 1);
+        assert_eq!(intr_evt.read().unwrap(), 1);
+        assert_eq!(counters.invalid_accesses(), 0);
+
+        // The accesses outside of the device, or of invalid widths.
+        LegacyDevice::read(&mut serial, 8, &mut data);
+        LegacyDevice::write(&mut serial, 7, &[0; 2]);
+        LegacyDevice::write(&mut serial, 0, &[]);
+        LegacyDevice::read(&mut serial, 0, &mut [0; 16]);
+        assert_eq!(counters.invalid_accesses(), 4);
+        assert_eq!(counters.reads(), 3);
+        assert_eq!(counters.writes(), 4);
+        assert_eq!(serial.metrics().errors(), 0);
+
+        // The reset and the state are those of the device.
+        serial.device_mut().write(7, 0x42).unwrap();
+        assert_eq!(LegacyDevice::state(&serial), serial.device().state());
+        LegacyDevice::reset(&mut serial);
+        assert_eq!(serial.into_inner().state().scratch, 0);
+    }
+
+    #[test]
+    fn test_metered_trigger() {
+        let counters = Arc::new(DeviceCounters::new());
+        let trigger = MeteredTrigger::new(FailingTrigger, counters.clone());
+        assert!(trigger.trigger().is_err());
+        assert_eq!(counters.errors(), 1);
+        assert_eq!(counters.interrupts(), 0);
+
+        // The errors are recorded, even though `LegacyDevice` drops them.
+        let mut serial = Serial::new(trigger, sink());
+        LegacyDevice::write(&mut serial, 1, &[0x02]);
+        LegacyDevice::write(&mut serial, 0, b"a");
+        assert_eq!(counters.errors(), 2);
+        assert_eq!(counters.interrupts(), 0);
+    }
+
+    #[test]
+    fn test_metered_irq_line() {
+        let counters = Arc::new(DeviceCounters::new());
+        let line = MeteredTrigger::new(
+            EventFdIrqLine::new(EventFd::new(libc::EFD_NONBLOCK).unwrap()),
+            counters.clone(),
+        );
+        line.assert().unwrap();
+        line.resample().unwrap();
+        line.deassert().unwrap();
+        assert_eq!(counters.interrupts(), 1);
+        assert_eq!(counters.errors(), 0);
+        assert_eq!(line.inner().irqfd().read().unwrap(), 2);
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the validation of the MMIO accesses shared by the MMIO devices.
+//!
+//! Each device describes its registers with a table of
+//! [`MmioRegister`](struct.MmioRegister.html), against which the width and
+//! the alignment of the guest accesses are checked by
+//! [`find_register`](fn.find_register.html). The accesses that don't match
+//! any register are handled as configured by a
+//! [`BadAccessPolicy`](enum.BadAccessPolicy.html), so the devices handle
+//! them the same way.
+
+use alloc::sync::Arc;
+
+/// A register of an MMIO device, and the accesses it accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MmioRegister {
+    /// The offset of the register, from the base address of the device.
+    pub offset: u16,
+    /// The width of the register, in bytes.
+    pub size: u8,
+    /// The narrowest access accepted by the register, in bytes.
+    pub min_access: u8,
+    /// The widest access accepted by the register, in bytes. The bytes of
+    /// a wider access than the register are outside of it.
+    pub max_access: u8,
+    /// The alignment of the accesses, in bytes.
+    pub align: u8,
+}
+
+impl MmioRegister {
+    /// Creates a new `MmioRegister`, which only accepts the aligned accesses
+    /// of its whole width.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset of the register, from the base address of the
+    ///   device.
+    /// * `size` - The width of the register, in bytes.
+    pub const fn new(offset: u16, size: u8) -> Self {
+        MmioRegister {
+            offset,
+            size,
+            min_access: size,
+            max_access: size,
+            align: size,
+        }
+    }
+
+    // Returns whether the access of `len` bytes at `offset` starts in the
+    // register, and is accepted by it.
+    fn accepts(&self, offset: u16, len: usize) -> bool {
+        let start = u32::from(self.offset);
+        let offset = u32::from(offset);
+        len.is_power_of_two()
+            && (usize::from(self.min_access)..=usize::from(self.max_access)).contains(&len)
+            && (start..start + u32::from(self.size)).contains(&offset)
+            && offset % u32::from(self.align.max(1)) == 0
+    }
+}
+
+/// A valid access, as returned by [`find_register`](fn.find_register.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MmioAccess {
+    /// The index of the accessed register, in the table of the device.
+    pub index: usize,
+    /// The offset of the access, from the start of the register.
+    pub shift: usize,
+}
+
+/// Looks up the register accessed by `len` bytes at `offset`.
+///
+/// Returns `None` when no register of `registers` accepts the access, either
+/// because of its offset, its width or its alignment.
+///
+/// # Arguments
+/// * `registers` - The registers of the device.
+/// * `offset` - The offset of the access, from the base address of the
+///   device.
+/// * `len` - The width of the access, in bytes.
+pub fn find_register(registers: &[MmioRegister], offset: u16, len: usize) -> Option<MmioAccess> {
+    let access = registers
+        .iter()
+        .position(|reg| reg.accepts(offset, len))
+        .map(|index| MmioAccess {
+            index,
+            shift: usize::from(offset - registers[index].offset),
+        });
+    if access.is_none() {
+        guest_warn!(
+            "No register accepts the {}-byte access at offset {:#x}",
+            len,
+            offset
+        );
+    }
+    access
+}
+
+/// The handling of the accesses that don't match any register of a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BadAccessPolicy {
+    /// Leave the data of the reads unchanged, and drop the writes.
+    Ignore,
+    /// Read as 0, and drop the writes.
+    Zero,
+    /// Like `Ignore`, and report the access to the events object of the
+    /// device.
+    Event,
+}
+
+impl BadAccessPolicy {
+    /// Applies the policy to a bad read of `data`, and returns whether the
+    /// access is reported to the events object of the device.
+    ///
+    /// # Arguments
+    /// * `data` - The byte array storing the read value.
+    pub fn bad_read(self, data: &mut [u8]) -> bool {
+        match self {
+            BadAccessPolicy::Ignore => false,
+            BadAccessPolicy::Zero => {
+                data.fill(0);
+                false
+            }
+            BadAccessPolicy::Event => true,
+        }
+    }
+
+    /// Applies the policy to a bad write, and returns whether the access is
+    /// reported to the events object of the device.
+    pub fn bad_write(self) -> bool {
+        self == BadAccessPolicy::Event
+    }
+}
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the MMIO access validation. The methods below can be implemented by a
+/// backend that keeps track of such events by incrementing metrics, logging messages, or any
+/// other action.
+pub trait MmioEvents {
+    /// The driver attempted a read of `len` bytes at `offset`, which didn't
+    /// match any register.
+    fn bad_read(&self, offset: u16, len: usize);
+
+    /// The driver attempted a write of `len` bytes at `offset`, which didn't
+    /// match any register.
+    fn bad_write(&self, offset: u16, len: usize);
+}
+
+/// Provides a no-op implementation of `MmioEvents` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `MmioEvents`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl MmioEvents for NoEvents {
+    fn bad_read(&self, _offset: u16, _len: usize) {}
+    fn bad_write(&self, _offset: u16, _len: usize) {}
+}
+
+impl<EV: MmioEvents> MmioEvents for Arc<EV> {
+    fn bad_read(&self, offset: u16, len: usize) {
+        self.as_ref().bad_read(offset, len);
+    }
+
+    fn bad_write(&self, offset: u16, len: usize) {
+        self.as_ref().bad_write(offset, len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGISTERS: [MmioRegister; 2] = [
+        MmioRegister::new(0x0, 4),
+        MmioRegister {
+            offset: 0x8,
+            size: 4,
+            min_access: 1,
+            max_access: 8,
+            align: 1,
+        },
+    ];
+
+    #[test]
+    fn test_find_register() {
+        assert_eq!(
+            find_register(&REGISTERS, 0x0, 4),
+            Some(MmioAccess { index: 0, shift: 0 })
+        );
+        // The first register only accepts aligned accesses of its width.
+        assert_eq!(find_register(&REGISTERS, 0x0, 2), None);
+        assert_eq!(find_register(&REGISTERS, 0x0, 8), None);
+        assert_eq!(find_register(&REGISTERS, 0x2, 4), None);
+        assert_eq!(find_register(&REGISTERS, 0x4, 4), None);
+
+        // The second one accepts the accesses of 1 to 8 bytes starting in it.
+        assert_eq!(
+            find_register(&REGISTERS, 0x8, 8),
+            Some(MmioAccess { index: 1, shift: 0 })
+        );
+        assert_eq!(
+            find_register(&REGISTERS, 0xB, 1),
+            Some(MmioAccess { index: 1, shift: 3 })
+        );
+        assert_eq!(find_register(&REGISTERS, 0xC, 1), None);
+        assert_eq!(find_register(&REGISTERS, 0x8, 3), None);
+        assert_eq!(find_register(&REGISTERS, 0x8, 0), None);
+        assert_eq!(find_register(&REGISTERS, u16::MAX, 1), None);
+    }
+
+    #[test]
+    fn test_bad_access_policy() {
+        let mut data = [0xFF; 4];
+        assert!(!BadAccessPolicy::Ignore.bad_read(&mut data));
+        assert_eq!(data, [0xFF; 4]);
+        assert!(BadAccessPolicy::Event.bad_read(&mut data));
+        assert_eq!(data, [0xFF; 4]);
+        assert!(!BadAccessPolicy::Zero.bad_read(&mut data));
+        assert_eq!(data, [0; 4]);
+
+        assert!(!BadAccessPolicy::Ignore.bad_write());
+        assert!(!BadAccessPolicy::Zero.bad_write());
+        assert!(BadAccessPolicy::Event.bad_write());
+    }
+}
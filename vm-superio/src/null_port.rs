@@ -0,0 +1,249 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides a catch-all device for the unclaimed port I/O ranges.
+//!
+//! On real hardware, the reads from a port without any device behind it
+//! return the floating bus value, and the writes are lost. Guests probe many
+//! such ports while looking for legacy devices, so registering this device for
+//! the ranges that the VMM doesn't otherwise emulate keeps these accesses out
+//! of the vCPU loop, while still making them visible when needed.
+
+use alloc::sync::Arc;
+
+use crate::LegacyDevice;
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the null port device emulation logic. The methods below can be
+/// implemented by a backend that keeps track of such events by incrementing metrics, logging
+/// messages, or any other action.
+pub trait NullPortEvents {
+    /// The driver read `len` bytes from the port at `offset`.
+    fn unclaimed_read(&self, offset: u16, len: usize);
+    /// The driver wrote `data` to the port at `offset`, which is dropped.
+    fn unclaimed_write(&self, offset: u16, data: &[u8]);
+}
+
+/// Provides a no-op implementation of `NullPortEvents` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `NullPortEvents`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl NullPortEvents for NoEvents {
+    fn unclaimed_read(&self, _offset: u16, _len: usize) {}
+    fn unclaimed_write(&self, _offset: u16, _data: &[u8]) {}
+}
+
+impl<EV: NullPortEvents> NullPortEvents for Arc<EV> {
+    fn unclaimed_read(&self, offset: u16, len: usize) {
+        self.as_ref().unclaimed_read(offset, len);
+    }
+
+    fn unclaimed_write(&self, offset: u16, data: &[u8]) {
+        self.as_ref().unclaimed_write(offset, data);
+    }
+}
+
+/// The value returned by the reads from the unclaimed ports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NullReadValue {
+    /// Each byte reads as 0x00.
+    Zeros,
+    /// Each byte reads as 0xFF, like a floating ISA bus. This is what the
+    /// guests usually expect from the ports without any device behind them.
+    #[default]
+    Ones,
+}
+
+impl NullReadValue {
+    fn byte(self) -> u8 {
+        match self {
+            NullReadValue::Zeros => 0x00,
+            NullReadValue::Ones => 0xFF,
+        }
+    }
+}
+
+/// A device backing a range of unclaimed ports, which reads as a constant
+/// value and drops the writes.
+///
+/// Unlike the other devices, the offsets are 16 bits wide, so that a single
+/// instance can back any port range. The accesses are reported through
+/// [`NullPortEvents`](trait.NullPortEvents.html), which lets the VMM count or
+/// log them.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::null_port::{NullPortDevice, NullReadValue};
+/// let null = NullPortDevice::new(NullReadValue::Ones);
+///
+/// let mut data = [0u8; 2];
+/// null.read(0x10, &mut data);
+/// assert_eq!(data, [0xFF, 0xFF]);
+///
+/// // The writes are dropped.
+/// null.write(0x10, &[0x12]);
+/// null.read(0x10, &mut data[..1]);
+/// assert_eq!(data[0], 0xFF);
+/// ```
+#[derive(Debug)]
+pub struct NullPortDevice<EV: NullPortEvents> {
+    events: EV,
+    read_value: NullReadValue,
+}
+
+impl NullPortDevice<NoEvents> {
+    /// Creates a new `NullPortDevice` instance, whose ports read as
+    /// `read_value`.
+    ///
+    /// # Arguments
+    /// * `read_value` - The value returned by each byte that is read.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `NullPortDevice`](struct.NullPortDevice.html#example).
+    pub const fn new(read_value: NullReadValue) -> Self {
+        Self::with_events(read_value, NoEvents)
+    }
+}
+
+impl Default for NullPortDevice<NoEvents> {
+    fn default() -> Self {
+        Self::new(NullReadValue::default())
+    }
+}
+
+impl<EV: NullPortEvents> NullPortDevice<EV> {
+    /// Creates a new `NullPortDevice` instance, whose ports read as
+    /// `read_value`, and which invokes the `null_evts` implementation of
+    /// `NullPortEvents` during operation.
+    ///
+    /// # Arguments
+    /// * `read_value` - The value returned by each byte that is read.
+    /// * `null_evts` - The `NullPortEvents` implementation used to track the
+    ///   accesses to the unclaimed ports.
+    pub const fn with_events(read_value: NullReadValue, null_evts: EV) -> Self {
+        NullPortDevice {
+            events: null_evts,
+            read_value,
+        }
+    }
+
+    /// Provides a reference to the null port events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Returns the value that the ports read as.
+    pub fn read_value(&self) -> NullReadValue {
+        self.read_value
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Fills `data` with the configured read value.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    /// * `data` - The buffer receiving the bytes that are read.
+    pub fn read(&self, offset: u16, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            *byte = self.read_value.byte();
+        }
+        self.events.unclaimed_read(offset, data.len());
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// The bytes are dropped, after being reported through the events object.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `data` - The bytes that should be written.
+    pub fn write(&self, offset: u16, data: &[u8]) {
+        self.events.unclaimed_write(offset, data);
+    }
+}
+
+impl<EV: NullPortEvents> LegacyDevice for NullPortDevice<EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        NullPortDevice::read(self, offset, data);
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        NullPortDevice::write(self, offset, data);
+    }
+
+    fn reset(&mut self) {}
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use vmm_sys_util::metric::Metric;
+
+    #[derive(Default)]
+    struct ExampleNullPortEvents {
+        reads: AtomicU64,
+        writes: AtomicU64,
+        last_offset: AtomicU64,
+    }
+
+    impl NullPortEvents for ExampleNullPortEvents {
+        fn unclaimed_read(&self, offset: u16, _len: usize) {
+            self.reads.inc();
+            self.last_offset.store(u64::from(offset), Ordering::Relaxed);
+        }
+
+        fn unclaimed_write(&self, offset: u16, _data: &[u8]) {
+            self.writes.inc();
+            self.last_offset.store(u64::from(offset), Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_null_port_read_value() {
+        let null = NullPortDevice::default();
+        assert_eq!(null.read_value(), NullReadValue::Ones);
+        let mut data = [0u8; 4];
+        null.read(0, &mut data);
+        assert_eq!(data, [0xFF; 4]);
+
+        let null = NullPortDevice::new(NullReadValue::Zeros);
+        null.read(0x3FF, &mut data);
+        assert_eq!(data, [0x00; 4]);
+        null.write(0x3FF, &[0xAB, 0xCD]);
+        null.read(0x3FF, &mut data[..2]);
+        assert_eq!(data, [0x00; 4]);
+    }
+
+    #[test]
+    fn test_null_port_events() {
+        let events = Arc::new(ExampleNullPortEvents::default());
+        let null = NullPortDevice::with_events(NullReadValue::Ones, events.clone());
+
+        let mut data = [0u8; 2];
+        null.read(0x1234, &mut data);
+        assert_eq!(null.events().reads.count(), 1);
+        assert_eq!(events.last_offset.load(Ordering::Relaxed), 0x1234);
+
+        null.write(0x10, &[0x00]);
+        null.write(0x11, &[0x00, 0x00]);
+        assert_eq!(events.reads.count(), 1);
+        assert_eq!(events.writes.count(), 2);
+        assert_eq!(events.last_offset.load(Ordering::Relaxed), 0x11);
+    }
+}
@@ -0,0 +1,382 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the parallel port (LPT1, ports 0x378-0x37A).
+//!
+//! The guest prints a byte by writing it to the data register, and pulsing
+//! the strobe line of the control register. It then waits, by polling the
+//! status register or through the interrupt, for the printer to acknowledge
+//! the byte.
+
+use core::fmt;
+use core::result::Result;
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
+
+use crate::{byte_offset, ByteSink, LegacyDevice, SinkError, Trigger};
+
+// Register offsets.
+const DATA_OFFSET: u8 = 0;
+const STATUS_OFFSET: u8 = 1;
+const CONTROL_OFFSET: u8 = 2;
+
+// Status register bits.
+// The printer is selected, i.e. online.
+const STATUS_SELECT_BIT: u8 = 0b0001_0000;
+// The printer doesn't report an error (active low).
+const STATUS_NERROR_BIT: u8 = 0b0000_1000;
+// The printer isn't acknowledging a byte (active low).
+const STATUS_NACK_BIT: u8 = 0b0100_0000;
+// The printer isn't busy (inverted busy line).
+const STATUS_NBUSY_BIT: u8 = 0b1000_0000;
+// The status of an idle printer.
+const STATUS_READY: u8 = STATUS_NBUSY_BIT | STATUS_NACK_BIT | STATUS_SELECT_BIT | STATUS_NERROR_BIT;
+
+// Control register bits.
+// Latches the data into the printer.
+const CONTROL_STROBE_BIT: u8 = 0b0000_0001;
+// Resets the printer when cleared (active low).
+const CONTROL_NINIT_BIT: u8 = 0b0000_0100;
+// Selects the printer.
+const CONTROL_SELECT_IN_BIT: u8 = 0b0000_1000;
+// Raises the interrupt when the printer acknowledges a byte.
+const CONTROL_IRQ_BIT: u8 = 0b0001_0000;
+// Turns the data lines into inputs, on bidirectional ports.
+const CONTROL_INPUT_BIT: u8 = 0b0010_0000;
+// The bits that read back as written.
+const CONTROL_MASK: u8 = 0b0011_1111;
+// The unused bits, which read as 1.
+const CONTROL_UNUSED_BITS: u8 = 0b1100_0000;
+
+// The value of the data register in input mode, when nothing drives the
+// data lines.
+const DATA_FLOATING: u8 = 0xFF;
+
+/// Errors encountered while handling parallel port operations.
+#[derive(Debug)]
+pub enum Error<E> {
+    /// Failed to trigger interrupt.
+    Trigger(E),
+    /// Couldn't write/flush to the given destination.
+    IOError(SinkError),
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Trigger(e) => write!(f, "Failed to trigger interrupt: {}", e),
+            Error::IOError(e) => write!(f, "Couldn't write/flush to the given destination: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: StdError> StdError for Error<E> {}
+
+/// The parallel port, usually found at ports 0x378-0x37A (LPT1).
+///
+/// The bytes printed by the guest go to the `W: ByteSink` object provided at
+/// creation, e.g. a log file. The printer is always ready: it turns busy when
+/// the guest strobes a byte, and the following reads of the status register
+/// show the acknowledge pulse, after which the printer is ready again. A
+/// [`Trigger`](../trait.Trigger.html) object is used for raising the
+/// interrupt at the end of the pulse, when the guest enabled it.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::parallel::Parallel;
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut lpt = Parallel::new(DummyTrigger, Vec::new());
+///
+/// // The guest writes the byte, and then pulses the strobe line.
+/// lpt.write(0, b'a').unwrap();
+/// lpt.write(2, 0x0D).unwrap();
+/// lpt.write(2, 0x0C).unwrap();
+/// // It waits for the printer to be ready again.
+/// while lpt.read(1).unwrap() & 0x80 == 0 {}
+/// assert_eq!(lpt.writer(), b"a");
+/// ```
+#[derive(Debug)]
+pub struct Parallel<T: Trigger, W: ByteSink> {
+    // Interrupt of the port, i.e. IRQ 7 for LPT1.
+    interrupt_evt: T,
+    out: W,
+    data: u8,
+    status: u8,
+    control: u8,
+}
+
+impl<T: Trigger, W: ByteSink> Parallel<T, W> {
+    /// Creates a new `Parallel` instance, which writes the guest output to
+    /// `out`, and uses `interrupt_evt` for raising the interrupt of the port.
+    ///
+    /// # Arguments
+    /// * `interrupt_evt` - A Trigger object that will be used to raise the
+    ///   interrupt of the port.
+    /// * `out` - An object for writing the guest output to. The output is
+    ///   flushed after each byte.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Parallel`](struct.Parallel.html#example).
+    pub const fn new(interrupt_evt: T, out: W) -> Self {
+        Parallel {
+            interrupt_evt,
+            out,
+            data: 0,
+            status: STATUS_READY,
+            control: CONTROL_NINIT_BIT | CONTROL_SELECT_IN_BIT,
+        }
+    }
+
+    /// Provides a reference to the interrupt event object.
+    pub fn interrupt_evt(&self) -> &T {
+        &self.interrupt_evt
+    }
+
+    /// Gets a reference to the output `ByteSink` object.
+    pub fn writer(&self) -> &W {
+        &self.out
+    }
+
+    /// Gets a mutable reference to the output `ByteSink` object.
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    /// Consumes the device and retrieves the inner writer.
+    pub fn into_writer(self) -> W {
+        self.out
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Reading the status register of a busy printer moves its handshake
+    /// forward: the first read after the strobe shows the printer busy, and
+    /// the next one shows the acknowledge line asserted, after which the
+    /// printer is ready again and the interrupt is raised, if enabled.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&mut self, offset: u8) -> Result<u8, Error<T::E>> {
+        let value = match offset {
+            DATA_OFFSET if (self.control & CONTROL_INPUT_BIT) != 0 => DATA_FLOATING,
+            DATA_OFFSET => self.data,
+            STATUS_OFFSET => {
+                let status = self.status;
+                self.advance_handshake()?;
+                status
+            }
+            CONTROL_OFFSET => self.control | CONTROL_UNUSED_BITS,
+            _ => {
+                guest_warn!("Invalid parallel port read at offset {:#x}", offset);
+                0x00
+            }
+        };
+        Ok(value)
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Setting the strobe line of the control register prints the byte held
+    /// by the data register, which is written to the output object.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) -> Result<(), Error<T::E>> {
+        match offset {
+            DATA_OFFSET => self.data = value,
+            CONTROL_OFFSET => {
+                let strobe = (value & !self.control & CONTROL_STROBE_BIT) != 0;
+                self.control = value & CONTROL_MASK;
+                if (value & CONTROL_NINIT_BIT) == 0 {
+                    // The printer is reset.
+                    self.status = STATUS_READY;
+                } else if strobe && (value & CONTROL_SELECT_IN_BIT) != 0 {
+                    self.status &= !STATUS_NBUSY_BIT;
+                    self.out.write_all(&[self.data]).map_err(Error::IOError)?;
+                    self.out.flush().map_err(Error::IOError)?;
+                }
+            }
+            _ => guest_warn!("Invalid parallel port write at offset {:#x}", offset),
+        }
+        Ok(())
+    }
+
+    // Moves the handshake of a busy printer one step forward, once the guest
+    // released the strobe line.
+    fn advance_handshake(&mut self) -> Result<(), Error<T::E>> {
+        if (self.status & STATUS_NBUSY_BIT) != 0 || (self.control & CONTROL_STROBE_BIT) != 0 {
+            return Ok(());
+        }
+        if (self.status & STATUS_NACK_BIT) != 0 {
+            self.status &= !STATUS_NACK_BIT;
+            return Ok(());
+        }
+        self.status |= STATUS_NACK_BIT | STATUS_NBUSY_BIT;
+        if (self.control & CONTROL_IRQ_BIT) != 0 {
+            self.interrupt_evt.trigger().map_err(Error::Trigger)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Trigger, W: ByteSink> LegacyDevice for Parallel<T, W> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = Parallel::read(self, offset).unwrap_or(0),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            let _ = Parallel::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.data = 0;
+        self.status = STATUS_READY;
+        self.control = CONTROL_NINIT_BIT | CONTROL_SELECT_IN_BIT;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vmm_sys_util::eventfd::EventFd;
+
+    const CONTROL_IDLE: u8 = CONTROL_NINIT_BIT | CONTROL_SELECT_IN_BIT;
+
+    fn print(lpt: &mut Parallel<EventFd, Vec<u8>>, byte: u8, control: u8) {
+        lpt.write(DATA_OFFSET, byte).unwrap();
+        lpt.write(CONTROL_OFFSET, control | CONTROL_STROBE_BIT)
+            .unwrap();
+        lpt.write(CONTROL_OFFSET, control).unwrap();
+    }
+
+    #[test]
+    fn test_parallel_print() {
+        let mut lpt = Parallel::new(EventFd::new(libc::EFD_NONBLOCK).unwrap(), Vec::new());
+        assert_eq!(lpt.read(STATUS_OFFSET).unwrap(), STATUS_READY);
+        assert_eq!(
+            lpt.read(CONTROL_OFFSET).unwrap(),
+            CONTROL_UNUSED_BITS | CONTROL_IDLE
+        );
+
+        // The data register holds the last byte written.
+        lpt.write(DATA_OFFSET, b'x').unwrap();
+        assert_eq!(lpt.read(DATA_OFFSET).unwrap(), b'x');
+        assert!(lpt.writer().is_empty());
+
+        print(&mut lpt, b'a', CONTROL_IDLE);
+        assert_eq!(lpt.writer(), b"a");
+
+        // The printer is busy, then acknowledges the byte, and is ready again.
+        let busy = STATUS_READY & !STATUS_NBUSY_BIT;
+        assert_eq!(lpt.read(STATUS_OFFSET).unwrap(), busy);
+        assert_eq!(lpt.read(STATUS_OFFSET).unwrap(), busy & !STATUS_NACK_BIT);
+        assert_eq!(lpt.read(STATUS_OFFSET).unwrap(), STATUS_READY);
+        assert_eq!(lpt.read(STATUS_OFFSET).unwrap(), STATUS_READY);
+        // The interrupt is disabled.
+        assert!(lpt.interrupt_evt().read().is_err());
+
+        // The handshake waits for the strobe line to be released.
+        lpt.write(DATA_OFFSET, b'b').unwrap();
+        lpt.write(CONTROL_OFFSET, CONTROL_IDLE | CONTROL_STROBE_BIT)
+            .unwrap();
+        // Holding the strobe line doesn't print the byte twice.
+        lpt.write(CONTROL_OFFSET, CONTROL_IDLE | CONTROL_STROBE_BIT)
+            .unwrap();
+        assert_eq!(lpt.read(STATUS_OFFSET).unwrap(), busy);
+        assert_eq!(lpt.read(STATUS_OFFSET).unwrap(), busy);
+        lpt.write(CONTROL_OFFSET, CONTROL_IDLE).unwrap();
+        assert_eq!(lpt.read(STATUS_OFFSET).unwrap(), busy);
+        assert_eq!(lpt.read(STATUS_OFFSET).unwrap(), busy & !STATUS_NACK_BIT);
+        assert_eq!(lpt.writer(), b"ab");
+
+        // The printer ignores the strobe unless it is selected, and when
+        // it is being reset.
+        print(&mut lpt, b'c', CONTROL_NINIT_BIT);
+        print(&mut lpt, b'd', CONTROL_SELECT_IN_BIT);
+        assert_eq!(lpt.read(STATUS_OFFSET).unwrap(), STATUS_READY);
+        lpt.writer_mut().clear();
+        assert!(lpt.into_writer().is_empty());
+    }
+
+    #[test]
+    fn test_parallel_interrupt() {
+        let mut lpt = Parallel::new(EventFd::new(libc::EFD_NONBLOCK).unwrap(), Vec::new());
+
+        print(&mut lpt, b'a', CONTROL_IDLE | CONTROL_IRQ_BIT);
+        lpt.read(STATUS_OFFSET).unwrap();
+        assert!(lpt.interrupt_evt().read().is_err());
+        // The interrupt is raised at the end of the acknowledge pulse.
+        lpt.read(STATUS_OFFSET).unwrap();
+        assert_eq!(lpt.interrupt_evt().read().unwrap(), 1);
+        lpt.read(STATUS_OFFSET).unwrap();
+        assert!(lpt.interrupt_evt().read().is_err());
+
+        // Resetting the printer ends the handshake.
+        print(&mut lpt, b'b', CONTROL_IDLE | CONTROL_IRQ_BIT);
+        lpt.write(CONTROL_OFFSET, CONTROL_SELECT_IN_BIT).unwrap();
+        assert_eq!(lpt.read(STATUS_OFFSET).unwrap(), STATUS_READY);
+        assert!(lpt.interrupt_evt().read().is_err());
+        assert_eq!(lpt.writer(), b"ab");
+    }
+
+    #[test]
+    fn test_parallel_input_mode() {
+        let mut lpt = Parallel::new(EventFd::new(libc::EFD_NONBLOCK).unwrap(), Vec::new());
+        lpt.write(DATA_OFFSET, 0x12).unwrap();
+        lpt.write(CONTROL_OFFSET, CONTROL_IDLE | CONTROL_INPUT_BIT)
+            .unwrap();
+        assert_eq!(lpt.read(DATA_OFFSET).unwrap(), DATA_FLOATING);
+        lpt.write(CONTROL_OFFSET, CONTROL_IDLE).unwrap();
+        assert_eq!(lpt.read(DATA_OFFSET).unwrap(), 0x12);
+
+        // Other offsets are ignored.
+        lpt.write(CONTROL_OFFSET + 1, 0xFF).unwrap();
+        assert_eq!(lpt.read(CONTROL_OFFSET + 1).unwrap(), 0x00);
+    }
+
+    #[test]
+    fn test_parallel_legacy_device() {
+        let mut lpt = Parallel::new(EventFd::new(libc::EFD_NONBLOCK).unwrap(), Vec::new());
+
+        LegacyDevice::write(&mut lpt, u16::from(DATA_OFFSET), b"a");
+        LegacyDevice::write(
+            &mut lpt,
+            u16::from(CONTROL_OFFSET),
+            &[CONTROL_IDLE | CONTROL_STROBE_BIT],
+        );
+        assert_eq!(lpt.writer(), b"a");
+        let mut data = [0; 1];
+        LegacyDevice::read(&mut lpt, u16::from(STATUS_OFFSET), &mut data);
+        assert_eq!(data[0] & STATUS_NBUSY_BIT, 0);
+
+        // The reset ends the handshake, and keeps the output.
+        lpt.reset();
+        assert_eq!(lpt.read(STATUS_OFFSET).unwrap(), STATUS_READY);
+        assert_eq!(lpt.read(DATA_OFFSET).unwrap(), 0);
+        assert_eq!(lpt.writer(), b"a");
+    }
+}
@@ -0,0 +1,1624 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the i8254 Programmable Interval Timer (PIT).
+//!
+//! The PIT has three 16-bit channels, which count down at 1.193182 MHz. The
+//! counters are not updated periodically; instead, their values are derived
+//! from the time elapsed since they were loaded, as reported by a
+//! [`ClockSource`](trait.ClockSource.html).
+
+use alloc::sync::Arc;
+use core::convert::{Infallible, TryFrom};
+use core::result::Result;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use crate::{byte_offset, LegacyDevice, Persist, StateError, Trigger};
+
+// Offset of the channel 0 data register (port 0x40). The channel 1 and 2
+// data registers follow it.
+const CHANNEL0_OFFSET: u8 = 0;
+// Index of channel 0, whose output is connected to IRQ0.
+const IRQ_CHANNEL: usize = 0;
+// Index of channel 2, whose gate and output are connected to port 0x61.
+const SPEAKER_CHANNEL: usize = 2;
+// Offset of the mode/command register (port 0x43), which is write only.
+const COMMAND_OFFSET: u8 = 3;
+
+// Number of channels.
+const NUM_CHANNELS: usize = 3;
+
+// Frequency of the PIT input clock.
+const PIT_FREQ_HZ: u64 = 1_193_182;
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+// Command register fields.
+// Selects the channel (bits 6-7).
+const CMD_CHANNEL_SHIFT: u8 = 6;
+// Selects the access mode (bits 4-5).
+const CMD_ACCESS_SHIFT: u8 = 4;
+const CMD_ACCESS_MASK: u8 = 0b11;
+// Selects the operating mode (bits 1-3).
+const CMD_MODE_SHIFT: u8 = 1;
+const CMD_MODE_MASK: u8 = 0b111;
+// Selects the BCD counting (bit 0).
+const CMD_BCD_BIT: u8 = 0b1;
+// The channel value of the read-back command.
+const CMD_READ_BACK: u8 = 0b11;
+
+// Read-back command fields.
+// The counts of the selected channels are latched when the bit is 0.
+const READ_BACK_NO_COUNT_BIT: u8 = 0b0010_0000;
+// The statuses of the selected channels are latched when the bit is 0.
+const READ_BACK_NO_STATUS_BIT: u8 = 0b0001_0000;
+// Selects channel 0; the bits of channels 1 and 2 follow it.
+const READ_BACK_CHANNEL0_BIT: u8 = 0b0000_0010;
+
+// Status byte fields. The access and operating modes are at the same
+// positions as in the command register.
+// The state of the output.
+const STATUS_OUTPUT_BIT: u8 = 0b1000_0000;
+// The initial count was not loaded into the counter yet.
+const STATUS_NULL_COUNT_BIT: u8 = 0b0100_0000;
+
+// Access modes.
+// Counter latch command.
+const ACCESS_LATCH: u8 = 0b00;
+// Only the low byte is read and written.
+const ACCESS_LOW: u8 = 0b01;
+// Only the high byte is read and written.
+const ACCESS_HIGH: u8 = 0b10;
+// The low byte is read and written first, followed by the high byte.
+const ACCESS_WORD: u8 = 0b11;
+
+// Operating modes. Modes 6 and 7 are aliases of modes 2 and 3.
+// Interrupt on terminal count.
+const MODE_INTERRUPT: u8 = 0;
+// Hardware re-triggerable one-shot.
+const MODE_ONE_SHOT: u8 = 1;
+// Rate generator.
+const MODE_RATE: u8 = 2;
+// Square wave generator.
+const MODE_SQUARE_WAVE: u8 = 3;
+// Software triggered strobe.
+const MODE_SOFTWARE_STROBE: u8 = 4;
+// Hardware triggered strobe.
+const MODE_HARDWARE_STROBE: u8 = 5;
+
+// The longest time since the counters were loaded in a valid state, about 292
+// years, which leaves room for the time arithmetic of the device on top of it.
+const MAX_ELAPSED_NS: u64 = i64::MAX as u64;
+// The ticks lost to the rounding of the elapsed time, which the device counts
+// from the times of the clock source instead of their difference.
+const ROUNDING_TICKS: u64 = 1;
+// The strobe modes have their rising edge one tick after the terminal count.
+const STROBE_TICKS: u64 = 1;
+
+/// A source of monotonic time, from which the PIT derives the values of its
+/// counters.
+///
+/// Using a trait allows the VMM to control the flow of time as seen by the
+/// device, e.g. to pause it while the VM is paused, or to run it in tests.
+pub trait ClockSource {
+    /// Returns the number of nanoseconds elapsed since an arbitrary, but
+    /// fixed, point in time.
+    fn now_ns(&self) -> u64;
+}
+
+impl<C: ClockSource> ClockSource for Arc<C> {
+    fn now_ns(&self) -> u64 {
+        self.as_ref().now_ns()
+    }
+}
+
+/// A `ClockSource` based on the host monotonic clock, which counts the time
+/// elapsed since its creation. It needs the `std` feature, while the VMMs
+/// without it implement `ClockSource` on top of the timer of their platform.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+pub struct MonotonicClock {
+    start: Instant,
+}
+
+#[cfg(feature = "std")]
+impl MonotonicClock {
+    /// Creates a new `MonotonicClock`, which starts counting from 0.
+    pub fn new() -> Self {
+        MonotonicClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl ClockSource for MonotonicClock {
+    fn now_ns(&self) -> u64 {
+        self.start.elapsed().as_nanos() as u64
+    }
+}
+
+// Converts a duration in nanoseconds to PIT input clock ticks.
+fn ns_to_ticks(ns: u64) -> u64 {
+    (ns as u128 * PIT_FREQ_HZ as u128 / NANOS_PER_SEC as u128) as u64
+}
+
+// Converts a number of PIT input clock ticks to the first nanosecond of the
+// last tick, i.e. the inverse of `ns_to_ticks`. The durations beyond `u64`
+// saturate, since they are never reached.
+fn ticks_to_ns(ticks: u64) -> u64 {
+    let ns = (ticks as u128 * NANOS_PER_SEC as u128).div_ceil(PIT_FREQ_HZ as u128);
+    u64::try_from(ns).unwrap_or(u64::MAX)
+}
+
+/// The state of a PIT channel.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PitChannelState {
+    /// Operating mode.
+    pub mode: u8,
+    /// Access mode.
+    pub access: u8,
+    /// Initial count. A value of 0 stands for 0x10000.
+    pub reload: u16,
+    /// Time elapsed since the counter was loaded with the initial count, in
+    /// nanoseconds. The counter value and the output are derived from it.
+    pub elapsed_ns: u64,
+    /// Whether a complete initial count was written after the operating
+    /// mode was set.
+    pub armed: bool,
+    /// Whether the counter counts.
+    pub counting: bool,
+    /// Level of the gate input.
+    pub gate: bool,
+    /// Number of ticks counted before the gate went low, which pauses the
+    /// counter in modes 0 and 4.
+    pub paused_ticks: Option<u64>,
+    /// Low byte of the initial count, waiting for the high byte.
+    pub write_low: Option<u8>,
+    /// Whether the next read returns the high byte of the counter.
+    pub read_high: bool,
+    /// Counter value latched by the latch or read-back commands.
+    pub latched_count: Option<u16>,
+    /// Status byte latched by the read-back command.
+    pub latched_status: Option<u8>,
+    /// Number of ticks since the counter was loaded after which the output
+    /// has its next rising edge, if any.
+    pub next_edge: Option<u64>,
+}
+
+impl PitChannelState {
+    /// Checks that the channel can be in this state, e.g. before restoring a
+    /// state deserialized from a snapshot: the operating and access modes are
+    /// valid, the counter didn't count longer than the time elapsed since it
+    /// was loaded, and its next rising edge is at most one period ahead of
+    /// its current count.
+    pub fn validate(&self) -> Result<(), StateError> {
+        // The modes 6 and 7 are stored as the modes 2 and 3.
+        if self.mode > MODE_HARDWARE_STROBE {
+            return Err(StateError::InvalidRegister {
+                register: "mode",
+                value: u32::from(self.mode),
+            });
+        }
+        // The latch command doesn't change the access mode.
+        if !(ACCESS_LOW..=ACCESS_WORD).contains(&self.access) {
+            return Err(StateError::InvalidRegister {
+                register: "access",
+                value: u32::from(self.access),
+            });
+        }
+        check_range("elapsed_ns", self.elapsed_ns, MAX_ELAPSED_NS)?;
+        let elapsed_ticks = ns_to_ticks(self.elapsed_ns) + ROUNDING_TICKS;
+        if let Some(paused_ticks) = self.paused_ticks {
+            check_range("paused_ticks", paused_ticks, elapsed_ticks)?;
+        }
+        if let Some(next_edge) = self.next_edge {
+            let period = match self.reload {
+                0 => 0x10000,
+                reload => u64::from(reload),
+            };
+            let ticks = self.paused_ticks.unwrap_or(elapsed_ticks);
+            check_range("next_edge", next_edge, ticks + period + STROBE_TICKS)?;
+        }
+        Ok(())
+    }
+}
+
+// Returns an error if `value` is above `max`.
+fn check_range(field: &'static str, value: u64, max: u64) -> Result<(), StateError> {
+    if value > max {
+        return Err(StateError::OutOfRange { field, value, max });
+    }
+    Ok(())
+}
+
+impl Default for PitChannelState {
+    fn default() -> Self {
+        PitChannelState {
+            mode: MODE_INTERRUPT,
+            access: ACCESS_WORD,
+            reload: 0,
+            elapsed_ns: 0,
+            armed: false,
+            counting: false,
+            gate: true,
+            paused_ticks: None,
+            write_low: None,
+            read_high: false,
+            latched_count: None,
+            latched_status: None,
+            next_edge: None,
+        }
+    }
+}
+
+/// The state of the PIT.
+///
+/// The time is saved relative to the clock source, so the counters keep
+/// their phase when the state is restored on top of a different clock.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PitState {
+    /// The state of each channel.
+    pub channels: [PitChannelState; NUM_CHANNELS],
+}
+
+impl PitState {
+    /// Checks that the PIT can be in this state, i.e. that each of its
+    /// channels can, as checked by
+    /// [`PitChannelState::validate`](struct.PitChannelState.html#method.validate).
+    pub fn validate(&self) -> Result<(), StateError> {
+        self.channels.iter().try_for_each(PitChannelState::validate)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Channel {
+    // The operating mode.
+    mode: u8,
+    // The access mode.
+    access: u8,
+    // The initial count. A value of 0 stands for 0x10000.
+    reload: u16,
+    // The time when the counter was loaded with the initial count.
+    load_time_ns: u64,
+    // Whether a complete initial count was written after the operating mode
+    // was set.
+    armed: bool,
+    // Whether the counter counts. The hardware triggered modes wait for a
+    // rising edge of the gate, and the rate and square wave generators stop
+    // while the gate is low.
+    counting: bool,
+    // The level of the gate input.
+    gate: bool,
+    // The number of ticks counted before the gate went low, which pauses the
+    // counter in modes 0 and 4.
+    paused_ticks: Option<u64>,
+    // The low byte of the initial count, waiting for the high byte.
+    write_low: Option<u8>,
+    // The next read returns the high byte of the counter.
+    read_high: bool,
+    // The counter value latched by the latch or read-back commands.
+    latched_count: Option<u16>,
+    // The status byte latched by the read-back command.
+    latched_status: Option<u8>,
+    // The number of ticks since the counter was loaded after which the
+    // output has its next rising edge, if any.
+    next_edge: Option<u64>,
+}
+
+impl Channel {
+    fn from_state(state: &PitChannelState, now_ns: u64) -> Self {
+        Channel {
+            mode: state.mode,
+            access: state.access,
+            reload: state.reload,
+            load_time_ns: now_ns.saturating_sub(state.elapsed_ns),
+            armed: state.armed,
+            counting: state.counting,
+            gate: state.gate,
+            paused_ticks: state.paused_ticks,
+            write_low: state.write_low,
+            read_high: state.read_high,
+            latched_count: state.latched_count,
+            latched_status: state.latched_status,
+            next_edge: state.next_edge,
+        }
+    }
+
+    fn state(&self, now_ns: u64) -> PitChannelState {
+        PitChannelState {
+            mode: self.mode,
+            access: self.access,
+            reload: self.reload,
+            elapsed_ns: now_ns.saturating_sub(self.load_time_ns),
+            armed: self.armed,
+            counting: self.counting,
+            gate: self.gate,
+            paused_ticks: self.paused_ticks,
+            write_low: self.write_low,
+            read_high: self.read_high,
+            latched_count: self.latched_count,
+            latched_status: self.latched_status,
+            next_edge: self.next_edge,
+        }
+    }
+
+    fn period(&self) -> u64 {
+        match self.reload {
+            0 => 0x10000,
+            reload => reload as u64,
+        }
+    }
+
+    // Returns the number of input clock edges counted since the counter was
+    // loaded.
+    fn elapsed_ticks(&self, now_ns: u64) -> u64 {
+        match self.paused_ticks {
+            Some(ticks) => ticks,
+            None => ns_to_ticks(now_ns).saturating_sub(ns_to_ticks(self.load_time_ns)),
+        }
+    }
+
+    // Returns the current value of the counter.
+    fn count(&self, now_ns: u64) -> u16 {
+        if !self.counting {
+            return self.reload;
+        }
+        let period = self.period();
+        let ticks = self.elapsed_ticks(now_ns);
+        let count = match self.mode {
+            MODE_RATE => period - ticks % period,
+            // The counter is decremented by two on each tick.
+            MODE_SQUARE_WAVE => period - (2 * (ticks % period)) % period,
+            // The counter wraps around once it reaches the terminal count.
+            _ => period.wrapping_sub(ticks),
+        };
+        count as u16
+    }
+
+    // Returns the state of the output.
+    fn output(&self, now_ns: u64) -> bool {
+        if !self.counting {
+            // Mode 0 sets the output low as soon as it is selected.
+            return self.mode != MODE_INTERRUPT;
+        }
+        let period = self.period();
+        let ticks = self.elapsed_ticks(now_ns);
+        match self.mode {
+            MODE_INTERRUPT | MODE_ONE_SHOT => ticks >= period,
+            // The output goes low for one tick before the counter reloads.
+            MODE_RATE => ticks % period != period - 1,
+            // The output is high during the first half of the period, which
+            // gets the extra tick of odd periods.
+            MODE_SQUARE_WAVE => ticks % period < period.div_ceil(2),
+            // The strobe modes set the output low for one tick at the
+            // terminal count.
+            _ => ticks != period,
+        }
+    }
+
+    fn status(&self, now_ns: u64) -> u8 {
+        let mut status = (self.access << CMD_ACCESS_SHIFT) | (self.mode << CMD_MODE_SHIFT);
+        if self.output(now_ns) {
+            status |= STATUS_OUTPUT_BIT;
+        }
+        if !self.counting {
+            status |= STATUS_NULL_COUNT_BIT;
+        }
+        status
+    }
+
+    // Latches the counter value, unless a previously latched one wasn't read
+    // yet.
+    fn latch_count(&mut self, now_ns: u64) {
+        if self.latched_count.is_none() {
+            self.latched_count = Some(self.count(now_ns));
+        }
+    }
+
+    // Latches the status byte, unless a previously latched one wasn't read
+    // yet.
+    fn latch_status(&mut self, now_ns: u64) {
+        if self.latched_status.is_none() {
+            self.latched_status = Some(self.status(now_ns));
+        }
+    }
+
+    fn set_command(&mut self, access: u8, mode: u8) {
+        self.access = access;
+        self.mode = mode;
+        // The counter stops until a new initial count is written.
+        self.armed = false;
+        self.counting = false;
+        self.paused_ticks = None;
+        self.write_low = None;
+        self.read_high = false;
+        self.latched_count = None;
+        self.latched_status = None;
+        self.next_edge = None;
+    }
+
+    // Starts counting down from the initial count.
+    fn start(&mut self, now_ns: u64) {
+        self.load_time_ns = now_ns;
+        self.counting = true;
+        self.paused_ticks = None;
+        self.next_edge = match self.mode {
+            // The output goes high when the counter reaches the terminal count.
+            MODE_INTERRUPT | MODE_ONE_SHOT | MODE_RATE | MODE_SQUARE_WAVE => Some(self.period()),
+            // The output goes low for one tick at the terminal count.
+            _ => Some(self.period() + 1),
+        };
+    }
+
+    fn load(&mut self, reload: u16, now_ns: u64) {
+        self.reload = reload;
+        self.armed = true;
+        if matches!(self.mode, MODE_ONE_SHOT | MODE_HARDWARE_STROBE) {
+            // The hardware triggered modes wait for the gate, instead of the
+            // write.
+            self.counting = false;
+            self.next_edge = None;
+            return;
+        }
+        self.start(now_ns);
+        if !self.gate {
+            self.gate_low(now_ns);
+        }
+    }
+
+    fn gate_low(&mut self, now_ns: u64) {
+        match self.mode {
+            MODE_INTERRUPT | MODE_SOFTWARE_STROBE if self.counting => {
+                self.paused_ticks = Some(self.elapsed_ticks(now_ns));
+            }
+            MODE_RATE | MODE_SQUARE_WAVE => {
+                self.counting = false;
+                self.next_edge = None;
+            }
+            _ => (),
+        }
+    }
+
+    fn gate_high(&mut self, now_ns: u64) {
+        match self.mode {
+            MODE_INTERRUPT | MODE_SOFTWARE_STROBE => {
+                // Resume counting from where the counter was paused.
+                if let Some(ticks) = self.paused_ticks.take() {
+                    self.load_time_ns = ticks_to_ns(ns_to_ticks(now_ns).saturating_sub(ticks));
+                }
+            }
+            // The rising edge reloads the counter in the other modes, and
+            // triggers the hardware triggered ones.
+            _ => self.start(now_ns),
+        }
+    }
+
+    fn set_gate(&mut self, level: bool, now_ns: u64) {
+        if level == self.gate {
+            return;
+        }
+        self.gate = level;
+        if !self.armed {
+            return;
+        }
+        if level {
+            self.gate_high(now_ns);
+        } else {
+            self.gate_low(now_ns);
+        }
+    }
+
+    // Returns the time of the next rising edge of the output.
+    fn next_edge_ns(&self) -> Option<u64> {
+        if self.paused_ticks.is_some() {
+            return None;
+        }
+        // The restored states may hold any edge, which saturates instead of
+        // overflowing.
+        self.next_edge
+            .map(|edge| ticks_to_ns(ns_to_ticks(self.load_time_ns).saturating_add(edge)))
+    }
+
+    // Returns whether the output had a rising edge since the last call,
+    // and moves on to the next edge. Missed edges are coalesced.
+    fn check_edge(&mut self, now_ns: u64) -> bool {
+        let edge = match self.next_edge {
+            Some(edge) if self.paused_ticks.is_none() => edge,
+            _ => return false,
+        };
+        let ticks = self.elapsed_ticks(now_ns);
+        if ticks < edge {
+            return false;
+        }
+        self.next_edge = match self.mode {
+            // The periodic modes have an edge each time the counter reloads.
+            MODE_RATE | MODE_SQUARE_WAVE => {
+                let period = self.period();
+                Some((ticks / period).saturating_add(1).saturating_mul(period))
+            }
+            _ => None,
+        };
+        true
+    }
+
+    fn read(&mut self, now_ns: u64) -> u8 {
+        // A latched status is read before the counter.
+        if let Some(status) = self.latched_status.take() {
+            return status;
+        }
+        let count = self.latched_count.unwrap_or_else(|| self.count(now_ns));
+        let (value, last) = match self.access {
+            ACCESS_LOW => (count as u8, true),
+            ACCESS_HIGH => ((count >> 8) as u8, true),
+            _ => {
+                self.read_high = !self.read_high;
+                if self.read_high {
+                    (count as u8, false)
+                } else {
+                    ((count >> 8) as u8, true)
+                }
+            }
+        };
+        // The latched value is kept until it is completely read.
+        if last {
+            self.latched_count = None;
+        }
+        value
+    }
+
+    fn write(&mut self, value: u8, now_ns: u64) {
+        match self.access {
+            ACCESS_LOW => self.load(value as u16, now_ns),
+            ACCESS_HIGH => self.load((value as u16) << 8, now_ns),
+            _ => match self.write_low.take() {
+                Some(low) => self.load(u16::from_le_bytes([low, value]), now_ns),
+                None => self.write_low = Some(value),
+            },
+        }
+    }
+}
+
+/// An i8254 Programmable Interval Timer, usually found at ports 0x40-0x43.
+///
+/// The three channels support all the access modes and the operating modes
+/// 0 to 5, as well as the counter latch and read-back commands, while the
+/// BCD counting is not supported. The values of the
+/// counters are derived from the time reported by a
+/// [`ClockSource`](trait.ClockSource.html).
+///
+/// The output of channel 0 is connected to IRQ0, which is raised through a
+/// [`Trigger`](../trait.Trigger.html) object. The device has no timer of its
+/// own: the VMM arms one (e.g. a timerfd) for the time returned by
+/// [`next_deadline`](#method.next_deadline), and calls
+/// [`on_timer_expired`](#method.on_timer_expired) once it fires.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::pit::{ClockSource, MonotonicClock, Pit};
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut pit = Pit::new(DummyTrigger, MonotonicClock::new());
+///
+/// // Program channel 0 as a rate generator with a 100 Hz frequency.
+/// pit.write(3, 0x34);
+/// pit.write(0, 0x9B);
+/// pit.write(0, 0x2E);
+///
+/// // Read the counter, which is counting down from 11931.
+/// let low = pit.read(0);
+/// let high = pit.read(0);
+/// assert!(u16::from_le_bytes([low, high]) <= 11931);
+///
+/// // The first interrupt is due in 10 ms.
+/// let deadline = pit.next_deadline().unwrap();
+/// assert!(deadline - pit.clock().now_ns() <= 10_000_000);
+///
+/// // Once the deadline passes, the interrupt is raised by calling
+/// // `on_timer_expired`, and the next deadline is 10 ms later.
+/// pit.on_timer_expired().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Pit<T: Trigger, C: ClockSource> {
+    // Interrupt line of channel 0 (IRQ0).
+    irq0_evt: T,
+    clock: C,
+    // Added to the time of the clock source, so that the counters restored
+    // from a state can be loaded before the origin of the clock.
+    time_offset_ns: u64,
+    channels: [Channel; NUM_CHANNELS],
+}
+
+impl<T: Trigger, C: ClockSource> Pit<T, C> {
+    /// Creates a new `Pit` instance, which derives the values of its counters
+    /// from `clock`, and raises IRQ0 through `irq0_evt`.
+    ///
+    /// # Arguments
+    /// * `irq0_evt` - The Trigger object used for raising the interrupt of
+    ///   channel 0 (IRQ0).
+    /// * `clock` - The source of the time used by the counters.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Pit`](struct.Pit.html#example).
+    pub fn new(irq0_evt: T, clock: C) -> Self {
+        trace_device!("pit", "create");
+        Self::with_state(&PitState::default(), irq0_evt, clock)
+    }
+
+    /// Creates a new `Pit` instance from a given `state`, which derives the
+    /// values of its counters from `clock`, and raises IRQ0 through
+    /// `irq0_evt`.
+    ///
+    /// The time elapsed since the counters were loaded is taken relative to
+    /// the current time of `clock`. The VMM should query
+    /// [`next_deadline`](#method.next_deadline) again afterwards.
+    /// The states are not validated: the times and tick counts beyond the
+    /// range of the counters saturate instead of overflowing.
+    ///
+    /// # Arguments
+    /// * `state` - A reference to the state from which the `Pit` is
+    ///   constructed.
+    /// * `irq0_evt` - The Trigger object used for raising the interrupt of
+    ///   channel 0 (IRQ0).
+    /// * `clock` - The source of the time used by the counters.
+    pub fn from_state(state: &PitState, irq0_evt: T, clock: C) -> Self {
+        trace_device!("pit", "restore");
+        Self::with_state(state, irq0_evt, clock)
+    }
+
+    fn with_state(state: &PitState, irq0_evt: T, clock: C) -> Self {
+        // The longest running counter is loaded at time 0, at the earliest.
+        let max_elapsed_ns = state
+            .channels
+            .iter()
+            .map(|channel| channel.elapsed_ns)
+            .max()
+            .unwrap_or(0);
+        let time_offset_ns = max_elapsed_ns.saturating_sub(clock.now_ns());
+        let now_ns = clock.now_ns().saturating_add(time_offset_ns);
+        let channels = state
+            .channels
+            .map(|channel| Channel::from_state(&channel, now_ns));
+        Pit {
+            irq0_evt,
+            clock,
+            time_offset_ns,
+            channels,
+        }
+    }
+
+    /// Returns the state of the PIT.
+    pub fn state(&self) -> PitState {
+        trace_device!("pit", "save");
+        let now_ns = self.now_ns();
+        PitState {
+            channels: self.channels.map(|channel| channel.state(now_ns)),
+        }
+    }
+
+    // Returns the time used by the counters.
+    fn now_ns(&self) -> u64 {
+        self.clock.now_ns().saturating_add(self.time_offset_ns)
+    }
+
+    /// Provides a reference to the clock source.
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Provides a reference to the IRQ0 event object.
+    pub fn irq0_evt(&self) -> &T {
+        &self.irq0_evt
+    }
+
+    /// Returns the time, as reported by the clock source, when IRQ0 should
+    /// be raised next, or `None` if channel 0 won't raise it anymore.
+    ///
+    /// The deadline changes when the driver programs channel 0, so the VMM
+    /// should query it again after writes to the device, as well as after
+    /// calling [`on_timer_expired`](#method.on_timer_expired).
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.channels[IRQ_CHANNEL]
+            .next_edge_ns()
+            .map(|ns| ns.saturating_sub(self.time_offset_ns))
+    }
+
+    /// Raises IRQ0 if the deadline returned by
+    /// [`next_deadline`](#method.next_deadline) passed, and moves on to the
+    /// next one. Multiple deadlines that passed since the last call only
+    /// raise the interrupt once, like a late timerfd does.
+    pub fn on_timer_expired(&mut self) -> Result<(), T::E> {
+        let now_ns = self.now_ns();
+        if self.channels[IRQ_CHANNEL].check_edge(now_ns) {
+            trace_interrupt!("pit", "irq0");
+            return self.irq0_evt.trigger();
+        }
+        Ok(())
+    }
+
+    /// Sets the level of the gate input of channel 2, which is controlled
+    /// through port 0x61 on PC compatible machines. The gates of channels 0
+    /// and 1 are always high.
+    ///
+    /// While the gate is low, the counter is paused in modes 0 and 4, and
+    /// stopped with its output high in modes 2 and 3. A rising edge of the
+    /// gate reloads the counter in modes 1, 2, 3 and 5.
+    ///
+    /// # Arguments
+    /// * `level` - The new level of the gate.
+    pub fn set_channel2_gate(&mut self, level: bool) {
+        let now_ns = self.now_ns();
+        self.channels[SPEAKER_CHANNEL].set_gate(level, now_ns);
+    }
+
+    /// Returns the level of the gate input of channel 2.
+    pub fn channel2_gate(&self) -> bool {
+        self.channels[SPEAKER_CHANNEL].gate
+    }
+
+    /// Returns the level of the output of channel 2, which drives the PC
+    /// speaker and can be read through port 0x61.
+    pub fn channel2_output(&self) -> bool {
+        self.channels[SPEAKER_CHANNEL].output(self.now_ns())
+    }
+
+    /// Returns the frequency of the signal generated by channel 2, rounded
+    /// to the nearest Hz, when it is programmed as a rate or square wave
+    /// generator, which is how the guests make the PC speaker beep.
+    pub fn channel2_frequency_hz(&self) -> Option<u32> {
+        let channel = &self.channels[SPEAKER_CHANNEL];
+        match channel.mode {
+            MODE_RATE | MODE_SQUARE_WAVE if channel.armed => {
+                let period = channel.period();
+                Some(((PIT_FREQ_HZ + period / 2) / period) as u32)
+            }
+            _ => None,
+        }
+    }
+
+    fn read_back(&mut self, value: u8, now_ns: u64) {
+        for (i, channel) in self.channels.iter_mut().enumerate() {
+            if (value & (READ_BACK_CHANNEL0_BIT << i)) == 0 {
+                continue;
+            }
+            if (value & READ_BACK_NO_COUNT_BIT) == 0 {
+                channel.latch_count(now_ns);
+            }
+            if (value & READ_BACK_NO_STATUS_BIT) == 0 {
+                channel.latch_status(now_ns);
+            }
+        }
+    }
+
+    fn write_command(&mut self, value: u8, now_ns: u64) {
+        let channel = value >> CMD_CHANNEL_SHIFT;
+        let access = (value >> CMD_ACCESS_SHIFT) & CMD_ACCESS_MASK;
+        if channel == CMD_READ_BACK {
+            return self.read_back(value, now_ns);
+        }
+        if access == ACCESS_LATCH {
+            return self.channels[channel as usize].latch_count(now_ns);
+        }
+        let mut mode = (value >> CMD_MODE_SHIFT) & CMD_MODE_MASK;
+        if mode > MODE_HARDWARE_STROBE {
+            mode -= 4;
+        }
+        // The BCD counting is not supported, so the counters are always
+        // binary.
+        if (value & CMD_BCD_BIT) != 0 {
+            guest_warn!("Ignoring the BCD counting of the PIT channel {}", channel);
+        }
+        self.channels[channel as usize].set_command(access, mode);
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Returns the read value. Reads from the channel data registers return
+    /// the current value of the counter, one byte at a time as selected by
+    /// the access mode, while reads from other offsets return 0.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&mut self, offset: u8) -> u8 {
+        let now_ns = self.now_ns();
+        let value = match self
+            .channels
+            .get_mut(offset.wrapping_sub(CHANNEL0_OFFSET) as usize)
+        {
+            Some(channel) => channel.read(now_ns),
+            None => {
+                guest_warn!("Invalid PIT read at offset {:#x}", offset);
+                0x00
+            }
+        };
+        trace_access!("pit", "read", offset, value);
+        value
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) {
+        trace_access!("pit", "write", offset, value);
+        let now_ns = self.now_ns();
+        match offset {
+            COMMAND_OFFSET => self.write_command(value, now_ns),
+            _ => {
+                if let Some(channel) = self
+                    .channels
+                    .get_mut(offset.wrapping_sub(CHANNEL0_OFFSET) as usize)
+                {
+                    channel.write(value, now_ns);
+                } else {
+                    guest_warn!("Invalid PIT write at offset {:#x}", offset);
+                }
+            }
+        }
+    }
+}
+
+impl<T: Trigger, C: ClockSource> LegacyDevice for Pit<T, C> {
+    type State = PitState;
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = Pit::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            Pit::write(self, offset, data[0]);
+        }
+    }
+
+    // The gates are inputs of the PIT, so they keep their levels.
+    fn reset(&mut self) {
+        let now_ns = self.now_ns();
+        for channel in self.channels.iter_mut() {
+            let state = PitChannelState {
+                gate: channel.gate,
+                ..Default::default()
+            };
+            *channel = Channel::from_state(&state, now_ns);
+        }
+    }
+
+    fn state(&self) -> Self::State {
+        Pit::state(self)
+    }
+}
+
+// The PIT is restored with its IRQ0 `Trigger` object and its clock source, and
+// never fails.
+impl<T: Trigger, C: ClockSource> Persist for Pit<T, C> {
+    type State = PitState;
+    type ConstructorArgs = (T, C);
+    type Error = Infallible;
+
+    fn save(&self) -> Self::State {
+        self.state()
+    }
+
+    fn restore(
+        (irq0_evt, clock): Self::ConstructorArgs,
+        state: &Self::State,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self::from_state(state, irq0_evt, clock))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockClock;
+    use std::io;
+    use vmm_sys_util::eventfd::EventFd;
+
+    type TestPit = Pit<EventFd, MockClock>;
+
+    fn new_pit() -> (TestPit, MockClock) {
+        let clock = MockClock::new();
+        let irq0_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        (Pit::new(irq0_evt, clock.clone()), clock)
+    }
+
+    fn irq0_count(pit: &TestPit) -> u64 {
+        match pit.irq0_evt().read() {
+            Ok(count) => count,
+            Err(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+                0
+            }
+        }
+    }
+
+    fn command(channel: u8, access: u8, mode: u8) -> u8 {
+        (channel << CMD_CHANNEL_SHIFT) | (access << CMD_ACCESS_SHIFT) | (mode << CMD_MODE_SHIFT)
+    }
+
+    fn read_word(pit: &mut TestPit, offset: u8) -> u16 {
+        let low = pit.read(offset);
+        let high = pit.read(offset);
+        u16::from_le_bytes([low, high])
+    }
+
+    #[test]
+    fn test_ns_to_ticks() {
+        assert_eq!(ns_to_ticks(0), 0);
+        assert_eq!(ns_to_ticks(NANOS_PER_SEC), PIT_FREQ_HZ);
+        assert_eq!(ns_to_ticks(838), 0);
+        assert_eq!(ns_to_ticks(839), 1);
+        assert_eq!(ns_to_ticks(u64::MAX), 22_010_322_987_356_910);
+
+        assert_eq!(ticks_to_ns(0), 0);
+        assert_eq!(ticks_to_ns(1), 839);
+        assert_eq!(ticks_to_ns(u64::MAX), u64::MAX);
+        assert_eq!(ticks_to_ns(PIT_FREQ_HZ), NANOS_PER_SEC);
+        for ticks in [1, 2, 1000, 0xFFFF, PIT_FREQ_HZ + 1] {
+            assert_eq!(ns_to_ticks(ticks_to_ns(ticks)), ticks);
+            assert_eq!(ns_to_ticks(ticks_to_ns(ticks) - 1), ticks - 1);
+        }
+    }
+
+    #[test]
+    fn test_pit_mode_interrupt() {
+        let (mut pit, clock) = new_pit();
+
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_WORD, MODE_INTERRUPT));
+        // The counter doesn't count until the whole initial count is written.
+        pit.write(0, 0x10);
+        clock.set_ns(ticks_to_ns(1000));
+        pit.write(0, 0x27);
+        assert_eq!(read_word(&mut pit, 0), 10000);
+
+        clock.set_ns(ticks_to_ns(2000));
+        assert_eq!(read_word(&mut pit, 0), 9000);
+
+        // The counter wraps around once it reaches the terminal count.
+        clock.set_ns(ticks_to_ns(10999));
+        assert_eq!(read_word(&mut pit, 0), 1);
+        clock.set_ns(ticks_to_ns(11000));
+        assert_eq!(read_word(&mut pit, 0), 0);
+        clock.set_ns(ticks_to_ns(12000));
+        assert_eq!(read_word(&mut pit, 0), 0xFFFF - 999);
+    }
+
+    #[test]
+    fn test_pit_mode_rate() {
+        let (mut pit, clock) = new_pit();
+
+        // Modes 6 and 7 are aliases of modes 2 and 3.
+        pit.write(COMMAND_OFFSET, command(1, ACCESS_LOW, MODE_RATE + 4));
+        assert_eq!(pit.channels[1].mode, MODE_RATE);
+        pit.write(1, 200);
+        assert_eq!(pit.read(1), 200);
+
+        // The counter reloads periodically.
+        clock.set_ns(ticks_to_ns(1000));
+        assert_eq!(pit.read(1), 200);
+        clock.set_ns(ticks_to_ns(1100));
+        assert_eq!(pit.read(1), 100);
+        clock.set_ns(ticks_to_ns(1199));
+        assert_eq!(pit.read(1), 1);
+        clock.set_ns(ticks_to_ns(1200));
+        assert_eq!(pit.read(1), 200);
+    }
+
+    #[test]
+    fn test_pit_mode_square_wave() {
+        let (mut pit, clock) = new_pit();
+
+        // A 0 initial count stands for 0x10000.
+        pit.write(COMMAND_OFFSET, command(2, ACCESS_HIGH, MODE_SQUARE_WAVE));
+        pit.write(2, 0);
+        assert_eq!(pit.read(2), 0);
+
+        // The counter is decremented by two on each tick.
+        clock.set_ns(ticks_to_ns(1000));
+        assert_eq!(pit.read(2), ((0x10000 - 2000) >> 8) as u8);
+        clock.set_ns(ticks_to_ns(0x8000));
+        assert_eq!(pit.read(2), 0);
+    }
+
+    #[test]
+    fn test_pit_mode_one_shot() {
+        let (mut pit, clock) = new_pit();
+
+        // The hardware triggered modes wait for a rising edge of the gate,
+        // which is always high on channel 0.
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_LOW, MODE_ONE_SHOT));
+        pit.write(0, 100);
+        clock.set_ns(ticks_to_ns(1000));
+        assert_eq!(pit.read(0), 100);
+
+        // The software triggered strobe counts as mode 0.
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_LOW, MODE_SOFTWARE_STROBE));
+        pit.write(0, 100);
+        clock.set_ns(ticks_to_ns(1099));
+        assert_eq!(pit.read(0), 1);
+        clock.set_ns(ticks_to_ns(1100));
+        assert_eq!(pit.read(0), 0);
+        clock.set_ns(ticks_to_ns(1101));
+        assert_eq!(pit.read(0), 0xFF);
+    }
+
+    #[test]
+    fn test_pit_access_modes() {
+        let (mut pit, _) = new_pit();
+
+        // The low and high bytes alternate, and a new command resets the
+        // sequence.
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_WORD, MODE_RATE));
+        pit.write(0, 0x34);
+        pit.write(0, 0x12);
+        assert_eq!(pit.read(0), 0x34);
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_WORD, MODE_RATE));
+        pit.write(0, 0x78);
+        pit.write(0, 0x56);
+        assert_eq!(pit.read(0), 0x78);
+        assert_eq!(pit.read(0), 0x56);
+        assert_eq!(pit.read(0), 0x78);
+
+        // The high byte only.
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_HIGH, MODE_RATE));
+        pit.write(0, 0x9A);
+        assert_eq!(pit.read(0), 0x9A);
+        assert_eq!(pit.channels[0].reload, 0x9A00);
+
+        // The command register is write only, and invalid offsets are ignored.
+        assert_eq!(pit.read(COMMAND_OFFSET), 0);
+        pit.write(COMMAND_OFFSET + 1, 0xFF);
+        assert_eq!(pit.read(COMMAND_OFFSET + 1), 0);
+    }
+
+    #[test]
+    fn test_pit_irq0_periodic() {
+        let (mut pit, clock) = new_pit();
+        assert_eq!(pit.next_deadline(), None);
+
+        clock.set_ns(ticks_to_ns(500));
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_LOW, MODE_RATE));
+        pit.write(0, 100);
+        assert_eq!(pit.next_deadline(), Some(ticks_to_ns(600)));
+
+        // Nothing happens before the deadline.
+        clock.set_ns(ticks_to_ns(599));
+        pit.on_timer_expired().unwrap();
+        assert_eq!(irq0_count(&pit), 0);
+        assert_eq!(pit.next_deadline(), Some(ticks_to_ns(600)));
+
+        clock.set_ns(ticks_to_ns(600));
+        pit.on_timer_expired().unwrap();
+        assert_eq!(irq0_count(&pit), 1);
+        assert_eq!(pit.next_deadline(), Some(ticks_to_ns(700)));
+
+        // The interrupts that were missed are coalesced.
+        clock.set_ns(ticks_to_ns(950));
+        pit.on_timer_expired().unwrap();
+        assert_eq!(irq0_count(&pit), 1);
+        assert_eq!(pit.next_deadline(), Some(ticks_to_ns(1000)));
+
+        // The square wave generator has the same period.
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_LOW, MODE_SQUARE_WAVE));
+        assert_eq!(pit.next_deadline(), None);
+        pit.write(0, 50);
+        assert_eq!(pit.next_deadline(), Some(ticks_to_ns(1000)));
+        clock.set_ns(ticks_to_ns(1000));
+        pit.on_timer_expired().unwrap();
+        assert_eq!(irq0_count(&pit), 1);
+        assert_eq!(pit.next_deadline(), Some(ticks_to_ns(1050)));
+
+        // Only channel 0 raises IRQ0.
+        pit.write(COMMAND_OFFSET, command(1, ACCESS_LOW, MODE_RATE));
+        pit.write(1, 10);
+        assert_eq!(pit.next_deadline(), Some(ticks_to_ns(1050)));
+    }
+
+    #[test]
+    fn test_pit_irq0_one_shot() {
+        let (mut pit, clock) = new_pit();
+
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_WORD, MODE_INTERRUPT));
+        pit.write(0, 0x00);
+        pit.write(0, 0x01);
+        assert_eq!(pit.next_deadline(), Some(ticks_to_ns(0x100)));
+        clock.set_ns(ticks_to_ns(0x100));
+        pit.on_timer_expired().unwrap();
+        assert_eq!(irq0_count(&pit), 1);
+
+        // The interrupt is raised once, even though the counter wraps around.
+        assert_eq!(pit.next_deadline(), None);
+        clock.set_ns(ticks_to_ns(0x20000));
+        pit.on_timer_expired().unwrap();
+        assert_eq!(irq0_count(&pit), 0);
+
+        // The software triggered strobe raises it one tick later.
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_LOW, MODE_SOFTWARE_STROBE));
+        pit.write(0, 10);
+        assert_eq!(pit.next_deadline(), Some(ticks_to_ns(0x20000 + 11)));
+
+        // The hardware triggered modes never count on channel 0, since its
+        // gate is always high.
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_LOW, MODE_ONE_SHOT));
+        pit.write(0, 10);
+        assert_eq!(pit.next_deadline(), None);
+    }
+
+    #[test]
+    fn test_pit_latch() {
+        let (mut pit, clock) = new_pit();
+
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_WORD, MODE_RATE));
+        pit.write(0, 0x00);
+        pit.write(0, 0x10);
+        clock.set_ns(ticks_to_ns(0x100));
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_LATCH, 0));
+
+        // The latched value doesn't change while it is read, and a second
+        // latch command is ignored until it is completely read.
+        clock.set_ns(ticks_to_ns(0x180));
+        assert_eq!(pit.read(0), 0x00);
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_LATCH, 0));
+        clock.set_ns(ticks_to_ns(0x200));
+        assert_eq!(pit.read(0), 0x0F);
+
+        // The counter is read again after that.
+        assert_eq!(read_word(&mut pit, 0), 0x0E00);
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_LATCH, 0));
+        clock.set_ns(ticks_to_ns(0x280));
+        assert_eq!(read_word(&mut pit, 0), 0x0E00);
+        assert_eq!(read_word(&mut pit, 0), 0x0D80);
+
+        // With a single byte access mode, a single read consumes the latch.
+        pit.write(COMMAND_OFFSET, command(1, ACCESS_HIGH, MODE_RATE));
+        pit.write(1, 0x10);
+        clock.set_ns(ticks_to_ns(0x380));
+        pit.write(COMMAND_OFFSET, command(1, ACCESS_LATCH, 0));
+        clock.set_ns(ticks_to_ns(0x480));
+        assert_eq!(pit.read(1), 0x0F);
+        assert_eq!(pit.read(1), 0x0E);
+
+        // A new command drops the latched value.
+        pit.write(COMMAND_OFFSET, command(1, ACCESS_LATCH, 0));
+        pit.write(COMMAND_OFFSET, command(1, ACCESS_LOW, MODE_RATE));
+        pit.write(1, 0x20);
+        assert_eq!(pit.read(1), 0x20);
+    }
+
+    #[test]
+    fn test_pit_read_back() {
+        let (mut pit, clock) = new_pit();
+
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_WORD, MODE_INTERRUPT));
+        pit.write(COMMAND_OFFSET, command(2, ACCESS_LOW, MODE_SQUARE_WAVE));
+        pit.write(2, 100);
+
+        // Latch the status of all the channels. The counters of channels 0
+        // and 1 weren't loaded yet, and their outputs are low in mode 0.
+        let read_back = (CMD_READ_BACK << CMD_CHANNEL_SHIFT) | READ_BACK_NO_COUNT_BIT | 0b1110;
+        pit.write(COMMAND_OFFSET, read_back);
+        assert_eq!(
+            pit.read(0),
+            STATUS_NULL_COUNT_BIT | (ACCESS_WORD << CMD_ACCESS_SHIFT)
+        );
+        assert_eq!(
+            pit.read(1),
+            STATUS_NULL_COUNT_BIT
+                | (ACCESS_WORD << CMD_ACCESS_SHIFT)
+                | (MODE_INTERRUPT << CMD_MODE_SHIFT)
+        );
+        assert_eq!(
+            pit.read(2),
+            STATUS_OUTPUT_BIT
+                | (ACCESS_LOW << CMD_ACCESS_SHIFT)
+                | (MODE_SQUARE_WAVE << CMD_MODE_SHIFT)
+        );
+        // The status is only read once.
+        assert_eq!(pit.read(2), 100);
+
+        // Latch both the status and the count of channel 2, during the
+        // second half of the square wave.
+        clock.set_ns(ticks_to_ns(60));
+        let read_back = (CMD_READ_BACK << CMD_CHANNEL_SHIFT) | 0b1000;
+        pit.write(COMMAND_OFFSET, read_back);
+        clock.set_ns(ticks_to_ns(70));
+        assert_eq!(
+            pit.read(2),
+            (ACCESS_LOW << CMD_ACCESS_SHIFT) | (MODE_SQUARE_WAVE << CMD_MODE_SHIFT)
+        );
+        assert_eq!(pit.read(2), 80);
+        assert_eq!(pit.read(2), 60);
+
+        // Channel 0 counts, and its output goes high at the terminal count.
+        pit.write(0, 10);
+        pit.write(0, 0);
+        clock.set_ns(ticks_to_ns(75));
+        let read_back = (CMD_READ_BACK << CMD_CHANNEL_SHIFT) | 0b0010;
+        pit.write(COMMAND_OFFSET, read_back);
+        assert_eq!(pit.read(0), ACCESS_WORD << CMD_ACCESS_SHIFT);
+        assert_eq!(read_word(&mut pit, 0), 5);
+        clock.set_ns(ticks_to_ns(81));
+        pit.write(COMMAND_OFFSET, read_back);
+        assert_eq!(
+            pit.read(0),
+            STATUS_OUTPUT_BIT | (ACCESS_WORD << CMD_ACCESS_SHIFT)
+        );
+        assert_eq!(read_word(&mut pit, 0), 0xFFFF);
+    }
+
+    #[test]
+    fn test_pit_state() {
+        let (mut pit, clock) = new_pit();
+        assert_eq!(pit.state(), PitState::default());
+
+        clock.set_ns(ticks_to_ns(1000));
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_WORD, MODE_RATE));
+        pit.write(0, 0xE8);
+        pit.write(0, 0x03);
+        pit.write(COMMAND_OFFSET, command(2, ACCESS_WORD, MODE_SQUARE_WAVE));
+        pit.write(2, 0x34);
+        clock.set_ns(ticks_to_ns(1600));
+        pit.on_timer_expired().unwrap();
+        pit.write(COMMAND_OFFSET, command(0, ACCESS_LATCH, 0));
+        assert_eq!(pit.read(0), 0x90);
+
+        let state = pit.state();
+        assert_eq!(
+            state.channels[0].elapsed_ns,
+            ticks_to_ns(1600) - ticks_to_ns(1000)
+        );
+        assert_eq!(state.channels[0].latched_count, Some(400));
+        assert_eq!(state.channels[2].write_low, Some(0x34));
+
+        // Restore the state on top of a clock that is 1 second ahead.
+        let clock = MockClock::new();
+        clock.set_ns(ticks_to_ns(PIT_FREQ_HZ + 1600));
+        let irq0_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let mut pit = Pit::from_state(&state, irq0_evt, clock.clone());
+        assert_eq!(pit.state(), state);
+
+        // The latched value is read, and the counter keeps its phase.
+        assert_eq!(pit.read(0), 0x01);
+        assert_eq!(read_word(&mut pit, 0), 400);
+        assert_eq!(pit.next_deadline(), Some(ticks_to_ns(PIT_FREQ_HZ + 2000)));
+        clock.set_ns(ticks_to_ns(PIT_FREQ_HZ + 2000));
+        pit.on_timer_expired().unwrap();
+        assert_eq!(irq0_count(&pit), 1);
+        assert_eq!(read_word(&mut pit, 0), 1000);
+
+        // The initial count of channel 2 is completed.
+        pit.write(2, 0x12);
+        assert_eq!(read_word(&mut pit, 2), 0x1234);
+    }
+
+    #[test]
+    fn test_pit_state_out_of_range() {
+        // The restored states may hold any phase, e.g. from a corrupted
+        // snapshot, with which the counters must not overflow.
+        for mode in MODE_INTERRUPT..=MODE_HARDWARE_STROBE {
+            for (elapsed_ns, paused_ticks, next_edge) in [
+                (0, None, Some(u64::MAX)),
+                (u64::MAX, None, Some(u64::MAX)),
+                (u64::MAX, Some(u64::MAX), Some(u64::MAX)),
+                (0, Some(u64::MAX), Some(0)),
+            ] {
+                let channel = PitChannelState {
+                    mode,
+                    reload: 100,
+                    elapsed_ns,
+                    armed: true,
+                    counting: true,
+                    paused_ticks,
+                    next_edge,
+                    ..Default::default()
+                };
+                let state = PitState {
+                    channels: [channel; NUM_CHANNELS],
+                };
+                for now_ticks in [0, u64::MAX / 2, u64::MAX] {
+                    let clock = MockClock::new();
+                    clock.set_ns(ticks_to_ns(now_ticks));
+                    let irq0_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+                    let mut pit = Pit::from_state(&state, irq0_evt, clock.clone());
+                    pit.next_deadline();
+                    pit.on_timer_expired().unwrap();
+                    pit.next_deadline();
+                    for offset in 0..3 {
+                        pit.read(offset);
+                    }
+                    pit.write(COMMAND_OFFSET, 0xC2 | READ_BACK_CHANNEL0_BIT);
+                    pit.read(0);
+                    pit.set_channel2_gate(true);
+                    pit.set_channel2_gate(false);
+                    pit.set_channel2_gate(true);
+                    pit.state();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pit_channel2_gate() {
+        let (mut pit, clock) = new_pit();
+        assert!(pit.channel2_gate());
+
+        // Mode 0 pauses while the gate is low, which is how Linux calibrates
+        // the TSC.
+        pit.set_channel2_gate(false);
+        pit.write(COMMAND_OFFSET, command(2, ACCESS_WORD, MODE_INTERRUPT));
+        pit.write(2, 0xE8);
+        pit.write(2, 0x03);
+        assert!(!pit.channel2_output());
+        clock.set_ns(ticks_to_ns(500));
+        assert_eq!(read_word(&mut pit, 2), 1000);
+        pit.set_channel2_gate(true);
+        clock.set_ns(ticks_to_ns(900));
+        assert_eq!(read_word(&mut pit, 2), 600);
+        pit.set_channel2_gate(false);
+        clock.set_ns(ticks_to_ns(2000));
+        assert_eq!(read_word(&mut pit, 2), 600);
+        assert!(!pit.channel2_output());
+        pit.set_channel2_gate(true);
+        clock.set_ns(ticks_to_ns(2600));
+        assert_eq!(read_word(&mut pit, 2), 0);
+        assert!(pit.channel2_output());
+
+        // The square wave generator stops with its output high while the
+        // gate is low, and restarts on the rising edge.
+        pit.write(COMMAND_OFFSET, command(2, ACCESS_LOW, MODE_SQUARE_WAVE));
+        pit.write(2, 100);
+        clock.set_ns(ticks_to_ns(2660));
+        assert!(!pit.channel2_output());
+        pit.set_channel2_gate(false);
+        assert!(pit.channel2_output());
+        assert_eq!(pit.read(2), 100);
+        clock.set_ns(ticks_to_ns(3000));
+        pit.set_channel2_gate(true);
+        clock.set_ns(ticks_to_ns(3040));
+        assert!(pit.channel2_output());
+        assert_eq!(pit.read(2), 20);
+
+        // The hardware triggered one-shot waits for a rising edge of the
+        // gate, and sets its output low until the terminal count.
+        pit.write(COMMAND_OFFSET, command(2, ACCESS_LOW, MODE_ONE_SHOT));
+        pit.write(2, 50);
+        clock.set_ns(ticks_to_ns(4000));
+        assert_eq!(pit.read(2), 50);
+        assert!(pit.channel2_output());
+        pit.set_channel2_gate(false);
+        pit.set_channel2_gate(true);
+        clock.set_ns(ticks_to_ns(4049));
+        assert_eq!(pit.read(2), 1);
+        assert!(!pit.channel2_output());
+        clock.set_ns(ticks_to_ns(4050));
+        assert!(pit.channel2_output());
+
+        // The gate is part of the state.
+        pit.set_channel2_gate(false);
+        assert!(!pit.state().channels[2].gate);
+        assert!(pit.state().channels[0].gate);
+    }
+
+    #[test]
+    fn test_pit_channel2_frequency() {
+        let (mut pit, _) = new_pit();
+        assert_eq!(pit.channel2_frequency_hz(), None);
+
+        // Program a 1 kHz square wave.
+        pit.write(COMMAND_OFFSET, command(2, ACCESS_WORD, MODE_SQUARE_WAVE));
+        assert_eq!(pit.channel2_frequency_hz(), None);
+        pit.write(2, 0xA9);
+        pit.write(2, 0x04);
+        assert_eq!(pit.channel2_frequency_hz(), Some(1000));
+
+        // A 0 initial count stands for the lowest frequency.
+        pit.write(COMMAND_OFFSET, command(2, ACCESS_LOW, MODE_RATE));
+        pit.write(2, 0);
+        assert_eq!(pit.channel2_frequency_hz(), Some(18));
+
+        pit.write(COMMAND_OFFSET, command(2, ACCESS_LOW, MODE_INTERRUPT));
+        pit.write(2, 100);
+        assert_eq!(pit.channel2_frequency_hz(), None);
+    }
+
+    #[test]
+    fn test_pit_legacy_device() {
+        let (mut pit, _) = new_pit();
+
+        LegacyDevice::write(
+            &mut pit,
+            u16::from(COMMAND_OFFSET),
+            &[command(0, ACCESS_LOW, MODE_RATE)],
+        );
+        LegacyDevice::write(&mut pit, u16::from(CHANNEL0_OFFSET), &[100]);
+        let mut data = [0; 1];
+        LegacyDevice::read(&mut pit, u16::from(CHANNEL0_OFFSET), &mut data);
+        assert_eq!(data, [100]);
+        assert!(pit.next_deadline().is_some());
+        pit.set_channel2_gate(false);
+
+        // The reset stops the counters, and keeps the gates.
+        pit.reset();
+        assert!(pit.next_deadline().is_none());
+        let state = LegacyDevice::state(&pit);
+        assert_eq!(state.channels[0], PitChannelState::default());
+        assert!(!state.channels[2].gate);
+    }
+
+    #[cfg(feature = "fuzzing")]
+    #[test]
+    fn test_arbitrary_state() {
+        // A rate generator armed with an edge far beyond its period.
+        let channel = PitChannelState {
+            mode: MODE_RATE,
+            reload: 100,
+            armed: true,
+            counting: true,
+            next_edge: Some(u64::MAX),
+            ..Default::default()
+        };
+        let paused = PitChannelState {
+            mode: MODE_INTERRUPT,
+            elapsed_ns: u64::MAX,
+            paused_ticks: Some(u64::MAX),
+            ..channel
+        };
+        let edge_states = [
+            PitState {
+                channels: [channel; NUM_CHANNELS],
+            },
+            PitState {
+                channels: [paused; NUM_CHANNELS],
+            },
+        ];
+        crate::testing::for_each_arbitrary_state(&edge_states, |state| {
+            let clock = MockClock::new();
+            clock.set_ns(ticks_to_ns(u64::from(u16::MAX)));
+            let mut pit = Pit::from_state(
+                state,
+                EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+                clock.clone(),
+            );
+            for offset in 0..3 {
+                pit.read(offset);
+            }
+            pit.next_deadline();
+            clock.set_ns(ticks_to_ns(u64::from(u32::MAX)));
+            pit.on_timer_expired().unwrap();
+            pit.next_deadline();
+            pit.set_channel2_gate(!state.channels[SPEAKER_CHANNEL].gate);
+            pit.read(0);
+            pit.state();
+        });
+    }
+
+    #[test]
+    fn test_persist() {
+        let (mut pit, clock) = new_pit();
+        pit.write(COMMAND_OFFSET, command(0, 3, 2));
+        pit.write(CHANNEL0_OFFSET, 0x34);
+        pit.write(CHANNEL0_OFFSET, 0x12);
+        clock.set_ns(ticks_to_ns(0x10));
+
+        let state = pit.save();
+        assert_eq!(state, pit.state());
+        let irq0_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let restored = Pit::restore((irq0_evt, clock), &state).unwrap();
+        assert_eq!(restored.save(), state);
+    }
+
+    #[test]
+    fn test_state_validate() {
+        assert_eq!(PitState::default().validate(), Ok(()));
+
+        let mut state = PitState::default();
+        state.channels[2].mode = 6;
+        assert_eq!(
+            state.validate(),
+            Err(StateError::InvalidRegister {
+                register: "mode",
+                value: 6,
+            })
+        );
+
+        let mut state = PitState::default();
+        state.channels[1].access = ACCESS_LATCH;
+        assert_eq!(
+            state.validate(),
+            Err(StateError::InvalidRegister {
+                register: "access",
+                value: 0,
+            })
+        );
+
+        // A counter armed with an edge out of reach of its period.
+        let mut state = PitChannelState {
+            mode: MODE_RATE,
+            access: ACCESS_WORD,
+            reload: 100,
+            armed: true,
+            counting: true,
+            next_edge: Some(u64::MAX),
+            ..Default::default()
+        };
+        assert_eq!(
+            state.validate(),
+            Err(StateError::OutOfRange {
+                field: "next_edge",
+                value: u64::MAX,
+                max: 102,
+            })
+        );
+        state.next_edge = Some(102);
+        assert_eq!(state.validate(), Ok(()));
+
+        state.paused_ticks = Some(2);
+        assert_eq!(
+            state.validate(),
+            Err(StateError::OutOfRange {
+                field: "paused_ticks",
+                value: 2,
+                max: 1,
+            })
+        );
+
+        state.elapsed_ns = u64::MAX;
+        assert_eq!(
+            state.validate(),
+            Err(StateError::OutOfRange {
+                field: "elapsed_ns",
+                value: u64::MAX,
+                max: MAX_ELAPSED_NS,
+            })
+        );
+    }
+
+    #[test]
+    fn test_state_validate_phases() {
+        // The states saved by the device always pass the validation, whatever
+        // the phase of the counters.
+        let (mut pit, clock) = new_pit();
+        for mode in MODE_INTERRUPT..=MODE_HARDWARE_STROBE {
+            pit.write(COMMAND_OFFSET, command(2, ACCESS_WORD, mode));
+            pit.write(CHANNEL0_OFFSET + 2, 0x07);
+            pit.write(CHANNEL0_OFFSET + 2, 0x00);
+            pit.set_channel2_gate(true);
+            for now_ns in (0..50_000).step_by(839) {
+                clock.set_ns(now_ns);
+                pit.on_timer_expired().unwrap();
+                let state = pit.state();
+                assert_eq!(state.validate(), Ok(()), "{:?}", state);
+            }
+            pit.set_channel2_gate(false);
+            let state = pit.state();
+            assert_eq!(state.validate(), Ok(()), "{:?}", state);
+        }
+    }
+}
@@ -0,0 +1,905 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the ARM PL050 Keyboard/Mouse Interface (KMI).
+//!
+//! Each PL050 instance connects a single PS/2 device, either a keyboard or a
+//! mouse, to the guest, through a small MMIO register block. This module
+//! emulates the interface, along with just enough of the PS/2 device for the
+//! guest drivers to probe and configure it. The input is sent to the guest
+//! the same way as with the i8042 controller, either as raw PS/2 bytes, or as
+//! [`KeyEvent`](../i8042/struct.KeyEvent.html)s for the keyboard.
+
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::result::Result;
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
+
+use crate::i8042::{
+    KeyEvent, DEFAULT_KBD_TYPEMATIC, KBD_ACK, KBD_BAT_OK, KBD_CMD_DISABLE_SCANNING, KBD_CMD_ECHO,
+    KBD_CMD_ENABLE_SCANNING, KBD_CMD_IDENTIFY, KBD_CMD_RESET, KBD_CMD_SET_DEFAULTS,
+    KBD_CMD_SET_LEDS, KBD_CMD_SET_TYPEMATIC, KBD_ECHO, KBD_ID, KBD_LEDS_MASK, KBD_RESEND,
+    KBD_TYPEMATIC_MASK,
+};
+use crate::{LegacyDevice, Trigger};
+
+// The following defines are mapping to the specification:
+// https://developer.arm.com/documentation/ddi0143/c/programmer-s-model/summary-of-primecell-kmi-registers
+const KMICR: u16 = 0x000; // Control Register.
+const KMISTAT: u16 = 0x004; // Status Register (RO).
+const KMIDATA: u16 = 0x008; // Received data (read) / Data to be transmitted (write).
+const KMICLKDIV: u16 = 0x00C; // Clock Divisor Register.
+const KMIIR: u16 = 0x010; // Interrupt Status Register (RO).
+
+// From 0xFE0 to 0xFFF => Peripheral and PrimeCell Identification Registers.
+const AMBA_IDS: [u8; 8] = [0x50, 0x10, 0x04, 0x00, 0x0d, 0xf0, 0x05, 0xb1];
+const AMBA_ID_LOW: u16 = 0xFE0;
+const AMBA_ID_HIGH: u16 = 0xFFF;
+
+// Control register bits.
+// Enables the transmitter interrupt.
+const CR_TXINTREN: u32 = 1 << 3;
+// Enables the receiver interrupt.
+const CR_RXINTREN: u32 = 1 << 4;
+// The writable bits, which also include the forced clock and data lines, the
+// interface enable and the interface type.
+const CR_MASK: u32 = 0x3F;
+
+// Status register bits.
+// The data and clock lines, which are idle (high).
+const STAT_KMID: u32 = 1 << 0;
+const STAT_KMIC: u32 = 1 << 1;
+// The receive register holds a byte for the driver.
+const STAT_RXFULL: u32 = 1 << 4;
+// The transmit register is empty, which is always the case since the bytes
+// are handled as soon as they are written.
+const STAT_TXEMPTY: u32 = 1 << 6;
+
+// Interrupt status register bits.
+const IR_RXINTR: u32 = 1 << 0;
+const IR_TXINTR: u32 = 1 << 1;
+
+// The clock divisor is 4 bits wide.
+const CLKDIV_MASK: u32 = 0x0F;
+
+// Mouse commands, written by the driver to the data register. The mouse
+// acknowledges the other commands, such as the mode ones, without any effect.
+// Set the resolution; the value is the next byte written.
+const MOUSE_CMD_SET_RESOLUTION: u8 = 0xE8;
+// Send the status bytes.
+const MOUSE_CMD_STATUS_REQUEST: u8 = 0xE9;
+// Set the scaling to 1:1 and 2:1.
+const MOUSE_CMD_SET_SCALING_1_1: u8 = 0xE6;
+const MOUSE_CMD_SET_SCALING_2_1: u8 = 0xE7;
+// Identify the mouse.
+const MOUSE_CMD_GET_ID: u8 = 0xF2;
+// Set the sample rate; the value is the next byte written.
+const MOUSE_CMD_SET_SAMPLE_RATE: u8 = 0xF3;
+// Enable and disable the data reporting.
+const MOUSE_CMD_ENABLE_REPORTING: u8 = 0xF4;
+const MOUSE_CMD_DISABLE_REPORTING: u8 = 0xF5;
+// Restore the default parameters.
+const MOUSE_CMD_SET_DEFAULTS: u8 = 0xF6;
+// Reset the mouse and run its self-test.
+const MOUSE_CMD_RESET: u8 = 0xFF;
+// The identification byte of a standard PS/2 mouse.
+const MOUSE_ID: u8 = 0x00;
+// The default resolution (4 counts/mm) and sample rate (100 samples/s).
+const DEFAULT_MOUSE_RESOLUTION: u8 = 0x02;
+const DEFAULT_MOUSE_SAMPLE_RATE: u8 = 100;
+// Bits of the first status byte.
+const MOUSE_STATUS_SCALING_2_1: u8 = 1 << 4;
+const MOUSE_STATUS_REPORTING: u8 = 1 << 5;
+
+// The size of the receive buffer, in bytes.
+const BUFFER_SIZE: usize = 16;
+
+/// The PS/2 device connected to a PL050 instance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ps2Device {
+    /// A standard MF2 keyboard, using scancode set 2.
+    Keyboard,
+    /// A standard PS/2 mouse, without a scroll wheel.
+    Mouse,
+}
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the PL050 emulation logic. The methods below can be implemented by a
+/// backend that keeps track of such events by incrementing metrics, logging messages, or any
+/// other action.
+pub trait Pl050Events {
+    /// The `dropped` bytes were lost because there was no space left in the
+    /// receive buffer.
+    fn buffer_overflow(&self, dropped: usize);
+    /// The driver sent a command which is not known by the PS/2 device.
+    fn unknown_command(&self);
+    /// The driver changed the keyboard LEDs. Bit 0 of `leds` is Scroll Lock,
+    /// bit 1 is Num Lock and bit 2 is Caps Lock.
+    fn leds_changed(&self, leds: u8);
+}
+
+/// Provides a no-op implementation of `Pl050Events` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `Pl050Events`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl Pl050Events for NoEvents {
+    fn buffer_overflow(&self, _dropped: usize) {}
+    fn unknown_command(&self) {}
+    fn leds_changed(&self, _leds: u8) {}
+}
+
+impl<EV: Pl050Events> Pl050Events for Arc<EV> {
+    fn buffer_overflow(&self, dropped: usize) {
+        self.as_ref().buffer_overflow(dropped);
+    }
+
+    fn unknown_command(&self) {
+        self.as_ref().unknown_command();
+    }
+
+    fn leds_changed(&self, leds: u8) {
+        self.as_ref().leds_changed(leds);
+    }
+}
+
+/// Errors encountered while handling PL050 operations.
+#[derive(Debug)]
+pub enum Error<E> {
+    /// Failed to trigger interrupt.
+    Trigger(E),
+    /// No space left in the receive buffer.
+    FullBuffer,
+    /// The operation is not supported by the connected PS/2 device.
+    WrongDevice,
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Trigger(e) => write!(f, "Failed to trigger interrupt: {}", e),
+            Error::FullBuffer => write!(f, "No space left in the receive buffer"),
+            Error::WrongDevice => write!(f, "Not supported by the connected PS/2 device"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: StdError> StdError for Error<E> {}
+
+/// An ARM PL050 Keyboard/Mouse Interface, with a PS/2 keyboard or mouse
+/// connected to it.
+///
+/// The bytes sent by the PS/2 device wait in a receive buffer, and a
+/// [`Trigger`](../trait.Trigger.html) object raises the KMI interrupt when a
+/// new one is available and the driver enabled the receiver interrupt. The
+/// bytes written by the driver are handled by the PS/2 device right away.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::i8042::{KeyCode, KeyEvent};
+/// # use vm_superio::pl050::{Pl050, Ps2Device};
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut kmi = Pl050::new(DummyTrigger, Ps2Device::Keyboard);
+///
+/// // The driver enables the interface and the receiver interrupt.
+/// kmi.write(0x00, &0x14u32.to_le_bytes()).unwrap();
+///
+/// // Press Escape.
+/// let event = KeyEvent {
+///     key: KeyCode::Escape,
+///     pressed: true,
+/// };
+/// kmi.send_key_event(event).unwrap();
+/// let mut data = [0; 4];
+/// kmi.read(0x08, &mut data);
+/// assert_eq!(u32::from_le_bytes(data), 0x76);
+/// ```
+#[derive(Debug)]
+pub struct Pl050<T: Trigger, EV: Pl050Events> {
+    // KMI interrupt object.
+    interrupt_evt: T,
+    events: EV,
+    device: Ps2Device,
+    // The control and clock divisor registers.
+    cr: u32,
+    clkdiv: u32,
+    // Bytes sent by the PS/2 device, waiting to be read by the driver.
+    rx_buffer: VecDeque<u8>,
+    // The last byte read by the driver, which is read again when the receive
+    // buffer is empty.
+    last_rx: u8,
+    // The PS/2 device command waiting for its parameter.
+    pending_command: Option<u8>,
+    // Whether the keyboard sends scancodes, or the mouse reports movements.
+    reporting: bool,
+    kbd_leds: u8,
+    kbd_typematic: u8,
+    mouse_resolution: u8,
+    mouse_sample_rate: u8,
+    mouse_scaling_2_1: bool,
+}
+
+impl<T: Trigger> Pl050<T, NoEvents> {
+    /// Creates a new `Pl050` instance, connected to `device`, which will
+    /// signal `interrupt_evt` when a byte is available for the driver.
+    ///
+    /// # Arguments
+    /// * `interrupt_evt` - A Trigger object that will be used to raise the
+    ///   KMI interrupt.
+    /// * `device` - The PS/2 device connected to the interface.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Pl050`](struct.Pl050.html#example).
+    pub fn new(interrupt_evt: T, device: Ps2Device) -> Self {
+        Self::with_events(interrupt_evt, device, NoEvents)
+    }
+}
+
+impl<T: Trigger, EV: Pl050Events> Pl050<T, EV> {
+    /// Creates a new `Pl050` instance, connected to `device`, which will
+    /// signal `interrupt_evt` when a byte is available for the driver, and
+    /// invokes the `pl050_evts` implementation of `Pl050Events` during
+    /// operation.
+    ///
+    /// # Arguments
+    /// * `interrupt_evt` - A Trigger object that will be used to raise the
+    ///   KMI interrupt.
+    /// * `device` - The PS/2 device connected to the interface.
+    /// * `pl050_evts` - The `Pl050Events` implementation used to track the
+    ///   dropped bytes, the unknown commands and the keyboard LEDs.
+    pub fn with_events(interrupt_evt: T, device: Ps2Device, pl050_evts: EV) -> Self {
+        Pl050 {
+            interrupt_evt,
+            events: pl050_evts,
+            device,
+            cr: 0,
+            clkdiv: 0,
+            rx_buffer: VecDeque::with_capacity(BUFFER_SIZE),
+            last_rx: 0,
+            pending_command: None,
+            // The mouse only reports the movements once enabled by the driver.
+            reporting: device == Ps2Device::Keyboard,
+            kbd_leds: 0,
+            kbd_typematic: DEFAULT_KBD_TYPEMATIC,
+            mouse_resolution: DEFAULT_MOUSE_RESOLUTION,
+            mouse_sample_rate: DEFAULT_MOUSE_SAMPLE_RATE,
+            mouse_scaling_2_1: false,
+        }
+    }
+
+    /// Provides a reference to the interrupt event object.
+    pub fn interrupt_evt(&self) -> &T {
+        &self.interrupt_evt
+    }
+
+    /// Provides a reference to the PL050 events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Returns the PS/2 device connected to the interface.
+    pub fn device(&self) -> Ps2Device {
+        self.device
+    }
+
+    /// Returns whether the PS/2 device sends its input to the guest, i.e.
+    /// whether the keyboard scanning or the mouse data reporting is enabled.
+    pub fn is_reporting(&self) -> bool {
+        self.reporting
+    }
+
+    fn rx_interrupt_enabled(&self) -> bool {
+        (self.cr & CR_RXINTREN) != 0
+    }
+
+    // Queues the bytes sent by the PS/2 device, and raises the interrupt if
+    // they are the next ones to be read. The bytes which don't fit are
+    // dropped, and reported through the events object.
+    fn push_rx(&mut self, bytes: &[u8]) -> Result<usize, T::E> {
+        let was_empty = self.rx_buffer.is_empty();
+        let count = core::cmp::min(BUFFER_SIZE - self.rx_buffer.len(), bytes.len());
+        self.rx_buffer.extend(&bytes[..count]);
+        if count < bytes.len() {
+            self.events.buffer_overflow(bytes.len() - count);
+        }
+
+        if was_empty && count > 0 && self.rx_interrupt_enabled() {
+            self.interrupt_evt.trigger()?;
+        }
+        Ok(count)
+    }
+
+    fn pop_rx(&mut self) -> u8 {
+        if let Some(byte) = self.rx_buffer.pop_front() {
+            self.last_rx = byte;
+            // The interrupt is raised again for the next byte, if any. There
+            // is no way of reporting a failure to the driver from a read.
+            if !self.rx_buffer.is_empty() && self.rx_interrupt_enabled() {
+                let _ = self.interrupt_evt.trigger();
+            }
+        }
+        self.last_rx
+    }
+
+    /// Sends bytes coming from the PS/2 device to the guest, by storing them
+    /// in the receive buffer and raising the interrupt when enabled.
+    ///
+    /// Returns the number of bytes that fit in the receive buffer. The other
+    /// ones are dropped, and so are all of them when the driver disabled the
+    /// keyboard scanning or the mouse data reporting.
+    ///
+    /// # Arguments
+    /// * `bytes` - The PS/2 data to be sent to the guest, i.e. scancode set
+    ///   2 codes for the keyboard, or movement packets for the mouse.
+    pub fn enqueue_bytes(&mut self, bytes: &[u8]) -> Result<usize, T::E> {
+        if !self.reporting {
+            return Ok(0);
+        }
+        self.push_rx(bytes)
+    }
+
+    /// Sends a key press or release to the guest, encoded using scancode set
+    /// 2, only if the whole scancode sequence fits in the receive buffer.
+    ///
+    /// # Arguments
+    /// * `event` - The key event to be sent to the guest.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Pl050`](struct.Pl050.html#example).
+    pub fn send_key_event(&mut self, event: KeyEvent) -> Result<(), Error<T::E>> {
+        if self.device != Ps2Device::Keyboard {
+            return Err(Error::WrongDevice);
+        }
+
+        let sequence = if event.pressed {
+            event.key.make_code().to_vec()
+        } else {
+            event.key.break_code()
+        };
+        if sequence.len() > BUFFER_SIZE - self.rx_buffer.len() {
+            return Err(Error::FullBuffer);
+        }
+        self.enqueue_bytes(&sequence).map_err(Error::Trigger)?;
+        Ok(())
+    }
+
+    fn set_kbd_leds(&mut self, leds: u8) {
+        if self.kbd_leds != leds {
+            self.kbd_leds = leds;
+            self.events.leds_changed(leds);
+        }
+    }
+
+    // Handles a byte sent by the driver to the keyboard, and returns the
+    // response.
+    fn write_kbd(&mut self, value: u8) -> Vec<u8> {
+        match self.pending_command.take() {
+            Some(KBD_CMD_SET_LEDS) => {
+                self.set_kbd_leds(value & KBD_LEDS_MASK);
+                return vec![KBD_ACK];
+            }
+            Some(KBD_CMD_SET_TYPEMATIC) => {
+                self.kbd_typematic = value & KBD_TYPEMATIC_MASK;
+                return vec![KBD_ACK];
+            }
+            _ => {}
+        }
+
+        match value {
+            KBD_CMD_SET_LEDS | KBD_CMD_SET_TYPEMATIC => {
+                self.pending_command = Some(value);
+                vec![KBD_ACK]
+            }
+            KBD_CMD_ECHO => vec![KBD_ECHO],
+            KBD_CMD_IDENTIFY => vec![KBD_ACK, KBD_ID[0], KBD_ID[1]],
+            KBD_CMD_ENABLE_SCANNING => {
+                self.reporting = true;
+                vec![KBD_ACK]
+            }
+            KBD_CMD_DISABLE_SCANNING => {
+                self.reporting = false;
+                vec![KBD_ACK]
+            }
+            KBD_CMD_SET_DEFAULTS => {
+                self.kbd_typematic = DEFAULT_KBD_TYPEMATIC;
+                vec![KBD_ACK]
+            }
+            KBD_CMD_RESET => {
+                self.reporting = true;
+                self.kbd_typematic = DEFAULT_KBD_TYPEMATIC;
+                self.set_kbd_leds(0);
+                vec![KBD_ACK, KBD_BAT_OK]
+            }
+            _ => {
+                guest_warn!("Unknown PL050 keyboard command {:#x}", value);
+                self.events.unknown_command();
+                vec![KBD_RESEND]
+            }
+        }
+    }
+
+    fn reset_mouse(&mut self) {
+        self.reporting = false;
+        self.mouse_resolution = DEFAULT_MOUSE_RESOLUTION;
+        self.mouse_sample_rate = DEFAULT_MOUSE_SAMPLE_RATE;
+        self.mouse_scaling_2_1 = false;
+    }
+
+    // Handles a byte sent by the driver to the mouse, and returns the
+    // response.
+    fn write_mouse(&mut self, value: u8) -> Vec<u8> {
+        match self.pending_command.take() {
+            Some(MOUSE_CMD_SET_RESOLUTION) => {
+                self.mouse_resolution = value & 0x03;
+                return vec![KBD_ACK];
+            }
+            Some(MOUSE_CMD_SET_SAMPLE_RATE) => {
+                self.mouse_sample_rate = value;
+                return vec![KBD_ACK];
+            }
+            _ => {}
+        }
+
+        match value {
+            MOUSE_CMD_SET_RESOLUTION | MOUSE_CMD_SET_SAMPLE_RATE => {
+                self.pending_command = Some(value);
+                vec![KBD_ACK]
+            }
+            MOUSE_CMD_STATUS_REQUEST => {
+                let mut status = 0;
+                if self.reporting {
+                    status |= MOUSE_STATUS_REPORTING;
+                }
+                if self.mouse_scaling_2_1 {
+                    status |= MOUSE_STATUS_SCALING_2_1;
+                }
+                vec![
+                    KBD_ACK,
+                    status,
+                    self.mouse_resolution,
+                    self.mouse_sample_rate,
+                ]
+            }
+            MOUSE_CMD_SET_SCALING_1_1 | MOUSE_CMD_SET_SCALING_2_1 => {
+                self.mouse_scaling_2_1 = value == MOUSE_CMD_SET_SCALING_2_1;
+                vec![KBD_ACK]
+            }
+            MOUSE_CMD_GET_ID => vec![KBD_ACK, MOUSE_ID],
+            MOUSE_CMD_ENABLE_REPORTING => {
+                self.reporting = true;
+                vec![KBD_ACK]
+            }
+            MOUSE_CMD_DISABLE_REPORTING => {
+                self.reporting = false;
+                vec![KBD_ACK]
+            }
+            MOUSE_CMD_SET_DEFAULTS => {
+                self.reset_mouse();
+                vec![KBD_ACK]
+            }
+            MOUSE_CMD_RESET => {
+                self.reset_mouse();
+                vec![KBD_ACK, KBD_BAT_OK, MOUSE_ID]
+            }
+            // The remote and wrap modes, and reading the data on request,
+            // aren't supported, but acknowledged.
+            0xEA..=0xF0 => vec![KBD_ACK],
+            _ => {
+                guest_warn!("Unknown PL050 mouse command {:#x}", value);
+                self.events.unknown_command();
+                vec![KBD_RESEND]
+            }
+        }
+    }
+
+    fn status(&self) -> u32 {
+        let mut status = STAT_KMID | STAT_KMIC | STAT_TXEMPTY;
+        if !self.rx_buffer.is_empty() {
+            status |= STAT_RXFULL;
+        }
+        status
+    }
+
+    fn interrupt_status(&self) -> u32 {
+        let mut status = 0;
+        if !self.rx_buffer.is_empty() && self.rx_interrupt_enabled() {
+            status |= IR_RXINTR;
+        }
+        if (self.cr & CR_TXINTREN) != 0 {
+            status |= IR_TXINTR;
+        }
+        status
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The little endian, 4 byte array to write to the register.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Pl050`](struct.Pl050.html#example).
+    pub fn write(&mut self, offset: u16, data: &[u8; 4]) -> Result<(), T::E> {
+        let val = u32::from_le_bytes(*data);
+
+        match offset {
+            KMICR => {
+                let old_cr = self.cr;
+                self.cr = val & CR_MASK;
+                // Newly enabled interrupts are raised for the pending data,
+                // and, for the transmitter, right away.
+                let enabled = self.cr & !old_cr;
+                if ((enabled & CR_RXINTREN) != 0 && !self.rx_buffer.is_empty())
+                    || (enabled & CR_TXINTREN) != 0
+                {
+                    return self.interrupt_evt.trigger();
+                }
+            }
+            KMIDATA => {
+                let response = match self.device {
+                    Ps2Device::Keyboard => self.write_kbd(val as u8),
+                    Ps2Device::Mouse => self.write_mouse(val as u8),
+                };
+                self.push_rx(&response)?;
+            }
+            KMICLKDIV => self.clkdiv = val & CLKDIV_MASK,
+            // The other registers are read-only.
+            _ => guest_warn!("Invalid PL050 write at offset {:#x}", offset),
+        }
+        Ok(())
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// Reads from the data register return the oldest byte from the receive
+    /// buffer, or the last byte read when it is empty. Invalid offsets read
+    /// as 0.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The little-endian, 4 byte array storing the read value.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Pl050`](struct.Pl050.html#example).
+    pub fn read(&mut self, offset: u16, data: &mut [u8; 4]) {
+        let v = if (AMBA_ID_LOW..=AMBA_ID_HIGH).contains(&offset) {
+            let index = ((offset - AMBA_ID_LOW) >> 2) as usize;
+            u32::from(AMBA_IDS[index])
+        } else {
+            match offset {
+                KMICR => self.cr,
+                KMISTAT => self.status(),
+                KMIDATA => u32::from(self.pop_rx()),
+                KMICLKDIV => self.clkdiv,
+                KMIIR => self.interrupt_status(),
+                _ => {
+                    guest_warn!("Invalid PL050 read at offset {:#x}", offset);
+                    0
+                }
+            }
+        };
+
+        *data = v.to_le_bytes();
+    }
+}
+
+impl<T: Trigger, EV: Pl050Events> LegacyDevice for Pl050<T, EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match <&mut [u8; 4]>::try_from(&mut *data) {
+            Ok(data) => Pl050::read(self, offset, data),
+            Err(_) => {
+                guest_warn!(
+                    "Ignoring a {}-byte access at offset {:#x}",
+                    data.len(),
+                    offset
+                );
+                data.fill(0)
+            }
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Ok(data) = <&[u8; 4]>::try_from(data) {
+            let _ = Pl050::write(self, offset, data);
+        } else {
+            guest_warn!(
+                "Ignoring a {}-byte access at offset {:#x}",
+                data.len(),
+                offset
+            );
+        }
+    }
+
+    // The bytes waiting in the receive buffer are dropped.
+    fn reset(&mut self) {
+        self.cr = 0;
+        self.clkdiv = 0;
+        self.rx_buffer.clear();
+        self.last_rx = 0;
+        self.pending_command = None;
+        self.kbd_leds = 0;
+        self.kbd_typematic = DEFAULT_KBD_TYPEMATIC;
+        self.reset_mouse();
+        self.reporting = self.device == Ps2Device::Keyboard;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i8042::KeyCode;
+    use std::io;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use vmm_sys_util::eventfd::EventFd;
+    use vmm_sys_util::metric::Metric;
+
+    #[derive(Default)]
+    struct ExamplePl050Events {
+        buffer_overflows: AtomicU64,
+        unknown_commands: AtomicU64,
+        leds: AtomicU64,
+    }
+
+    impl Pl050Events for ExamplePl050Events {
+        fn buffer_overflow(&self, dropped: usize) {
+            self.buffer_overflows.add(dropped as u64);
+        }
+
+        fn unknown_command(&self) {
+            self.unknown_commands.inc();
+        }
+
+        fn leds_changed(&self, leds: u8) {
+            self.leds.store(u64::from(leds), Ordering::Relaxed);
+        }
+    }
+
+    // Enables the interface.
+    const CR_KMIEN: u32 = 1 << 2;
+
+    type TestPl050 = Pl050<EventFd, Arc<ExamplePl050Events>>;
+
+    fn new_pl050(device: Ps2Device) -> TestPl050 {
+        let mut kmi = Pl050::with_events(
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            device,
+            Arc::new(ExamplePl050Events::default()),
+        );
+        write_reg(&mut kmi, KMICR, CR_RXINTREN | CR_KMIEN);
+        kmi
+    }
+
+    fn evt_count(evt: &EventFd) -> u64 {
+        match evt.read() {
+            Ok(count) => count,
+            Err(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+                0
+            }
+        }
+    }
+
+    fn read_reg(kmi: &mut TestPl050, offset: u16) -> u32 {
+        let mut data = [0; 4];
+        kmi.read(offset, &mut data);
+        u32::from_le_bytes(data)
+    }
+
+    fn write_reg(kmi: &mut TestPl050, offset: u16, value: u32) {
+        kmi.write(offset, &value.to_le_bytes()).unwrap();
+    }
+
+    fn read_all(kmi: &mut TestPl050) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        while (read_reg(kmi, KMISTAT) & STAT_RXFULL) != 0 {
+            bytes.push(read_reg(kmi, KMIDATA) as u8);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_pl050_registers() {
+        let mut kmi = new_pl050(Ps2Device::Keyboard);
+        assert_eq!(kmi.device(), Ps2Device::Keyboard);
+        assert_eq!(read_reg(&mut kmi, KMICR), CR_RXINTREN | CR_KMIEN);
+        assert_eq!(
+            read_reg(&mut kmi, KMISTAT),
+            STAT_KMID | STAT_KMIC | STAT_TXEMPTY
+        );
+        assert_eq!(read_reg(&mut kmi, KMIIR), 0);
+
+        write_reg(&mut kmi, KMICLKDIV, 0xFF);
+        assert_eq!(read_reg(&mut kmi, KMICLKDIV), 0x0F);
+
+        for (i, &id) in AMBA_IDS.iter().enumerate() {
+            assert_eq!(
+                read_reg(&mut kmi, AMBA_ID_LOW + 4 * i as u16),
+                u32::from(id)
+            );
+        }
+        write_reg(&mut kmi, AMBA_ID_LOW, 0);
+        assert_eq!(read_reg(&mut kmi, AMBA_ID_LOW), 0x50);
+        assert_eq!(read_reg(&mut kmi, 0x100), 0);
+
+        // The transmitter is always empty, so its interrupt is raised as soon
+        // as it is enabled.
+        assert_eq!(evt_count(kmi.interrupt_evt()), 0);
+        write_reg(&mut kmi, KMICR, CR_RXINTREN | CR_TXINTREN | CR_KMIEN);
+        assert_eq!(evt_count(kmi.interrupt_evt()), 1);
+        assert_eq!(read_reg(&mut kmi, KMIIR), IR_TXINTR);
+    }
+
+    #[test]
+    fn test_pl050_keyboard() {
+        let mut kmi = new_pl050(Ps2Device::Keyboard);
+
+        // Reset the keyboard.
+        write_reg(&mut kmi, KMIDATA, u32::from(KBD_CMD_RESET));
+        assert_eq!(evt_count(kmi.interrupt_evt()), 1);
+        assert_eq!(read_reg(&mut kmi, KMIIR), IR_RXINTR);
+        assert_eq!(read_all(&mut kmi), vec![KBD_ACK, KBD_BAT_OK]);
+        // The interrupt was raised again for the second byte.
+        assert_eq!(evt_count(kmi.interrupt_evt()), 1);
+        // The last byte is read again when the buffer is empty.
+        assert_eq!(read_reg(&mut kmi, KMIDATA), u32::from(KBD_BAT_OK));
+
+        write_reg(&mut kmi, KMIDATA, u32::from(KBD_CMD_IDENTIFY));
+        assert_eq!(read_all(&mut kmi), vec![KBD_ACK, KBD_ID[0], KBD_ID[1]]);
+        write_reg(&mut kmi, KMIDATA, u32::from(KBD_CMD_SET_LEDS));
+        write_reg(&mut kmi, KMIDATA, 0x07);
+        assert_eq!(read_all(&mut kmi), vec![KBD_ACK, KBD_ACK]);
+        assert_eq!(kmi.events().leds.load(Ordering::Relaxed), 0x07);
+        write_reg(&mut kmi, KMIDATA, 0x00);
+        assert_eq!(read_all(&mut kmi), vec![KBD_RESEND]);
+        assert_eq!(kmi.events().unknown_commands.count(), 1);
+
+        // Key events are sent only while scanning.
+        let event = KeyEvent {
+            key: KeyCode::ArrowUp,
+            pressed: false,
+        };
+        kmi.send_key_event(event).unwrap();
+        assert_eq!(read_all(&mut kmi), vec![0xE0, 0xF0, 0x75]);
+        write_reg(&mut kmi, KMIDATA, u32::from(KBD_CMD_DISABLE_SCANNING));
+        assert_eq!(read_all(&mut kmi), vec![KBD_ACK]);
+        assert!(!kmi.is_reporting());
+        kmi.send_key_event(event).unwrap();
+        assert_eq!(kmi.enqueue_bytes(&[0x1C]).unwrap(), 0);
+        assert!(read_all(&mut kmi).is_empty());
+    }
+
+    #[test]
+    fn test_pl050_buffer() {
+        let mut kmi = new_pl050(Ps2Device::Keyboard);
+
+        assert_eq!(
+            kmi.enqueue_bytes(&[0x1C; BUFFER_SIZE + 2]).unwrap(),
+            BUFFER_SIZE
+        );
+        assert_eq!(kmi.events().buffer_overflows.count(), 2);
+        let event = KeyEvent {
+            key: KeyCode::KeyA,
+            pressed: true,
+        };
+        assert!(matches!(kmi.send_key_event(event), Err(Error::FullBuffer)));
+        assert_eq!(read_all(&mut kmi).len(), BUFFER_SIZE);
+        // One interrupt for the first byte, and one for each following one.
+        assert_eq!(evt_count(kmi.interrupt_evt()), BUFFER_SIZE as u64);
+
+        // The pending data raises the interrupt once it is enabled.
+        write_reg(&mut kmi, KMICR, CR_KMIEN);
+        kmi.send_key_event(event).unwrap();
+        assert_eq!(evt_count(kmi.interrupt_evt()), 0);
+        write_reg(&mut kmi, KMICR, CR_RXINTREN | CR_KMIEN);
+        assert_eq!(evt_count(kmi.interrupt_evt()), 1);
+    }
+
+    #[test]
+    fn test_pl050_mouse() {
+        let mut kmi = new_pl050(Ps2Device::Mouse);
+        let event = KeyEvent {
+            key: KeyCode::KeyA,
+            pressed: true,
+        };
+        assert!(matches!(kmi.send_key_event(event), Err(Error::WrongDevice)));
+
+        // The movements are reported only once enabled.
+        assert!(!kmi.is_reporting());
+        assert_eq!(kmi.enqueue_bytes(&[0x08, 0x01, 0x01]).unwrap(), 0);
+
+        write_reg(&mut kmi, KMIDATA, u32::from(MOUSE_CMD_RESET));
+        assert_eq!(read_all(&mut kmi), vec![KBD_ACK, KBD_BAT_OK, MOUSE_ID]);
+        write_reg(&mut kmi, KMIDATA, u32::from(MOUSE_CMD_GET_ID));
+        assert_eq!(read_all(&mut kmi), vec![KBD_ACK, MOUSE_ID]);
+        write_reg(&mut kmi, KMIDATA, u32::from(MOUSE_CMD_SET_SAMPLE_RATE));
+        write_reg(&mut kmi, KMIDATA, 200);
+        write_reg(&mut kmi, KMIDATA, u32::from(MOUSE_CMD_SET_RESOLUTION));
+        write_reg(&mut kmi, KMIDATA, 3);
+        write_reg(&mut kmi, KMIDATA, u32::from(MOUSE_CMD_SET_SCALING_2_1));
+        write_reg(&mut kmi, KMIDATA, u32::from(MOUSE_CMD_ENABLE_REPORTING));
+        assert_eq!(read_all(&mut kmi), vec![KBD_ACK; 6]);
+        write_reg(&mut kmi, KMIDATA, u32::from(MOUSE_CMD_STATUS_REQUEST));
+        assert_eq!(
+            read_all(&mut kmi),
+            vec![
+                KBD_ACK,
+                MOUSE_STATUS_REPORTING | MOUSE_STATUS_SCALING_2_1,
+                3,
+                200
+            ]
+        );
+
+        assert_eq!(kmi.enqueue_bytes(&[0x08, 0x01, 0x01]).unwrap(), 3);
+        assert_eq!(read_all(&mut kmi), vec![0x08, 0x01, 0x01]);
+
+        write_reg(&mut kmi, KMIDATA, u32::from(MOUSE_CMD_SET_DEFAULTS));
+        write_reg(&mut kmi, KMIDATA, u32::from(MOUSE_CMD_STATUS_REQUEST));
+        assert_eq!(
+            read_all(&mut kmi),
+            vec![
+                KBD_ACK,
+                KBD_ACK,
+                0,
+                DEFAULT_MOUSE_RESOLUTION,
+                DEFAULT_MOUSE_SAMPLE_RATE
+            ]
+        );
+        write_reg(&mut kmi, KMIDATA, 0x00);
+        assert_eq!(read_all(&mut kmi), vec![KBD_RESEND]);
+        assert_eq!(kmi.events().unknown_commands.count(), 1);
+    }
+
+    #[test]
+    fn test_pl050_legacy_device() {
+        let mut kmi = new_pl050(Ps2Device::Mouse);
+
+        LegacyDevice::write(&mut kmi, KMICLKDIV, &0x0Fu32.to_le_bytes());
+        LegacyDevice::write(
+            &mut kmi,
+            KMIDATA,
+            &u32::from(MOUSE_CMD_ENABLE_REPORTING).to_le_bytes(),
+        );
+        assert!(kmi.is_reporting());
+        let mut data = [0; 4];
+        LegacyDevice::read(&mut kmi, KMICLKDIV, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 0x0F);
+        let mut data = [0xFF; 2];
+        LegacyDevice::read(&mut kmi, KMICLKDIV, &mut data);
+        assert_eq!(data, [0; 2]);
+
+        // The reset drops the pending response, and disables the mouse.
+        kmi.reset();
+        assert!(!kmi.is_reporting());
+        assert_eq!(read_reg(&mut kmi, KMICLKDIV), 0);
+        assert_eq!(read_reg(&mut kmi, KMISTAT) & STAT_RXFULL, 0);
+    }
+}
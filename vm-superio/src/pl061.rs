@@ -0,0 +1,541 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the ARM PL061 General Purpose Input/Output (GPIO)
+//! controller.
+//!
+//! The PL061 exposes 8 pins, which the guest configures as inputs or outputs,
+//! with an interrupt on the level or on the edges of the inputs. Microvms
+//! without ACPI use it for the power button: the device tree describes a
+//! `gpio-keys` node bound to one of the input pins, which the VMM drives for
+//! requesting a graceful shutdown.
+
+use alloc::sync::Arc;
+use core::convert::TryFrom;
+use core::result::Result;
+
+use crate::{LegacyDevice, Trigger};
+
+// The following defines are mapping to the specification:
+// https://developer.arm.com/documentation/ddi0190/b/programmer-s-model/summary-of-primecell-gpio-registers
+//
+// The data register spans from 0x000 to 0x3FC: bits 9-2 of the address mask
+// the pins which are read or written.
+const GPIODATA_HIGH: u16 = 0x3FC;
+const GPIODIR: u16 = 0x400; // Data Direction Register.
+const GPIOIS: u16 = 0x404; // Interrupt Sense Register.
+const GPIOIBE: u16 = 0x408; // Interrupt Both Edges Register.
+const GPIOIEV: u16 = 0x40C; // Interrupt Event Register.
+const GPIOIE: u16 = 0x410; // Interrupt Mask Register.
+const GPIORIS: u16 = 0x414; // Raw Interrupt Status Register (RO).
+const GPIOMIS: u16 = 0x418; // Masked Interrupt Status Register (RO).
+const GPIOIC: u16 = 0x41C; // Interrupt Clear Register (WO).
+const GPIOAFSEL: u16 = 0x420; // Mode Control Select Register.
+
+// From 0xFE0 to 0xFFF => Peripheral and PrimeCell Identification Registers.
+const AMBA_IDS: [u8; 8] = [0x61, 0x10, 0x04, 0x00, 0x0d, 0xf0, 0x05, 0xb1];
+const AMBA_ID_LOW: u16 = 0xFE0;
+const AMBA_ID_HIGH: u16 = 0xFFF;
+
+/// The number of pins of the controller.
+pub const PL061_PINS: u8 = 8;
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the PL061 emulation logic. The methods below can be implemented by a
+/// backend that keeps track of such events by driving other devices, logging messages, or
+/// any other action.
+pub trait Pl061Events {
+    /// The level of the output `pin` changed to `level`, either because the
+    /// guest wrote it, or because the guest configured the pin as an output.
+    fn output_changed(&self, pin: u8, level: bool);
+}
+
+/// Provides a no-op implementation of `Pl061Events` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `Pl061Events`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl Pl061Events for NoEvents {
+    fn output_changed(&self, _pin: u8, _level: bool) {}
+}
+
+impl<EV: Pl061Events> Pl061Events for Arc<EV> {
+    fn output_changed(&self, pin: u8, level: bool) {
+        self.as_ref().output_changed(pin, level);
+    }
+}
+
+/// An ARM PL061 GPIO controller.
+///
+/// The VMM drives the input pins with [`set_input`](#method.set_input), while
+/// the changes of the output pins are reported through
+/// [`Pl061Events`](trait.Pl061Events.html). The combined interrupt of the
+/// pins is raised through a [`Trigger`](../trait.Trigger.html) object.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::pl061::Pl061;
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut gpio = Pl061::new(DummyTrigger);
+///
+/// // The guest enables the interrupt on the rising edge of pin 3.
+/// gpio.write(0x40C, &0x08u32.to_le_bytes()).unwrap();
+/// gpio.write(0x410, &0x08u32.to_le_bytes()).unwrap();
+///
+/// // Press the power button.
+/// gpio.set_input(3, true).unwrap();
+/// assert!(gpio.interrupt_pending());
+/// gpio.set_input(3, false).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Pl061<T: Trigger, EV: Pl061Events> {
+    // GPIO interrupt object.
+    interrupt_evt: T,
+    events: EV,
+    // The levels written by the guest for the output pins.
+    data: u8,
+    // The levels driven by the VMM on the input pins.
+    inputs: u8,
+    dir: u8,
+    is: u8,
+    ibe: u8,
+    iev: u8,
+    ie: u8,
+    // The edge interrupts that were detected, and not cleared yet.
+    edge_ris: u8,
+    afsel: u8,
+    // The level of the combined interrupt.
+    interrupt: bool,
+}
+
+impl<T: Trigger> Pl061<T, NoEvents> {
+    /// Creates a new `Pl061` instance, with all the pins configured as low
+    /// inputs, which will signal `interrupt_evt` when an enabled interrupt
+    /// occurs.
+    ///
+    /// # Arguments
+    /// * `interrupt_evt` - A Trigger object that will be used to raise the
+    ///   GPIO interrupt.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Pl061`](struct.Pl061.html#example).
+    pub const fn new(interrupt_evt: T) -> Self {
+        Self::with_events(interrupt_evt, NoEvents)
+    }
+}
+
+impl<T: Trigger, EV: Pl061Events> Pl061<T, EV> {
+    /// Creates a new `Pl061` instance, with all the pins configured as low
+    /// inputs, which will signal `interrupt_evt` when an enabled interrupt
+    /// occurs, and invokes the `pl061_evts` implementation of `Pl061Events`
+    /// during operation.
+    ///
+    /// # Arguments
+    /// * `interrupt_evt` - A Trigger object that will be used to raise the
+    ///   GPIO interrupt.
+    /// * `pl061_evts` - The `Pl061Events` implementation used to track the
+    ///   output pins.
+    pub const fn with_events(interrupt_evt: T, pl061_evts: EV) -> Self {
+        Pl061 {
+            interrupt_evt,
+            events: pl061_evts,
+            data: 0,
+            inputs: 0,
+            dir: 0,
+            is: 0,
+            ibe: 0,
+            iev: 0,
+            ie: 0,
+            edge_ris: 0,
+            afsel: 0,
+            interrupt: false,
+        }
+    }
+
+    /// Provides a reference to the interrupt event object.
+    pub fn interrupt_evt(&self) -> &T {
+        &self.interrupt_evt
+    }
+
+    /// Provides a reference to the PL061 events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Returns whether the GPIO interrupt is asserted. The interrupt is
+    /// level triggered, so the VMM can use this for keeping the line high
+    /// until the guest handles it.
+    pub fn interrupt_pending(&self) -> bool {
+        self.interrupt
+    }
+
+    // Returns the levels of the pins, i.e. the ones written by the guest for
+    // the outputs, and the ones driven by the VMM for the inputs.
+    fn pins(&self) -> u8 {
+        (self.data & self.dir) | (self.inputs & !self.dir)
+    }
+
+    /// Returns the level of `pin`, whether it is an input or an output.
+    ///
+    /// # Arguments
+    /// * `pin` - The pin number, between 0 and 7. Other pins read as low.
+    pub fn pin(&self, pin: u8) -> bool {
+        pin < PL061_PINS && (self.pins() & (1 << pin)) != 0
+    }
+
+    // Returns the raw interrupt status, i.e. the latched edge interrupts,
+    // and the level interrupts of the inputs at their active level.
+    fn ris(&self) -> u8 {
+        let active = !(self.pins() ^ self.iev);
+        (self.edge_ris & !self.is) | (active & self.is & !self.dir)
+    }
+
+    // Updates the level of the interrupt, and signals its rising edges.
+    fn update_interrupt(&mut self) -> Result<(), T::E> {
+        let interrupt = (self.ris() & self.ie) != 0;
+        let raised = interrupt && !self.interrupt;
+        self.interrupt = interrupt;
+        if raised {
+            return self.interrupt_evt.trigger();
+        }
+        Ok(())
+    }
+
+    // Latches the edge interrupts of the input pins that changed from
+    // `old_pins`, reports the output pins that changed, and updates the
+    // interrupt.
+    fn pins_changed(&mut self, old_pins: u8) -> Result<(), T::E> {
+        let pins = self.pins();
+        let changed = old_pins ^ pins;
+        let inputs = !self.dir & !self.is;
+        let rising = changed & pins;
+        let falling = changed & !pins;
+        // An edge interrupt is detected on both edges, or on the one given by
+        // the event register.
+        let edges = (changed & self.ibe) | (rising & self.iev) | (falling & !self.iev);
+        self.edge_ris |= edges & inputs;
+
+        for pin in 0..PL061_PINS {
+            if (changed & self.dir & (1 << pin)) != 0 {
+                self.events.output_changed(pin, (pins & (1 << pin)) != 0);
+            }
+        }
+        self.update_interrupt()
+    }
+
+    /// Sets the level of the input `pin`, e.g. for pressing or releasing a
+    /// `gpio-keys` button. The level is kept when the pin is configured as
+    /// an output, and used again when it is configured as an input.
+    ///
+    /// # Arguments
+    /// * `pin` - The pin number, between 0 and 7. Other pins are ignored.
+    /// * `level` - Whether the pin is high.
+    pub fn set_input(&mut self, pin: u8, level: bool) -> Result<(), T::E> {
+        if pin >= PL061_PINS {
+            return Ok(());
+        }
+
+        let old_pins = self.pins();
+        if level {
+            self.inputs |= 1 << pin;
+        } else {
+            self.inputs &= !(1 << pin);
+        }
+        self.pins_changed(old_pins)
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The little endian, 4 byte array to write to the register.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Pl061`](struct.Pl061.html#example).
+    pub fn write(&mut self, offset: u16, data: &[u8; 4]) -> Result<(), T::E> {
+        let val = data[0];
+        let old_pins = self.pins();
+
+        match offset {
+            0..=GPIODATA_HIGH => {
+                // Only the pins selected by the address are written.
+                let mask = (offset >> 2) as u8;
+                self.data = (self.data & !mask) | (val & mask);
+            }
+            GPIODIR => self.dir = val,
+            GPIOIS => self.is = val,
+            GPIOIBE => self.ibe = val,
+            GPIOIEV => self.iev = val,
+            GPIOIE => self.ie = val,
+            GPIOIC => self.edge_ris &= !val,
+            GPIOAFSEL => self.afsel = val,
+            // The other registers are read-only.
+            _ => {
+                guest_warn!("Invalid PL061 write at offset {:#x}", offset);
+                return Ok(());
+            }
+        }
+        self.pins_changed(old_pins)
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// Invalid offsets, and the write only interrupt clear register, read as
+    /// 0.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The little-endian, 4 byte array storing the read value.
+    pub fn read(&self, offset: u16, data: &mut [u8; 4]) {
+        let v = if (AMBA_ID_LOW..=AMBA_ID_HIGH).contains(&offset) {
+            let index = ((offset - AMBA_ID_LOW) >> 2) as usize;
+            AMBA_IDS[index]
+        } else {
+            match offset {
+                0..=GPIODATA_HIGH => self.pins() & (offset >> 2) as u8,
+                GPIODIR => self.dir,
+                GPIOIS => self.is,
+                GPIOIBE => self.ibe,
+                GPIOIEV => self.iev,
+                GPIOIE => self.ie,
+                GPIORIS => self.ris(),
+                GPIOMIS => self.ris() & self.ie,
+                GPIOAFSEL => self.afsel,
+                GPIOIC => 0,
+                _ => {
+                    guest_warn!("Invalid PL061 read at offset {:#x}", offset);
+                    0
+                }
+            }
+        };
+
+        *data = u32::from(v).to_le_bytes();
+    }
+}
+
+impl<T: Trigger, EV: Pl061Events> LegacyDevice for Pl061<T, EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match <&mut [u8; 4]>::try_from(&mut *data) {
+            Ok(data) => Pl061::read(self, offset, data),
+            Err(_) => {
+                guest_warn!(
+                    "Ignoring a {}-byte access at offset {:#x}",
+                    data.len(),
+                    offset
+                );
+                data.fill(0)
+            }
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Ok(data) = <&[u8; 4]>::try_from(data) {
+            let _ = Pl061::write(self, offset, data);
+        } else {
+            guest_warn!(
+                "Ignoring a {}-byte access at offset {:#x}",
+                data.len(),
+                offset
+            );
+        }
+    }
+
+    // All the pins become inputs again, whose levels are driven by the VMM.
+    fn reset(&mut self) {
+        self.data = 0;
+        self.dir = 0;
+        self.is = 0;
+        self.ibe = 0;
+        self.iev = 0;
+        self.ie = 0;
+        self.edge_ris = 0;
+        self.afsel = 0;
+        self.interrupt = false;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::Mutex;
+    use vmm_sys_util::eventfd::EventFd;
+
+    #[derive(Default)]
+    struct ExamplePl061Events {
+        outputs: Mutex<Vec<(u8, bool)>>,
+    }
+
+    impl Pl061Events for ExamplePl061Events {
+        fn output_changed(&self, pin: u8, level: bool) {
+            self.outputs.lock().unwrap().push((pin, level));
+        }
+    }
+
+    type TestPl061 = Pl061<EventFd, Arc<ExamplePl061Events>>;
+
+    fn new_pl061() -> TestPl061 {
+        Pl061::with_events(
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            Arc::new(ExamplePl061Events::default()),
+        )
+    }
+
+    fn evt_count(evt: &EventFd) -> u64 {
+        match evt.read() {
+            Ok(count) => count,
+            Err(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+                0
+            }
+        }
+    }
+
+    fn read_reg(gpio: &TestPl061, offset: u16) -> u32 {
+        let mut data = [0; 4];
+        gpio.read(offset, &mut data);
+        u32::from_le_bytes(data)
+    }
+
+    fn write_reg(gpio: &mut TestPl061, offset: u16, value: u32) {
+        gpio.write(offset, &value.to_le_bytes()).unwrap();
+    }
+
+    // Returns the offset of the data register accessing the pins in `mask`.
+    fn data_offset(mask: u8) -> u16 {
+        u16::from(mask) << 2
+    }
+
+    #[test]
+    fn test_pl061_data() {
+        let mut gpio = new_pl061();
+
+        // Pins 0-3 are outputs, and only the pins selected by the address
+        // are written.
+        write_reg(&mut gpio, GPIODIR, 0x0F);
+        write_reg(&mut gpio, data_offset(0x03), 0xFF);
+        assert_eq!(read_reg(&gpio, data_offset(0xFF)), 0x03);
+        assert_eq!(read_reg(&gpio, data_offset(0x02)), 0x02);
+        assert_eq!(
+            *gpio.events().outputs.lock().unwrap(),
+            vec![(0, true), (1, true)]
+        );
+
+        // The inputs are driven by the VMM, and the guest can't write them.
+        gpio.set_input(7, true).unwrap();
+        gpio.set_input(1, false).unwrap();
+        gpio.set_input(PL061_PINS, true).unwrap();
+        write_reg(&mut gpio, data_offset(0xF0), 0x00);
+        assert_eq!(read_reg(&gpio, data_offset(0xFF)), 0x83);
+        assert!(gpio.pin(7));
+        assert!(gpio.pin(1));
+        assert!(!gpio.pin(PL061_PINS));
+
+        // Configuring an output as an input uses the level of the input.
+        write_reg(&mut gpio, GPIODIR, 0x0E);
+        assert_eq!(read_reg(&gpio, data_offset(0xFF)), 0x82);
+        assert_eq!(gpio.events().outputs.lock().unwrap().len(), 2);
+
+        for (i, &id) in AMBA_IDS.iter().enumerate() {
+            assert_eq!(read_reg(&gpio, AMBA_ID_LOW + 4 * i as u16), u32::from(id));
+        }
+        write_reg(&mut gpio, GPIOAFSEL, 0x10);
+        assert_eq!(read_reg(&gpio, GPIOAFSEL), 0x10);
+        assert_eq!(read_reg(&gpio, GPIOIC), 0);
+        assert_eq!(read_reg(&gpio, 0x800), 0);
+    }
+
+    #[test]
+    fn test_pl061_edge_interrupts() {
+        let mut gpio = new_pl061();
+
+        // Pin 3 interrupts on its rising edge, and pin 4 on both edges.
+        write_reg(&mut gpio, GPIOIEV, 0x08);
+        write_reg(&mut gpio, GPIOIBE, 0x10);
+        write_reg(&mut gpio, GPIOIE, 0x18);
+
+        gpio.set_input(3, true).unwrap();
+        assert!(gpio.interrupt_pending());
+        assert_eq!(evt_count(gpio.interrupt_evt()), 1);
+        assert_eq!(read_reg(&gpio, GPIOMIS), 0x08);
+        // The interrupt stays latched after the falling edge.
+        gpio.set_input(3, false).unwrap();
+        assert_eq!(read_reg(&gpio, GPIORIS), 0x08);
+        write_reg(&mut gpio, GPIOIC, 0x08);
+        assert!(!gpio.interrupt_pending());
+
+        gpio.set_input(4, true).unwrap();
+        write_reg(&mut gpio, GPIOIC, 0x10);
+        gpio.set_input(4, false).unwrap();
+        assert_eq!(read_reg(&gpio, GPIOMIS), 0x10);
+        assert_eq!(evt_count(gpio.interrupt_evt()), 2);
+        write_reg(&mut gpio, GPIOIC, 0xFF);
+
+        // The masked interrupts are still latched.
+        write_reg(&mut gpio, GPIOIE, 0x00);
+        gpio.set_input(3, true).unwrap();
+        assert_eq!(read_reg(&gpio, GPIORIS), 0x08);
+        assert_eq!(read_reg(&gpio, GPIOMIS), 0x00);
+        assert!(!gpio.interrupt_pending());
+        write_reg(&mut gpio, GPIOIE, 0x08);
+        assert!(gpio.interrupt_pending());
+        assert_eq!(evt_count(gpio.interrupt_evt()), 1);
+    }
+
+    #[test]
+    fn test_pl061_level_interrupts() {
+        let mut gpio = new_pl061();
+
+        // Pin 5 interrupts while it is low.
+        write_reg(&mut gpio, GPIOIS, 0x20);
+        gpio.set_input(5, true).unwrap();
+        write_reg(&mut gpio, GPIOIE, 0x20);
+        assert!(!gpio.interrupt_pending());
+
+        gpio.set_input(5, false).unwrap();
+        assert!(gpio.interrupt_pending());
+        assert_eq!(evt_count(gpio.interrupt_evt()), 1);
+        // Clearing doesn't affect the level interrupts.
+        write_reg(&mut gpio, GPIOIC, 0x20);
+        assert!(gpio.interrupt_pending());
+        gpio.set_input(5, true).unwrap();
+        assert!(!gpio.interrupt_pending());
+        assert_eq!(read_reg(&gpio, GPIORIS), 0);
+    }
+
+    #[test]
+    fn test_pl061_legacy_device() {
+        let mut gpio = new_pl061();
+        gpio.set_input(7, true).unwrap();
+
+        LegacyDevice::write(&mut gpio, GPIODIR, &0x0Fu32.to_le_bytes());
+        LegacyDevice::write(&mut gpio, data_offset(0x01), &0x01u32.to_le_bytes());
+        let mut data = [0; 4];
+        LegacyDevice::read(&mut gpio, data_offset(0xFF), &mut data);
+        assert_eq!(u32::from_le_bytes(data), 0x81);
+
+        gpio.reset();
+        assert_eq!(read_reg(&gpio, GPIODIR), 0);
+        assert_eq!(read_reg(&gpio, data_offset(0xFF)), 0x80);
+    }
+}
@@ -0,0 +1,521 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the ACPI PM1 event and control blocks.
+//!
+//! These fixed hardware registers let the guest enter the sleep states, e.g.
+//! shut down by entering S5, and let the VMM notify the guest about the power
+//! button presses, through the System Control Interrupt (SCI).
+
+use alloc::sync::Arc;
+use core::convert::TryFrom;
+use core::result::Result;
+
+use crate::{LegacyDevice, Trigger};
+
+// Offsets of the registers, relative to the start of the PM1a_EVT_BLK. The
+// event block holds the status and enable registers, and is directly
+// followed by the PM1a_CNT_BLK, which holds the control register. All the
+// registers are 16 bits wide.
+const STATUS_OFFSET: u8 = 0;
+const ENABLE_OFFSET: u8 = 2;
+const CONTROL_OFFSET: u8 = 4;
+
+// PM1 status and enable register bits. The status bits are cleared by
+// writing 1.
+// Power management timer overflow.
+const TMR_BIT: u16 = 1 << 0;
+// The BIOS wants the global lock.
+const GBL_BIT: u16 = 1 << 5;
+// Power button.
+const PWRBTN_BIT: u16 = 1 << 8;
+// Sleep button.
+const SLPBTN_BIT: u16 = 1 << 9;
+// RTC alarm.
+const RTC_BIT: u16 = 1 << 10;
+// The system woke up. Status only.
+const WAK_STS_BIT: u16 = 1 << 15;
+const STATUS_MASK: u16 = TMR_BIT | GBL_BIT | PWRBTN_BIT | SLPBTN_BIT | RTC_BIT | WAK_STS_BIT;
+const ENABLE_MASK: u16 = TMR_BIT | GBL_BIT | PWRBTN_BIT | SLPBTN_BIT | RTC_BIT;
+
+// PM1 control register bits.
+// The events raise the SCI, instead of the SMI.
+const SCI_EN_BIT: u16 = 1 << 0;
+// Bus master requests wake the CPU from C3.
+const BM_RLD_BIT: u16 = 1 << 1;
+// The type of the sleep state that is entered by setting SLP_EN.
+const SLP_TYP_SHIFT: u16 = 10;
+const SLP_TYP_MASK: u16 = 0b111;
+// Enters the sleep state. Write only.
+const SLP_EN_BIT: u16 = 1 << 13;
+// The bits that read back as written. GBL_RLS (bit 2), which releases the
+// global lock, is write only and ignored, since the global lock is not
+// emulated.
+const CONTROL_MASK: u16 = SCI_EN_BIT | BM_RLD_BIT | (SLP_TYP_MASK << SLP_TYP_SHIFT);
+
+// The SLP_TYP value of the S5 (soft off) state, which should be given to
+// the guest by the `_S5` object of the DSDT.
+const S5_SLP_TYP: u8 = 5;
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the ACPI PM1 emulation logic. The methods below can be implemented by a
+/// backend that keeps track of such events by suspending the VM, logging messages, or any
+/// other action.
+pub trait Pm1Events {
+    /// The guest requested to enter the sleep state with the given SLP_TYP,
+    /// as defined by the `_Sx` objects of the DSDT. This is invoked for the S5
+    /// state as well, besides triggering the shutdown event.
+    fn sleep_requested(&self, slp_typ: u8);
+}
+
+/// Provides a no-op implementation of `Pm1Events` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `Pm1Events`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl Pm1Events for NoEvents {
+    fn sleep_requested(&self, _slp_typ: u8) {}
+}
+
+impl<EV: Pm1Events> Pm1Events for Arc<EV> {
+    fn sleep_requested(&self, slp_typ: u8) {
+        self.as_ref().sleep_requested(slp_typ);
+    }
+}
+
+/// The ACPI PM1a event and control blocks, found at the ports given by the
+/// `PM1a_EVT_BLK` and `PM1a_CNT_BLK` fields of the FADT.
+///
+/// The offsets are relative to the start of the 4 bytes long event block,
+/// which must be directly followed by the 2 bytes long control block, i.e.
+/// the status register is at offset 0, the enable register at offset 2, and
+/// the control register at offset 4.
+///
+/// Two [`Trigger`](../trait.Trigger.html) objects are used: one for raising
+/// the System Control Interrupt (SCI) when an enabled event occurs, and one
+/// for notifying the VMM when the guest shuts down by entering the S5 state,
+/// whose SLP_TYP must be 5 in the `_S5` object of the DSDT. The device starts
+/// in ACPI mode, i.e. with SCI_EN set, as expected when the FADT doesn't
+/// describe an SMI command port. Otherwise, the VMM clears SCI_EN with
+/// [`set_sci_enabled`](#method.set_sci_enabled) before booting the guest,
+/// and the [`Apm`](../apm/struct.Apm.html) command port sets it again when the
+/// guest requests the ACPI mode.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::pm1::Pm1;
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut pm1 = Pm1::new(DummyTrigger, DummyTrigger);
+///
+/// // The guest enables the power button event.
+/// pm1.write(2, &0x0100u16.to_le_bytes()).unwrap();
+/// pm1.press_power_button().unwrap();
+/// assert!(pm1.sci_pending());
+///
+/// // The guest acknowledges the event, and then shuts down.
+/// pm1.write(0, &0x0100u16.to_le_bytes()).unwrap();
+/// assert!(!pm1.sci_pending());
+/// pm1.write(4, &0x3401u16.to_le_bytes()).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Pm1<T: Trigger, EV: Pm1Events> {
+    // System Control Interrupt.
+    sci_evt: T,
+    // S5 entry event object.
+    shutdown_evt: T,
+    events: EV,
+    status: u16,
+    enable: u16,
+    control: u16,
+    // The level of the SCI.
+    sci: bool,
+}
+
+impl<T: Trigger> Pm1<T, NoEvents> {
+    /// Creates a new `Pm1` instance, which will signal `sci_evt` when an
+    /// enabled event occurs, and `shutdown_evt` when the guest enters S5.
+    ///
+    /// # Arguments
+    /// * `sci_evt` - A Trigger object that will be used to raise the SCI.
+    /// * `shutdown_evt` - A Trigger object that will be used to notify the
+    ///   VMM about the shutdown of the guest.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Pm1`](struct.Pm1.html#example).
+    pub const fn new(sci_evt: T, shutdown_evt: T) -> Self {
+        Self::with_events(sci_evt, shutdown_evt, NoEvents)
+    }
+}
+
+impl<T: Trigger, EV: Pm1Events> Pm1<T, EV> {
+    /// Creates a new `Pm1` instance, which will signal `sci_evt` when an
+    /// enabled event occurs, and `shutdown_evt` when the guest enters S5,
+    /// and invokes the `pm1_evts` implementation of `Pm1Events` during
+    /// operation.
+    ///
+    /// # Arguments
+    /// * `sci_evt` - A Trigger object that will be used to raise the SCI.
+    /// * `shutdown_evt` - A Trigger object that will be used to notify the
+    ///   VMM about the shutdown of the guest.
+    /// * `pm1_evts` - The `Pm1Events` implementation used to track the
+    ///   sleep state requests of the guest.
+    pub const fn with_events(sci_evt: T, shutdown_evt: T, pm1_evts: EV) -> Self {
+        Pm1 {
+            sci_evt,
+            shutdown_evt,
+            events: pm1_evts,
+            status: 0,
+            enable: 0,
+            control: SCI_EN_BIT,
+            sci: false,
+        }
+    }
+
+    /// Provides a reference to the SCI event object.
+    pub fn sci_evt(&self) -> &T {
+        &self.sci_evt
+    }
+
+    /// Provides a reference to the shutdown event object.
+    pub fn shutdown_evt(&self) -> &T {
+        &self.shutdown_evt
+    }
+
+    /// Provides a reference to the PM1 events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Returns whether the SCI is asserted, i.e. whether an enabled event is
+    /// waiting to be handled by the guest. The SCI is level triggered, so
+    /// the VMM can use this for keeping the line high until the guest clears
+    /// the event.
+    pub fn sci_pending(&self) -> bool {
+        self.sci
+    }
+
+    /// Returns whether the events raise the SCI, i.e. whether SCI_EN is set.
+    pub fn sci_enabled(&self) -> bool {
+        (self.control & SCI_EN_BIT) != 0
+    }
+
+    /// Sets or clears SCI_EN, which switches between the ACPI and the legacy
+    /// modes.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether the events raise the SCI.
+    pub fn set_sci_enabled(&mut self, enabled: bool) -> Result<(), T::E> {
+        if enabled {
+            self.control |= SCI_EN_BIT;
+        } else {
+            self.control &= !SCI_EN_BIT;
+        }
+        self.update_sci()
+    }
+
+    /// Notifies the guest that the power button was pressed, e.g. for
+    /// requesting a graceful shutdown.
+    pub fn press_power_button(&mut self) -> Result<(), T::E> {
+        self.status |= PWRBTN_BIT;
+        self.update_sci()
+    }
+
+    // Updates the level of the SCI, and signals its rising edges.
+    fn update_sci(&mut self) -> Result<(), T::E> {
+        let sci = (self.control & SCI_EN_BIT) != 0 && (self.status & self.enable) != 0;
+        let raised = sci && !self.sci;
+        self.sci = sci;
+        if raised {
+            return self.sci_evt.trigger();
+        }
+        Ok(())
+    }
+
+    // Returns the byte at `offset` in the register file.
+    fn read_byte(&self, offset: u8) -> u8 {
+        let register = match offset & !1 {
+            STATUS_OFFSET => self.status,
+            ENABLE_OFFSET => self.enable,
+            CONTROL_OFFSET => self.control,
+            _ => {
+                guest_warn!("Invalid PM1 read at offset {:#x}", offset);
+                return 0x00;
+            }
+        };
+        register.to_le_bytes()[usize::from(offset & 1)]
+    }
+
+    // Writes the byte at `offset` in the register file, and returns the
+    // SLP_TYP of the requested sleep state, if any.
+    fn write_byte(&mut self, offset: u8, value: u8) -> Option<u8> {
+        let shift = (offset & 1) * 8;
+        let bits = u16::from(value) << shift;
+        let byte_mask = 0xFFu16 << shift;
+        match offset & !1 {
+            STATUS_OFFSET => self.status &= !(bits & STATUS_MASK),
+            ENABLE_OFFSET => {
+                self.enable = (self.enable & !byte_mask) | (bits & ENABLE_MASK);
+            }
+            CONTROL_OFFSET => {
+                self.control = (self.control & !byte_mask) | (bits & CONTROL_MASK);
+                if (bits & SLP_EN_BIT) != 0 {
+                    return Some(((self.control >> SLP_TYP_SHIFT) & SLP_TYP_MASK) as u8);
+                }
+            }
+            _ => guest_warn!("Invalid PM1 write at offset {:#x}", offset),
+        }
+        None
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address of the PM1a_EVT_BLK.
+    ///
+    /// `data` is filled with the little endian bytes of the registers,
+    /// starting at `offset`. The bytes of invalid offsets read as 0, and the
+    /// write only bits read as 0.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    /// * `data` - The byte array storing the read value.
+    pub fn read(&self, offset: u8, data: &mut [u8]) {
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = offset
+                .checked_add(i as u8)
+                .map_or(0x00, |offset| self.read_byte(offset));
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address of the PM1a_EVT_BLK.
+    ///
+    /// Writing 1 to a status bit clears it. Setting SLP_EN in the control
+    /// register requests the sleep state given by SLP_TYP.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `data` - The little endian byte array to write to the registers.
+    pub fn write(&mut self, offset: u8, data: &[u8]) -> Result<(), T::E> {
+        let mut slp_typ = None;
+        for (i, &value) in data.iter().enumerate() {
+            if let Some(offset) = offset.checked_add(i as u8) {
+                slp_typ = self.write_byte(offset, value).or(slp_typ);
+            }
+        }
+        self.update_sci()?;
+
+        if let Some(slp_typ) = slp_typ {
+            self.events.sleep_requested(slp_typ);
+            if slp_typ == S5_SLP_TYP {
+                return self.shutdown_evt.trigger();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Trigger, EV: Pm1Events> LegacyDevice for Pm1<T, EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match u8::try_from(offset) {
+            Ok(offset) => Pm1::read(self, offset, data),
+            Err(_) => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Ok(offset) = u8::try_from(offset) {
+            let _ = Pm1::write(self, offset, data);
+        }
+    }
+
+    fn reset(&mut self) {
+        // Like at creation, SCI_EN is set, so the VMM clears it again before
+        // booting a guest that starts in the legacy mode.
+        self.status = 0;
+        self.enable = 0;
+        self.control = SCI_EN_BIT;
+        self.sci = false;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::Mutex;
+    use vmm_sys_util::eventfd::EventFd;
+
+    #[derive(Default)]
+    struct ExamplePm1Events {
+        sleep_requests: Mutex<Vec<u8>>,
+    }
+
+    impl Pm1Events for ExamplePm1Events {
+        fn sleep_requested(&self, slp_typ: u8) {
+            self.sleep_requests.lock().unwrap().push(slp_typ);
+        }
+    }
+
+    fn evt_count(evt: &EventFd) -> u64 {
+        match evt.read() {
+            Ok(count) => count,
+            Err(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+                0
+            }
+        }
+    }
+
+    fn new_pm1() -> Pm1<EventFd, Arc<ExamplePm1Events>> {
+        Pm1::with_events(
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            Arc::new(ExamplePm1Events::default()),
+        )
+    }
+
+    fn read_word(pm1: &Pm1<EventFd, Arc<ExamplePm1Events>>, offset: u8) -> u16 {
+        let mut data = [0; 2];
+        pm1.read(offset, &mut data);
+        u16::from_le_bytes(data)
+    }
+
+    #[test]
+    fn test_pm1_power_button() {
+        let mut pm1 = new_pm1();
+        assert_eq!(read_word(&pm1, CONTROL_OFFSET), SCI_EN_BIT);
+
+        // A disabled event sets its status bit, without raising the SCI.
+        pm1.press_power_button().unwrap();
+        assert_eq!(read_word(&pm1, STATUS_OFFSET), PWRBTN_BIT);
+        assert!(!pm1.sci_pending());
+        assert_eq!(evt_count(pm1.sci_evt()), 0);
+
+        // Enabling it raises the SCI.
+        pm1.write(ENABLE_OFFSET, &PWRBTN_BIT.to_le_bytes()).unwrap();
+        assert_eq!(read_word(&pm1, ENABLE_OFFSET), PWRBTN_BIT);
+        assert!(pm1.sci_pending());
+        assert_eq!(evt_count(pm1.sci_evt()), 1);
+        // The SCI is already raised.
+        pm1.press_power_button().unwrap();
+        assert_eq!(evt_count(pm1.sci_evt()), 0);
+
+        // Writing 0 doesn't clear the status, but writing 1 does.
+        pm1.write(STATUS_OFFSET, &[0x00, 0x00]).unwrap();
+        assert!(pm1.sci_pending());
+        pm1.write(STATUS_OFFSET + 1, &[0x01]).unwrap();
+        assert_eq!(read_word(&pm1, STATUS_OFFSET), 0);
+        assert!(!pm1.sci_pending());
+
+        // The events don't raise the SCI while SCI_EN is cleared.
+        pm1.write(CONTROL_OFFSET, &[0x00, 0x00]).unwrap();
+        pm1.press_power_button().unwrap();
+        assert!(!pm1.sci_pending());
+        pm1.write(CONTROL_OFFSET, &[SCI_EN_BIT as u8]).unwrap();
+        assert!(pm1.sci_pending());
+        assert_eq!(evt_count(pm1.sci_evt()), 1);
+
+        // Likewise when the VMM switches the modes.
+        pm1.set_sci_enabled(false).unwrap();
+        assert!(!pm1.sci_enabled());
+        assert!(!pm1.sci_pending());
+        assert_eq!(read_word(&pm1, CONTROL_OFFSET), 0);
+        pm1.set_sci_enabled(true).unwrap();
+        assert!(pm1.sci_enabled());
+        assert!(pm1.sci_pending());
+        assert_eq!(evt_count(pm1.sci_evt()), 1);
+    }
+
+    #[test]
+    fn test_pm1_registers() {
+        let mut pm1 = new_pm1();
+
+        // A 4 bytes access covers both the status and the enable registers,
+        // and only the supported bits are kept.
+        pm1.write(STATUS_OFFSET, &[0x00, 0x00, 0xFF, 0xFF]).unwrap();
+        let mut data = [0; 4];
+        pm1.read(STATUS_OFFSET, &mut data);
+        assert_eq!(data, [0x00, 0x00, 0x21, 0x07]);
+
+        // GBL_RLS (bit 2) is write only.
+        pm1.write(
+            CONTROL_OFFSET,
+            &(0x0004 | BM_RLD_BIT | SCI_EN_BIT).to_le_bytes(),
+        )
+        .unwrap();
+        assert_eq!(read_word(&pm1, CONTROL_OFFSET), BM_RLD_BIT | SCI_EN_BIT);
+
+        // Invalid offsets are ignored.
+        pm1.write(CONTROL_OFFSET + 2, &[0xFF, 0xFF]).unwrap();
+        pm1.write(0xFF, &[0xFF, 0xFF]).unwrap();
+        let mut data = [0xFF; 4];
+        pm1.read(CONTROL_OFFSET + 1, &mut data);
+        assert_eq!(data, [0x00; 4]);
+        pm1.read(0xFE, &mut data);
+        assert_eq!(data, [0x00; 4]);
+    }
+
+    #[test]
+    fn test_pm1_sleep() {
+        let mut pm1 = new_pm1();
+
+        // Setting SLP_TYP alone doesn't enter the sleep state.
+        let s3 = 3 << SLP_TYP_SHIFT;
+        pm1.write(CONTROL_OFFSET, &(s3 | SCI_EN_BIT).to_le_bytes())
+            .unwrap();
+        assert!(pm1.events().sleep_requests.lock().unwrap().is_empty());
+        // SLP_EN is write only.
+        pm1.write(CONTROL_OFFSET + 1, &[(SLP_EN_BIT >> 8) as u8 | 0x0C])
+            .unwrap();
+        assert_eq!(read_word(&pm1, CONTROL_OFFSET), s3 | SCI_EN_BIT);
+        assert_eq!(*pm1.events().sleep_requests.lock().unwrap(), vec![3]);
+        assert_eq!(evt_count(pm1.shutdown_evt()), 0);
+
+        // Entering S5 shuts the guest down.
+        let s5 = u16::from(S5_SLP_TYP) << SLP_TYP_SHIFT;
+        pm1.write(
+            CONTROL_OFFSET,
+            &(s5 | SLP_EN_BIT | SCI_EN_BIT).to_le_bytes(),
+        )
+        .unwrap();
+        assert_eq!(*pm1.events().sleep_requests.lock().unwrap(), vec![3, 5]);
+        assert_eq!(evt_count(pm1.shutdown_evt()), 1);
+    }
+
+    #[test]
+    fn test_pm1_legacy_device() {
+        let mut pm1 = new_pm1();
+
+        LegacyDevice::write(
+            &mut pm1,
+            u16::from(ENABLE_OFFSET),
+            &PWRBTN_BIT.to_le_bytes(),
+        );
+        pm1.press_power_button().unwrap();
+        assert!(pm1.sci_pending());
+        let mut data = [0; 2];
+        LegacyDevice::read(&mut pm1, u16::from(STATUS_OFFSET), &mut data);
+        assert_eq!(u16::from_le_bytes(data), PWRBTN_BIT);
+
+        pm1.reset();
+        assert!(!pm1.sci_pending());
+        assert_eq!(read_word(&pm1, STATUS_OFFSET), 0);
+        assert_eq!(read_word(&pm1, ENABLE_OFFSET), 0);
+        assert_eq!(read_word(&pm1, CONTROL_OFFSET), SCI_EN_BIT);
+    }
+}
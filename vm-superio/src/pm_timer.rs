@@ -0,0 +1,217 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the ACPI Power Management timer.
+//!
+//! The PM timer is a free running counter, incremented at 3.579545 MHz, which
+//! guests use for calibrating the TSC and for delay loops when no HPET is
+//! present. Its port is given to the guest by the `PM_TMR_BLK` field of the
+//! FADT.
+
+use core::convert::TryFrom;
+
+use crate::pit::ClockSource;
+use crate::LegacyDevice;
+
+// Frequency of the PM timer clock.
+const PM_TIMER_FREQ_HZ: u64 = 3_579_545;
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+// Size of the counter register, which is the only register of the device.
+const COUNTER_SIZE: usize = 4;
+
+// Masks of the 24-bit and 32-bit counters.
+const COUNTER_24BIT_MASK: u32 = 0x00FF_FFFF;
+const COUNTER_32BIT_MASK: u32 = 0xFFFF_FFFF;
+
+/// The ACPI Power Management timer, found at the port given by the
+/// `PM_TMR_BLK` field of the FADT.
+///
+/// The value of the counter is derived from the time reported by a
+/// [`ClockSource`](../pit/trait.ClockSource.html). The counter is 24 bits
+/// wide by default, while a 32-bit counter must be advertised through the
+/// `TMR_VAL_EXT` flag of the FADT.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::pit::MonotonicClock;
+/// # use vm_superio::pm_timer::PmTimer;
+/// let pm_timer = PmTimer::new(MonotonicClock::new());
+///
+/// // The guest reads the whole counter at once.
+/// let mut data = [0; 4];
+/// pm_timer.read(0, &mut data);
+/// assert!(u32::from_le_bytes(data) <= 0x00FF_FFFF);
+/// ```
+#[derive(Debug)]
+pub struct PmTimer<C: ClockSource> {
+    clock: C,
+    mask: u32,
+}
+
+impl<C: ClockSource> PmTimer<C> {
+    /// Creates a new `PmTimer` instance, with a 24-bit counter.
+    ///
+    /// # Arguments
+    /// * `clock` - The source of the time from which the counter is derived.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `PmTimer`](struct.PmTimer.html#example).
+    pub const fn new(clock: C) -> Self {
+        PmTimer {
+            clock,
+            mask: COUNTER_24BIT_MASK,
+        }
+    }
+
+    /// Creates a new `PmTimer` instance, with a 32-bit counter.
+    ///
+    /// # Arguments
+    /// * `clock` - The source of the time from which the counter is derived.
+    pub fn new_32bit(clock: C) -> Self {
+        PmTimer {
+            clock,
+            mask: COUNTER_32BIT_MASK,
+        }
+    }
+
+    /// Provides a reference to the clock source.
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Returns whether the counter is 32 bits wide, i.e. whether the
+    /// `TMR_VAL_EXT` flag should be set in the FADT.
+    pub fn is_32bit(&self) -> bool {
+        self.mask == COUNTER_32BIT_MASK
+    }
+
+    /// Returns the current value of the counter.
+    pub fn counter(&self) -> u32 {
+        let ticks = self.clock.now_ns() as u128 * PM_TIMER_FREQ_HZ as u128 / NANOS_PER_SEC as u128;
+        (ticks as u32) & self.mask
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// `data` is filled with the little endian bytes of the counter, starting
+    /// at `offset`. The bytes past the end of the counter read as 0.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    /// * `data` - The byte array storing the read value.
+    pub fn read(&self, offset: u8, data: &mut [u8]) {
+        let counter = self.counter().to_le_bytes();
+        if usize::from(offset) + data.len() > COUNTER_SIZE {
+            guest_warn!(
+                "Invalid PM timer read of {} bytes at offset {:#x}",
+                data.len(),
+                offset
+            );
+        }
+        for (i, byte) in data.iter_mut().enumerate() {
+            let index = usize::from(offset) + i;
+            *byte = if index < COUNTER_SIZE {
+                counter[index]
+            } else {
+                0x00
+            };
+        }
+    }
+}
+
+impl<C: ClockSource> LegacyDevice for PmTimer<C> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match u8::try_from(offset) {
+            Ok(offset) => PmTimer::read(self, offset, data),
+            Err(_) => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, _data: &[u8]) {
+        guest_warn!("Ignoring the PM timer write at offset {:#x}", offset);
+    }
+
+    // The counter is free running, and isn't reset.
+    fn reset(&mut self) {}
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockClock;
+
+    #[test]
+    fn test_pm_timer_counter() {
+        let clock = MockClock::new();
+        let pm_timer = PmTimer::new(clock.clone());
+        assert!(!pm_timer.is_32bit());
+        assert_eq!(pm_timer.counter(), 0);
+
+        // One second is 3579545 ticks.
+        clock.set_ns(NANOS_PER_SEC);
+        assert_eq!(pm_timer.counter(), 3_579_545);
+        // Partial ticks are not counted.
+        clock.set_ns(279);
+        assert_eq!(pm_timer.counter(), 0);
+        clock.set_ns(280);
+        assert_eq!(pm_timer.counter(), 1);
+
+        // The 24-bit counter wraps after 2^24 ticks, unlike the 32-bit one.
+        let ns = 5 * NANOS_PER_SEC;
+        clock.set_ns(ns);
+        assert_eq!(pm_timer.counter(), (5 * 3_579_545) & 0x00FF_FFFF);
+        let pm_timer = PmTimer::new_32bit(clock.clone());
+        assert!(pm_timer.is_32bit());
+        assert_eq!(pm_timer.counter(), 5 * 3_579_545);
+        // And the 32-bit one wraps after 2^32 ticks.
+        clock.set_ns(1200 * NANOS_PER_SEC);
+        assert_eq!(pm_timer.counter(), (1200 * 3_579_545u64) as u32);
+    }
+
+    #[test]
+    fn test_pm_timer_read() {
+        let clock = MockClock::new();
+        let pm_timer = PmTimer::new_32bit(clock.clone());
+        // 0x01020304 ticks.
+        clock.set_ns(4_723_801_489);
+        assert_eq!(pm_timer.counter(), 0x0102_0304);
+
+        let mut data = [0xFF; 4];
+        pm_timer.read(0, &mut data);
+        assert_eq!(data, [0x04, 0x03, 0x02, 0x01]);
+        let mut data = [0xFF; 2];
+        pm_timer.read(2, &mut data);
+        assert_eq!(data, [0x02, 0x01]);
+        // The bytes past the counter read as 0.
+        let mut data = [0xFF; 2];
+        pm_timer.read(3, &mut data);
+        assert_eq!(data, [0x01, 0x00]);
+    }
+
+    #[test]
+    fn test_pm_timer_legacy_device() {
+        let clock = MockClock::new();
+        let mut pm_timer = PmTimer::new(clock.clone());
+        clock.set_ns(4_723_801_489);
+
+        // The counter is read-only.
+        LegacyDevice::write(&mut pm_timer, 0, &[0; 4]);
+        pm_timer.reset();
+        let mut data = [0xFF; 4];
+        LegacyDevice::read(&mut pm_timer, 0, &mut data);
+        assert_eq!(data, [0x04, 0x03, 0x02, 0x00]);
+        LegacyDevice::read(&mut pm_timer, 0x100, &mut data);
+        assert_eq!(data, [0; 4]);
+    }
+}
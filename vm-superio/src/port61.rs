@@ -0,0 +1,474 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the System Control Port B (port 0x61).
+//!
+//! This emulates the bits that control the gate of the PIT channel 2 and the
+//! PC speaker, as well as the refresh bit, which toggles periodically and is
+//! used by some guests for short delays.
+
+use alloc::sync::Arc;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+use crate::pit::ClockSource;
+#[cfg(feature = "std")]
+use crate::pit::Pit;
+#[cfg(feature = "std")]
+use crate::Trigger;
+use crate::{byte_offset, LegacyDevice};
+
+// Offset of the control register, which is the only register of the device.
+const CONTROL_OFFSET: u8 = 0;
+
+// Control register bits.
+// Gate of the PIT channel 2.
+const TIMER2_GATE_BIT: u8 = 0b0000_0001;
+// Connects the output of the PIT channel 2 to the speaker.
+const SPEAKER_DATA_BIT: u8 = 0b0000_0010;
+// The bits above, along with the parity and I/O channel check enable bits,
+// are read back as written.
+const WRITABLE_MASK: u8 = 0b0000_1111;
+// Toggles with each memory refresh request. Read only.
+const REFRESH_BIT: u8 = 0b0001_0000;
+// Output of the PIT channel 2. Read only.
+const TIMER2_OUTPUT_BIT: u8 = 0b0010_0000;
+
+// Period of the memory refresh requests, i.e. 18 PIT input clock ticks.
+const REFRESH_PERIOD_NS: u64 = 15_085;
+
+/// The PIT channel whose gate and output are connected to port 0x61, i.e.
+/// channel 2 on PC compatible machines.
+///
+/// It is implemented for a `Mutex` wrapping a [`Pit`](../pit/struct.Pit.html),
+/// so that the PIT can be shared with the VMM code handling the PIT ports.
+pub trait SpeakerTimer {
+    /// Sets the level of the gate input of the channel.
+    fn set_gate(&self, level: bool);
+
+    /// Returns the level of the output of the channel.
+    fn output(&self) -> bool;
+
+    /// Returns the frequency of the signal generated by the channel, if it
+    /// is programmed to generate one.
+    fn frequency_hz(&self) -> Option<u32>;
+}
+
+#[cfg(feature = "std")]
+impl<T: Trigger, C: ClockSource> SpeakerTimer for Mutex<Pit<T, C>> {
+    fn set_gate(&self, level: bool) {
+        // A poisoned lock still holds a consistent PIT, since its methods
+        // don't panic halfway through an update.
+        let mut pit = self.lock().unwrap_or_else(|e| e.into_inner());
+        pit.set_channel2_gate(level);
+    }
+
+    fn output(&self) -> bool {
+        let pit = self.lock().unwrap_or_else(|e| e.into_inner());
+        pit.channel2_output()
+    }
+
+    fn frequency_hz(&self) -> Option<u32> {
+        let pit = self.lock().unwrap_or_else(|e| e.into_inner());
+        pit.channel2_frequency_hz()
+    }
+}
+
+impl<S: SpeakerTimer> SpeakerTimer for Arc<S> {
+    fn set_gate(&self, level: bool) {
+        self.as_ref().set_gate(level);
+    }
+
+    fn output(&self) -> bool {
+        self.as_ref().output()
+    }
+
+    fn frequency_hz(&self) -> Option<u32> {
+        self.as_ref().frequency_hz()
+    }
+}
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the port 0x61 emulation logic. The methods below can be implemented by a
+/// backend that keeps track of such events by playing sounds, logging messages, or any
+/// other action.
+pub trait Port61Events {
+    /// The driver turned the speaker on, while the PIT channel 2 generates a
+    /// signal with the given frequency.
+    fn beep_started(&self, frequency_hz: u32);
+
+    /// The driver turned the speaker off, after it beeped with the given
+    /// frequency for the given duration.
+    fn beep_stopped(&self, frequency_hz: u32, duration: Duration);
+}
+
+/// Provides a no-op implementation of `Port61Events` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `Port61Events`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl Port61Events for NoEvents {
+    fn beep_started(&self, _frequency_hz: u32) {}
+    fn beep_stopped(&self, _frequency_hz: u32, _duration: Duration) {}
+}
+
+impl<EV: Port61Events> Port61Events for Arc<EV> {
+    fn beep_started(&self, frequency_hz: u32) {
+        self.as_ref().beep_started(frequency_hz);
+    }
+
+    fn beep_stopped(&self, frequency_hz: u32, duration: Duration) {
+        self.as_ref().beep_stopped(frequency_hz, duration);
+    }
+}
+
+/// The System Control Port B, usually found at port 0x61.
+///
+/// The gate and the output of the PIT channel 2 are accessed through a
+/// [`SpeakerTimer`](trait.SpeakerTimer.html) object, while the refresh bit
+/// is derived from the time reported by a
+/// [`ClockSource`](../pit/trait.ClockSource.html).
+///
+/// The speaker beeps while both the gate and the speaker data bits are set,
+/// and the PIT channel 2 generates a signal. The frequency reported through
+/// [`Port61Events`](trait.Port61Events.html) is the one programmed when the
+/// speaker is turned on.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::{Arc, Mutex};
+/// # use vm_superio::pit::{MonotonicClock, Pit};
+/// # use vm_superio::port61::Port61;
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let clock = MonotonicClock::new();
+/// let pit = Arc::new(Mutex::new(Pit::new(DummyTrigger, clock)));
+/// let mut port61 = Port61::new(pit.clone(), clock);
+///
+/// // Enable the gate of the PIT channel 2, and program it in mode 0.
+/// port61.write(0, 0x01);
+/// pit.lock().unwrap().write(3, 0xB0);
+/// pit.lock().unwrap().write(2, 0xFF);
+/// pit.lock().unwrap().write(2, 0xFF);
+///
+/// // The output is low until the terminal count.
+/// assert_eq!(port61.read(0) & 0x20, 0);
+/// ```
+#[derive(Debug)]
+pub struct Port61<S: SpeakerTimer, C: ClockSource, EV: Port61Events> {
+    timer: S,
+    clock: C,
+    events: EV,
+    // The writable bits of the control register.
+    control: u8,
+    // The frequency and the start time of the ongoing beep.
+    beep: Option<(u32, u64)>,
+}
+
+impl<S: SpeakerTimer, C: ClockSource> Port61<S, C, NoEvents> {
+    /// Creates a new `Port61` instance, which controls the channel 2 of
+    /// `timer`, and derives the refresh bit from `clock`.
+    ///
+    /// The gate of the channel is set low, as the control register.
+    ///
+    /// # Arguments
+    /// * `timer` - The PIT channel connected to the device.
+    /// * `clock` - The source of the time used by the refresh bit.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Port61`](struct.Port61.html#example).
+    pub fn new(timer: S, clock: C) -> Self {
+        Self::with_events(timer, clock, NoEvents)
+    }
+}
+
+impl<S: SpeakerTimer, C: ClockSource, EV: Port61Events> Port61<S, C, EV> {
+    /// Creates a new `Port61` instance, which controls the channel 2 of
+    /// `timer`, derives the refresh bit from `clock`, and invokes the
+    /// `port61_evts` implementation of `Port61Events` during operation.
+    ///
+    /// The gate of the channel is set low, as the control register.
+    ///
+    /// # Arguments
+    /// * `timer` - The PIT channel connected to the device.
+    /// * `clock` - The source of the time used by the refresh bit and the
+    ///   beep durations.
+    /// * `port61_evts` - The `Port61Events` implementation used to track the
+    ///   occurrence of significant events in the port 0x61 operation logic.
+    pub fn with_events(timer: S, clock: C, port61_evts: EV) -> Self {
+        timer.set_gate(false);
+        Port61 {
+            timer,
+            clock,
+            events: port61_evts,
+            control: 0,
+            beep: None,
+        }
+    }
+
+    /// Provides a reference to the PIT channel connected to the device.
+    pub fn timer(&self) -> &S {
+        &self.timer
+    }
+
+    /// Provides a reference to the port 0x61 events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    fn update_beep(&mut self) {
+        let speaker_on = (self.control & (TIMER2_GATE_BIT | SPEAKER_DATA_BIT))
+            == (TIMER2_GATE_BIT | SPEAKER_DATA_BIT);
+        match (self.beep, speaker_on) {
+            (None, true) => {
+                if let Some(frequency_hz) = self.timer.frequency_hz() {
+                    self.beep = Some((frequency_hz, self.clock.now_ns()));
+                    self.events.beep_started(frequency_hz);
+                }
+            }
+            (Some((frequency_hz, start_ns)), false) => {
+                self.beep = None;
+                let duration_ns = self.clock.now_ns().saturating_sub(start_ns);
+                self.events
+                    .beep_stopped(frequency_hz, Duration::from_nanos(duration_ns));
+            }
+            _ => (),
+        }
+    }
+
+    /// Returns whether the output of the PIT channel 2 drives the speaker.
+    pub fn speaker_enabled(&self) -> bool {
+        (self.control & SPEAKER_DATA_BIT) != 0
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Returns the value of the control register, or 0 for invalid offsets.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&self, offset: u8) -> u8 {
+        if offset != CONTROL_OFFSET {
+            guest_warn!("Invalid port 0x61 read at offset {:#x}", offset);
+            return 0x00;
+        }
+
+        let mut value = self.control;
+        if ((self.clock.now_ns() / REFRESH_PERIOD_NS) & 1) != 0 {
+            value |= REFRESH_BIT;
+        }
+        if self.timer.output() {
+            value |= TIMER2_OUTPUT_BIT;
+        }
+        value
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) {
+        if offset != CONTROL_OFFSET {
+            guest_warn!("Invalid port 0x61 write at offset {:#x}", offset);
+            return;
+        }
+
+        let old_control = self.control;
+        self.control = value & WRITABLE_MASK;
+        if ((old_control ^ self.control) & TIMER2_GATE_BIT) != 0 {
+            self.timer.set_gate((self.control & TIMER2_GATE_BIT) != 0);
+        }
+        self.update_beep();
+    }
+}
+
+impl<S: SpeakerTimer, C: ClockSource, EV: Port61Events> LegacyDevice for Port61<S, C, EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = Port61::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            Port61::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {
+        // Closes the gate of the PIT channel 2, and stops the ongoing beep.
+        Port61::write(self, CONTROL_OFFSET, 0);
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockClock;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use vmm_sys_util::eventfd::EventFd;
+    use vmm_sys_util::metric::Metric;
+
+    const NANOS_PER_MS: u64 = 1_000_000;
+
+    #[derive(Default)]
+    struct ExamplePort61Events {
+        beeps: AtomicU64,
+        frequency_hz: AtomicU64,
+        duration_ns: AtomicU64,
+    }
+
+    impl Port61Events for ExamplePort61Events {
+        fn beep_started(&self, frequency_hz: u32) {
+            self.beeps.inc();
+            self.frequency_hz
+                .store(frequency_hz as u64, Ordering::Relaxed);
+        }
+
+        fn beep_stopped(&self, frequency_hz: u32, duration: Duration) {
+            assert_eq!(
+                frequency_hz as u64,
+                self.frequency_hz.load(Ordering::Relaxed)
+            );
+            self.duration_ns.add(duration.as_nanos() as u64);
+        }
+    }
+
+    type TestPit = Pit<EventFd, MockClock>;
+    type TestPort61 = Port61<Arc<Mutex<TestPit>>, MockClock, NoEvents>;
+
+    fn new_port61() -> (TestPort61, MockClock) {
+        let clock = MockClock::new();
+        let irq0_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let pit = Arc::new(Mutex::new(Pit::new(irq0_evt, clock.clone())));
+        (Port61::new(pit, clock.clone()), clock)
+    }
+
+    #[test]
+    fn test_port61_control() {
+        let (mut port61, _) = new_port61();
+        assert!(!port61.timer().lock().unwrap().channel2_gate());
+
+        // The writable bits are read back, and the gate is forwarded to the
+        // PIT.
+        port61.write(CONTROL_OFFSET, 0xFF);
+        assert_eq!(port61.read(CONTROL_OFFSET) & !TIMER2_OUTPUT_BIT, 0x0F);
+        assert!(port61.timer().lock().unwrap().channel2_gate());
+        assert!(port61.speaker_enabled());
+
+        port61.write(CONTROL_OFFSET, SPEAKER_DATA_BIT);
+        assert!(!port61.timer().lock().unwrap().channel2_gate());
+        assert!(port61.speaker_enabled());
+
+        // Invalid offsets are ignored.
+        port61.write(CONTROL_OFFSET + 1, TIMER2_GATE_BIT);
+        assert_eq!(port61.read(CONTROL_OFFSET + 1), 0);
+        assert!(!port61.timer().lock().unwrap().channel2_gate());
+    }
+
+    #[test]
+    fn test_port61_refresh() {
+        let (port61, clock) = new_port61();
+
+        assert_eq!(port61.read(CONTROL_OFFSET) & REFRESH_BIT, 0);
+        clock.set_ns(REFRESH_PERIOD_NS);
+        assert_eq!(port61.read(CONTROL_OFFSET) & REFRESH_BIT, REFRESH_BIT);
+        clock.set_ns(2 * REFRESH_PERIOD_NS - 1);
+        assert_eq!(port61.read(CONTROL_OFFSET) & REFRESH_BIT, REFRESH_BIT);
+        clock.set_ns(2 * REFRESH_PERIOD_NS);
+        assert_eq!(port61.read(CONTROL_OFFSET) & REFRESH_BIT, 0);
+    }
+
+    #[test]
+    fn test_port61_timer2_output() {
+        let (mut port61, clock) = new_port61();
+
+        // Program the channel 2 in mode 0 with a 1000 ticks count, while the
+        // gate is low.
+        {
+            let mut pit = port61.timer().lock().unwrap();
+            pit.write(3, 0xB0);
+            pit.write(2, 0xE8);
+            pit.write(2, 0x03);
+        }
+        assert_eq!(port61.read(CONTROL_OFFSET) & TIMER2_OUTPUT_BIT, 0);
+
+        // The counter doesn't count until the gate is enabled.
+        clock.set_ns(NANOS_PER_MS);
+        assert_eq!(port61.read(CONTROL_OFFSET) & TIMER2_OUTPUT_BIT, 0);
+        port61.write(CONTROL_OFFSET, TIMER2_GATE_BIT);
+        clock.set_ns(2 * NANOS_PER_MS);
+        assert_eq!(
+            port61.read(CONTROL_OFFSET) & TIMER2_OUTPUT_BIT,
+            TIMER2_OUTPUT_BIT
+        );
+    }
+
+    #[test]
+    fn test_port61_beep() {
+        let clock = MockClock::new();
+        let irq0_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let pit = Arc::new(Mutex::new(Pit::new(irq0_evt, clock.clone())));
+        let events = Arc::new(ExamplePort61Events::default());
+        let mut port61 = Port61::with_events(pit.clone(), clock.clone(), events.clone());
+
+        // The speaker doesn't beep until the channel 2 generates a signal.
+        port61.write(CONTROL_OFFSET, TIMER2_GATE_BIT | SPEAKER_DATA_BIT);
+        port61.write(CONTROL_OFFSET, 0);
+        assert_eq!(events.beeps.count(), 0);
+
+        // Program an 880 Hz square wave, and beep for 100 ms.
+        {
+            let mut pit = pit.lock().unwrap();
+            pit.write(3, 0xB6);
+            pit.write(2, 0x4C);
+            pit.write(2, 0x05);
+        }
+        port61.write(CONTROL_OFFSET, SPEAKER_DATA_BIT);
+        assert_eq!(events.beeps.count(), 0);
+        clock.set_ns(NANOS_PER_MS);
+        port61.write(CONTROL_OFFSET, TIMER2_GATE_BIT | SPEAKER_DATA_BIT);
+        assert_eq!(events.beeps.count(), 1);
+        assert_eq!(events.frequency_hz.load(Ordering::Relaxed), 880);
+
+        // Writing the same value again doesn't start a new beep.
+        port61.write(CONTROL_OFFSET, TIMER2_GATE_BIT | SPEAKER_DATA_BIT);
+        assert_eq!(events.beeps.count(), 1);
+
+        clock.set_ns(101 * NANOS_PER_MS);
+        port61.write(CONTROL_OFFSET, TIMER2_GATE_BIT);
+        assert_eq!(events.duration_ns.count(), 100 * NANOS_PER_MS);
+    }
+
+    #[test]
+    fn test_port61_legacy_device() {
+        let (mut port61, _) = new_port61();
+
+        LegacyDevice::write(&mut port61, u16::from(CONTROL_OFFSET), &[TIMER2_GATE_BIT]);
+        assert!(port61.timer().lock().unwrap().channel2_gate());
+
+        port61.reset();
+        assert!(!port61.timer().lock().unwrap().channel2_gate());
+        assert_eq!(port61.read(CONTROL_OFFSET) & WRITABLE_MASK, 0);
+    }
+}
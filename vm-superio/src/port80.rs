@@ -0,0 +1,252 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the POST code port (port 0x80).
+//!
+//! The firmware writes its Power-On Self-Test (POST) progress codes to this
+//! port, and some guests write to it for short I/O delays. Capturing these
+//! writes helps with debugging the early firmware hangs.
+
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+use crate::{byte_offset, LegacyDevice};
+
+// Offset of the POST code register, which is the only register of the
+// device.
+const POST_CODE_OFFSET: u8 = 0;
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the port 0x80 emulation logic. The methods below can be implemented by a
+/// backend that keeps track of such events by incrementing metrics, logging messages, or any
+/// other action.
+pub trait Port80Events {
+    /// The driver wrote a POST code.
+    fn post_code(&self, code: u8);
+}
+
+/// Provides a no-op implementation of `Port80Events` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `Port80Events`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl Port80Events for NoEvents {
+    fn post_code(&self, _code: u8) {}
+}
+
+impl<EV: Port80Events> Port80Events for Arc<EV> {
+    fn post_code(&self, code: u8) {
+        self.as_ref().post_code(code);
+    }
+}
+
+/// The POST code port, usually found at port 0x80.
+///
+/// Each code written by the guest is reported through
+/// [`Port80Events`](trait.Port80Events.html), and the most recent ones are
+/// kept in a history with a configurable length. Reads return the last code.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::port80::Port80;
+/// let mut port80 = Port80::new(2);
+///
+/// for &code in [0x01, 0x02, 0x03].iter() {
+///     port80.write(0, code);
+/// }
+/// assert_eq!(port80.read(0), 0x03);
+/// assert_eq!(port80.last_code(), Some(0x03));
+/// assert_eq!(port80.history().collect::<Vec<u8>>(), vec![0x02, 0x03]);
+/// ```
+#[derive(Debug)]
+pub struct Port80<EV: Port80Events> {
+    events: EV,
+    last_code: Option<u8>,
+    // The most recent codes, the oldest first.
+    history: VecDeque<u8>,
+    history_len: usize,
+}
+
+impl Port80<NoEvents> {
+    /// Creates a new `Port80` instance, which keeps the last `history_len`
+    /// codes.
+    ///
+    /// # Arguments
+    /// * `history_len` - The number of codes kept in the history. A length
+    ///   of 0 disables the history.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Port80`](struct.Port80.html#example).
+    pub fn new(history_len: usize) -> Self {
+        Self::with_events(history_len, NoEvents)
+    }
+}
+
+impl<EV: Port80Events> Port80<EV> {
+    /// Creates a new `Port80` instance, which keeps the last `history_len`
+    /// codes, and invokes the `port80_evts` implementation of `Port80Events`
+    /// during operation.
+    ///
+    /// # Arguments
+    /// * `history_len` - The number of codes kept in the history. A length
+    ///   of 0 disables the history.
+    /// * `port80_evts` - The `Port80Events` implementation used to track the
+    ///   POST codes written by the guest.
+    pub fn with_events(history_len: usize, port80_evts: EV) -> Self {
+        Port80 {
+            events: port80_evts,
+            last_code: None,
+            history: VecDeque::with_capacity(history_len),
+            history_len,
+        }
+    }
+
+    /// Provides a reference to the port 0x80 events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Returns the last code written by the guest, if any.
+    pub fn last_code(&self) -> Option<u8> {
+        self.last_code
+    }
+
+    /// Returns the most recent codes written by the guest, the oldest first.
+    pub fn history(&self) -> impl Iterator<Item = u8> + '_ {
+        self.history.iter().copied()
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Returns the last code written by the guest, or 0xFF if there is none
+    /// or the offset is invalid.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&self, offset: u8) -> u8 {
+        match offset {
+            POST_CODE_OFFSET => self.last_code.unwrap_or(0xFF),
+            _ => {
+                guest_warn!("Invalid POST code port read at offset {:#x}", offset);
+                0xFF
+            }
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) {
+        if offset != POST_CODE_OFFSET {
+            guest_warn!("Invalid POST code port write at offset {:#x}", offset);
+            return;
+        }
+
+        self.last_code = Some(value);
+        if self.history_len > 0 {
+            if self.history.len() == self.history_len {
+                self.history.pop_front();
+            }
+            self.history.push_back(value);
+        }
+        self.events.post_code(value);
+    }
+}
+
+impl<EV: Port80Events> LegacyDevice for Port80<EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = Port80::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            Port80::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.last_code = None;
+        self.history.clear();
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct ExamplePort80Events {
+        codes: Mutex<Vec<u8>>,
+    }
+
+    impl Port80Events for ExamplePort80Events {
+        fn post_code(&self, code: u8) {
+            self.codes.lock().unwrap().push(code);
+        }
+    }
+
+    #[test]
+    fn test_port80_history() {
+        let events = Arc::new(ExamplePort80Events::default());
+        let mut port80 = Port80::with_events(3, events.clone());
+        assert_eq!(port80.last_code(), None);
+        assert_eq!(port80.read(POST_CODE_OFFSET), 0xFF);
+
+        for code in 1..=5 {
+            port80.write(POST_CODE_OFFSET, code);
+        }
+        assert_eq!(port80.last_code(), Some(5));
+        assert_eq!(port80.read(POST_CODE_OFFSET), 5);
+        assert_eq!(port80.history().collect::<Vec<u8>>(), vec![3, 4, 5]);
+        // All the codes are reported.
+        assert_eq!(*events.codes.lock().unwrap(), vec![1, 2, 3, 4, 5]);
+
+        // Other offsets are ignored.
+        port80.write(POST_CODE_OFFSET + 1, 6);
+        assert_eq!(port80.read(POST_CODE_OFFSET + 1), 0xFF);
+        assert_eq!(port80.last_code(), Some(5));
+        assert_eq!(events.codes.lock().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_port80_no_history() {
+        let mut port80 = Port80::new(0);
+
+        port80.write(POST_CODE_OFFSET, 0xAA);
+        assert_eq!(port80.last_code(), Some(0xAA));
+        assert_eq!(port80.history().count(), 0);
+    }
+
+    #[test]
+    fn test_port80_legacy_device() {
+        let mut port80 = Port80::new(2);
+
+        LegacyDevice::write(&mut port80, u16::from(POST_CODE_OFFSET), &[0xAA]);
+        let mut data = [0; 1];
+        LegacyDevice::read(&mut port80, u16::from(POST_CODE_OFFSET), &mut data);
+        assert_eq!(data, [0xAA]);
+
+        port80.reset();
+        assert_eq!(port80.last_code(), None);
+        assert_eq!(port80.history().count(), 0);
+    }
+}
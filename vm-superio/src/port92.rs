@@ -0,0 +1,290 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the System Control Port A (port 0x92).
+//!
+//! This emulates the fast A20 gate and the fast reset bits, which many
+//! bootloaders and kernels use instead of the i8042 output port.
+
+use alloc::sync::Arc;
+use core::result::Result;
+
+use crate::{byte_offset, LegacyDevice, Trigger};
+
+// Offset of the control register, which is the only register of the device.
+const CONTROL_OFFSET: u8 = 0;
+
+// Control register bits.
+// Writing 1 resets the CPU. The bit always reads back as 0.
+const FAST_RESET_BIT: u8 = 0b0000_0001;
+// Fast A20 gate.
+const FAST_A20_BIT: u8 = 0b0000_0010;
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the port 0x92 emulation logic. The methods below can be implemented by a
+/// backend that keeps track of such events by incrementing metrics, logging messages, or any
+/// other action.
+pub trait Port92Events {
+    /// The driver enabled or disabled the fast A20 gate. The VMM can
+    /// implement the address wrap-around of the disabled gate here, or just
+    /// log the transition.
+    fn a20_changed(&self, enabled: bool);
+}
+
+/// Provides a no-op implementation of `Port92Events` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `Port92Events`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl Port92Events for NoEvents {
+    fn a20_changed(&self, _enabled: bool) {}
+}
+
+impl<EV: Port92Events> Port92Events for Arc<EV> {
+    fn a20_changed(&self, enabled: bool) {
+        self.as_ref().a20_changed(enabled);
+    }
+}
+
+/// The System Control Port A, usually found at port 0x92.
+///
+/// A [`Trigger`](../trait.Trigger.html) object is used for notifying the VMM
+/// about the CPU reset event. The A20 gate of this port is separate from the
+/// one of the i8042 output port, and the address wrap-around is disabled as
+/// soon as any of them is enabled.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::port92::Port92;
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut port92 = Port92::new(DummyTrigger);
+///
+/// // Enable the A20 gate.
+/// port92.write(0, 0x02).unwrap();
+/// assert!(port92.a20_enabled());
+/// assert_eq!(port92.read(0), 0x02);
+///
+/// // Reset the CPU.
+/// port92.write(0, 0x03).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Port92<T: Trigger, EV: Port92Events> {
+    // CPU reset event object.
+    reset_evt: T,
+    events: EV,
+    // The value of the control register, without the fast reset bit.
+    control: u8,
+}
+
+impl<T: Trigger> Port92<T, NoEvents> {
+    /// Creates a new `Port92` instance, which will signal `reset_evt` when
+    /// the guest requests a reset.
+    ///
+    /// # Arguments
+    /// * `reset_evt` - A Trigger object that will be used to notify the driver
+    ///   about the reset event.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Port92`](struct.Port92.html#example).
+    pub const fn new(reset_evt: T) -> Self {
+        Self::with_events(reset_evt, NoEvents)
+    }
+}
+
+impl<T: Trigger, EV: Port92Events> Port92<T, EV> {
+    /// Creates a new `Port92` instance, which will signal `reset_evt` when
+    /// the guest requests a reset, and invokes the `port92_evts`
+    /// implementation of `Port92Events` during operation.
+    ///
+    /// # Arguments
+    /// * `reset_evt` - A Trigger object that will be used to notify the driver
+    ///   about the reset event.
+    /// * `port92_evts` - The `Port92Events` implementation used to track the
+    ///   occurrence of significant events in the port 0x92 operation logic.
+    pub const fn with_events(reset_evt: T, port92_evts: EV) -> Self {
+        Port92 {
+            reset_evt,
+            events: port92_evts,
+            control: 0,
+        }
+    }
+
+    /// Provides a reference to the reset event object.
+    pub fn reset_evt(&self) -> &T {
+        &self.reset_evt
+    }
+
+    /// Provides a reference to the port 0x92 events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Returns whether the fast A20 gate is enabled.
+    pub fn a20_enabled(&self) -> bool {
+        (self.control & FAST_A20_BIT) != 0
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Returns the value of the control register, or 0 for invalid offsets.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&self, offset: u8) -> u8 {
+        match offset {
+            CONTROL_OFFSET => self.control,
+            _ => {
+                guest_warn!("Invalid port 0x92 read at offset {:#x}", offset);
+                0x00
+            }
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) -> Result<(), T::E> {
+        if offset != CONTROL_OFFSET {
+            guest_warn!("Invalid port 0x92 write at offset {:#x}", offset);
+            return Ok(());
+        }
+
+        let old_control = self.control;
+        self.control = value & !FAST_RESET_BIT;
+        if ((old_control ^ self.control) & FAST_A20_BIT) != 0 {
+            self.events.a20_changed(self.a20_enabled());
+        }
+        if (value & FAST_RESET_BIT) != 0 {
+            return self.reset_evt.trigger();
+        }
+        Ok(())
+    }
+}
+
+impl<T: Trigger, EV: Port92Events> LegacyDevice for Port92<T, EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = Port92::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            let _ = Port92::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {
+        // Clearing the register doesn't request a reset, so it can't fail.
+        let _ = Port92::write(self, CONTROL_OFFSET, 0);
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+    use vmm_sys_util::eventfd::EventFd;
+    use vmm_sys_util::metric::Metric;
+
+    struct ExamplePort92Events {
+        a20_changes: AtomicU64,
+        a20_enabled: AtomicBool,
+    }
+
+    impl Port92Events for ExamplePort92Events {
+        fn a20_changed(&self, enabled: bool) {
+            self.a20_changes.inc();
+            self.a20_enabled.store(enabled, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_port92_reset() {
+        let reset_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let mut port92 = Port92::new(reset_evt.try_clone().unwrap());
+
+        port92.write(CONTROL_OFFSET, FAST_RESET_BIT).unwrap();
+        assert_eq!(reset_evt.read().unwrap(), 1);
+        // The reset bit doesn't stick.
+        assert_eq!(port92.read(CONTROL_OFFSET), 0);
+
+        // Writes to other offsets are ignored.
+        port92.write(CONTROL_OFFSET + 1, FAST_RESET_BIT).unwrap();
+        assert_eq!(
+            reset_evt.read().unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+        assert_eq!(port92.read(CONTROL_OFFSET + 1), 0);
+    }
+
+    #[test]
+    fn test_port92_a20() {
+        let reset_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let events = Arc::new(ExamplePort92Events {
+            a20_changes: AtomicU64::new(0),
+            a20_enabled: AtomicBool::new(false),
+        });
+        let mut port92 = Port92::with_events(reset_evt.try_clone().unwrap(), events.clone());
+
+        assert!(!port92.a20_enabled());
+        port92.write(CONTROL_OFFSET, FAST_A20_BIT).unwrap();
+        assert!(port92.a20_enabled());
+        assert_eq!(port92.read(CONTROL_OFFSET), FAST_A20_BIT);
+        assert_eq!(events.a20_changes.count(), 1);
+        assert!(events.a20_enabled.load(Ordering::Relaxed));
+
+        // The other bits are kept, and the A20 gate didn't change.
+        port92.write(CONTROL_OFFSET, 0xC2).unwrap();
+        assert_eq!(port92.read(CONTROL_OFFSET), 0xC2);
+        assert_eq!(events.a20_changes.count(), 1);
+
+        // Disable the A20 gate while resetting the CPU.
+        port92.write(CONTROL_OFFSET, FAST_RESET_BIT).unwrap();
+        assert_eq!(reset_evt.read().unwrap(), 1);
+        assert!(!port92.a20_enabled());
+        assert_eq!(events.a20_changes.count(), 2);
+        assert!(!events.a20_enabled.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_port92_legacy_device() {
+        let reset_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let events = Arc::new(ExamplePort92Events {
+            a20_changes: AtomicU64::new(0),
+            a20_enabled: AtomicBool::new(false),
+        });
+        let mut port92 = Port92::with_events(reset_evt, events.clone());
+
+        LegacyDevice::write(&mut port92, u16::from(CONTROL_OFFSET), &[FAST_A20_BIT]);
+        assert!(port92.a20_enabled());
+
+        // The reset disables the A20 gate.
+        port92.reset();
+        assert!(!port92.a20_enabled());
+        assert_eq!(events.a20_changes.count(), 2);
+        assert!(!events.a20_enabled.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the [Kani](https://github.com/model-checking/kani) proof harnesses
+//! of the devices.
+//!
+//! Unlike the unit tests and the fuzzers, which exercise some inputs, the
+//! harnesses prove their properties for all the offsets, values and states
+//! they quantify over. The module is only compiled by Kani, which sets the
+//! `kani` configuration option, and the harnesses run with:
+//!
+//! ```bash
+//! cargo kani -p vm-superio
+//! ```
+
+use std::io::{sink, Sink};
+
+use crate::i8042::{I8042Device, BUFFER_SIZE};
+use crate::rtc_pl031::Rtc;
+use crate::serial::{NoEvents, Serial, SerialState, FIFO_SIZE};
+use crate::Trigger;
+
+// A `Trigger` which never fails, so that the harnesses only explore the
+// paths of the devices.
+struct NoopTrigger;
+
+impl Trigger for NoopTrigger {
+    type E = ();
+
+    fn trigger(&self) -> Result<(), ()> {
+        Ok(())
+    }
+}
+
+// Returns a serial port with arbitrary registers and an empty FIFO.
+fn any_serial() -> Serial<NoopTrigger, NoEvents, Sink> {
+    let state = SerialState {
+        baud_divisor_low: kani::any(),
+        baud_divisor_high: kani::any(),
+        interrupt_enable: kani::any(),
+        interrupt_identification: kani::any(),
+        fifo_control: kani::any(),
+        line_control: kani::any(),
+        line_status: kani::any(),
+        modem_control: kani::any(),
+        modem_status: kani::any(),
+        scratch: kani::any(),
+        in_buffer: Vec::new(),
+    };
+    // The trigger never fails, and the FIFO is empty.
+    Serial::from_state(&state, NoopTrigger, NoEvents, sink()).unwrap()
+}
+
+#[kani::proof]
+fn serial_write_read_never_panic() {
+    let mut serial = any_serial();
+
+    let _ = serial.write(kani::any(), kani::any());
+    serial.read(kani::any());
+}
+
+#[kani::proof]
+#[kani::unwind(68)]
+fn serial_in_buffer_within_fifo_size() {
+    let len: usize = kani::any();
+    kani::assume(len <= FIFO_SIZE);
+    let state = SerialState {
+        // The loopback mode feeds the writes to THR back to the FIFO.
+        modem_control: kani::any(),
+        in_buffer: vec![0; len],
+        ..Default::default()
+    };
+    let mut serial = Serial::from_state(&state, NoopTrigger, NoEvents, sink()).unwrap();
+
+    let _ = serial.write(kani::any(), kani::any());
+    let input: [u8; 2] = kani::any();
+    let _ = serial.enqueue_raw_bytes(&input);
+
+    assert!(serial.state().in_buffer.len() <= FIFO_SIZE);
+}
+
+#[kani::proof]
+fn rtc_read_within_amba_ids() {
+    let mut rtc = Rtc::new();
+    let offset: u16 = kani::any();
+    // The data register, at offset 0, reads the time of the host, which Kani
+    // doesn't model.
+    kani::assume(offset >= 4);
+    let mut data = [0; 4];
+
+    rtc.read(offset, &mut data);
+}
+
+#[kani::proof]
+#[kani::unwind(20)]
+fn i8042_write_read_never_panic() {
+    let mut i8042 = I8042Device::with_irqs(NoopTrigger, NoopTrigger, NoopTrigger);
+    let bytes: [u8; 2] = kani::any();
+    let _ = i8042.enqueue_kbd_bytes(&bytes);
+
+    let _ = i8042.write(kani::any(), kani::any());
+    i8042.read(kani::any());
+
+    let state = i8042.state();
+    assert!(state.kbd_buffer.len() <= BUFFER_SIZE);
+    assert!(state.aux_buffer.len() <= BUFFER_SIZE);
+}
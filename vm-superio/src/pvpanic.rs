@@ -0,0 +1,465 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the pvpanic device, in its port I/O (port 0x505)
+//! and MMIO flavors.
+//!
+//! The guest kernel writes to this device when it panics, or when it loads
+//! a crash kernel after a panic, which makes these events observable from
+//! the host. The MMIO flavor is used on the machines without port I/O, such
+//! as the aarch64 ones, where it is described in the device tree by the
+//! `qemu,pvpanic-mmio` compatible string.
+
+use alloc::sync::Arc;
+
+use crate::mmio::{find_register, BadAccessPolicy, MmioEvents, MmioRegister};
+use crate::{byte_offset, LegacyDevice};
+
+// Offset of the event register, which is the only register of the device.
+const EVENT_OFFSET: u8 = 0;
+// Offset of the event register of the MMIO flavor.
+const MMIO_EVENT_OFFSET: u16 = 0;
+
+// The event register of the MMIO flavor accepts the accesses of any width,
+// and only their first byte is used.
+const MMIO_REGISTERS: [MmioRegister; 1] = [MmioRegister {
+    offset: MMIO_EVENT_OFFSET,
+    size: 1,
+    min_access: 1,
+    max_access: 8,
+    align: 1,
+}];
+
+// Event register bits.
+// The guest kernel panicked.
+const PVPANIC_PANICKED: u8 = 0b0000_0001;
+// The guest kernel loaded a crash kernel after a panic.
+const PVPANIC_CRASH_LOADED: u8 = 0b0000_0010;
+// The events supported by the device, read from the event register.
+const SUPPORTED_EVENTS: u8 = PVPANIC_PANICKED | PVPANIC_CRASH_LOADED;
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the pvpanic emulation logic. The methods below can be implemented by a
+/// backend that keeps track of such events by pausing the VM, collecting a dump, logging
+/// messages, or any other action.
+pub trait PvPanicEvents {
+    /// The guest kernel panicked.
+    fn panicked(&self);
+
+    /// The guest kernel loaded a crash kernel after a panic, e.g. through
+    /// kdump, so the VM should keep running.
+    fn crash_loaded(&self);
+}
+
+/// Provides a no-op implementation of `PvPanicEvents` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `PvPanicEvents`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl PvPanicEvents for NoEvents {
+    fn panicked(&self) {}
+    fn crash_loaded(&self) {}
+}
+
+impl MmioEvents for NoEvents {
+    fn bad_read(&self, _offset: u16, _len: usize) {}
+    fn bad_write(&self, _offset: u16, _len: usize) {}
+}
+
+impl<EV: PvPanicEvents> PvPanicEvents for Arc<EV> {
+    fn panicked(&self) {
+        self.as_ref().panicked();
+    }
+
+    fn crash_loaded(&self) {
+        self.as_ref().crash_loaded();
+    }
+}
+
+/// The pvpanic device, usually found at port 0x505.
+///
+/// Reads return the events supported by the device, and the events written
+/// by the guest are delivered through
+/// [`PvPanicEvents`](trait.PvPanicEvents.html).
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::atomic::{AtomicBool, Ordering};
+/// # use std::sync::Arc;
+/// # use vm_superio::pvpanic::{PvPanic, PvPanicEvents};
+/// #[derive(Default)]
+/// struct PanicEvents {
+///     panicked: AtomicBool,
+/// }
+///
+/// impl PvPanicEvents for PanicEvents {
+///     fn panicked(&self) {
+///         self.panicked.store(true, Ordering::Relaxed);
+///     }
+///     fn crash_loaded(&self) {}
+/// }
+///
+/// let events = Arc::new(PanicEvents::default());
+/// let mut pvpanic = PvPanic::with_events(events.clone());
+///
+/// // The guest checks the supported events, and then panics.
+/// assert_eq!(pvpanic.read(0), 0x03);
+/// pvpanic.write(0, 0x01);
+/// assert!(events.panicked.load(Ordering::Relaxed));
+/// ```
+#[derive(Debug)]
+pub struct PvPanic<EV: PvPanicEvents> {
+    events: EV,
+}
+
+impl PvPanic<NoEvents> {
+    /// Creates a new `PvPanic` instance, which ignores the guest events.
+    pub const fn new() -> Self {
+        Self::with_events(NoEvents)
+    }
+}
+
+impl Default for PvPanic<NoEvents> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<EV: PvPanicEvents> PvPanic<EV> {
+    /// Creates a new `PvPanic` instance, which delivers the guest events to
+    /// the `pvpanic_evts` implementation of `PvPanicEvents`.
+    ///
+    /// # Arguments
+    /// * `pvpanic_evts` - The `PvPanicEvents` implementation notified about
+    ///   the panics of the guest.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `PvPanic`](struct.PvPanic.html#example).
+    pub const fn with_events(pvpanic_evts: EV) -> Self {
+        PvPanic {
+            events: pvpanic_evts,
+        }
+    }
+
+    /// Provides a reference to the pvpanic events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Returns the events supported by the device, or 0 for invalid offsets.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&self, offset: u8) -> u8 {
+        match offset {
+            EVENT_OFFSET => SUPPORTED_EVENTS,
+            _ => {
+                guest_warn!("Invalid pvpanic read at offset {:#x}", offset);
+                0x00
+            }
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// A panic takes precedence over the loading of a crash kernel, when the
+    /// guest writes both events at once. Unsupported events are ignored.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) {
+        if offset != EVENT_OFFSET {
+            guest_warn!("Invalid pvpanic write at offset {:#x}", offset);
+            return;
+        }
+
+        if (value & PVPANIC_PANICKED) != 0 {
+            self.events.panicked();
+        } else if (value & PVPANIC_CRASH_LOADED) != 0 {
+            self.events.crash_loaded();
+        }
+    }
+}
+
+/// The MMIO flavor of the pvpanic device, as used on aarch64 machines.
+///
+/// The event register is the first byte of the MMIO region, and behaves
+/// like the one of [`PvPanic`](struct.PvPanic.html), delivering the guest
+/// events through the same [`PvPanicEvents`](trait.PvPanicEvents.html). The
+/// other accesses are handled as configured by a
+/// [`BadAccessPolicy`](../mmio/enum.BadAccessPolicy.html), which reads them
+/// as 0 by default, and reported to the
+/// [`MmioEvents`](../mmio/trait.MmioEvents.html) implementation of the same
+/// events object.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::pvpanic::PvPanicMmio;
+/// let mut pvpanic = PvPanicMmio::new();
+///
+/// let mut data = [0; 1];
+/// pvpanic.read(0, &mut data);
+/// assert_eq!(data, [0x03]);
+/// pvpanic.write(0, &[0x01]);
+/// ```
+#[derive(Debug)]
+pub struct PvPanicMmio<EV: PvPanicEvents> {
+    inner: PvPanic<EV>,
+    bad_access_policy: BadAccessPolicy,
+}
+
+impl PvPanicMmio<NoEvents> {
+    /// Creates a new `PvPanicMmio` instance, which ignores the guest events.
+    pub const fn new() -> Self {
+        Self::with_events(NoEvents)
+    }
+}
+
+impl Default for PvPanicMmio<NoEvents> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<EV: PvPanicEvents + MmioEvents> PvPanicMmio<EV> {
+    /// Creates a new `PvPanicMmio` instance, which delivers the guest events
+    /// to the `pvpanic_evts` implementation of `PvPanicEvents`.
+    ///
+    /// # Arguments
+    /// * `pvpanic_evts` - The `PvPanicEvents` implementation notified about
+    ///   the panics of the guest, whose `MmioEvents` implementation tracks
+    ///   the bad accesses, with the `BadAccessPolicy::Event` policy.
+    pub const fn with_events(pvpanic_evts: EV) -> Self {
+        PvPanicMmio {
+            inner: PvPanic::with_events(pvpanic_evts),
+            bad_access_policy: BadAccessPolicy::Zero,
+        }
+    }
+
+    /// Provides a reference to the pvpanic events object.
+    pub fn events(&self) -> &EV {
+        self.inner.events()
+    }
+
+    /// Sets the handling of the accesses that don't match the event
+    /// register, which is `BadAccessPolicy::Zero` by default.
+    ///
+    /// # Arguments
+    /// * `policy` - The policy applied to the bad accesses.
+    pub fn set_bad_access_policy(&mut self, policy: BadAccessPolicy) {
+        self.bad_access_policy = policy;
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// The first byte of `data` is set to the events supported by the device,
+    /// and the other ones are cleared. The bad accesses are handled as
+    /// configured by the `BadAccessPolicy`.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The little-endian byte array storing the read value.
+    pub fn read(&self, offset: u16, data: &mut [u8]) {
+        if find_register(&MMIO_REGISTERS, offset, data.len()).is_none() {
+            if self.bad_access_policy.bad_read(data) {
+                self.events().bad_read(offset, data.len());
+            }
+            return;
+        }
+        data.fill(0);
+        data[0] = self.inner.read(EVENT_OFFSET);
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// Only the first byte of `data` is used, the other ones are ignored.
+    /// The bad accesses are handled as configured by the `BadAccessPolicy`.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The little-endian byte array to write to the register.
+    pub fn write(&mut self, offset: u16, data: &[u8]) {
+        if find_register(&MMIO_REGISTERS, offset, data.len()).is_none() {
+            if self.bad_access_policy.bad_write() {
+                self.events().bad_write(offset, data.len());
+            }
+            return;
+        }
+        self.inner.write(EVENT_OFFSET, data[0]);
+    }
+}
+
+impl<EV: PvPanicEvents> LegacyDevice for PvPanic<EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = PvPanic::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            PvPanic::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn state(&self) -> Self::State {}
+}
+
+impl<EV: PvPanicEvents + MmioEvents> LegacyDevice for PvPanicMmio<EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        PvPanicMmio::read(self, offset, data);
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        PvPanicMmio::write(self, offset, data);
+    }
+
+    fn reset(&mut self) {}
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU64;
+    use vmm_sys_util::metric::Metric;
+
+    #[derive(Default)]
+    struct ExamplePvPanicEvents {
+        panics: AtomicU64,
+        crash_loads: AtomicU64,
+        bad_accesses: AtomicU64,
+    }
+
+    impl PvPanicEvents for ExamplePvPanicEvents {
+        fn panicked(&self) {
+            self.panics.inc();
+        }
+
+        fn crash_loaded(&self) {
+            self.crash_loads.inc();
+        }
+    }
+
+    impl MmioEvents for ExamplePvPanicEvents {
+        fn bad_read(&self, _offset: u16, _len: usize) {
+            self.bad_accesses.inc();
+        }
+
+        fn bad_write(&self, _offset: u16, _len: usize) {
+            self.bad_accesses.inc();
+        }
+    }
+
+    #[test]
+    fn test_pvpanic_events() {
+        let events = Arc::new(ExamplePvPanicEvents::default());
+        let mut pvpanic = PvPanic::with_events(events.clone());
+        assert_eq!(pvpanic.read(EVENT_OFFSET), SUPPORTED_EVENTS);
+
+        pvpanic.write(EVENT_OFFSET, PVPANIC_PANICKED);
+        assert_eq!(events.panics.count(), 1);
+        pvpanic.write(EVENT_OFFSET, PVPANIC_CRASH_LOADED);
+        assert_eq!(events.crash_loads.count(), 1);
+
+        // The panic takes precedence.
+        pvpanic.write(EVENT_OFFSET, PVPANIC_PANICKED | PVPANIC_CRASH_LOADED);
+        assert_eq!(events.panics.count(), 2);
+        assert_eq!(events.crash_loads.count(), 1);
+
+        // Unsupported events and other offsets are ignored.
+        pvpanic.write(EVENT_OFFSET, 0x04);
+        pvpanic.write(EVENT_OFFSET + 1, PVPANIC_PANICKED);
+        assert_eq!(pvpanic.read(EVENT_OFFSET + 1), 0x00);
+        assert_eq!(events.panics.count(), 2);
+        assert_eq!(events.crash_loads.count(), 1);
+    }
+
+    #[test]
+    fn test_pvpanic_mmio() {
+        let events = Arc::new(ExamplePvPanicEvents::default());
+        let mut pvpanic = PvPanicMmio::with_events(events.clone());
+
+        // The supported events are in the first byte of a wider read.
+        let mut data = [0xFF; 4];
+        pvpanic.read(MMIO_EVENT_OFFSET, &mut data);
+        assert_eq!(data, [SUPPORTED_EVENTS, 0, 0, 0]);
+
+        pvpanic.write(MMIO_EVENT_OFFSET, &[PVPANIC_CRASH_LOADED, 0, 0, 0]);
+        assert_eq!(events.crash_loads.count(), 1);
+        pvpanic.write(MMIO_EVENT_OFFSET, &[PVPANIC_PANICKED]);
+        assert_eq!(events.panics.count(), 1);
+
+        // Other offsets and empty accesses are ignored.
+        pvpanic.write(MMIO_EVENT_OFFSET + 4, &[PVPANIC_PANICKED]);
+        pvpanic.write(MMIO_EVENT_OFFSET, &[]);
+        assert_eq!(events.panics.count(), 1);
+        let mut data = [0xFF; 1];
+        pvpanic.read(MMIO_EVENT_OFFSET + 4, &mut data);
+        assert_eq!(data, [0]);
+        pvpanic.read(MMIO_EVENT_OFFSET, &mut []);
+        assert_eq!(events.bad_accesses.count(), 0);
+    }
+
+    #[test]
+    fn test_pvpanic_mmio_bad_access_policy() {
+        let events = Arc::new(ExamplePvPanicEvents::default());
+        let mut pvpanic = PvPanicMmio::with_events(events.clone());
+
+        // The accesses of 3 bytes are bad.
+        pvpanic.set_bad_access_policy(BadAccessPolicy::Event);
+        let mut data = [0xFF; 3];
+        pvpanic.read(MMIO_EVENT_OFFSET, &mut data);
+        assert_eq!(data, [0xFF; 3]);
+        pvpanic.write(MMIO_EVENT_OFFSET, &[PVPANIC_PANICKED, 0, 0]);
+        assert_eq!(events.panics.count(), 0);
+        assert_eq!(events.bad_accesses.count(), 2);
+
+        pvpanic.set_bad_access_policy(BadAccessPolicy::Ignore);
+        pvpanic.read(MMIO_EVENT_OFFSET + 1, &mut data[..1]);
+        assert_eq!(data, [0xFF; 3]);
+        assert_eq!(events.bad_accesses.count(), 2);
+    }
+
+    #[test]
+    fn test_pvpanic_legacy_device() {
+        let events = Arc::new(ExamplePvPanicEvents::default());
+        let mut pvpanic = PvPanic::with_events(events.clone());
+        let mut pvpanic_mmio = PvPanicMmio::with_events(events.clone());
+
+        LegacyDevice::write(&mut pvpanic, u16::from(EVENT_OFFSET), &[PVPANIC_PANICKED]);
+        LegacyDevice::write(&mut pvpanic_mmio, MMIO_EVENT_OFFSET, &[PVPANIC_PANICKED]);
+        assert_eq!(events.panics.count(), 2);
+
+        let mut data = [0xFF; 2];
+        LegacyDevice::read(&mut pvpanic, u16::from(EVENT_OFFSET), &mut data);
+        assert_eq!(data, [0; 2]);
+        LegacyDevice::read(&mut pvpanic_mmio, MMIO_EVENT_OFFSET, &mut data);
+        assert_eq!(data, [SUPPORTED_EVENTS, 0]);
+    }
+}
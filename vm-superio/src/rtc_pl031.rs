@@ -6,11 +6,24 @@
 //! This module implements a PL031 Real Time Clock (RTC) that provides a long
 //! time base counter. This is achieved by generating an interrupt signal after
 //! counting for a programmed number of cycles of a real-time clock input.
-
-use std::convert::TryFrom;
-use std::sync::Arc;
+//!
+//! The time of the counter is taken from a [`WallClock`](trait.WallClock.html),
+//! which is the host time of the [`SystemClock`](struct.SystemClock.html) with
+//! the `std` feature. The [`SharedRtc`](struct.SharedRtc.html) also needs this
+//! feature, for its `Mutex`.
+
+use alloc::sync::Arc;
+use core::convert::{Infallible, TryFrom};
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "std")]
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::mmio::{find_register, BadAccessPolicy, MmioRegister};
+use crate::{LegacyDevice, Persist, StateError};
+
 // The following defines are mapping to the specification:
 // https://developer.arm.com/documentation/ddi0224/c/Programmers-model/Summary-of-RTC-registers
 //
@@ -38,6 +51,37 @@ const AMBA_IDS: [u8; 8] = [0x31, 0x10, 0x04, 0x00, 0x0d, 0xf0, 0x05, 0xb1];
 const AMBA_ID_LOW: u16 = 0xFE0;
 const AMBA_ID_HIGH: u16 = 0xFFF;
 
+// The registers are accessed as 32-bit words. The accesses to the AMBA ID
+// registers are aligned down to the start of the register.
+const REGISTERS: [MmioRegister; 16] = [
+    MmioRegister::new(RTCDR, 4),
+    MmioRegister::new(RTCMR, 4),
+    MmioRegister::new(RTCLR, 4),
+    MmioRegister::new(RTCCR, 4),
+    MmioRegister::new(RTCIMSC, 4),
+    MmioRegister::new(RTCRIS, 4),
+    MmioRegister::new(RTCMIS, 4),
+    MmioRegister::new(RTCICR, 4),
+    amba_id_register(0),
+    amba_id_register(1),
+    amba_id_register(2),
+    amba_id_register(3),
+    amba_id_register(4),
+    amba_id_register(5),
+    amba_id_register(6),
+    amba_id_register(7),
+];
+
+const fn amba_id_register(index: u16) -> MmioRegister {
+    MmioRegister {
+        offset: AMBA_ID_LOW + index * 4,
+        size: 4,
+        min_access: 4,
+        max_access: 4,
+        align: 1,
+    }
+}
+
 /// Defines a series of callbacks that are invoked in response to the occurrence of specific
 /// failure or missed events as part of the RTC operation (e.g., write to an invalid offset). The
 /// methods below can be implemented by a backend that keeps track of such events by incrementing
@@ -74,9 +118,47 @@ impl<EV: RtcEvents> RtcEvents for Arc<EV> {
     }
 }
 
+/// A source of wall clock time, from which the RTC derives the value of its
+/// counter.
+///
+/// Using a trait allows the VMMs without the `std` feature to provide the
+/// time of their platform, and the tests to control the time seen by the
+/// device.
+pub trait WallClock {
+    /// Returns the number of seconds elapsed since the UNIX epoch.
+    fn now_secs(&self) -> u64;
+}
+
+impl<C: WallClock> WallClock for Arc<C> {
+    fn now_secs(&self) -> u64 {
+        self.as_ref().now_secs()
+    }
+}
+
+/// A `WallClock` based on the system time of the host. It needs the `std`
+/// feature.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl WallClock for SystemClock {
+    fn now_secs(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            // This expect should never fail because UNIX_EPOCH is in 1970,
+            // and the only possible failure is if `now` time is before UNIX EPOCH.
+            .expect("SystemTime::duration_since failed")
+            .as_secs()
+    }
+}
+
 /// A PL031 Real Time Clock (RTC) that emulates a long time base counter.
 ///
-/// This structure emulates the registers for the RTC.
+/// This structure emulates the registers for the RTC. The accesses to the
+/// invalid offsets are handled as configured by a
+/// [`BadAccessPolicy`](../mmio/enum.BadAccessPolicy.html), which reports
+/// them through [`RtcEvents`](trait.RtcEvents.html) by default.
 ///
 /// # Example
 ///
@@ -113,7 +195,7 @@ impl<EV: RtcEvents> RtcEvents for Arc<EV> {
 /// assert!(u32::from_le_bytes(data) > v);
 /// ```
 #[derive(Debug)]
-pub struct Rtc<EV: RtcEvents> {
+pub struct Rtc<EV: RtcEvents, C: WallClock> {
     // The load register.
     lr: u32,
 
@@ -137,10 +219,18 @@ pub struct Rtc<EV: RtcEvents> {
 
     // Used for tracking the occurrence of significant events.
     events: EV,
+
+    // The handling of the invalid accesses.
+    bad_access_policy: BadAccessPolicy,
+
+    // The source of the time of the counter.
+    clock: C,
 }
 
 /// The state of the Rtc device.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RtcState {
     /// The load register.
     pub lr: u32,
@@ -154,29 +244,40 @@ pub struct RtcState {
     pub ris: u32,
 }
 
-fn get_current_time() -> u32 {
-    let epoch_time = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        // This expect should never fail because UNIX_EPOCH is in 1970,
-        // and the only possible failure is if `now` time is before UNIX EPOCH.
-        .expect("SystemTime::duration_since failed");
+fn get_current_time<C: WallClock>(clock: &C) -> u32 {
     // The following conversion is safe because u32::MAX would correspond to
     // year 2106. By then we would not be able to use the RTC in its
     // current form because RTC only works with 32-bits registers, and a bigger
     // time value would not fit.
-    epoch_time.as_secs() as u32
+    clock.now_secs() as u32
 }
 
-impl Default for Rtc<NoEvents> {
+fn get_rtc_value<C: WallClock>(clock: &C, offset: i64) -> u32 {
+    // The RTC value is the time + offset as per:
+    // https://developer.arm.com/documentation/ddi0224/c/Functional-overview/RTC-functional-description/Update-block
+    //
+    // In the unlikely case of the value not fitting in an u32, we just set the time to
+    // the current time on the host.
+    let current_host_time = get_current_time(clock);
+    u32::try_from(
+        (current_host_time as i64)
+            .checked_add(offset)
+            .unwrap_or(current_host_time as i64),
+    )
+    .unwrap_or(current_host_time)
+}
+
+#[cfg(feature = "std")]
+impl Default for Rtc<NoEvents, SystemClock> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-// This is the state from which a fresh Rtc can be created.
-#[allow(clippy::derivable_impls)]
-impl Default for RtcState {
-    fn default() -> Self {
+impl RtcState {
+    /// Returns the state from which a fresh Rtc is created, which is also its
+    /// `Default`. It is usable in constant expressions.
+    pub const fn default_state() -> Self {
         RtcState {
             // The load register is initialized to 0.
             lr: 0,
@@ -190,27 +291,74 @@ impl Default for RtcState {
             ris: 0,
         }
     }
+
+    /// Checks that the device can be in this state, e.g. before restoring a
+    /// state deserialized from a snapshot: the interrupt mask and the raw
+    /// interrupt status are single bits.
+    pub fn validate(&self) -> Result<(), StateError> {
+        for (register, value) in [("imsc", self.imsc), ("ris", self.ris)] {
+            if value > 1 {
+                return Err(StateError::InvalidRegister { register, value });
+            }
+        }
+        Ok(())
+    }
 }
 
-impl Rtc<NoEvents> {
+impl Default for RtcState {
+    fn default() -> Self {
+        Self::default_state()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Rtc<NoEvents, SystemClock> {
     /// Creates a new `AMBA PL031 RTC` instance without any metric capabilities. The instance is
-    /// created from the default state.
-    pub fn new() -> Self {
-        Self::from_state(&RtcState::default(), NoEvents)
+    /// created from the default state, and counts the system time of the host.
+    pub const fn new() -> Self {
+        Self::from_state(&RtcState::default_state(), NoEvents)
     }
 }
 
-impl<EV: RtcEvents> Rtc<EV> {
+#[cfg(feature = "std")]
+impl<EV: RtcEvents> Rtc<EV, SystemClock> {
     /// Creates a new `AMBA PL031 RTC` instance from a given `state` and that is able to track
-    /// events during operation using the passed `rtc_events` object.
+    /// events during operation using the passed `rtc_events` object. The instance counts the
+    /// system time of the host.
     /// For creating the instance from a fresh state, [`with_events`](#method.with_events) or
     /// [`new`](#method.new) methods can be used.
     ///
     /// # Arguments
     /// * `state` - A reference to the state from which the `Rtc` is constructed.
     /// * `rtc_events` - The `RtcEvents` implementation used to track the occurrence
-    ///                  of failure or missed events in the RTC operation.
-    pub fn from_state(state: &RtcState, rtc_events: EV) -> Self {
+    ///   of failure or missed events in the RTC operation.
+    pub const fn from_state(state: &RtcState, rtc_events: EV) -> Self {
+        Self::from_state_with_clock(state, rtc_events, SystemClock)
+    }
+
+    /// Creates a new `AMBA PL031 RTC` instance that is able to track events during operation using
+    /// the passed `rtc_events` object. The instance is created from the default state, and counts
+    /// the system time of the host.
+    ///
+    /// # Arguments
+    /// * `rtc_events` - The `RtcEvents` implementation used to track the occurrence
+    ///   of failure or missed events in the RTC operation.
+    pub const fn with_events(rtc_events: EV) -> Self {
+        Self::from_state(&RtcState::default_state(), rtc_events)
+    }
+}
+
+impl<EV: RtcEvents, C: WallClock> Rtc<EV, C> {
+    /// Creates a new `AMBA PL031 RTC` instance from a given `state`, which counts the time of
+    /// `clock`, and is able to track events during operation using the passed `rtc_events`
+    /// object. Without the `std` feature, this is the only way of creating an `Rtc`.
+    ///
+    /// # Arguments
+    /// * `state` - A reference to the state from which the `Rtc` is constructed.
+    /// * `rtc_events` - The `RtcEvents` implementation used to track the occurrence
+    ///   of failure or missed events in the RTC operation.
+    /// * `clock` - The source of the time of the counter.
+    pub const fn from_state_with_clock(state: &RtcState, rtc_events: EV, clock: C) -> Self {
         Rtc {
             lr: state.lr,
             offset: state.offset,
@@ -220,21 +368,14 @@ impl<EV: RtcEvents> Rtc<EV> {
             // A struct implementing `RtcEvents` for tracking the occurrence of
             // significant events.
             events: rtc_events,
+            bad_access_policy: BadAccessPolicy::Event,
+            clock,
         }
     }
 
-    /// Creates a new `AMBA PL031 RTC` instance that is able to track events during operation using
-    /// the passed `rtc_events` object. The instance is created from the default state.
-    ///
-    /// # Arguments
-    /// * `rtc_events` - The `RtcEvents` implementation used to track the occurrence
-    ///                  of failure or missed events in the RTC operation.
-    pub fn with_events(rtc_events: EV) -> Self {
-        Self::from_state(&RtcState::default(), rtc_events)
-    }
-
     /// Returns the state of the RTC.
     pub fn state(&self) -> RtcState {
+        trace_device!("rtc", "save");
         RtcState {
             lr: self.lr,
             offset: self.offset,
@@ -249,19 +390,32 @@ impl<EV: RtcEvents> Rtc<EV> {
         &self.events
     }
 
+    /// Sets the handling of the accesses to invalid offsets, and of the
+    /// writes to read-only registers and reads from write-only ones, which
+    /// is `BadAccessPolicy::Event` by default.
+    ///
+    /// # Arguments
+    /// * `policy` - The policy applied to the bad accesses, whose events are
+    ///   reported through `RtcEvents::invalid_read` and
+    ///   `RtcEvents::invalid_write`.
+    pub fn set_bad_access_policy(&mut self, policy: BadAccessPolicy) {
+        self.bad_access_policy = policy;
+    }
+
+    pub(crate) fn bad_read(&self, data: &mut [u8; 4]) {
+        if self.bad_access_policy.bad_read(data) {
+            self.events.invalid_read();
+        }
+    }
+
+    pub(crate) fn bad_write(&self) {
+        if self.bad_access_policy.bad_write() {
+            self.events.invalid_write();
+        }
+    }
+
     fn get_rtc_value(&self) -> u32 {
-        // The RTC value is the time + offset as per:
-        // https://developer.arm.com/documentation/ddi0224/c/Functional-overview/RTC-functional-description/Update-block
-        //
-        // In the unlikely case of the value not fitting in an u32, we just set the time to
-        // the current time on the host.
-        let current_host_time = get_current_time();
-        u32::try_from(
-            (current_host_time as i64)
-                .checked_add(self.offset)
-                .unwrap_or(current_host_time as i64),
-        )
-        .unwrap_or(current_host_time)
+        get_rtc_value(&self.clock, self.offset)
     }
 
     /// Handles a write request from the driver at `offset` offset from the
@@ -269,7 +423,7 @@ impl<EV: RtcEvents> Rtc<EV> {
     ///
     /// # Arguments
     /// * `offset` - The offset from the base register specifying
-    ///              the register to be written.
+    ///   the register to be written.
     /// * `data` - The little endian, 4 byte array to write to the register
     ///
     /// # Example
@@ -278,6 +432,11 @@ impl<EV: RtcEvents> Rtc<EV> {
     /// [`Example` section from `Rtc`](struct.Rtc.html#example).
     pub fn write(&mut self, offset: u16, data: &[u8; 4]) {
         let val = u32::from_le_bytes(*data);
+        trace_access!("rtc", "write", offset, val);
+        let offset = match find_register(&REGISTERS, offset, data.len()) {
+            Some(access) => REGISTERS[access.index].offset,
+            None => return self.bad_write(),
+        };
 
         match offset {
             RTCMR => {
@@ -294,7 +453,7 @@ impl<EV: RtcEvents> Rtc<EV> {
                 self.lr = val;
                 // Both lr & offset are u32, hence the following
                 // conversions are safe, and the result fits in an i64.
-                self.offset = self.lr as i64 - get_current_time() as i64;
+                self.offset = self.lr as i64 - get_current_time(&self.clock) as i64;
             }
             RTCCR => {
                 // Writing 1 to the control register resets the RTC value,
@@ -314,10 +473,10 @@ impl<EV: RtcEvents> Rtc<EV> {
                 self.ris &= !val;
             }
             _ => {
-                // RTCDR, RTCRIS, and RTCMIS are read-only, so writes to these
-                // registers or to an invalid offset are ignored; however,
-                // We increment the invalid_write() method of the events struct.
-                self.events.invalid_write();
+                // RTCDR, RTCRIS, RTCMIS and the AMBA IDs are read-only, so
+                // writes to these registers are handled like the ones to an
+                // invalid offset.
+                self.bad_write();
             }
         };
     }
@@ -327,7 +486,7 @@ impl<EV: RtcEvents> Rtc<EV> {
     ///
     /// # Arguments
     /// * `offset` - The offset from the base register specifying
-    ///              the register to be read.
+    ///   the register to be read.
     /// * `data` - The little-endian, 4 byte array storing the read value.
     ///
     /// # Example
@@ -335,6 +494,11 @@ impl<EV: RtcEvents> Rtc<EV> {
     /// You can see an example of how to use this function in the
     /// [`Example` section from `Rtc`](struct.Rtc.html#example).
     pub fn read(&mut self, offset: u16, data: &mut [u8; 4]) {
+        let offset = match find_register(&REGISTERS, offset, data.len()) {
+            Some(access) => REGISTERS[access.index].offset,
+            None => return self.bad_read(data),
+        };
+
         let v = if (AMBA_ID_LOW..=AMBA_ID_HIGH).contains(&offset) {
             let index = ((offset - AMBA_ID_LOW) >> 2) as usize;
             u32::from(AMBA_IDS[index])
@@ -352,25 +516,237 @@ impl<EV: RtcEvents> Rtc<EV> {
                 RTCRIS => self.ris,
                 RTCMIS => self.ris & self.imsc,
                 _ => {
-                    // RTCICR is write only.  For reads of this register or
-                    // an invalid offset, call the invalid_read method of the
-                    // events struct and return.
-                    self.events.invalid_read();
+                    // RTCICR is write only, so reads of this register are
+                    // handled like the ones of an invalid offset.
+                    self.bad_read(data);
                     return;
                 }
             }
         };
 
+        trace_access!("rtc", "read", offset, v);
         *data = v.to_le_bytes();
     }
 }
 
+impl<EV: RtcEvents, C: WallClock> LegacyDevice for Rtc<EV, C> {
+    type State = RtcState;
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match <&mut [u8; 4]>::try_from(data) {
+            Ok(data) => Rtc::read(self, offset, data),
+            Err(_) => self.bad_read(&mut [0; 4]),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        match <&[u8; 4]>::try_from(data) {
+            Ok(data) => Rtc::write(self, offset, data),
+            Err(_) => self.bad_write(),
+        }
+    }
+
+    // The RTC keeps the time across the resets, like a battery backed one.
+    fn reset(&mut self) {
+        self.mr = 0;
+        self.imsc = 0;
+        self.ris = 0;
+    }
+
+    fn state(&self) -> Self::State {
+        Rtc::state(self)
+    }
+}
+
+// The RTC is restored with its events object and its clock, and never fails.
+impl<EV: RtcEvents, C: WallClock> Persist for Rtc<EV, C> {
+    type State = RtcState;
+    type ConstructorArgs = (EV, C);
+    type Error = Infallible;
+
+    fn save(&self) -> Self::State {
+        self.state()
+    }
+
+    fn restore(
+        (rtc_events, clock): Self::ConstructorArgs,
+        state: &Self::State,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self::from_state_with_clock(state, rtc_events, clock))
+    }
+}
+
+/// A [`Rtc`](struct.Rtc.html) shared between several threads, such as the
+/// vCPU threads.
+///
+/// The reads of the read-only registers, i.e. of the data register, of the
+/// interrupt status registers, of the control register and of the AMBA ID
+/// registers, don't take the lock of the device. They are served from copies
+/// of the offset of the counter and of the interrupt registers, which are
+/// updated after each access changing them, and of the clock of the RTC. It
+/// needs the `std` feature.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use std::thread;
+/// # use vm_superio::rtc_pl031::SharedRtc;
+/// # use vm_superio::Rtc;
+/// const RTCDR: u16 = 0x0; // Data Register.
+/// const RTCLR: u16 = 0x8; // Load Register.
+///
+/// let rtc = Arc::new(SharedRtc::new(Rtc::new()));
+///
+/// // A vCPU sets the time, while another one reads it.
+/// let vcpu = rtc.clone();
+/// thread::spawn(move || vcpu.write(RTCLR, &1_000_000u32.to_le_bytes()))
+///     .join()
+///     .unwrap();
+///
+/// let mut data = [0; 4];
+/// rtc.read(RTCDR, &mut data);
+/// assert!(u32::from_le_bytes(data) >= 1_000_000);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct SharedRtc<EV: RtcEvents, C: WallClock> {
+    inner: Mutex<Rtc<EV, C>>,
+    // A copy of the clock of the RTC.
+    clock: C,
+    // The offset applied to the counter to get the RTC value.
+    offset: AtomicI64,
+    // The interrupt mask in the low half, and the raw interrupt value in the
+    // high half, so that the masked interrupt status is read at once.
+    interrupts: AtomicU64,
+}
+
+#[cfg(feature = "std")]
+impl<EV: RtcEvents, C: WallClock + Clone> SharedRtc<EV, C> {
+    /// Creates a new `SharedRtc` instance, sharing `rtc`.
+    ///
+    /// # Arguments
+    /// * `rtc` - The RTC to share.
+    pub fn new(rtc: Rtc<EV, C>) -> Self {
+        SharedRtc {
+            offset: AtomicI64::new(rtc.offset),
+            interrupts: AtomicU64::new(Self::interrupts_of(&rtc)),
+            clock: rtc.clock.clone(),
+            inner: Mutex::new(rtc),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<EV: RtcEvents, C: WallClock> SharedRtc<EV, C> {
+    fn interrupts_of(rtc: &Rtc<EV, C>) -> u64 {
+        u64::from(rtc.imsc) | (u64::from(rtc.ris) << 32)
+    }
+
+    /// Calls `f` with the locked RTC, and returns its result.
+    ///
+    /// The registers read without locking the RTC are updated once `f`
+    /// returns.
+    ///
+    /// # Arguments
+    /// * `f` - The function accessing the RTC.
+    pub fn with<R, F: FnOnce(&mut Rtc<EV, C>) -> R>(&self, f: F) -> R {
+        let mut rtc = self.inner.lock().unwrap();
+        let result = f(&mut rtc);
+        self.offset.store(rtc.offset, Ordering::Release);
+        self.interrupts
+            .store(Self::interrupts_of(&rtc), Ordering::Release);
+        result
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base register address, as [`Rtc::read`](struct.Rtc.html#method.read)
+    /// does.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The little-endian, 4 byte array storing the read value.
+    pub fn read(&self, offset: u16, data: &mut [u8; 4]) {
+        let reg = match find_register(&REGISTERS, offset, data.len()) {
+            Some(access) => REGISTERS[access.index].offset,
+            None => return self.with(|rtc| rtc.bad_read(data)),
+        };
+
+        let interrupts = self.interrupts.load(Ordering::Acquire);
+        let (imsc, ris) = (interrupts as u32, (interrupts >> 32) as u32);
+        let v = if (AMBA_ID_LOW..=AMBA_ID_HIGH).contains(&reg) {
+            u32::from(AMBA_IDS[((reg - AMBA_ID_LOW) >> 2) as usize])
+        } else {
+            match reg {
+                RTCDR => get_rtc_value(&self.clock, self.offset.load(Ordering::Acquire)),
+                RTCCR => 1,
+                RTCRIS => ris,
+                RTCMIS => ris & imsc,
+                _ => return self.with(|rtc| rtc.read(offset, data)),
+            }
+        };
+
+        trace_access!("rtc", "read", offset, v);
+        *data = v.to_le_bytes();
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base register address, as [`Rtc::write`](struct.Rtc.html#method.write)
+    /// does.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The little endian, 4 byte array to write to the register
+    pub fn write(&self, offset: u16, data: &[u8; 4]) {
+        self.with(|rtc| rtc.write(offset, data))
+    }
+
+    /// Returns the state of the RTC.
+    pub fn state(&self) -> RtcState {
+        self.inner.lock().unwrap().state()
+    }
+
+    /// Consumes the wrapper, and returns the shared RTC.
+    pub fn into_inner(self) -> Rtc<EV, C> {
+        self.inner.into_inner().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<EV: RtcEvents, C: WallClock> LegacyDevice for SharedRtc<EV, C> {
+    type State = RtcState;
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match <&mut [u8; 4]>::try_from(data) {
+            Ok(data) => SharedRtc::read(self, offset, data),
+            Err(_) => self.with(|rtc| rtc.bad_read(&mut [0; 4])),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        match <&[u8; 4]>::try_from(data) {
+            Ok(data) => SharedRtc::write(self, offset, data),
+            Err(_) => self.with(|rtc| rtc.bad_write()),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.with(|rtc| rtc.reset());
+    }
+
+    fn state(&self) -> Self::State {
+        SharedRtc::state(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::MockClock;
 
     use std::sync::atomic::AtomicU64;
-    use std::sync::Arc;
     use std::thread;
     use std::time::Duration;
 
@@ -398,7 +774,7 @@ mod tests {
         // This is a regression test for: https://github.com/rust-vmm/vm-superio/issues/47.
         // The problem is that the time in the guest would show up as in the 1970s.
         let mut rtc = Rtc::new();
-        let expected_time = get_current_time();
+        let expected_time = get_current_time(&SystemClock);
 
         let mut actual_time = [0u8; 4];
         rtc.read(RTCDR, &mut actual_time);
@@ -408,6 +784,33 @@ mod tests {
         assert!(u32::from_le_bytes(actual_time) - expected_time <= 1);
     }
 
+    #[test]
+    fn test_wall_clock() {
+        let clock = MockClock::new();
+        clock.set_secs(1000);
+        let mut rtc = Rtc::from_state_with_clock(&RtcState::default(), NoEvents, clock.clone());
+        let mut data = [0; 4];
+
+        rtc.read(RTCDR, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 1000);
+
+        // The guest sets the time, which then follows the clock.
+        rtc.write(RTCLR, &5000u32.to_le_bytes());
+        assert_eq!(rtc.state().offset, 4000);
+        clock.set_secs(1010);
+        rtc.read(RTCDR, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 5010);
+
+        // The restored RTC and the shared one count the time of their clock.
+        let mut restored = Rtc::restore((NoEvents, clock.clone()), &rtc.state()).unwrap();
+        restored.read(RTCDR, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 5010);
+        let shared = SharedRtc::new(restored);
+        clock.set_secs(1020);
+        shared.read(RTCDR, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 5020);
+    }
+
     #[test]
     fn test_data_register() {
         // Verify we can read the Data Register, but not write to it,
@@ -484,7 +887,7 @@ mod tests {
         // Read and write to the load register to confirm we can both
         // set the RTC value forward and backward.
         // This also tests the default Rtc constructor.
-        let mut rtc: Rtc<NoEvents> = Default::default();
+        let mut rtc: Rtc<NoEvents, SystemClock> = Default::default();
         let mut data = [0; 4];
 
         // Get the RTC value with a load register of 0 (the initial value).
@@ -492,7 +895,7 @@ mod tests {
         let old_val = u32::from_le_bytes(data);
 
         // Increment LR and verify that the value was updated.
-        let lr = get_current_time() + 100;
+        let lr = get_current_time(&SystemClock) + 100;
         data = lr.to_le_bytes();
         rtc.write(RTCLR, &data);
 
@@ -512,13 +915,13 @@ mod tests {
         assert!(new_val > old_val);
 
         // Set the LR in the past, and check that the RTC value is updated.
-        let lr = get_current_time() - 100;
+        let lr = get_current_time(&SystemClock) - 100;
         data = lr.to_le_bytes();
         rtc.write(RTCLR, &data);
 
         rtc.read(RTCDR, &mut data);
         let rtc_value = u32::from_le_bytes(data);
-        assert!(rtc_value < get_current_time());
+        assert!(rtc_value < get_current_time(&SystemClock));
 
         // Checking that setting the maximum possible value for the LR does
         // not cause overflows.
@@ -667,7 +1070,7 @@ mod tests {
         let mut data: [u8; 4];
 
         // Let's move the guest time in the future.
-        let lr = get_current_time() + 100;
+        let lr = get_current_time(&SystemClock) + 100;
         data = lr.to_le_bytes();
         rtc.write(RTCLR, &data);
 
@@ -876,6 +1279,30 @@ mod tests {
         assert_eq!(rtc.events.invalid_write_count.count(), 2);
     }
 
+    #[test]
+    fn test_bad_access_policy() {
+        let metrics = Arc::new(ExampleRtcMetrics::default());
+        let mut rtc = Rtc::with_events(metrics);
+
+        // The bad reads read as 0, without being reported.
+        rtc.set_bad_access_policy(BadAccessPolicy::Zero);
+        let mut data = 123u32.to_le_bytes();
+        rtc.read(RTCLR + 2, &mut data);
+        assert_eq!(data, [0; 4]);
+        data = 123u32.to_le_bytes();
+        rtc.read(RTCICR, &mut data);
+        assert_eq!(data, [0; 4]);
+        rtc.write(RTCDR, &data);
+        assert_eq!(rtc.events.invalid_read_count.count(), 0);
+        assert_eq!(rtc.events.invalid_write_count.count(), 0);
+
+        rtc.set_bad_access_policy(BadAccessPolicy::Ignore);
+        data = 123u32.to_le_bytes();
+        rtc.read(AMBA_ID_HIGH + 4, &mut data);
+        assert_eq!(123, u32::from_le_bytes(data));
+        assert_eq!(rtc.events.invalid_read_count.count(), 0);
+    }
+
     #[test]
     fn test_invalid_read_offset() {
         // Test that reading from an invalid register offset has no effect,
@@ -918,7 +1345,7 @@ mod tests {
         let first_read = u32::from_le_bytes(data);
 
         // Increment LR and verify that the value was updated.
-        let lr = get_current_time() + 100;
+        let lr = get_current_time(&SystemClock) + 100;
         data = lr.to_le_bytes();
         rtc.write(RTCLR, &data);
 
@@ -981,4 +1408,176 @@ mod tests {
         let mut data = [0u8; 4];
         rtc.read(RTCDR, &mut data);
     }
+
+    #[test]
+    fn test_rtc_legacy_device() {
+        let metrics = Arc::new(ExampleRtcMetrics::default());
+        let mut rtc = Rtc::with_events(metrics.clone());
+
+        LegacyDevice::write(&mut rtc, RTCLR, &1000u32.to_le_bytes());
+        LegacyDevice::write(&mut rtc, RTCIMSC, &1u32.to_le_bytes());
+        let mut data = [0; 4];
+        LegacyDevice::read(&mut rtc, RTCIMSC, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 1);
+
+        // The accesses which aren't 4 bytes wide are invalid.
+        let mut data = [0xFF; 2];
+        LegacyDevice::read(&mut rtc, RTCIMSC, &mut data);
+        assert_eq!(data, [0xFF; 2]);
+        LegacyDevice::write(&mut rtc, RTCIMSC, &[0; 2]);
+        assert_eq!(metrics.invalid_read_count.count(), 1);
+        assert_eq!(metrics.invalid_write_count.count(), 1);
+
+        // The reset clears the interrupt mask, and keeps the time.
+        rtc.reset();
+        let state = LegacyDevice::state(&rtc);
+        assert_eq!(state.imsc, 0);
+        assert_eq!(state.lr, 1000);
+    }
+
+    #[cfg(feature = "fuzzing")]
+    #[test]
+    fn test_arbitrary_state() {
+        let edge_states = [i64::MIN, i64::MAX].map(|offset| RtcState {
+            lr: u32::MAX,
+            offset,
+            mr: u32::MAX,
+            imsc: u32::MAX,
+            ris: u32::MAX,
+        });
+        crate::testing::for_each_arbitrary_state(&edge_states, |state| {
+            let mut rtc = Rtc::from_state(state, NoEvents);
+            let mut data = [0u8; 4];
+            for offset in (0..0x20).step_by(4) {
+                rtc.read(offset, &mut data);
+            }
+            rtc.write(RTCLR, &data);
+            assert_eq!(Rtc::from_state(&rtc.state(), NoEvents).state(), rtc.state());
+        });
+    }
+
+    #[test]
+    fn test_const_rtc() {
+        const STATE: RtcState = RtcState::default_state();
+        static RTC: std::sync::Mutex<Rtc<NoEvents, SystemClock>> =
+            std::sync::Mutex::new(Rtc::new());
+
+        let mut rtc = RTC.lock().unwrap();
+        assert_eq!(STATE, RtcState::default());
+        assert_eq!(rtc.state(), STATE);
+        rtc.write(RTCLR, &0x1234u32.to_le_bytes());
+        assert_eq!(rtc.state().lr, 0x1234);
+    }
+
+    #[test]
+    fn test_shared_rtc() {
+        let events = Arc::new(ExampleRtcMetrics::default());
+        let shared = SharedRtc::new(Rtc::with_events(events.clone()));
+        let mut data = [0; 4];
+
+        // The read-only registers are read without locking the RTC.
+        shared.write(RTCLR, &1_000_000u32.to_le_bytes());
+        shared.read(RTCDR, &mut data);
+        assert!(u32::from_le_bytes(data) >= 1_000_000);
+        shared.read(RTCCR, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 1);
+        for (index, id) in AMBA_IDS.iter().enumerate() {
+            shared.read(AMBA_ID_LOW + index as u16 * 4, &mut data);
+            assert_eq!(u32::from_le_bytes(data), u32::from(*id));
+        }
+
+        // The interrupt registers follow the accesses through `with`.
+        shared.write(RTCIMSC, &1u32.to_le_bytes());
+        shared.with(|rtc| rtc.ris = 1);
+        shared.read(RTCRIS, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 1);
+        shared.read(RTCMIS, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 1);
+        shared.write(RTCICR, &1u32.to_le_bytes());
+        shared.read(RTCMIS, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 0);
+
+        // The other registers, and the invalid accesses, go through the RTC.
+        shared.read(RTCLR, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 1_000_000);
+        shared.read(RTCICR, &mut data);
+        shared.write(RTCDR, &data);
+        shared.read(0x20, &mut data);
+        assert_eq!(events.invalid_read_count.count(), 2);
+        assert_eq!(events.invalid_write_count.count(), 1);
+
+        let mut shared = shared;
+        LegacyDevice::write(&mut shared, RTCMR, &[0; 2]);
+        assert_eq!(events.invalid_write_count.count(), 2);
+        LegacyDevice::reset(&mut shared);
+        assert_eq!(shared.state().imsc, 0);
+        assert_eq!(shared.into_inner().state().lr, 1_000_000);
+    }
+
+    #[test]
+    fn test_shared_rtc_threads() {
+        let shared = Arc::new(SharedRtc::new(Rtc::new()));
+
+        // The vCPUs read the time while another one keeps setting it.
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let rtc = shared.clone();
+                thread::spawn(move || {
+                    let mut data = [0; 4];
+                    for _ in 0..1000 {
+                        rtc.read(RTCDR, &mut data);
+                        assert!(u32::from_le_bytes(data) >= 1_000_000);
+                    }
+                })
+            })
+            .collect();
+        shared.write(RTCLR, &1_000_000u32.to_le_bytes());
+        for i in 0..1000u32 {
+            shared.write(RTCLR, &(1_000_000 + i).to_le_bytes());
+        }
+        readers.into_iter().for_each(|t| t.join().unwrap());
+    }
+
+    #[test]
+    fn test_persist() {
+        let mut rtc = Rtc::new();
+        rtc.write(RTCLR, &0x1234u32.to_le_bytes());
+        rtc.write(RTCIMSC, &1u32.to_le_bytes());
+
+        let state = rtc.save();
+        assert_eq!(state, rtc.state());
+        let events = Arc::new(ExampleRtcMetrics::default());
+        let mut restored = Rtc::restore((events.clone(), SystemClock), &state).unwrap();
+        assert_eq!(restored.save(), state);
+        restored.write(RTCDR, &[0; 4]);
+        assert_eq!(events.invalid_write_count.count(), 1);
+    }
+
+    #[test]
+    fn test_state_validate() {
+        assert_eq!(RtcState::default().validate(), Ok(()));
+
+        let state = RtcState {
+            imsc: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            state.validate(),
+            Err(StateError::InvalidRegister {
+                register: "imsc",
+                value: 2,
+            })
+        );
+        let state = RtcState {
+            ris: u32::MAX,
+            ..Default::default()
+        };
+        assert_eq!(
+            state.validate(),
+            Err(StateError::InvalidRegister {
+                register: "ris",
+                value: u32::MAX,
+            })
+        );
+    }
 }
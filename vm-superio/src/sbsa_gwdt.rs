@@ -0,0 +1,598 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the SBSA Generic Watchdog.
+//!
+//! The watchdog, defined by the Arm Server Base System Architecture, is
+//! handled by the Linux `sbsa_gwdt` driver. It compares the system counter
+//! with a compare value, which the guest moves forward by refreshing the
+//! watchdog. The first time the counter reaches the compare value, the
+//! watchdog asserts the first watch signal (WS0), which is an interrupt, and
+//! refreshes itself. If the counter reaches the compare value again before
+//! the guest refreshes the watchdog, the watchdog asserts the second watch
+//! signal (WS1), which resets the system.
+//!
+//! The registers are split in two frames: the refresh frame, which the guest
+//! writes for refreshing the watchdog, and the control frame.
+
+use core::convert::TryFrom;
+use core::result::Result;
+
+use crate::pit::ClockSource;
+use crate::watchdog::{NoEvents, WatchdogAction, WatchdogEvents};
+use crate::{LegacyDevice, Trigger};
+
+// The following defines are mapping to the specification:
+// https://developer.arm.com/documentation/den0029/latest
+//
+// Offsets of the registers in the refresh frame.
+const WRR: u16 = 0x000; // Watchdog Refresh Register.
+
+// Offsets of the registers in the control frame.
+const WCS: u16 = 0x000; // Watchdog Control and Status Register.
+const WOR: u16 = 0x008; // Watchdog Offset Register.
+const WCV_LOW: u16 = 0x010; // Watchdog Compare Value Register, bits 31-0.
+const WCV_HIGH: u16 = 0x014; // Watchdog Compare Value Register, bits 63-32.
+
+// Size of each frame, which is also the offset of the control frame through
+// the `LegacyDevice` interface.
+const FRAME_SIZE: u16 = 0x1000;
+
+// Offset of the interface identification register, in both frames.
+const W_IIDR: u16 = 0xFCC;
+
+// The interface identification register reports the architecture version 0,
+// with a 32 bit offset register, implemented by Arm.
+const IIDR_VALUE: u32 = 0x0000_043B;
+
+// Control and status register bits.
+// Enables the watchdog.
+const WCS_EN: u32 = 1 << 0;
+// The first watch signal.
+const WCS_WS0: u32 = 1 << 1;
+// The second watch signal.
+const WCS_WS1: u32 = 1 << 2;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// An SBSA Generic Watchdog.
+///
+/// The registers of the refresh frame are accessed through
+/// [`read_refresh`](#method.read_refresh) and
+/// [`write_refresh`](#method.write_refresh), while the ones of the control
+/// frame are accessed through [`read_control`](#method.read_control) and
+/// [`write_control`](#method.write_control). Through the
+/// [`LegacyDevice`](../trait.LegacyDevice.html) interface, the frames are
+/// consecutive pages, with the refresh frame first.
+///
+/// The system counter is derived from the time reported by a
+/// [`ClockSource`](../pit/trait.ClockSource.html), at the frequency of the
+/// guest generic timer (CNTFRQ_EL0). The VMM arms a timer for the deadline
+/// returned by [`next_deadline`](#method.next_deadline), and calls
+/// [`on_timer_expired`](#method.on_timer_expired) when it fires. Two
+/// [`Trigger`](../trait.Trigger.html) objects are used: one for the
+/// interrupt raised by WS0, and one for the
+/// [expiry action](#method.set_expiry_action) taken on WS1, which is the
+/// system reset by default.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::pit::MonotonicClock;
+/// # use vm_superio::sbsa_gwdt::SbsaGwdt;
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut wdt = SbsaGwdt::new(DummyTrigger, DummyTrigger, MonotonicClock::new(), 62_500_000);
+///
+/// // Set a 10 second timeout for WS0, and enable the watchdog.
+/// wdt.write_control(0x008, &625_000_000u32.to_le_bytes());
+/// wdt.write_control(0x000, &1u32.to_le_bytes());
+/// assert!(wdt.next_deadline().is_some());
+///
+/// // Refresh it periodically.
+/// wdt.write_refresh(0x000, &0u32.to_le_bytes());
+/// ```
+#[derive(Debug)]
+pub struct SbsaGwdt<T: Trigger, C: ClockSource, EV: WatchdogEvents> {
+    // Interrupt raised by the first watch signal.
+    ws0_evt: T,
+    // Expiry action event object, signaled by the second watch signal.
+    ws1_evt: T,
+    clock: C,
+    // The frequency of the system counter.
+    counter_hz: u64,
+    events: EV,
+    expiry_action: WatchdogAction,
+    wcs: u32,
+    wor: u32,
+    wcv: u64,
+}
+
+impl<T: Trigger, C: ClockSource> SbsaGwdt<T, C, NoEvents> {
+    /// Creates a new `SbsaGwdt` instance, which will signal `ws0_evt` when
+    /// the first watch signal is asserted, and `ws1_evt` when the second
+    /// one is asserted.
+    ///
+    /// # Arguments
+    /// * `ws0_evt` - A Trigger object that will be used to raise the
+    ///   interrupt of the device.
+    /// * `ws1_evt` - A Trigger object that will be used to notify the VMM
+    ///   about the expiry action, i.e. the system reset by default.
+    /// * `clock` - The source of the time from which the system counter is
+    ///   derived.
+    /// * `counter_hz` - The frequency of the system counter, as reported to
+    ///   the guest by CNTFRQ_EL0.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `SbsaGwdt`](struct.SbsaGwdt.html#example).
+    pub fn new(ws0_evt: T, ws1_evt: T, clock: C, counter_hz: u64) -> Self {
+        Self::with_events(ws0_evt, ws1_evt, clock, counter_hz, NoEvents)
+    }
+}
+
+impl<T: Trigger, C: ClockSource, EV: WatchdogEvents> SbsaGwdt<T, C, EV> {
+    /// Creates a new `SbsaGwdt` instance, which will signal `ws0_evt` when
+    /// the first watch signal is asserted, and `ws1_evt` when the second
+    /// one is asserted, and invokes the `gwdt_evts` implementation of
+    /// `WatchdogEvents` during operation.
+    ///
+    /// # Arguments
+    /// * `ws0_evt` - A Trigger object that will be used to raise the
+    ///   interrupt of the device.
+    /// * `ws1_evt` - A Trigger object that will be used to notify the VMM
+    ///   about the expiry action, i.e. the system reset by default.
+    /// * `clock` - The source of the time from which the system counter is
+    ///   derived.
+    /// * `counter_hz` - The frequency of the system counter, as reported to
+    ///   the guest by CNTFRQ_EL0.
+    /// * `gwdt_evts` - The `WatchdogEvents` implementation used to track the
+    ///   expiry of the watchdog.
+    pub fn with_events(ws0_evt: T, ws1_evt: T, clock: C, counter_hz: u64, gwdt_evts: EV) -> Self {
+        SbsaGwdt {
+            ws0_evt,
+            ws1_evt,
+            clock,
+            counter_hz,
+            events: gwdt_evts,
+            expiry_action: WatchdogAction::default(),
+            wcs: 0,
+            wor: 0,
+            wcv: 0,
+        }
+    }
+
+    /// Provides a reference to the WS0 interrupt event object.
+    pub fn ws0_evt(&self) -> &T {
+        &self.ws0_evt
+    }
+
+    /// Provides a reference to the WS1 event object.
+    pub fn ws1_evt(&self) -> &T {
+        &self.ws1_evt
+    }
+
+    /// Provides a reference to the clock source.
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Provides a reference to the SBSA Generic Watchdog events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Returns the action taken when the watchdog expires.
+    pub fn expiry_action(&self) -> WatchdogAction {
+        self.expiry_action
+    }
+
+    /// Sets the action taken when the watchdog expires, which is
+    /// `WatchdogAction::Reset` by default.
+    ///
+    /// # Arguments
+    /// * `action` - The action reported through `WatchdogEvents::expired`,
+    ///   for which the WS1 event object is signaled, unless it is
+    ///   `WatchdogAction::NotifyOnly`.
+    pub fn set_expiry_action(&mut self, action: WatchdogAction) {
+        self.expiry_action = action;
+    }
+
+    /// Returns whether the watchdog is enabled.
+    pub fn enabled(&self) -> bool {
+        (self.wcs & WCS_EN) != 0
+    }
+
+    // Returns the value of the system counter.
+    fn counter(&self) -> u64 {
+        let ticks = u128::from(self.clock.now_ns()) * u128::from(self.counter_hz) / NANOS_PER_SEC;
+        ticks as u64
+    }
+
+    /// Returns the time, as reported by the clock source, when the system
+    /// counter reaches the compare value, or `None` if the watchdog is
+    /// disabled, or already asserted WS1.
+    ///
+    /// The deadline changes when the guest refreshes or reconfigures the
+    /// watchdog, so the VMM should query it again after writes to the
+    /// device, as well as after calling
+    /// [`on_timer_expired`](#method.on_timer_expired).
+    pub fn next_deadline(&self) -> Option<u64> {
+        if !self.enabled() || (self.wcs & WCS_WS1) != 0 || self.counter_hz == 0 {
+            return None;
+        }
+        // Round up, so that the counter reached the compare value by then.
+        let deadline_ns =
+            (u128::from(self.wcv) * NANOS_PER_SEC).div_ceil(u128::from(self.counter_hz));
+        Some(u64::try_from(deadline_ns).unwrap_or(u64::MAX))
+    }
+
+    // Clears the watch signals, and moves the compare value forward.
+    fn refresh(&mut self) {
+        self.wcs &= !(WCS_WS0 | WCS_WS1);
+        self.wcv = self.counter().saturating_add(u64::from(self.wor));
+    }
+
+    /// Handles the expiry of the deadline returned by
+    /// [`next_deadline`](#method.next_deadline), if it passed.
+    ///
+    /// The first expiry asserts WS0, which raises the interrupt and
+    /// refreshes the watchdog. The expiry while WS0 is asserted asserts WS1,
+    /// which takes the expiry action.
+    pub fn on_timer_expired(&mut self) -> Result<(), T::E> {
+        if self.next_deadline().is_none() || self.counter() < self.wcv {
+            return Ok(());
+        }
+
+        if (self.wcs & WCS_WS0) == 0 {
+            self.wcv = self.counter().saturating_add(u64::from(self.wor));
+            self.wcs |= WCS_WS0;
+            self.events.pretimeout();
+            self.ws0_evt.trigger()
+        } else {
+            self.wcs |= WCS_WS1;
+            self.events.expired(self.expiry_action);
+            if self.expiry_action == WatchdogAction::NotifyOnly {
+                return Ok(());
+            }
+            self.ws1_evt.trigger()
+        }
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base address of the refresh frame.
+    ///
+    /// The refresh register reads as 0, like the invalid offsets.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The little-endian, 4 byte array storing the read value.
+    pub fn read_refresh(&self, offset: u16, data: &mut [u8; 4]) {
+        let v = match offset {
+            W_IIDR => IIDR_VALUE,
+            WRR => 0,
+            _ => {
+                guest_warn!(
+                    "Invalid SBSA watchdog refresh frame read at offset {:#x}",
+                    offset
+                );
+                0
+            }
+        };
+        *data = v.to_le_bytes();
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base address of the refresh frame.
+    ///
+    /// Any write to the refresh register refreshes the watchdog.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The little endian, 4 byte array to write to the register.
+    pub fn write_refresh(&mut self, offset: u16, _data: &[u8; 4]) {
+        if offset == WRR {
+            self.refresh();
+        } else {
+            guest_warn!(
+                "Invalid SBSA watchdog refresh frame write at offset {:#x}",
+                offset
+            );
+        }
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base address of the control frame.
+    ///
+    /// Invalid offsets read as 0.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The little-endian, 4 byte array storing the read value.
+    pub fn read_control(&self, offset: u16, data: &mut [u8; 4]) {
+        let v = match offset {
+            WCS => self.wcs,
+            WOR => self.wor,
+            WCV_LOW => self.wcv as u32,
+            WCV_HIGH => (self.wcv >> 32) as u32,
+            W_IIDR => IIDR_VALUE,
+            _ => {
+                guest_warn!(
+                    "Invalid SBSA watchdog control frame read at offset {:#x}",
+                    offset
+                );
+                0
+            }
+        };
+        *data = v.to_le_bytes();
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base address of the control frame.
+    ///
+    /// The writes to the control and status register, and to the offset
+    /// register, refresh the watchdog, while the writes to the compare value
+    /// register set the compare value directly.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The little endian, 4 byte array to write to the register.
+    pub fn write_control(&mut self, offset: u16, data: &[u8; 4]) {
+        let val = u32::from_le_bytes(*data);
+        match offset {
+            WCS => {
+                self.wcs = (self.wcs & !WCS_EN) | (val & WCS_EN);
+                self.refresh();
+            }
+            WOR => {
+                self.wor = val;
+                self.refresh();
+            }
+            WCV_LOW => self.wcv = (self.wcv & !0xFFFF_FFFF) | u64::from(val),
+            WCV_HIGH => self.wcv = (self.wcv & 0xFFFF_FFFF) | (u64::from(val) << 32),
+            _ => guest_warn!(
+                "Invalid SBSA watchdog control frame write at offset {:#x}",
+                offset
+            ),
+        }
+    }
+}
+
+impl<T: Trigger, C: ClockSource, EV: WatchdogEvents> LegacyDevice for SbsaGwdt<T, C, EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match <&mut [u8; 4]>::try_from(&mut *data) {
+            Ok(data) if offset < FRAME_SIZE => self.read_refresh(offset, data),
+            Ok(data) if offset < 2 * FRAME_SIZE => self.read_control(offset - FRAME_SIZE, data),
+            _ => {
+                guest_warn!(
+                    "Ignoring a {}-byte access at offset {:#x}",
+                    data.len(),
+                    offset
+                );
+                data.fill(0)
+            }
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        match <&[u8; 4]>::try_from(data) {
+            Ok(data) if offset < FRAME_SIZE => self.write_refresh(offset, data),
+            Ok(data) if offset < 2 * FRAME_SIZE => self.write_control(offset - FRAME_SIZE, data),
+            _ => guest_warn!(
+                "Ignoring a {}-byte access at offset {:#x}",
+                data.len(),
+                offset
+            ),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.wcs = 0;
+        self.wor = 0;
+        self.wcv = 0;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockClock;
+    use std::io;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::{Arc, Mutex};
+    use vmm_sys_util::eventfd::EventFd;
+    use vmm_sys_util::metric::Metric;
+
+    // A 1 KHz system counter, i.e. a tick every millisecond.
+    const COUNTER_HZ: u64 = 1_000;
+    const TICK_NS: u64 = 1_000_000;
+
+    #[derive(Default)]
+    struct ExampleSbsaGwdtEvents {
+        pretimeouts: AtomicU64,
+        timeouts: AtomicU64,
+        actions: Mutex<Vec<WatchdogAction>>,
+    }
+
+    impl WatchdogEvents for ExampleSbsaGwdtEvents {
+        fn pretimeout(&self) {
+            self.pretimeouts.inc();
+        }
+
+        fn expired(&self, action: WatchdogAction) {
+            self.timeouts.inc();
+            self.actions.lock().unwrap().push(action);
+        }
+    }
+
+    type TestGwdt = SbsaGwdt<EventFd, MockClock, Arc<ExampleSbsaGwdtEvents>>;
+
+    fn new_gwdt() -> (TestGwdt, MockClock) {
+        let clock = MockClock::new();
+        let wdt = SbsaGwdt::with_events(
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            clock.clone(),
+            COUNTER_HZ,
+            Arc::new(ExampleSbsaGwdtEvents::default()),
+        );
+        (wdt, clock)
+    }
+
+    fn evt_count(evt: &EventFd) -> u64 {
+        match evt.read() {
+            Ok(count) => count,
+            Err(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+                0
+            }
+        }
+    }
+
+    fn read_control(wdt: &TestGwdt, offset: u16) -> u32 {
+        let mut data = [0; 4];
+        wdt.read_control(offset, &mut data);
+        u32::from_le_bytes(data)
+    }
+
+    fn write_control(wdt: &mut TestGwdt, offset: u16, value: u32) {
+        wdt.write_control(offset, &value.to_le_bytes());
+    }
+
+    fn expire(wdt: &mut TestGwdt, clock: &MockClock) {
+        clock.set_ns(wdt.next_deadline().unwrap());
+        wdt.on_timer_expired().unwrap();
+    }
+
+    #[test]
+    fn test_sbsa_gwdt_registers() {
+        let (mut wdt, clock) = new_gwdt();
+        assert_eq!(wdt.next_deadline(), None);
+
+        let mut data = [0; 4];
+        wdt.read_refresh(W_IIDR, &mut data);
+        assert_eq!(u32::from_le_bytes(data), IIDR_VALUE);
+        wdt.read_refresh(WRR, &mut data);
+        assert_eq!(data, [0; 4]);
+        assert_eq!(read_control(&wdt, W_IIDR), IIDR_VALUE);
+
+        // The offset register refreshes the watchdog.
+        clock.set_ns(5 * TICK_NS);
+        write_control(&mut wdt, WOR, 10);
+        assert_eq!(read_control(&wdt, WOR), 10);
+        assert_eq!(read_control(&wdt, WCV_LOW), 15);
+        assert_eq!(read_control(&wdt, WCV_HIGH), 0);
+
+        // The compare value can be set directly.
+        write_control(&mut wdt, WCV_HIGH, 1);
+        write_control(&mut wdt, WCV_LOW, 2);
+        assert_eq!(wdt.wcv, (1 << 32) | 2);
+
+        // Only the enable bit is writable.
+        write_control(&mut wdt, WCS, 0xFF);
+        assert_eq!(read_control(&wdt, WCS), WCS_EN);
+        assert_eq!(wdt.next_deadline(), Some(15 * TICK_NS));
+        assert_eq!(read_control(&wdt, 0x100), 0);
+    }
+
+    #[test]
+    fn test_sbsa_gwdt_expiry() {
+        let (mut wdt, clock) = new_gwdt();
+        write_control(&mut wdt, WOR, 100);
+        write_control(&mut wdt, WCS, WCS_EN);
+        assert_eq!(wdt.next_deadline(), Some(100 * TICK_NS));
+
+        // Nothing happens before the deadline.
+        wdt.on_timer_expired().unwrap();
+        assert_eq!(wdt.events().pretimeouts.count(), 0);
+
+        // The refresh frame moves the deadline.
+        clock.set_ns(50 * TICK_NS);
+        wdt.write_refresh(WRR, &[0; 4]);
+        assert_eq!(wdt.next_deadline(), Some(150 * TICK_NS));
+
+        // The first expiry asserts WS0, and refreshes the watchdog.
+        expire(&mut wdt, &clock);
+        assert_eq!(read_control(&wdt, WCS), WCS_EN | WCS_WS0);
+        assert_eq!(wdt.events().pretimeouts.count(), 1);
+        assert_eq!(evt_count(wdt.ws0_evt()), 1);
+        assert_eq!(wdt.next_deadline(), Some(250 * TICK_NS));
+
+        // The second one asserts WS1, which stops the watchdog.
+        expire(&mut wdt, &clock);
+        assert_eq!(read_control(&wdt, WCS), WCS_EN | WCS_WS0 | WCS_WS1);
+        assert_eq!(wdt.events().timeouts.count(), 1);
+        assert_eq!(evt_count(wdt.ws1_evt()), 1);
+        assert_eq!(wdt.next_deadline(), None);
+
+        // A refresh clears the watch signals.
+        wdt.write_refresh(WRR, &[0; 4]);
+        assert_eq!(read_control(&wdt, WCS), WCS_EN);
+        assert_eq!(wdt.next_deadline(), Some(350 * TICK_NS));
+
+        // Disabling the watchdog stops it.
+        write_control(&mut wdt, WCS, 0);
+        assert_eq!(wdt.next_deadline(), None);
+        wdt.on_timer_expired().unwrap();
+        assert_eq!(evt_count(wdt.ws0_evt()), 0);
+    }
+
+    #[test]
+    fn test_sbsa_gwdt_expiry_action() {
+        let (mut wdt, clock) = new_gwdt();
+        assert_eq!(wdt.expiry_action(), WatchdogAction::Reset);
+        wdt.set_expiry_action(WatchdogAction::NotifyOnly);
+        write_control(&mut wdt, WOR, 100);
+        write_control(&mut wdt, WCS, WCS_EN);
+
+        // WS1 is asserted, but its event object isn't signaled.
+        expire(&mut wdt, &clock);
+        expire(&mut wdt, &clock);
+        assert_eq!(read_control(&wdt, WCS), WCS_EN | WCS_WS0 | WCS_WS1);
+        assert_eq!(evt_count(wdt.ws1_evt()), 0);
+
+        wdt.set_expiry_action(WatchdogAction::Poweroff);
+        wdt.write_refresh(WRR, &[0; 4]);
+        expire(&mut wdt, &clock);
+        expire(&mut wdt, &clock);
+        assert_eq!(evt_count(wdt.ws1_evt()), 1);
+        assert_eq!(
+            *wdt.events().actions.lock().unwrap(),
+            vec![WatchdogAction::NotifyOnly, WatchdogAction::Poweroff]
+        );
+    }
+
+    #[test]
+    fn test_sbsa_gwdt_legacy_device() {
+        let (mut wdt, _) = new_gwdt();
+
+        // The control frame follows the refresh frame.
+        LegacyDevice::write(&mut wdt, FRAME_SIZE + WOR, &10u32.to_le_bytes());
+        LegacyDevice::write(&mut wdt, FRAME_SIZE + WCS, &WCS_EN.to_le_bytes());
+        assert!(wdt.enabled());
+        let mut data = [0; 4];
+        LegacyDevice::read(&mut wdt, W_IIDR, &mut data);
+        assert_eq!(u32::from_le_bytes(data), IIDR_VALUE);
+        LegacyDevice::read(&mut wdt, FRAME_SIZE + WOR, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 10);
+
+        wdt.reset();
+        assert!(!wdt.enabled());
+        assert_eq!(wdt.next_deadline(), None);
+        assert_eq!(read_control(&wdt, WOR), 0);
+    }
+}
@@ -10,14 +10,16 @@
 //!
 //! This is done by emulating an UART serial port.
 
-use std::collections::VecDeque;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::result::Result;
+#[cfg(feature = "std")]
 use std::error::Error as StdError;
-use std::fmt;
-use std::io::{self, Write};
-use std::result::Result;
-use std::sync::Arc;
 
-use crate::Trigger;
+use crate::{byte_offset, ByteSink, LegacyDevice, Persist, SinkError, StateError, Trigger};
 
 // Register offsets.
 // Receiver and Transmitter registers offset, depending on the I/O
@@ -33,7 +35,7 @@ const SCR_OFFSET: u8 = 7;
 const DLAB_LOW_OFFSET: u8 = 0;
 const DLAB_HIGH_OFFSET: u8 = 1;
 
-const FIFO_SIZE: usize = 0x40;
+pub(crate) const FIFO_SIZE: usize = 0x40;
 
 // Received Data Available interrupt - for letting the driver know that
 // there is some pending data to be processed.
@@ -42,24 +44,40 @@ const IER_RDA_BIT: u8 = 0b0000_0001;
 // know that the entire content of the output buffer was sent.
 const IER_THR_EMPTY_BIT: u8 = 0b0000_0010;
 // The interrupts that are available on 16550 and older models.
-const IER_UART_VALID_BITS: u8 = 0b0000_1111;
+pub(crate) const IER_UART_VALID_BITS: u8 = 0b0000_1111;
 
 //FIFO enabled.
 const IIR_FIFO_BITS: u8 = 0b1100_0000;
-const IIR_NONE_BIT: u8 = 0b0000_0001;
-const IIR_THR_EMPTY_BIT: u8 = 0b0000_0010;
-const IIR_RDA_BIT: u8 = 0b0000_0100;
+pub(crate) const IIR_NONE_BIT: u8 = 0b0000_0001;
+pub(crate) const IIR_THR_EMPTY_BIT: u8 = 0b0000_0010;
+pub(crate) const IIR_RDA_BIT: u8 = 0b0000_0100;
+// The values of the IIR the device produces: no pending interrupt, or the
+// pending THR empty and received data interrupts.
+const IIR_VALID_VALUES: [u8; 4] = [
+    IIR_NONE_BIT,
+    IIR_THR_EMPTY_BIT,
+    IIR_RDA_BIT,
+    IIR_THR_EMPTY_BIT | IIR_RDA_BIT,
+];
+
+// FIFO Control Register bits. The FIFO enable bit is reported by the IIR,
+// while the trigger level and the DMA mode are kept, but not emulated. The
+// FIFO reset bits are self-clearing, and never kept.
+const FCR_FIFO_ENABLE_BIT: u8 = 0b0000_0001;
+const FCR_DMA_MODE_BIT: u8 = 0b0000_1000;
+const FCR_TRIGGER_LEVEL_BITS: u8 = 0b1100_0000;
+const FCR_VALID_BITS: u8 = FCR_FIFO_ENABLE_BIT | FCR_DMA_MODE_BIT | FCR_TRIGGER_LEVEL_BITS;
 
 const LCR_DLAB_BIT: u8 = 0b1000_0000;
 
-const LSR_DATA_READY_BIT: u8 = 0b0000_0001;
+pub(crate) const LSR_DATA_READY_BIT: u8 = 0b0000_0001;
 // These two bits help the driver know if the device is ready to accept
 // another character.
 // THR is empty.
-const LSR_EMPTY_THR_BIT: u8 = 0b0010_0000;
+pub(crate) const LSR_EMPTY_THR_BIT: u8 = 0b0010_0000;
 // The shift register, which takes a byte from THR and breaks it in bits
 // for sending them on the line, is empty.
-const LSR_IDLE_BIT: u8 = 0b0100_0000;
+pub(crate) const LSR_IDLE_BIT: u8 = 0b0100_0000;
 
 // The following five MCR bits allow direct manipulation of the device and
 // are available on 16550 and older models.
@@ -91,6 +109,9 @@ const DEFAULT_BAUD_DIVISOR_LOW: u8 = 0x0C;
 const DEFAULT_INTERRUPT_ENABLE: u8 = 0x00;
 // No pending interrupt.
 const DEFAULT_INTERRUPT_IDENTIFICATION: u8 = IIR_NONE_BIT;
+// The FIFOs are enabled out of reset, so that the drivers probing the IIR
+// before writing the FCR find a 16550A.
+const DEFAULT_FIFO_CONTROL: u8 = FCR_FIFO_ENABLE_BIT;
 // We're setting the default to include LSR_EMPTY_THR_BIT and LSR_IDLE_BIT
 // and never update those bits because we're working with a virtual device,
 // hence we should always be ready to receive more data.
@@ -157,6 +178,8 @@ impl<EV: SerialEvents> SerialEvents for Arc<EV> {
 
 /// The state of the Serial device.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SerialState {
     /// Divisor Latch Low Byte
     pub baud_divisor_low: u8,
@@ -166,6 +189,8 @@ pub struct SerialState {
     pub interrupt_enable: u8,
     /// Interrupt Identification Register
     pub interrupt_identification: u8,
+    /// FIFO Control Register
+    pub fifo_control: u8,
     /// Line Control Register
     pub line_control: u8,
     /// Line Status Register
@@ -180,13 +205,16 @@ pub struct SerialState {
     pub in_buffer: Vec<u8>,
 }
 
-impl Default for SerialState {
-    fn default() -> Self {
+impl SerialState {
+    /// Returns the state of a freshly reset serial port, which is also its
+    /// `Default`. It is usable in constant expressions.
+    pub const fn default_state() -> Self {
         SerialState {
             baud_divisor_low: DEFAULT_BAUD_DIVISOR_LOW,
             baud_divisor_high: DEFAULT_BAUD_DIVISOR_HIGH,
             interrupt_enable: DEFAULT_INTERRUPT_ENABLE,
             interrupt_identification: DEFAULT_INTERRUPT_IDENTIFICATION,
+            fifo_control: DEFAULT_FIFO_CONTROL,
             line_control: DEFAULT_LINE_CONTROL,
             line_status: DEFAULT_LINE_STATUS,
             modem_control: DEFAULT_MODEM_CONTROL,
@@ -195,6 +223,48 @@ impl Default for SerialState {
             in_buffer: Vec::new(),
         }
     }
+
+    /// Checks that the device can be in this state, e.g. before restoring a
+    /// state deserialized from a snapshot: the FIFO fits in the device, the
+    /// reserved bits of the IER and FCR are clear, the IIR holds a value the
+    /// device produces, and the data ready bit of the LSR matches the FIFO.
+    pub fn validate(&self) -> Result<(), StateError> {
+        if self.in_buffer.len() > FIFO_SIZE {
+            return Err(StateError::BufferOverflow {
+                buffer: "in_buffer",
+                len: self.in_buffer.len(),
+                capacity: FIFO_SIZE,
+            });
+        }
+        if self.interrupt_enable & !IER_UART_VALID_BITS != 0 {
+            return Err(StateError::InvalidRegister {
+                register: "interrupt_enable",
+                value: u32::from(self.interrupt_enable),
+            });
+        }
+        if self.fifo_control & !FCR_VALID_BITS != 0 {
+            return Err(StateError::InvalidRegister {
+                register: "fifo_control",
+                value: u32::from(self.fifo_control),
+            });
+        }
+        if !IIR_VALID_VALUES.contains(&self.interrupt_identification) {
+            return Err(StateError::InvalidRegister {
+                register: "interrupt_identification",
+                value: u32::from(self.interrupt_identification),
+            });
+        }
+        if (self.line_status & LSR_DATA_READY_BIT != 0) == self.in_buffer.is_empty() {
+            return Err(StateError::DataReadyMismatch);
+        }
+        Ok(())
+    }
+}
+
+impl Default for SerialState {
+    fn default() -> Self {
+        Self::default_state()
+    }
 }
 
 /// The serial console emulation is done by emulating a serial COM port.
@@ -205,42 +275,19 @@ impl Default for SerialState {
 /// This structure emulates the registers that make sense for UART 16550 (and below)
 /// and helps in the interaction between the driver and device by using a
 /// [`Trigger`](../trait.Trigger.html) object for notifications. It also writes the
-/// guest's output to an `out` Write object.
+/// guest's output to an `out` [`ByteSink`](../trait.ByteSink.html) object,
+/// which is any `std::io::Write` with the `std` feature.
 ///
 /// # Example
 ///
 /// ```rust
-/// # use std::io::{sink, Error, Result};
-/// # use std::ops::Deref;
-/// # use vm_superio::Trigger;
+/// # use std::io::sink;
+/// # use vm_superio::testing::MockTrigger;
 /// # use vm_superio::Serial;
-/// # use vmm_sys_util::eventfd::EventFd;
-///
-/// struct EventFdTrigger(EventFd);
-/// impl Trigger for EventFdTrigger {
-///     type E = Error;
-///
-///     fn trigger(&self) -> Result<()> {
-///         self.write(1)
-///     }
-/// }
-/// impl Deref for EventFdTrigger {
-///     type Target = EventFd;
-///     fn deref(&self) -> &Self::Target {
-///         &self.0
-///     }
-/// }
-/// impl EventFdTrigger {
-///     pub fn new(flag: i32) -> Self {
-///         EventFdTrigger(EventFd::new(flag).unwrap())
-///     }
-///     pub fn try_clone(&self) -> Self {
-///         EventFdTrigger((**self).try_clone().unwrap())
-///     }
-/// }
-///
-/// let intr_evt = EventFdTrigger::new(libc::EFD_NONBLOCK);
-/// let mut serial = Serial::new(intr_evt.try_clone(), Vec::new());
+/// // The VMMs usually notify the guest through an `EventFdTrigger`, with the
+/// // `eventfd` feature.
+/// let intr_evt = MockTrigger::new();
+/// let mut serial = Serial::new(intr_evt.clone(), Vec::new());
 /// // std::io::Sink can be used if user is not interested in guest's output.
 /// let serial_with_sink = Serial::new(intr_evt, sink());
 ///
@@ -258,12 +305,13 @@ impl Default for SerialState {
 /// }
 /// ```
 #[derive(Debug)]
-pub struct Serial<T: Trigger, EV: SerialEvents, W: Write> {
+pub struct Serial<T: Trigger, EV: SerialEvents, W: ByteSink> {
     // Some UART registers.
     baud_divisor_low: u8,
     baud_divisor_high: u8,
     interrupt_enable: u8,
     interrupt_identification: u8,
+    fifo_control: u8,
     line_control: u8,
     line_status: u8,
     modem_control: u8,
@@ -286,7 +334,7 @@ pub enum Error<E> {
     /// Failed to trigger interrupt.
     Trigger(E),
     /// Couldn't write/flush to the given destination.
-    IOError(io::Error),
+    IOError(SinkError),
     /// No space left in FIFO.
     FullFifo,
 }
@@ -301,31 +349,32 @@ impl<E: fmt::Display> fmt::Display for Error<E> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<E: StdError> StdError for Error<E> {}
 
-impl<T: Trigger, W: Write> Serial<T, NoEvents, W> {
+impl<T: Trigger, W: ByteSink> Serial<T, NoEvents, W> {
     /// Creates a new `Serial` instance which writes the guest's output to
     /// `out` and uses `trigger` object to notify the driver about new
     /// events.
     ///
     /// # Arguments
     /// * `trigger` - The Trigger object that will be used to notify the driver
-    ///               about events.
+    ///   about events.
     /// * `out` - An object for writing guest's output to. In case the output
-    ///           is not of interest,
-    ///           [std::io::Sink](https://doc.rust-lang.org/std/io/struct.Sink.html)
-    ///           can be used here.
+    ///   is not of interest,
+    ///   [std::io::Sink](https://doc.rust-lang.org/std/io/struct.Sink.html)
+    ///   can be used here.
     ///
     /// # Example
     ///
     /// You can see an example of how to use this function in the
     /// [`Example` section from `Serial`](struct.Serial.html#example).
-    pub fn new(trigger: T, out: W) -> Serial<T, NoEvents, W> {
+    pub const fn new(trigger: T, out: W) -> Serial<T, NoEvents, W> {
         Self::with_events(trigger, NoEvents, out)
     }
 }
 
-impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
+impl<T: Trigger, EV: SerialEvents, W: ByteSink> Serial<T, EV, W> {
     /// Creates a new `Serial` instance from a given `state`, which writes the guest's output to
     /// `out`, uses `trigger` object to notify the driver about new
     /// events, and invokes the `serial_evts` implementation of `SerialEvents`
@@ -336,19 +385,20 @@ impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
     /// # Arguments
     /// * `state` - A reference to the state from which the `Serial` is constructed.
     /// * `trigger` - The `Trigger` object that will be used to notify the driver
-    ///               about events.
+    ///   about events.
     /// * `serial_evts` - The `SerialEvents` implementation used to track the occurrence
-    ///                   of significant events in the serial operation logic.
+    ///   of significant events in the serial operation logic.
     /// * `out` - An object for writing guest's output to. In case the output
-    ///           is not of interest,
-    ///           [std::io::Sink](https://doc.rust-lang.org/std/io/struct.Sink.html)
-    ///           can be used here.
+    ///   is not of interest,
+    ///   [std::io::Sink](https://doc.rust-lang.org/std/io/struct.Sink.html)
+    ///   can be used here.
     pub fn from_state(
         state: &SerialState,
         trigger: T,
         serial_evts: EV,
         out: W,
     ) -> Result<Self, Error<T::E>> {
+        trace_device!("serial", "restore");
         if state.in_buffer.len() > FIFO_SIZE {
             return Err(Error::FullFifo);
         }
@@ -358,6 +408,7 @@ impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
             baud_divisor_high: state.baud_divisor_high,
             interrupt_enable: state.interrupt_enable,
             interrupt_identification: state.interrupt_identification,
+            fifo_control: state.fifo_control,
             line_control: state.line_control,
             line_status: state.line_status,
             modem_control: state.modem_control,
@@ -386,26 +437,44 @@ impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
     ///
     /// # Arguments
     /// * `trigger` - The `Trigger` object that will be used to notify the driver
-    ///               about events.
+    ///   about events.
     /// * `serial_evts` - The `SerialEvents` implementation used to track the occurrence
-    ///                   of significant events in the serial operation logic.
+    ///   of significant events in the serial operation logic.
     /// * `out` - An object for writing guest's output to. In case the output
-    ///           is not of interest,
-    ///           [std::io::Sink](https://doc.rust-lang.org/std/io/struct.Sink.html)
-    ///           can be used here.
-    pub fn with_events(trigger: T, serial_evts: EV, out: W) -> Self {
-        // Safe because we are using the default state that has an appropriately size input buffer
-        // and there are no pending interrupts to be triggered.
-        Self::from_state(&SerialState::default(), trigger, serial_evts, out).unwrap()
+    ///   is not of interest,
+    ///   [std::io::Sink](https://doc.rust-lang.org/std/io/struct.Sink.html)
+    ///   can be used here.
+    pub const fn with_events(trigger: T, serial_evts: EV, out: W) -> Self {
+        // The default state has an empty input buffer and no pending
+        // interrupts to be triggered, so it is built directly, which keeps
+        // this function `const`.
+        Serial {
+            baud_divisor_low: DEFAULT_BAUD_DIVISOR_LOW,
+            baud_divisor_high: DEFAULT_BAUD_DIVISOR_HIGH,
+            interrupt_enable: DEFAULT_INTERRUPT_ENABLE,
+            interrupt_identification: DEFAULT_INTERRUPT_IDENTIFICATION,
+            fifo_control: DEFAULT_FIFO_CONTROL,
+            line_control: DEFAULT_LINE_CONTROL,
+            line_status: DEFAULT_LINE_STATUS,
+            modem_control: DEFAULT_MODEM_CONTROL,
+            modem_status: DEFAULT_MODEM_STATUS,
+            scratch: DEFAULT_SCRATCH,
+            in_buffer: VecDeque::new(),
+            interrupt_evt: trigger,
+            events: serial_evts,
+            out,
+        }
     }
 
     /// Returns the state of the Serial.
     pub fn state(&self) -> SerialState {
+        trace_device!("serial", "save");
         SerialState {
             baud_divisor_low: self.baud_divisor_low,
             baud_divisor_high: self.baud_divisor_high,
             interrupt_enable: self.interrupt_enable,
             interrupt_identification: self.interrupt_identification,
+            fifo_control: self.fifo_control,
             line_control: self.line_control,
             line_status: self.line_status,
             modem_control: self.modem_control,
@@ -415,7 +484,7 @@ impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
         }
     }
 
-    /// Gets a reference to the output Write object
+    /// Gets a reference to the output `ByteSink` object
     ///
     /// ```rust
     /// # use vm_superio::Trigger;
@@ -436,7 +505,7 @@ impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
         &self.out
     }
 
-    /// Gets a mutable reference to the output Write object
+    /// Gets a mutable reference to the output `ByteSink` object
     ///
     /// ```rust
     /// # use vm_superio::Trigger;
@@ -498,6 +567,17 @@ impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
         &self.events
     }
 
+    /// Returns whether the serial port has a pending interrupt, i.e. the
+    /// level of its interrupt line.
+    ///
+    /// The 16550A holds its interrupt line high until the driver clears the
+    /// pending conditions, so a VMM wiring the device to an
+    /// [`IrqLine`](../trait.IrqLine.html) can update its level with this after
+    /// each access.
+    pub fn interrupt_pending(&self) -> bool {
+        (self.interrupt_identification & IIR_NONE_BIT) == 0
+    }
+
     fn is_dlab_set(&self) -> bool {
         (self.line_control & LCR_DLAB_BIT) != 0
     }
@@ -522,7 +602,57 @@ impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
         (self.modem_control & MCR_LOOP_BIT) != 0
     }
 
+    fn modem_status_register(&self) -> u8 {
+        if self.is_in_loop_mode() {
+            // In loopback mode, the four modem control inputs (CTS, DSR, RI, DCD) are
+            // internally connected to the four modem control outputs (RTS, DTR, OUT1, OUT2).
+            // This way CTS is controlled by RTS, DSR by DTR, RI by OUT1 and DCD by OUT2.
+            // (so they will basically contain the same value).
+            let mut msr =
+                self.modem_status & !(MSR_DSR_BIT | MSR_CTS_BIT | MSR_RI_BIT | MSR_DCD_BIT);
+            if (self.modem_control & MCR_DTR_BIT) != 0 {
+                msr |= MSR_DSR_BIT;
+            }
+            if (self.modem_control & MCR_RTS_BIT) != 0 {
+                msr |= MSR_CTS_BIT;
+            }
+            if (self.modem_control & MCR_OUT1_BIT) != 0 {
+                msr |= MSR_RI_BIT;
+            }
+            if (self.modem_control & MCR_OUT2_BIT) != 0 {
+                msr |= MSR_DCD_BIT;
+            }
+            msr
+        } else {
+            self.modem_status
+        }
+    }
+
+    // Returns the registers whose reads have no side effects, indexed by
+    // their offset. The IIR is stored at its offset as well, but reading it
+    // acknowledges the THR empty interrupt, and the data register only holds
+    // the low byte of the baud divisor when DLAB is set.
+    #[cfg(feature = "std")]
+    fn register_snapshot(&self) -> u64 {
+        let dlab = self.is_dlab_set();
+        u64::from_le_bytes([
+            if dlab { self.baud_divisor_low } else { 0 },
+            if dlab {
+                self.baud_divisor_high
+            } else {
+                self.interrupt_enable
+            },
+            self.interrupt_identification,
+            self.line_control,
+            self.modem_control,
+            self.line_status,
+            self.modem_status_register(),
+            self.scratch,
+        ])
+    }
+
     fn trigger_interrupt(&mut self) -> Result<(), T::E> {
+        trace_interrupt!("serial", "irq");
         self.interrupt_evt.trigger()
     }
 
@@ -579,7 +709,7 @@ impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
     ///
     /// # Arguments
     /// * `offset` - The offset that will be added to the base PIO address
-    ///              for writing to a specific register.
+    ///   for writing to a specific register.
     /// * `value` - The byte that should be written.
     ///
     /// # Example
@@ -587,6 +717,7 @@ impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
     /// You can see an example of how to use this function in the
     /// [`Example` section from `Serial`](struct.Serial.html#example).
     pub fn write(&mut self, offset: u8, value: u8) -> Result<(), Error<T::E>> {
+        trace_access!("serial", "write", offset, value);
         match offset {
             DLAB_LOW_OFFSET if self.is_dlab_set() => self.baud_divisor_low = value,
             DLAB_HIGH_OFFSET if self.is_dlab_set() => self.baud_divisor_high = value,
@@ -621,11 +752,14 @@ impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
             }
             // We want to enable only the interrupts that are available for 16550A (and below).
             IER_OFFSET => self.interrupt_enable = value & IER_UART_VALID_BITS,
+            // The writes to the IIR offset go to the FCR.
+            IIR_OFFSET => self.fifo_control = value & FCR_VALID_BITS,
             LCR_OFFSET => self.line_control = value,
             MCR_OFFSET => self.modem_control = value,
             SCR_OFFSET => self.scratch = value,
-            // We are not interested in writing to other offsets (such as FCR offset).
-            _ => {}
+            // The LSR and MSR are read-only.
+            LSR_OFFSET | MSR_OFFSET => {}
+            _ => guest_warn!("Invalid serial port write at offset {:#x}", offset),
         }
         Ok(())
     }
@@ -637,14 +771,14 @@ impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
     ///
     /// # Arguments
     /// * `offset` - The offset that will be added to the base PIO address
-    ///              for reading from a specific register.
+    ///   for reading from a specific register.
     ///
     /// # Example
     ///
     /// You can see an example of how to use this function in the
     /// [`Example` section from `Serial`](struct.Serial.html#example).
     pub fn read(&mut self, offset: u8) -> u8 {
-        match offset {
+        let value = match offset {
             DLAB_LOW_OFFSET if self.is_dlab_set() => self.baud_divisor_low,
             DLAB_HIGH_OFFSET if self.is_dlab_set() => self.baud_divisor_high,
             DATA_OFFSET => {
@@ -664,42 +798,27 @@ impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
             IER_OFFSET => self.interrupt_enable,
             IIR_OFFSET => {
                 // We're enabling FIFO capability by setting the serial port to 16550A:
-                // https://elixir.bootlin.com/linux/latest/source/drivers/tty/serial/8250/8250_port.c#L1299.
-                let iir = self.interrupt_identification | IIR_FIFO_BITS;
+                // https://elixir.bootlin.com/linux/latest/source/drivers/tty/serial/8250/8250_port.c#L1299,
+                // unless the driver disabled the FIFOs.
+                let mut iir = self.interrupt_identification;
+                if self.fifo_control & FCR_FIFO_ENABLE_BIT != 0 {
+                    iir |= IIR_FIFO_BITS;
+                }
                 self.reset_iir();
                 iir
             }
             LCR_OFFSET => self.line_control,
             MCR_OFFSET => self.modem_control,
             LSR_OFFSET => self.line_status,
-            MSR_OFFSET => {
-                if self.is_in_loop_mode() {
-                    // In loopback mode, the four modem control inputs (CTS, DSR, RI, DCD) are
-                    // internally connected to the four modem control outputs (RTS, DTR, OUT1, OUT2).
-                    // This way CTS is controlled by RTS, DSR by DTR, RI by OUT1 and DCD by OUT2.
-                    // (so they will basically contain the same value).
-                    let mut msr =
-                        self.modem_status & !(MSR_DSR_BIT | MSR_CTS_BIT | MSR_RI_BIT | MSR_DCD_BIT);
-                    if (self.modem_control & MCR_DTR_BIT) != 0 {
-                        msr |= MSR_DSR_BIT;
-                    }
-                    if (self.modem_control & MCR_RTS_BIT) != 0 {
-                        msr |= MSR_CTS_BIT;
-                    }
-                    if (self.modem_control & MCR_OUT1_BIT) != 0 {
-                        msr |= MSR_RI_BIT;
-                    }
-                    if (self.modem_control & MCR_OUT2_BIT) != 0 {
-                        msr |= MSR_DCD_BIT;
-                    }
-                    msr
-                } else {
-                    self.modem_status
-                }
-            }
+            MSR_OFFSET => self.modem_status_register(),
             SCR_OFFSET => self.scratch,
-            _ => 0,
-        }
+            _ => {
+                guest_warn!("Invalid serial port read at offset {:#x}", offset);
+                0
+            }
+        };
+        trace_access!("serial", "read", offset, value);
+        value
     }
 
     /// Returns how much space is still available in the FIFO.
@@ -744,7 +863,7 @@ impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
                 return Err(Error::FullFifo);
             }
 
-            write_count = std::cmp::min(self.fifo_capacity(), input.len());
+            write_count = core::cmp::min(self.fifo_capacity(), input.len());
             self.in_buffer.extend(&input[0..write_count]);
             self.set_lsr_rda_bit();
             self.received_data_interrupt().map_err(Error::Trigger)?;
@@ -753,11 +872,391 @@ impl<T: Trigger, EV: SerialEvents, W: Write> Serial<T, EV, W> {
     }
 }
 
+/// An MMIO adapter exposing a [`Serial`](struct.Serial.html) device as an
+/// ns16550a UART, following the `reg-shift` and `reg-io-width` device tree
+/// properties.
+///
+/// The registers are spaced `1 << reg_shift` bytes apart, and are accessed
+/// with `reg_io_width` bytes wide accesses, of which only the low byte is
+/// used. The RISC-V virt guests expect such a UART at 0x10000000, e.g. with
+/// the registers 4 bytes apart, and accessed with 32-bit accesses.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::serial::SerialMmio;
+/// # use vm_superio::{Serial, Trigger};
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// // reg-shift = <2>, reg-io-width = <4>.
+/// let mut uart = SerialMmio::new(Serial::new(DummyTrigger, Vec::new()), 2, 4);
+///
+/// // Write to THR, and read the scratch register (7).
+/// uart.write(0, &u32::from(b'a').to_le_bytes()).unwrap();
+/// let mut data = [0; 4];
+/// uart.read(7 << 2, &mut data);
+/// assert_eq!(uart.serial().writer(), b"a");
+/// ```
+#[derive(Debug)]
+pub struct SerialMmio<T: Trigger, EV: SerialEvents, W: ByteSink> {
+    inner: Serial<T, EV, W>,
+    reg_shift: u32,
+    reg_io_width: usize,
+}
+
+impl<T: Trigger, EV: SerialEvents, W: ByteSink> SerialMmio<T, EV, W> {
+    /// Creates a new `SerialMmio` instance, exposing the registers of
+    /// `serial`.
+    ///
+    /// # Arguments
+    /// * `serial` - The serial device emulating the UART.
+    /// * `reg_shift` - The `reg-shift` property, i.e. the log2 of the
+    ///   distance between the registers.
+    /// * `reg_io_width` - The `reg-io-width` property, i.e. the size of the
+    ///   accesses to the registers, usually 1 or 4.
+    pub const fn new(serial: Serial<T, EV, W>, reg_shift: u32, reg_io_width: usize) -> Self {
+        SerialMmio {
+            inner: serial,
+            reg_shift,
+            reg_io_width,
+        }
+    }
+
+    /// Provides a reference to the wrapped serial device.
+    pub fn serial(&self) -> &Serial<T, EV, W> {
+        &self.inner
+    }
+
+    /// Provides a mutable reference to the wrapped serial device, e.g. for
+    /// sending bytes to the guest.
+    pub fn serial_mut(&mut self) -> &mut Serial<T, EV, W> {
+        &mut self.inner
+    }
+
+    /// Consumes the adapter, and returns the wrapped serial device.
+    pub fn into_serial(self) -> Serial<T, EV, W> {
+        self.inner
+    }
+
+    // Returns the register accessed at `offset` with `len` bytes, if the
+    // access targets a register with the right width.
+    fn register(&self, offset: u16, len: usize) -> Option<u8> {
+        let stride = 1u16.checked_shl(self.reg_shift)?;
+        if len != self.reg_io_width || (offset & (stride - 1)) != 0 {
+            return None;
+        }
+        u8::try_from(offset >> self.reg_shift)
+            .ok()
+            .filter(|&reg| reg <= SCR_OFFSET)
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// The first byte of `data` is set to the value of the register, and the
+    /// other ones are cleared. The unaligned offsets, the accesses which
+    /// aren't `reg_io_width` bytes wide, and the offsets past the registers
+    /// read as 0.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The little-endian byte array storing the read value.
+    pub fn read(&mut self, offset: u16, data: &mut [u8]) {
+        let value = match self.register(offset, data.len()) {
+            Some(reg) => self.inner.read(reg),
+            None => {
+                guest_warn!(
+                    "Invalid serial port read of {} bytes at offset {:#x}",
+                    data.len(),
+                    offset
+                );
+                0
+            }
+        };
+        data.fill(0);
+        if let Some(byte) = data.first_mut() {
+            *byte = value;
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// Only the first byte of `data` is written to the register. The
+    /// unaligned offsets, the accesses which aren't `reg_io_width` bytes
+    /// wide, and the offsets past the registers are ignored.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The little-endian byte array to write to the register.
+    pub fn write(&mut self, offset: u16, data: &[u8]) -> Result<(), Error<T::E>> {
+        match (self.register(offset, data.len()), data.first()) {
+            (Some(reg), Some(&value)) => self.inner.write(reg, value),
+            _ => {
+                guest_warn!(
+                    "Invalid serial port write of {} bytes at offset {:#x}",
+                    data.len(),
+                    offset
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A [`Serial`](struct.Serial.html) device shared between several threads,
+/// such as the vCPU threads and a console thread.
+///
+/// The reads of the registers without side effects, i.e. all the registers
+/// but RBR and IIR, are served from a snapshot of the registers which is
+/// updated after each access changing them, without taking the lock of the
+/// device. The other accesses lock the device, so that the output of a vCPU
+/// and the input of the console thread don't wait on the reads of the line
+/// status register while the guest polls it.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use std::thread;
+/// # use vm_superio::serial::SharedSerial;
+/// # use vm_superio::testing::MockTrigger;
+/// # use vm_superio::Serial;
+/// let serial = Arc::new(SharedSerial::new(Serial::new(MockTrigger::new(), Vec::new())));
+///
+/// // The console thread sends the input of the user to the guest.
+/// let console = serial.clone();
+/// thread::spawn(move || console.enqueue_raw_bytes(b"a").unwrap())
+///     .join()
+///     .unwrap();
+///
+/// // A vCPU reads the line status register, then the received byte.
+/// assert_eq!(serial.read(5) & 0x01, 0x01);
+/// assert_eq!(serial.read(0), b'a');
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct SharedSerial<T: Trigger, EV: SerialEvents, W: ByteSink> {
+    inner: std::sync::Mutex<Serial<T, EV, W>>,
+    // The registers returned by `Serial::register_snapshot`.
+    registers: core::sync::atomic::AtomicU64,
+}
+
+#[cfg(feature = "std")]
+impl<T: Trigger, EV: SerialEvents, W: ByteSink> SharedSerial<T, EV, W> {
+    /// Creates a new `SharedSerial` instance, sharing `serial`.
+    ///
+    /// # Arguments
+    /// * `serial` - The serial device to share.
+    pub fn new(serial: Serial<T, EV, W>) -> Self {
+        let registers = core::sync::atomic::AtomicU64::new(serial.register_snapshot());
+        SharedSerial {
+            inner: std::sync::Mutex::new(serial),
+            registers,
+        }
+    }
+
+    /// Calls `f` with the locked serial device, and returns its result.
+    ///
+    /// The registers read without locking the device are updated once `f`
+    /// returns.
+    ///
+    /// # Arguments
+    /// * `f` - The function accessing the serial device.
+    pub fn with<R, F: FnOnce(&mut Serial<T, EV, W>) -> R>(&self, f: F) -> R {
+        let mut serial = self.inner.lock().unwrap();
+        let result = f(&mut serial);
+        self.registers.store(
+            serial.register_snapshot(),
+            core::sync::atomic::Ordering::Release,
+        );
+        result
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base Port I/O address, as [`Serial::read`](struct.Serial.html#method.read)
+    /// does.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base PIO address
+    ///   for reading from a specific register.
+    pub fn read(&self, offset: u8) -> u8 {
+        let registers = self
+            .registers
+            .load(core::sync::atomic::Ordering::Acquire)
+            .to_le_bytes();
+        let dlab = (registers[usize::from(LCR_OFFSET)] & LCR_DLAB_BIT) != 0;
+        match offset {
+            DATA_OFFSET if !dlab => self.with(|serial| serial.read(offset)),
+            IIR_OFFSET => self.with(|serial| serial.read(offset)),
+            DATA_OFFSET..=SCR_OFFSET => {
+                let value = registers[usize::from(offset)];
+                trace_access!("serial", "read", offset, value);
+                value
+            }
+            _ => self.with(|serial| serial.read(offset)),
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base Port I/O address, as [`Serial::write`](struct.Serial.html#method.write)
+    /// does.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&self, offset: u8, value: u8) -> Result<(), Error<T::E>> {
+        self.with(|serial| serial.write(offset, value))
+    }
+
+    /// Sends `input` bytes to the guest, as
+    /// [`Serial::enqueue_raw_bytes`](struct.Serial.html#method.enqueue_raw_bytes)
+    /// does.
+    ///
+    /// # Arguments
+    /// * `input` - The data to be sent to the guest.
+    pub fn enqueue_raw_bytes(&self, input: &[u8]) -> Result<usize, Error<T::E>> {
+        self.with(|serial| serial.enqueue_raw_bytes(input))
+    }
+
+    /// Returns how much space is still available in the FIFO.
+    pub fn fifo_capacity(&self) -> usize {
+        self.inner.lock().unwrap().fifo_capacity()
+    }
+
+    /// Returns whether the serial port has a pending interrupt, without
+    /// locking the device.
+    pub fn interrupt_pending(&self) -> bool {
+        let registers = self
+            .registers
+            .load(core::sync::atomic::Ordering::Acquire)
+            .to_le_bytes();
+        (registers[usize::from(IIR_OFFSET)] & IIR_NONE_BIT) == 0
+    }
+
+    /// Returns the state of the serial device.
+    pub fn state(&self) -> SerialState {
+        self.inner.lock().unwrap().state()
+    }
+
+    /// Consumes the wrapper, and returns the shared serial device.
+    pub fn into_inner(self) -> Serial<T, EV, W> {
+        self.inner.into_inner().unwrap()
+    }
+}
+
+impl<T: Trigger, EV: SerialEvents, W: ByteSink> LegacyDevice for Serial<T, EV, W> {
+    type State = SerialState;
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = Serial::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            let _ = Serial::write(self, offset, data[0]);
+        }
+    }
+
+    // The bytes waiting in the input buffer are dropped.
+    fn reset(&mut self) {
+        self.baud_divisor_low = DEFAULT_BAUD_DIVISOR_LOW;
+        self.baud_divisor_high = DEFAULT_BAUD_DIVISOR_HIGH;
+        self.interrupt_enable = DEFAULT_INTERRUPT_ENABLE;
+        self.interrupt_identification = DEFAULT_INTERRUPT_IDENTIFICATION;
+        self.fifo_control = DEFAULT_FIFO_CONTROL;
+        self.line_control = DEFAULT_LINE_CONTROL;
+        self.line_status = DEFAULT_LINE_STATUS;
+        self.modem_control = DEFAULT_MODEM_CONTROL;
+        self.modem_status = DEFAULT_MODEM_STATUS;
+        self.scratch = DEFAULT_SCRATCH;
+        self.in_buffer.clear();
+    }
+
+    fn state(&self) -> Self::State {
+        Serial::state(self)
+    }
+}
+
+// The serial port is restored with its `Trigger` object, its events object and
+// its output.
+impl<T: Trigger, EV: SerialEvents, W: ByteSink> Persist for Serial<T, EV, W> {
+    type State = SerialState;
+    type ConstructorArgs = (T, EV, W);
+    type Error = Error<T::E>;
+
+    fn save(&self) -> Self::State {
+        self.state()
+    }
+
+    fn restore(
+        (trigger, serial_evts, out): Self::ConstructorArgs,
+        state: &Self::State,
+    ) -> Result<Self, Self::Error> {
+        Self::from_state(state, trigger, serial_evts, out)
+    }
+}
+
+impl<T: Trigger, EV: SerialEvents, W: ByteSink> LegacyDevice for SerialMmio<T, EV, W> {
+    type State = SerialState;
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        SerialMmio::read(self, offset, data);
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        let _ = SerialMmio::write(self, offset, data);
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    fn state(&self) -> Self::State {
+        self.inner.state()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Trigger, EV: SerialEvents, W: ByteSink> LegacyDevice for SharedSerial<T, EV, W> {
+    type State = SerialState;
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = SharedSerial::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            let _ = SharedSerial::write(self, offset, data[0]);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.with(|serial| serial.reset());
+    }
+
+    fn state(&self) -> Self::State {
+        SharedSerial::state(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use std::io::{sink, Result};
+    use std::io::{self, sink};
     use std::sync::atomic::AtomicU64;
     use std::sync::Arc;
 
@@ -766,14 +1265,6 @@ mod tests {
 
     const RAW_INPUT_BUF: [u8; 3] = [b'a', b'b', b'c'];
 
-    impl Trigger for EventFd {
-        type E = io::Error;
-
-        fn trigger(&self) -> Result<()> {
-            self.write(1)
-        }
-    }
-
     struct ExampleSerialEvents {
         read_count: AtomicU64,
         out_byte_count: AtomicU64,
@@ -879,14 +1370,18 @@ mod tests {
             serial.interrupt_enable,
             IER_THR_EMPTY_BIT & IER_UART_VALID_BITS
         );
+        assert!(!serial.interrupt_pending());
         serial.write(DATA_OFFSET, b'a').unwrap();
 
         // Verify the serial raised an interrupt.
         assert_eq!(intr_evt.read().unwrap(), 1);
+        assert!(serial.interrupt_pending());
 
         let ier = serial.read(IER_OFFSET);
         assert_eq!(ier & IER_UART_VALID_BITS, IER_THR_EMPTY_BIT);
         let iir = serial.read(IIR_OFFSET);
+        // Reading the IIR clears the THR empty interrupt.
+        assert!(!serial.interrupt_pending());
         // Verify the raised interrupt is indeed the empty THR one.
         assert_ne!(iir & IIR_THR_EMPTY_BIT, 0);
 
@@ -899,6 +1394,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_serial_fifo_control() {
+        let mut serial = Serial::new(EventFd::new(libc::EFD_NONBLOCK).unwrap(), sink());
+        assert_eq!(serial.state().fifo_control, FCR_FIFO_ENABLE_BIT);
+
+        // The FIFO reset bits are self-clearing, and the trigger level is
+        // kept.
+        serial.write(IIR_OFFSET, 0xC7).unwrap();
+        assert_eq!(serial.state().fifo_control, 0xC1);
+        assert_eq!(serial.read(IIR_OFFSET), IIR_NONE_BIT | IIR_FIFO_BITS);
+
+        // Once the FIFOs are disabled, the IIR reports a 16450.
+        serial.write(IIR_OFFSET, 0x00).unwrap();
+        assert_eq!(serial.read(IIR_OFFSET), IIR_NONE_BIT);
+
+        let state = serial.state();
+        let mut restored = Serial::from_state(
+            &state,
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            NoEvents,
+            sink(),
+        )
+        .unwrap();
+        assert_eq!(restored.read(IIR_OFFSET), IIR_NONE_BIT);
+    }
+
     #[test]
     fn test_serial_loop_mode() {
         let intr_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
@@ -1196,4 +1717,320 @@ mod tests {
         // Verify the serial raised an interrupt again.
         assert_eq!(intr_evt.read().unwrap(), 1);
     }
+
+    #[test]
+    fn test_serial_mmio() {
+        let intr_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let serial = Serial::new(intr_evt.try_clone().unwrap(), Vec::new());
+        let mut uart = SerialMmio::new(serial, 2, 4);
+
+        // The registers are 4 bytes apart, and accessed with 32-bit accesses.
+        uart.write(u16::from(SCR_OFFSET) << 2, &[0x42, 0xFF, 0xFF, 0xFF])
+            .unwrap();
+        let mut data = [0xFF; 4];
+        uart.read(u16::from(SCR_OFFSET) << 2, &mut data);
+        assert_eq!(data, [0x42, 0, 0, 0]);
+        uart.write(0, &u32::from(b'x').to_le_bytes()).unwrap();
+        assert_eq!(uart.serial().writer(), b"x");
+
+        // The other accesses are ignored, and read as 0.
+        uart.write(u16::from(SCR_OFFSET) << 2, &[0x24]).unwrap();
+        uart.write((u16::from(SCR_OFFSET) << 2) + 1, &[0x24; 4])
+            .unwrap();
+        uart.write(8 << 2, &[0x24; 4]).unwrap();
+        assert_eq!(uart.serial_mut().read(SCR_OFFSET), 0x42);
+        let mut data = [0xFF; 1];
+        uart.read(u16::from(SCR_OFFSET) << 2, &mut data);
+        assert_eq!(data, [0]);
+        let mut data = [0xFF; 4];
+        uart.read(8 << 2, &mut data);
+        assert_eq!(data, [0; 4]);
+
+        // The input raises the interrupt of the wrapped device.
+        uart.write(
+            u16::from(IER_OFFSET) << 2,
+            &u32::from(IER_RDA_BIT).to_le_bytes(),
+        )
+        .unwrap();
+        uart.serial_mut().enqueue_raw_bytes(&RAW_INPUT_BUF).unwrap();
+        assert_eq!(intr_evt.read().unwrap(), 1);
+        uart.read(u16::from(DATA_OFFSET) << 2, &mut data);
+        assert_eq!(data, [b'a', 0, 0, 0]);
+
+        // Without a shift, the registers are consecutive bytes.
+        let serial = uart.into_serial();
+        let mut uart = SerialMmio::new(serial, 0, 1);
+        let mut data = [0xFF; 1];
+        uart.read(u16::from(SCR_OFFSET), &mut data);
+        assert_eq!(data, [0x42]);
+    }
+
+    #[test]
+    fn test_serial_legacy_device() {
+        let intr_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let mut serial = Serial::new(intr_evt, Vec::new());
+
+        LegacyDevice::write(&mut serial, u16::from(SCR_OFFSET), &[0x42]);
+        LegacyDevice::write(&mut serial, u16::from(DATA_OFFSET), b"a");
+        assert_eq!(serial.writer(), b"a");
+        serial.enqueue_raw_bytes(&RAW_INPUT_BUF).unwrap();
+        let mut data = [0; 1];
+        LegacyDevice::read(&mut serial, u16::from(SCR_OFFSET), &mut data);
+        assert_eq!(data, [0x42]);
+        assert_eq!(LegacyDevice::state(&serial).in_buffer, RAW_INPUT_BUF);
+
+        // The reset returns the registers to their default state, and keeps
+        // the output.
+        let mut uart = SerialMmio::new(serial, 0, 1);
+        uart.reset();
+        assert_eq!(LegacyDevice::state(&uart), SerialState::default());
+        assert_eq!(uart.serial().writer(), b"a");
+    }
+
+    #[cfg(feature = "fuzzing")]
+    #[test]
+    fn test_arbitrary_state() {
+        let full_fifo = SerialState {
+            line_status: 0x60 | LSR_DATA_READY_BIT,
+            in_buffer: vec![0xFF; FIFO_SIZE],
+            ..Default::default()
+        };
+        let oversized_fifo = SerialState {
+            in_buffer: vec![0xFF; FIFO_SIZE + 1],
+            ..full_fifo.clone()
+        };
+        crate::testing::for_each_arbitrary_state(&[full_fifo, oversized_fifo], |state| {
+            let serial = Serial::from_state(
+                state,
+                EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+                NoEvents,
+                sink(),
+            );
+            // The states with an oversized FIFO are rejected.
+            let mut serial = match serial {
+                Ok(serial) => serial,
+                Err(Error::FullFifo) => return,
+                Err(e) => panic!("Unexpected error: {}", e),
+            };
+            for offset in 0..8 {
+                serial.read(offset);
+            }
+            serial.enqueue_raw_bytes(&[0]).ok();
+            assert!(serial.state().in_buffer.len() <= FIFO_SIZE);
+        });
+    }
+
+    #[test]
+    fn test_const_serial() {
+        struct NoopTrigger;
+
+        impl Trigger for NoopTrigger {
+            type E = ();
+
+            fn trigger(&self) -> Result<(), ()> {
+                Ok(())
+            }
+        }
+
+        const STATE: SerialState = SerialState::default_state();
+        static SERIAL: std::sync::Mutex<Serial<NoopTrigger, NoEvents, Vec<u8>>> =
+            std::sync::Mutex::new(Serial::new(NoopTrigger, Vec::new()));
+
+        let mut serial = SERIAL.lock().unwrap();
+        assert_eq!(STATE, SerialState::default());
+        assert_eq!(serial.state(), STATE);
+        serial.write(0, b'a').unwrap();
+        assert_eq!(serial.writer(), b"a");
+    }
+
+    #[test]
+    fn test_shared_serial() {
+        let intr_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let mut serial = Serial::new(intr_evt.try_clone().unwrap(), Vec::new());
+        let shared = SharedSerial::new(Serial::new(intr_evt.try_clone().unwrap(), Vec::new()));
+
+        // The registers read without locking the device follow the writes.
+        let writes = [
+            (LCR_OFFSET, LCR_DLAB_BIT),
+            (DLAB_LOW_OFFSET, 0x0C),
+            (DLAB_HIGH_OFFSET, 0x01),
+            (LCR_OFFSET, 0x03),
+            (IER_OFFSET, IER_RDA_BIT),
+            (MCR_OFFSET, MCR_LOOP_BIT | MCR_DTR_BIT | MCR_OUT2_BIT),
+            (SCR_OFFSET, 0x42),
+            (MCR_OFFSET, 0),
+        ];
+        for (offset, value) in writes {
+            serial.write(offset, value).unwrap();
+            shared.write(offset, value).unwrap();
+            for offset in [
+                IER_OFFSET, LCR_OFFSET, MCR_OFFSET, LSR_OFFSET, MSR_OFFSET, SCR_OFFSET,
+            ] {
+                assert_eq!(shared.read(offset), serial.read(offset));
+            }
+            if serial.is_dlab_set() {
+                assert_eq!(shared.read(DLAB_LOW_OFFSET), serial.read(DLAB_LOW_OFFSET));
+            }
+        }
+
+        // RBR and IIR are read through the device.
+        shared.enqueue_raw_bytes(b"ab").unwrap();
+        assert!(shared.interrupt_pending());
+        assert_eq!(
+            shared.read(LSR_OFFSET) & LSR_DATA_READY_BIT,
+            LSR_DATA_READY_BIT
+        );
+        assert_eq!(shared.read(IIR_OFFSET), IIR_RDA_BIT | IIR_FIFO_BITS);
+        assert_eq!(shared.fifo_capacity(), FIFO_SIZE - 2);
+        assert_eq!(shared.read(DATA_OFFSET), b'a');
+        assert_eq!(shared.read(DATA_OFFSET), b'b');
+        assert!(!shared.interrupt_pending());
+        assert_eq!(shared.read(LSR_OFFSET) & LSR_DATA_READY_BIT, 0);
+
+        // The accesses through `with` update the registers as well.
+        shared
+            .with(|serial| serial.write(SCR_OFFSET, 0x24))
+            .unwrap();
+        assert_eq!(shared.read(SCR_OFFSET), 0x24);
+        assert_eq!(shared.state().scratch, 0x24);
+
+        let mut shared = shared;
+        LegacyDevice::reset(&mut shared);
+        let mut data = [0xFF];
+        LegacyDevice::read(&mut shared, u16::from(SCR_OFFSET), &mut data);
+        assert_eq!(data[0], DEFAULT_SCRATCH);
+        assert_eq!(shared.into_inner().state(), SerialState::default());
+    }
+
+    #[test]
+    fn test_shared_serial_threads() {
+        const BYTES: usize = 1000;
+
+        let intr_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let shared = Arc::new(SharedSerial::new(Serial::new(intr_evt, sink())));
+
+        // A console thread sends the bytes while the vCPU polls LSR, and
+        // reads them.
+        let console = shared.clone();
+        let sender = std::thread::spawn(move || {
+            let mut sent = 0;
+            while sent < BYTES {
+                if console.fifo_capacity() > 0 {
+                    console.enqueue_raw_bytes(&[sent as u8]).unwrap();
+                    sent += 1;
+                }
+            }
+        });
+
+        let mut received = 0;
+        while received < BYTES {
+            if (shared.read(LSR_OFFSET) & LSR_DATA_READY_BIT) != 0 {
+                assert_eq!(shared.read(DATA_OFFSET), received as u8);
+                received += 1;
+            }
+        }
+        sender.join().unwrap();
+        assert_eq!(shared.fifo_capacity(), FIFO_SIZE);
+    }
+
+    #[test]
+    fn test_persist() {
+        let intr_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let mut serial = Serial::new(intr_evt.try_clone().unwrap(), sink());
+        serial.write(SCR_OFFSET, 0x42).unwrap();
+        serial.enqueue_raw_bytes(b"ab").unwrap();
+
+        let state = serial.save();
+        assert_eq!(state, serial.state());
+        let mut restored =
+            Serial::restore((intr_evt.try_clone().unwrap(), NoEvents, sink()), &state).unwrap();
+        assert_eq!(restored.read(SCR_OFFSET), 0x42);
+        assert_eq!(restored.read(DATA_OFFSET), b'a');
+
+        let state = SerialState {
+            in_buffer: vec![0; FIFO_SIZE + 1],
+            ..Default::default()
+        };
+        assert!(matches!(
+            Serial::restore((intr_evt, NoEvents, sink()), &state),
+            Err(Error::FullFifo)
+        ));
+    }
+
+    #[test]
+    fn test_state_validate() {
+        assert_eq!(SerialState::default().validate(), Ok(()));
+
+        let state = SerialState {
+            in_buffer: vec![0; FIFO_SIZE + 1],
+            line_status: LSR_DATA_READY_BIT,
+            ..Default::default()
+        };
+        assert_eq!(
+            state.validate(),
+            Err(StateError::BufferOverflow {
+                buffer: "in_buffer",
+                len: FIFO_SIZE + 1,
+                capacity: FIFO_SIZE,
+            })
+        );
+
+        let state = SerialState {
+            interrupt_enable: 0x10,
+            ..Default::default()
+        };
+        assert_eq!(
+            state.validate(),
+            Err(StateError::InvalidRegister {
+                register: "interrupt_enable",
+                value: 0x10,
+            })
+        );
+
+        let state = SerialState {
+            fifo_control: 0x02,
+            ..Default::default()
+        };
+        assert_eq!(
+            state.validate(),
+            Err(StateError::InvalidRegister {
+                register: "fifo_control",
+                value: 0x02,
+            })
+        );
+
+        // The FIFO bits are reported on reads, but never kept.
+        for iir in [0x00, 0x03, 0x08, 0xC1] {
+            let state = SerialState {
+                interrupt_identification: iir,
+                ..Default::default()
+            };
+            assert!(matches!(
+                state.validate(),
+                Err(StateError::InvalidRegister {
+                    register: "interrupt_identification",
+                    ..
+                })
+            ));
+        }
+
+        let state = SerialState {
+            in_buffer: vec![b'a'],
+            ..Default::default()
+        };
+        assert_eq!(state.validate(), Err(StateError::DataReadyMismatch));
+        let state = SerialState {
+            line_status: LSR_DATA_READY_BIT,
+            ..Default::default()
+        };
+        assert_eq!(state.validate(), Err(StateError::DataReadyMismatch));
+
+        // The states saved by the device pass the validation.
+        let mut serial = Serial::new(EventFd::new(libc::EFD_NONBLOCK).unwrap(), sink());
+        serial
+            .write(IER_OFFSET, IER_RDA_BIT | IER_THR_EMPTY_BIT)
+            .unwrap();
+        serial.enqueue_raw_bytes(b"abc").unwrap();
+        assert_eq!(serial.state().validate(), Ok(()));
+    }
 }
@@ -0,0 +1,385 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the SiFive test finisher.
+//!
+//! The RISC-V virt machine exposes this device at 0x100000, and the guests
+//! and the test suites use it for terminating the VM: the low 16 bits of the
+//! value written to its register select between the pass, fail and reset
+//! requests, while the high 16 bits hold the exit code of a failure. The
+//! device tree usually also points the `syscon-poweroff` and `syscon-reboot`
+//! nodes to it.
+
+use alloc::sync::Arc;
+use core::convert::TryFrom;
+use core::result::Result;
+
+use crate::{LegacyDevice, Trigger};
+
+// Offset of the finisher register, which is the only register of the
+// device.
+const FINISHER_OFFSET: u16 = 0;
+
+// The requests, written to the low 16 bits of the finisher register.
+const FINISHER_FAIL: u16 = 0x3333;
+const FINISHER_PASS: u16 = 0x5555;
+const FINISHER_RESET: u16 = 0x7777;
+
+/// The request written by the guest to the finisher register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FinisherRequest {
+    /// The guest passed, and the VM should exit successfully.
+    Pass,
+    /// The guest failed, and the VM should exit with the given code.
+    Fail(u16),
+    /// The guest requested a system reset.
+    Reset,
+}
+
+impl FinisherRequest {
+    /// Returns the exit code of the VM for the pass and fail requests, or
+    /// `None` for the reset requests.
+    pub fn exit_code(self) -> Option<u16> {
+        match self {
+            FinisherRequest::Pass => Some(0),
+            FinisherRequest::Fail(code) => Some(code),
+            FinisherRequest::Reset => None,
+        }
+    }
+}
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the SiFive test finisher emulation logic. The methods below can be
+/// implemented by a backend that keeps track of such events by incrementing metrics, logging
+/// messages, or any other action.
+pub trait SifiveTestEvents {
+    /// The guest wrote the given request. The corresponding event object is
+    /// signaled right after.
+    fn finisher_requested(&self, request: FinisherRequest);
+
+    /// The guest wrote a value which isn't a valid request.
+    fn invalid_request(&self, value: u32);
+}
+
+/// Provides a no-op implementation of `SifiveTestEvents` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `SifiveTestEvents`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl SifiveTestEvents for NoEvents {
+    fn finisher_requested(&self, _request: FinisherRequest) {}
+    fn invalid_request(&self, _value: u32) {}
+}
+
+impl<EV: SifiveTestEvents> SifiveTestEvents for Arc<EV> {
+    fn finisher_requested(&self, request: FinisherRequest) {
+        self.as_ref().finisher_requested(request);
+    }
+
+    fn invalid_request(&self, value: u32) {
+        self.as_ref().invalid_request(value);
+    }
+}
+
+/// The SiFive test finisher, usually found at 0x100000 on RISC-V machines.
+///
+/// Two [`Trigger`](../trait.Trigger.html) objects are used for notifying the
+/// VMM: one for the pass and fail requests, which exit the VM, and one for
+/// the reset requests. The last request, which holds the exit code, is
+/// available through [`last_request`](#method.last_request), and is reported
+/// through [`SifiveTestEvents`](trait.SifiveTestEvents.html).
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::sifive_test::{FinisherRequest, SifiveTest};
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut finisher = SifiveTest::new(DummyTrigger, DummyTrigger);
+///
+/// // The guest fails with the exit code 3.
+/// finisher.write(0, &0x0003_3333u32.to_le_bytes()).unwrap();
+/// assert_eq!(finisher.last_request(), Some(FinisherRequest::Fail(3)));
+/// assert_eq!(finisher.last_request().unwrap().exit_code(), Some(3));
+/// ```
+#[derive(Debug)]
+pub struct SifiveTest<T: Trigger, EV: SifiveTestEvents> {
+    // Exit event object.
+    exit_evt: T,
+    // Reset event object.
+    reset_evt: T,
+    events: EV,
+    last_request: Option<FinisherRequest>,
+}
+
+impl<T: Trigger> SifiveTest<T, NoEvents> {
+    /// Creates a new `SifiveTest` instance, which will signal `exit_evt`
+    /// when the guest passes or fails, and `reset_evt` when it requests a
+    /// reset.
+    ///
+    /// # Arguments
+    /// * `exit_evt` - A Trigger object that will be used to notify the VMM
+    ///   about the exit requests.
+    /// * `reset_evt` - A Trigger object that will be used to notify the VMM
+    ///   about the reset requests.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `SifiveTest`](struct.SifiveTest.html#example).
+    pub const fn new(exit_evt: T, reset_evt: T) -> Self {
+        Self::with_events(exit_evt, reset_evt, NoEvents)
+    }
+}
+
+impl<T: Trigger, EV: SifiveTestEvents> SifiveTest<T, EV> {
+    /// Creates a new `SifiveTest` instance, which will signal `exit_evt`
+    /// when the guest passes or fails, and `reset_evt` when it requests a
+    /// reset, and invokes the `finisher_evts` implementation of
+    /// `SifiveTestEvents` during operation.
+    ///
+    /// # Arguments
+    /// * `exit_evt` - A Trigger object that will be used to notify the VMM
+    ///   about the exit requests.
+    /// * `reset_evt` - A Trigger object that will be used to notify the VMM
+    ///   about the reset requests.
+    /// * `finisher_evts` - The `SifiveTestEvents` implementation used to
+    ///   track the requests of the guest.
+    pub const fn with_events(exit_evt: T, reset_evt: T, finisher_evts: EV) -> Self {
+        SifiveTest {
+            exit_evt,
+            reset_evt,
+            events: finisher_evts,
+            last_request: None,
+        }
+    }
+
+    /// Provides a reference to the exit event object.
+    pub fn exit_evt(&self) -> &T {
+        &self.exit_evt
+    }
+
+    /// Provides a reference to the reset event object.
+    pub fn reset_evt(&self) -> &T {
+        &self.reset_evt
+    }
+
+    /// Provides a reference to the SiFive test finisher events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Returns the last valid request written by the guest, if any.
+    pub fn last_request(&self) -> Option<FinisherRequest> {
+        self.last_request
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// The finisher register is write only, so all the offsets read as 0.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The little-endian, 4 byte array storing the read value.
+    pub fn read(&self, _offset: u16, data: &mut [u8; 4]) {
+        *data = [0; 4];
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// The low 16 bits of the value select the request, while the high 16
+    /// bits hold the exit code of the fail requests. Invalid requests are
+    /// reported through `SifiveTestEvents`, and otherwise ignored.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The little endian, 4 byte array to write to the register.
+    pub fn write(&mut self, offset: u16, data: &[u8; 4]) -> Result<(), T::E> {
+        if offset != FINISHER_OFFSET {
+            guest_warn!("Invalid SiFive test finisher write at offset {:#x}", offset);
+            return Ok(());
+        }
+
+        let val = u32::from_le_bytes(*data);
+        let request = match val as u16 {
+            FINISHER_FAIL => FinisherRequest::Fail((val >> 16) as u16),
+            FINISHER_PASS => FinisherRequest::Pass,
+            FINISHER_RESET => FinisherRequest::Reset,
+            _ => {
+                guest_warn!("Invalid SiFive test finisher request {:#x}", val);
+                self.events.invalid_request(val);
+                return Ok(());
+            }
+        };
+
+        self.last_request = Some(request);
+        self.events.finisher_requested(request);
+        match request {
+            FinisherRequest::Reset => self.reset_evt.trigger(),
+            _ => self.exit_evt.trigger(),
+        }
+    }
+}
+
+impl<T: Trigger, EV: SifiveTestEvents> LegacyDevice for SifiveTest<T, EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match <&mut [u8; 4]>::try_from(&mut *data) {
+            Ok(data) => SifiveTest::read(self, offset, data),
+            Err(_) => {
+                guest_warn!(
+                    "Ignoring a {}-byte access at offset {:#x}",
+                    data.len(),
+                    offset
+                );
+                data.fill(0)
+            }
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Ok(data) = <&[u8; 4]>::try_from(data) {
+            let _ = SifiveTest::write(self, offset, data);
+        } else {
+            guest_warn!(
+                "Ignoring a {}-byte access at offset {:#x}",
+                data.len(),
+                offset
+            );
+        }
+    }
+
+    fn reset(&mut self) {
+        self.last_request = None;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::Mutex;
+    use vmm_sys_util::eventfd::EventFd;
+    use vmm_sys_util::metric::Metric;
+
+    #[derive(Default)]
+    struct ExampleSifiveTestEvents {
+        requests: Mutex<Vec<FinisherRequest>>,
+        invalid_requests: AtomicU64,
+    }
+
+    impl SifiveTestEvents for ExampleSifiveTestEvents {
+        fn finisher_requested(&self, request: FinisherRequest) {
+            self.requests.lock().unwrap().push(request);
+        }
+
+        fn invalid_request(&self, _value: u32) {
+            self.invalid_requests.inc();
+        }
+    }
+
+    type TestFinisher = SifiveTest<EventFd, Arc<ExampleSifiveTestEvents>>;
+
+    fn new_finisher() -> TestFinisher {
+        SifiveTest::with_events(
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            Arc::new(ExampleSifiveTestEvents::default()),
+        )
+    }
+
+    fn evt_count(evt: &EventFd) -> u64 {
+        match evt.read() {
+            Ok(count) => count,
+            Err(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+                0
+            }
+        }
+    }
+
+    fn write_reg(finisher: &mut TestFinisher, offset: u16, value: u32) {
+        finisher.write(offset, &value.to_le_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_sifive_test_requests() {
+        let mut finisher = new_finisher();
+        assert_eq!(finisher.last_request(), None);
+
+        // The exit code of a pass request is ignored.
+        write_reg(&mut finisher, FINISHER_OFFSET, 0x0001_5555);
+        assert_eq!(finisher.last_request(), Some(FinisherRequest::Pass));
+        assert_eq!(FinisherRequest::Pass.exit_code(), Some(0));
+        assert_eq!(evt_count(finisher.exit_evt()), 1);
+
+        write_reg(&mut finisher, FINISHER_OFFSET, 0xFFFF_3333);
+        assert_eq!(finisher.last_request(), Some(FinisherRequest::Fail(0xFFFF)));
+        assert_eq!(evt_count(finisher.exit_evt()), 1);
+
+        write_reg(&mut finisher, FINISHER_OFFSET, 0x0000_7777);
+        assert_eq!(finisher.last_request(), Some(FinisherRequest::Reset));
+        assert_eq!(FinisherRequest::Reset.exit_code(), None);
+        assert_eq!(evt_count(finisher.reset_evt()), 1);
+        assert_eq!(evt_count(finisher.exit_evt()), 0);
+
+        assert_eq!(
+            *finisher.events().requests.lock().unwrap(),
+            vec![
+                FinisherRequest::Pass,
+                FinisherRequest::Fail(0xFFFF),
+                FinisherRequest::Reset
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sifive_test_invalid() {
+        let mut finisher = new_finisher();
+
+        write_reg(&mut finisher, FINISHER_OFFSET, 0x0000_1234);
+        assert_eq!(finisher.events().invalid_requests.count(), 1);
+        // The other offsets are ignored.
+        write_reg(&mut finisher, 4, 0x0000_5555);
+        assert_eq!(finisher.events().invalid_requests.count(), 1);
+        assert_eq!(finisher.last_request(), None);
+        assert_eq!(evt_count(finisher.exit_evt()), 0);
+        assert_eq!(evt_count(finisher.reset_evt()), 0);
+
+        let mut data = [0xFF; 4];
+        finisher.read(FINISHER_OFFSET, &mut data);
+        assert_eq!(data, [0; 4]);
+    }
+
+    #[test]
+    fn test_sifive_test_legacy_device() {
+        let mut finisher = new_finisher();
+
+        // The narrower accesses are ignored.
+        LegacyDevice::write(&mut finisher, FINISHER_OFFSET, &0x5555u16.to_le_bytes());
+        assert_eq!(finisher.last_request(), None);
+        LegacyDevice::write(&mut finisher, FINISHER_OFFSET, &0x5555u32.to_le_bytes());
+        assert_eq!(finisher.last_request(), Some(FinisherRequest::Pass));
+        assert_eq!(evt_count(finisher.exit_evt()), 1);
+        let mut data = [0xFF; 4];
+        LegacyDevice::read(&mut finisher, FINISHER_OFFSET, &mut data);
+        assert_eq!(data, [0; 4]);
+
+        finisher.reset();
+        assert_eq!(finisher.last_request(), None);
+    }
+}
@@ -0,0 +1,626 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the ARM SP804 dual-timer module.
+//!
+//! The module contains two identical down counters, each of them with its
+//! own registers. A counter runs in free-running, periodic or one-shot mode,
+//! is 16 or 32 bits wide, and divides its clock by a prescaler of 1, 16 or
+//! 256. Reaching 0 raises its interrupt, if enabled. The interrupts of the two
+//! counters are combined into a single line.
+
+use core::convert::TryFrom;
+use core::result::Result;
+
+use crate::pit::ClockSource;
+use crate::{LegacyDevice, Trigger};
+
+// The following defines are mapping to the specification:
+// https://developer.arm.com/documentation/ddi0271/d/programmer-s-model/summary-of-registers
+//
+// Offset of the registers of each timer.
+const TIMER_STRIDE: u16 = 0x20;
+const NUM_TIMERS: usize = 2;
+// Offsets of the registers, relative to the ones of the timer.
+const TIMER_LOAD: u16 = 0x00; // Load Register.
+const TIMER_VALUE: u16 = 0x04; // Current Value Register (RO).
+const TIMER_CONTROL: u16 = 0x08; // Control Register.
+const TIMER_INTCLR: u16 = 0x0C; // Interrupt Clear Register (WO).
+const TIMER_RIS: u16 = 0x10; // Raw Interrupt Status Register (RO).
+const TIMER_MIS: u16 = 0x14; // Masked Interrupt Status Register (RO).
+const TIMER_BGLOAD: u16 = 0x18; // Background Load Register.
+
+// From 0xFE0 to 0xFFF => Peripheral and PrimeCell Identification Registers.
+const AMBA_IDS: [u8; 8] = [0x04, 0x18, 0x14, 0x00, 0x0d, 0xf0, 0x05, 0xb1];
+const AMBA_ID_LOW: u16 = 0xFE0;
+const AMBA_ID_HIGH: u16 = 0xFFF;
+
+// Control register bits.
+// Stops the counter when it reaches 0.
+const CONTROL_ONESHOT: u32 = 1 << 0;
+// Selects the 32 bit counter, instead of the 16 bit one.
+const CONTROL_32BIT: u32 = 1 << 1;
+// Selects the prescaler: 0 => 1, 1 => 16, 2 => 256 (3 is undefined, and
+// treated as 256).
+const CONTROL_PRESCALE_SHIFT: u32 = 2;
+const CONTROL_PRESCALE_MASK: u32 = 0b11 << CONTROL_PRESCALE_SHIFT;
+// Enables the interrupt.
+const CONTROL_INTEN: u32 = 1 << 5;
+// Reloads the counter from the load register, instead of wrapping around.
+const CONTROL_PERIODIC: u32 = 1 << 6;
+// Enables the counter.
+const CONTROL_ENABLE: u32 = 1 << 7;
+const CONTROL_MASK: u32 = 0xFF & !(1 << 4);
+
+// The values of the registers after reset.
+const DEFAULT_CONTROL: u32 = CONTROL_INTEN;
+const DEFAULT_VALUE: u32 = 0xFFFF_FFFF;
+
+/// The frequency of the timer clock (TIMCLK), which the device tree should
+/// describe as the fixed clock of the device.
+pub const SP804_CLOCK_HZ: u64 = 1_000_000;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+// One of the two counters of the module.
+#[derive(Clone, Copy, Debug)]
+struct Timer {
+    load: u32,
+    control: u32,
+    ris: bool,
+    // The value of the counter at `start_ns`, or while it is stopped.
+    value: u32,
+    // The time when the counter had `value`, if it is counting. The one-shot
+    // counters stop counting at 0, while still being enabled.
+    start_ns: Option<u64>,
+}
+
+impl Timer {
+    fn new() -> Self {
+        Timer {
+            load: 0,
+            control: DEFAULT_CONTROL,
+            ris: false,
+            value: DEFAULT_VALUE,
+            start_ns: None,
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        (self.control & CONTROL_ENABLE) != 0
+    }
+
+    fn interrupt(&self) -> bool {
+        self.ris && (self.control & CONTROL_INTEN) != 0
+    }
+
+    // The largest value of the counter.
+    fn max_value(&self) -> u32 {
+        if (self.control & CONTROL_32BIT) != 0 {
+            0xFFFF_FFFF
+        } else {
+            0xFFFF
+        }
+    }
+
+    // The value loaded in the counter after it reaches 0.
+    fn reload_value(&self) -> u32 {
+        if (self.control & CONTROL_PERIODIC) != 0 {
+            self.load & self.max_value()
+        } else {
+            self.max_value()
+        }
+    }
+
+    // The duration of a tick of the counter, i.e. the period of the clock,
+    // multiplied by the prescaler.
+    fn tick_ns(&self) -> u64 {
+        let prescale_shift = match (self.control & CONTROL_PRESCALE_MASK) >> CONTROL_PRESCALE_SHIFT
+        {
+            0 => 0,
+            1 => 4,
+            _ => 8,
+        };
+        (NANOS_PER_SEC << prescale_shift) / SP804_CLOCK_HZ
+    }
+
+    // Returns the value of the counter at `now_ns`.
+    fn value(&self, now_ns: u64) -> u32 {
+        let start_ns = match self.start_ns {
+            Some(start_ns) => start_ns,
+            None => return self.value,
+        };
+
+        // The counter stays at 0 until the next tick, when it reloads.
+        if now_ns < start_ns {
+            return 0;
+        }
+        let ticks = (now_ns - start_ns) / self.tick_ns();
+        let value = u64::from(self.value);
+        if ticks <= value {
+            return (value - ticks) as u32;
+        }
+        if (self.control & CONTROL_ONESHOT) != 0 {
+            return 0;
+        }
+        let reload = u64::from(self.reload_value());
+        (reload - (ticks - value - 1) % (reload + 1)) as u32
+    }
+
+    // Returns the time when the counter reaches 0, if it is counting.
+    fn deadline_ns(&self) -> Option<u64> {
+        self.start_ns
+            .map(|start_ns| start_ns + u64::from(self.value) * self.tick_ns())
+    }
+
+    // Starts counting from `value`, if enabled.
+    fn start(&mut self, value: u32, now_ns: u64) {
+        self.value = value & self.max_value();
+        self.start_ns = if self.enabled() { Some(now_ns) } else { None };
+    }
+
+    // Updates the counter if it reached 0 at `now_ns`. Multiple expiries
+    // since the last call only count once.
+    fn check_expired(&mut self, now_ns: u64) {
+        let deadline_ns = match self.deadline_ns() {
+            Some(deadline_ns) if now_ns >= deadline_ns => deadline_ns,
+            _ => return,
+        };
+
+        self.ris = true;
+        if (self.control & CONTROL_ONESHOT) != 0 {
+            self.value = 0;
+            self.start_ns = None;
+            return;
+        }
+
+        let tick_ns = self.tick_ns();
+        let reload = u64::from(self.reload_value());
+        let period_ns = (reload + 1) * tick_ns;
+        // The counter is reloaded on the next tick, and skips the periods
+        // that already passed.
+        let mut start_ns = deadline_ns + tick_ns;
+        if now_ns >= start_ns + reload * tick_ns {
+            start_ns += ((now_ns - start_ns - reload * tick_ns) / period_ns + 1) * period_ns;
+        }
+        self.value = reload as u32;
+        self.start_ns = Some(start_ns);
+    }
+
+    fn read(&self, offset: u16, now_ns: u64) -> u32 {
+        match offset {
+            TIMER_LOAD | TIMER_BGLOAD => self.load,
+            TIMER_VALUE => self.value(now_ns),
+            TIMER_CONTROL => self.control,
+            TIMER_RIS => u32::from(self.ris),
+            TIMER_MIS => u32::from(self.interrupt()),
+            _ => {
+                guest_warn!("Invalid SP804 timer read at offset {:#x}", offset);
+                0
+            }
+        }
+    }
+
+    fn write(&mut self, offset: u16, val: u32, now_ns: u64) {
+        match offset {
+            TIMER_LOAD => {
+                self.load = val;
+                self.start(val, now_ns);
+            }
+            // The background load only applies the next time the counter
+            // reaches 0.
+            TIMER_BGLOAD => self.load = val,
+            TIMER_CONTROL => {
+                // The counter carries on from its current value with the
+                // new configuration, unless it is a one-shot counter which
+                // already stopped.
+                let value = self.value(now_ns);
+                let restart = self.start_ns.is_some() || !self.enabled();
+                self.control = val & CONTROL_MASK;
+                if restart {
+                    self.start(value, now_ns);
+                }
+            }
+            TIMER_INTCLR => self.ris = false,
+            // The other registers are read-only.
+            _ => guest_warn!("Invalid SP804 timer write at offset {:#x}", offset),
+        }
+    }
+}
+
+/// An ARM SP804 dual-timer module.
+///
+/// The counters are derived from the time reported by a
+/// [`ClockSource`](../pit/trait.ClockSource.html), and count at
+/// [`SP804_CLOCK_HZ`](constant.SP804_CLOCK_HZ.html), divided by their
+/// prescalers. The combined interrupt of the counters is raised through a
+/// [`Trigger`](../trait.Trigger.html) object. The device has no timer of its
+/// own: the VMM arms one for the time returned by
+/// [`next_deadline`](#method.next_deadline), and calls
+/// [`on_timer_expired`](#method.on_timer_expired) once it fires.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::pit::{ClockSource, MonotonicClock};
+/// # use vm_superio::sp804::Sp804;
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut timer = Sp804::new(DummyTrigger, MonotonicClock::new());
+///
+/// // Program the first counter as a 32 bit periodic timer, with a 100 Hz
+/// // frequency and the interrupt enabled.
+/// timer.write(0x00, &10_000u32.to_le_bytes()).unwrap();
+/// timer.write(0x08, &0xE2u32.to_le_bytes()).unwrap();
+///
+/// // The first interrupt is due in 10 ms.
+/// let deadline = timer.next_deadline().unwrap();
+/// assert!(deadline - timer.clock().now_ns() <= 10_000_000);
+///
+/// // Once the deadline passes, the interrupt is raised by calling
+/// // `on_timer_expired`, and the guest clears it.
+/// timer.on_timer_expired().unwrap();
+/// timer.write(0x0C, &0u32.to_le_bytes()).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Sp804<T: Trigger, C: ClockSource> {
+    // The combined interrupt of the counters.
+    interrupt_evt: T,
+    clock: C,
+    timers: [Timer; NUM_TIMERS],
+    // The level of the combined interrupt.
+    interrupt: bool,
+}
+
+impl<T: Trigger, C: ClockSource> Sp804<T, C> {
+    /// Creates a new `Sp804` instance, which derives the values of its
+    /// counters from `clock`, and raises their combined interrupt through
+    /// `interrupt_evt`.
+    ///
+    /// # Arguments
+    /// * `interrupt_evt` - The Trigger object used for raising the combined
+    ///   interrupt of the counters.
+    /// * `clock` - The source of the time used by the counters.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Sp804`](struct.Sp804.html#example).
+    pub fn new(interrupt_evt: T, clock: C) -> Self {
+        Sp804 {
+            interrupt_evt,
+            clock,
+            timers: [Timer::new(); NUM_TIMERS],
+            interrupt: false,
+        }
+    }
+
+    /// Provides a reference to the clock source.
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Provides a reference to the interrupt event object.
+    pub fn interrupt_evt(&self) -> &T {
+        &self.interrupt_evt
+    }
+
+    /// Returns whether the combined interrupt is asserted. The interrupt is
+    /// level triggered, so the VMM can use this for keeping the line high
+    /// until the guest clears it.
+    pub fn interrupt_pending(&self) -> bool {
+        self.interrupt
+    }
+
+    /// Returns the time, as reported by the clock source, when the next
+    /// counter reaches 0, or `None` if none of them is counting.
+    ///
+    /// The deadline changes when the driver programs the counters, so the
+    /// VMM should query it again after writes to the device, as well as after
+    /// calling [`on_timer_expired`](#method.on_timer_expired).
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.timers.iter().filter_map(Timer::deadline_ns).min()
+    }
+
+    // Updates the level of the combined interrupt, and signals its rising
+    // edges.
+    fn update_interrupt(&mut self) -> Result<(), T::E> {
+        let interrupt = self.timers.iter().any(Timer::interrupt);
+        let raised = interrupt && !self.interrupt;
+        self.interrupt = interrupt;
+        if raised {
+            return self.interrupt_evt.trigger();
+        }
+        Ok(())
+    }
+
+    /// Handles the counters which reached 0, if the deadline returned by
+    /// [`next_deadline`](#method.next_deadline) passed, and raises the
+    /// interrupt if enabled. Multiple deadlines that passed since the last
+    /// call only raise the interrupt once, like a late timerfd does.
+    pub fn on_timer_expired(&mut self) -> Result<(), T::E> {
+        let now_ns = self.clock.now_ns();
+        for timer in self.timers.iter_mut() {
+            timer.check_expired(now_ns);
+        }
+        self.update_interrupt()
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// Invalid offsets, and the write only interrupt clear registers, read
+    /// as 0.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The little-endian, 4 byte array storing the read value.
+    pub fn read(&self, offset: u16, data: &mut [u8; 4]) {
+        let v = if (AMBA_ID_LOW..=AMBA_ID_HIGH).contains(&offset) {
+            let index = ((offset - AMBA_ID_LOW) >> 2) as usize;
+            u32::from(AMBA_IDS[index])
+        } else {
+            match self.timers.get(usize::from(offset / TIMER_STRIDE)) {
+                Some(timer) => timer.read(offset % TIMER_STRIDE, self.clock.now_ns()),
+                None => {
+                    guest_warn!("Invalid SP804 read at offset {:#x}", offset);
+                    0
+                }
+            }
+        };
+
+        *data = v.to_le_bytes();
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The little endian, 4 byte array to write to the register.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Sp804`](struct.Sp804.html#example).
+    pub fn write(&mut self, offset: u16, data: &[u8; 4]) -> Result<(), T::E> {
+        let now_ns = self.clock.now_ns();
+        if let Some(timer) = self.timers.get_mut(usize::from(offset / TIMER_STRIDE)) {
+            timer.write(offset % TIMER_STRIDE, u32::from_le_bytes(*data), now_ns);
+        } else {
+            guest_warn!("Invalid SP804 write at offset {:#x}", offset);
+        }
+        self.update_interrupt()
+    }
+}
+
+impl<T: Trigger, C: ClockSource> LegacyDevice for Sp804<T, C> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match <&mut [u8; 4]>::try_from(&mut *data) {
+            Ok(data) => Sp804::read(self, offset, data),
+            Err(_) => {
+                guest_warn!(
+                    "Ignoring a {}-byte access at offset {:#x}",
+                    data.len(),
+                    offset
+                );
+                data.fill(0)
+            }
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Ok(data) = <&[u8; 4]>::try_from(data) {
+            let _ = Sp804::write(self, offset, data);
+        } else {
+            guest_warn!(
+                "Ignoring a {}-byte access at offset {:#x}",
+                data.len(),
+                offset
+            );
+        }
+    }
+
+    fn reset(&mut self) {
+        self.timers = [Timer::new(); NUM_TIMERS];
+        self.interrupt = false;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockClock;
+    use std::io;
+    use vmm_sys_util::eventfd::EventFd;
+
+    type TestSp804 = Sp804<EventFd, MockClock>;
+
+    fn new_sp804() -> (TestSp804, MockClock) {
+        let clock = MockClock::new();
+        let timer = Sp804::new(EventFd::new(libc::EFD_NONBLOCK).unwrap(), clock.clone());
+        (timer, clock)
+    }
+
+    fn evt_count(evt: &EventFd) -> u64 {
+        match evt.read() {
+            Ok(count) => count,
+            Err(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+                0
+            }
+        }
+    }
+
+    fn read_reg(timer: &TestSp804, offset: u16) -> u32 {
+        let mut data = [0; 4];
+        timer.read(offset, &mut data);
+        u32::from_le_bytes(data)
+    }
+
+    fn write_reg(timer: &mut TestSp804, offset: u16, value: u32) {
+        timer.write(offset, &value.to_le_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_sp804_registers() {
+        let (mut timer, clock) = new_sp804();
+        for i in 0..NUM_TIMERS as u16 {
+            let base = i * TIMER_STRIDE;
+            assert_eq!(read_reg(&timer, base + TIMER_CONTROL), DEFAULT_CONTROL);
+            assert_eq!(read_reg(&timer, base + TIMER_VALUE), DEFAULT_VALUE);
+            assert_eq!(read_reg(&timer, base + TIMER_LOAD), 0);
+        }
+        assert_eq!(timer.next_deadline(), None);
+
+        // The counter doesn't run while disabled.
+        write_reg(&mut timer, TIMER_STRIDE + TIMER_LOAD, 100);
+        assert_eq!(read_reg(&timer, TIMER_STRIDE + TIMER_BGLOAD), 100);
+        assert_eq!(read_reg(&timer, TIMER_STRIDE + TIMER_VALUE), 100);
+        clock.set_ns(1_000_000);
+        assert_eq!(read_reg(&timer, TIMER_STRIDE + TIMER_VALUE), 100);
+        assert_eq!(read_reg(&timer, TIMER_VALUE), DEFAULT_VALUE);
+
+        write_reg(&mut timer, TIMER_CONTROL, 0xFF);
+        assert_eq!(read_reg(&timer, TIMER_CONTROL), CONTROL_MASK);
+
+        for (i, &id) in AMBA_IDS.iter().enumerate() {
+            assert_eq!(read_reg(&timer, AMBA_ID_LOW + 4 * i as u16), u32::from(id));
+        }
+        assert_eq!(read_reg(&timer, TIMER_INTCLR), 0);
+        assert_eq!(read_reg(&timer, 0x800), 0);
+    }
+
+    #[test]
+    fn test_sp804_periodic() {
+        let (mut timer, clock) = new_sp804();
+        clock.set_ns(1_000);
+
+        // A 32 bit periodic counter, with a prescaler of 16.
+        write_reg(&mut timer, TIMER_LOAD, 9);
+        write_reg(
+            &mut timer,
+            TIMER_CONTROL,
+            CONTROL_ENABLE | CONTROL_PERIODIC | CONTROL_INTEN | CONTROL_32BIT | (1 << 2),
+        );
+        assert_eq!(timer.next_deadline(), Some(1_000 + 9 * 16_000));
+        clock.set_ns(1_000 + 4 * 16_000);
+        assert_eq!(read_reg(&timer, TIMER_VALUE), 5);
+
+        // Nothing happens before the deadline.
+        timer.on_timer_expired().unwrap();
+        assert!(!timer.interrupt_pending());
+
+        clock.set_ns(1_000 + 9 * 16_000);
+        timer.on_timer_expired().unwrap();
+        assert!(timer.interrupt_pending());
+        assert_eq!(evt_count(timer.interrupt_evt()), 1);
+        assert_eq!(read_reg(&timer, TIMER_RIS), 1);
+        assert_eq!(read_reg(&timer, TIMER_MIS), 1);
+        assert_eq!(read_reg(&timer, TIMER_VALUE), 0);
+        // The counter reloads on the next tick.
+        assert_eq!(timer.next_deadline(), Some(1_000 + 19 * 16_000));
+        clock.set_ns(1_000 + 10 * 16_000);
+        assert_eq!(read_reg(&timer, TIMER_VALUE), 9);
+
+        write_reg(&mut timer, TIMER_INTCLR, 0);
+        assert!(!timer.interrupt_pending());
+
+        // The deadlines missed by the VMM only raise the interrupt once.
+        clock.set_ns(1_000 + 45 * 16_000);
+        assert_eq!(read_reg(&timer, TIMER_VALUE), 4);
+        timer.on_timer_expired().unwrap();
+        assert_eq!(evt_count(timer.interrupt_evt()), 1);
+        assert_eq!(timer.next_deadline(), Some(1_000 + 49 * 16_000));
+        assert_eq!(read_reg(&timer, TIMER_VALUE), 4);
+    }
+
+    #[test]
+    fn test_sp804_free_running() {
+        let (mut timer, clock) = new_sp804();
+
+        // A 16 bit free-running counter, with the interrupt masked, wraps
+        // around to 0xFFFF.
+        write_reg(&mut timer, TIMER_LOAD, 0x1_0002);
+        write_reg(&mut timer, TIMER_CONTROL, CONTROL_ENABLE);
+        assert_eq!(read_reg(&timer, TIMER_VALUE), 2);
+        clock.set_ns(2_000);
+        timer.on_timer_expired().unwrap();
+        assert!(!timer.interrupt_pending());
+        assert_eq!(read_reg(&timer, TIMER_RIS), 1);
+        assert_eq!(read_reg(&timer, TIMER_MIS), 0);
+        clock.set_ns(3_000);
+        assert_eq!(read_reg(&timer, TIMER_VALUE), 0xFFFF);
+
+        // Unmasking the pending interrupt raises it.
+        write_reg(&mut timer, TIMER_CONTROL, CONTROL_ENABLE | CONTROL_INTEN);
+        assert!(timer.interrupt_pending());
+        assert_eq!(evt_count(timer.interrupt_evt()), 1);
+
+        // Disabling the counter stops it.
+        clock.set_ns(5_000);
+        write_reg(&mut timer, TIMER_CONTROL, 0);
+        assert_eq!(timer.next_deadline(), None);
+        clock.set_ns(10_000);
+        assert_eq!(read_reg(&timer, TIMER_VALUE), 0xFFFD);
+    }
+
+    #[test]
+    fn test_sp804_oneshot() {
+        let (mut timer, clock) = new_sp804();
+
+        // A one-shot counter on the second timer, with a prescaler of 256.
+        write_reg(&mut timer, TIMER_STRIDE + TIMER_LOAD, 3);
+        write_reg(
+            &mut timer,
+            TIMER_STRIDE + TIMER_CONTROL,
+            CONTROL_ENABLE | CONTROL_ONESHOT | CONTROL_INTEN | (2 << 2),
+        );
+        assert_eq!(timer.next_deadline(), Some(3 * 256_000));
+        clock.set_ns(10 * 256_000);
+        assert_eq!(read_reg(&timer, TIMER_STRIDE + TIMER_VALUE), 0);
+        timer.on_timer_expired().unwrap();
+        assert!(timer.interrupt_pending());
+        assert_eq!(timer.next_deadline(), None);
+        assert_eq!(read_reg(&timer, TIMER_STRIDE + TIMER_VALUE), 0);
+
+        // The load register starts it again.
+        write_reg(&mut timer, TIMER_STRIDE + TIMER_INTCLR, 0);
+        write_reg(&mut timer, TIMER_STRIDE + TIMER_LOAD, 1);
+        assert_eq!(timer.next_deadline(), Some(11 * 256_000));
+        assert!(!timer.interrupt_pending());
+    }
+
+    #[test]
+    fn test_sp804_legacy_device() {
+        let (mut timer, _) = new_sp804();
+
+        LegacyDevice::write(&mut timer, TIMER_LOAD, &1000u32.to_le_bytes());
+        LegacyDevice::write(
+            &mut timer,
+            TIMER_CONTROL,
+            &(CONTROL_ENABLE | CONTROL_32BIT).to_le_bytes(),
+        );
+        assert!(timer.next_deadline().is_some());
+        let mut data = [0; 4];
+        LegacyDevice::read(&mut timer, TIMER_LOAD, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 1000);
+
+        // The reset stops the counters.
+        timer.reset();
+        assert!(timer.next_deadline().is_none());
+        assert_eq!(read_reg(&timer, TIMER_LOAD), 0);
+        assert_eq!(read_reg(&timer, TIMER_CONTROL), DEFAULT_CONTROL);
+    }
+}
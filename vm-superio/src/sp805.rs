@@ -0,0 +1,601 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the ARM SP805 watchdog module.
+//!
+//! The watchdog is handled by the Linux `sp805_wdt` driver. Its counter
+//! decrements while the interrupt is enabled, and raises the interrupt when
+//! it reaches 0, before reloading. If it reaches 0 again while the interrupt
+//! is still pending, i.e. the guest is considered hung, the watchdog resets
+//! the system.
+
+use core::convert::TryFrom;
+use core::result::Result;
+
+use crate::pit::ClockSource;
+use crate::watchdog::{NoEvents, WatchdogAction, WatchdogEvents};
+use crate::{LegacyDevice, Trigger};
+
+// The following defines are mapping to the specification:
+// https://developer.arm.com/documentation/ddi0270/b/programmer-s-model/summary-of-watchdog-registers
+const WDOGLOAD: u16 = 0x000; // Load Register.
+const WDOGVALUE: u16 = 0x004; // Value Register (RO).
+const WDOGCONTROL: u16 = 0x008; // Control Register.
+const WDOGINTCLR: u16 = 0x00C; // Interrupt Clear Register (WO).
+const WDOGRIS: u16 = 0x010; // Raw Interrupt Status Register (RO).
+const WDOGMIS: u16 = 0x014; // Masked Interrupt Status Register (RO).
+const WDOGLOCK: u16 = 0xC00; // Lock Register.
+
+// From 0xFE0 to 0xFFF => Peripheral and PrimeCell Identification Registers.
+const AMBA_IDS: [u8; 8] = [0x05, 0x18, 0x14, 0x00, 0x0d, 0xf0, 0x05, 0xb1];
+const AMBA_ID_LOW: u16 = 0xFE0;
+const AMBA_ID_HIGH: u16 = 0xFFF;
+
+// Control register bits.
+// Enables the counter and the interrupt.
+const CONTROL_INTEN: u32 = 1 << 0;
+// Enables the reset output.
+const CONTROL_RESEN: u32 = 1 << 1;
+const CONTROL_MASK: u32 = CONTROL_INTEN | CONTROL_RESEN;
+
+// Writing this value to the lock register enables the writes to the other
+// registers, while writing any other value disables them.
+const UNLOCK_KEY: u32 = 0x1ACC_E551;
+
+// The value of the counter after reset.
+const DEFAULT_LOAD: u32 = 0xFFFF_FFFF;
+
+/// The frequency of the watchdog clock (WDOGCLK), which the device tree
+/// should describe as the fixed clock of the device.
+pub const SP805_CLOCK_HZ: u64 = 1_000_000;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// An ARM SP805 watchdog.
+///
+/// The counter is derived from the time reported by a
+/// [`ClockSource`](../pit/trait.ClockSource.html), and counts at
+/// [`SP805_CLOCK_HZ`](constant.SP805_CLOCK_HZ.html). The VMM arms a timer for
+/// the deadline returned by [`next_deadline`](#method.next_deadline), and
+/// calls [`on_timer_expired`](#method.on_timer_expired) when it fires.
+/// Two [`Trigger`](../trait.Trigger.html) objects are used: one for the
+/// interrupt raised when the counter first reaches 0, and one for the
+/// [expiry action](#method.set_expiry_action) taken when it reaches 0 again,
+/// which is the system reset by default.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::pit::MonotonicClock;
+/// # use vm_superio::sp805::Sp805;
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// let mut wdt = Sp805::new(DummyTrigger, DummyTrigger, MonotonicClock::new());
+///
+/// // Unlock the registers, and start the watchdog with a 1 second period,
+/// // like the Linux driver.
+/// wdt.write(0xC00, &0x1ACC_E551u32.to_le_bytes());
+/// wdt.write(0x000, &1_000_000u32.to_le_bytes());
+/// wdt.write(0x008, &0x03u32.to_le_bytes());
+/// wdt.write(0xC00, &0u32.to_le_bytes());
+/// assert!(wdt.next_deadline().is_some());
+///
+/// // Ping it periodically.
+/// wdt.write(0xC00, &0x1ACC_E551u32.to_le_bytes());
+/// wdt.write(0x00C, &0u32.to_le_bytes());
+/// wdt.write(0xC00, &0u32.to_le_bytes());
+/// ```
+#[derive(Debug)]
+pub struct Sp805<T: Trigger, C: ClockSource, EV: WatchdogEvents> {
+    // Interrupt raised when the counter first reaches 0.
+    irq_evt: T,
+    // Expiry action event object, i.e. the system reset by default.
+    reset_evt: T,
+    clock: C,
+    events: EV,
+    expiry_action: WatchdogAction,
+    load: u32,
+    control: u32,
+    // Whether the interrupt is pending.
+    ris: bool,
+    // Whether the writes to the registers, other than the lock register,
+    // are disabled.
+    locked: bool,
+    // The value of the counter, while it is stopped.
+    value: u32,
+    // The time when the counter reaches 0, while it is counting.
+    deadline_ns: Option<u64>,
+}
+
+impl<T: Trigger, C: ClockSource> Sp805<T, C, NoEvents> {
+    /// Creates a new `Sp805` instance, which will signal `irq_evt` when the
+    /// counter first reaches 0, and `reset_evt` when it reaches 0 again.
+    ///
+    /// # Arguments
+    /// * `irq_evt` - A Trigger object that will be used to raise the
+    ///   interrupt of the device.
+    /// * `reset_evt` - A Trigger object that will be used to notify the VMM
+    ///   about the expiry action, i.e. the system reset by default.
+    /// * `clock` - The source of the time from which the counter is derived.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Sp805`](struct.Sp805.html#example).
+    pub fn new(irq_evt: T, reset_evt: T, clock: C) -> Self {
+        Self::with_events(irq_evt, reset_evt, clock, NoEvents)
+    }
+}
+
+impl<T: Trigger, C: ClockSource, EV: WatchdogEvents> Sp805<T, C, EV> {
+    /// Creates a new `Sp805` instance, which will signal `irq_evt` when the
+    /// counter first reaches 0, and `reset_evt` when it reaches 0 again, and
+    /// invokes the `sp805_evts` implementation of `WatchdogEvents` during
+    /// operation.
+    ///
+    /// # Arguments
+    /// * `irq_evt` - A Trigger object that will be used to raise the
+    ///   interrupt of the device.
+    /// * `reset_evt` - A Trigger object that will be used to notify the VMM
+    ///   about the expiry action, i.e. the system reset by default.
+    /// * `clock` - The source of the time from which the counter is derived.
+    /// * `sp805_evts` - The `WatchdogEvents` implementation used to track the
+    ///   expiry of the watchdog.
+    pub fn with_events(irq_evt: T, reset_evt: T, clock: C, sp805_evts: EV) -> Self {
+        Sp805 {
+            irq_evt,
+            reset_evt,
+            clock,
+            events: sp805_evts,
+            expiry_action: WatchdogAction::default(),
+            load: DEFAULT_LOAD,
+            control: 0,
+            ris: false,
+            locked: false,
+            value: DEFAULT_LOAD,
+            deadline_ns: None,
+        }
+    }
+
+    /// Provides a reference to the interrupt event object.
+    pub fn irq_evt(&self) -> &T {
+        &self.irq_evt
+    }
+
+    /// Provides a reference to the reset event object.
+    pub fn reset_evt(&self) -> &T {
+        &self.reset_evt
+    }
+
+    /// Provides a reference to the clock source.
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Provides a reference to the SP805 events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Returns the action taken when the watchdog expires.
+    pub fn expiry_action(&self) -> WatchdogAction {
+        self.expiry_action
+    }
+
+    /// Sets the action taken when the watchdog expires, which is
+    /// `WatchdogAction::Reset` by default.
+    ///
+    /// # Arguments
+    /// * `action` - The action reported through `WatchdogEvents::expired`,
+    ///   for which the reset event object is signaled, unless it is
+    ///   `WatchdogAction::NotifyOnly`.
+    pub fn set_expiry_action(&mut self, action: WatchdogAction) {
+        self.expiry_action = action;
+    }
+
+    /// Returns whether the watchdog is counting, i.e. whether the guest
+    /// enabled its interrupt.
+    pub fn enabled(&self) -> bool {
+        (self.control & CONTROL_INTEN) != 0
+    }
+
+    /// Returns the time, as reported by the clock source, when the counter
+    /// reaches 0, or `None` if the watchdog is disabled.
+    ///
+    /// The deadline changes when the guest reloads or reconfigures the
+    /// watchdog, so the VMM should query it again after writes to the
+    /// device, as well as after calling
+    /// [`on_timer_expired`](#method.on_timer_expired).
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.deadline_ns
+    }
+
+    // Reloads the counter from the load register, if it is counting. A load
+    // value of 0 expires right away.
+    fn reload(&mut self, now_ns: u64) {
+        self.value = self.load;
+        self.deadline_ns = if self.enabled() {
+            let duration_ns = u64::from(self.load) * NANOS_PER_SEC / SP805_CLOCK_HZ;
+            Some(now_ns + duration_ns)
+        } else {
+            None
+        };
+    }
+
+    // Returns the current value of the counter.
+    fn counter(&self) -> u32 {
+        match self.deadline_ns {
+            Some(deadline_ns) => {
+                let remaining_ns = deadline_ns.saturating_sub(self.clock.now_ns());
+                // The counter can't exceed the load value, so it fits in u32.
+                (remaining_ns * SP805_CLOCK_HZ / NANOS_PER_SEC) as u32
+            }
+            None => self.value,
+        }
+    }
+
+    /// Handles the expiry of the deadline returned by
+    /// [`next_deadline`](#method.next_deadline), if it passed.
+    ///
+    /// When the counter reaches 0, it raises the interrupt, or if the
+    /// interrupt is still pending, takes the expiry action, unless the guest
+    /// disabled the reset output. In both cases, the counter is reloaded.
+    pub fn on_timer_expired(&mut self) -> Result<(), T::E> {
+        let now_ns = self.clock.now_ns();
+        match self.deadline_ns {
+            Some(deadline_ns) if now_ns >= deadline_ns => (),
+            _ => return Ok(()),
+        }
+
+        self.reload(now_ns);
+        if !self.ris {
+            self.ris = true;
+            self.events.pretimeout();
+            return self.irq_evt.trigger();
+        }
+
+        let action = if (self.control & CONTROL_RESEN) != 0 {
+            self.expiry_action
+        } else {
+            WatchdogAction::NotifyOnly
+        };
+        self.events.expired(action);
+        if action != WatchdogAction::NotifyOnly {
+            return self.reset_evt.trigger();
+        }
+        Ok(())
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// The interrupt clear register is write only, so it reads as 0, like
+    /// the invalid offsets.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The little-endian, 4 byte array storing the read value.
+    pub fn read(&self, offset: u16, data: &mut [u8; 4]) {
+        let v = if (AMBA_ID_LOW..=AMBA_ID_HIGH).contains(&offset) {
+            let index = ((offset - AMBA_ID_LOW) >> 2) as usize;
+            u32::from(AMBA_IDS[index])
+        } else {
+            match offset {
+                WDOGLOAD => self.load,
+                WDOGVALUE => self.counter(),
+                WDOGCONTROL => self.control,
+                WDOGRIS => u32::from(self.ris),
+                WDOGMIS => u32::from(self.ris && self.enabled()),
+                WDOGLOCK => u32::from(self.locked),
+                WDOGINTCLR => 0,
+                _ => {
+                    guest_warn!("Invalid SP805 read at offset {:#x}", offset);
+                    0
+                }
+            }
+        };
+
+        *data = v.to_le_bytes();
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// While the registers are locked, only the lock register can be
+    /// written, i.e. the guest has to unlock them first, by writing
+    /// 0x1ACCE551 to the lock register.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The little endian, 4 byte array to write to the register.
+    pub fn write(&mut self, offset: u16, data: &[u8; 4]) {
+        let val = u32::from_le_bytes(*data);
+
+        if offset == WDOGLOCK {
+            self.locked = val != UNLOCK_KEY;
+            return;
+        }
+        if self.locked {
+            guest_warn!("Ignoring the locked SP805 write at offset {:#x}", offset);
+            return;
+        }
+
+        match offset {
+            WDOGLOAD => {
+                self.load = val;
+                let now_ns = self.clock.now_ns();
+                self.reload(now_ns);
+            }
+            WDOGCONTROL => {
+                let was_enabled = self.enabled();
+                self.control = val & CONTROL_MASK;
+                if self.enabled() && !was_enabled {
+                    let now_ns = self.clock.now_ns();
+                    self.reload(now_ns);
+                } else if !self.enabled() && was_enabled {
+                    // The counter stops at its current value.
+                    self.value = self.counter();
+                    self.deadline_ns = None;
+                }
+            }
+            WDOGINTCLR => {
+                self.ris = false;
+                let now_ns = self.clock.now_ns();
+                self.reload(now_ns);
+            }
+            // The other registers are read-only.
+            _ => guest_warn!("Invalid SP805 write at offset {:#x}", offset),
+        }
+    }
+}
+
+impl<T: Trigger, C: ClockSource, EV: WatchdogEvents> LegacyDevice for Sp805<T, C, EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match <&mut [u8; 4]>::try_from(&mut *data) {
+            Ok(data) => Sp805::read(self, offset, data),
+            Err(_) => {
+                guest_warn!(
+                    "Ignoring a {}-byte access at offset {:#x}",
+                    data.len(),
+                    offset
+                );
+                data.fill(0)
+            }
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Ok(data) = <&[u8; 4]>::try_from(data) {
+            Sp805::write(self, offset, data);
+        } else {
+            guest_warn!(
+                "Ignoring a {}-byte access at offset {:#x}",
+                data.len(),
+                offset
+            );
+        }
+    }
+
+    fn reset(&mut self) {
+        self.load = DEFAULT_LOAD;
+        self.control = 0;
+        self.ris = false;
+        self.locked = false;
+        self.value = DEFAULT_LOAD;
+        self.deadline_ns = None;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockClock;
+    use std::io;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::{Arc, Mutex};
+    use vmm_sys_util::eventfd::EventFd;
+    use vmm_sys_util::metric::Metric;
+
+    #[derive(Default)]
+    struct ExampleSp805Events {
+        pretimeouts: AtomicU64,
+        timeouts: AtomicU64,
+        reboots: AtomicU64,
+        actions: Mutex<Vec<WatchdogAction>>,
+    }
+
+    impl WatchdogEvents for ExampleSp805Events {
+        fn pretimeout(&self) {
+            self.pretimeouts.inc();
+        }
+
+        fn expired(&self, action: WatchdogAction) {
+            self.timeouts.inc();
+            if action != WatchdogAction::NotifyOnly {
+                self.reboots.inc();
+            }
+            self.actions.lock().unwrap().push(action);
+        }
+    }
+
+    type TestSp805 = Sp805<EventFd, MockClock, Arc<ExampleSp805Events>>;
+
+    fn new_sp805() -> (TestSp805, MockClock) {
+        let clock = MockClock::new();
+        let wdt = Sp805::with_events(
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            clock.clone(),
+            Arc::new(ExampleSp805Events::default()),
+        );
+        (wdt, clock)
+    }
+
+    fn evt_count(evt: &EventFd) -> u64 {
+        match evt.read() {
+            Ok(count) => count,
+            Err(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+                0
+            }
+        }
+    }
+
+    fn read_reg(wdt: &TestSp805, offset: u16) -> u32 {
+        let mut data = [0; 4];
+        wdt.read(offset, &mut data);
+        u32::from_le_bytes(data)
+    }
+
+    fn write_reg(wdt: &mut TestSp805, offset: u16, value: u32) {
+        wdt.write(offset, &value.to_le_bytes());
+    }
+
+    fn expire(wdt: &mut TestSp805, clock: &MockClock) {
+        clock.set_ns(wdt.next_deadline().unwrap());
+        wdt.on_timer_expired().unwrap();
+    }
+
+    #[test]
+    fn test_sp805_registers() {
+        let (mut wdt, clock) = new_sp805();
+        assert_eq!(read_reg(&wdt, WDOGLOAD), DEFAULT_LOAD);
+        assert_eq!(read_reg(&wdt, WDOGVALUE), DEFAULT_LOAD);
+        assert_eq!(wdt.next_deadline(), None);
+
+        // The counter only decrements while enabled.
+        write_reg(&mut wdt, WDOGLOAD, 1000);
+        clock.set_ns(10_000);
+        assert_eq!(read_reg(&wdt, WDOGVALUE), 1000);
+        write_reg(&mut wdt, WDOGCONTROL, 0xFF);
+        assert_eq!(read_reg(&wdt, WDOGCONTROL), CONTROL_MASK);
+        assert!(wdt.enabled());
+        assert_eq!(wdt.next_deadline(), Some(10_000 + 1_000_000));
+        clock.set_ns(10_000 + 400_000);
+        assert_eq!(read_reg(&wdt, WDOGVALUE), 600);
+
+        // Disabling the watchdog stops the counter.
+        write_reg(&mut wdt, WDOGCONTROL, 0);
+        assert_eq!(wdt.next_deadline(), None);
+        clock.set_ns(10_000_000);
+        assert_eq!(read_reg(&wdt, WDOGVALUE), 600);
+
+        for (i, &id) in AMBA_IDS.iter().enumerate() {
+            assert_eq!(read_reg(&wdt, AMBA_ID_LOW + 4 * i as u16), u32::from(id));
+        }
+        assert_eq!(read_reg(&wdt, WDOGINTCLR), 0);
+        assert_eq!(read_reg(&wdt, 0x800), 0);
+    }
+
+    #[test]
+    fn test_sp805_lock() {
+        let (mut wdt, _) = new_sp805();
+
+        write_reg(&mut wdt, WDOGLOCK, 0);
+        assert_eq!(read_reg(&wdt, WDOGLOCK), 1);
+        write_reg(&mut wdt, WDOGLOAD, 10);
+        write_reg(&mut wdt, WDOGCONTROL, CONTROL_INTEN);
+        assert_eq!(read_reg(&wdt, WDOGLOAD), DEFAULT_LOAD);
+        assert!(!wdt.enabled());
+
+        write_reg(&mut wdt, WDOGLOCK, UNLOCK_KEY);
+        assert_eq!(read_reg(&wdt, WDOGLOCK), 0);
+        write_reg(&mut wdt, WDOGLOAD, 10);
+        assert_eq!(read_reg(&wdt, WDOGLOAD), 10);
+    }
+
+    #[test]
+    fn test_sp805_expiry() {
+        let (mut wdt, clock) = new_sp805();
+        write_reg(&mut wdt, WDOGLOAD, 1000);
+        write_reg(&mut wdt, WDOGCONTROL, CONTROL_INTEN | CONTROL_RESEN);
+
+        // Nothing happens before the deadline.
+        wdt.on_timer_expired().unwrap();
+        assert_eq!(wdt.events().pretimeouts.count(), 0);
+
+        // The first expiry raises the interrupt.
+        expire(&mut wdt, &clock);
+        assert_eq!(wdt.events().pretimeouts.count(), 1);
+        assert_eq!(evt_count(wdt.irq_evt()), 1);
+        assert_eq!(read_reg(&wdt, WDOGRIS), 1);
+        assert_eq!(read_reg(&wdt, WDOGMIS), 1);
+        let now_ns = clock.now_ns();
+        assert_eq!(wdt.next_deadline(), Some(now_ns + 1_000_000));
+
+        // Clearing the interrupt reloads the counter.
+        clock.advance_ns(500_000);
+        write_reg(&mut wdt, WDOGINTCLR, 0);
+        assert_eq!(read_reg(&wdt, WDOGRIS), 0);
+        assert_eq!(wdt.next_deadline(), Some(now_ns + 1_500_000));
+
+        // The second expiry, with the interrupt pending, resets the system.
+        expire(&mut wdt, &clock);
+        expire(&mut wdt, &clock);
+        assert_eq!(wdt.events().pretimeouts.count(), 2);
+        assert_eq!(wdt.events().timeouts.count(), 1);
+        assert_eq!(wdt.events().reboots.count(), 1);
+        assert_eq!(evt_count(wdt.irq_evt()), 1);
+        assert_eq!(evt_count(wdt.reset_evt()), 1);
+
+        // Without the reset output, the counter just reloads.
+        write_reg(&mut wdt, WDOGCONTROL, CONTROL_INTEN);
+        expire(&mut wdt, &clock);
+        assert_eq!(wdt.events().timeouts.count(), 2);
+        assert_eq!(wdt.events().reboots.count(), 1);
+        assert_eq!(evt_count(wdt.reset_evt()), 0);
+        assert!(wdt.next_deadline().is_some());
+    }
+
+    #[test]
+    fn test_sp805_expiry_action() {
+        let (mut wdt, clock) = new_sp805();
+        assert_eq!(wdt.expiry_action(), WatchdogAction::Reset);
+        wdt.set_expiry_action(WatchdogAction::Poweroff);
+        write_reg(&mut wdt, WDOGLOAD, 1000);
+        write_reg(&mut wdt, WDOGCONTROL, CONTROL_INTEN | CONTROL_RESEN);
+
+        // The configured action is taken, through the reset event object.
+        expire(&mut wdt, &clock);
+        expire(&mut wdt, &clock);
+        assert_eq!(evt_count(wdt.reset_evt()), 1);
+
+        // With the notification only, the event object isn't signaled.
+        wdt.set_expiry_action(WatchdogAction::NotifyOnly);
+        expire(&mut wdt, &clock);
+        assert_eq!(evt_count(wdt.reset_evt()), 0);
+        assert_eq!(
+            *wdt.events().actions.lock().unwrap(),
+            vec![WatchdogAction::Poweroff, WatchdogAction::NotifyOnly]
+        );
+    }
+
+    #[test]
+    fn test_sp805_legacy_device() {
+        let (mut wdt, _) = new_sp805();
+
+        LegacyDevice::write(&mut wdt, WDOGLOAD, &1000u32.to_le_bytes());
+        LegacyDevice::write(&mut wdt, WDOGCONTROL, &CONTROL_INTEN.to_le_bytes());
+        assert!(wdt.next_deadline().is_some());
+        let mut data = [0; 4];
+        LegacyDevice::read(&mut wdt, WDOGLOAD, &mut data);
+        assert_eq!(u32::from_le_bytes(data), 1000);
+
+        // The reset stops the watchdog.
+        wdt.reset();
+        assert_eq!(wdt.next_deadline(), None);
+        assert_eq!(read_reg(&wdt, WDOGLOAD), DEFAULT_LOAD);
+        assert_eq!(read_reg(&wdt, WDOGCONTROL), 0);
+    }
+}
@@ -0,0 +1,541 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the configuration interface of an LPC Super I/O
+//! chip (ports 0x2E and 0x2F).
+//!
+//! The firmware, and some guest drivers, look up the legacy devices through
+//! this index/data pair. After entering the configuration mode, they select a
+//! logical device, and read or program its I/O base addresses and IRQs. The
+//! interface follows the SMSC LPC47 chips, with the same logical device
+//! numbers, so that the answers match the devices provided by this crate.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+use crate::{byte_offset, LegacyDevice};
+
+// Offsets of the index and data registers.
+const INDEX_OFFSET: u8 = 0;
+const DATA_OFFSET: u8 = 1;
+
+// The values written to the index register for entering and exiting the
+// configuration mode.
+const ENTER_CONFIG_KEY: u8 = 0x55;
+const EXIT_CONFIG_KEY: u8 = 0xAA;
+
+// Global configuration registers.
+const LDN_REG: u8 = 0x07;
+const DEVICE_ID_REG: u8 = 0x20;
+const REVISION_REG: u8 = 0x21;
+
+// Configuration registers of the selected logical device.
+const ACTIVATE_REG: u8 = 0x30;
+// The primary and secondary I/O base addresses, the high byte first.
+const IO_BASE_REG: u8 = 0x60;
+const IO_BASE_LAST_REG: u8 = 0x63;
+// The primary and secondary IRQs, in the low nibble.
+const IRQ_REG: u8 = 0x70;
+const IRQ2_REG: u8 = 0x72;
+const IRQ_MASK: u8 = 0x0F;
+const DMA_REG: u8 = 0x74;
+// The value of the DMA register of the logical devices without a DMA channel.
+const NO_DMA: u8 = 0x04;
+
+// The device ID and revision of the SMSC LPC47M10x, whose logical device
+// numbers are the ones used here.
+const DEFAULT_DEVICE_ID: u8 = 0x59;
+const DEFAULT_REVISION: u8 = 0x01;
+
+/// The logical device number of the floppy disk controller.
+pub const LDN_FDC: u8 = 0x00;
+/// The logical device number of the parallel port.
+pub const LDN_PARALLEL: u8 = 0x03;
+/// The logical device number of the first UART (COM1).
+pub const LDN_UART1: u8 = 0x04;
+/// The logical device number of the second UART (COM2).
+pub const LDN_UART2: u8 = 0x05;
+/// The logical device number of the keyboard controller.
+pub const LDN_KEYBOARD: u8 = 0x07;
+
+/// The configuration of a logical device of the Super I/O chip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LogicalDevice {
+    /// Whether the device is active (register 0x30).
+    pub active: bool,
+    /// The primary and secondary I/O base addresses (registers 0x60-0x63).
+    pub io_bases: [u16; 2],
+    /// The primary and secondary IRQs (registers 0x70 and 0x72), or 0 for
+    /// none.
+    pub irqs: [u8; 2],
+}
+
+impl LogicalDevice {
+    /// Creates the configuration of an active logical device, with a single
+    /// I/O base address and IRQ.
+    ///
+    /// # Arguments
+    /// * `io_base` - The I/O base address of the device.
+    /// * `irq` - The IRQ of the device.
+    pub fn new(io_base: u16, irq: u8) -> Self {
+        LogicalDevice {
+            active: true,
+            io_bases: [io_base, 0],
+            irqs: [irq & IRQ_MASK, 0],
+        }
+    }
+
+    fn read(&self, index: u8) -> u8 {
+        match index {
+            ACTIVATE_REG => u8::from(self.active),
+            IO_BASE_REG..=IO_BASE_LAST_REG => {
+                let base = self.io_bases[usize::from((index - IO_BASE_REG) / 2)];
+                if (index - IO_BASE_REG) & 1 == 0 {
+                    (base >> 8) as u8
+                } else {
+                    base as u8
+                }
+            }
+            IRQ_REG => self.irqs[0],
+            IRQ2_REG => self.irqs[1],
+            DMA_REG => NO_DMA,
+            _ => 0x00,
+        }
+    }
+
+    fn write(&mut self, index: u8, value: u8) {
+        match index {
+            ACTIVATE_REG => self.active = value & 0x01 != 0,
+            IO_BASE_REG..=IO_BASE_LAST_REG => {
+                let base = &mut self.io_bases[usize::from((index - IO_BASE_REG) / 2)];
+                if (index - IO_BASE_REG) & 1 == 0 {
+                    *base = (*base & 0x00FF) | (u16::from(value) << 8);
+                } else {
+                    *base = (*base & 0xFF00) | u16::from(value);
+                }
+            }
+            IRQ_REG => self.irqs[0] = value & IRQ_MASK,
+            IRQ2_REG => self.irqs[1] = value & IRQ_MASK,
+            _ => {}
+        }
+    }
+}
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the Super I/O emulation logic. The methods below can be implemented by a
+/// backend that keeps track of such events by relocating the devices, logging messages, or any
+/// other action.
+pub trait SuperIoEvents {
+    /// The driver changed the configuration of the logical device `ldn`.
+    fn device_changed(&self, ldn: u8, device: LogicalDevice);
+}
+
+/// Provides a no-op implementation of `SuperIoEvents` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `SuperIoEvents`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl SuperIoEvents for NoEvents {
+    fn device_changed(&self, _ldn: u8, _device: LogicalDevice) {}
+}
+
+impl<EV: SuperIoEvents> SuperIoEvents for Arc<EV> {
+    fn device_changed(&self, ldn: u8, device: LogicalDevice) {
+        self.as_ref().device_changed(ldn, device);
+    }
+}
+
+/// The configuration interface of a Super I/O chip, usually found at ports
+/// 0x2E (index) and 0x2F (data).
+///
+/// The guest enters the configuration mode by writing 0x55 to the index
+/// register, and exits it by writing 0xAA. Outside of the configuration mode,
+/// both registers read as 0xFF, and the writes are ignored, like on a system
+/// without a Super I/O chip.
+///
+/// The logical devices default to the standard PC layout, with the UARTs and
+/// the keyboard controller of this crate. Their configuration changes are
+/// reported through [`SuperIoEvents`](trait.SuperIoEvents.html), for the VMMs
+/// that let the firmware relocate the devices.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::superio::{SuperIo, LDN_UART2};
+/// let mut superio = SuperIo::new();
+///
+/// // Enter the configuration mode, and look up the base address and the IRQ
+/// // of COM2.
+/// superio.write(0, 0x55);
+/// superio.write(0, 0x07);
+/// superio.write(1, LDN_UART2);
+/// superio.write(0, 0x60);
+/// assert_eq!(superio.read(1), 0x02);
+/// superio.write(0, 0x61);
+/// assert_eq!(superio.read(1), 0xF8);
+/// superio.write(0, 0x70);
+/// assert_eq!(superio.read(1), 3);
+/// superio.write(0, 0xAA);
+/// ```
+#[derive(Debug)]
+pub struct SuperIo<EV: SuperIoEvents> {
+    events: EV,
+    device_id: u8,
+    revision: u8,
+    config_mode: bool,
+    index: u8,
+    // The selected logical device number.
+    ldn: u8,
+    devices: BTreeMap<u8, LogicalDevice>,
+}
+
+impl SuperIo<NoEvents> {
+    /// Creates a new `SuperIo` instance, with the UARTs and the keyboard
+    /// controller at their standard base addresses and IRQs.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `SuperIo`](struct.SuperIo.html#example).
+    pub fn new() -> Self {
+        Self::with_events(NoEvents)
+    }
+}
+
+impl Default for SuperIo<NoEvents> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<EV: SuperIoEvents> SuperIo<EV> {
+    /// Creates a new `SuperIo` instance, with the UARTs and the keyboard
+    /// controller at their standard base addresses and IRQs, which invokes
+    /// the `superio_evts` implementation of `SuperIoEvents` during operation.
+    ///
+    /// # Arguments
+    /// * `superio_evts` - The `SuperIoEvents` implementation used to track
+    ///   the configuration changes of the logical devices.
+    pub fn with_events(superio_evts: EV) -> Self {
+        let mut devices = BTreeMap::new();
+        devices.insert(LDN_UART1, LogicalDevice::new(0x3F8, 4));
+        devices.insert(LDN_UART2, LogicalDevice::new(0x2F8, 3));
+        devices.insert(
+            LDN_KEYBOARD,
+            LogicalDevice {
+                active: true,
+                io_bases: [0x60, 0x64],
+                irqs: [1, 12],
+            },
+        );
+
+        SuperIo {
+            events: superio_evts,
+            device_id: DEFAULT_DEVICE_ID,
+            revision: DEFAULT_REVISION,
+            config_mode: false,
+            index: 0,
+            ldn: 0,
+            devices,
+        }
+    }
+
+    /// Provides a reference to the Super I/O events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Sets the device ID and revision reported in the global registers 0x20
+    /// and 0x21.
+    ///
+    /// # Arguments
+    /// * `device_id` - The device ID of the chip.
+    /// * `revision` - The revision of the chip.
+    pub fn set_id(&mut self, device_id: u8, revision: u8) {
+        self.device_id = device_id;
+        self.revision = revision;
+    }
+
+    /// Adds, or replaces, the logical device `ldn`.
+    ///
+    /// # Arguments
+    /// * `ldn` - The logical device number, e.g. [`LDN_UART1`](constant.LDN_UART1.html).
+    /// * `device` - The configuration of the logical device.
+    pub fn set_device(&mut self, ldn: u8, device: LogicalDevice) {
+        self.devices.insert(ldn, device);
+    }
+
+    /// Removes the logical device `ldn`, whose registers then read as 0.
+    ///
+    /// # Arguments
+    /// * `ldn` - The logical device number.
+    pub fn remove_device(&mut self, ldn: u8) -> Option<LogicalDevice> {
+        self.devices.remove(&ldn)
+    }
+
+    /// Returns the configuration of the logical device `ldn`, if any.
+    ///
+    /// # Arguments
+    /// * `ldn` - The logical device number.
+    pub fn device(&self, ldn: u8) -> Option<LogicalDevice> {
+        self.devices.get(&ldn).copied()
+    }
+
+    /// Returns whether the guest is in the configuration mode.
+    pub fn in_config_mode(&self) -> bool {
+        self.config_mode
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// Returns 0xFF outside of the configuration mode, and for invalid
+    /// offsets.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    pub fn read(&self, offset: u8) -> u8 {
+        if !self.config_mode {
+            return 0xFF;
+        }
+
+        match offset {
+            INDEX_OFFSET => self.index,
+            DATA_OFFSET => match self.index {
+                LDN_REG => self.ldn,
+                DEVICE_ID_REG => self.device_id,
+                REVISION_REG => self.revision,
+                index if index >= ACTIVATE_REG => self
+                    .devices
+                    .get(&self.ldn)
+                    .map_or(0x00, |device| device.read(index)),
+                _ => 0x00,
+            },
+            _ => {
+                guest_warn!("Invalid Super I/O read at offset {:#x}", offset);
+                0xFF
+            }
+        }
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base I/O address.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for writing to a specific register.
+    /// * `value` - The byte that should be written.
+    pub fn write(&mut self, offset: u8, value: u8) {
+        match offset {
+            INDEX_OFFSET if !self.config_mode => {
+                self.config_mode = value == ENTER_CONFIG_KEY;
+            }
+            INDEX_OFFSET if value == EXIT_CONFIG_KEY => self.config_mode = false,
+            INDEX_OFFSET => self.index = value,
+            DATA_OFFSET if self.config_mode => self.write_config(value),
+            DATA_OFFSET => {}
+            _ => guest_warn!("Invalid Super I/O write at offset {:#x}", offset),
+        }
+    }
+
+    fn write_config(&mut self, value: u8) {
+        match self.index {
+            LDN_REG => self.ldn = value,
+            index if index >= ACTIVATE_REG => {
+                let ldn = self.ldn;
+                if let Some(device) = self.devices.get_mut(&ldn) {
+                    let old_device = *device;
+                    device.write(index, value);
+                    if *device != old_device {
+                        let device = *device;
+                        self.events.device_changed(ldn, device);
+                    }
+                } else {
+                    guest_warn!("Invalid Super I/O logical device {:#x}", ldn);
+                }
+            }
+            // The other global registers are read-only.
+            _ => {}
+        }
+    }
+}
+
+impl<EV: SuperIoEvents> LegacyDevice for SuperIo<EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match byte_offset(offset, data.len()) {
+            Some(offset) => data[0] = SuperIo::read(self, offset),
+            None => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Some(offset) = byte_offset(offset, data.len()) {
+            SuperIo::write(self, offset, data[0]);
+        }
+    }
+
+    // The logical devices, as configured by the VMM or relocated by the
+    // guest, are kept.
+    fn reset(&mut self) {
+        self.config_mode = false;
+        self.index = 0;
+        self.ldn = 0;
+    }
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use vmm_sys_util::metric::Metric;
+
+    #[derive(Default)]
+    struct ExampleSuperIoEvents {
+        changes: AtomicU64,
+        last_ldn: AtomicU64,
+    }
+
+    impl SuperIoEvents for ExampleSuperIoEvents {
+        fn device_changed(&self, ldn: u8, _device: LogicalDevice) {
+            self.changes.inc();
+            self.last_ldn.store(u64::from(ldn), Ordering::Relaxed);
+        }
+    }
+
+    fn read_reg<EV: SuperIoEvents>(superio: &mut SuperIo<EV>, index: u8) -> u8 {
+        superio.write(INDEX_OFFSET, index);
+        SuperIo::read(superio, DATA_OFFSET)
+    }
+
+    fn write_reg<EV: SuperIoEvents>(superio: &mut SuperIo<EV>, index: u8, value: u8) {
+        superio.write(INDEX_OFFSET, index);
+        superio.write(DATA_OFFSET, value);
+    }
+
+    #[test]
+    fn test_superio_config_mode() {
+        let mut superio = SuperIo::default();
+
+        // Nothing is visible outside of the configuration mode.
+        assert!(!superio.in_config_mode());
+        superio.write(INDEX_OFFSET, DEVICE_ID_REG);
+        assert_eq!(superio.read(INDEX_OFFSET), 0xFF);
+        assert_eq!(superio.read(DATA_OFFSET), 0xFF);
+
+        superio.write(INDEX_OFFSET, ENTER_CONFIG_KEY);
+        assert!(superio.in_config_mode());
+        assert_eq!(read_reg(&mut superio, DEVICE_ID_REG), DEFAULT_DEVICE_ID);
+        assert_eq!(read_reg(&mut superio, REVISION_REG), DEFAULT_REVISION);
+        assert_eq!(superio.read(INDEX_OFFSET), REVISION_REG);
+        assert_eq!(superio.read(DATA_OFFSET + 1), 0xFF);
+
+        // The global registers are read-only.
+        write_reg(&mut superio, DEVICE_ID_REG, 0x12);
+        assert_eq!(read_reg(&mut superio, DEVICE_ID_REG), DEFAULT_DEVICE_ID);
+        superio.set_id(0x12, 0x34);
+        assert_eq!(read_reg(&mut superio, DEVICE_ID_REG), 0x12);
+        assert_eq!(read_reg(&mut superio, REVISION_REG), 0x34);
+
+        superio.write(INDEX_OFFSET, EXIT_CONFIG_KEY);
+        assert!(!superio.in_config_mode());
+        assert_eq!(superio.read(DATA_OFFSET), 0xFF);
+        write_reg(&mut superio, LDN_REG, LDN_UART1);
+        superio.write(INDEX_OFFSET, ENTER_CONFIG_KEY);
+        assert_eq!(read_reg(&mut superio, LDN_REG), 0);
+    }
+
+    #[test]
+    fn test_superio_logical_devices() {
+        let mut superio = SuperIo::new();
+        superio.write(INDEX_OFFSET, ENTER_CONFIG_KEY);
+
+        write_reg(&mut superio, LDN_REG, LDN_UART1);
+        assert_eq!(read_reg(&mut superio, LDN_REG), LDN_UART1);
+        assert_eq!(read_reg(&mut superio, ACTIVATE_REG), 1);
+        assert_eq!(read_reg(&mut superio, IO_BASE_REG), 0x03);
+        assert_eq!(read_reg(&mut superio, IO_BASE_REG + 1), 0xF8);
+        assert_eq!(read_reg(&mut superio, IRQ_REG), 4);
+        assert_eq!(read_reg(&mut superio, DMA_REG), NO_DMA);
+
+        write_reg(&mut superio, LDN_REG, LDN_KEYBOARD);
+        assert_eq!(read_reg(&mut superio, IO_BASE_REG + 1), 0x60);
+        assert_eq!(read_reg(&mut superio, IO_BASE_REG + 3), 0x64);
+        assert_eq!(read_reg(&mut superio, IRQ_REG), 1);
+        assert_eq!(read_reg(&mut superio, IRQ2_REG), 12);
+
+        // The logical devices that aren't present read as inactive.
+        write_reg(&mut superio, LDN_REG, LDN_FDC);
+        assert_eq!(read_reg(&mut superio, ACTIVATE_REG), 0);
+        assert_eq!(read_reg(&mut superio, IO_BASE_REG + 1), 0);
+        superio.set_device(LDN_FDC, LogicalDevice::new(0x3F0, 6));
+        assert_eq!(read_reg(&mut superio, IO_BASE_REG + 1), 0xF0);
+        assert_eq!(read_reg(&mut superio, IRQ_REG), 6);
+        assert_eq!(
+            superio.remove_device(LDN_FDC),
+            Some(LogicalDevice::new(0x3F0, 6))
+        );
+        assert_eq!(read_reg(&mut superio, ACTIVATE_REG), 0);
+        assert_eq!(superio.device(LDN_FDC), None);
+    }
+
+    #[test]
+    fn test_superio_reconfigure() {
+        let events = Arc::new(ExampleSuperIoEvents::default());
+        let mut superio = SuperIo::with_events(events.clone());
+        superio.write(INDEX_OFFSET, ENTER_CONFIG_KEY);
+
+        // Move COM2 to COM4, and disable it.
+        write_reg(&mut superio, LDN_REG, LDN_UART2);
+        write_reg(&mut superio, IO_BASE_REG + 1, 0xE8);
+        write_reg(&mut superio, IRQ_REG, 0xF3);
+        assert_eq!(superio.events().changes.count(), 1);
+        assert_eq!(
+            events.last_ldn.load(Ordering::Relaxed),
+            u64::from(LDN_UART2)
+        );
+        write_reg(&mut superio, ACTIVATE_REG, 0);
+        assert_eq!(
+            superio.device(LDN_UART2),
+            Some(LogicalDevice {
+                active: false,
+                io_bases: [0x2E8, 0],
+                irqs: [3, 0],
+            })
+        );
+        assert_eq!(events.changes.count(), 2);
+
+        // The DMA register is read-only, and the unknown logical devices
+        // ignore the writes.
+        write_reg(&mut superio, DMA_REG, 0x01);
+        assert_eq!(read_reg(&mut superio, DMA_REG), NO_DMA);
+        write_reg(&mut superio, LDN_REG, LDN_PARALLEL);
+        write_reg(&mut superio, ACTIVATE_REG, 1);
+        assert_eq!(superio.device(LDN_PARALLEL), None);
+        assert_eq!(events.changes.count(), 2);
+    }
+
+    #[test]
+    fn test_superio_legacy_device() {
+        let mut superio = SuperIo::new();
+
+        LegacyDevice::write(&mut superio, u16::from(INDEX_OFFSET), &[ENTER_CONFIG_KEY]);
+        write_reg(&mut superio, LDN_REG, LDN_UART1);
+        let mut data = [0; 1];
+        LegacyDevice::read(&mut superio, u16::from(DATA_OFFSET), &mut data);
+        assert_eq!(data, [LDN_UART1]);
+
+        // The reset exits the configuration mode.
+        superio.reset();
+        assert!(!superio.in_config_mode());
+        superio.write(INDEX_OFFSET, ENTER_CONFIG_KEY);
+        assert_eq!(read_reg(&mut superio, LDN_REG), 0);
+        assert!(superio.device(LDN_UART1).is_some());
+    }
+}
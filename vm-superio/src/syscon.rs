@@ -0,0 +1,380 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for a system controller (syscon) register block, used
+//! for powering off and rebooting the system.
+//!
+//! The device tree describes such a block with the `syscon-poweroff` and
+//! `syscon-reboot` nodes, which give the offset of the register written by
+//! the guest, and the value (and optionally the mask) it writes. The Linux
+//! drivers of these nodes are used by the RISC-V and ARM guests which don't
+//! rely on the firmware for powering off or rebooting the system.
+
+use alloc::sync::Arc;
+use core::convert::TryFrom;
+use core::result::Result;
+
+use crate::{LegacyDevice, Trigger};
+
+/// The action requested by the guest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SysconAction {
+    /// The guest requested the system power off.
+    Poweroff,
+    /// The guest requested the system reboot.
+    Reboot,
+}
+
+/// The register write requesting an action, as described by the `offset`,
+/// `value` and `mask` properties of a `syscon-poweroff` or `syscon-reboot`
+/// device tree node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SysconMagic {
+    /// The offset of the register, from the base address of the block.
+    pub offset: u16,
+    /// The value written to the register.
+    pub value: u32,
+    /// The bits of the register compared with `value`.
+    pub mask: u32,
+}
+
+impl SysconMagic {
+    /// Creates a new `SysconMagic`, which compares all the bits of the
+    /// register at `offset` with `value`, like the device tree nodes without
+    /// a `mask` property.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset of the register, from the base address of the
+    ///   block.
+    /// * `value` - The value written to the register.
+    pub const fn new(offset: u16, value: u32) -> Self {
+        SysconMagic {
+            offset,
+            value,
+            mask: 0xFFFF_FFFF,
+        }
+    }
+
+    // Returns whether writing `val` to the register at `offset` matches.
+    fn matches(&self, offset: u16, val: u32) -> bool {
+        offset == self.offset && (val & self.mask) == (self.value & self.mask)
+    }
+}
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the syscon emulation logic. The methods below can be implemented by a
+/// backend that keeps track of such events by incrementing metrics, logging messages, or any
+/// other action.
+pub trait SysconEvents {
+    /// The guest requested the given action. The corresponding event object
+    /// is signaled right after.
+    fn action_requested(&self, action: SysconAction);
+}
+
+/// Provides a no-op implementation of `SysconEvents` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `SysconEvents`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl SysconEvents for NoEvents {
+    fn action_requested(&self, _action: SysconAction) {}
+}
+
+impl<EV: SysconEvents> SysconEvents for Arc<EV> {
+    fn action_requested(&self, action: SysconAction) {
+        self.as_ref().action_requested(action);
+    }
+}
+
+/// A system controller register block, which powers off or reboots the
+/// system when the guest writes the configured values.
+///
+/// Two [`Trigger`](../trait.Trigger.html) objects are used for notifying the
+/// VMM: one for the power off requests, and one for the reboot requests.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::syscon::{Syscon, SysconMagic};
+/// # use vm_superio::Trigger;
+/// # struct DummyTrigger;
+/// # impl Trigger for DummyTrigger {
+/// #     type E = ();
+/// #     fn trigger(&self) -> Result<(), ()> { Ok(()) }
+/// # }
+/// // The values used by the RISC-V virt machine, with both nodes pointing to
+/// // the register at offset 0.
+/// let mut syscon = Syscon::new(
+///     DummyTrigger,
+///     DummyTrigger,
+///     SysconMagic::new(0, 0x5555),
+///     SysconMagic::new(0, 0x7777),
+/// );
+///
+/// // The guest powers off the system.
+/// syscon.write(0, &0x5555u32.to_le_bytes()).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Syscon<T: Trigger, EV: SysconEvents> {
+    // Power off event object.
+    poweroff_evt: T,
+    // Reboot event object.
+    reboot_evt: T,
+    poweroff: SysconMagic,
+    reboot: SysconMagic,
+    events: EV,
+}
+
+impl<T: Trigger> Syscon<T, NoEvents> {
+    /// Creates a new `Syscon` instance, which will signal `poweroff_evt`
+    /// when the guest writes the `poweroff` value, and `reboot_evt` when it
+    /// writes the `reboot` value.
+    ///
+    /// # Arguments
+    /// * `poweroff_evt` - A Trigger object that will be used to notify the
+    ///   VMM about the power off requests.
+    /// * `reboot_evt` - A Trigger object that will be used to notify the VMM
+    ///   about the reboot requests.
+    /// * `poweroff` - The register write described by the `syscon-poweroff`
+    ///   node.
+    /// * `reboot` - The register write described by the `syscon-reboot`
+    ///   node.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `Syscon`](struct.Syscon.html#example).
+    pub const fn new(
+        poweroff_evt: T,
+        reboot_evt: T,
+        poweroff: SysconMagic,
+        reboot: SysconMagic,
+    ) -> Self {
+        Self::with_events(poweroff_evt, reboot_evt, poweroff, reboot, NoEvents)
+    }
+}
+
+impl<T: Trigger, EV: SysconEvents> Syscon<T, EV> {
+    /// Creates a new `Syscon` instance, which will signal `poweroff_evt`
+    /// when the guest writes the `poweroff` value, and `reboot_evt` when it
+    /// writes the `reboot` value, and invokes the `syscon_evts`
+    /// implementation of `SysconEvents` during operation.
+    ///
+    /// # Arguments
+    /// * `poweroff_evt` - A Trigger object that will be used to notify the
+    ///   VMM about the power off requests.
+    /// * `reboot_evt` - A Trigger object that will be used to notify the VMM
+    ///   about the reboot requests.
+    /// * `poweroff` - The register write described by the `syscon-poweroff`
+    ///   node.
+    /// * `reboot` - The register write described by the `syscon-reboot`
+    ///   node.
+    /// * `syscon_evts` - The `SysconEvents` implementation used to track the
+    ///   requests of the guest.
+    pub const fn with_events(
+        poweroff_evt: T,
+        reboot_evt: T,
+        poweroff: SysconMagic,
+        reboot: SysconMagic,
+        syscon_evts: EV,
+    ) -> Self {
+        Syscon {
+            poweroff_evt,
+            reboot_evt,
+            poweroff,
+            reboot,
+            events: syscon_evts,
+        }
+    }
+
+    /// Provides a reference to the power off event object.
+    pub fn poweroff_evt(&self) -> &T {
+        &self.poweroff_evt
+    }
+
+    /// Provides a reference to the reboot event object.
+    pub fn reboot_evt(&self) -> &T {
+        &self.reboot_evt
+    }
+
+    /// Provides a reference to the syscon events object.
+    pub fn events(&self) -> &EV {
+        &self.events
+    }
+
+    /// Handles a read request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// All the registers read as 0, so that the drivers updating only the
+    /// masked bits of a register write the configured value.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be read.
+    /// * `data` - The little-endian, 4 byte array storing the read value.
+    pub fn read(&self, _offset: u16, data: &mut [u8; 4]) {
+        *data = [0; 4];
+    }
+
+    /// Handles a write request from the driver at `offset` offset from the
+    /// base register address.
+    ///
+    /// Writing the power off or the reboot value to its register requests
+    /// the corresponding action. The power off value is checked first, in
+    /// case both match.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset from the base register specifying
+    ///   the register to be written.
+    /// * `data` - The little endian, 4 byte array to write to the register.
+    pub fn write(&mut self, offset: u16, data: &[u8; 4]) -> Result<(), T::E> {
+        let val = u32::from_le_bytes(*data);
+        if self.poweroff.matches(offset, val) {
+            self.events.action_requested(SysconAction::Poweroff);
+            self.poweroff_evt.trigger()
+        } else if self.reboot.matches(offset, val) {
+            self.events.action_requested(SysconAction::Reboot);
+            self.reboot_evt.trigger()
+        } else {
+            guest_warn!(
+                "Ignoring the syscon write of {:#x} at offset {:#x}",
+                val,
+                offset
+            );
+            Ok(())
+        }
+    }
+}
+
+impl<T: Trigger, EV: SysconEvents> LegacyDevice for Syscon<T, EV> {
+    type State = ();
+
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        match <&mut [u8; 4]>::try_from(&mut *data) {
+            Ok(data) => Syscon::read(self, offset, data),
+            Err(_) => {
+                guest_warn!(
+                    "Ignoring a {}-byte access at offset {:#x}",
+                    data.len(),
+                    offset
+                );
+                data.fill(0)
+            }
+        }
+    }
+
+    fn write(&mut self, offset: u16, data: &[u8]) {
+        if let Ok(data) = <&[u8; 4]>::try_from(data) {
+            let _ = Syscon::write(self, offset, data);
+        } else {
+            guest_warn!(
+                "Ignoring a {}-byte access at offset {:#x}",
+                data.len(),
+                offset
+            );
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::Mutex;
+    use vmm_sys_util::eventfd::EventFd;
+
+    #[derive(Default)]
+    struct ExampleSysconEvents {
+        actions: Mutex<Vec<SysconAction>>,
+    }
+
+    impl SysconEvents for ExampleSysconEvents {
+        fn action_requested(&self, action: SysconAction) {
+            self.actions.lock().unwrap().push(action);
+        }
+    }
+
+    type TestSyscon = Syscon<EventFd, Arc<ExampleSysconEvents>>;
+
+    fn new_syscon(poweroff: SysconMagic, reboot: SysconMagic) -> TestSyscon {
+        Syscon::with_events(
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            poweroff,
+            reboot,
+            Arc::new(ExampleSysconEvents::default()),
+        )
+    }
+
+    fn evt_count(evt: &EventFd) -> u64 {
+        match evt.read() {
+            Ok(count) => count,
+            Err(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+                0
+            }
+        }
+    }
+
+    #[test]
+    fn test_syscon_shared_register() {
+        let mut syscon = new_syscon(SysconMagic::new(0, 0x5555), SysconMagic::new(0, 0x7777));
+
+        syscon.write(0, &0x5555u32.to_le_bytes()).unwrap();
+        assert_eq!(evt_count(syscon.poweroff_evt()), 1);
+        syscon.write(0, &0x7777u32.to_le_bytes()).unwrap();
+        assert_eq!(evt_count(syscon.reboot_evt()), 1);
+
+        // Other values, or other registers, are ignored.
+        syscon.write(0, &0x5556u32.to_le_bytes()).unwrap();
+        syscon.write(4, &0x5555u32.to_le_bytes()).unwrap();
+        assert_eq!(evt_count(syscon.poweroff_evt()), 0);
+        assert_eq!(evt_count(syscon.reboot_evt()), 0);
+        assert_eq!(
+            *syscon.events().actions.lock().unwrap(),
+            vec![SysconAction::Poweroff, SysconAction::Reboot]
+        );
+
+        let mut data = [0xFF; 4];
+        syscon.read(0, &mut data);
+        assert_eq!(data, [0; 4]);
+    }
+
+    #[test]
+    fn test_syscon_mask() {
+        let poweroff = SysconMagic {
+            offset: 0x10,
+            value: 0x0000_0100,
+            mask: 0x0000_0F00,
+        };
+        let mut syscon = new_syscon(poweroff, SysconMagic::new(0x14, 1));
+
+        // Only the masked bits are compared.
+        syscon.write(0x10, &0xFFFF_F1FFu32.to_le_bytes()).unwrap();
+        assert_eq!(evt_count(syscon.poweroff_evt()), 1);
+        syscon.write(0x10, &0x0000_0200u32.to_le_bytes()).unwrap();
+        assert_eq!(evt_count(syscon.poweroff_evt()), 0);
+
+        syscon.write(0x14, &1u32.to_le_bytes()).unwrap();
+        assert_eq!(evt_count(syscon.reboot_evt()), 1);
+    }
+
+    #[test]
+    fn test_syscon_legacy_device() {
+        let mut syscon = new_syscon(SysconMagic::new(0, 0x5555), SysconMagic::new(4, 0x7777));
+
+        LegacyDevice::write(&mut syscon, 4, &0x7777u16.to_le_bytes());
+        assert_eq!(evt_count(syscon.reboot_evt()), 0);
+        LegacyDevice::write(&mut syscon, 4, &0x7777u32.to_le_bytes());
+        assert_eq!(evt_count(syscon.reboot_evt()), 1);
+        let mut data = [0xFF; 4];
+        LegacyDevice::read(&mut syscon, 0, &mut data);
+        assert_eq!(data, [0; 4]);
+    }
+}
@@ -0,0 +1,635 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the test doubles and the property testing helpers of the devices.
+//!
+//! The [`MockTrigger`](struct.MockTrigger.html) counts its events and can be
+//! made to fail, while the [`CaptureWriter`](struct.CaptureWriter.html) keeps
+//! the output of a device, so that the unit tests of a VMM don't depend on
+//! real eventfds. The time of the [`MockClock`](struct.MockClock.html) only
+//! moves when the test sets it, so that the timers and the RTC don't depend on
+//! the host time. Their clones share their state, so the test keeps a clone of
+//! the objects moved into the device.
+//!
+//! With the `proptest` feature, the strategies generate the sequences of
+//! register accesses of a driver, which are applied to any
+//! [`LegacyDevice`](../trait.LegacyDevice.html), while the `*_invariants`
+//! functions check the consistency of the state of a device after each access,
+//! and fail the test case with `prop_assert!` otherwise.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use vm_superio::testing::{CaptureWriter, MockTrigger};
+//! # use vm_superio::Serial;
+//! let intr_evt = MockTrigger::new();
+//! let out = CaptureWriter::new();
+//! let mut serial = Serial::new(intr_evt.clone(), out.clone());
+//!
+//! // Enable the THR empty interrupt, and send a byte.
+//! serial.write(1, 0x02).unwrap();
+//! serial.write(0, b'a').unwrap();
+//! assert_eq!(intr_evt.count(), 1);
+//! assert_eq!(out.contents(), b"a");
+//!
+//! // The interrupt fails to be delivered.
+//! intr_evt.set_fail(true);
+//! serial.read(2);
+//! assert!(serial.write(0, b'b').is_err());
+//! ```
+
+use std::io;
+#[cfg(feature = "proptest")]
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "fuzzing")]
+use arbitrary::{Arbitrary, Unstructured};
+#[cfg(feature = "proptest")]
+use proptest::collection::vec;
+#[cfg(feature = "proptest")]
+use proptest::prelude::*;
+#[cfg(feature = "proptest")]
+use proptest::test_runner::TestCaseError;
+
+#[cfg(feature = "proptest")]
+use crate::i8042::{self, I8042State};
+use crate::pit::ClockSource;
+use crate::rtc_pl031::WallClock;
+#[cfg(feature = "proptest")]
+use crate::serial::{self, SerialState};
+#[cfg(feature = "proptest")]
+use crate::LegacyDevice;
+use crate::Trigger;
+
+/// Records the order in which several [`MockTrigger`](struct.MockTrigger.html)
+/// objects are triggered, by their names.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::testing::TriggerLog;
+/// # use vm_superio::I8042Device;
+/// let log = TriggerLog::new();
+/// let mut i8042 = I8042Device::with_irqs(
+///     log.trigger("reset"),
+///     log.trigger("kbd"),
+///     log.trigger("aux"),
+/// );
+///
+/// // Send a key press to the guest, and reset the CPU.
+/// i8042.enqueue_kbd_bytes(&[0x1C]).unwrap();
+/// i8042.write(4, 0xFE).unwrap();
+/// assert_eq!(log.events(), ["kbd", "reset"]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TriggerLog(Arc<Mutex<Vec<&'static str>>>);
+
+impl TriggerLog {
+    /// Creates a new empty `TriggerLog` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a new `MockTrigger` recording its events in the log as `name`.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the trigger, e.g. the interrupt it raises.
+    pub fn trigger(&self, name: &'static str) -> MockTrigger {
+        MockTrigger {
+            log: Some((self.clone(), name)),
+            ..MockTrigger::default()
+        }
+    }
+
+    /// Returns the names of the triggers, in the order of their events.
+    pub fn events(&self) -> Vec<&'static str> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Forgets the recorded events.
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+/// A [`Trigger`](../trait.Trigger.html) counting its events, which can be
+/// made to fail. Its clones share the count and the failure mode.
+///
+/// # Example
+///
+/// You can see an example of how to use this structure in the
+/// [`Example` section from the `testing` module](index.html#example).
+#[derive(Clone, Debug, Default)]
+pub struct MockTrigger {
+    count: Arc<AtomicU64>,
+    fail: Arc<AtomicBool>,
+    log: Option<(TriggerLog, &'static str)>,
+}
+
+impl MockTrigger {
+    /// Creates a new `MockTrigger` instance, which succeeds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of successful events.
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Resets the number of events to 0.
+    pub fn reset_count(&self) {
+        self.count.store(0, Ordering::SeqCst);
+    }
+
+    /// Sets whether the next events fail, in which case they aren't counted
+    /// nor logged.
+    ///
+    /// # Arguments
+    /// * `fail` - Whether the events fail.
+    pub fn set_fail(&self, fail: bool) {
+        self.fail.store(fail, Ordering::SeqCst);
+    }
+}
+
+impl Trigger for MockTrigger {
+    type E = io::Error;
+
+    fn trigger(&self) -> io::Result<()> {
+        if self.fail.load(Ordering::SeqCst) {
+            return Err(io::Error::other("mock trigger failure"));
+        }
+        self.count.fetch_add(1, Ordering::SeqCst);
+        if let Some((log, name)) = &self.log {
+            log.0.lock().unwrap().push(name);
+        }
+        Ok(())
+    }
+}
+
+/// A `std::io::Write` capturing the output of a device, which can be made to
+/// fail. Its clones share the output and the failure mode.
+///
+/// # Example
+///
+/// You can see an example of how to use this structure in the
+/// [`Example` section from the `testing` module](index.html#example).
+#[derive(Clone, Debug, Default)]
+pub struct CaptureWriter {
+    data: Arc<Mutex<Vec<u8>>>,
+    fail: Arc<AtomicBool>,
+}
+
+impl CaptureWriter {
+    /// Creates a new `CaptureWriter` instance, without any output.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the output written so far.
+    pub fn contents(&self) -> Vec<u8> {
+        self.data.lock().unwrap().clone()
+    }
+
+    /// Returns the output written so far, and forgets it.
+    pub fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.data.lock().unwrap())
+    }
+
+    /// Sets whether the next writes fail, in which case their data is
+    /// dropped.
+    ///
+    /// # Arguments
+    /// * `fail` - Whether the writes fail.
+    pub fn set_fail(&self, fail: bool) {
+        self.fail.store(fail, Ordering::SeqCst);
+    }
+}
+
+impl io::Write for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.fail.load(Ordering::SeqCst) {
+            return Err(io::Error::other("mock writer failure"));
+        }
+        self.data.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// The number of nanoseconds in a second.
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// A [`ClockSource`](../pit/trait.ClockSource.html) and
+/// [`WallClock`](../rtc_pl031/trait.WallClock.html) whose time only moves
+/// when the test sets it. It starts at 0, and its clones share the time.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::pit::ClockSource;
+/// # use vm_superio::testing::{MockClock, MockTrigger};
+/// # use vm_superio::Pit;
+/// let clock = MockClock::new();
+/// let irq0 = MockTrigger::new();
+/// let mut pit = Pit::new(irq0.clone(), clock.clone());
+///
+/// // Channel 0 in mode 2, with a reload value of 1000.
+/// pit.write(3, 0x34);
+/// pit.write(0, 0xE8);
+/// pit.write(0, 0x03);
+/// let deadline = pit.next_deadline().unwrap();
+///
+/// // The deadline only passes when the test moves the time.
+/// assert!(deadline > clock.now_ns());
+/// clock.set_ns(deadline);
+/// pit.on_timer_expired().unwrap();
+/// assert_eq!(irq0.count(), 1);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MockClock {
+    now_ns: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    /// Creates a new `MockClock` instance, at time 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the time to `ns` nanoseconds.
+    ///
+    /// # Arguments
+    /// * `ns` - The new time, which may be before the current one.
+    pub fn set_ns(&self, ns: u64) {
+        self.now_ns.store(ns, Ordering::SeqCst);
+    }
+
+    /// Moves the time forward by `ns` nanoseconds, saturating at `u64::MAX`.
+    ///
+    /// # Arguments
+    /// * `ns` - The time elapsed.
+    pub fn advance_ns(&self, ns: u64) {
+        self.set_ns(self.now_ns().saturating_add(ns));
+    }
+
+    /// Moves the time to `secs` seconds, e.g. a number of seconds since the
+    /// UNIX epoch for an RTC.
+    ///
+    /// # Arguments
+    /// * `secs` - The new time, which may be before the current one.
+    pub fn set_secs(&self, secs: u64) {
+        self.set_ns(secs.saturating_mul(NANOS_PER_SEC));
+    }
+}
+
+impl ClockSource for MockClock {
+    fn now_ns(&self) -> u64 {
+        self.now_ns.load(Ordering::SeqCst)
+    }
+}
+
+impl WallClock for MockClock {
+    fn now_secs(&self) -> u64 {
+        self.now_ns() / NANOS_PER_SEC
+    }
+}
+
+#[cfg(feature = "fuzzing")]
+// The number of pseudo-random bytes from which `for_each_arbitrary_state`
+// builds the states.
+const ARBITRARY_BYTES: usize = 4096;
+#[cfg(feature = "fuzzing")]
+// The number of bytes from which `for_each_arbitrary_state` builds the states
+// with the lowest and the highest values of their fields.
+const SATURATED_BYTES: usize = 256;
+
+#[cfg(feature = "fuzzing")]
+/// Calls `check` with the states built by `Arbitrary` from a fixed sequence
+/// of pseudo-random bytes, then from bytes all set to 0x00 and to 0xFF, which
+/// give the lowest and the highest values of the fields, e.g. `u64::MAX` times
+/// and full buffers, and finally with each of `edge_states`.
+///
+/// The unit tests of the restore paths run `check` on the same inputs in
+/// every run, while the fuzz targets explore the others.
+///
+/// # Arguments
+/// * `edge_states` - The states the device is expected to handle, or to
+///   reject, which the arbitrary bytes are unlikely to produce.
+/// * `check` - Restores the device from the state, and exercises it.
+pub fn for_each_arbitrary_state<S, F>(edge_states: &[S], mut check: F)
+where
+    S: for<'a> Arbitrary<'a>,
+    F: FnMut(&S),
+{
+    // A multiplicative hash of the indexes, which is deterministic.
+    let data: Vec<u8> = (0..ARBITRARY_BYTES as u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+        .collect();
+    let mut u = Unstructured::new(&data);
+    while !u.is_empty() {
+        let len = u.len();
+        check(&S::arbitrary(&mut u).unwrap());
+        // The states without variable length fields may consume no bytes
+        // once they run out.
+        if u.len() == len {
+            break;
+        }
+    }
+
+    for byte in [0x00, 0xFF] {
+        let data = [byte; SATURATED_BYTES];
+        check(&S::arbitrary(&mut Unstructured::new(&data)).unwrap());
+    }
+
+    edge_states.iter().for_each(check);
+}
+
+#[cfg(feature = "proptest")]
+// The number of ports decoded by a serial port.
+const SERIAL_PORTS: u16 = 8;
+#[cfg(feature = "proptest")]
+// The longest sequence of accesses generated by the device strategies.
+const MAX_ACCESSES: usize = 64;
+
+#[cfg(feature = "proptest")]
+/// A one byte wide access of the driver to a register of a device.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RegisterAccess {
+    /// A read at the offset.
+    Read(u16),
+    /// A write of the value at the offset.
+    Write(u16, u8),
+}
+
+#[cfg(feature = "proptest")]
+impl RegisterAccess {
+    /// Performs the access on `device`. Returns the read value, if any.
+    ///
+    /// # Arguments
+    /// * `device` - The device, accessed at the offset of the access.
+    pub fn apply<D: LegacyDevice>(&self, device: &mut D) -> Option<u8> {
+        match *self {
+            RegisterAccess::Read(offset) => {
+                let mut data = [0u8; 1];
+                device.read(offset, &mut data);
+                Some(data[0])
+            }
+            RegisterAccess::Write(offset, value) => {
+                device.write(offset, &[value]);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(feature = "proptest")]
+/// Returns a strategy generating the reads and the writes at `offsets`.
+///
+/// # Arguments
+/// * `offsets` - The range of the offsets of the accesses.
+pub fn register_access(offsets: Range<u16>) -> impl Strategy<Value = RegisterAccess> {
+    prop_oneof![
+        offsets.clone().prop_map(RegisterAccess::Read),
+        (offsets, any::<u8>()).prop_map(|(offset, value)| RegisterAccess::Write(offset, value)),
+    ]
+}
+
+#[cfg(feature = "proptest")]
+/// Returns a strategy generating the sequences of accesses at `offsets`.
+///
+/// # Arguments
+/// * `offsets` - The range of the offsets of the accesses.
+/// * `len` - The range of the number of accesses of a sequence.
+pub fn access_sequence(
+    offsets: Range<u16>,
+    len: Range<usize>,
+) -> impl Strategy<Value = Vec<RegisterAccess>> {
+    vec(register_access(offsets), len)
+}
+
+#[cfg(feature = "proptest")]
+/// Returns a strategy generating the sequences of accesses to the registers
+/// of a serial port.
+pub fn serial_accesses() -> impl Strategy<Value = Vec<RegisterAccess>> {
+    access_sequence(0..SERIAL_PORTS, 0..MAX_ACCESSES)
+}
+
+#[cfg(feature = "proptest")]
+/// Returns a strategy generating the sequences of accesses to the data and
+/// command ports of an i8042 controller, at the offsets 0 and 4.
+pub fn i8042_accesses() -> impl Strategy<Value = Vec<RegisterAccess>> {
+    let offset = prop_oneof![
+        Just(u16::from(i8042::DATA_OFFSET)),
+        Just(u16::from(i8042::COMMAND_OFFSET)),
+    ];
+    let access = prop_oneof![
+        offset.clone().prop_map(RegisterAccess::Read),
+        (offset, any::<u8>()).prop_map(|(offset, value)| RegisterAccess::Write(offset, value)),
+    ];
+    vec(access, 0..MAX_ACCESSES)
+}
+
+#[cfg(feature = "proptest")]
+/// Checks the consistency of the state of a serial port:
+/// - the IIR reports no pending interrupt exactly when neither the received
+///   data available nor the THR empty interrupts are pending;
+/// - the received data available interrupt is only pending with data in the
+///   FIFO;
+/// - the data ready bit of the LSR is set exactly when the FIFO holds data,
+///   and the THR empty and idle bits are always set;
+/// - the IER only enables the interrupts of the 16550A;
+/// - the FIFO doesn't exceed its capacity.
+///
+/// # Arguments
+/// * `state` - The state of a serial port created with `new`, as returned by
+///   its `state()`.
+///
+/// # Example
+///
+/// ```rust
+/// # use proptest::prelude::*;
+/// # use vm_superio::testing::{serial_accesses, serial_invariants, MockTrigger};
+/// # use vm_superio::Serial;
+/// proptest!(|(accesses in serial_accesses())| {
+///     let mut serial = Serial::new(MockTrigger::new(), std::io::sink());
+///     serial.enqueue_raw_bytes(b"abc").unwrap();
+///
+///     for access in accesses {
+///         access.apply(&mut serial);
+///         serial_invariants(&serial.state())?;
+///     }
+/// });
+/// ```
+pub fn serial_invariants(state: &SerialState) -> Result<(), TestCaseError> {
+    let iir = state.interrupt_identification;
+    let lsr = state.line_status;
+    let pending = serial::IIR_RDA_BIT | serial::IIR_THR_EMPTY_BIT;
+
+    prop_assert_eq!(
+        iir & serial::IIR_NONE_BIT != 0,
+        iir & pending == 0,
+        "Inconsistent IIR {:#x}",
+        iir
+    );
+    prop_assert!(
+        iir & serial::IIR_RDA_BIT == 0 || !state.in_buffer.is_empty(),
+        "Received data interrupt pending without data"
+    );
+    prop_assert_eq!(
+        lsr & serial::LSR_DATA_READY_BIT != 0,
+        !state.in_buffer.is_empty(),
+        "Inconsistent LSR {:#x}",
+        lsr
+    );
+    let idle = serial::LSR_EMPTY_THR_BIT | serial::LSR_IDLE_BIT;
+    prop_assert_eq!(lsr & idle, idle, "Busy transmitter in LSR {:#x}", lsr);
+    prop_assert_eq!(
+        state.interrupt_enable & !serial::IER_UART_VALID_BITS,
+        0,
+        "Invalid IER {:#x}",
+        state.interrupt_enable
+    );
+    prop_assert!(state.in_buffer.len() <= serial::FIFO_SIZE);
+    Ok(())
+}
+
+#[cfg(feature = "proptest")]
+/// Checks that the output buffers of an i8042 controller don't exceed their
+/// capacity.
+///
+/// # Arguments
+/// * `state` - The state of the controller, as returned by its `state()`.
+pub fn i8042_invariants(state: &I8042State) -> Result<(), TestCaseError> {
+    prop_assert!(state.kbd_buffer.len() <= i8042::BUFFER_SIZE);
+    prop_assert!(state.aux_buffer.len() <= i8042::BUFFER_SIZE);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Serial;
+    use std::io::Write;
+    #[cfg(feature = "proptest")]
+    use {crate::I8042Device, std::io::sink};
+
+    #[cfg(feature = "proptest")]
+    proptest! {
+        #[test]
+        fn test_serial_accesses(
+            input in vec(any::<u8>(), 0..2 * serial::FIFO_SIZE),
+            accesses in serial_accesses(),
+        ) {
+            let mut serial = Serial::new(MockTrigger::new(), sink());
+            serial_invariants(&serial.state())?;
+
+            for access in accesses {
+                access.apply(&mut serial);
+                serial_invariants(&serial.state())?;
+                serial.enqueue_raw_bytes(&input).ok();
+                serial_invariants(&serial.state())?;
+            }
+        }
+
+        #[test]
+        fn test_i8042_accesses(
+            input in vec(any::<u8>(), 0..2 * i8042::BUFFER_SIZE),
+            accesses in i8042_accesses(),
+        ) {
+            let mut i8042 =
+                I8042Device::with_irqs(MockTrigger::new(), MockTrigger::new(), MockTrigger::new());
+
+            for access in accesses {
+                access.apply(&mut i8042);
+                i8042.enqueue_kbd_bytes(&input).ok();
+                i8042.enqueue_aux_bytes(&input).ok();
+                i8042_invariants(&i8042.state())?;
+            }
+        }
+
+        #[test]
+        fn test_access_sequence(accesses in access_sequence(0x10..0x20, 1..8)) {
+            prop_assert!(!accesses.is_empty() && accesses.len() < 8);
+            for access in accesses {
+                match access {
+                    RegisterAccess::Read(offset) | RegisterAccess::Write(offset, _) => {
+                        prop_assert!((0x10..0x20).contains(&offset));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mock_trigger() {
+        let trigger = MockTrigger::new();
+        let clone = trigger.clone();
+        clone.trigger().unwrap();
+        trigger.trigger().unwrap();
+        assert_eq!(trigger.count(), 2);
+
+        trigger.set_fail(true);
+        assert!(clone.trigger().is_err());
+        assert_eq!(clone.count(), 2);
+        trigger.set_fail(false);
+        trigger.reset_count();
+        clone.trigger().unwrap();
+        assert_eq!(trigger.count(), 1);
+    }
+
+    #[test]
+    fn test_trigger_log() {
+        let log = TriggerLog::new();
+        let (a, b) = (log.trigger("a"), log.trigger("b"));
+        b.trigger().unwrap();
+        a.trigger().unwrap();
+        a.set_fail(true);
+        assert!(a.trigger().is_err());
+        b.clone().trigger().unwrap();
+        assert_eq!(log.events(), ["b", "a", "b"]);
+        assert_eq!((a.count(), b.count()), (1, 2));
+
+        log.clear();
+        assert!(log.events().is_empty());
+    }
+
+    #[test]
+    fn test_capture_writer() {
+        let out = CaptureWriter::new();
+        let mut serial = Serial::new(MockTrigger::new(), out.clone());
+        serial.write(0, b'a').unwrap();
+        serial.write(0, b'b').unwrap();
+        assert_eq!(out.contents(), b"ab");
+        assert_eq!(out.take(), b"ab");
+        assert!(out.contents().is_empty());
+
+        out.set_fail(true);
+        assert!(serial.write(0, b'c').is_err());
+        assert!(out.clone().write_all(b"d").is_err());
+        out.set_fail(false);
+        serial.write(0, b'e').unwrap();
+        assert_eq!(out.contents(), b"e");
+    }
+
+    #[cfg(feature = "proptest")]
+    #[test]
+    fn test_serial_invariants() {
+        let serial = Serial::new(MockTrigger::new(), sink());
+        let mut state = serial.state();
+        assert!(serial_invariants(&state).is_ok());
+
+        state.line_status |= serial::LSR_DATA_READY_BIT;
+        assert!(serial_invariants(&state).is_err());
+        state.in_buffer.push(0);
+        assert!(serial_invariants(&state).is_ok());
+        state.interrupt_identification = serial::IIR_RDA_BIT | serial::IIR_NONE_BIT;
+        assert!(serial_invariants(&state).is_err());
+    }
+}
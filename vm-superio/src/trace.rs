@@ -0,0 +1,175 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+// Provides the instrumentation of the devices, which emits `tracing` events
+// and spans with the `tracing` feature.
+//
+// The events carry the label of the device, e.g. `device = "serial"`. The
+// register accesses and the interrupts are `TRACE` events, as the guest may
+// issue millions of them, while the creation of the devices and the saves and
+// restores of their states are `DEBUG` ones. The dispatchers enter a span for
+// each access, with the address of the access, so that the events of the
+// devices are attributed to the instance which handled them. Without the
+// `tracing` feature, the macros only borrow their arguments.
+
+// Emits an event for the access of the guest to the register at `offset`,
+// with the value read or written.
+macro_rules! trace_access {
+    ($device:expr, $access:expr, $offset:expr, $value:expr) => {{
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            device = $device,
+            access = $access,
+            offset = $offset,
+            value = $value,
+            "register access"
+        );
+        #[cfg(not(feature = "tracing"))]
+        let _ = (&$device, &$access, &$offset, &$value);
+    }};
+}
+
+// Emits an event for an interrupt, or any other event notified through a
+// `Trigger`, such as a reset request.
+macro_rules! trace_interrupt {
+    ($device:expr, $interrupt:expr) => {{
+        #[cfg(feature = "tracing")]
+        tracing::trace!(device = $device, interrupt = $interrupt, "interrupt");
+        #[cfg(not(feature = "tracing"))]
+        let _ = (&$device, &$interrupt);
+    }};
+}
+
+// Emits an event for a change of the lifecycle of a device, i.e. its creation,
+// or the save or the restore of its state.
+macro_rules! trace_device {
+    ($device:expr, $action:expr) => {{
+        #[cfg(feature = "tracing")]
+        tracing::debug!(device = $device, action = $action, "device");
+        #[cfg(not(feature = "tracing"))]
+        let _ = (&$device, &$action);
+    }};
+}
+
+// Enters a span named `$name`, with the `$addr` address of the access, until
+// the end of the enclosing block. It is only used by the dispatchers, which
+// need the `std` feature.
+#[cfg(feature = "std")]
+macro_rules! trace_span {
+    ($name:literal, $addr:expr) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!($name, addr = $addr).entered();
+        #[cfg(not(feature = "tracing"))]
+        let _ = &$addr;
+    };
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::fmt::Write;
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use crate::testing::MockTrigger;
+    use crate::{PortIoDispatcher, Serial};
+
+    // Records the events as `name{field=value,...}` lines, prefixed with the
+    // names of the entered spans.
+    #[derive(Clone, Default)]
+    struct Recorder {
+        lines: Arc<Mutex<Vec<String>>>,
+        spans: Arc<Mutex<Vec<String>>>,
+        stack: Arc<Mutex<Vec<u64>>>,
+    }
+
+    struct Fields(String);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if !self.0.is_empty() {
+                self.0.push(',');
+            }
+            let _ = write!(self.0, "{}={:?}", field.name(), value);
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes) -> Id {
+            let mut fields = Fields(String::new());
+            span.record(&mut fields);
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(format!("{}{{{}}}", span.metadata().name(), fields.0));
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _: &Id, _: &Record) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event) {
+            let mut fields = Fields(String::new());
+            event.record(&mut fields);
+            let spans = self.spans.lock().unwrap();
+            let prefix: String = self
+                .stack
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|id| format!("{}:", spans[*id as usize - 1]))
+                .collect();
+            self.lines
+                .lock()
+                .unwrap()
+                .push(format!("{}{}", prefix, fields.0));
+        }
+
+        fn enter(&self, span: &Id) {
+            self.stack.lock().unwrap().push(span.into_u64());
+        }
+
+        fn exit(&self, _: &Id) {
+            self.stack.lock().unwrap().pop();
+        }
+    }
+
+    #[test]
+    fn test_trace_serial() {
+        let recorder = Recorder::default();
+        let trigger = MockTrigger::new();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let serial = Serial::new(trigger.clone(), Vec::new());
+            let state = serial.state();
+            let serial = Arc::new(Mutex::new(
+                Serial::from_state(&state, trigger.clone(), crate::serial::NoEvents, Vec::new())
+                    .unwrap(),
+            ));
+            let mut dispatcher = PortIoDispatcher::new();
+            dispatcher.insert_com(1, serial).unwrap();
+            // Enable the THR empty interrupt, and write a byte.
+            dispatcher.pio_write(0x3F9, &[0x02]);
+            dispatcher.pio_write(0x3F8, b"a");
+            let mut data = [0];
+            dispatcher.pio_read(0x3FD, &mut data);
+        });
+
+        let lines = recorder.lines.lock().unwrap();
+        let expected = [
+            "message=device,device=\"serial\",action=\"save\"",
+            "message=device,device=\"serial\",action=\"restore\"",
+            "pio_write{addr=1017}:message=register access,device=\"serial\",access=\"write\",offset=1,value=2",
+            "pio_write{addr=1016}:message=register access,device=\"serial\",access=\"write\",offset=0,value=97",
+            "pio_write{addr=1016}:message=interrupt,device=\"serial\",interrupt=\"irq\"",
+            "pio_read{addr=1021}:message=register access,device=\"serial\",access=\"read\",offset=5,value=96",
+        ];
+        assert_eq!(*lines, expected);
+        assert_eq!(trigger.count(), 1);
+    }
+}
@@ -0,0 +1,264 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides emulation for the VMware backdoor port (port 0x5658).
+//!
+//! Guests running the VMware tools, and the Linux kernel when detecting the
+//! hypervisor, call into the backdoor with a 32-bit `IN` from this port,
+//! passing the command and its arguments in the general purpose registers.
+//! This stub only answers the GETVERSION command, which is what the guests use
+//! for detecting the backdoor.
+
+use core::convert::TryFrom;
+
+use crate::LegacyDevice;
+
+// Offset of the backdoor port, which is the only register of the device.
+const BACKDOOR_OFFSET: u8 = 0;
+
+// The magic value passed by the guest in EAX, and returned in EBX by
+// GETVERSION.
+const VMPORT_MAGIC: u32 = 0x564D_5868;
+// The command returning the version of the backdoor.
+const CMD_GETVERSION: u16 = 10;
+// The value of EAX after an unknown command.
+const UNKNOWN_COMMAND: u32 = 0xFFFF_FFFF;
+
+// The version and product type answered by default, which are the ones of
+// QEMU.
+const DEFAULT_VERSION: u32 = 6;
+const DEFAULT_PRODUCT_TYPE: u32 = 2;
+
+/// The general purpose registers involved in a backdoor call.
+///
+/// The VMM fills them from the vCPU state before handling the `IN`
+/// instruction, and writes them back afterwards.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VmPortRegs {
+    /// The magic value on entry, and the result of the command on exit.
+    pub eax: u32,
+    /// The argument of the command.
+    pub ebx: u32,
+    /// The command, in the low 16 bits.
+    pub ecx: u32,
+    /// The port number.
+    pub edx: u32,
+}
+
+/// How the backdoor answers the guest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VmPortMode {
+    /// GETVERSION returns the given version and product type, along with the
+    /// magic value, which tells the guest that the backdoor is available.
+    Respond {
+        /// The version of the backdoor, returned in EAX.
+        version: u32,
+        /// The VMware product type, returned in ECX.
+        product_type: u32,
+    },
+    /// The backdoor is hidden: the port reads as all ones, and the registers
+    /// are otherwise left untouched, as if no device was present.
+    Deny,
+}
+
+impl Default for VmPortMode {
+    fn default() -> Self {
+        VmPortMode::Respond {
+            version: DEFAULT_VERSION,
+            product_type: DEFAULT_PRODUCT_TYPE,
+        }
+    }
+}
+
+/// The VMware backdoor port, usually found at port 0x5658.
+///
+/// The backdoor is available with [`VmPortMode::Respond`], and hidden with
+/// [`VmPortMode::Deny`], so that the VMM can opt in or out explicitly, while
+/// still handling the accesses of the guests that probe the port.
+///
+/// # Example
+///
+/// ```rust
+/// # use vm_superio::vmport::{VmPort, VmPortRegs};
+/// let vmport = VmPort::new();
+///
+/// // The guest asks for the version of the backdoor.
+/// let mut regs = VmPortRegs {
+///     eax: 0x564D_5868,
+///     ebx: 0,
+///     ecx: 10,
+///     edx: 0x5658,
+/// };
+/// vmport.read(0, &mut regs);
+/// assert_eq!(regs.eax, 6);
+/// assert_eq!(regs.ebx, 0x564D_5868);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct VmPort {
+    mode: VmPortMode,
+}
+
+impl VmPort {
+    /// Creates a new `VmPort` instance, which answers GETVERSION like QEMU.
+    ///
+    /// # Example
+    ///
+    /// You can see an example of how to use this function in the
+    /// [`Example` section from `VmPort`](struct.VmPort.html#example).
+    pub fn new() -> Self {
+        Self::with_mode(VmPortMode::default())
+    }
+
+    /// Creates a new `VmPort` instance, which answers the guest according to
+    /// `mode`.
+    ///
+    /// # Arguments
+    /// * `mode` - Whether, and how, the backdoor answers the guest.
+    pub fn with_mode(mode: VmPortMode) -> Self {
+        VmPort { mode }
+    }
+
+    /// Returns how the backdoor answers the guest.
+    pub fn mode(&self) -> VmPortMode {
+        self.mode
+    }
+
+    /// Handles a 32-bit read request from the driver at `offset` offset from
+    /// the base I/O address.
+    ///
+    /// `regs` holds the registers of the guest when it issued the `IN`
+    /// instruction, and is updated with the result of the command, which is
+    /// EAX for the `IN` itself. Calls without the magic value in EAX, and
+    /// reads from invalid offsets, return all ones.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset that will be added to the base address
+    ///   for reading from a specific register.
+    /// * `regs` - The registers of the guest.
+    pub fn read(&self, offset: u8, regs: &mut VmPortRegs) {
+        let (version, product_type) = match self.mode {
+            VmPortMode::Respond {
+                version,
+                product_type,
+            } if offset == BACKDOOR_OFFSET && regs.eax == VMPORT_MAGIC => (version, product_type),
+            _ => {
+                regs.eax = UNKNOWN_COMMAND;
+                return;
+            }
+        };
+
+        if regs.ecx as u16 == CMD_GETVERSION {
+            regs.eax = version;
+            regs.ebx = VMPORT_MAGIC;
+            regs.ecx = product_type;
+        } else {
+            regs.eax = UNKNOWN_COMMAND;
+        }
+    }
+}
+
+impl Default for VmPort {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LegacyDevice for VmPort {
+    type State = ();
+
+    // The backdoor calls need the registers of the guest, which aren't
+    // passed through this interface, so the reads are handled like the calls
+    // without the magic value, and return all ones.
+    fn read(&mut self, offset: u16, data: &mut [u8]) {
+        let mut regs = VmPortRegs::default();
+        VmPort::read(self, u8::try_from(offset).unwrap_or(u8::MAX), &mut regs);
+        let eax = regs.eax.to_le_bytes();
+        data.fill(0);
+        let len = data.len().min(eax.len());
+        data[..len].copy_from_slice(&eax[..len]);
+    }
+
+    fn write(&mut self, _offset: u16, _data: &[u8]) {}
+
+    fn reset(&mut self) {}
+
+    fn state(&self) -> Self::State {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(command: u32) -> VmPortRegs {
+        VmPortRegs {
+            eax: VMPORT_MAGIC,
+            ebx: 0,
+            ecx: command,
+            edx: 0x5658,
+        }
+    }
+
+    #[test]
+    fn test_vmport_getversion() {
+        let vmport = VmPort::default();
+        assert_eq!(vmport.mode(), VmPortMode::default());
+
+        // The high 16 bits of ECX aren't part of the command.
+        let mut regs = call(0xABCD_0000 | u32::from(CMD_GETVERSION));
+        vmport.read(BACKDOOR_OFFSET, &mut regs);
+        assert_eq!(
+            regs,
+            VmPortRegs {
+                eax: DEFAULT_VERSION,
+                ebx: VMPORT_MAGIC,
+                ecx: DEFAULT_PRODUCT_TYPE,
+                edx: 0x5658,
+            }
+        );
+
+        let vmport = VmPort::with_mode(VmPortMode::Respond {
+            version: 3,
+            product_type: 4,
+        });
+        let mut regs = call(u32::from(CMD_GETVERSION));
+        vmport.read(BACKDOOR_OFFSET, &mut regs);
+        assert_eq!((regs.eax, regs.ebx, regs.ecx), (3, VMPORT_MAGIC, 4));
+
+        // The other commands are unknown.
+        let mut regs = call(u32::from(CMD_GETVERSION) + 1);
+        vmport.read(BACKDOOR_OFFSET, &mut regs);
+        assert_eq!(regs.eax, UNKNOWN_COMMAND);
+        assert_eq!(regs.ebx, 0);
+    }
+
+    #[test]
+    fn test_vmport_deny() {
+        let vmport = VmPort::with_mode(VmPortMode::Deny);
+        let mut regs = call(u32::from(CMD_GETVERSION));
+        vmport.read(BACKDOOR_OFFSET, &mut regs);
+        assert_eq!(regs.eax, UNKNOWN_COMMAND);
+        // The guest doesn't find the magic value in EBX.
+        assert_eq!(regs.ebx, 0);
+        assert_eq!(regs.ecx, u32::from(CMD_GETVERSION));
+
+        // Neither does it without the magic value, nor at another offset.
+        let vmport = VmPort::new();
+        let mut regs = call(u32::from(CMD_GETVERSION));
+        regs.eax = 0;
+        vmport.read(BACKDOOR_OFFSET, &mut regs);
+        assert_eq!((regs.eax, regs.ebx), (UNKNOWN_COMMAND, 0));
+        let mut regs = call(u32::from(CMD_GETVERSION));
+        vmport.read(BACKDOOR_OFFSET + 1, &mut regs);
+        assert_eq!((regs.eax, regs.ebx), (UNKNOWN_COMMAND, 0));
+    }
+
+    #[test]
+    fn test_vmport_legacy_device() {
+        let mut vmport = VmPort::new();
+
+        let mut data = [0; 4];
+        LegacyDevice::read(&mut vmport, u16::from(BACKDOOR_OFFSET), &mut data);
+        assert_eq!(u32::from_le_bytes(data), UNKNOWN_COMMAND);
+    }
+}
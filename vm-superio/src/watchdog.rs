@@ -0,0 +1,66 @@
+// Copyright 2026 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+
+//! Provides the events and the expiry actions shared by the watchdogs.
+//!
+//! The [`I6300Esb`](../i6300esb/struct.I6300Esb.html), the
+//! [`Sp805`](../sp805/struct.Sp805.html) and the
+//! [`SbsaGwdt`](../sbsa_gwdt/struct.SbsaGwdt.html) watchdogs count down in
+//! two stages: the first one notifies the guest, and the second one takes
+//! the expiry action configured by the VMM. They all report these stages
+//! through [`WatchdogEvents`](trait.WatchdogEvents.html), so the policy of
+//! the VMM is implemented once, regardless of the watchdog used by the
+//! guest.
+
+use alloc::sync::Arc;
+
+/// The action taken when a watchdog expires, i.e. when the guest is
+/// considered hung.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WatchdogAction {
+    /// Reset the system.
+    #[default]
+    Reset,
+    /// Power the system off.
+    Poweroff,
+    /// Only report the expiry through `WatchdogEvents`, without signaling
+    /// the expiry event object.
+    NotifyOnly,
+}
+
+/// Defines a series of callbacks that are invoked in response to the occurrence of specific
+/// events as part of the watchdog emulation logic. The methods below can be implemented by a
+/// backend that keeps track of such events by restarting or stopping the VM, incrementing
+/// metrics, logging messages, or any other action.
+pub trait WatchdogEvents {
+    /// The first stage expired, because the guest didn't reload the
+    /// watchdog, and the guest is notified, usually through an interrupt.
+    fn pretimeout(&self);
+
+    /// The second stage expired, i.e. the guest is considered hung. The
+    /// expiry event object of the watchdog is signaled right after, unless
+    /// `action` is `WatchdogAction::NotifyOnly`.
+    fn expired(&self, action: WatchdogAction);
+}
+
+/// Provides a no-op implementation of `WatchdogEvents` which can be used in situations that
+/// do not require logging or otherwise doing anything in response to the events defined
+/// as part of `WatchdogEvents`.
+#[derive(Debug, Clone, Copy)]
+pub struct NoEvents;
+
+impl WatchdogEvents for NoEvents {
+    fn pretimeout(&self) {}
+    fn expired(&self, _action: WatchdogAction) {}
+}
+
+impl<EV: WatchdogEvents> WatchdogEvents for Arc<EV> {
+    fn pretimeout(&self) {
+        self.as_ref().pretimeout();
+    }
+
+    fn expired(&self, action: WatchdogAction) {
+        self.as_ref().expired(action);
+    }
+}